@@ -62,47 +62,73 @@ pub(crate) type AssistHandler = fn(AssistCtx) -> Option<Assist>;
 ///
 /// To avoid repeating the same code twice for both "check" and "apply"
 /// functions, we use an approach reminiscent of that of Django's function based
-/// views dealing with forms. Each assist receives a runtime parameter,
-/// `should_compute_edit`. It first check if an edit is applicable (potentially
-/// computing info required to compute the actual edit). If it is applicable,
-/// and `should_compute_edit` is `true`, it then computes the actual edit.
+/// views dealing with forms. Each assist first checks if it is applicable
+/// (potentially computing info required to compute the actual edit), and then
+/// passes its id, label and target, together with a closure computing the
+/// actual edit, to `add_assist`. The closure is called only if the
+/// `ResolveStrategy` of the context asks for this assist to be resolved.
 ///
-/// So, to implement the original assists workflow, we can first apply each edit
-/// with `should_compute_edit = false`, and then applying the selected edit
-/// again, with `should_compute_edit = true` this time.
-///
-/// Note, however, that we don't actually use such two-phase logic at the
-/// moment, because the LSP API is pretty awkward in this place, and it's much
-/// easier to just compute the edit eagerly :-)
+/// So, to implement the original assists workflow, we first run each assist
+/// with `ResolveStrategy::None`, and then run them again with
+/// `ResolveStrategy::Single`, to compute the edit of the selected assist only.
 #[derive(Clone)]
 pub(crate) struct AssistCtx<'a> {
     pub(crate) sema: &'a Semantics<'a, RootDatabase>,
     pub(crate) db: &'a RootDatabase,
     pub(crate) frange: FileRange,
     source_file: SourceFile,
-    should_compute_edit: bool,
+    resolve: ResolveStrategy<'a>,
+}
+
+/// Which of the applicable assists get their edit computed.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ResolveStrategy<'a> {
+    None,
+    All,
+    /// Only the assist with the given id and label. The label tells apart the
+    /// assists of a group, which share their id.
+    Single {
+        id: &'a str,
+        label: &'a str,
+    },
+}
+
+impl ResolveStrategy<'_> {
+    fn should_resolve(self, label: &AssistLabel) -> bool {
+        match self {
+            ResolveStrategy::None => false,
+            ResolveStrategy::All => true,
+            ResolveStrategy::Single { id, label: text } => label.id.0 == id && label.label == text,
+        }
+    }
 }
 
 impl<'a> AssistCtx<'a> {
-    pub fn new(
+    pub(crate) fn new(
         sema: &'a Semantics<'a, RootDatabase>,
         frange: FileRange,
-        should_compute_edit: bool,
+        resolve: ResolveStrategy<'a>,
     ) -> AssistCtx<'a> {
         let source_file = sema.parse(frange.file_id);
-        AssistCtx { sema, db: sema.db, frange, source_file, should_compute_edit }
+        AssistCtx { sema, db: sema.db, frange, source_file, resolve }
     }
 
+    /// Adds an assist, active within the `target` range. `f` computes the edit,
+    /// and is called only if the assist needs to be resolved.
+    ///
+    /// Target ranges are used to sort assists: the smaller the target range,
+    /// the more specific assist is, and so it should be sorted first.
     pub(crate) fn add_assist(
         self,
         id: AssistId,
         label: impl Into<String>,
+        target: TextRange,
         f: impl FnOnce(&mut ActionBuilder),
     ) -> Option<Assist> {
-        let label = AssistLabel::new(label.into(), id);
+        let label = AssistLabel::new(label.into(), id, target);
 
         let mut info = AssistInfo::new(label);
-        if self.should_compute_edit {
+        if self.resolve.should_resolve(&info.label) {
             let action = {
                 let mut edit = ActionBuilder::default();
                 f(&mut edit);
@@ -148,12 +174,13 @@ impl<'a> AssistGroup<'a> {
         &mut self,
         id: AssistId,
         label: impl Into<String>,
+        target: TextRange,
         f: impl FnOnce(&mut ActionBuilder),
     ) {
-        let label = AssistLabel::new(label.into(), id);
+        let label = AssistLabel::new(label.into(), id, target);
 
         let mut info = AssistInfo::new(label).with_group(GroupLabel(self.group_name.clone()));
-        if self.ctx.should_compute_edit {
+        if self.ctx.resolve.should_resolve(&info.label) {
             let action = {
                 let mut edit = ActionBuilder::default();
                 f(&mut edit);
@@ -175,7 +202,6 @@ impl<'a> AssistGroup<'a> {
 pub(crate) struct ActionBuilder {
     edit: TextEditBuilder,
     cursor_position: Option<TextUnit>,
-    target: Option<TextRange>,
 }
 
 impl ActionBuilder {
@@ -215,14 +241,6 @@ impl ActionBuilder {
         self.cursor_position = Some(offset)
     }
 
-    /// Specify that the assist should be active withing the `target` range.
-    ///
-    /// Target ranges are used to sort assists: the smaller the target range,
-    /// the more specific assist is, and so it should be sorted first.
-    pub(crate) fn target(&mut self, target: TextRange) {
-        self.target = Some(target)
-    }
-
     /// Get access to the raw `TextEditBuilder`.
     pub(crate) fn text_edit_builder(&mut self) -> &mut TextEditBuilder {
         &mut self.edit
@@ -233,10 +251,6 @@ impl ActionBuilder {
     }
 
     fn build(self) -> AssistAction {
-        AssistAction {
-            edit: self.edit.finish(),
-            cursor_position: self.cursor_position,
-            target: self.target,
-        }
+        AssistAction { edit: self.edit.finish(), cursor_position: self.cursor_position }
     }
 }
@@ -233,6 +233,23 @@ pub(crate) fn frobnicate() {}
     )
 }
 
+#[test]
+fn doctest_convert_number_representation() {
+    check(
+        "convert_number_representation",
+        r#####"
+fn main() {
+    let x = <|>65535u16;
+}
+"#####,
+        r#####"
+fn main() {
+    let x = 0xFFFFu16;
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_convert_to_guarded_return() {
     check(
@@ -257,6 +274,24 @@ fn main() {
     )
 }
 
+#[test]
+fn doctest_extract_variable() {
+    check(
+        "extract_variable",
+        r#####"
+fn main() {
+    <|>(1 + 2)<|> * 4;
+}
+"#####,
+        r#####"
+fn main() {
+    let var_name = (1 + 2);
+    var_name * 4;
+}
+"#####,
+    )
+}
+
 #[test]
 fn doctest_fill_match_arms() {
     check(
@@ -348,24 +383,6 @@ fn main() {
     )
 }
 
-#[test]
-fn doctest_introduce_variable() {
-    check(
-        "introduce_variable",
-        r#####"
-fn main() {
-    <|>(1 + 2)<|> * 4;
-}
-"#####,
-        r#####"
-fn main() {
-    let var_name = (1 + 2);
-    var_name * 4;
-}
-"#####,
-    )
-}
-
 #[test]
 fn doctest_invert_if() {
     check(
@@ -620,3 +637,29 @@ use std::{collections::HashMap};
 "#####,
     )
 }
+
+#[test]
+fn doctest_split_or_pattern() {
+    check(
+        "split_or_pattern",
+        r#####"
+enum Action { Move { distance: u32 }, Stop }
+
+fn handle(action: Action) {
+    match action {
+        <|>Action::Move(..) | Action::Stop => foo(),
+    }
+}
+"#####,
+        r#####"
+enum Action { Move { distance: u32 }, Stop }
+
+fn handle(action: Action) {
+    match action {
+        Action::Move(..) => foo(),
+        Action::Stop => foo(),
+    }
+}
+"#####,
+    )
+}
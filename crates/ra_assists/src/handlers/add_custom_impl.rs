@@ -52,9 +52,7 @@ pub(crate) fn add_custom_impl(ctx: AssistCtx) -> Option<Assist> {
     let label =
         format!("Add custom impl '{}' for '{}'", trait_token.text().as_str(), annotated_name);
 
-    ctx.add_assist(AssistId("add_custom_impl"), label, |edit| {
-        edit.target(attr.syntax().text_range());
-
+    ctx.add_assist(AssistId("add_custom_impl"), label, attr.syntax().text_range(), |edit| {
         let new_attr_input = input
             .syntax()
             .descendants_with_tokens()
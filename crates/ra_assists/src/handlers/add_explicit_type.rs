@@ -58,15 +58,18 @@ pub(crate) fn add_explicit_type(ctx: AssistCtx) -> Option<Assist> {
     }
 
     let db = ctx.db;
+    let module = ctx.sema.scope(stmt.syntax()).module()?;
+    // Assist not applicable if the type can't be written down here
+    let ty_text = ty.display_source_code(db, module.into())?;
     ctx.add_assist(
         AssistId("add_explicit_type"),
-        format!("Insert explicit type '{}'", ty.display(db)),
+        format!("Insert explicit type '{}'", ty_text),
+        pat_range,
         |edit| {
-            edit.target(pat_range);
             if let Some(ascribed_ty) = ascribed_ty {
-                edit.replace(ascribed_ty.syntax().text_range(), format!("{}", ty.display(db)));
+                edit.replace(ascribed_ty.syntax().text_range(), ty_text);
             } else {
-                edit.insert(name_range.end(), format!(": {}", ty.display(db)));
+                edit.insert(name_range.end(), format!(": {}", ty_text));
             }
         },
     )
@@ -144,6 +147,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn add_explicit_type_qualifies_types_from_other_modules() {
+        check_assist(
+            add_explicit_type,
+            r#"
+            mod foo {
+                pub struct Bar;
+            }
+
+            fn f() {
+                let a<|> = foo::Bar;
+            }"#,
+            r#"
+            mod foo {
+                pub struct Bar;
+            }
+
+            fn f() {
+                let a<|>: foo::Bar = foo::Bar;
+            }"#,
+        );
+    }
+
+    #[test]
+    fn add_explicit_type_qualifies_types_relative_to_current_module() {
+        check_assist(
+            add_explicit_type,
+            r#"
+            mod foo {
+                pub struct Bar;
+            }
+
+            mod baz {
+                mod qux {
+                    fn f() {
+                        let a<|> = crate::foo::Bar;
+                    }
+                }
+            }"#,
+            r#"
+            mod foo {
+                pub struct Bar;
+            }
+
+            mod baz {
+                mod qux {
+                    fn f() {
+                        let a<|>: crate::foo::Bar = crate::foo::Bar;
+                    }
+                }
+            }"#,
+        );
+    }
+
+    #[test]
+    fn add_explicit_type_not_applicable_if_ty_contains_unknown() {
+        check_assist_not_applicable(
+            add_explicit_type,
+            r#"
+            struct S<T>(T);
+
+            fn f() {
+                let a<|> = S(unresolved());
+            }"#,
+        );
+    }
+
+    #[test]
+    fn add_explicit_type_not_applicable_for_closure() {
+        check_assist_not_applicable(add_explicit_type, "fn f() { let a<|> = |x: i32| x; }");
+    }
+
     #[test]
     fn add_explicit_type_not_applicable_if_ty_not_inferred() {
         check_assist_not_applicable(add_explicit_type, "fn f() { let a<|> = None; }");
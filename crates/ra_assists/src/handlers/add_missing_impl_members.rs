@@ -138,7 +138,8 @@ fn add_missing_impl_members_inner(
 
     let sema = ctx.sema;
 
-    ctx.add_assist(AssistId(assist_id), label, |edit| {
+    let target = impl_node.syntax().text_range();
+    ctx.add_assist(AssistId(assist_id), label, target, |edit| {
         let n_existing_items = impl_item_list.impl_items().count();
         let source_scope = sema.scope_for_def(trait_);
         let target_scope = sema.scope(impl_item_list.syntax());
@@ -3,21 +3,23 @@ use crate::{
     insert_use_statement, AssistId,
 };
 use hir::{
-    AsAssocItem, AssocItemContainer, ModPath, Module, ModuleDef, PathResolution, Semantics, Trait,
-    Type,
+    Adt, AsAssocItem, AssocItemContainer, ModPath, Module, ModuleDef, PathResolution, Semantics,
+    Trait, Type,
 };
 use ra_ide_db::{imports_locator::ImportsLocator, RootDatabase};
 use ra_prof::profile;
 use ra_syntax::{
     ast::{self, AstNode},
-    SyntaxNode,
+    SyntaxKind::*,
+    SyntaxNode, T,
 };
 use rustc_hash::FxHashSet;
 use std::collections::BTreeSet;
 
 // Assist: auto_import
 //
-// If the name is unresolved, provides all possible imports for it.
+// If the name is unresolved, provides all possible imports for it, or the
+// paths to qualify it with in place.
 //
 // ```
 // fn main() {
@@ -47,16 +49,31 @@ pub(crate) fn auto_import(ctx: AssistCtx) -> Option<Assist> {
         auto_import_assets.get_import_group_message()
     };
     let mut group = ctx.add_assist_group(assist_group_name);
+    let target = auto_import_assets.syntax_under_caret.text_range();
     for import in proposed_imports {
-        group.add_assist(AssistId("auto_import"), format!("Import `{}`", &import), |edit| {
-            edit.target(auto_import_assets.syntax_under_caret.text_range());
-            insert_use_statement(
-                &auto_import_assets.syntax_under_caret,
-                &auto_import_assets.syntax_under_caret,
-                &import,
-                edit.text_edit_builder(),
+        group.add_assist(
+            AssistId("auto_import"),
+            format!("Import `{}`", &import),
+            target,
+            |edit| {
+                insert_use_statement(
+                    &auto_import_assets.syntax_under_caret,
+                    &auto_import_assets.syntax_under_caret,
+                    &import,
+                    edit.text_edit_builder(),
+                );
+            },
+        );
+        if let Some(name_ref) = auto_import_assets.import_candidate.name_to_qualify() {
+            group.add_assist(
+                AssistId("auto_import"),
+                format!("Qualify as `{}`", &import),
+                target,
+                |edit| {
+                    edit.replace(name_ref.syntax().text_range(), import.to_string());
+                },
             );
-        });
+        }
     }
     group.finish()
 }
@@ -69,6 +86,10 @@ struct AutoImportAssets {
 
 impl AutoImportAssets {
     fn new(ctx: &AssistCtx) -> Option<Self> {
+        // The paths in macro calls are not parsed yet.
+        if ctx.find_node_at_offset::<ast::TokenTree>().is_some() {
+            return None;
+        }
         if let Some(path_under_caret) = ctx.find_node_at_offset::<ast::Path>() {
             Self::for_regular_path(path_under_caret, &ctx)
         } else {
@@ -102,8 +123,8 @@ impl AutoImportAssets {
 
     fn get_search_query(&self) -> &str {
         match &self.import_candidate {
-            ImportCandidate::UnqualifiedName(name) => name,
-            ImportCandidate::QualifierStart(qualifier_start) => qualifier_start,
+            ImportCandidate::UnqualifiedName(name, _) => name.text().as_str(),
+            ImportCandidate::QualifierStart(qualifier_start) => qualifier_start.text().as_str(),
             ImportCandidate::TraitAssocItem(_, trait_assoc_item_name) => trait_assoc_item_name,
             ImportCandidate::TraitMethod(_, trait_method_name) => trait_method_name,
         }
@@ -111,9 +132,9 @@ impl AutoImportAssets {
 
     fn get_import_group_message(&self) -> String {
         match &self.import_candidate {
-            ImportCandidate::UnqualifiedName(name) => format!("Import {}", name),
+            ImportCandidate::UnqualifiedName(name, _) => format!("Import {}", name.text()),
             ImportCandidate::QualifierStart(qualifier_start) => {
-                format!("Import {}", qualifier_start)
+                format!("Import {}", qualifier_start.text())
             }
             ImportCandidate::TraitAssocItem(_, trait_assoc_item_name) => {
                 format!("Import a trait for item {}", trait_assoc_item_name)
@@ -183,7 +204,12 @@ impl AutoImportAssets {
                         )
                         .map(ModuleDef::from)
                 }
-                _ => Some(module_def),
+                ImportCandidate::UnqualifiedName(_, position) => {
+                    Some(module_def).filter(|&it| position.accepts(db, it))
+                }
+                ImportCandidate::QualifierStart(_) => {
+                    Some(module_def).filter(|&it| PathPosition::Type.accepts(db, it))
+                }
             })
             .filter_map(|module_def| self.module_with_name_to_import.find_use_path(db, module_def))
             .filter(|use_path| !use_path.segments.is_empty())
@@ -202,11 +228,11 @@ impl AutoImportAssets {
 
 #[derive(Debug)]
 enum ImportCandidate {
-    /// Simple name like 'HashMap'
-    UnqualifiedName(String),
+    /// Simple name like 'HashMap', with the kind of items its position allows.
+    UnqualifiedName(ast::NameRef, PathPosition),
     /// First part of the qualified name.
     /// For 'std::collections::HashMap', that will be 'std'.
-    QualifierStart(String),
+    QualifierStart(ast::NameRef),
     /// A trait associated function (with no self parameter) or associated constant.
     /// For 'test_mod::TestEnum::test_function', `Type` is the `test_mod::TestEnum` expression type
     /// and `String` is the `test_function`
@@ -218,6 +244,16 @@ enum ImportCandidate {
 }
 
 impl ImportCandidate {
+    /// The name which is replaced by the whole path of an import to qualify it
+    /// in place. Trait items can't be qualified this way.
+    fn name_to_qualify(&self) -> Option<&ast::NameRef> {
+        match self {
+            ImportCandidate::UnqualifiedName(name, _) => Some(name),
+            ImportCandidate::QualifierStart(qualifier_start) => Some(qualifier_start),
+            ImportCandidate::TraitAssocItem(..) | ImportCandidate::TraitMethod(..) => None,
+        }
+    }
+
     fn for_method_call(
         sema: &Semantics<RootDatabase>,
         method_call: &ast::MethodCallExpr,
@@ -259,20 +295,80 @@ impl ImportCandidate {
                     None
                 }
             } else {
-                Some(ImportCandidate::QualifierStart(qualifier_start.syntax().to_string()))
+                Some(ImportCandidate::QualifierStart(qualifier_start))
             }
         } else {
             Some(ImportCandidate::UnqualifiedName(
-                segment.syntax().descendants().find_map(ast::NameRef::cast)?.syntax().to_string(),
+                segment.syntax().descendants().find_map(ast::NameRef::cast)?,
+                PathPosition::of_path(path_under_caret),
             ))
         }
     }
 }
 
+/// The kinds of items an unresolved path can refer to, given its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathPosition {
+    /// A type, or a qualifier like `HashMap` in `HashMap::new()`.
+    Type,
+    /// An expression or a pattern.
+    Value,
+    /// The path of a macro call, like `foo` in `foo!()`.
+    Macro,
+    /// A path the parser could not make sense of.
+    Any,
+}
+
+impl PathPosition {
+    fn of_path(path: &ast::Path) -> PathPosition {
+        let parent = match path.syntax().parent() {
+            Some(it) => it,
+            None => return PathPosition::Any,
+        };
+        match parent.kind() {
+            PATH | PATH_TYPE | RECORD_LIT | RECORD_PAT => PathPosition::Type,
+            PATH_EXPR | PATH_PAT | TUPLE_STRUCT_PAT => PathPosition::Value,
+            MACRO_CALL if parent.children_with_tokens().any(|it| it.kind() == T![!]) => {
+                PathPosition::Macro
+            }
+            _ => PathPosition::Any,
+        }
+    }
+
+    fn accepts(self, db: &RootDatabase, module_def: ModuleDef) -> bool {
+        match self {
+            PathPosition::Type => match module_def {
+                ModuleDef::Module(_)
+                | ModuleDef::Adt(_)
+                | ModuleDef::Trait(_)
+                | ModuleDef::TypeAlias(_)
+                | ModuleDef::BuiltinType(_) => true,
+                _ => false,
+            },
+            PathPosition::Value => match module_def {
+                // Trait items are only offered through method calls and
+                // qualified paths.
+                ModuleDef::Function(it) => it.as_assoc_item(db).is_none(),
+                ModuleDef::Const(it) => it.as_assoc_item(db).is_none(),
+                ModuleDef::Static(_)
+                | ModuleDef::EnumVariant(_)
+                | ModuleDef::Adt(Adt::Struct(_)) => true,
+                _ => false,
+            },
+            // Macros are not part of the symbol index.
+            PathPosition::Macro => false,
+            PathPosition::Any => true,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::helpers::{check_assist, check_assist_not_applicable, check_assist_target};
+    use crate::helpers::{
+        check_assist, check_assist_group_with_fixture, check_assist_not_applicable,
+        check_assist_target,
+    };
 
     #[test]
     fn applicable_when_found_an_import() {
@@ -776,4 +872,165 @@ mod tests {
             ",
         )
     }
+
+    #[test]
+    fn imports_or_qualifies_candidates_from_dependencies() {
+        check_assist_group_with_fixture(
+            auto_import,
+            r"
+            //- /main.rs crate:main deps:a,b
+            fn main() {
+                let map = HashMap<|>::new();
+            }
+
+            //- /a.rs crate:a
+            pub mod collections {
+                pub struct HashMap;
+            }
+            pub mod functions {
+                pub fn HashMap() {}
+            }
+
+            //- /b.rs crate:b
+            pub struct HashMap;
+            ",
+            &[
+                (
+                    "Import `a::collections::HashMap`",
+                    r"
+                    use a::collections::HashMap;
+
+                    fn main() {
+                        let map = HashMap::new();
+                    }
+                    ",
+                ),
+                (
+                    "Qualify as `a::collections::HashMap`",
+                    r"
+                    fn main() {
+                        let map = a::collections::HashMap::new();
+                    }
+                    ",
+                ),
+                (
+                    "Import `b::HashMap`",
+                    r"
+                    use b::HashMap;
+
+                    fn main() {
+                        let map = HashMap::new();
+                    }
+                    ",
+                ),
+                (
+                    "Qualify as `b::HashMap`",
+                    r"
+                    fn main() {
+                        let map = b::HashMap::new();
+                    }
+                    ",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn trait_method_is_only_imported_for_method_calls() {
+        check_assist(
+            auto_import,
+            r"
+            mod m {
+                pub trait Frobnicate {
+                    fn frob(&self);
+                }
+                pub struct Thing;
+                impl Frobnicate for Thing {
+                    fn frob(&self) {}
+                }
+            }
+
+            fn main() {
+                m::Thing.fr<|>ob();
+            }
+            ",
+            r"
+            use m::Frobnicate;
+
+            mod m {
+                pub trait Frobnicate {
+                    fn frob(&self);
+                }
+                pub struct Thing;
+                impl Frobnicate for Thing {
+                    fn frob(&self) {}
+                }
+            }
+
+            fn main() {
+                m::Thing.fr<|>ob();
+            }
+            ",
+        );
+        check_assist_not_applicable(
+            auto_import,
+            r"
+            mod m {
+                pub trait Frobnicate {
+                    fn frob(&self);
+                }
+                pub struct Thing;
+                impl Frobnicate for Thing {
+                    fn frob(&self) {}
+                }
+            }
+
+            fn main() {
+                fr<|>ob(&m::Thing);
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn not_applicable_for_function_in_macro_position() {
+        check_assist_not_applicable(
+            auto_import,
+            r"
+            pub mod m {
+                pub fn foo() {}
+            }
+
+            fn main() {
+                fo<|>o!();
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn not_applicable_in_macro_token_trees() {
+        check_assist_not_applicable(
+            auto_import,
+            r"
+            mod m {
+                pub trait Frobnicate {
+                    fn frob(&self, thing: Thing);
+                }
+                pub struct Thing;
+                impl Frobnicate for Thing {
+                    fn frob(&self, thing: Thing) {}
+                }
+            }
+
+            macro_rules! id {
+                ($e:expr) => { $e };
+            }
+
+            fn main() {
+                m::Thing.frob(id!(Thi<|>ng));
+            }
+            ",
+        );
+    }
 }
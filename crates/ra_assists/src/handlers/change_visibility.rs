@@ -56,11 +56,15 @@ fn add_vis(ctx: AssistCtx) -> Option<Assist> {
         (vis_offset(field.syntax()), ident.text_range())
     };
 
-    ctx.add_assist(AssistId("change_visibility"), "Change visibility to pub(crate)", |edit| {
-        edit.target(target);
-        edit.insert(offset, "pub(crate) ");
-        edit.set_cursor(offset);
-    })
+    ctx.add_assist(
+        AssistId("change_visibility"),
+        "Change visibility to pub(crate)",
+        target,
+        |edit| {
+            edit.insert(offset, "pub(crate) ");
+            edit.set_cursor(offset);
+        },
+    )
 }
 
 fn vis_offset(node: &SyntaxNode) -> TextUnit {
@@ -79,19 +83,24 @@ fn change_vis(ctx: AssistCtx, vis: ast::Visibility) -> Option<Assist> {
         return ctx.add_assist(
             AssistId("change_visibility"),
             "Change Visibility to pub(crate)",
+            vis.syntax().text_range(),
             |edit| {
-                edit.target(vis.syntax().text_range());
                 edit.replace(vis.syntax().text_range(), "pub(crate)");
                 edit.set_cursor(vis.syntax().text_range().start())
             },
         );
     }
     if vis.syntax().text() == "pub(crate)" {
-        return ctx.add_assist(AssistId("change_visibility"), "Change visibility to pub", |edit| {
-            edit.target(vis.syntax().text_range());
-            edit.replace(vis.syntax().text_range(), "pub");
-            edit.set_cursor(vis.syntax().text_range().start());
-        });
+        let target = vis.syntax().text_range();
+        return ctx.add_assist(
+            AssistId("change_visibility"),
+            "Change visibility to pub",
+            target,
+            |edit| {
+                edit.replace(vis.syntax().text_range(), "pub");
+                edit.set_cursor(vis.syntax().text_range().start());
+            },
+        );
     }
     None
 }
@@ -0,0 +1,153 @@
+use ra_syntax::{
+    ast::{self, Radix},
+    AstToken,
+    SyntaxKind::{ATTR, INT_NUMBER, TOKEN_TREE},
+};
+
+use crate::{Assist, AssistCtx, AssistId};
+
+// Assist: convert_number_representation
+//
+// Converts an integer literal to another base, cycling through decimal,
+// hexadecimal, binary and octal.
+//
+// ```
+// fn main() {
+//     let x = <|>65535u16;
+// }
+// ```
+// ->
+// ```
+// fn main() {
+//     let x = 0xFFFFu16;
+// }
+// ```
+pub(crate) fn convert_number_representation(ctx: AssistCtx) -> Option<Assist> {
+    let number = ctx.find_token_at_offset(INT_NUMBER).and_then(ast::IntNumber::cast)?;
+    if number.syntax().ancestors().any(|it| it.kind() == ATTR || it.kind() == TOKEN_TREE) {
+        return None;
+    }
+    let value = number.value()?;
+    let (_, _, suffix) = number.split_into_parts();
+    let radix = next_radix(number.radix());
+    let converted = format!("{}{}{}", radix.prefix(), digits_with_separators(value, radix), suffix);
+
+    let target = number.syntax().text_range();
+    let label = format!("Convert to {}", radix_name(radix));
+    ctx.add_assist(AssistId("convert_number_representation"), label, target, |edit| {
+        edit.replace(target, converted);
+    })
+}
+
+fn next_radix(radix: Radix) -> Radix {
+    match radix {
+        Radix::Decimal => Radix::Hexadecimal,
+        Radix::Hexadecimal => Radix::Binary,
+        Radix::Binary => Radix::Octal,
+        Radix::Octal => Radix::Decimal,
+    }
+}
+
+fn radix_name(radix: Radix) -> &'static str {
+    match radix {
+        Radix::Binary => "binary",
+        Radix::Octal => "octal",
+        Radix::Decimal => "decimal",
+        Radix::Hexadecimal => "hexadecimal",
+    }
+}
+
+/// Formats `value` in `radix`, separating groups of 4 hexadecimal or binary
+/// digits and of 3 decimal digits by `_`.
+fn digits_with_separators(value: u128, radix: Radix) -> String {
+    let (digits, group_len) = match radix {
+        Radix::Binary => (format!("{:b}", value), 4),
+        Radix::Octal => return format!("{:o}", value),
+        Radix::Decimal => (value.to_string(), 3),
+        Radix::Hexadecimal => (format!("{:X}", value), 4),
+    };
+    let mut res = String::with_capacity(digits.len() + digits.len() / group_len);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx) % group_len == 0 {
+            res.push('_');
+        }
+        res.push(digit);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::helpers::{check_assist, check_assist_not_applicable, check_assist_target};
+
+    #[test]
+    fn convert_number_representation_target() {
+        check_assist_target(
+            convert_number_representation,
+            "fn f() { let x = <|>1_000u32; }",
+            "1_000u32",
+        );
+    }
+
+    #[test]
+    fn convert_decimal_to_hexadecimal() {
+        check_assist(
+            convert_number_representation,
+            "fn f() { let x = <|>1_000_000u64; }",
+            "fn f() { let x = <|>0xF_4240u64; }",
+        );
+    }
+
+    #[test]
+    fn convert_hexadecimal_to_binary() {
+        check_assist(
+            convert_number_representation,
+            "fn f() { let x = <|>0xFF_FF; }",
+            "fn f() { let x = <|>0b1111_1111_1111_1111; }",
+        );
+    }
+
+    #[test]
+    fn convert_binary_to_octal() {
+        check_assist(
+            convert_number_representation,
+            "fn f() { let x = <|>0b1010_1010i16; }",
+            "fn f() { let x = <|>0o252i16; }",
+        );
+    }
+
+    #[test]
+    fn convert_octal_to_decimal() {
+        check_assist(
+            convert_number_representation,
+            "fn f() { let x = <|>0o17777usize; }",
+            "fn f() { let x = <|>8_191usize; }",
+        );
+    }
+
+    #[test]
+    fn convert_max_u128() {
+        check_assist(
+            convert_number_representation,
+            "fn f() { let x = <|>340_282_366_920_938_463_463_374_607_431_768_211_455u128; }",
+            "fn f() { let x = <|>0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF_FFFFu128; }",
+        );
+    }
+
+    #[test]
+    fn convert_number_representation_not_applicable_to_floats() {
+        check_assist_not_applicable(convert_number_representation, "fn f() { let x = <|>1.5; }");
+        check_assist_not_applicable(convert_number_representation, "fn f() { let x = <|>1f32; }");
+    }
+
+    #[test]
+    fn convert_number_representation_not_applicable_in_attributes_and_macros() {
+        check_assist_not_applicable(
+            convert_number_representation,
+            "#[repr(align(<|>8))] struct S;",
+        );
+        check_assist_not_applicable(convert_number_representation, "fn f() { foo!(<|>92); }");
+    }
+}
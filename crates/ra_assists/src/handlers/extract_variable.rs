@@ -1,6 +1,8 @@
+use std::iter;
+
 use format_buf::format;
 use ra_syntax::{
-    ast::{self, AstNode},
+    ast::{self, edit::replace_descendants, make, AstNode},
     SyntaxKind::{
         BLOCK_EXPR, BREAK_EXPR, COMMENT, LAMBDA_EXPR, LOOP_EXPR, MATCH_ARM, PATH_EXPR, RETURN_EXPR,
         WHITESPACE,
@@ -11,7 +13,7 @@ use test_utils::tested_by;
 
 use crate::{Assist, AssistCtx, AssistId};
 
-// Assist: introduce_variable
+// Assist: extract_variable
 //
 // Extracts subexpression into a variable.
 //
@@ -27,13 +29,13 @@ use crate::{Assist, AssistCtx, AssistId};
 //     var_name * 4;
 // }
 // ```
-pub(crate) fn introduce_variable(ctx: AssistCtx) -> Option<Assist> {
+pub(crate) fn extract_variable(ctx: AssistCtx) -> Option<Assist> {
     if ctx.frange.range.is_empty() {
         return None;
     }
     let node = ctx.covering_element();
     if node.kind() == COMMENT {
-        tested_by!(introduce_var_in_comment_is_not_applicable);
+        tested_by!(extract_var_in_comment_is_not_applicable);
         return None;
     }
     let expr = node.ancestors().find_map(valid_target_expr)?;
@@ -42,29 +44,38 @@ pub(crate) fn introduce_variable(ctx: AssistCtx) -> Option<Assist> {
     if indent.kind() != WHITESPACE {
         return None;
     }
-    ctx.add_assist(AssistId("introduce_variable"), "Extract into variable", move |edit| {
+    let var_name = suggest_name(&expr);
+    let full_stmt = ast::ExprStmt::cast(anchor_stmt.clone());
+    let is_full_stmt = if let Some(expr_stmt) = &full_stmt {
+        Some(expr.syntax().clone()) == expr_stmt.expr().map(|e| e.syntax().clone())
+    } else {
+        false
+    };
+    let block_edit = if wrap_in_block || is_full_stmt {
+        None
+    } else {
+        Some(declare_var_before(&anchor_stmt, &expr, &var_name)?)
+    };
+    let target = expr.syntax().text_range();
+    ctx.add_assist(AssistId("extract_variable"), "Extract into variable", target, move |edit| {
         let mut buf = String::new();
 
         let cursor_offset = if wrap_in_block {
-            buf.push_str("{ let var_name = ");
+            buf.push_str("{ let ");
             TextUnit::of_str("{ let ")
         } else {
-            buf.push_str("let var_name = ");
+            buf.push_str("let ");
             TextUnit::of_str("let ")
         };
-        format!(buf, "{}", expr.syntax());
-        let full_stmt = ast::ExprStmt::cast(anchor_stmt.clone());
-        let is_full_stmt = if let Some(expr_stmt) = &full_stmt {
-            Some(expr.syntax().clone()) == expr_stmt.expr().map(|e| e.syntax().clone())
-        } else {
-            false
-        };
+        format!(buf, "{} = {}", var_name, expr.syntax());
         if is_full_stmt {
-            tested_by!(test_introduce_var_expr_stmt);
+            tested_by!(test_extract_var_expr_stmt);
             if !full_stmt.unwrap().has_semi() {
                 buf.push_str(";");
             }
             edit.replace(expr.syntax().text_range(), buf);
+        } else if let Some((block, new_block)) = block_edit {
+            edit.replace_ast(block, new_block);
         } else {
             buf.push_str(";");
 
@@ -79,17 +90,35 @@ pub(crate) fn introduce_variable(ctx: AssistCtx) -> Option<Assist> {
                 buf.push_str(text);
             }
 
-            edit.target(expr.syntax().text_range());
-            edit.replace(expr.syntax().text_range(), "var_name".to_string());
+            edit.replace(expr.syntax().text_range(), var_name);
             edit.insert(anchor_stmt.text_range().start(), buf);
-            if wrap_in_block {
-                edit.insert(anchor_stmt.text_range().end(), " }");
-            }
+            edit.insert(anchor_stmt.text_range().end(), " }");
         }
         edit.set_cursor(anchor_stmt.text_range().start() + cursor_offset);
     })
 }
 
+/// Replaces `expr` with a reference to `var_name` in the block containing
+/// `anchor_stmt`, and declares the variable right before `anchor_stmt`.
+/// Returns the old and the new block.
+fn declare_var_before(
+    anchor_stmt: &SyntaxNode,
+    expr: &ast::Expr,
+    var_name: &str,
+) -> Option<(ast::Block, ast::Block)> {
+    let block = anchor_stmt.parent().and_then(ast::Block::cast)?;
+    let anchor_idx = block.syntax().children().position(|it| &it == anchor_stmt)?;
+
+    let var_ref = make::expr_path(make::path_from_name_ref(make::name_ref(var_name)));
+    let new_block = replace_descendants(&block, iter::once((expr.clone(), var_ref)));
+    let new_anchor = new_block.syntax().children().nth(anchor_idx)?;
+
+    let pat = make::bind_pat(make::name(var_name));
+    let let_stmt = make::let_stmt(pat.into(), Some(expr.clone()));
+    let new_block = new_block.insert_stmt_before(&new_anchor, &let_stmt.into());
+    Some((block, new_block))
+}
+
 /// Check whether the node is a valid expression which can be extracted to a variable.
 /// In general that's true for any expression, but in some cases that would produce invalid code.
 fn valid_target_expr(node: SyntaxNode) -> Option<ast::Expr> {
@@ -104,6 +133,20 @@ fn valid_target_expr(node: SyntaxNode) -> Option<ast::Expr> {
     }
 }
 
+/// Suggests a name for the variable: the name of the called method or of the
+/// accessed named field, if any.
+fn suggest_name(expr: &ast::Expr) -> String {
+    let name_ref = match expr {
+        ast::Expr::MethodCallExpr(call) => call.name_ref(),
+        ast::Expr::FieldExpr(field) => field.name_ref(),
+        _ => None,
+    };
+    match name_ref {
+        Some(name_ref) if name_ref.as_tuple_field().is_none() => name_ref.text().to_string(),
+        _ => "var_name".to_string(),
+    }
+}
+
 /// Returns the syntax node which will follow the freshly introduced var
 /// and a boolean indicating whether we have to wrap it within a { } block
 /// to produce correct code.
@@ -113,7 +156,7 @@ fn anchor_stmt(expr: ast::Expr) -> Option<(SyntaxNode, bool)> {
     expr.syntax().ancestors().find_map(|node| {
         if let Some(expr) = node.parent().and_then(ast::Block::cast).and_then(|it| it.expr()) {
             if expr.syntax() == &node {
-                tested_by!(test_introduce_var_last_expr);
+                tested_by!(test_extract_var_last_expr);
                 return Some((node, false));
             }
         }
@@ -141,9 +184,9 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_introduce_var_simple() {
+    fn test_extract_var_simple() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() {
     foo(<|>1 + 1<|>);
@@ -157,16 +200,36 @@ fn foo() {
     }
 
     #[test]
-    fn introduce_var_in_comment_is_not_applicable() {
-        covers!(introduce_var_in_comment_is_not_applicable);
-        check_assist_not_applicable(introduce_variable, "fn main() { 1 + /* <|>comment<|> */ 1; }");
+    fn test_extract_var_keeps_comments() {
+        check_assist(
+            extract_variable,
+            "
+fn foo() {
+    // leading
+    foo(<|>1 /* one */ + 1<|>); // trailing
+    bar();
+}",
+            "
+fn foo() {
+    // leading
+    let <|>var_name = 1 /* one */ + 1;
+    foo(var_name); // trailing
+    bar();
+}",
+        );
     }
 
     #[test]
-    fn test_introduce_var_expr_stmt() {
-        covers!(test_introduce_var_expr_stmt);
+    fn extract_var_in_comment_is_not_applicable() {
+        covers!(extract_var_in_comment_is_not_applicable);
+        check_assist_not_applicable(extract_variable, "fn main() { 1 + /* <|>comment<|> */ 1; }");
+    }
+
+    #[test]
+    fn test_extract_var_expr_stmt() {
+        covers!(test_extract_var_expr_stmt);
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() {
     <|>1 + 1<|>;
@@ -177,7 +240,7 @@ fn foo() {
 }",
         );
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() {
     <|>{ let x = 0; x }<|>
@@ -192,9 +255,9 @@ fn foo() {
     }
 
     #[test]
-    fn test_introduce_var_part_of_expr_stmt() {
+    fn test_extract_var_part_of_expr_stmt() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() {
     <|>1<|> + 1;
@@ -208,10 +271,10 @@ fn foo() {
     }
 
     #[test]
-    fn test_introduce_var_last_expr() {
-        covers!(test_introduce_var_last_expr);
+    fn test_extract_var_last_expr() {
+        covers!(test_extract_var_last_expr);
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() {
     bar(<|>1 + 1<|>)
@@ -223,7 +286,7 @@ fn foo() {
 }",
         );
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() {
     <|>bar(1 + 1)<|>
@@ -237,9 +300,9 @@ fn foo() {
     }
 
     #[test]
-    fn test_introduce_var_in_match_arm_no_block() {
+    fn test_extract_var_in_match_arm_no_block() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let x = true;
@@ -262,9 +325,9 @@ fn main() {
     }
 
     #[test]
-    fn test_introduce_var_in_match_arm_with_block() {
+    fn test_extract_var_in_match_arm_with_block() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let x = true;
@@ -294,9 +357,9 @@ fn main() {
     }
 
     #[test]
-    fn test_introduce_var_in_closure_no_block() {
+    fn test_extract_var_in_closure_no_block() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let lambda = |x: u32| <|>x * 2<|>;
@@ -311,9 +374,9 @@ fn main() {
     }
 
     #[test]
-    fn test_introduce_var_in_closure_with_block() {
+    fn test_extract_var_in_closure_with_block() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let lambda = |x: u32| { <|>x * 2<|> };
@@ -328,9 +391,9 @@ fn main() {
     }
 
     #[test]
-    fn test_introduce_var_path_simple() {
+    fn test_extract_var_path_simple() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let o = <|>Some(true)<|>;
@@ -346,9 +409,9 @@ fn main() {
     }
 
     #[test]
-    fn test_introduce_var_path_method() {
+    fn test_extract_var_path_method() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let v = <|>bar.foo()<|>;
@@ -356,17 +419,76 @@ fn main() {
 ",
             "
 fn main() {
-    let <|>var_name = bar.foo();
-    let v = var_name;
+    let <|>foo = bar.foo();
+    let v = foo;
+}
+",
+        );
+    }
+
+    #[test]
+    fn test_extract_var_field() {
+        check_assist(
+            extract_variable,
+            "
+fn main() {
+    let v = <|>bar.foo<|> + 1;
+}
+",
+            "
+fn main() {
+    let <|>foo = bar.foo;
+    let v = foo + 1;
+}
+",
+        );
+    }
+
+    #[test]
+    fn test_extract_var_tuple_field() {
+        check_assist(
+            extract_variable,
+            "
+fn main() {
+    let v = <|>bar.0<|> + 1;
+}
+",
+            "
+fn main() {
+    let <|>var_name = bar.0;
+    let v = var_name + 1;
+}
+",
+        );
+    }
+
+    #[test]
+    fn test_extract_var_method_in_match_arm() {
+        check_assist(
+            extract_variable,
+            "
+fn main() {
+    let x = match y {
+        true => <|>s.len()<|> + 1,
+        _ => 0,
+    };
+}
+",
+            "
+fn main() {
+    let x = match y {
+        true => { let <|>len = s.len(); len + 1 },
+        _ => 0,
+    };
 }
 ",
         );
     }
 
     #[test]
-    fn test_introduce_var_return() {
+    fn test_extract_var_return() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() -> u32 {
     <|>return 2 + 2<|>;
@@ -382,9 +504,9 @@ fn foo() -> u32 {
     }
 
     #[test]
-    fn test_introduce_var_does_not_add_extra_whitespace() {
+    fn test_extract_var_does_not_add_extra_whitespace() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() -> u32 {
 
@@ -403,7 +525,7 @@ fn foo() -> u32 {
         );
 
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() -> u32 {
 
@@ -420,7 +542,7 @@ fn foo() -> u32 {
         );
 
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn foo() -> u32 {
     let foo = 1;
@@ -446,9 +568,9 @@ fn foo() -> u32 {
     }
 
     #[test]
-    fn test_introduce_var_break() {
+    fn test_extract_var_break() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let result = loop {
@@ -468,9 +590,9 @@ fn main() {
     }
 
     #[test]
-    fn test_introduce_var_for_cast() {
+    fn test_extract_var_for_cast() {
         check_assist(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let v = <|>0f32 as u32<|>;
@@ -486,22 +608,22 @@ fn main() {
     }
 
     #[test]
-    fn test_introduce_var_for_return_not_applicable() {
-        check_assist_not_applicable(introduce_variable, "fn foo() { <|>return<|>; } ");
+    fn test_extract_var_for_return_not_applicable() {
+        check_assist_not_applicable(extract_variable, "fn foo() { <|>return<|>; } ");
     }
 
     #[test]
-    fn test_introduce_var_for_break_not_applicable() {
-        check_assist_not_applicable(introduce_variable, "fn main() { loop { <|>break<|>; }; }");
+    fn test_extract_var_for_break_not_applicable() {
+        check_assist_not_applicable(extract_variable, "fn main() { loop { <|>break<|>; }; }");
     }
 
     // FIXME: This is not quite correct, but good enough(tm) for the sorting heuristic
     #[test]
-    fn introduce_var_target() {
-        check_assist_target(introduce_variable, "fn foo() -> u32 { <|>return 2 + 2<|>; }", "2 + 2");
+    fn extract_var_target() {
+        check_assist_target(extract_variable, "fn foo() -> u32 { <|>return 2 + 2<|>; }", "2 + 2");
 
         check_assist_target(
-            introduce_variable,
+            extract_variable,
             "
 fn main() {
     let x = true;
@@ -57,7 +57,8 @@ pub(crate) fn fill_match_arms(ctx: AssistCtx) -> Option<Assist> {
 
     let db = ctx.db;
 
-    ctx.add_assist(AssistId("fill_match_arms"), "Fill match arms", |edit| {
+    let target = match_expr.syntax().text_range();
+    ctx.add_assist(AssistId("fill_match_arms"), "Fill match arms", target, |edit| {
         let indent_level = IndentLevel::from_node(match_arm_list.syntax());
 
         let new_arm_list = {
@@ -68,7 +69,6 @@ pub(crate) fn fill_match_arms(ctx: AssistCtx) -> Option<Assist> {
             indent_level.increase_indent(make::match_arm_list(arms))
         };
 
-        edit.target(match_expr.syntax().text_range());
         edit.set_cursor(expr.syntax().text_range().start());
         edit.replace_ast(match_arm_list, new_arm_list);
     })
@@ -1,4 +1,7 @@
-use ra_syntax::ast::{AstNode, BinExpr, BinOp};
+use ra_syntax::{
+    ast::{BinExpr, BinOp},
+    SyntaxKind, T,
+};
 
 use crate::{Assist, AssistCtx, AssistId};
 
@@ -19,8 +22,6 @@ use crate::{Assist, AssistCtx, AssistId};
 // ```
 pub(crate) fn flip_binexpr(ctx: AssistCtx) -> Option<Assist> {
     let expr = ctx.find_node_at_offset::<BinExpr>()?;
-    let lhs = expr.lhs()?.syntax().clone();
-    let rhs = expr.rhs()?.syntax().clone();
     let op_range = expr.op_token()?.text_range();
     // The assist should be applied only if the cursor is on the operator
     let cursor_in_range = ctx.frange.range.is_subrange(&op_range);
@@ -33,21 +34,21 @@ pub(crate) fn flip_binexpr(ctx: AssistCtx) -> Option<Assist> {
         return None;
     }
 
-    ctx.add_assist(AssistId("flip_binexpr"), "Flip binary expression", |edit| {
-        edit.target(op_range);
-        if let FlipAction::FlipAndReplaceOp(new_op) = action {
-            edit.replace(op_range, new_op);
-        }
-        edit.replace(lhs.text_range(), rhs.text());
-        edit.replace(rhs.text_range(), lhs.text());
+    let mut flipped = expr.swap_operands()?;
+    if let FlipAction::FlipAndReplaceOp(new_op) = action {
+        flipped = flipped.replace_op(new_op)?;
+    }
+
+    ctx.add_assist(AssistId("flip_binexpr"), "Flip binary expression", op_range, |edit| {
+        edit.replace_ast(expr, flipped);
     })
 }
 
 enum FlipAction {
     // Flip the expression
     Flip,
-    // Flip the expression and replace the operator with this string
-    FlipAndReplaceOp(&'static str),
+    // Flip the expression and replace the operator with this one
+    FlipAndReplaceOp(SyntaxKind),
     // Do not flip the expression
     DontFlip,
 }
@@ -56,10 +57,10 @@ impl From<BinOp> for FlipAction {
     fn from(op_kind: BinOp) -> Self {
         match op_kind {
             kind if kind.is_assignment() => FlipAction::DontFlip,
-            BinOp::GreaterTest => FlipAction::FlipAndReplaceOp("<"),
-            BinOp::GreaterEqualTest => FlipAction::FlipAndReplaceOp("<="),
-            BinOp::LesserTest => FlipAction::FlipAndReplaceOp(">"),
-            BinOp::LesserEqualTest => FlipAction::FlipAndReplaceOp(">="),
+            BinOp::GreaterTest => FlipAction::FlipAndReplaceOp(T![<]),
+            BinOp::GreaterEqualTest => FlipAction::FlipAndReplaceOp(T![<=]),
+            BinOp::LesserTest => FlipAction::FlipAndReplaceOp(T![>]),
+            BinOp::LesserEqualTest => FlipAction::FlipAndReplaceOp(T![>=]),
             _ => FlipAction::Flip,
         }
     }
@@ -117,6 +118,15 @@ mod tests {
         )
     }
 
+    #[test]
+    fn flip_binexpr_keeps_comments() {
+        check_assist(
+            flip_binexpr,
+            "fn f() { let res = 1 /* one */ <<|> /* two */ 2; }",
+            "fn f() { let res = 2 /* one */ ><|> /* two */ 1; }",
+        )
+    }
+
     #[test]
     fn flip_binexpr_works_inside_match() {
         check_assist(
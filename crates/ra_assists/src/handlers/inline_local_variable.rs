@@ -95,6 +95,7 @@ pub(crate) fn inline_local_variable(ctx: AssistCtx) -> Option<Assist> {
     ctx.add_assist(
         AssistId("inline_local_variable"),
         "Inline variable",
+        bind_pat.syntax().text_range(),
         move |edit: &mut ActionBuilder| {
             edit.delete(delete_range);
             for (desc, should_wrap) in refs.iter().zip(wrap_in_parens) {
@@ -40,8 +40,7 @@ pub(crate) fn invert_if(ctx: AssistCtx) -> Option<Assist> {
         let else_node = else_block.syntax();
         let else_range = else_node.text_range();
         let then_range = then_node.text_range();
-        return ctx.add_assist(AssistId("invert_if"), "Invert if", |edit| {
-            edit.target(if_range);
+        return ctx.add_assist(AssistId("invert_if"), "Invert if", if_range, |edit| {
             edit.replace(cond_range, flip_cond.syntax().text());
             edit.replace(else_range, then_node.text());
             edit.replace(then_range, else_node.text());
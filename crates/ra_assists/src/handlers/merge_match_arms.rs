@@ -1,8 +1,10 @@
 use std::iter::successors;
 
 use ra_syntax::{
-    ast::{self, AstNode},
-    Direction, TextUnit,
+    ast::{self, edit::IndentLevel, AstNode, NameOwner},
+    Direction,
+    SyntaxKind::COMMENT,
+    TextUnit,
 };
 
 use crate::{Assist, AssistCtx, AssistId, TextRange};
@@ -33,12 +35,11 @@ use crate::{Assist, AssistCtx, AssistId, TextRange};
 // ```
 pub(crate) fn merge_match_arms(ctx: AssistCtx) -> Option<Assist> {
     let current_arm = ctx.find_node_at_offset::<ast::MatchArm>()?;
-    // Don't try to handle arms with guards for now - can add support for this later
-    if current_arm.guard().is_some() {
-        return None;
-    }
     let current_expr = current_arm.expr()?;
+    let current_guard = guard_text(&current_arm);
+    let current_bindings = bindings(&current_arm);
     let current_text_range = current_arm.syntax().text_range();
+    let indent = "    ".repeat(IndentLevel::from_node(current_arm.syntax()).0 as usize);
 
     enum CursorPos {
         InExpr(TextUnit),
@@ -55,7 +56,12 @@ pub(crate) fn merge_match_arms(ctx: AssistCtx) -> Option<Assist> {
     // compare to the previous match arm as well.
     let arms_to_merge = successors(Some(current_arm), next_arm)
         .take_while(|arm| {
-            if arm.guard().is_some() {
+            // A guard can only be kept if all merged arms share it.
+            if guard_text(arm) != current_guard {
+                return false;
+            }
+            // All alternatives of an or-pattern must bind the same names.
+            if bindings(arm) != current_bindings {
                 return false;
             }
             match arm.expr() {
@@ -69,7 +75,7 @@ pub(crate) fn merge_match_arms(ctx: AssistCtx) -> Option<Assist> {
         return None;
     }
 
-    ctx.add_assist(AssistId("merge_match_arms"), "Merge match arms", |edit| {
+    ctx.add_assist(AssistId("merge_match_arms"), "Merge match arms", current_text_range, |edit| {
         let pats = if arms_to_merge.iter().any(contains_placeholder) {
             "_".into()
         } else {
@@ -81,15 +87,31 @@ pub(crate) fn merge_match_arms(ctx: AssistCtx) -> Option<Assist> {
                 .join(" | ")
         };
 
-        let arm = format!("{} => {}", pats, current_expr.syntax().text());
+        let guard = match &current_guard {
+            Some(guard) => format!(" {}", guard),
+            None => String::new(),
+        };
 
         let start = arms_to_merge.first().unwrap().syntax().text_range().start();
         let end = arms_to_merge.last().unwrap().syntax().text_range().end();
 
-        edit.target(current_text_range);
+        // Comments between the merged arms would be swallowed by the edit, so
+        // move them in front of the merged arm.
+        let comments = arms_to_merge
+            .first()
+            .unwrap()
+            .syntax()
+            .siblings_with_tokens(Direction::Next)
+            .take_while(|it| it.text_range().end() <= end)
+            .filter_map(|it| it.into_token())
+            .filter(|it| it.kind() == COMMENT)
+            .map(|it| format!("{}\n{}", it.text(), indent))
+            .collect::<String>();
+        let arm = format!("{}{}{} => {}", comments, pats, guard, current_expr.syntax().text());
+
         edit.set_cursor(match cursor_pos {
             CursorPos::InExpr(back_offset) => start + TextUnit::from_usize(arm.len()) - back_offset,
-            CursorPos::InPat(offset) => offset,
+            CursorPos::InPat(offset) => offset + TextUnit::of_str(&comments),
         });
         edit.replace(TextRange::from_to(start, end), arm);
     })
@@ -102,6 +124,26 @@ fn contains_placeholder(a: &ast::MatchArm) -> bool {
     }
 }
 
+fn guard_text(arm: &ast::MatchArm) -> Option<String> {
+    arm.guard().map(|guard| guard.syntax().text().to_string())
+}
+
+fn bindings(arm: &ast::MatchArm) -> Vec<String> {
+    let mut res = match arm.pat() {
+        Some(pat) => pat
+            .syntax()
+            .descendants()
+            .filter_map(ast::BindPat::cast)
+            .filter_map(|it| it.name())
+            .map(|it| it.text().to_string())
+            .collect(),
+        None => Vec::new(),
+    };
+    res.sort();
+    res.dedup();
+    res
+}
+
 fn next_arm(arm: &ast::MatchArm) -> Option<ast::MatchArm> {
     arm.syntax().siblings(Direction::Next).skip(1).find_map(ast::MatchArm::cast)
 }
@@ -261,4 +303,133 @@ mod tests {
             "#,
         );
     }
+
+    #[test]
+    fn merge_match_arms_with_same_guard() {
+        check_assist(
+            merge_match_arms,
+            r#"
+            enum X { A(i32), B(i32), C }
+
+            fn main() {
+                let x = X::A(92);
+                let y = match x {
+                    X::A(a) if a > 5 => { <|>1i32 },
+                    X::B(a) if a > 5 => { 1i32 },
+                    X::C => { 2i32 }
+                }
+            }
+            "#,
+            r#"
+            enum X { A(i32), B(i32), C }
+
+            fn main() {
+                let x = X::A(92);
+                let y = match x {
+                    X::A(a) | X::B(a) if a > 5 => { <|>1i32 },
+                    X::C => { 2i32 }
+                }
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn merge_match_arms_rejects_different_guards() {
+        check_assist_not_applicable(
+            merge_match_arms,
+            r#"
+            enum X { A(i32), B(i32), C }
+
+            fn main() {
+                let x = X::A(92);
+                let y = match x {
+                    X::A(a) if a > 5 => { <|>1i32 },
+                    X::B(a) if a > 6 => { 1i32 },
+                    X::C => { 2i32 }
+                }
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn merge_match_arms_rejects_different_bindings() {
+        check_assist_not_applicable(
+            merge_match_arms,
+            r#"
+            enum X { A(i32), B(i32), C }
+
+            fn main() {
+                let x = X::A(92);
+                let y = match x {
+                    X::A(a) => { <|>1i32 },
+                    X::B(b) => { 1i32 },
+                    X::C => { 2i32 }
+                }
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn merge_match_arms_keeps_comments() {
+        check_assist(
+            merge_match_arms,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                let y = match X::A {
+                    // first
+                    <|>X::A => 1i32, // trailing
+                    // second
+                    X::B => 1i32,
+                    X::C => 2i32,
+                }
+            }
+            "#,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                let y = match X::A {
+                    // first
+                    // trailing
+                    // second
+                    <|>X::A | X::B => 1i32,
+                    X::C => 2i32,
+                }
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn merge_match_arms_last_arm_without_comma() {
+        check_assist(
+            merge_match_arms,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                let y = match X::A {
+                    X::A => 1i32,
+                    X::B<|> => 2i32,
+                    X::C => 2i32
+                }
+            }
+            "#,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                let y = match X::A {
+                    X::A => 1i32,
+                    X::B<|> | X::C => 2i32
+                }
+            }
+            "#,
+        );
+    }
 }
@@ -44,7 +44,8 @@ pub(crate) fn replace_if_let_with_match(ctx: AssistCtx) -> Option<Assist> {
         ast::ElseBranch::IfExpr(_) => return None,
     };
 
-    ctx.add_assist(AssistId("replace_if_let_with_match"), "Replace with match", |edit| {
+    let target = if_expr.syntax().text_range();
+    ctx.add_assist(AssistId("replace_if_let_with_match"), "Replace with match", target, |edit| {
         let match_expr = {
             let then_arm = {
                 let then_expr = unwrap_trivial_block(then_block);
@@ -59,7 +60,6 @@ pub(crate) fn replace_if_let_with_match(ctx: AssistCtx) -> Option<Assist> {
 
         let match_expr = IndentLevel::from_node(if_expr.syntax()).increase_indent(match_expr);
 
-        edit.target(if_expr.syntax().text_range());
         edit.set_cursor(if_expr.syntax().text_range().start());
         edit.replace_ast::<ast::Expr>(if_expr.into(), match_expr);
     })
@@ -75,6 +75,7 @@ pub(crate) fn replace_qualified_name_with_use(ctx: AssistCtx) -> Option<Assist>
     ctx.add_assist(
         AssistId("replace_qualified_name_with_use"),
         "Replace qualified path with use",
+        path.syntax().text_range(),
         |edit| {
             replace_with_use(&position, &path, &segments, edit.text_edit_builder());
         },
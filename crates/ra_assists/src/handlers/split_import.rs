@@ -31,8 +31,7 @@ pub(crate) fn split_import(ctx: AssistCtx) -> Option<Assist> {
         None => top_path.syntax().text_range().end(),
     };
 
-    ctx.add_assist(AssistId("split_import"), "Split import", |edit| {
-        edit.target(colon_colon.text_range());
+    ctx.add_assist(AssistId("split_import"), "Split import", colon_colon.text_range(), |edit| {
         edit.insert(l_curly, "{");
         edit.insert(r_curly, "}");
         edit.set_cursor(l_curly + TextUnit::of_str("{"));
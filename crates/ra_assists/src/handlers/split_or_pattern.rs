@@ -0,0 +1,228 @@
+use ra_syntax::ast::{self, edit::IndentLevel, AstNode};
+
+use crate::{Assist, AssistCtx, AssistId};
+
+// Assist: split_or_pattern
+//
+// Splits a match arm with an or-pattern into one arm per alternative.
+//
+// ```
+// enum Action { Move { distance: u32 }, Stop }
+//
+// fn handle(action: Action) {
+//     match action {
+//         <|>Action::Move(..) | Action::Stop => foo(),
+//     }
+// }
+// ```
+// ->
+// ```
+// enum Action { Move { distance: u32 }, Stop }
+//
+// fn handle(action: Action) {
+//     match action {
+//         Action::Move(..) => foo(),
+//         Action::Stop => foo(),
+//     }
+// }
+// ```
+pub(crate) fn split_or_pattern(ctx: AssistCtx) -> Option<Assist> {
+    let arm = ctx.find_node_at_offset::<ast::MatchArm>()?;
+    let or_pat = match arm.pat()? {
+        ast::Pat::OrPat(it) => it,
+        _ => return None,
+    };
+    let expr = arm.expr()?;
+
+    let arm_range = arm.syntax().text_range();
+    let pat_range = or_pat.syntax().text_range();
+    let arm_text = arm.syntax().text().to_string();
+    let prefix = &arm_text[..(pat_range.start() - arm_range.start()).to_usize()];
+    let suffix = &arm_text[(pat_range.end() - arm_range.start()).to_usize()..];
+
+    // Arms with a block body don't need a comma to separate them from the
+    // next one. The comma after the last arm, if any, is left in place.
+    let separator = match expr {
+        ast::Expr::BlockExpr(_) => "",
+        _ => ",",
+    };
+    let indent = "    ".repeat(IndentLevel::from_node(arm.syntax()).0 as usize);
+    let arms = or_pat
+        .pats()
+        .map(|pat| format!("{}{}{}", prefix, pat.syntax(), suffix))
+        .collect::<Vec<_>>()
+        .join(&format!("{}\n{}", separator, indent));
+
+    ctx.add_assist(
+        AssistId("split_or_pattern"),
+        "Split or-pattern into separate arms",
+        pat_range,
+        |edit| {
+            edit.set_cursor(arm_range.start());
+            edit.replace(arm_range, arms);
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_or_pattern;
+    use crate::helpers::{check_assist, check_assist_not_applicable, check_assist_target};
+
+    #[test]
+    fn split_or_pattern_simple() {
+        check_assist(
+            split_or_pattern,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                let y = match X::A {
+                    X::A |<|> X::B => 1i32,
+                    X::C => 2i32,
+                };
+            }
+            "#,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                let y = match X::A {
+                    <|>X::A => 1i32,
+                    X::B => 1i32,
+                    X::C => 2i32,
+                };
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn split_or_pattern_last_arm_without_comma() {
+        check_assist(
+            split_or_pattern,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                let y = match X::A {
+                    X::A => 1i32,
+                    X::B | X::C<|> => 2i32
+                };
+            }
+            "#,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                let y = match X::A {
+                    X::A => 1i32,
+                    <|>X::B => 2i32,
+                    X::C => 2i32
+                };
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn split_or_pattern_block_body() {
+        check_assist(
+            split_or_pattern,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                match X::A {
+                    X::A | X::B | X::C<|> => {
+                        // keep me
+                        foo();
+                    }
+                }
+            }
+            "#,
+            r#"
+            enum X { A, B, C }
+
+            fn main() {
+                match X::A {
+                    <|>X::A => {
+                        // keep me
+                        foo();
+                    }
+                    X::B => {
+                        // keep me
+                        foo();
+                    }
+                    X::C => {
+                        // keep me
+                        foo();
+                    }
+                }
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn split_or_pattern_with_guard_and_bindings() {
+        check_assist(
+            split_or_pattern,
+            r#"
+            enum X { A(i32), B(i32), C }
+
+            fn main() {
+                let y = match X::A(92) {
+                    X::A(a) | X::B(a)<|> if a > 5 => a + 1,
+                    _ => 0,
+                };
+            }
+            "#,
+            r#"
+            enum X { A(i32), B(i32), C }
+
+            fn main() {
+                let y = match X::A(92) {
+                    <|>X::A(a) if a > 5 => a + 1,
+                    X::B(a) if a > 5 => a + 1,
+                    _ => 0,
+                };
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn split_or_pattern_target() {
+        check_assist_target(
+            split_or_pattern,
+            r#"
+            enum X { A, B }
+
+            fn main() {
+                match X::A {
+                    X::A | X::B<|> => (),
+                }
+            }
+            "#,
+            "X::A | X::B",
+        );
+    }
+
+    #[test]
+    fn split_or_pattern_not_applicable_without_or_pattern() {
+        check_assist_not_applicable(
+            split_or_pattern,
+            r#"
+            enum X { A, B }
+
+            fn main() {
+                match X::A {
+                    X::A<|> => (),
+                    X::B => (),
+                }
+            }
+            "#,
+        );
+    }
+}
@@ -14,10 +14,10 @@ pub mod ast_transform;
 
 use ra_db::FileRange;
 use ra_ide_db::RootDatabase;
-use ra_syntax::{TextRange, TextUnit};
+use ra_syntax::{AstNode, TextRange, TextUnit};
 use ra_text_edit::TextEdit;
 
-pub(crate) use crate::assist_ctx::{Assist, AssistCtx, AssistHandler};
+pub(crate) use crate::assist_ctx::{Assist, AssistCtx, AssistHandler, ResolveStrategy};
 pub use crate::handlers::replace_qualified_name_with_use::insert_use_statement;
 use hir::Semantics;
 
@@ -31,16 +31,18 @@ pub struct AssistLabel {
     /// Short description of the assist, as shown in the UI.
     pub label: String,
     pub id: AssistId,
+    /// The range the assist is active within.
+    pub target: TextRange,
 }
 
 #[derive(Clone, Debug)]
 pub struct GroupLabel(pub String);
 
 impl AssistLabel {
-    pub(crate) fn new(label: String, id: AssistId) -> AssistLabel {
+    pub(crate) fn new(label: String, id: AssistId, target: TextRange) -> AssistLabel {
         // FIXME: make fields private, so that this invariant can't be broken
         assert!(label.starts_with(|c: char| c.is_uppercase()));
-        AssistLabel { label, id }
+        AssistLabel { label, id, target }
     }
 }
 
@@ -48,8 +50,12 @@ impl AssistLabel {
 pub struct AssistAction {
     pub edit: TextEdit,
     pub cursor_position: Option<TextUnit>,
-    // FIXME: This belongs to `AssistLabel`
-    pub target: Option<TextRange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UnresolvedAssist {
+    pub label: AssistLabel,
+    pub group_label: Option<GroupLabel>,
 }
 
 #[derive(Debug, Clone)]
@@ -62,16 +68,19 @@ pub struct ResolvedAssist {
 /// Return all the assists applicable at the given position.
 ///
 /// Assists are returned in the "unresolved" state, that is only labels are
-/// returned, without actual edits.
-pub fn unresolved_assists(db: &RootDatabase, range: FileRange) -> Vec<AssistLabel> {
+/// returned, without actual edits. Use `resolve_assist` to compute the edit of
+/// one of them.
+pub fn unresolved_assists(db: &RootDatabase, range: FileRange) -> Vec<UnresolvedAssist> {
     let sema = Semantics::new(db);
-    let ctx = AssistCtx::new(&sema, range, false);
-    handlers::all()
+    let ctx = AssistCtx::new(&sema, range, ResolveStrategy::None);
+    let mut a = handlers::all()
         .iter()
         .filter_map(|f| f(ctx.clone()))
         .flat_map(|it| it.0)
-        .map(|a| a.label)
-        .collect()
+        .map(|it| UnresolvedAssist { label: it.label, group_label: it.group_label })
+        .collect::<Vec<_>>();
+    a.sort_by_key(|it| it.label.target.len());
+    a
 }
 
 /// Return all the assists applicable at the given position.
@@ -80,17 +89,41 @@ pub fn unresolved_assists(db: &RootDatabase, range: FileRange) -> Vec<AssistLabe
 /// computed.
 pub fn resolved_assists(db: &RootDatabase, range: FileRange) -> Vec<ResolvedAssist> {
     let sema = Semantics::new(db);
-    let ctx = AssistCtx::new(&sema, range, true);
+    let ctx = AssistCtx::new(&sema, range, ResolveStrategy::All);
     let mut a = handlers::all()
         .iter()
         .filter_map(|f| f(ctx.clone()))
         .flat_map(|it| it.0)
         .map(|it| it.into_resolved().unwrap())
         .collect::<Vec<_>>();
-    a.sort_by_key(|it| it.action.target.map_or(TextUnit::from(!0u32), |it| it.len()));
+    a.sort_by_key(|it| it.label.target.len());
     a
 }
 
+/// Computes the edit of the assist with the given `id` and `label`, returned by
+/// `unresolved_assists` for the same range.
+///
+/// Returns `None` if the assist is not applicable anymore, for example because
+/// the file was changed in between.
+pub fn resolve_assist(
+    db: &RootDatabase,
+    range: FileRange,
+    id: &str,
+    label: &str,
+) -> Option<ResolvedAssist> {
+    let sema = Semantics::new(db);
+    let file_range = sema.parse(range.file_id).syntax().text_range();
+    if !range.range.is_subrange(&file_range) {
+        return None;
+    }
+    let ctx = AssistCtx::new(&sema, range, ResolveStrategy::Single { id, label });
+    handlers::all()
+        .iter()
+        .filter_map(|f| f(ctx.clone()))
+        .flat_map(|it| it.0)
+        .find_map(|it| it.into_resolved())
+}
+
 mod handlers {
     use crate::AssistHandler;
 
@@ -106,14 +139,16 @@ mod handlers {
     mod flip_binexpr;
     mod flip_trait_bound;
     mod change_visibility;
+    mod convert_number_representation;
     mod fill_match_arms;
     mod merge_match_arms;
-    mod introduce_variable;
+    mod extract_variable;
     mod inline_local_variable;
     mod raw_string;
     mod remove_mut;
     mod replace_if_let_with_match;
     mod split_import;
+    mod split_or_pattern;
     mod remove_dbg;
     pub(crate) mod replace_qualified_name_with_use;
     mod add_missing_impl_members;
@@ -131,14 +166,16 @@ mod handlers {
             apply_demorgan::apply_demorgan,
             invert_if::invert_if,
             change_visibility::change_visibility,
+            convert_number_representation::convert_number_representation,
             fill_match_arms::fill_match_arms,
             merge_match_arms::merge_match_arms,
             flip_comma::flip_comma,
             flip_binexpr::flip_binexpr,
             flip_trait_bound::flip_trait_bound,
-            introduce_variable::introduce_variable,
+            extract_variable::extract_variable,
             replace_if_let_with_match::replace_if_let_with_match,
             split_import::split_import,
+            split_or_pattern::split_or_pattern,
             remove_dbg::remove_dbg,
             replace_qualified_name_with_use::replace_qualified_name_with_use,
             add_missing_impl_members::add_missing_impl_members,
@@ -167,7 +204,7 @@ mod helpers {
     use ra_syntax::TextRange;
     use test_utils::{add_cursor, assert_eq_text, extract_range_or_offset, RangeOrOffset};
 
-    use crate::{AssistCtx, AssistHandler};
+    use crate::{AssistCtx, AssistHandler, ResolveStrategy};
     use hir::Semantics;
 
     pub(crate) fn with_single_file(text: &str) -> (RootDatabase, FileId) {
@@ -194,6 +231,49 @@ mod helpers {
         check(assist, before, ExpectedResult::NotApplicable);
     }
 
+    /// Checks the labels and the results of all the assists of a group, for
+    /// the cursor position of a multi-file fixture. The results are the texts
+    /// of the file with the cursor, with their indentation trimmed.
+    pub(crate) fn check_assist_group_with_fixture(
+        assist: AssistHandler,
+        fixture: &str,
+        expected: &[(&str, &str)],
+    ) {
+        let (mut db, position) = RootDatabase::with_position(fixture);
+        let local_roots = vec![db.file_source_root(position.file_id)];
+        db.set_local_roots(Arc::new(local_roots));
+        let frange = FileRange {
+            file_id: position.file_id,
+            range: TextRange::offset_len(position.offset, 0.into()),
+        };
+        let sema = Semantics::new(&db);
+        let assist_ctx = AssistCtx::new(&sema, frange, ResolveStrategy::All);
+        let assist = assist(assist_ctx).expect("code action is not applicable");
+
+        let before = db.file_text(position.file_id);
+        let actual = assist
+            .0
+            .iter()
+            .map(|it| (it.label.label.as_str(), it.action.as_ref().unwrap().edit.apply(&before)))
+            .collect::<Vec<_>>();
+        let labels = actual.iter().map(|(label, _)| *label).collect::<Vec<_>>();
+        let expected_labels = expected.iter().map(|(label, _)| *label).collect::<Vec<_>>();
+        assert_eq!(labels, expected_labels);
+        for ((_, actual), (_, after)) in actual.iter().zip(expected) {
+            assert_eq_text!(trim_indent(after).trim(), actual.trim());
+        }
+    }
+
+    fn trim_indent(text: &str) -> String {
+        let indent = text
+            .lines()
+            .filter(|it| !it.trim().is_empty())
+            .map(|it| it.len() - it.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        text.lines().map(|it| it.get(indent..).unwrap_or("")).collect::<Vec<_>>().join("\n")
+    }
+
     enum ExpectedResult<'a> {
         NotApplicable,
         After(&'a str),
@@ -207,7 +287,7 @@ mod helpers {
         let (db, file_id) = with_single_file(&before);
         let frange = FileRange { file_id, range };
         let sema = Semantics::new(&db);
-        let assist_ctx = AssistCtx::new(&sema, frange, true);
+        let assist_ctx = AssistCtx::new(&sema, frange, ResolveStrategy::All);
 
         match (assist(assist_ctx), expected) {
             (Some(assist), ExpectedResult::After(after)) => {
@@ -230,8 +310,7 @@ mod helpers {
                 assert_eq_text!(after, &actual);
             }
             (Some(assist), ExpectedResult::Target(target)) => {
-                let action = assist.0[0].action.clone().unwrap();
-                let range = action.target.expect("expected target on action");
+                let range = assist.0[0].label.target;
                 assert_eq_text!(&before[range.start().to_usize()..range.end().to_usize()], target);
             }
             (Some(_), ExpectedResult::NotApplicable) => panic!("assist should not be applicable!"),
@@ -245,11 +324,13 @@ mod helpers {
 
 #[cfg(test)]
 mod tests {
-    use ra_db::FileRange;
+    use std::sync::Arc;
+
+    use ra_db::{FileRange, SourceDatabaseExt};
     use ra_syntax::TextRange;
     use test_utils::{extract_offset, extract_range};
 
-    use crate::{helpers, resolved_assists};
+    use crate::{helpers, resolve_assist, resolved_assists, unresolved_assists};
 
     #[test]
     fn assist_order_field_struct() {
@@ -287,4 +368,42 @@ mod tests {
         assert_eq!(assists.next().expect("expected assist").label.label, "Extract into variable");
         assert_eq!(assists.next().expect("expected assist").label.label, "Replace with match");
     }
+
+    #[test]
+    fn resolve_unresolved_assist() {
+        let before = "fn foo() { let _ = 90 +<|> 2; }";
+        let (offset, before) = extract_offset(before);
+        let (db, file_id) = helpers::with_single_file(&before);
+        let frange = FileRange { file_id, range: TextRange::offset_len(offset, 0.into()) };
+
+        let assists = unresolved_assists(&db, frange);
+        let flip = assists.iter().find(|it| it.label.id.0 == "flip_binexpr").unwrap();
+        assert_eq!(&before[flip.label.target], "+");
+
+        let resolved = resolve_assist(&db, frange, "flip_binexpr", &flip.label.label).unwrap();
+        assert_eq!(resolved.action.edit.apply(&before), "fn foo() { let _ = 2 + 90; }");
+        assert!(resolve_assist(&db, frange, "flip_binexpr", "Flip comma").is_none());
+    }
+
+    #[test]
+    fn resolve_assist_after_edit() {
+        let before = "fn foo() { let _ = 90 +<|> 2; }";
+        let (offset, before) = extract_offset(before);
+        let (mut db, file_id) = helpers::with_single_file(&before);
+        let frange = FileRange { file_id, range: TextRange::offset_len(offset, 0.into()) };
+        let label = "Flip binary expression";
+        assert!(unresolved_assists(&db, frange).iter().any(|it| it.label.label == label));
+
+        // Assists are checked again when they are resolved.
+        db.set_file_text(file_id, Arc::new("fn foo() { let _ = 90 - 2; }".to_string()));
+        assert!(resolve_assist(&db, frange, "flip_binexpr", label).is_some());
+
+        // The assist is not applicable at the original range anymore.
+        db.set_file_text(file_id, Arc::new("fn foo() { let _ = 902; }".to_string()));
+        assert!(resolve_assist(&db, frange, "flip_binexpr", label).is_none());
+
+        // The original range is not even in the file anymore.
+        db.set_file_text(file_id, Arc::new("fn foo() {}".to_string()));
+        assert!(resolve_assist(&db, frange, "flip_binexpr", label).is_none());
+    }
 }
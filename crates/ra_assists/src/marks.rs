@@ -1,7 +1,7 @@
 //! See test_utils/src/marks.rs
 
 test_utils::marks!(
-    introduce_var_in_comment_is_not_applicable
-    test_introduce_var_expr_stmt
-    test_introduce_var_last_expr
+    extract_var_in_comment_is_not_applicable
+    test_extract_var_expr_stmt
+    test_extract_var_last_expr
 );
@@ -2,7 +2,7 @@
 //! another compatible command (f.x. clippy) in a background thread and provide
 //! LSP diagnostics based on the output of the command.
 use cargo_metadata::Message;
-use crossbeam_channel::{never, select, unbounded, Receiver, RecvError, Sender};
+use crossbeam_channel::{after, never, select, unbounded, Receiver, RecvError, Sender};
 use lsp_types::{
     CodeAction, CodeActionOrCommand, Diagnostic, Url, WorkDoneProgress, WorkDoneProgressBegin,
     WorkDoneProgressEnd, WorkDoneProgressReport,
@@ -12,7 +12,7 @@ use std::{
     path::PathBuf,
     process::{Command, Stdio},
     thread::JoinHandle,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 mod conv;
@@ -21,7 +21,11 @@ use crate::conv::{map_rust_diagnostic_to_lsp, MappedRustDiagnostic};
 
 pub use crate::conv::url_from_path_with_drive_lowercasing;
 
-#[derive(Clone, Debug)]
+/// How long to wait after the last update request before restarting the
+/// check, so that a burst of saves only runs it once.
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CheckOptions {
     pub enable: bool,
     pub args: Vec<String>,
@@ -118,6 +122,7 @@ impl CheckWatcherThread {
 
     fn run(&mut self, task_send: &Sender<CheckTask>, cmd_recv: &Receiver<CheckCommand>) {
         loop {
+            let debounce_timer = self.debounce_timer();
             select! {
                 recv(&cmd_recv) -> cmd => match cmd {
                     Ok(cmd) => self.handle_command(cmd),
@@ -133,7 +138,8 @@ impl CheckWatcherThread {
                         // avoid busy-waiting.
                         std::mem::replace(&mut self.watcher.message_recv, never());
                     },
-                }
+                },
+                recv(debounce_timer) -> _ => {},
             };
 
             if self.should_recheck() {
@@ -150,15 +156,25 @@ impl CheckWatcherThread {
     }
 
     fn should_recheck(&mut self) -> bool {
-        if let Some(_last_update_req) = &self.last_update_req {
-            // We currently only request an update on save, as we need up to
-            // date source on disk for cargo check to do it's magic, so we
-            // don't really need to debounce the requests at this point.
-            return true;
+        if let Some(last_update_req) = &self.last_update_req {
+            // Restarting has to wait for the running check to shut down, so
+            // wait for a burst of saves to settle instead of piling them up.
+            return last_update_req.elapsed() >= DEBOUNCE_DURATION;
         }
         false
     }
 
+    /// Fires when a pending update request is due, so that the loop wakes up
+    /// even if no other messages arrive.
+    fn debounce_timer(&self) -> Receiver<Instant> {
+        match &self.last_update_req {
+            Some(last_update_req) => {
+                after(DEBOUNCE_DURATION.checked_sub(last_update_req.elapsed()).unwrap_or_default())
+            }
+            None => never(),
+        }
+    }
+
     fn handle_command(&mut self, cmd: CheckCommand) {
         match cmd {
             CheckCommand::Update => self.last_update_req = Some(Instant::now()),
@@ -360,3 +376,26 @@ impl std::ops::Drop for WatchThread {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_requests_are_debounced() {
+        let options = CheckOptions {
+            enable: false,
+            args: Vec::new(),
+            command: "check".to_string(),
+            all_targets: false,
+        };
+        let mut check = CheckWatcherThread::new(options, PathBuf::new());
+        assert!(!check.should_recheck());
+
+        check.handle_command(CheckCommand::Update);
+        assert!(!check.should_recheck());
+
+        check.last_update_req = Some(Instant::now() - DEBOUNCE_DURATION);
+        assert!(check.should_recheck());
+    }
+}
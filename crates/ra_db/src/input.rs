@@ -177,6 +177,10 @@ impl CrateGraph {
         self.arena[&crate_id].edition
     }
 
+    pub fn env(&self, crate_id: CrateId) -> &Env {
+        &self.arena[&crate_id].env
+    }
+
     // FIXME: this only finds one crate with the given root; we could have multiple
     pub fn crate_id_for_crate_root(&self, file_id: FileId) -> Option<CrateId> {
         let (&crate_id, _) = self.arena.iter().find(|(_crate_id, data)| data.file_id == file_id)?;
@@ -239,6 +243,16 @@ impl CrateData {
     }
 }
 
+impl Env {
+    pub fn set(&mut self, env: &str, value: String) {
+        self.entries.insert(env.to_owned(), value);
+    }
+
+    pub fn get(&self, env: &str) -> Option<&str> {
+        self.entries.get(env).map(|it| it.as_str())
+    }
+}
+
 impl FromStr for Edition {
     type Err = ParseEditionError;
 
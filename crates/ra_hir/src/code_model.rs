@@ -13,7 +13,7 @@ use hir_def::{
     type_ref::{Mutability, TypeRef},
     AdtId, AssocContainerId, ConstId, DefWithBodyId, EnumId, FunctionId, GenericDefId, HasModule,
     ImplId, LocalEnumVariantId, LocalModuleId, LocalStructFieldId, Lookup, ModuleId, StaticId,
-    StructId, TraitId, TypeAliasId, TypeParamId, UnionId,
+    StructId, TraitId, TypeAliasId, TypeParamId, UnionId, VariantId,
 };
 use hir_expand::{
     diagnostics::DiagnosticSink,
@@ -21,8 +21,11 @@ use hir_expand::{
     MacroDefId,
 };
 use hir_ty::{
-    autoderef, display::HirFormatter, expr::ExprValidator, method_resolution, ApplicationTy,
-    Canonical, InEnvironment, Substs, TraitEnvironment, Ty, TyDefId, TypeCtor,
+    autoderef,
+    display::HirFormatter,
+    expr::ExprValidator,
+    method_resolution::{self, RejectionReason},
+    ApplicationTy, Canonical, InEnvironment, Substs, TraitEnvironment, Ty, TyDefId, TypeCtor,
 };
 use ra_db::{CrateId, Edition, FileId};
 use ra_prof::profile;
@@ -64,7 +67,6 @@ impl Crate {
             .collect()
     }
 
-    // FIXME: add `transitive_reverse_dependencies`.
     pub fn reverse_dependencies(self, db: &impl DefDatabase) -> Vec<Crate> {
         let crate_graph = db.crate_graph();
         crate_graph
@@ -74,6 +76,21 @@ impl Crate {
             .collect()
     }
 
+    /// Returns this crate and all crates which depend on it, directly or not.
+    pub fn transitive_reverse_dependencies(self, db: &impl DefDatabase) -> Vec<Crate> {
+        let mut res = vec![self];
+        let mut idx = 0;
+        while idx < res.len() {
+            for krate in res[idx].reverse_dependencies(db) {
+                if !res.contains(&krate) {
+                    res.push(krate);
+                }
+            }
+            idx += 1;
+        }
+        res
+    }
+
     pub fn root_module(self, db: &impl DefDatabase) -> Option<Module> {
         let module_id = db.crate_def_map(self.id).root;
         Some(Module::new(self, module_id))
@@ -91,6 +108,29 @@ impl Crate {
     pub fn all(db: &impl DefDatabase) -> Vec<Crate> {
         db.crate_graph().iter().map(|id| Crate { id }).collect()
     }
+
+    /// Searches the public items of this crate whose name fuzzy-matches
+    /// `query`, together with the shortest path to them from the crate root.
+    /// Macros are not included.
+    pub fn search_importable_items(
+        self,
+        db: &impl DefDatabase,
+        query: &str,
+    ) -> Vec<(ModuleDef, hir_def::path::ModPath)> {
+        let import_map = db.import_map(self.id);
+        let mut seen = FxHashSet::default();
+        import_map
+            .fuzzy_search(query)
+            .filter_map(|(item, path)| {
+                let def: ModuleDef = item.as_module_def_id()?.into();
+                if seen.insert(def) {
+                    Some((def, path.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -204,11 +244,31 @@ impl Module {
     }
 
     /// Returns a `ModuleScope`: a set of items, visible in this module.
-    pub fn scope(self, db: &impl HirDatabase) -> Vec<(Name, ScopeDef)> {
+    ///
+    /// If `visible_from` is given, items which are not visible from that
+    /// module are left out.
+    pub fn scope(
+        self,
+        db: &impl HirDatabase,
+        visible_from: Option<Module>,
+    ) -> Vec<(Name, ScopeDef)> {
         db.crate_def_map(self.id.krate)[self.id.local_id]
             .scope
             .entries()
-            .map(|(name, def)| (name.clone(), def.into()))
+            .filter_map(|(name, def)| {
+                let def = match visible_from {
+                    Some(module) => {
+                        let filtered =
+                            def.filter_visibility(|vis| vis.is_visible_from(db, module.id));
+                        if filtered.is_none() && !def.is_none() {
+                            return None;
+                        }
+                        filtered
+                    }
+                    None => def,
+                };
+                Some((name.clone(), def.into()))
+            })
             .collect()
     }
 
@@ -216,6 +276,7 @@ impl Module {
         let _p = profile("Module::diagnostics");
         let crate_def_map = db.crate_def_map(self.id.krate);
         crate_def_map.add_diagnostics(db, self.id.local_id, sink);
+        method_resolution::add_duplicate_item_diagnostics(db, self.id, sink);
         for decl in self.declarations(db) {
             match decl {
                 crate::ModuleDef::Function(f) => f.diagnostics(db, sink),
@@ -347,6 +408,10 @@ impl Struct {
         Type::from_def(db, self.id.lookup(db).container.module(db).krate, self.id)
     }
 
+    pub fn kind(self, db: &impl HirDatabase) -> StructKind {
+        self.variant_data(db).kind()
+    }
+
     fn variant_data(self, db: &impl DefDatabase) -> Arc<VariantData> {
         db.struct_data(self.id).variant_data.clone()
     }
@@ -410,6 +475,10 @@ impl Enum {
             .collect()
     }
 
+    pub fn has_explicit_discriminants(self, db: &impl DefDatabase) -> bool {
+        db.enum_data(self.id).has_explicit_discriminants()
+    }
+
     pub fn ty(self, db: &impl HirDatabase) -> Type {
         Type::from_def(db, self.id.lookup(db).container.module(db).krate, self.id)
     }
@@ -445,6 +514,12 @@ impl EnumVariant {
         self.variant_data(db).kind()
     }
 
+    /// The value of the discriminant of this variant, if it can be evaluated
+    /// and fits into the `repr` type of the enum.
+    pub fn discriminant(self, db: &impl DefDatabase) -> Option<i128> {
+        db.enum_data(self.parent.id).discriminant(self.id)
+    }
+
     pub(crate) fn variant_data(self, db: &impl DefDatabase) -> Arc<VariantData> {
         db.enum_data(self.parent.id).variants[self.id].variant_data.clone()
     }
@@ -534,6 +609,12 @@ impl DefWithBody {
             DefWithBody::Static(s) => s.module(db),
         }
     }
+
+    /// Renders the lowered body together with the inferred types, for
+    /// debugging.
+    pub fn debug_hir(self, db: &impl HirDatabase) -> String {
+        hir_ty::dump_body(db, self.into())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -562,8 +643,13 @@ impl Function {
         db.function_data(self.id).params.clone()
     }
 
+    pub fn is_unsafe(self, db: &impl HirDatabase) -> bool {
+        db.function_data(self.id).is_unsafe
+    }
+
     pub fn diagnostics(self, db: &impl HirDatabase, sink: &mut DiagnosticSink) {
         let _p = profile("Function::diagnostics");
+        db.body_with_source_map(self.id.into()).1.add_diagnostics(db, sink);
         let infer = db.infer(self.id.into());
         infer.add_diagnostics(db, self.id, sink);
         let mut validator = ExprValidator::new(self.id, infer, sink);
@@ -588,6 +674,12 @@ impl Const {
     pub fn name(self, db: &impl HirDatabase) -> Option<Name> {
         db.const_data(self.id).name.clone()
     }
+
+    pub fn ty(self, db: &impl HirDatabase) -> Type {
+        let substs = Substs::type_params(db, self.id);
+        let ty = db.value_ty(self.id.into()).subst(&substs);
+        Type::new(db, self.module(db).id.krate, self.id, ty)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -603,6 +695,10 @@ impl Static {
     pub fn krate(self, db: &impl DefDatabase) -> Option<Crate> {
         Some(self.module(db).krate())
     }
+
+    pub fn name(self, db: &impl HirDatabase) -> Option<Name> {
+        db.static_data(self.id).name.clone()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -623,6 +719,24 @@ impl Trait {
         db.trait_data(self.id).items.iter().map(|(_name, it)| (*it).into()).collect()
     }
 
+    /// The items of the trait and of all its super traits, where the items of
+    /// a sub trait shadow the items of the same name in its super traits.
+    pub fn items_with_supertraits(self, db: &impl DefDatabase) -> Vec<AssocItem> {
+        let mut shadowed = FxHashSet::default();
+        let mut res = Vec::new();
+        for trait_ in hir_ty::all_super_traits(db, self.id) {
+            let data = db.trait_data(trait_);
+            res.extend(
+                data.items
+                    .iter()
+                    .filter(|(name, _)| !shadowed.contains(name))
+                    .map(|(_name, it)| AssocItem::from(*it)),
+            );
+            shadowed.extend(data.items.iter().map(|(name, _)| name.clone()));
+        }
+        res
+    }
+
     pub fn is_auto(self, db: &impl DefDatabase) -> bool {
         db.trait_data(self.id).auto
     }
@@ -848,6 +962,14 @@ impl TypeParam {
     pub fn module(self, db: &impl HirDatabase) -> Module {
         self.id.parent.module(db).into()
     }
+
+    pub fn ty(self, db: &impl HirDatabase) -> Type {
+        let resolver = self.id.parent.resolver(db);
+        let krate = self.id.parent.module(db).krate;
+        let environment = TraitEnvironment::lower(db, &resolver);
+        let ty = Ty::Placeholder(self.id);
+        Type { krate, ty: InEnvironment { value: ty, environment } }
+    }
 }
 
 // FIXME: rename from `ImplBlock` to `Impl`
@@ -874,6 +996,10 @@ impl ImplBlock {
         db.impl_data(self.id).target_type.clone()
     }
 
+    pub fn target_trait_def(&self, db: &impl HirDatabase) -> Option<Trait> {
+        db.impl_trait(self.id).map(|it| it.value.trait_.into())
+    }
+
     pub fn target_ty(&self, db: &impl HirDatabase) -> Type {
         let impl_data = db.impl_data(self.id);
         let resolver = self.id.resolver(db);
@@ -957,6 +1083,16 @@ impl Type {
         }
     }
 
+    pub fn is_raw_ptr(&self) -> bool {
+        match &self.ty.value {
+            Ty::Apply(a_ty) => match a_ty.ctor {
+                TypeCtor::RawPtr(_) => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     pub fn is_mutable_reference(&self) -> bool {
         match &self.ty.value {
             Ty::Apply(a_ty) => match a_ty.ctor {
@@ -974,6 +1110,17 @@ impl Type {
         }
     }
 
+    /// Whether this is a union, whose fields can only be read in unsafe code.
+    pub fn is_union(&self) -> bool {
+        match &self.ty.value {
+            Ty::Apply(a_ty) => match a_ty.ctor {
+                TypeCtor::Adt(AdtId::UnionId(_)) => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
     /// Checks that particular type `ty` implements `std::future::Future`.
     /// This function is used in `.await` syntax completion.
     pub fn impls_future(&self, db: &impl HirDatabase) -> bool {
@@ -1015,8 +1162,12 @@ impl Type {
 
     pub fn fields(&self, db: &impl HirDatabase) -> Vec<(StructField, Type)> {
         if let Ty::Apply(a_ty) = &self.ty.value {
-            if let TypeCtor::Adt(AdtId::StructId(s)) = a_ty.ctor {
-                let var_def = s.into();
+            let var_def: Option<VariantId> = match a_ty.ctor {
+                TypeCtor::Adt(AdtId::StructId(s)) => Some(s.into()),
+                TypeCtor::Adt(AdtId::UnionId(u)) => Some(u.into()),
+                _ => None,
+            };
+            if let Some(var_def) = var_def {
                 return db
                     .field_types(var_def)
                     .iter()
@@ -1072,7 +1223,7 @@ impl Type {
         let environment = self.ty.environment.clone();
         let ty = InEnvironment { value: canonical, environment };
         autoderef(db, Some(self.krate), ty)
-            .map(|canonical| canonical.value)
+            .map(|canonical| canonical.into_value_with_unknowns())
             .map(move |ty| self.derived(ty))
     }
 
@@ -1129,6 +1280,64 @@ impl Type {
         )
     }
 
+    /// Like `iterate_method_candidates`, but additionally reports the methods
+    /// which can't be called from `module`, with the reason why.
+    pub fn iterate_method_candidates_with_reasons<T>(
+        &self,
+        db: &impl HirDatabase,
+        module: Module,
+        traits_in_scope: &FxHashSet<TraitId>,
+        name: Option<&Name>,
+        mut callback: impl FnMut(&Ty, Function, Option<RejectionReason>) -> Option<T>,
+    ) -> Option<T> {
+        // There should be no inference vars in types passed here
+        // FIXME check that?
+        // FIXME replace Unknown by bound vars here
+        let canonical = Canonical { value: self.ty.value.clone(), num_vars: 0 };
+
+        let env = self.ty.environment.clone();
+
+        method_resolution::iterate_method_candidates_with_reasons(
+            &canonical,
+            db,
+            env,
+            module.id.krate,
+            traits_in_scope,
+            module.id,
+            name,
+            method_resolution::LookupMode::MethodCall,
+            |ty, it, reason| match it {
+                AssocItemId::FunctionId(f) => callback(ty, f.into(), reason),
+                _ => None,
+            },
+        )
+    }
+
+    /// Like `iterate_method_candidates_with_reasons`, but additionally reports
+    /// the trait each method comes from, or `None` for inherent methods.
+    pub fn iterate_method_candidates_with_trait<T>(
+        &self,
+        db: &impl HirDatabase,
+        module: Module,
+        traits_in_scope: &FxHashSet<TraitId>,
+        name: Option<&Name>,
+        mut callback: impl FnMut(&Ty, Function, Option<Trait>, Option<RejectionReason>) -> Option<T>,
+    ) -> Option<T> {
+        self.iterate_method_candidates_with_reasons(
+            db,
+            module,
+            traits_in_scope,
+            name,
+            |ty, func, reason| {
+                let trait_ = match func.as_assoc_item(db).map(|it| it.container(db)) {
+                    Some(AssocItemContainer::Trait(it)) => Some(it),
+                    _ => None,
+                };
+                callback(ty, func, trait_, reason)
+            },
+        )
+    }
+
     pub fn iterate_path_candidates<T>(
         &self,
         db: &impl HirDatabase,
@@ -1241,6 +1450,23 @@ impl_froms!(
     MacroDef
 );
 
+impl AttrDef {
+    pub fn module(self, db: &impl HirDatabase) -> Option<Module> {
+        match self {
+            AttrDef::Module(it) => Some(it),
+            AttrDef::StructField(it) => Some(it.parent_def(db).module(db)),
+            AttrDef::Adt(it) => Some(it.module(db)),
+            AttrDef::Function(it) => Some(it.module(db)),
+            AttrDef::EnumVariant(it) => Some(it.module(db)),
+            AttrDef::Static(it) => Some(it.module(db)),
+            AttrDef::Const(it) => Some(it.module(db)),
+            AttrDef::Trait(it) => Some(it.module(db)),
+            AttrDef::TypeAlias(it) => Some(it.module(db)),
+            AttrDef::MacroDef(it) => it.module(db),
+        }
+    }
+}
+
 pub trait HasAttrs {
     fn attrs(self, db: &impl DefDatabase) -> Attrs;
 }
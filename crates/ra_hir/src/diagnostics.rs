@@ -1,4 +1,9 @@
 //! FIXME: write short doc here
-pub use hir_def::diagnostics::UnresolvedModule;
+pub use hir_def::diagnostics::{MacroError, UnresolvedModule};
 pub use hir_expand::diagnostics::{AstDiagnostic, Diagnostic, DiagnosticSink};
-pub use hir_ty::diagnostics::{MissingFields, MissingOkInTailExpr, NoSuchField};
+pub use hir_ty::diagnostics::{
+    DeprecatedItem, DuplicateInherentItem, InvalidBinaryOp, InvalidCast, MethodTraitNotInScope,
+    MissingEnvVar, MissingFields, MissingMatchArms, MissingOkInTailExpr, NoSuchField,
+    UnionLiteralFieldCount, UnionPatternFieldCount, UnresolvedMethodCall,
+    UnresolvedMethodSuggestion, UnusedMut, UnusedParam, UnusedVariable,
+};
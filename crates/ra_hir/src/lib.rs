@@ -46,7 +46,9 @@ pub use crate::{
         StructField, Trait, Type, TypeAlias, TypeParam, Union, VariantDef,
     },
     has_source::HasSource,
-    semantics::{original_range, Semantics, SemanticsScope},
+    semantics::{
+        original_macro_call_range, original_range, original_token_range, Semantics, SemanticsScope,
+    },
     source_analyzer::PathResolution,
 };
 
@@ -62,4 +64,8 @@ pub use hir_def::{
 pub use hir_expand::{
     name::Name, HirFileId, InFile, MacroCallId, MacroCallLoc, MacroDefId, MacroFile, Origin,
 };
-pub use hir_ty::{display::HirDisplay, CallableDef};
+pub use hir_ty::{
+    display::{ClosureStyle, HirDisplay},
+    method_resolution::RejectionReason,
+    CallableDef,
+};
@@ -107,14 +107,27 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.analyze(field.syntax()).resolve_field(field)
     }
 
-    pub fn resolve_record_field(&self, field: &ast::RecordField) -> Option<StructField> {
-        self.analyze(field.syntax()).resolve_record_field(field)
+    /// Resolves the field of a record literal. For a shorthand field, the
+    /// local variable it is initialized with is returned as well.
+    pub fn resolve_record_field(
+        &self,
+        field: &ast::RecordField,
+    ) -> Option<(StructField, Option<Local>)> {
+        self.analyze(field.syntax()).resolve_record_field(self.db, field)
     }
 
     pub fn resolve_record_literal(&self, record_lit: &ast::RecordLit) -> Option<VariantDef> {
         self.analyze(record_lit.syntax()).resolve_record_literal(record_lit)
     }
 
+    /// Returns the fields of the record literal's variant which are not yet
+    /// written in it. A literal with a `..spread` has no missing fields.
+    pub fn record_literal_missing_fields(&self, record_lit: &ast::RecordLit) -> Vec<StructField> {
+        self.analyze(record_lit.syntax())
+            .record_literal_missing_fields(self.db, record_lit)
+            .unwrap_or_default()
+    }
+
     pub fn resolve_record_pattern(&self, record_pat: &ast::RecordPat) -> Option<VariantDef> {
         self.analyze(record_pat.syntax()).resolve_record_pattern(record_pat)
     }
@@ -370,6 +383,31 @@ pub fn original_range(db: &impl HirDatabase, node: InFile<&SyntaxNode>) -> FileR
     FileRange { file_id: node.file_id.original_file(db), range: node.value.text_range() }
 }
 
+/// For a node inside a macro expansion, returns the range of the outermost
+/// macro call the expansion originates from.
+pub fn original_macro_call_range(db: &impl HirDatabase, file_id: HirFileId) -> Option<FileRange> {
+    let call = successors(file_id.call_node(db), |it| it.file_id.call_node(db)).last()?;
+    Some(FileRange { file_id: call.file_id.original_file(db), range: call.value.text_range() })
+}
+
+/// Maps a token through macro expansions back to the original file. Unlike
+/// `original_range`, this only succeeds if the token was written in the macro
+/// call, and not in the macro definition.
+pub fn original_token_range(
+    db: &impl HirDatabase,
+    token: InFile<SyntaxToken>,
+) -> Option<FileRange> {
+    let mut token = token;
+    while let Some(expansion) = token.file_id.expansion_info(db) {
+        let (up, origin) = expansion.map_token_up(token.as_ref())?;
+        if origin != Origin::Call {
+            return None;
+        }
+        token = up;
+    }
+    Some(FileRange { file_id: token.file_id.original_file(db), range: token.value.text_range() })
+}
+
 fn original_range_and_origin(
     db: &impl HirDatabase,
     elem: InFile<&SyntaxElement>,
@@ -5,7 +5,7 @@
 //!
 //! So, this modules should not be used during hir construction, it exists
 //! purely for "IDE needs".
-use std::sync::Arc;
+use std::{iter::once, sync::Arc};
 
 use either::Either;
 use hir_def::{
@@ -14,11 +14,12 @@ use hir_def::{
         BodySourceMap,
     },
     expr::{ExprId, PatId},
+    path::{ModPath, PathKind},
     resolver::{resolver_for_scope, Resolver, TypeNs, ValueNs},
-    AsMacroCall, DefWithBodyId,
+    AsMacroCall, DefWithBodyId, VariantId,
 };
 use hir_expand::{hygiene::Hygiene, name::AsName, HirFileId, InFile};
-use hir_ty::{InEnvironment, InferenceResult, TraitEnvironment};
+use hir_ty::{expr::missing_fields, InEnvironment, InferenceResult, TraitEnvironment};
 use ra_syntax::{
     ast::{self, AstNode},
     AstPtr, SyntaxNode, SyntaxNodePtr, TextRange, TextUnit,
@@ -159,16 +160,27 @@ impl SourceAnalyzer {
 
     pub(crate) fn resolve_record_field(
         &self,
+        db: &impl HirDatabase,
         field: &ast::RecordField,
-    ) -> Option<crate::StructField> {
-        let expr_id = match field.expr() {
-            Some(it) => self.expr_id(&it)?,
+    ) -> Option<(crate::StructField, Option<Local>)> {
+        let (expr_id, local) = match field.expr() {
+            Some(it) => (self.expr_id(&it)?, None),
             None => {
                 let src = InFile { file_id: self.file_id, value: field };
-                self.body_source_map.as_ref()?.field_init_shorthand_expr(src)?
+                let expr_id = self.body_source_map.as_ref()?.field_init_shorthand_expr(src)?;
+                let local_name = field.name_ref()?.as_name();
+                let path = ModPath::from_segments(PathKind::Plain, once(local_name));
+                let local = match self.resolver.resolve_path_in_value_ns_fully(db, &path) {
+                    Some(ValueNs::LocalBinding(pat_id)) => {
+                        Some(Local { pat_id, parent: self.resolver.body_owner()?.into() })
+                    }
+                    _ => None,
+                };
+                (expr_id, local)
             }
         };
-        self.infer.as_ref()?.record_field_resolution(expr_id).map(|it| it.into())
+        let field = self.infer.as_ref()?.record_field_resolution(expr_id)?;
+        Some((field.into(), local))
     }
 
     pub(crate) fn resolve_record_literal(
@@ -179,6 +191,29 @@ impl SourceAnalyzer {
         self.infer.as_ref()?.variant_resolution_for_expr(expr_id).map(|it| it.into())
     }
 
+    pub(crate) fn record_literal_missing_fields(
+        &self,
+        db: &impl HirDatabase,
+        record_lit: &ast::RecordLit,
+    ) -> Option<Vec<crate::StructField>> {
+        let expr_id = self.expr_id(&record_lit.clone().into())?;
+        let variant = self.infer.as_ref()?.variant_resolution_for_expr(expr_id)?;
+        let field_list = record_lit.record_field_list()?;
+        if let VariantId::UnionId(_) = variant {
+            return None;
+        }
+        if field_list.spread().is_some() {
+            return Some(Vec::new());
+        }
+        let present: Vec<_> =
+            field_list.fields().filter_map(|it| it.name_ref()).map(|it| it.as_name()).collect();
+        let fields = missing_fields(db, variant, &present)
+            .into_iter()
+            .map(|id| crate::StructField { parent: variant.into(), id })
+            .collect();
+        Some(fields)
+    }
+
     pub(crate) fn resolve_record_pattern(
         &self,
         record_pat: &ast::RecordPat,
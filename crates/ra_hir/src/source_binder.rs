@@ -208,8 +208,9 @@ impl ToId for ast::MacroCall {
         let krate = sb.to_module_def(db, src.file_id.original_file(db))?.id.krate;
 
         let ast_id = Some(AstId::new(src.file_id, db.ast_id_map(src.file_id).ast_id(&src.value)));
+        let local_inner = src.value.has_local_inner_macros();
 
-        Some(MacroDefId { krate: Some(krate), ast_id, kind })
+        Some(MacroDefId { krate: Some(krate), ast_id, kind, local_inner })
     }
 }
 
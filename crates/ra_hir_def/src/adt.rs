@@ -1,6 +1,6 @@
 //! Defines hir-level representation of structs, enums and unions
 
-use std::sync::Arc;
+use std::{convert::TryFrom, sync::Arc};
 
 use either::Either;
 use hir_expand::{
@@ -9,12 +9,21 @@ use hir_expand::{
 };
 use ra_arena::{map::ArenaMap, Arena};
 use ra_prof::profile;
-use ra_syntax::ast::{self, NameOwner, TypeAscriptionOwner, VisibilityOwner};
+use ra_syntax::{
+    ast::{self, NameOwner, TypeAscriptionOwner, VisibilityOwner},
+    AstToken,
+};
 
 use crate::{
-    db::DefDatabase, src::HasChildSource, src::HasSource, trace::Trace, type_ref::TypeRef,
-    visibility::RawVisibility, EnumId, LocalEnumVariantId, LocalStructFieldId, Lookup, StructId,
-    UnionId, VariantId,
+    builtin_type::{BuiltinInt, IntBitness, Signedness},
+    db::DefDatabase,
+    expr::ArithOp,
+    src::HasChildSource,
+    src::HasSource,
+    trace::Trace,
+    type_ref::TypeRef,
+    visibility::RawVisibility,
+    EnumId, LocalEnumVariantId, LocalStructFieldId, Lookup, StructId, UnionId, VariantId,
 };
 
 /// Note that we use `StructData` for unions as well!
@@ -28,12 +37,31 @@ pub struct StructData {
 pub struct EnumData {
     pub name: Name,
     pub variants: Arena<LocalEnumVariantId, EnumVariantData>,
+    /// The integer type given by `#[repr(..)]`, if any.
+    pub repr: Option<BuiltinInt>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EnumVariantData {
     pub name: Name,
     pub variant_data: Arc<VariantData>,
+    /// The explicit discriminant, like `2 << 3` in `B = 2 << 3`.
+    pub discriminant: Option<DiscriminantExpr>,
+}
+
+/// A lowered explicit enum discriminant. We only support the integer
+/// arithmetic commonly used for bitflag-style enums.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscriminantExpr {
+    Literal(u128),
+    Neg(Box<DiscriminantExpr>),
+    BinaryOp {
+        op: ArithOp,
+        lhs: Box<DiscriminantExpr>,
+        rhs: Box<DiscriminantExpr>,
+    },
+    /// Anything else, e.g. paths to constants.
+    Unsupported,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -82,13 +110,127 @@ impl EnumData {
         let name = src.value.name().map_or_else(Name::missing, |n| n.as_name());
         let mut trace = Trace::new_for_arena();
         lower_enum(db, &mut trace, &src);
-        Arc::new(EnumData { name, variants: trace.into_arena() })
+        let repr = db.attrs(e.into()).repr_int();
+        Arc::new(EnumData { name, variants: trace.into_arena(), repr })
     }
 
     pub fn variant(&self, name: &Name) -> Option<LocalEnumVariantId> {
         let (id, _) = self.variants.iter().find(|(_id, data)| &data.name == name)?;
         Some(id)
     }
+
+    pub fn has_explicit_discriminants(&self) -> bool {
+        self.variants.iter().any(|(_, data)| data.discriminant.is_some())
+    }
+
+    /// The value of the discriminant of `variant`, which is one more than the
+    /// one of the previous variant if it's not given explicitly. Returns `None`
+    /// if the value can't be evaluated or doesn't fit into the `repr` type
+    /// (`isize` by default).
+    pub fn discriminant(&self, variant: LocalEnumVariantId) -> Option<i128> {
+        let (min, max) = int_range(self.repr.unwrap_or(BuiltinInt::ISIZE));
+        let mut value = None;
+        for (idx, (id, data)) in self.variants.iter().enumerate() {
+            value = match &data.discriminant {
+                Some(expr) => expr.eval(),
+                None if idx == 0 => Some(0),
+                None => value.and_then(|it: i128| it.checked_add(1)),
+            }
+            .filter(|it| min <= *it && *it <= max);
+            if id == variant {
+                return value;
+            }
+        }
+        None
+    }
+}
+
+impl DiscriminantExpr {
+    fn lower(expr: ast::Expr) -> DiscriminantExpr {
+        match expr {
+            ast::Expr::Literal(lit) => {
+                match ast::IntNumber::cast(lit.token()).and_then(|it| it.value()) {
+                    Some(value) => DiscriminantExpr::Literal(value),
+                    None => DiscriminantExpr::Unsupported,
+                }
+            }
+            ast::Expr::ParenExpr(e) => DiscriminantExpr::lower_opt(e.expr()),
+            ast::Expr::PrefixExpr(e) => match e.op_kind() {
+                Some(ast::PrefixOp::Neg) => {
+                    DiscriminantExpr::Neg(Box::new(DiscriminantExpr::lower_opt(e.expr())))
+                }
+                _ => DiscriminantExpr::Unsupported,
+            },
+            ast::Expr::BinExpr(e) => {
+                let op = match e.op_kind() {
+                    Some(ast::BinOp::Addition) => ArithOp::Add,
+                    Some(ast::BinOp::Subtraction) => ArithOp::Sub,
+                    Some(ast::BinOp::Multiplication) => ArithOp::Mul,
+                    Some(ast::BinOp::Division) => ArithOp::Div,
+                    Some(ast::BinOp::Remainder) => ArithOp::Rem,
+                    Some(ast::BinOp::LeftShift) => ArithOp::Shl,
+                    Some(ast::BinOp::RightShift) => ArithOp::Shr,
+                    Some(ast::BinOp::BitwiseXor) => ArithOp::BitXor,
+                    Some(ast::BinOp::BitwiseOr) => ArithOp::BitOr,
+                    Some(ast::BinOp::BitwiseAnd) => ArithOp::BitAnd,
+                    _ => return DiscriminantExpr::Unsupported,
+                };
+                DiscriminantExpr::BinaryOp {
+                    op,
+                    lhs: Box::new(DiscriminantExpr::lower_opt(e.lhs())),
+                    rhs: Box::new(DiscriminantExpr::lower_opt(e.rhs())),
+                }
+            }
+            _ => DiscriminantExpr::Unsupported,
+        }
+    }
+
+    fn lower_opt(expr: Option<ast::Expr>) -> DiscriminantExpr {
+        expr.map_or(DiscriminantExpr::Unsupported, DiscriminantExpr::lower)
+    }
+
+    /// Evaluates the expression, returning `None` if it's unsupported or
+    /// overflows.
+    pub fn eval(&self) -> Option<i128> {
+        match self {
+            DiscriminantExpr::Literal(value) => i128::try_from(*value).ok(),
+            DiscriminantExpr::Neg(expr) => expr.eval()?.checked_neg(),
+            DiscriminantExpr::BinaryOp { op, lhs, rhs } => {
+                let (lhs, rhs) = (lhs.eval()?, rhs.eval()?);
+                match op {
+                    ArithOp::Add => lhs.checked_add(rhs),
+                    ArithOp::Sub => lhs.checked_sub(rhs),
+                    ArithOp::Mul => lhs.checked_mul(rhs),
+                    ArithOp::Div => lhs.checked_div(rhs),
+                    ArithOp::Rem => lhs.checked_rem(rhs),
+                    ArithOp::Shl => lhs.checked_shl(u32::try_from(rhs).ok()?),
+                    ArithOp::Shr => lhs.checked_shr(u32::try_from(rhs).ok()?),
+                    ArithOp::BitXor => Some(lhs ^ rhs),
+                    ArithOp::BitOr => Some(lhs | rhs),
+                    ArithOp::BitAnd => Some(lhs & rhs),
+                }
+            }
+            DiscriminantExpr::Unsupported => None,
+        }
+    }
+}
+
+/// The smallest and largest values of `ty` which fit into an `i128`.
+fn int_range(ty: BuiltinInt) -> (i128, i128) {
+    let bits = match ty.bitness {
+        IntBitness::X8 => 8,
+        IntBitness::X16 => 16,
+        IntBitness::X32 => 32,
+        // FIXME: this depends on the target
+        IntBitness::Xsize | IntBitness::X64 => 64,
+        IntBitness::X128 => 128,
+    };
+    match (ty.signedness, bits) {
+        (Signedness::Signed, 128) => (std::i128::MIN, std::i128::MAX),
+        (Signedness::Unsigned, 128) => (0, std::i128::MAX),
+        (Signedness::Signed, bits) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+        (Signedness::Unsigned, bits) => (0, (1 << bits) - 1),
+    }
 }
 
 impl HasChildSource for EnumId {
@@ -113,6 +255,7 @@ fn lower_enum(
             || EnumVariantData {
                 name: var.name().map_or_else(Name::missing, |it| it.as_name()),
                 variant_data: Arc::new(VariantData::new(db, ast.with_value(var.kind()))),
+                discriminant: var.expr().map(DiscriminantExpr::lower),
             },
         );
     }
@@ -3,8 +3,12 @@
 use std::{ops, sync::Arc};
 
 use either::Either;
-use hir_expand::{hygiene::Hygiene, AstId, InFile};
+use hir_expand::{
+    builtin_derive::find_builtin_derive, hygiene::Hygiene, name::AsName, AstId, InFile,
+};
 use mbe::ast_to_token_tree;
+use ra_cfg::CfgOptions;
+use ra_db::CrateId;
 use ra_syntax::{
     ast::{self, AstNode, AttrsOwner},
     SmolStr,
@@ -12,7 +16,12 @@ use ra_syntax::{
 use tt::Subtree;
 
 use crate::{
-    db::DefDatabase, path::ModPath, src::HasChildSource, src::HasSource, AdtId, AttrDefId, Lookup,
+    builtin_type::BuiltinInt,
+    db::DefDatabase,
+    path::{ModPath, PathKind},
+    src::HasChildSource,
+    src::HasSource,
+    AdtId, AttrDefId, HasModule, Lookup, VariantId,
 };
 
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
@@ -33,6 +42,14 @@ impl ops::Deref for Attrs {
 
 impl Attrs {
     pub(crate) fn attrs_query(db: &impl DefDatabase, def: AttrDefId) -> Attrs {
+        let attrs = Attrs::raw_attrs(db, def);
+        match attr_def_krate(db, def) {
+            Some(krate) => attrs.expand_cfg_attr(db.crate_graph().cfg_options(krate)),
+            None => attrs,
+        }
+    }
+
+    fn raw_attrs(db: &impl DefDatabase, def: AttrDefId) -> Attrs {
         match def {
             AttrDefId::ModuleId(module) => {
                 let def_map = db.crate_def_map(module.krate);
@@ -87,11 +104,116 @@ impl Attrs {
         Attrs { entries }
     }
 
+    pub(crate) fn merge(self, other: Attrs) -> Attrs {
+        match (self.entries, other.entries) {
+            (None, entries) | (entries, None) => Attrs { entries },
+            (Some(a), Some(b)) => {
+                Attrs { entries: Some(a.iter().chain(b.iter()).cloned().collect()) }
+            }
+        }
+    }
+
+    /// Replaces each `#[cfg_attr(pred, attr1, attr2)]` with `attr1` and `attr2`
+    /// if `pred` holds for the given options, and drops it otherwise.
+    pub(crate) fn expand_cfg_attr(&self, cfg_options: &CfgOptions) -> Attrs {
+        if !self.iter().any(|attr| attr.is_cfg_attr()) {
+            return self.clone();
+        }
+        let mut entries = Vec::new();
+        for attr in self.iter() {
+            attr.expand_cfg_attr(cfg_options, &mut entries);
+        }
+        let entries = if entries.is_empty() { None } else { Some(entries.into()) };
+        Attrs { entries }
+    }
+
     pub fn by_key(&self, key: &'static str) -> AttrQuery<'_> {
         AttrQuery { attrs: self, key }
     }
+
+    /// Whether the item is marked with `#[doc(hidden)]`.
+    pub fn is_doc_hidden(&self) -> bool {
+        self.by_key("doc").tt_values().any(|subtree| {
+            subtree.token_trees.iter().any(|tt| match tt {
+                tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => ident.text.as_str() == "hidden",
+                _ => false,
+            })
+        })
+    }
+
+    /// The integer type of `#[repr(u8)]` and the like.
+    pub fn repr_int(&self) -> Option<BuiltinInt> {
+        self.by_key("repr").tt_values().flat_map(|subtree| subtree.token_trees.iter()).find_map(
+            |tt| match tt {
+                tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => BuiltinInt::from_suffix(&ident.text),
+                _ => None,
+            },
+        )
+    }
+
+    /// Whether the item is marked with `#[deprecated]` in any of its forms.
+    pub fn is_deprecated(&self) -> bool {
+        self.by_key("deprecated").exists()
+    }
+
+    /// The note of `#[deprecated = "note"]` or `#[deprecated(note = "note")]`.
+    pub fn deprecation_note(&self) -> Option<SmolStr> {
+        self.by_key("deprecated")
+            .string_value()
+            .cloned()
+            .or_else(|| self.by_key("deprecated").key_value("note"))
+    }
+
+    /// Whether the item has derives or attributes which may be macros that we
+    /// don't expand, like proc-macro derives, which could add impls for it.
+    pub fn has_unexpanded_macros(&self) -> bool {
+        let has_unexpanded_derive =
+            self.by_key("derive").tt_values().flat_map(|subtree| subtree.token_trees.iter()).any(
+                |tt| match tt {
+                    tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => {
+                        find_builtin_derive(&ident.as_name()).is_none()
+                    }
+                    _ => false,
+                },
+            );
+        has_unexpanded_derive
+            || self.iter().any(|attr| match attr.path.segments.first() {
+                Some(name) => !BUILTIN_ATTRIBUTES.contains(&name.to_string().as_str()),
+                None => true,
+            })
+    }
 }
 
+/// Attributes, and tools of tool attributes, which are not macros.
+const BUILTIN_ATTRIBUTES: &[&str] = &[
+    "allow",
+    "automatically_derived",
+    "cfg",
+    "cfg_attr",
+    "clippy",
+    "cold",
+    "deny",
+    "deprecated",
+    "derive",
+    "doc",
+    "forbid",
+    "ignore",
+    "inline",
+    "lang",
+    "macro_export",
+    "macro_use",
+    "must_use",
+    "no_mangle",
+    "non_exhaustive",
+    "path",
+    "repr",
+    "rustc_builtin_macro",
+    "rustfmt",
+    "should_panic",
+    "test",
+    "warn",
+];
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Attr {
     pub(crate) path: ModPath,
@@ -121,6 +243,69 @@ impl Attr {
 
         Some(Attr { path, input })
     }
+
+    /// Parses an attribute from the tokens of a `cfg_attr`, like the
+    /// `path = "foo.rs"` of `#[cfg_attr(test, path = "foo.rs")]`.
+    fn from_tts(tts: &[tt::TokenTree]) -> Option<Attr> {
+        let mut segments = Vec::new();
+        let mut rest = tts;
+        loop {
+            match rest.first()? {
+                tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => segments.push(ident.as_name()),
+                _ => return None,
+            }
+            rest = &rest[1..];
+            match (rest.get(0), rest.get(1)) {
+                (
+                    Some(tt::TokenTree::Leaf(tt::Leaf::Punct(first))),
+                    Some(tt::TokenTree::Leaf(tt::Leaf::Punct(second))),
+                ) if first.char == ':' && second.char == ':' => rest = &rest[2..],
+                _ => break,
+            }
+        }
+        let path = ModPath::from_segments(PathKind::Plain, segments);
+        let input = match rest {
+            [] => None,
+            [tt::TokenTree::Leaf(tt::Leaf::Punct(eq)), tt::TokenTree::Leaf(tt::Leaf::Literal(lit))]
+                if eq.char == '=' =>
+            {
+                Some(AttrInput::Literal(lit.text.trim_matches('"').into()))
+            }
+            [tt::TokenTree::Subtree(subtree)] => Some(AttrInput::TokenTree(subtree.clone())),
+            _ => return None,
+        };
+        Some(Attr { path, input })
+    }
+
+    fn is_cfg_attr(&self) -> bool {
+        self.path.as_ident().map_or(false, |name| name.to_string() == "cfg_attr")
+    }
+
+    fn expand_cfg_attr(&self, cfg_options: &CfgOptions, acc: &mut Vec<Attr>) {
+        let subtree = match &self.input {
+            Some(AttrInput::TokenTree(subtree)) if self.is_cfg_attr() => subtree,
+            _ => {
+                acc.push(self.clone());
+                return;
+            }
+        };
+        let is_comma = |tt: &tt::TokenTree| match tt {
+            tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) => punct.char == ',',
+            _ => false,
+        };
+        let mut parts = subtree.token_trees.split(is_comma);
+        let predicate = match parts.next() {
+            Some(it) => tt::Subtree { delimiter: None, token_trees: it.to_vec() },
+            None => return,
+        };
+        if cfg_options.is_cfg_enabled(&predicate) == Some(false) {
+            return;
+        }
+        // The expanded attributes may be `cfg_attr`s themselves.
+        for attr in parts.filter_map(Attr::from_tts) {
+            attr.expand_cfg_attr(cfg_options, acc);
+        }
+    }
 }
 
 pub struct AttrQuery<'a> {
@@ -143,6 +328,27 @@ impl<'a> AttrQuery<'a> {
         })
     }
 
+    /// Finds the value of a `key = "value"` argument of the attribute, like
+    /// the `note` of `#[deprecated(since = "1.0.0", note = "...")]`.
+    pub fn key_value(self, key: &str) -> Option<SmolStr> {
+        self.tt_values().find_map(|subtree| {
+            subtree.token_trees.windows(3).find_map(|window| {
+                let leaf = |idx: usize| match &window[idx] {
+                    tt::TokenTree::Leaf(it) => Some(it),
+                    tt::TokenTree::Subtree(_) => None,
+                };
+                match (leaf(0)?, leaf(1)?, leaf(2)?) {
+                    (tt::Leaf::Ident(ident), tt::Leaf::Punct(punct), tt::Leaf::Literal(lit))
+                        if ident.text.as_str() == key && punct.char == '=' =>
+                    {
+                        Some(lit.text.trim_matches('"').into())
+                    }
+                    _ => None,
+                }
+            })
+        })
+    }
+
     pub fn exists(self) -> bool {
         self.attrs().next().is_some()
     }
@@ -155,6 +361,27 @@ impl<'a> AttrQuery<'a> {
     }
 }
 
+fn attr_def_krate(db: &impl DefDatabase, def: AttrDefId) -> Option<CrateId> {
+    let module = match def {
+        AttrDefId::ModuleId(it) => it,
+        AttrDefId::StructFieldId(it) => match it.parent {
+            VariantId::EnumVariantId(it) => it.parent.lookup(db).container.module(db),
+            VariantId::StructId(it) => it.lookup(db).container.module(db),
+            VariantId::UnionId(it) => it.lookup(db).container.module(db),
+        },
+        AttrDefId::EnumVariantId(it) => it.parent.lookup(db).container.module(db),
+        AttrDefId::AdtId(it) => it.module(db),
+        AttrDefId::TraitId(it) => it.lookup(db).container.module(db),
+        AttrDefId::MacroDefId(it) => return it.krate,
+        AttrDefId::ImplId(it) => it.lookup(db).container.module(db),
+        AttrDefId::ConstId(it) => it.lookup(db).module(db),
+        AttrDefId::StaticId(it) => it.lookup(db).module(db),
+        AttrDefId::FunctionId(it) => it.lookup(db).module(db),
+        AttrDefId::TypeAliasId(it) => it.lookup(db).module(db),
+    };
+    Some(module.krate)
+}
+
 fn attrs_from_ast<D, N>(src: AstId<N>, db: &D) -> Attrs
 where
     N: ast::AttrsOwner,
@@ -7,7 +7,10 @@ use std::{mem, ops::Index, sync::Arc};
 
 use drop_bomb::DropBomb;
 use either::Either;
-use hir_expand::{ast_id_map::AstIdMap, hygiene::Hygiene, AstId, HirFileId, InFile, MacroDefId};
+use hir_expand::{
+    ast_id_map::AstIdMap, diagnostics::DiagnosticSink, hygiene::Hygiene, AstId, HirFileId, InFile,
+    MacroDefId,
+};
 use ra_arena::{map::ArenaMap, Arena};
 use ra_prof::profile;
 use ra_syntax::{ast, AstNode, AstPtr};
@@ -15,11 +18,12 @@ use rustc_hash::FxHashMap;
 
 use crate::{
     db::DefDatabase,
+    diagnostics::MacroError,
     expr::{Expr, ExprId, Pat, PatId},
     item_scope::BuiltinShadowMode,
     item_scope::ItemScope,
     nameres::CrateDefMap,
-    path::{ModPath, Path},
+    path::{ModPath, Path, PathKind},
     src::HasSource,
     AsMacroCall, DefWithBodyId, HasModule, Lookup, ModuleId,
 };
@@ -30,6 +34,8 @@ pub(crate) struct Expander {
     hygiene: Hygiene,
     ast_id_map: Arc<AstIdMap>,
     module: ModuleId,
+    /// Macro calls which failed to expand, together with the error.
+    macro_errors: Vec<(InFile<AstPtr<ast::MacroCall>>, String)>,
 }
 
 impl Expander {
@@ -41,7 +47,14 @@ impl Expander {
         let crate_def_map = db.crate_def_map(module.krate);
         let hygiene = Hygiene::new(db, current_file_id);
         let ast_id_map = db.ast_id_map(current_file_id);
-        Expander { crate_def_map, current_file_id, hygiene, ast_id_map, module }
+        Expander {
+            crate_def_map,
+            current_file_id,
+            hygiene,
+            ast_id_map,
+            module,
+            macro_errors: Vec::new(),
+        }
     }
 
     pub(crate) fn enter_expand<T: ast::AstNode, DB: DefDatabase>(
@@ -54,6 +67,15 @@ impl Expander {
         if let Some(call_id) =
             macro_call.as_call_id(db, |path| self.resolve_path_as_macro(db, &path))
         {
+            if let Err(message) = db.macro_expand(call_id) {
+                // Errors in calls produced by other macros can't be shown in
+                // the source.
+                if self.current_file_id == self.current_file_id.original_file(db).into() {
+                    self.macro_errors.push((macro_call.map(|it| AstPtr::new(it)), message));
+                }
+                return None;
+            }
+
             let file_id = call_id.as_file();
             if let Some(node) = db.parse_or_expand(file_id) {
                 if let Some(expr) = T::cast(node) {
@@ -73,8 +95,6 @@ impl Expander {
             }
         }
 
-        // FIXME: Instead of just dropping the error from expansion
-        // report it
         None
     }
 
@@ -94,6 +114,25 @@ impl Expander {
     }
 
     fn resolve_path_as_macro(&self, db: &impl DefDatabase, path: &ModPath) -> Option<MacroDefId> {
+        // Bare macro calls in expansions of `#[macro_export(local_inner_macros)]`
+        // macros are resolved in the crate of that macro first.
+        if let Some(krate) = self.hygiene.local_inner_macros() {
+            if path.is_ident() {
+                let path =
+                    ModPath { kind: PathKind::DollarCrate(krate), segments: path.segments.clone() };
+                if let Some(def) = self.resolve_path_as_macro_in_module(db, &path) {
+                    return Some(def);
+                }
+            }
+        }
+        self.resolve_path_as_macro_in_module(db, path)
+    }
+
+    fn resolve_path_as_macro_in_module(
+        &self,
+        db: &impl DefDatabase,
+        path: &ModPath,
+    ) -> Option<MacroDefId> {
         self.crate_def_map
             .resolve_path(db, self.module.local_id, path, BuiltinShadowMode::Other)
             .0
@@ -154,6 +193,7 @@ pub struct BodySourceMap {
     pat_map_back: ArenaMap<PatId, PatSource>,
     field_map: FxHashMap<(ExprId, usize), AstPtr<ast::RecordField>>,
     expansions: FxHashMap<InFile<AstPtr<ast::MacroCall>>, HirFileId>,
+    macro_errors: Vec<(InFile<AstPtr<ast::MacroCall>>, String)>,
 }
 
 impl Body {
@@ -233,6 +273,14 @@ impl BodySourceMap {
         self.expansions.get(&src).cloned()
     }
 
+    /// Returns all macro calls in the body, along with the files of their
+    /// expansions.
+    pub fn macro_expansions(
+        &self,
+    ) -> impl Iterator<Item = (InFile<AstPtr<ast::MacroCall>>, HirFileId)> + '_ {
+        self.expansions.iter().map(|(call, file)| (call.clone(), *file))
+    }
+
     pub fn field_init_shorthand_expr(&self, node: InFile<&ast::RecordField>) -> Option<ExprId> {
         let src = node.map(|it| Either::Right(AstPtr::new(it)));
         self.expr_map.get(&src).cloned()
@@ -250,4 +298,24 @@ impl BodySourceMap {
     pub fn field_syntax(&self, expr: ExprId, field: usize) -> AstPtr<ast::RecordField> {
         self.field_map[&(expr, field)]
     }
+
+    /// Reports the macro calls in the body which failed to expand.
+    pub fn add_diagnostics(&self, db: &impl DefDatabase, sink: &mut DiagnosticSink) {
+        for (node, message) in &self.macro_errors {
+            let root = match db.parse_or_expand(node.file_id) {
+                Some(it) => it,
+                None => continue,
+            };
+            let path = match node.value.to_node(&root).path() {
+                Some(it) => it,
+                None => continue,
+            };
+            sink.push(MacroError {
+                file: node.file_id,
+                node: node.value,
+                path: AstPtr::new(&path),
+                message: message.clone(),
+            });
+        }
+    }
 }
@@ -1,6 +1,8 @@
 //! Transforms `ast::Expr` into an equivalent `hir_def::expr::Expr`
 //! representation.
 
+use std::mem;
+
 use either::Either;
 
 use hir_expand::name::{name, AsName, Name};
@@ -10,7 +12,7 @@ use ra_syntax::{
         self, ArgListOwner, ArrayExprKind, LiteralKind, LoopBodyOwner, ModuleItemOwner, NameOwner,
         SlicePatComponents, TypeAscriptionOwner,
     },
-    AstNode, AstPtr,
+    AstNode, AstPtr, T,
 };
 use test_utils::tested_by;
 
@@ -97,6 +99,7 @@ where
         };
 
         self.body.body_expr = self.collect_expr_opt(body);
+        self.source_map.macro_errors = mem::take(&mut self.expander.macro_errors);
         (self.body, self.source_map)
     }
 
@@ -130,7 +133,7 @@ where
     }
 
     fn empty_block(&mut self) -> ExprId {
-        let block = Expr::Block { statements: Vec::new(), tail: None };
+        let block = Expr::Block { statements: Vec::new(), tail: None, label: None };
         self.body.exprs.alloc(block)
     }
 
@@ -187,10 +190,14 @@ where
             }
             ast::Expr::BlockExpr(e) => self.collect_block(e),
             ast::Expr::LoopExpr(e) => {
+                let label =
+                    e.label().and_then(|l| l.lifetime_token()).map(|l| Name::new_lifetime(&l));
                 let body = self.collect_block_opt(e.loop_body());
-                self.alloc_expr(Expr::Loop { body }, syntax_ptr)
+                self.alloc_expr(Expr::Loop { body, label }, syntax_ptr)
             }
             ast::Expr::WhileExpr(e) => {
+                let label =
+                    e.label().and_then(|l| l.lifetime_token()).map(|l| Name::new_lifetime(&l));
                 let body = self.collect_block_opt(e.loop_body());
 
                 let condition = match e.condition() {
@@ -203,25 +210,29 @@ where
                             let pat = self.collect_pat(pat);
                             let match_expr = self.collect_expr_opt(condition.expr());
                             let placeholder_pat = self.missing_pat();
-                            let break_ = self.alloc_expr_desugared(Expr::Break { expr: None });
+                            let break_ =
+                                self.alloc_expr_desugared(Expr::Break { expr: None, label: None });
                             let arms = vec![
                                 MatchArm { pat, expr: body, guard: None },
                                 MatchArm { pat: placeholder_pat, expr: break_, guard: None },
                             ];
                             let match_expr =
                                 self.alloc_expr_desugared(Expr::Match { expr: match_expr, arms });
-                            return self.alloc_expr(Expr::Loop { body: match_expr }, syntax_ptr);
+                            return self
+                                .alloc_expr(Expr::Loop { body: match_expr, label }, syntax_ptr);
                         }
                     },
                 };
 
-                self.alloc_expr(Expr::While { condition, body }, syntax_ptr)
+                self.alloc_expr(Expr::While { condition, body, label }, syntax_ptr)
             }
             ast::Expr::ForExpr(e) => {
+                let label =
+                    e.label().and_then(|l| l.lifetime_token()).map(|l| Name::new_lifetime(&l));
                 let iterable = self.collect_expr_opt(e.iterable());
                 let pat = self.collect_pat_opt(e.pat());
                 let body = self.collect_block_opt(e.loop_body());
-                self.alloc_expr(Expr::For { iterable, pat, body }, syntax_ptr)
+                self.alloc_expr(Expr::For { iterable, pat, body, label }, syntax_ptr)
             }
             ast::Expr::CallExpr(e) => {
                 let callee = self.collect_expr_opt(e.expr());
@@ -273,13 +284,14 @@ where
                     .unwrap_or(Expr::Missing);
                 self.alloc_expr(path, syntax_ptr)
             }
-            ast::Expr::ContinueExpr(_e) => {
-                // FIXME: labels
-                self.alloc_expr(Expr::Continue, syntax_ptr)
+            ast::Expr::ContinueExpr(e) => {
+                let label = e.lifetime_token().map(|l| Name::new_lifetime(&l));
+                self.alloc_expr(Expr::Continue { label }, syntax_ptr)
             }
             ast::Expr::BreakExpr(e) => {
+                let label = e.lifetime_token().map(|l| Name::new_lifetime(&l));
                 let expr = e.expr().map(|e| self.collect_expr(e));
-                self.alloc_expr(Expr::Break { expr }, syntax_ptr)
+                self.alloc_expr(Expr::Break { expr, label }, syntax_ptr)
             }
             ast::Expr::ParenExpr(e) => {
                 let inner = self.collect_expr_opt(e.expr());
@@ -292,6 +304,10 @@ where
                 let expr = e.expr().map(|e| self.collect_expr(e));
                 self.alloc_expr(Expr::Return { expr }, syntax_ptr)
             }
+            ast::Expr::YieldExpr(e) => {
+                let expr = e.expr().map(|e| self.collect_expr(e));
+                self.alloc_expr(Expr::Yield { expr }, syntax_ptr)
+            }
             ast::Expr::RecordLit(e) => {
                 let path = e.path().and_then(|path| self.expander.parse_path(path));
                 let mut field_ptrs = Vec::new();
@@ -392,6 +408,11 @@ where
                 let expr = self.collect_expr_opt(e.expr());
                 self.alloc_expr(Expr::Box { expr }, syntax_ptr)
             }
+            ast::Expr::LetExpr(e) => {
+                let pat = self.collect_pat_opt(e.pat());
+                let expr = self.collect_expr_opt(e.expr());
+                self.alloc_expr(Expr::Let { pat, expr }, syntax_ptr)
+            }
 
             ast::Expr::ArrayExpr(e) => {
                 let kind = e.kind();
@@ -412,26 +433,7 @@ where
                 }
             }
 
-            ast::Expr::Literal(e) => {
-                let lit = match e.kind() {
-                    LiteralKind::IntNumber { suffix } => {
-                        let known_name = suffix.and_then(|it| BuiltinInt::from_suffix(&it));
-
-                        Literal::Int(Default::default(), known_name)
-                    }
-                    LiteralKind::FloatNumber { suffix } => {
-                        let known_name = suffix.and_then(|it| BuiltinFloat::from_suffix(&it));
-
-                        Literal::Float(Default::default(), known_name)
-                    }
-                    LiteralKind::ByteString => Literal::ByteString(Default::default()),
-                    LiteralKind::String => Literal::String(Default::default()),
-                    LiteralKind::Byte => Literal::Int(Default::default(), Some(BuiltinInt::U8)),
-                    LiteralKind::Bool => Literal::Bool(Default::default()),
-                    LiteralKind::Char => Literal::Char(Default::default()),
-                };
-                self.alloc_expr(Expr::Literal(lit), syntax_ptr)
-            }
+            ast::Expr::Literal(e) => self.alloc_expr(Expr::Literal(lower_literal(&e)), syntax_ptr),
             ast::Expr::IndexExpr(e) => {
                 let base = self.collect_expr_opt(e.base());
                 let index = self.collect_expr_opt(e.index());
@@ -490,13 +492,18 @@ where
                     let pat = self.collect_pat_opt(stmt.pat());
                     let type_ref = stmt.ascribed_type().map(TypeRef::from_ast);
                     let initializer = stmt.initializer().map(|e| self.collect_expr(e));
-                    Statement::Let { pat, type_ref, initializer }
+                    let else_branch = stmt
+                        .let_else()
+                        .and_then(|it| it.block_expr())
+                        .map(|e| self.collect_block(e));
+                    Statement::Let { pat, type_ref, initializer, else_branch }
                 }
                 ast::Stmt::ExprStmt(stmt) => Statement::Expr(self.collect_expr_opt(stmt.expr())),
             })
             .collect();
         let tail = block.expr().map(|e| self.collect_expr(e));
-        self.alloc_expr(Expr::Block { statements, tail }, syntax_node_ptr)
+        let label = expr.label().and_then(|l| l.lifetime_token()).map(|l| Name::new_lifetime(&l));
+        self.alloc_expr(Expr::Block { statements, tail, label }, syntax_node_ptr)
     }
 
     fn collect_block_items(&mut self, block: &ast::Block) {
@@ -663,9 +670,17 @@ where
                 }
             }
 
-            // FIXME: implement
-            ast::Pat::BoxPat(_) => Pat::Missing,
-            ast::Pat::LiteralPat(_) => Pat::Missing,
+            ast::Pat::BoxPat(p) => {
+                let inner = self.collect_pat_opt(p.pat());
+                Pat::Box { inner }
+            }
+            ast::Pat::LiteralPat(lit) => match lit.literal() {
+                Some(lit) => {
+                    let expr = self.alloc_expr_desugared(Expr::Literal(lower_literal(&lit)));
+                    Pat::Lit(expr)
+                }
+                None => Pat::Missing,
+            },
             ast::Pat::RangePat(_) => Pat::Missing,
         };
         let ptr = AstPtr::new(&pat);
@@ -724,3 +739,23 @@ impl From<ast::BinOp> for BinaryOp {
         }
     }
 }
+
+fn lower_literal(lit: &ast::Literal) -> Literal {
+    match lit.kind() {
+        LiteralKind::IntNumber { suffix } => {
+            let known_name = suffix.and_then(|it| BuiltinInt::from_suffix(&it));
+
+            Literal::Int(lit.int_value().unwrap_or_default(), known_name)
+        }
+        LiteralKind::FloatNumber { suffix } => {
+            let known_name = suffix.and_then(|it| BuiltinFloat::from_suffix(&it));
+
+            Literal::Float(Default::default(), known_name)
+        }
+        LiteralKind::ByteString => Literal::ByteString(Default::default()),
+        LiteralKind::String => Literal::String(Default::default()),
+        LiteralKind::Byte => Literal::Int(Default::default(), Some(BuiltinInt::U8)),
+        LiteralKind::Bool => Literal::Bool(lit.token().kind() == T![true]),
+        LiteralKind::Char => Literal::Char(Default::default()),
+    }
+}
@@ -8,7 +8,7 @@ use rustc_hash::FxHashMap;
 use crate::{
     body::Body,
     db::DefDatabase,
-    expr::{Expr, ExprId, Pat, PatId, Statement},
+    expr::{BinaryOp, Expr, ExprId, LogicOp, Pat, PatId, Statement},
     DefWithBodyId,
 };
 
@@ -118,11 +118,14 @@ fn compute_block_scopes(
 ) {
     for stmt in statements {
         match stmt {
-            Statement::Let { pat, initializer, .. } => {
+            Statement::Let { pat, initializer, else_branch, .. } => {
                 if let Some(expr) = initializer {
                     scopes.set_scope(*expr, scope);
                     compute_expr_scopes(*expr, body, scopes, scope);
                 }
+                if let Some(expr) = else_branch {
+                    compute_expr_scopes(*expr, body, scopes, scope);
+                }
                 scope = scopes.new_scope(scope);
                 scopes.add_bindings(body, scope, *pat);
             }
@@ -140,10 +143,21 @@ fn compute_block_scopes(
 fn compute_expr_scopes(expr: ExprId, body: &Body, scopes: &mut ExprScopes, scope: ScopeId) {
     scopes.set_scope(expr, scope);
     match &body[expr] {
-        Expr::Block { statements, tail } => {
+        Expr::Block { statements, tail, .. } => {
             compute_block_scopes(&statements, *tail, body, scopes, scope);
         }
-        Expr::For { iterable, pat, body: body_expr } => {
+        Expr::If { condition, then_branch, else_branch } => {
+            let then_scope = compute_cond_scopes(*condition, body, scopes, scope);
+            compute_expr_scopes(*then_branch, body, scopes, then_scope);
+            if let Some(else_branch) = else_branch {
+                compute_expr_scopes(*else_branch, body, scopes, scope);
+            }
+        }
+        Expr::While { condition, body: body_expr, .. } => {
+            let body_scope = compute_cond_scopes(*condition, body, scopes, scope);
+            compute_expr_scopes(*body_expr, body, scopes, body_scope);
+        }
+        Expr::For { iterable, pat, body: body_expr, .. } => {
             compute_expr_scopes(*iterable, body, scopes, scope);
             let scope = scopes.new_scope(scope);
             scopes.add_bindings(body, scope, *pat);
@@ -167,6 +181,34 @@ fn compute_expr_scopes(expr: ExprId, body: &Body, scopes: &mut ExprScopes, scope
     };
 }
 
+/// Computes the scopes of the condition of an `if` or a `while`, and returns
+/// the scope of the success branch, which sees the bindings of each `let` of
+/// the chain.
+fn compute_cond_scopes(
+    expr: ExprId,
+    body: &Body,
+    scopes: &mut ExprScopes,
+    scope: ScopeId,
+) -> ScopeId {
+    scopes.set_scope(expr, scope);
+    match &body[expr] {
+        Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::LogicOp(LogicOp::And)) } => {
+            let scope = compute_cond_scopes(*lhs, body, scopes, scope);
+            compute_cond_scopes(*rhs, body, scopes, scope)
+        }
+        Expr::Let { pat, expr } => {
+            compute_expr_scopes(*expr, body, scopes, scope);
+            let scope = scopes.new_scope(scope);
+            scopes.add_bindings(body, scope, *pat);
+            scope
+        }
+        _ => {
+            compute_expr_scopes(expr, body, scopes, scope);
+            scope
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use hir_expand::{name::AsName, InFile};
@@ -287,6 +329,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_let_chain_scope() {
+        do_check(
+            r"
+            fn quux() {
+                if let Some(x) = a && let Some(y) = x && y > 0 {
+                    <|>
+                }
+            }",
+            &["y", "x"],
+        );
+        do_check(
+            r"
+            fn quux() {
+                if let Some(x) = a && let Some(y) = x {
+                } else {
+                    <|>
+                }
+            }",
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_let_else_scope() {
+        do_check(
+            r"
+            fn quux() {
+                let Some(x) = a else {
+                    <|>
+                };
+            }",
+            &[],
+        );
+        do_check(
+            r"
+            fn quux() {
+                let Some(x) = a else { return };
+                <|>
+            }",
+            &["x"],
+        );
+    }
+
     #[test]
     fn test_shadow_variable() {
         do_check(
@@ -6,13 +6,20 @@ use hir_expand::{
     name::{name, AsName, Name},
     AstId, InFile,
 };
-use ra_syntax::ast::{self, AstNode, ImplItem, ModuleItemOwner, NameOwner, TypeAscriptionOwner};
+use ra_syntax::{
+    ast::{
+        self, AstNode, ImplItem, ModuleItemOwner, NameOwner, TypeAscriptionOwner, TypeBoundsOwner,
+        VisibilityOwner,
+    },
+    T,
+};
 
 use crate::{
     db::DefDatabase,
     path::{path, GenericArgs, Path},
     src::HasSource,
-    type_ref::{Mutability, TypeBound, TypeRef},
+    type_ref::{type_bounds_from_ast, Mutability, TypeBound, TypeRef},
+    visibility::RawVisibility,
     AssocContainerId, AssocItemId, ConstId, ConstLoc, Expander, FunctionId, FunctionLoc, HasModule,
     ImplId, Intern, Lookup, ModuleId, StaticId, TraitId, TypeAliasId, TypeAliasLoc,
 };
@@ -25,6 +32,8 @@ pub struct FunctionData {
     /// True if the first param is `self`. This is relevant to decide whether this
     /// can be called as a method.
     pub has_self_param: bool,
+    pub is_unsafe: bool,
+    pub visibility: RawVisibility,
 }
 
 impl FunctionData {
@@ -71,7 +80,9 @@ impl FunctionData {
             ret_type
         };
 
-        let sig = FunctionData { name, params, ret_type, has_self_param };
+        let is_unsafe = src.value.is_unsafe();
+        let visibility = RawVisibility::from_ast(db, src.with_value(src.value.visibility()));
+        let sig = FunctionData { name, params, ret_type, has_self_param, is_unsafe, visibility };
         Arc::new(sig)
     }
 }
@@ -99,7 +110,15 @@ impl TypeAliasData {
     ) -> Arc<TypeAliasData> {
         let node = typ.lookup(db).source(db).value;
         let name = node.name().map_or_else(Name::missing, |n| n.as_name());
-        let type_ref = node.type_ref().map(TypeRef::from_ast);
+        let type_ref = match node.type_ref() {
+            Some(type_ref) => Some(TypeRef::from_ast(type_ref)),
+            // `existential type Foo: Trait;` is the old syntax for
+            // `type Foo = impl Trait;`
+            None if node.syntax().children_with_tokens().any(|it| it.kind() == T![existential]) => {
+                Some(TypeRef::ImplTrait(type_bounds_from_ast(node.type_bound_list())))
+            }
+            None => None,
+        };
         Arc::new(TypeAliasData { name, type_ref })
     }
 }
@@ -174,6 +193,13 @@ impl TraitData {
             _ => None,
         })
     }
+
+    pub fn method_by_name(&self, name: &Name) -> Option<FunctionId> {
+        self.items.iter().find_map(|(item_name, item)| match item {
+            AssocItemId::FunctionId(f) if item_name == name => Some(*f),
+            _ => None,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -4,7 +4,7 @@ use std::any::Any;
 
 use hir_expand::diagnostics::Diagnostic;
 use ra_db::RelativePathBuf;
-use ra_syntax::{ast, AstPtr, SyntaxNodePtr};
+use ra_syntax::{ast, AstPtr, SyntaxNodePtr, TextRange};
 
 use hir_expand::{HirFileId, InFile};
 
@@ -19,6 +19,9 @@ impl Diagnostic for UnresolvedModule {
     fn message(&self) -> String {
         "unresolved module".to_string()
     }
+    fn code(&self) -> &'static str {
+        "unresolved-module"
+    }
     fn source(&self) -> InFile<SyntaxNodePtr> {
         InFile { file_id: self.file, value: self.decl.into() }
     }
@@ -26,3 +29,30 @@ impl Diagnostic for UnresolvedModule {
         self
     }
 }
+
+/// A macro call which failed to expand, highlighted on the path of the macro.
+#[derive(Debug)]
+pub struct MacroError {
+    pub file: HirFileId,
+    pub node: AstPtr<ast::MacroCall>,
+    pub path: AstPtr<ast::Path>,
+    pub message: String,
+}
+
+impl Diagnostic for MacroError {
+    fn message(&self) -> String {
+        self.message.clone()
+    }
+    fn code(&self) -> &'static str {
+        "macro-error"
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.node.into() }
+    }
+    fn highlight_range(&self) -> TextRange {
+        self.path.syntax_node_ptr().range()
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
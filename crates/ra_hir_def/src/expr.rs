@@ -59,18 +59,22 @@ pub enum Expr {
     Block {
         statements: Vec<Statement>,
         tail: Option<ExprId>,
+        label: Option<Name>,
     },
     Loop {
         body: ExprId,
+        label: Option<Name>,
     },
     While {
         condition: ExprId,
         body: ExprId,
+        label: Option<Name>,
     },
     For {
         iterable: ExprId,
         pat: PatId,
         body: ExprId,
+        label: Option<Name>,
     },
     Call {
         callee: ExprId,
@@ -86,13 +90,19 @@ pub enum Expr {
         expr: ExprId,
         arms: Vec<MatchArm>,
     },
-    Continue,
+    Continue {
+        label: Option<Name>,
+    },
     Break {
         expr: Option<ExprId>,
+        label: Option<Name>,
     },
     Return {
         expr: Option<ExprId>,
     },
+    Yield {
+        expr: Option<ExprId>,
+    },
     RecordLit {
         path: Option<Path>,
         fields: Vec<RecordLitField>,
@@ -122,6 +132,12 @@ pub enum Expr {
     Box {
         expr: ExprId,
     },
+    /// A `let` in the condition of an `if` or a `while`, possibly chained with
+    /// other conditions by `&&`.
+    Let {
+        pat: PatId,
+        expr: ExprId,
+    },
     UnaryOp {
         expr: ExprId,
         op: UnaryOp,
@@ -215,7 +231,12 @@ pub struct RecordLitField {
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Statement {
-    Let { pat: PatId, type_ref: Option<TypeRef>, initializer: Option<ExprId> },
+    Let {
+        pat: PatId,
+        type_ref: Option<TypeRef>,
+        initializer: Option<ExprId>,
+        else_branch: Option<ExprId>,
+    },
     Expr(ExprId),
 }
 
@@ -231,13 +252,16 @@ impl Expr {
                     f(*else_branch);
                 }
             }
-            Expr::Block { statements, tail } => {
+            Expr::Block { statements, tail, .. } => {
                 for stmt in statements {
                     match stmt {
-                        Statement::Let { initializer, .. } => {
+                        Statement::Let { initializer, else_branch, .. } => {
                             if let Some(expr) = initializer {
                                 f(*expr);
                             }
+                            if let Some(expr) = else_branch {
+                                f(*expr);
+                            }
                         }
                         Statement::Expr(e) => f(*e),
                     }
@@ -247,8 +271,8 @@ impl Expr {
                 }
             }
             Expr::TryBlock { body } => f(*body),
-            Expr::Loop { body } => f(*body),
-            Expr::While { condition, body } => {
+            Expr::Loop { body, .. } => f(*body),
+            Expr::While { condition, body, .. } => {
                 f(*condition);
                 f(*body);
             }
@@ -274,8 +298,8 @@ impl Expr {
                     f(arm.expr);
                 }
             }
-            Expr::Continue => {}
-            Expr::Break { expr } | Expr::Return { expr } => {
+            Expr::Continue { .. } => {}
+            Expr::Break { expr, .. } | Expr::Return { expr } | Expr::Yield { expr } => {
                 if let Some(expr) = expr {
                     f(*expr);
                 }
@@ -313,7 +337,8 @@ impl Expr {
             | Expr::Cast { expr, .. }
             | Expr::Ref { expr, .. }
             | Expr::UnaryOp { expr, .. }
-            | Expr::Box { expr } => {
+            | Expr::Box { expr }
+            | Expr::Let { expr, .. } => {
                 f(*expr);
             }
             Expr::Tuple { exprs } => {
@@ -412,6 +437,9 @@ pub enum Pat {
         pat: PatId,
         mutability: Mutability,
     },
+    Box {
+        inner: PatId,
+    },
 }
 
 impl Pat {
@@ -424,7 +452,7 @@ impl Pat {
             Pat::Or(args) | Pat::Tuple(args) | Pat::TupleStruct { args, .. } => {
                 args.iter().copied().for_each(f);
             }
-            Pat::Ref { pat, .. } => f(*pat),
+            Pat::Ref { pat, .. } | Pat::Box { inner: pat } => f(*pat),
             Pat::Slice { prefix, slice, suffix } => {
                 let total_iter = prefix.iter().chain(slice.iter()).chain(suffix.iter());
                 total_iter.copied().for_each(f);
@@ -0,0 +1,199 @@
+//! A map of all publicly exported items in a crate.
+
+use std::{collections::VecDeque, sync::Arc};
+
+use ra_db::CrateId;
+use ra_prof::profile;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    db::DefDatabase,
+    item_scope::ItemInNs,
+    path::{ModPath, PathKind},
+    AttrDefId, LocalModuleId, ModuleDefId,
+};
+
+/// Maps each publicly exported item of a crate to the shortest path which can
+/// be used to refer to it from another crate.
+///
+/// Items which are reachable through several paths (for example because they
+/// are re-exported) are recorded only once, with the shortest path. The path
+/// does not include the name of the crate itself, as it depends on how the
+/// crate is named by its dependant.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportMap {
+    /// Sorted by lowercased item name, so that lookups are deterministic.
+    entries: Vec<(String, ItemInNs, ModPath)>,
+}
+
+impl ImportMap {
+    pub(crate) fn import_map_query(db: &impl DefDatabase, krate: CrateId) -> Arc<ImportMap> {
+        let _p = profile("import_map_query");
+        let def_map = db.crate_def_map(krate);
+
+        let mut paths: FxHashMap<ItemInNs, ModPath> = FxHashMap::default();
+        let mut visited: FxHashSet<LocalModuleId> = FxHashSet::default();
+        let mut queue: VecDeque<(LocalModuleId, ModPath)> = VecDeque::new();
+        queue.push_back((def_map.root, ModPath::from_segments(PathKind::Plain, Vec::new())));
+        visited.insert(def_map.root);
+
+        while let Some((module, module_path)) = queue.pop_front() {
+            for (name, per_ns) in def_map[module].scope.entries() {
+                let mut path = module_path.clone();
+                path.segments.push(name.clone());
+
+                let items = per_ns
+                    .types
+                    .map(|(def, vis)| (ItemInNs::Types(def), vis))
+                    .into_iter()
+                    .chain(per_ns.values.map(|(def, vis)| (ItemInNs::Values(def), vis)))
+                    .chain(per_ns.macros.map(|(def, vis)| (ItemInNs::Macros(def), vis)));
+                for (item, vis) in items {
+                    if !vis.is_visible_from_other_crate() || is_doc_hidden(db, item) {
+                        continue;
+                    }
+
+                    if let ItemInNs::Types(ModuleDefId::ModuleId(it)) = item {
+                        if it.krate == krate && visited.insert(it.local_id) {
+                            queue.push_back((it.local_id, path.clone()));
+                        }
+                    }
+
+                    let is_shorter = match paths.get(&item) {
+                        Some(existing) => path_key(&path) < path_key(existing),
+                        None => true,
+                    };
+                    if is_shorter {
+                        paths.insert(item, path.clone());
+                    }
+                }
+            }
+        }
+
+        let mut entries: Vec<_> = paths
+            .into_iter()
+            .filter_map(|(item, path)| {
+                let name = path.segments.last()?.to_string().to_lowercase();
+                Some((name, item, path))
+            })
+            .collect();
+        entries.sort_by_cached_key(|(name, _, path)| (name.clone(), path_key(path)));
+
+        Arc::new(ImportMap { entries })
+    }
+
+    /// Returns the items whose name fuzzy-matches `query`: the first
+    /// character has to match, and the remaining ones have to occur in the
+    /// name in the same order. Matching is case-insensitive.
+    pub fn fuzzy_search<'a>(
+        &'a self,
+        query: &str,
+    ) -> impl Iterator<Item = (ItemInNs, &'a ModPath)> + 'a {
+        let query = query.to_lowercase();
+        self.entries
+            .iter()
+            .filter(move |(name, _, _)| fuzzy_match(name, &query))
+            .map(|(_, item, path)| (*item, path))
+    }
+}
+
+fn path_key(path: &ModPath) -> (usize, String) {
+    (path.segments.len(), path.to_string())
+}
+
+fn fuzzy_match(name: &str, query: &str) -> bool {
+    let mut query = query.chars();
+    let first = match query.next() {
+        Some(it) => it,
+        None => return true,
+    };
+    let mut name = name.chars();
+    if name.next() != Some(first) {
+        return false;
+    }
+    query.all(|q| name.any(|c| c == q))
+}
+
+fn is_doc_hidden(db: &impl DefDatabase, item: ItemInNs) -> bool {
+    let def: AttrDefId = match item {
+        ItemInNs::Types(def) | ItemInNs::Values(def) => match def {
+            ModuleDefId::ModuleId(it) => it.into(),
+            ModuleDefId::FunctionId(it) => it.into(),
+            ModuleDefId::AdtId(it) => it.into(),
+            ModuleDefId::EnumVariantId(it) => it.into(),
+            ModuleDefId::ConstId(it) => it.into(),
+            ModuleDefId::StaticId(it) => it.into(),
+            ModuleDefId::TraitId(it) => it.into(),
+            ModuleDefId::TypeAliasId(it) => it.into(),
+            ModuleDefId::BuiltinType(_) => return false,
+        },
+        ItemInNs::Macros(it) => it.into(),
+    };
+    db.attrs(def).is_doc_hidden()
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::{fixture::WithFixture, SourceDatabase};
+
+    use super::*;
+    use crate::test_db::TestDB;
+
+    fn check_search(fixture: &str, query: &str) -> Vec<String> {
+        let db = TestDB::with_files(fixture);
+        let krate = db.crate_graph().iter().next().unwrap();
+        let import_map = db.import_map(krate);
+        import_map.fuzzy_search(query).map(|(_, path)| path.to_string()).collect()
+    }
+
+    #[test]
+    fn reexport_uses_shortest_path() {
+        let paths = check_search(
+            r"
+            //- /lib.rs
+            pub mod collections {
+                pub mod hash_map {
+                    pub struct HashMap {}
+                }
+                pub use self::hash_map::HashMap;
+            }
+            ",
+            "HashMap",
+        );
+        assert_eq!(paths, vec!["collections::HashMap"]);
+    }
+
+    #[test]
+    fn private_and_hidden_items_are_skipped() {
+        let paths = check_search(
+            r"
+            //- /lib.rs
+            pub mod fmt {
+                pub trait Display {}
+                trait Debugish {}
+                #[doc(hidden)]
+                pub fn debug_impl() {}
+            }
+            mod private {
+                pub struct Dangerous;
+            }
+            ",
+            "d",
+        );
+        assert_eq!(paths, vec!["fmt::Display"]);
+    }
+
+    #[test]
+    fn fuzzy_search_matches_in_order() {
+        let paths = check_search(
+            r"
+            //- /lib.rs
+            pub struct HashMap {}
+            pub struct HashSet {}
+            pub struct MapHash {}
+            ",
+            "hmp",
+        );
+        assert_eq!(paths, vec!["HashMap"]);
+    }
+}
@@ -297,7 +297,12 @@ mod diagnostics {
     use ra_db::RelativePathBuf;
     use ra_syntax::{ast, AstPtr};
 
-    use crate::{db::DefDatabase, diagnostics::UnresolvedModule, nameres::LocalModuleId, AstId};
+    use crate::{
+        db::DefDatabase,
+        diagnostics::{MacroError, UnresolvedModule},
+        nameres::LocalModuleId,
+        AstId,
+    };
 
     #[derive(Debug, PartialEq, Eq)]
     pub(super) enum DefDiagnostic {
@@ -306,6 +311,11 @@ mod diagnostics {
             declaration: AstId<ast::Module>,
             candidate: RelativePathBuf,
         },
+        MacroError {
+            module: LocalModuleId,
+            ast_id: AstId<ast::MacroCall>,
+            message: String,
+        },
     }
 
     impl DefDiagnostic {
@@ -327,6 +337,22 @@ mod diagnostics {
                         candidate: candidate.clone(),
                     })
                 }
+                DefDiagnostic::MacroError { module, ast_id, message } => {
+                    if *module != target_module {
+                        return;
+                    }
+                    let node = ast_id.to_node(db);
+                    let path = match node.path() {
+                        Some(it) => it,
+                        None => return,
+                    };
+                    sink.push(MacroError {
+                        file: ast_id.file_id,
+                        node: AstPtr::new(&node),
+                        path: AstPtr::new(&path),
+                        message: message.clone(),
+                    })
+                }
             }
         }
     }
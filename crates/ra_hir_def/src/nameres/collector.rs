@@ -6,12 +6,13 @@
 use hir_expand::{
     builtin_derive::find_builtin_derive,
     builtin_macro::find_builtin_macro,
+    hygiene::Hygiene,
     name::{name, AsName, Name},
     HirFileId, MacroCallId, MacroDefId, MacroDefKind,
 };
 use ra_cfg::CfgOptions;
 use ra_db::{CrateId, FileId};
-use ra_syntax::ast;
+use ra_syntax::{ast, AstNode};
 use rustc_hash::FxHashMap;
 use test_utils::tested_by;
 
@@ -575,6 +576,10 @@ where
     }
 
     fn collect_macro_expansion(&mut self, module_id: LocalModuleId, macro_call_id: MacroCallId) {
+        if let Err(message) = self.db.macro_expand(macro_call_id) {
+            self.report_macro_error(module_id, macro_call_id, message);
+        }
+
         let file_id: HirFileId = macro_call_id.as_file();
         let raw_items = self.db.raw_items(file_id);
         let mod_dir = self.mod_dirs[&module_id].clone();
@@ -588,6 +593,34 @@ where
         .collect(raw_items.items());
     }
 
+    fn report_macro_error(
+        &mut self,
+        module_id: LocalModuleId,
+        macro_call_id: MacroCallId,
+        message: String,
+    ) {
+        let call = match macro_call_id.as_file().call_node(self.db) {
+            Some(it) => it,
+            None => return,
+        };
+        // Errors in calls produced by other macros can't be shown in the
+        // source.
+        if call.file_id != call.file_id.original_file(self.db).into() {
+            return;
+        }
+        // Derives are reported on the item they are attached to, skip them.
+        let node = match ast::MacroCall::cast(call.value) {
+            Some(it) => it,
+            None => return,
+        };
+        let ast_id = AstId::new(call.file_id, self.db.ast_id_map(call.file_id).ast_id(&node));
+        self.def_map.diagnostics.push(DefDiagnostic::MacroError {
+            module: module_id,
+            ast_id,
+            message,
+        });
+    }
+
     fn finish(self) -> CrateDefMap {
         self.def_map
     }
@@ -623,7 +656,8 @@ where
         // `#[macro_use] extern crate` is hoisted to imports macros before collecting
         // any other items.
         for item in items {
-            if self.is_cfg_enabled(&item.attrs) {
+            let attrs = item.attrs.expand_cfg_attr(self.def_collector.cfg_options);
+            if self.is_cfg_enabled(&attrs) {
                 if let raw::RawItemKind::Import(import_id) = item.kind {
                     let import = self.raw_items[import_id].clone();
                     if import.is_extern_crate && import.is_macro_use {
@@ -634,11 +668,10 @@ where
         }
 
         for item in items {
-            if self.is_cfg_enabled(&item.attrs) {
+            let attrs = item.attrs.expand_cfg_attr(self.def_collector.cfg_options);
+            if self.is_cfg_enabled(&attrs) {
                 match item.kind {
-                    raw::RawItemKind::Module(m) => {
-                        self.collect_module(&self.raw_items[m], &item.attrs)
-                    }
+                    raw::RawItemKind::Module(m) => self.collect_module(&self.raw_items[m], &attrs),
                     raw::RawItemKind::Import(import_id) => {
                         self.def_collector.unresolved_imports.push(ImportDirective {
                             module_id: self.module_id,
@@ -647,9 +680,7 @@ where
                             status: PartialResolvedImport::Unresolved,
                         })
                     }
-                    raw::RawItemKind::Def(def) => {
-                        self.define_def(&self.raw_items[def], &item.attrs)
-                    }
+                    raw::RawItemKind::Def(def) => self.define_def(&self.raw_items[def], &attrs),
                     raw::RawItemKind::Macro(mac) => self.collect_macro(&self.raw_items[mac]),
                     raw::RawItemKind::Impl(imp) => {
                         let module = ModuleId {
@@ -712,6 +743,11 @@ where
                             &visibility,
                         );
                         let raw_items = self.def_collector.db.raw_items(file_id.into());
+                        // `#![macro_use]` in the module file works like
+                        // `#[macro_use]` on the declaration.
+                        let inner_attrs =
+                            raw_items.attrs().expand_cfg_attr(self.def_collector.cfg_options);
+                        let is_macro_use = is_macro_use || inner_attrs.by_key("macro_use").exists();
                         ModCollector {
                             def_collector: &mut *self.def_collector,
                             module_id,
@@ -872,13 +908,47 @@ where
                     ast_id: Some(ast_id.ast_id),
                     krate: Some(self.def_collector.def_map.krate),
                     kind: MacroDefKind::Declarative,
+                    local_inner: mac.local_inner,
                 };
                 self.def_collector.define_macro(self.module_id, name.clone(), macro_id, mac.export);
             }
             return;
         }
 
-        // Case 2: try to resolve in legacy scope and expand macro_rules
+        // Case 2: a bare macro call in an expansion of a
+        // `#[macro_export(local_inner_macros)]` macro, try to resolve it in the
+        // root of the crate defining that macro first, as if it was `$crate::name`
+        if ast_id.path.is_ident() {
+            let hygiene = Hygiene::new(self.def_collector.db, self.file_id);
+            if let Some(krate) = hygiene.local_inner_macros() {
+                let path = ModPath {
+                    kind: PathKind::DollarCrate(krate),
+                    segments: ast_id.path.segments.clone(),
+                };
+                if let Some(macro_call_id) = ast_id.as_call_id(self.def_collector.db, |_| {
+                    self.def_collector
+                        .def_map
+                        .resolve_path_fp_with_macro(
+                            self.def_collector.db,
+                            ResolveMode::Other,
+                            self.module_id,
+                            &path,
+                            BuiltinShadowMode::Module,
+                        )
+                        .resolved_def
+                        .take_macros()
+                }) {
+                    self.def_collector.unexpanded_macros.push(MacroDirective {
+                        module_id: self.module_id,
+                        ast_id,
+                        legacy: Some(macro_call_id),
+                    });
+                    return;
+                }
+            }
+        }
+
+        // Case 3: try to resolve in legacy scope and expand macro_rules
         if let Some(macro_call_id) = ast_id.as_call_id(self.def_collector.db, |path| {
             path.as_ident().and_then(|name| {
                 self.def_collector.def_map[self.module_id].scope.get_legacy_macro(&name)
@@ -893,7 +963,7 @@ where
             return;
         }
 
-        // Case 3: resolve in module scope, expand during name resolution.
+        // Case 4: resolve in module scope, expand during name resolution.
         // We rewrite simple path `macro_name` to `self::macro_name` to force resolve in module scope only.
         if ast_id.path.is_ident() {
             ast_id.path.kind = PathKind::Super(0);
@@ -914,7 +984,6 @@ where
     }
 
     fn is_cfg_enabled(&self, attrs: &Attrs) -> bool {
-        // FIXME: handle cfg_attr :-)
         attrs
             .by_key("cfg")
             .tt_values()
@@ -40,6 +40,8 @@ pub struct RawItems {
     defs: Arena<Def, DefData>,
     macros: Arena<Macro, MacroData>,
     impls: Arena<Impl, ImplData>,
+    /// inner attributes of the top-level module, like `#![macro_use]`
+    attrs: Attrs,
     /// items for top-level module
     items: Vec<RawItem>,
 }
@@ -58,6 +60,7 @@ impl RawItems {
         };
         if let Some(node) = db.parse_or_expand(file_id) {
             if let Some(source_file) = ast::SourceFile::cast(node.clone()) {
+                collector.raw_items.attrs = collector.parse_attrs(&source_file);
                 collector.process_module(None, source_file);
             } else if let Some(item_list) = ast::MacroItems::cast(node) {
                 collector.process_module(None, item_list);
@@ -70,6 +73,10 @@ impl RawItems {
     pub(super) fn items(&self) -> &[RawItem] {
         &self.items
     }
+
+    pub(super) fn attrs(&self) -> &Attrs {
+        &self.attrs
+    }
 }
 
 impl Index<Module> for RawItems {
@@ -204,6 +211,7 @@ pub(super) struct MacroData {
     pub(super) path: ModPath,
     pub(super) name: Option<Name>,
     pub(super) export: bool,
+    pub(super) local_inner: bool,
     pub(super) builtin: bool,
 }
 
@@ -306,6 +314,8 @@ impl RawItemsCollector {
         }
 
         if let Some(item_list) = module.item_list() {
+            // Inner attributes apply to the module just like the outer ones.
+            let attrs = attrs.merge(self.parse_attrs(&item_list));
             let item = self.raw_items.modules.alloc(ModuleData::Definition {
                 name,
                 visibility,
@@ -386,12 +396,20 @@ impl RawItemsCollector {
         let ast_id = self.source_ast_id_map.ast_id(&m);
         // FIXME: cfg_attr
         let export = m.attrs().filter_map(|x| x.simple_name()).any(|name| name == "macro_export");
+        let local_inner = m.has_local_inner_macros();
 
         // FIXME: cfg_attr
         let builtin =
             m.attrs().filter_map(|x| x.simple_name()).any(|name| name == "rustc_builtin_macro");
 
-        let m = self.raw_items.macros.alloc(MacroData { ast_id, path, name, export, builtin });
+        let m = self.raw_items.macros.alloc(MacroData {
+            ast_id,
+            path,
+            name,
+            export,
+            local_inner,
+            builtin,
+        });
         self.push_item(current_module, attrs, RawItemKind::Macro(m));
     }
 
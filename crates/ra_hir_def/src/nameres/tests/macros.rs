@@ -135,6 +135,46 @@ fn macro_rules_export_with_local_inner_macros_are_visible() {
     "###);
 }
 
+#[test]
+fn macro_rules_export_with_local_inner_macros_resolve_inner_calls_in_their_crate() {
+    let map = def_map(
+        "
+        //- /main.rs crate:main deps:foo
+        foo::structs!(Foo, Bar);
+        mod bar;
+
+        //- /bar.rs
+        use crate::*;
+
+        //- /lib.rs crate:foo
+        #[macro_export(local_inner_macros)]
+        macro_rules! structs {
+            ($($i:ident),*) => {
+                inner!($($i),*);
+            }
+        }
+
+        #[macro_export]
+        macro_rules! inner {
+            ($($i:ident),*) => {
+                $(struct $i { field: u32 } )*
+            }
+        }
+        ",
+    );
+    assert_snapshot!(map, @r###"
+   ⋮crate
+   ⋮Bar: t v
+   ⋮Foo: t v
+   ⋮bar: t
+   ⋮
+   ⋮crate::bar
+   ⋮Bar: t v
+   ⋮Foo: t v
+   ⋮bar: t
+    "###);
+}
+
 #[test]
 fn unexpanded_macro_should_expand_by_fixedpoint_loop() {
     let map = def_map(
@@ -400,6 +440,62 @@ fn plain_macros_are_legacy_textual_scoped() {
     "###);
 }
 
+#[test]
+fn macro_use_module_is_visible_to_later_siblings_only() {
+    let map = def_map(
+        r#"
+        //- /lib.rs
+        mod before;
+        #[macro_use]
+        mod macros;
+        mod after;
+
+        //- /macros.rs
+        macro_rules! structs {
+            ($($i:ident),*) => { $(struct $i;)* }
+        }
+
+        //- /before.rs
+        structs!(NotFoundBefore);
+
+        //- /after.rs
+        structs!(FoundAfter);
+        "#,
+    );
+    assert!(map.contains("crate::after\nFoundAfter: t v"), "{}", map);
+    assert!(!map.contains("NotFoundBefore"), "{}", map);
+}
+
+#[test]
+fn inner_macro_use_attribute() {
+    let map = def_map(
+        r#"
+        //- /lib.rs
+        structs!(NotFoundBefore);
+        mod macros;
+        mod inline {
+            #![macro_use]
+            macro_rules! fns {
+                ($($i:ident),*) => { $(fn $i() {})* }
+            }
+        }
+        structs!(FoundAfter);
+        fns!(found_after);
+
+        //- /macros.rs
+        #![macro_use]
+        macro_rules! structs {
+            ($($i:ident),*) => { $(struct $i;)* }
+        }
+        "#,
+    );
+    assert!(
+        map.starts_with("crate\nFoundAfter: t v\nfound_after: v\ninline: t\nmacros: t\n"),
+        "{}",
+        map
+    );
+}
+
 #[test]
 fn type_value_macro_live_in_different_scopes() {
     let map = def_map(
@@ -505,6 +601,52 @@ fn path_qualified_macros() {
     "###);
 }
 
+#[test]
+fn self_qualified_and_textual_macros() {
+    let map = def_map(
+        "
+        //- /main.rs
+        macro_rules! foo {
+            ($x:ident) => { struct $x; }
+        }
+
+        macro_rules! baz {
+            ($x:ident) => { fn $x() {} }
+        }
+
+        use m::alias as baz;
+
+        foo!(OkPlain);
+        baz!(OkTextual);
+        self::foo!(NotResolved);
+        self::bar!(OkSelf);
+        self::m::alias!(OkSelfAlias);
+
+        mod m {
+            #[macro_export]
+            macro_rules! bar {
+                ($x:ident) => { struct $x; }
+            }
+
+            pub use bar as alias;
+        }
+        ",
+    );
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮OkPlain: t v
+        ⋮OkSelf: t v
+        ⋮OkSelfAlias: t v
+        ⋮OkTextual: v
+        ⋮bar: m
+        ⋮baz: m
+        ⋮m: t
+        ⋮
+        ⋮crate::m
+        ⋮alias: m
+    "###);
+}
+
 #[test]
 fn macro_dollar_crate_is_correct_in_item() {
     covers!(macro_dollar_crate_self);
@@ -825,3 +825,78 @@ fn nested_out_of_line_module_with_path() {
     X: t v
     "###);
 }
+
+#[test]
+fn module_resolution_cfg_attr_path_enabled() {
+    let map = def_map(
+        r###"
+        //- /lib.rs crate:lib cfg:test
+        #[cfg_attr(test, path = "bar.rs")]
+        mod foo;
+
+        //- /foo.rs
+        pub struct Foo;
+
+        //- /bar.rs
+        pub struct Bar;
+        "###,
+    );
+
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮foo: t
+        ⋮
+        ⋮crate::foo
+        ⋮Bar: t v
+    "###);
+}
+
+#[test]
+fn module_resolution_cfg_attr_path_disabled() {
+    let map = def_map(
+        r###"
+        //- /lib.rs crate:lib
+        #[cfg_attr(test, path = "bar.rs")]
+        mod foo;
+
+        //- /foo.rs
+        pub struct Foo;
+
+        //- /bar.rs
+        pub struct Bar;
+        "###,
+    );
+
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮foo: t
+        ⋮
+        ⋮crate::foo
+        ⋮Foo: t v
+    "###);
+}
+
+#[test]
+fn module_resolution_nested_cfg_attr_path() {
+    let map = def_map(
+        r###"
+        //- /lib.rs crate:lib cfg:test,feature=baz
+        #[cfg_attr(test, allow(unused), cfg_attr(feature = "baz", path = "bar.rs"))]
+        mod foo;
+
+        //- /foo.rs
+        pub struct Foo;
+
+        //- /bar.rs
+        pub struct Bar;
+        "###,
+    );
+
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮foo: t
+        ⋮
+        ⋮crate::foo
+        ⋮Bar: t v
+    "###);
+}
@@ -44,7 +44,7 @@ pub enum TypeRef {
     Path(Path),
     RawPtr(Box<TypeRef>, Mutability),
     Reference(Box<TypeRef>, Mutability),
-    Array(Box<TypeRef> /*, Expr*/),
+    Array(Box<TypeRef>, ArrayLen),
     Slice(Box<TypeRef>),
     /// A fn pointer. Last element of the vector is the return type.
     Fn(Vec<TypeRef>),
@@ -54,6 +54,29 @@ pub enum TypeRef {
     Error,
 }
 
+/// The length of an array type. Only literals and paths to constants are
+/// understood.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ArrayLen {
+    Literal(u64),
+    Path(Path),
+    Unknown,
+}
+
+impl ArrayLen {
+    pub(crate) fn from_ast_opt(node: Option<ast::Expr>) -> Self {
+        match node {
+            Some(ast::Expr::Literal(lit)) => {
+                lit.int_value().map_or(ArrayLen::Unknown, ArrayLen::Literal)
+            }
+            Some(ast::Expr::PathExpr(path)) => {
+                path.path().and_then(Path::from_ast).map_or(ArrayLen::Unknown, ArrayLen::Path)
+            }
+            _ => ArrayLen::Unknown,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum TypeBound {
     Path(Path),
@@ -80,9 +103,10 @@ impl TypeRef {
                 let mutability = Mutability::from_mutable(inner.is_mut());
                 TypeRef::RawPtr(Box::new(inner_ty), mutability)
             }
-            ast::TypeRef::ArrayType(inner) => {
-                TypeRef::Array(Box::new(TypeRef::from_ast_opt(inner.type_ref())))
-            }
+            ast::TypeRef::ArrayType(inner) => TypeRef::Array(
+                Box::new(TypeRef::from_ast_opt(inner.type_ref())),
+                ArrayLen::from_ast_opt(inner.expr()),
+            ),
             ast::TypeRef::SliceType(inner) => {
                 TypeRef::Slice(Box::new(TypeRef::from_ast_opt(inner.type_ref())))
             }
@@ -134,7 +158,7 @@ impl TypeRef {
                 TypeRef::Fn(types) | TypeRef::Tuple(types) => types.iter().for_each(|t| go(t, f)),
                 TypeRef::RawPtr(type_ref, _)
                 | TypeRef::Reference(type_ref, _)
-                | TypeRef::Array(type_ref)
+                | TypeRef::Array(type_ref, _)
                 | TypeRef::Slice(type_ref) => go(&type_ref, f),
                 TypeRef::ImplTrait(bounds) | TypeRef::DynTrait(bounds) => {
                     for bound in bounds {
@@ -179,19 +203,26 @@ pub(crate) fn type_bounds_from_ast(type_bounds_opt: Option<ast::TypeBoundList>)
 impl TypeBound {
     pub(crate) fn from_ast(node: ast::TypeBound) -> Self {
         match node.kind() {
-            ast::TypeBoundKind::PathType(path_type) => {
-                let path = match path_type.path() {
-                    Some(p) => p,
-                    None => return TypeBound::Error,
-                };
-                // FIXME: Use `Path::from_src`
-                let path = match Path::from_ast(path) {
-                    Some(p) => p,
-                    None => return TypeBound::Error,
-                };
-                TypeBound::Path(path)
-            }
-            ast::TypeBoundKind::ForType(_) | ast::TypeBoundKind::Lifetime(_) => TypeBound::Error,
+            ast::TypeBoundKind::PathType(path_type) => TypeBound::from_path_type(path_type),
+            // `for<'a> Trait<'a>` bounds are close enough to the inner trait for
+            // our purposes, like `for` types.
+            ast::TypeBoundKind::ForType(for_type) => match for_type.type_ref() {
+                Some(ast::TypeRef::PathType(path_type)) => TypeBound::from_path_type(path_type),
+                _ => TypeBound::Error,
+            },
+            ast::TypeBoundKind::Lifetime(_) => TypeBound::Error,
+        }
+    }
+
+    fn from_path_type(path_type: ast::PathType) -> Self {
+        let path = match path_type.path() {
+            Some(p) => p,
+            None => return TypeBound::Error,
+        };
+        // FIXME: Use `Path::from_src`
+        match Path::from_ast(path) {
+            Some(p) => TypeBound::Path(p),
+            None => TypeBound::Error,
         }
     }
 
@@ -38,7 +38,12 @@ macro_rules! register_builtin {
                  _ => return None,
             };
 
-            Some(MacroDefId { krate: None, ast_id: None, kind: MacroDefKind::BuiltInDerive(kind) })
+            Some(MacroDefId {
+                krate: None,
+                ast_id: None,
+                kind: MacroDefKind::BuiltInDerive(kind),
+                local_inner: false,
+            })
         }
     };
 }
@@ -240,8 +245,12 @@ mod tests {
         let ast_id_map = db.ast_id_map(file_id.into());
 
         // the first one should be a macro_rules
-        let def =
-            MacroDefId { krate: None, ast_id: None, kind: MacroDefKind::BuiltInDerive(expander) };
+        let def = MacroDefId {
+            krate: None,
+            ast_id: None,
+            kind: MacroDefKind::BuiltInDerive(expander),
+            local_inner: false,
+        };
 
         let loc = MacroCallLoc {
             def,
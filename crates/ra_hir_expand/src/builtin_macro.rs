@@ -2,8 +2,11 @@
 use crate::db::AstDatabase;
 use crate::{
     ast::{self},
-    name, AstId, CrateId, MacroCallId, MacroDefId, MacroDefKind, TextUnit,
+    name, AstId, CrateId, HirFileId, HirFileIdRepr, MacroCallId, MacroDefId, MacroDefKind,
+    TextUnit,
 };
+use ra_db::RelativePath;
+use ra_syntax::{AstNode, SmolStr};
 
 use crate::quote;
 
@@ -42,7 +45,12 @@ macro_rules! register_builtin {
         ) -> Option<MacroDefId> {
             let kind = BuiltinFnLikeExpander::by_name(ident)?;
 
-            Some(MacroDefId { krate: Some(krate), ast_id: Some(ast_id), kind: MacroDefKind::BuiltIn(kind) })
+            Some(MacroDefId {
+                krate: Some(krate),
+                ast_id: Some(ast_id),
+                kind: MacroDefKind::BuiltIn(kind),
+                local_inner: false,
+            })
         }
     };
 }
@@ -56,6 +64,8 @@ register_builtin! {
     (format_args, FormatArgs) => format_args_expand,
     (env, Env) => env_expand,
     (option_env, OptionEnv) => option_env_expand,
+    (include, Include) => include_expand,
+    (concat, Concat) => concat_expand,
     // format_args_nl only differs in that it adds a newline in the end,
     // so we use the same stub expansion for now
     (format_args_nl, FormatArgsNl) => format_args_expand
@@ -64,8 +74,9 @@ register_builtin! {
 fn line_expand(
     _db: &dyn AstDatabase,
     _id: MacroCallId,
-    _tt: &tt::Subtree,
+    tt: &tt::Subtree,
 ) -> Result<tt::Subtree, mbe::ExpandError> {
+    expect_no_args("line", tt)?;
     // dummy implementation for type-checking purposes
     let line_num = 0;
     let expanded = quote! {
@@ -98,16 +109,44 @@ fn stringify_expand(
 }
 
 fn env_expand(
-    _db: &dyn AstDatabase,
-    _id: MacroCallId,
-    _tt: &tt::Subtree,
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
 ) -> Result<tt::Subtree, mbe::ExpandError> {
-    // dummy implementation for type-checking purposes
-    let expanded = quote! { "" };
+    let key = parse_string(tt)?;
+    // Unknown variables expand to an empty string, so that type inference
+    // still works; `missing_env_var` is used to report them.
+    let value = get_env(db, id, &key).unwrap_or_default();
+    let value = tt::Literal { text: format!("{:?}", value).into(), id: tt::TokenId::unspecified() };
+    let expanded = quote! { #value };
 
     Ok(expanded)
 }
 
+/// If `file_id` is the expansion of an `env!` call referring to a variable
+/// which isn't set for the calling crate, returns the variable name.
+pub fn missing_env_var(db: &dyn AstDatabase, file_id: HirFileId) -> Option<SmolStr> {
+    let id = match file_id.0 {
+        HirFileIdRepr::MacroFile(it) => it.macro_call_id,
+        HirFileIdRepr::FileId(_) => return None,
+    };
+    let loc = db.lookup_intern_macro(id);
+    if loc.def.kind != MacroDefKind::BuiltIn(BuiltinFnLikeExpander::Env) {
+        return None;
+    }
+    let arg = db.macro_arg(id)?;
+    let key = parse_string(&arg.0).ok()?;
+    match get_env(db, id, &key) {
+        Some(_) => None,
+        None => Some(key.into()),
+    }
+}
+
+fn get_env(db: &dyn AstDatabase, id: MacroCallId, key: &str) -> Option<String> {
+    let krate = db.lookup_intern_macro(id).def.krate?;
+    db.crate_graph().env(krate).get(key).map(|it| it.to_string())
+}
+
 fn option_env_expand(
     _db: &dyn AstDatabase,
     _id: MacroCallId,
@@ -122,8 +161,9 @@ fn option_env_expand(
 fn column_expand(
     _db: &dyn AstDatabase,
     _id: MacroCallId,
-    _tt: &tt::Subtree,
+    tt: &tt::Subtree,
 ) -> Result<tt::Subtree, mbe::ExpandError> {
+    expect_no_args("column", tt)?;
     // dummy implementation for type-checking purposes
     let col_num = 0;
     let expanded = quote! {
@@ -136,8 +176,9 @@ fn column_expand(
 fn file_expand(
     _db: &dyn AstDatabase,
     _id: MacroCallId,
-    _tt: &tt::Subtree,
+    tt: &tt::Subtree,
 ) -> Result<tt::Subtree, mbe::ExpandError> {
+    expect_no_args("file", tt)?;
     // FIXME: RA purposefully lacks knowledge of absolute file names
     // so just return "".
     let file_name = "";
@@ -208,6 +249,151 @@ fn format_args_expand(
     Ok(expanded)
 }
 
+fn include_expand(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    let path = parse_string(tt)?;
+    let loc = db.lookup_intern_macro(id);
+    let anchor = loc.kind.file_id().original_file(db);
+    let file_id = db
+        .resolve_relative_path(anchor, RelativePath::new(&path))
+        .ok_or_else(|| mbe::ExpandError::ConversionError)?;
+
+    // The included file is converted as a whole, whether it contains items or
+    // an expression; the expansion is then parsed according to the position
+    // of the call.
+    let parse = db.parse(file_id);
+    let (mut subtree, _token_map) = mbe::syntax_node_to_token_tree(parse.tree().syntax())
+        .ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    // The token ids refer to the included file, not to the macro call, so
+    // they must not be mapped back to the call's arguments.
+    forget_token_ids(&mut subtree);
+
+    Ok(subtree)
+}
+
+fn forget_token_ids(subtree: &mut tt::Subtree) {
+    if let Some(delimiter) = &mut subtree.delimiter {
+        delimiter.id = tt::TokenId::unspecified();
+    }
+    for tt in subtree.token_trees.iter_mut() {
+        match tt {
+            tt::TokenTree::Subtree(it) => forget_token_ids(it),
+            tt::TokenTree::Leaf(tt::Leaf::Literal(it)) => it.id = tt::TokenId::unspecified(),
+            tt::TokenTree::Leaf(tt::Leaf::Punct(it)) => it.id = tt::TokenId::unspecified(),
+            tt::TokenTree::Leaf(tt::Leaf::Ident(it)) => it.id = tt::TokenId::unspecified(),
+        }
+    }
+}
+
+fn concat_expand(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    let text = concat_text(db, id, tt)?;
+    let expanded =
+        tt::Literal { text: format!("\"{}\"", text).into(), id: tt::TokenId::unspecified() };
+
+    Ok(quote! { #expanded })
+}
+
+fn concat_text(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> Result<String, mbe::ExpandError> {
+    let mut text = String::new();
+    let mut tokens = tt.token_trees.iter().peekable();
+    while let Some(t) = tokens.next() {
+        match t {
+            tt::TokenTree::Leaf(tt::Leaf::Literal(it)) => {
+                text += &unquote(&it.text).unwrap_or_else(|| it.text.to_string());
+            }
+            tt::TokenTree::Leaf(tt::Leaf::Punct(it)) if it.char == ',' => (),
+            tt::TokenTree::Leaf(tt::Leaf::Ident(it)) if it.text == "true" || it.text == "false" => {
+                text += &it.text;
+            }
+            // Negative numbers are lexed as a `-` followed by a literal.
+            tt::TokenTree::Leaf(tt::Leaf::Punct(it)) if it.char == '-' => match tokens.next() {
+                Some(tt::TokenTree::Leaf(tt::Leaf::Literal(lit)))
+                    if lit.text.starts_with(|c: char| c.is_ascii_digit()) =>
+                {
+                    text += "-";
+                    text += &lit.text;
+                }
+                _ => return Err(mbe::ExpandError::BindingError("expected a literal".into())),
+            },
+            // Macro calls in the arguments are expanded eagerly; we only
+            // support the builtin ones which produce string literals.
+            tt::TokenTree::Leaf(tt::Leaf::Ident(name)) if is_bang(tokens.peek()) => {
+                tokens.next();
+                let args = match tokens.next() {
+                    Some(tt::TokenTree::Subtree(it)) => it,
+                    _ => return Err(mbe::ExpandError::UnexpectedToken),
+                };
+                match name.text.as_str() {
+                    "concat" => text += &concat_text(db, id, args)?,
+                    "env" => {
+                        let value = get_env(db, id, &parse_string(args)?).unwrap_or_default();
+                        text += &value.escape_default().to_string();
+                    }
+                    _ => {
+                        return Err(mbe::ExpandError::BindingError(format!(
+                            "can't expand `{}!` inside `concat!`",
+                            name.text
+                        )))
+                    }
+                }
+            }
+            _ => return Err(mbe::ExpandError::BindingError("expected a literal".into())),
+        }
+    }
+    Ok(text)
+}
+
+fn expect_no_args(name: &str, tt: &tt::Subtree) -> Result<(), mbe::ExpandError> {
+    if tt.token_trees.is_empty() {
+        Ok(())
+    } else {
+        Err(mbe::ExpandError::BindingError(format!("`{}!` takes no arguments", name)))
+    }
+}
+
+fn is_bang(tt: Option<&&tt::TokenTree>) -> bool {
+    match tt {
+        Some(tt::TokenTree::Leaf(tt::Leaf::Punct(it))) => it.char == '!',
+        _ => false,
+    }
+}
+
+/// Parses the single string literal argument of a macro like `env!` or
+/// `include!`.
+fn parse_string(tt: &tt::Subtree) -> Result<String, mbe::ExpandError> {
+    tt.token_trees
+        .get(0)
+        .and_then(|tt| match tt {
+            tt::TokenTree::Leaf(tt::Leaf::Literal(it)) => strip_quotes(&it.text, '"'),
+            _ => None,
+        })
+        .ok_or_else(|| mbe::ExpandError::ConversionError)
+}
+
+/// Returns the contents of a string or char literal, with escapes left as is.
+fn unquote(lit: &str) -> Option<String> {
+    strip_quotes(lit, '"').or_else(|| strip_quotes(lit, '\''))
+}
+
+fn strip_quotes(lit: &str, quote: char) -> Option<String> {
+    if lit.len() >= 2 && lit.starts_with(quote) && lit.ends_with(quote) {
+        Some(lit[1..lit.len() - 1].to_string())
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,6 +417,7 @@ mod tests {
             krate: Some(CrateId(0)),
             ast_id: Some(AstId::new(file_id.into(), ast_id_map.ast_id(&macro_calls[0]))),
             kind: MacroDefKind::BuiltIn(expander),
+            local_inner: false,
         };
 
         let loc = MacroCallLoc {
@@ -299,6 +486,45 @@ mod tests {
         assert_eq!(expanded, "\"\"");
     }
 
+    #[test]
+    fn test_concat_expand() {
+        let expanded = expand_builtin_macro(
+            r#"
+            #[rustc_builtin_macro]
+            macro_rules! concat {() => {}}
+            concat!("foo", 0, 'r', true)
+            "#,
+        );
+
+        assert_eq!(expanded, r#""foo0rtrue""#);
+    }
+
+    #[test]
+    fn test_concat_expand_negative_and_bool() {
+        let expanded = expand_builtin_macro(
+            r#"
+            #[rustc_builtin_macro]
+            macro_rules! concat {() => {}}
+            concat!("a", -1, false, -2.5)
+            "#,
+        );
+
+        assert_eq!(expanded, r#""a-1false-2.5""#);
+    }
+
+    #[test]
+    fn test_concat_expand_nested() {
+        let expanded = expand_builtin_macro(
+            r#"
+            #[rustc_builtin_macro]
+            macro_rules! concat {() => {}}
+            concat!("a", concat!("b", env!("TEST_ENV_VAR")), "c")
+            "#,
+        );
+
+        assert_eq!(expanded, r#""abc""#);
+    }
+
     #[test]
     fn test_option_env_expand() {
         let expanded = expand_builtin_macro(
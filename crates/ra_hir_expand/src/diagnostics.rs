@@ -22,6 +22,8 @@ use crate::{db::AstDatabase, InFile};
 
 pub trait Diagnostic: Any + Send + Sync + fmt::Debug + 'static {
     fn message(&self) -> String;
+    /// A stable identifier of the kind of diagnostic, like `unresolved-module`.
+    fn code(&self) -> &'static str;
     fn source(&self) -> InFile<SyntaxNodePtr>;
     fn highlight_range(&self) -> TextRange {
         self.source().value.range()
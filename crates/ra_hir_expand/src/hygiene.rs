@@ -1,7 +1,8 @@
 //! This modules handles hygiene information.
 //!
 //! Specifically, `ast` + `Hygiene` allows you to create a `Name`. Note that, at
-//! this moment, this is horribly incomplete and handles only `$crate`.
+//! this moment, this is horribly incomplete and handles only `$crate` and
+//! `local_inner_macros`.
 use either::Either;
 use ra_db::CrateId;
 use ra_syntax::ast;
@@ -16,26 +17,39 @@ use crate::{
 pub struct Hygiene {
     // This is what `$crate` expands to
     def_crate: Option<CrateId>,
+    // Whether the expanded macro is `#[macro_export(local_inner_macros)]`
+    local_inner: bool,
 }
 
 impl Hygiene {
     pub fn new(db: &impl AstDatabase, file_id: HirFileId) -> Hygiene {
-        let def_crate = match file_id.0 {
-            HirFileIdRepr::FileId(_) => None,
+        let (def_crate, local_inner) = match file_id.0 {
+            HirFileIdRepr::FileId(_) => (None, false),
             HirFileIdRepr::MacroFile(macro_file) => {
                 let loc = db.lookup_intern_macro(macro_file.macro_call_id);
                 match loc.def.kind {
-                    MacroDefKind::Declarative => loc.def.krate,
-                    MacroDefKind::BuiltIn(_) => None,
-                    MacroDefKind::BuiltInDerive(_) => None,
+                    MacroDefKind::Declarative => (loc.def.krate, loc.def.local_inner),
+                    MacroDefKind::BuiltIn(_) => (None, false),
+                    MacroDefKind::BuiltInDerive(_) => (None, false),
                 }
             }
         };
-        Hygiene { def_crate }
+        Hygiene { def_crate, local_inner }
     }
 
     pub fn new_unhygienic() -> Hygiene {
-        Hygiene { def_crate: None }
+        Hygiene { def_crate: None, local_inner: false }
+    }
+
+    /// If this is an expansion of a `#[macro_export(local_inner_macros)]`
+    /// macro, returns the crate in which bare macro calls should be resolved
+    /// first.
+    pub fn local_inner_macros(&self) -> Option<CrateId> {
+        if self.local_inner {
+            self.def_crate
+        } else {
+            None
+        }
     }
 
     // FIXME: this should just return name
@@ -158,6 +158,8 @@ pub struct MacroDefId {
     pub krate: Option<CrateId>,
     pub ast_id: Option<AstId<ast::MacroCall>>,
     pub kind: MacroDefKind,
+    /// Whether the macro is `#[macro_export(local_inner_macros)]`.
+    pub local_inner: bool,
 }
 
 impl MacroDefId {
@@ -52,6 +52,11 @@ impl Name {
         }
     }
 
+    /// Creates the name of a lifetime or of a label, like `'a`.
+    pub fn new_lifetime(lifetime: &ra_syntax::SyntaxToken) -> Name {
+        Name::new_text(lifetime.text().clone())
+    }
+
     pub fn missing() -> Name {
         Name::new_text("[missing name]".into())
     }
@@ -150,6 +155,8 @@ pub mod known {
         boxed,
         // Components of known path (type name)
         IntoIterator,
+        IntoIter,
+        Iterator,
         Item,
         Try,
         Ok,
@@ -177,6 +184,8 @@ pub mod known {
         format_args_nl,
         env,
         option_env,
+        include,
+        concat,
         // Builtin derives
         Copy,
         Clone,
@@ -19,16 +19,35 @@ use crate::{
 
 const AUTODEREF_RECURSION_LIMIT: usize = 10;
 
+/// Returns `ty` followed by the types it successively derefs to.
+///
+/// The chain ends as soon as a type can't be derefed further, derefs to a type
+/// we've already seen (e.g. `impl Deref for S { type Target = S; }`), or is
+/// unknown or an unresolved variable, which would only ever deref to fresh
+/// variables. The types produced before that point are still returned, so
+/// callers like method resolution can find candidates on them.
 pub fn autoderef<'a>(
     db: &'a impl HirDatabase,
     krate: Option<CrateId>,
     ty: InEnvironment<Canonical<Ty>>,
 ) -> impl Iterator<Item = Canonical<Ty>> + 'a {
     let InEnvironment { value: ty, environment } = ty;
+    let mut seen: Vec<Ty> = Vec::new();
     successors(Some(ty), move |ty| {
+        match ty.value {
+            Ty::Bound(_) | Ty::Unknown => return None,
+            _ => (),
+        }
         deref(db, krate?, InEnvironment { value: ty, environment: environment.clone() })
     })
     .take(AUTODEREF_RECURSION_LIMIT)
+    .take_while(move |ty| {
+        if seen.contains(&ty.value) {
+            return false;
+        }
+        seen.push(ty.value.clone());
+        true
+    })
 }
 
 pub(crate) fn deref(
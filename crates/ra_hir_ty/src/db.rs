@@ -3,16 +3,16 @@
 use std::sync::Arc;
 
 use hir_def::{
-    db::DefDatabase, DefWithBodyId, GenericDefId, ImplId, LocalStructFieldId, TraitId, TypeParamId,
-    VariantId,
+    db::DefDatabase, AssocItemId, DefWithBodyId, GenericDefId, ImplId, LocalStructFieldId,
+    ModuleId, TraitId, TypeParamId, VariantId,
 };
 use ra_arena::map::ArenaMap;
-use ra_db::{impl_intern_key, salsa, CrateId};
+use ra_db::{salsa, CrateId};
 use ra_prof::profile;
 
 use crate::{
     method_resolution::CrateImplBlocks,
-    traits::{chalk, AssocTyValue, Impl},
+    traits::{chalk, AssocTyValue, Impl, Placeholder},
     Binders, CallableDef, GenericPredicate, InferenceResult, PolyFnSig, Substs, TraitRef, Ty,
     TyDefId, TypeCtor, ValueTyDefId,
 };
@@ -59,9 +59,15 @@ pub trait HirDatabase: DefDatabase {
     #[salsa::invoke(crate::lower::generic_defaults_query)]
     fn generic_defaults(&self, def: GenericDefId) -> Substs;
 
+    #[salsa::invoke(crate::method_resolution::CrateImplBlocks::impls_in_module_query)]
+    fn impls_in_module(&self, module: ModuleId) -> Arc<CrateImplBlocks>;
+
     #[salsa::invoke(crate::method_resolution::CrateImplBlocks::impls_in_crate_query)]
     fn impls_in_crate(&self, krate: CrateId) -> Arc<CrateImplBlocks>;
 
+    #[salsa::invoke(crate::method_resolution::CrateImplBlocks::duplicate_inherent_items_query)]
+    fn duplicate_inherent_items(&self, krate: CrateId) -> Arc<[(AssocItemId, AssocItemId)]>;
+
     #[salsa::invoke(crate::traits::impls_for_trait_query)]
     fn impls_for_trait(&self, krate: CrateId, trait_: TraitId) -> Arc<[ImplId]>;
 
@@ -73,11 +79,26 @@ pub trait HirDatabase: DefDatabase {
     #[salsa::invoke(crate::traits::trait_solver_query)]
     fn trait_solver(&self, krate: CrateId) -> crate::traits::TraitSolver;
 
+    /// A hash of everything the impls of `trait_` depend on, and the same for
+    /// all traits mentioned in their where clauses. This changes much less
+    /// often than the trait solver is thrown away, so it is used to decide
+    /// whether a goal which couldn't be solved before still can't be solved.
+    /// `None` if the impls can't be fingerprinted, e.g. because there are
+    /// built-in impls.
+    #[salsa::invoke(crate::traits::trait_impls_fingerprint_query)]
+    fn trait_impls_fingerprint(&self, krate: CrateId, trait_: TraitId) -> Option<u64>;
+
+    /// The goals which couldn't be solved, with the fingerprint of the impls
+    /// they were solved against. This query has no inputs, so the cache is
+    /// kept across revisions.
+    #[salsa::invoke(crate::traits::negative_trait_solve_cache_query)]
+    fn negative_trait_solve_cache(&self, krate: CrateId) -> crate::traits::NegativeTraitSolveCache;
+
     // Interned IDs for Chalk integration
     #[salsa::interned]
     fn intern_type_ctor(&self, type_ctor: TypeCtor) -> crate::TypeCtorId;
     #[salsa::interned]
-    fn intern_type_param_id(&self, param_id: TypeParamId) -> GlobalTypeParamId;
+    fn intern_placeholder(&self, placeholder: Placeholder) -> crate::traits::PlaceholderId;
     #[salsa::interned]
     fn intern_chalk_impl(&self, impl_: Impl) -> crate::traits::GlobalImplId;
     #[salsa::interned]
@@ -119,7 +140,3 @@ fn infer(db: &impl HirDatabase, def: DefWithBodyId) -> Arc<InferenceResult> {
 fn hir_database_is_object_safe() {
     fn _assert_object_safe(_: &dyn HirDatabase) {}
 }
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct GlobalTypeParamId(salsa::InternId);
-impl_intern_key!(GlobalTypeParamId);
@@ -3,7 +3,7 @@
 use std::any::Any;
 
 use hir_expand::{db::AstDatabase, name::Name, HirFileId, InFile};
-use ra_syntax::{ast, AstNode, AstPtr, SyntaxNodePtr};
+use ra_syntax::{ast, AstNode, AstPtr, SmolStr, SyntaxNodePtr};
 
 pub use hir_def::diagnostics::UnresolvedModule;
 pub use hir_expand::diagnostics::{AstDiagnostic, Diagnostic, DiagnosticSink};
@@ -19,6 +19,10 @@ impl Diagnostic for NoSuchField {
         "no such field".to_string()
     }
 
+    fn code(&self) -> &'static str {
+        "no-such-field"
+    }
+
     fn source(&self) -> InFile<SyntaxNodePtr> {
         InFile { file_id: self.file, value: self.field.into() }
     }
@@ -28,6 +32,161 @@ impl Diagnostic for NoSuchField {
     }
 }
 
+#[derive(Debug)]
+pub struct MethodTraitNotInScope {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub method: Name,
+    pub self_ty: String,
+    pub trait_: Name,
+}
+
+impl Diagnostic for MethodTraitNotInScope {
+    fn message(&self) -> String {
+        format!(
+            "method `{}` exists on `{}` but trait `{}` is not in scope",
+            self.method, self.self_ty, self.trait_
+        )
+    }
+
+    fn code(&self) -> &'static str {
+        "method-trait-not-in-scope"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.into() }
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct UnresolvedMethodCall {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub method: Name,
+    pub receiver_ty: String,
+    pub suggestion: Option<UnresolvedMethodSuggestion>,
+}
+
+/// Why a method call probably doesn't resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnresolvedMethodSuggestion {
+    /// The receiver has a field of a callable type named like the method,
+    /// which can be called as `(receiver.field)()`.
+    CallableField,
+    /// The method takes `&mut self`, but it was found behind the shared
+    /// reference the receiver is.
+    MutableReceiver,
+}
+
+pub(crate) fn unresolved_method_call_message(
+    method: &Name,
+    receiver_ty: &str,
+    suggestion: Option<UnresolvedMethodSuggestion>,
+) -> String {
+    match suggestion {
+        None => format!("no method `{}` found for `{}`", method, receiver_ty),
+        Some(UnresolvedMethodSuggestion::CallableField) => format!(
+            "no method `{}` found for `{}`, but it has a callable field with that name",
+            method, receiver_ty
+        ),
+        Some(UnresolvedMethodSuggestion::MutableReceiver) => format!(
+            "method `{}` takes `&mut self`, but the receiver `{}` is a shared reference; \
+             consider making the binding a mutable reference",
+            method, receiver_ty
+        ),
+    }
+}
+
+impl Diagnostic for UnresolvedMethodCall {
+    fn message(&self) -> String {
+        unresolved_method_call_message(&self.method, &self.receiver_ty, self.suggestion)
+    }
+
+    fn code(&self) -> &'static str {
+        "unresolved-method-call"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.into() }
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for UnresolvedMethodCall {
+    type AST = ast::MethodCallExpr;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        let node = self.source().value.to_node(&root);
+        ast::MethodCallExpr::cast(node).unwrap()
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidCast {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub from: String,
+    pub to: String,
+}
+
+impl Diagnostic for InvalidCast {
+    fn message(&self) -> String {
+        format!("casting `{}` as `{}` is invalid", self.from, self.to)
+    }
+
+    fn code(&self) -> &'static str {
+        "invalid-cast"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.into() }
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+/// A comparison or compound assignment whose operator trait isn't implemented
+/// for the left operand.
+#[derive(Debug)]
+pub struct InvalidBinaryOp {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub op: &'static str,
+    pub lhs_ty: String,
+}
+
+impl Diagnostic for InvalidBinaryOp {
+    fn message(&self) -> String {
+        invalid_binary_op_message(self.op, &self.lhs_ty)
+    }
+
+    fn code(&self) -> &'static str {
+        "invalid-binary-op"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.expr.into() }
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+pub(crate) fn invalid_binary_op_message(op: &str, lhs_ty: &str) -> String {
+    format!("binary operation `{}` cannot be applied to type `{}`", op, lhs_ty)
+}
+
 #[derive(Debug)]
 pub struct MissingFields {
     pub file: HirFileId,
@@ -44,6 +203,10 @@ impl Diagnostic for MissingFields {
         }
         message
     }
+    fn code(&self) -> &'static str {
+        "missing-fields"
+    }
+
     fn source(&self) -> InFile<SyntaxNodePtr> {
         InFile { file_id: self.file, value: self.field_list.into() }
     }
@@ -62,6 +225,50 @@ impl AstDiagnostic for MissingFields {
     }
 }
 
+#[derive(Debug)]
+pub struct UnionLiteralFieldCount {
+    pub file: HirFileId,
+    pub field_list: AstPtr<ast::RecordFieldList>,
+}
+
+impl Diagnostic for UnionLiteralFieldCount {
+    fn message(&self) -> String {
+        "union expressions should have exactly one field".to_string()
+    }
+    fn code(&self) -> &'static str {
+        "union-literal-field-count"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.field_list.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct UnionPatternFieldCount {
+    pub file: HirFileId,
+    pub field_list: AstPtr<ast::RecordFieldPatList>,
+}
+
+impl Diagnostic for UnionPatternFieldCount {
+    fn message(&self) -> String {
+        "union patterns should have exactly one field".to_string()
+    }
+    fn code(&self) -> &'static str {
+        "union-pattern-field-count"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.field_list.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
 #[derive(Debug)]
 pub struct MissingOkInTailExpr {
     pub file: HirFileId,
@@ -72,6 +279,10 @@ impl Diagnostic for MissingOkInTailExpr {
     fn message(&self) -> String {
         "wrap return expression in Ok".to_string()
     }
+    fn code(&self) -> &'static str {
+        "missing-ok-in-tail-expr"
+    }
+
     fn source(&self) -> InFile<SyntaxNodePtr> {
         InFile { file_id: self.file, value: self.expr.into() }
     }
@@ -89,3 +300,209 @@ impl AstDiagnostic for MissingOkInTailExpr {
         ast::Expr::cast(node).unwrap()
     }
 }
+
+#[derive(Debug)]
+pub struct MissingMatchArms {
+    pub file: HirFileId,
+    pub match_expr: AstPtr<ast::Expr>,
+    pub arms: AstPtr<ast::MatchArmList>,
+    pub missed_patterns: Vec<String>,
+}
+
+impl Diagnostic for MissingMatchArms {
+    fn message(&self) -> String {
+        use std::fmt::Write;
+        let mut message = String::from("Missing match arms:\n");
+        for pat in &self.missed_patterns {
+            writeln!(message, "- {}", pat).unwrap();
+        }
+        message
+    }
+    fn code(&self) -> &'static str {
+        "missing-match-arms"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.match_expr.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for MissingMatchArms {
+    type AST = ast::MatchArmList;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        self.arms.to_node(&root)
+    }
+}
+
+#[derive(Debug)]
+pub struct DeprecatedItem {
+    pub file: HirFileId,
+    pub name_ref: AstPtr<ast::NameRef>,
+    pub name: Name,
+    pub note: Option<SmolStr>,
+}
+
+impl Diagnostic for DeprecatedItem {
+    fn message(&self) -> String {
+        match &self.note {
+            Some(note) => format!("use of deprecated item `{}`: {}", self.name, note),
+            None => format!("use of deprecated item `{}`", self.name),
+        }
+    }
+    fn code(&self) -> &'static str {
+        "deprecated-item"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.name_ref.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct UnusedParam {
+    pub file: HirFileId,
+    pub name: AstPtr<ast::Name>,
+    pub param_name: Name,
+}
+
+impl Diagnostic for UnusedParam {
+    fn message(&self) -> String {
+        format!("unused parameter: `{}`", self.param_name)
+    }
+    fn code(&self) -> &'static str {
+        "unused-param"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.name.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct UnusedVariable {
+    pub file: HirFileId,
+    pub name: AstPtr<ast::Name>,
+    pub var_name: Name,
+    /// Whether the variable is assigned to after its declaration.
+    pub assigned: bool,
+}
+
+impl Diagnostic for UnusedVariable {
+    fn message(&self) -> String {
+        if self.assigned {
+            format!("variable `{}` is assigned to, but never used", self.var_name)
+        } else {
+            format!("unused variable: `{}`", self.var_name)
+        }
+    }
+    fn code(&self) -> &'static str {
+        "unused-variable"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.name.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for UnusedVariable {
+    type AST = ast::Name;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        self.name.to_node(&root)
+    }
+}
+
+#[derive(Debug)]
+pub struct UnusedMut {
+    pub file: HirFileId,
+    pub bind_pat: AstPtr<ast::BindPat>,
+    pub var_name: Name,
+}
+
+impl Diagnostic for UnusedMut {
+    fn message(&self) -> String {
+        format!("variable does not need to be mutable: `{}`", self.var_name)
+    }
+    fn code(&self) -> &'static str {
+        "unused-mut"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.bind_pat.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for UnusedMut {
+    type AST = ast::BindPat;
+
+    fn ast(&self, db: &impl AstDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file).unwrap();
+        self.bind_pat.to_node(&root)
+    }
+}
+
+#[derive(Debug)]
+pub struct MissingEnvVar {
+    pub file: HirFileId,
+    pub macro_call: AstPtr<ast::MacroCall>,
+    pub name: SmolStr,
+}
+
+impl Diagnostic for MissingEnvVar {
+    fn message(&self) -> String {
+        format!("environment variable `{}` not defined", self.name)
+    }
+    fn code(&self) -> &'static str {
+        "missing-env-var"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.macro_call.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct DuplicateInherentItem {
+    pub file: HirFileId,
+    pub name: AstPtr<ast::Name>,
+    pub item_name: Name,
+    /// The name of the first definition of the item.
+    pub first: InFile<AstPtr<ast::Name>>,
+}
+
+impl Diagnostic for DuplicateInherentItem {
+    fn message(&self) -> String {
+        format!("duplicate definitions with name `{}`", self.item_name)
+    }
+    fn code(&self) -> &'static str {
+        "duplicate-inherent-item"
+    }
+
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.name.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
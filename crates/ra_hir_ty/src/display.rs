@@ -6,7 +6,10 @@ use crate::{
     db::HirDatabase, utils::generics, ApplicationTy, CallableDef, FnSig, GenericPredicate,
     Obligation, ProjectionTy, Substs, TraitRef, Ty, TypeCtor,
 };
-use hir_def::{generics::TypeParamProvenance, AdtId, AssocContainerId, Lookup};
+use hir_def::{
+    find_path, generics::TypeParamProvenance, item_scope::ItemInNs, AdtId, AssocContainerId,
+    Lookup, ModuleId,
+};
 use hir_expand::name::Name;
 
 pub struct HirFormatter<'a, 'b, DB> {
@@ -16,6 +19,8 @@ pub struct HirFormatter<'a, 'b, DB> {
     curr_size: usize,
     pub(crate) max_size: Option<usize>,
     omit_verbose_types: bool,
+    display_target: DisplayTarget,
+    closure_style: ClosureStyle,
 }
 
 pub trait HirDisplay {
@@ -25,9 +30,15 @@ pub trait HirDisplay {
     where
         Self: Sized,
     {
-        HirDisplayWrapper(db, self, None, false)
+        HirDisplayWrapper::new(db, self, None, false, DisplayTarget::Diagnostics)
     }
 
+    /// Displays `self` for the user, e.g. in hovers, inlay hints and completion
+    /// details: verbose parts like default type arguments are left out, unknown
+    /// types are shown as `_`, and everything past `max_size` characters is
+    /// elided as `…`.
+    /// The outermost type constructor is always kept, and names are never
+    /// split.
     fn display_truncated<'a, DB>(
         &'a self,
         db: &'a DB,
@@ -36,7 +47,22 @@ pub trait HirDisplay {
     where
         Self: Sized,
     {
-        HirDisplayWrapper(db, self, max_size, true)
+        HirDisplayWrapper::new(db, self, max_size, true, DisplayTarget::Diagnostics)
+    }
+
+    /// Returns a string displaying `self` as valid source code in the context
+    /// of `module_id`, or `None` if it can't be written down there (for
+    /// example, because it contains a closure type).
+    fn display_source_code<DB>(&self, db: &DB, module_id: ModuleId) -> Option<String>
+    where
+        Self: Sized,
+        DB: HirDatabase,
+    {
+        let wrapper =
+            HirDisplayWrapper::new(db, self, None, false, DisplayTarget::SourceCode { module_id });
+        let mut res = String::new();
+        fmt::write(&mut res, format_args!("{}", wrapper)).ok()?;
+        Some(res)
     }
 }
 
@@ -54,6 +80,10 @@ where
             if !first {
                 write!(self, "{}", sep)?;
             }
+            if self.should_truncate() {
+                // Elide all the remaining elements at once.
+                return write!(self, "{}", TYPE_HINT_TRUNCATION);
+            }
             first = false;
             e.hir_fmt(self)?;
         }
@@ -71,20 +101,79 @@ where
         self.fmt.write_str(&self.buf)
     }
 
+    /// Whether the next type should be elided. Nothing is elided before the
+    /// first type constructor is written.
     pub fn should_truncate(&self) -> bool {
-        if let Some(max_size) = self.max_size {
-            self.curr_size >= max_size
-        } else {
-            false
+        match self.max_size {
+            Some(max_size) => self.curr_size > 0 && self.curr_size >= max_size,
+            None => false,
         }
     }
 
     pub fn omit_verbose_types(&self) -> bool {
         self.omit_verbose_types
     }
+
+    fn is_source_code(&self) -> bool {
+        match self.display_target {
+            DisplayTarget::SourceCode { .. } => true,
+            DisplayTarget::Diagnostics => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DisplayTarget {
+    /// Display types for inlays, hovers, completion details and so on, where
+    /// `{unknown}` and unqualified names are fine.
+    Diagnostics,
+    /// Display types to be inserted into source code, so paths need to resolve
+    /// from `module_id` and unnameable types are an error.
+    SourceCode { module_id: ModuleId },
+}
+
+/// How closure types are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClosureStyle {
+    /// `|u64, u64| -> i32`, which is the most compact form.
+    Pipes,
+    /// `impl Fn(u64, u64) -> i32`, which can be written down in argument and
+    /// return position.
+    ImplFn,
+}
+
+pub struct HirDisplayWrapper<'a, DB, T> {
+    db: &'a DB,
+    t: &'a T,
+    max_size: Option<usize>,
+    omit_verbose_types: bool,
+    display_target: DisplayTarget,
+    closure_style: ClosureStyle,
 }
 
-pub struct HirDisplayWrapper<'a, DB, T>(&'a DB, &'a T, Option<usize>, bool);
+impl<'a, DB, T> HirDisplayWrapper<'a, DB, T> {
+    fn new(
+        db: &'a DB,
+        t: &'a T,
+        max_size: Option<usize>,
+        omit_verbose_types: bool,
+        display_target: DisplayTarget,
+    ) -> Self {
+        HirDisplayWrapper {
+            db,
+            t,
+            max_size,
+            omit_verbose_types,
+            display_target,
+            closure_style: ClosureStyle::Pipes,
+        }
+    }
+
+    pub fn with_closure_style(mut self, closure_style: ClosureStyle) -> Self {
+        self.closure_style = closure_style;
+        self
+    }
+}
 
 impl<'a, DB, T> fmt::Display for HirDisplayWrapper<'a, DB, T>
 where
@@ -92,13 +181,15 @@ where
     T: HirDisplay,
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.1.hir_fmt(&mut HirFormatter {
-            db: self.0,
+        self.t.hir_fmt(&mut HirFormatter {
+            db: self.db,
             fmt: f,
             buf: String::with_capacity(20),
             curr_size: 0,
-            max_size: self.2,
-            omit_verbose_types: self.3,
+            max_size: self.max_size,
+            omit_verbose_types: self.omit_verbose_types,
+            display_target: self.display_target,
+            closure_style: self.closure_style,
         })
     }
 }
@@ -125,30 +216,46 @@ impl HirDisplay for ApplicationTy {
             TypeCtor::Str => write!(f, "str")?,
             TypeCtor::Slice => {
                 let t = self.parameters.as_single();
-                write!(f, "[{}]", t.display(f.db))?;
+                write!(f, "[")?;
+                t.hir_fmt(f)?;
+                write!(f, "]")?;
             }
-            TypeCtor::Array => {
+            TypeCtor::Array { len } => {
                 let t = self.parameters.as_single();
-                write!(f, "[{}; _]", t.display(f.db))?;
+                // We don't know the length, and `_` isn't allowed there.
+                if len.is_none() && f.is_source_code() {
+                    return Err(fmt::Error);
+                }
+                write!(f, "[")?;
+                t.hir_fmt(f)?;
+                match len {
+                    Some(len) => write!(f, "; {}]", len)?,
+                    None => write!(f, "; _]")?,
+                }
             }
             TypeCtor::RawPtr(m) => {
                 let t = self.parameters.as_single();
-                write!(f, "*{}{}", m.as_keyword_for_ptr(), t.display(f.db))?;
+                write!(f, "*{}", m.as_keyword_for_ptr())?;
+                t.hir_fmt(f)?;
             }
             TypeCtor::Ref(m) => {
                 let t = self.parameters.as_single();
-                let ty_display = if f.omit_verbose_types() {
-                    t.display_truncated(f.db, f.max_size)
+                write!(f, "&{}", m.as_keyword_for_ref())?;
+                if f.omit_verbose_types() {
+                    let pointee =
+                        t.display_truncated(f.db, f.max_size).with_closure_style(f.closure_style);
+                    write!(f, "{}", pointee)?;
                 } else {
-                    t.display(f.db)
-                };
-                write!(f, "&{}{}", m.as_keyword_for_ref(), ty_display)?;
+                    t.hir_fmt(f)?;
+                }
             }
             TypeCtor::Never => write!(f, "!")?,
             TypeCtor::Tuple { .. } => {
                 let ts = &self.parameters;
                 if ts.len() == 1 {
-                    write!(f, "({},)", ts[0].display(f.db))?;
+                    write!(f, "(")?;
+                    ts[0].hir_fmt(f)?;
+                    write!(f, ",)")?;
                 } else {
                     write!(f, "(")?;
                     f.write_joined(&*ts.0, ", ")?;
@@ -159,9 +266,13 @@ impl HirDisplay for ApplicationTy {
                 let sig = FnSig::from_fn_ptr_substs(&self.parameters);
                 write!(f, "fn(")?;
                 f.write_joined(sig.params(), ", ")?;
-                write!(f, ") -> {}", sig.ret().display(f.db))?;
+                write!(f, ") -> ")?;
+                sig.ret().hir_fmt(f)?;
             }
             TypeCtor::FnDef(def) => {
+                if f.is_source_code() {
+                    return Err(fmt::Error);
+                }
                 let sig = f.db.callable_item_signature(def).subst(&self.parameters);
                 let name = match def {
                     CallableDef::FunctionId(ff) => f.db.function_data(ff).name.clone(),
@@ -191,15 +302,25 @@ impl HirDisplay for ApplicationTy {
                 }
                 write!(f, "(")?;
                 f.write_joined(sig.params(), ", ")?;
-                write!(f, ") -> {}", sig.ret().display(f.db))?;
+                write!(f, ") -> ")?;
+                sig.ret().hir_fmt(f)?;
             }
             TypeCtor::Adt(def_id) => {
-                let name = match def_id {
-                    AdtId::StructId(it) => f.db.struct_data(it).name.clone(),
-                    AdtId::UnionId(it) => f.db.union_data(it).name.clone(),
-                    AdtId::EnumId(it) => f.db.enum_data(it).name.clone(),
-                };
-                write!(f, "{}", name)?;
+                match f.display_target {
+                    DisplayTarget::Diagnostics => {
+                        let name = match def_id {
+                            AdtId::StructId(it) => f.db.struct_data(it).name.clone(),
+                            AdtId::UnionId(it) => f.db.union_data(it).name.clone(),
+                            AdtId::EnumId(it) => f.db.enum_data(it).name.clone(),
+                        };
+                        write!(f, "{}", name)?;
+                    }
+                    DisplayTarget::SourceCode { module_id } => {
+                        let item = ItemInNs::Types(def_id.into());
+                        let path = find_path::find_path(f.db, item, module_id).ok_or(fmt::Error)?;
+                        write!(f, "{}", path)?;
+                    }
+                }
                 if self.parameters.len() > 0 {
                     write!(f, "<")?;
 
@@ -238,6 +359,9 @@ impl HirDisplay for ApplicationTy {
                 }
             }
             TypeCtor::AssociatedType(type_alias) => {
+                if f.is_source_code() {
+                    return Err(fmt::Error);
+                }
                 let trait_ = match type_alias.lookup(f.db).container {
                     AssocContainerId::TraitId(it) => it,
                     _ => panic!("not an associated type"),
@@ -252,19 +376,33 @@ impl HirDisplay for ApplicationTy {
                 }
             }
             TypeCtor::Closure { .. } => {
+                if f.is_source_code() {
+                    return Err(fmt::Error);
+                }
                 let sig = self.parameters[0]
                     .callable_sig(f.db)
                     .expect("first closure parameter should contain signature");
-                let return_type_hint = sig.ret().display(f.db);
-                if sig.params().is_empty() {
-                    write!(f, "|| -> {}", return_type_hint)?;
-                } else if f.omit_verbose_types() {
-                    write!(f, "|{}| -> {}", TYPE_HINT_TRUNCATION, return_type_hint)?;
-                } else {
-                    write!(f, "|")?;
-                    f.write_joined(sig.params(), ", ")?;
-                    write!(f, "| -> {}", return_type_hint)?;
-                };
+                match f.closure_style {
+                    ClosureStyle::Pipes => {
+                        write!(f, "|")?;
+                        if f.omit_verbose_types() && !sig.params().is_empty() {
+                            write!(f, "{}", TYPE_HINT_TRUNCATION)?;
+                        } else {
+                            f.write_joined(sig.params(), ", ")?;
+                        }
+                        write!(f, "| -> ")?;
+                        sig.ret().hir_fmt(f)?;
+                    }
+                    ClosureStyle::ImplFn => {
+                        write!(f, "impl Fn(")?;
+                        f.write_joined(sig.params(), ", ")?;
+                        write!(f, ")")?;
+                        if *sig.ret() != Ty::unit() {
+                            write!(f, " -> ")?;
+                            sig.ret().hir_fmt(f)?;
+                        }
+                    }
+                }
             }
         }
         Ok(())
@@ -276,6 +414,10 @@ impl HirDisplay for ProjectionTy {
         if f.should_truncate() {
             return write!(f, "{}", TYPE_HINT_TRUNCATION);
         }
+        // FIXME: qualify the trait path instead of giving up
+        if f.is_source_code() {
+            return Err(fmt::Error);
+        }
 
         let trait_name = f.db.trait_data(self.trait_(f.db)).name.clone();
         write!(f, "<{} as {}", self.parameters[0].display(f.db), trait_name,)?;
@@ -306,6 +448,9 @@ impl HirDisplay for Ty {
                         write!(f, "{}", param_data.name.clone().unwrap_or_else(Name::missing))?
                     }
                     TypeParamProvenance::ArgumentImplTrait => {
+                        if f.is_source_code() {
+                            return Err(fmt::Error);
+                        }
                         write!(f, "impl ")?;
                         let bounds = f.db.generic_predicates_for_param(*id);
                         let substs = Substs::type_params_for_generics(&generics);
@@ -316,7 +461,13 @@ impl HirDisplay for Ty {
                     }
                 }
             }
-            Ty::Bound(idx) => write!(f, "?{}", idx)?,
+            Ty::Bound(_) | Ty::Dyn(_) | Ty::Opaque(_) if f.is_source_code() => {
+                // FIXME: `dyn Trait` is nameable, if we qualify the trait paths
+                return Err(fmt::Error);
+            }
+            // Bound variables which get here aren't bound in the type itself,
+            // e.g. those of a `Canonical`; they don't mean anything to the user
+            Ty::Bound(_) => write!(f, "_")?,
             Ty::Dyn(predicates) | Ty::Opaque(predicates) => {
                 match self {
                     Ty::Dyn(_) => write!(f, "dyn ")?,
@@ -325,6 +476,7 @@ impl HirDisplay for Ty {
                 };
                 write_bounds_like_dyn_trait(&predicates, f)?;
             }
+            Ty::Unknown if f.is_source_code() || f.omit_verbose_types() => write!(f, "_")?,
             Ty::Unknown => write!(f, "{{unknown}}")?,
             Ty::Infer(..) => write!(f, "_")?,
         }
@@ -4,17 +4,27 @@ use std::sync::Arc;
 
 use hir_def::{
     path::{path, Path},
-    resolver::HasResolver,
-    AdtId, FunctionId,
+    resolver::{resolver_for_expr, HasResolver, ValueNs},
+    src::HasSource,
+    type_ref::{Mutability, TypeRef},
+    AdtId, AssocContainerId, AssocItemId, AttrDefId, FunctionId, LocalStructFieldId, Lookup,
 };
-use hir_expand::{diagnostics::DiagnosticSink, name::Name};
-use ra_syntax::ast;
-use ra_syntax::AstPtr;
-use rustc_hash::FxHashSet;
+use hir_expand::{
+    builtin_macro::missing_env_var,
+    diagnostics::DiagnosticSink,
+    name::{name, AsName, Name},
+};
+use ra_syntax::ast::{self, AstNode, NameOwner};
+use ra_syntax::{AstPtr, SyntaxKind};
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     db::HirDatabase,
-    diagnostics::{MissingFields, MissingOkInTailExpr},
+    diagnostics::{
+        DeprecatedItem, MissingEnvVar, MissingFields, MissingMatchArms, MissingOkInTailExpr,
+        UnionLiteralFieldCount, UnionPatternFieldCount, UnusedMut, UnusedParam, UnusedVariable,
+    },
+    match_check::missing_patterns,
     utils::variant_data,
     ApplicationTy, InferenceResult, Ty, TypeCtor,
 };
@@ -49,18 +59,342 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
     pub fn validate_body(&mut self, db: &impl HirDatabase) {
         let body = db.body(self.func.into());
 
-        for e in body.exprs.iter() {
-            if let (id, Expr::RecordLit { path, fields, spread }) = e {
-                self.validate_record_literal(id, path, fields, *spread, db);
+        self.validate_unused_params(&body, db);
+        self.validate_unused_locals(&body, db);
+        self.validate_deprecated_uses(&body, db);
+        self.validate_env_macros(db);
+
+        for (id, expr) in body.exprs.iter() {
+            match expr {
+                Expr::RecordLit { path, fields, spread } => {
+                    self.validate_record_literal(id, path, fields, *spread, db);
+                }
+                Expr::Match { expr, arms } => {
+                    self.validate_match(id, *expr, arms, &body, db);
+                }
+                _ => {}
+            }
+        }
+        for (id, pat) in body.pats.iter() {
+            if let Pat::Record { args, .. } = pat {
+                self.validate_record_pat(id, args, db);
             }
         }
 
         let body_expr = &body[body.body_expr];
-        if let Expr::Block { statements: _, tail: Some(t) } = body_expr {
+        if let Expr::Block { tail: Some(t), .. } = body_expr {
             self.validate_results_in_tail_expr(body.body_expr, *t, db);
         }
     }
 
+    fn validate_unused_params(&mut self, body: &Body, db: &impl HirDatabase) {
+        let loc = self.func.lookup(db);
+        // The signature of a trait impl method is dictated by the trait.
+        if let AssocContainerId::ImplId(impl_id) = loc.container {
+            if db.impl_data(impl_id).target_trait.is_some() {
+                return;
+            }
+        }
+        // FIXME: uses of parameters inside of macro calls aren't visible here,
+        // so we bail out rather than report a parameter used in `println!`.
+        if self.has_macro_calls(db) {
+            return;
+        }
+
+        let used: FxHashSet<PatId> = self.local_paths(body, db).values().copied().collect();
+
+        let mut bindings = Vec::new();
+        for &param in body.params.iter() {
+            collect_bindings(body, param, &mut bindings);
+        }
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        for (pat, param_name) in bindings {
+            if used.contains(&pat)
+                || param_name == name![self]
+                || param_name.to_string().starts_with('_')
+            {
+                continue;
+            }
+            if let Some(source_ptr) = source_map.pat_syntax(pat) {
+                if let Some(ptr) = source_ptr.value.left() {
+                    let root = source_ptr.file_syntax(db);
+                    if let ast::Pat::BindPat(bind_pat) = ptr.to_node(&root) {
+                        if let Some(name) = bind_pat.name() {
+                            self.sink.push(UnusedParam {
+                                file: source_ptr.file_id,
+                                name: AstPtr::new(&name),
+                                param_name,
+                            })
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_unused_locals(&mut self, body: &Body, db: &impl HirDatabase) {
+        // FIXME: as for parameters, uses of locals inside of macro calls aren't
+        // visible here.
+        if self.has_macro_calls(db) {
+            return;
+        }
+
+        let local_paths = self.local_paths(body, db);
+        let place_local = |mut expr: ExprId| loop {
+            match &body[expr] {
+                Expr::Field { expr: inner, .. }
+                | Expr::Index { base: inner, .. }
+                | Expr::UnaryOp { expr: inner, op: UnaryOp::Deref } => expr = *inner,
+                Expr::Path(_) => return local_paths.get(&expr).copied(),
+                _ => return None,
+            }
+        };
+
+        // Plain assignments write to a local without reading it. Anything that
+        // may need a mutable place marks its local as mutated: we'd rather miss
+        // an unneeded `mut` than suggest removing a needed one.
+        let mut assigned = FxHashSet::default();
+        let mut mutated = FxHashSet::default();
+        let mut roots = Vec::new();
+        roots.extend(body.params.iter().map(|&pat| (pat, true)));
+        for (id, expr) in body.exprs.iter() {
+            let mut_place = match expr {
+                Expr::BinaryOp { lhs, op: Some(BinaryOp::Assignment { op }), .. } => {
+                    if op.is_none() {
+                        assigned.insert(*lhs);
+                    }
+                    Some(*lhs)
+                }
+                Expr::Ref { expr, mutability: Mutability::Mut } => Some(*expr),
+                Expr::MethodCall { receiver, .. } => {
+                    let takes_mut_self = match self.infer.method_resolution(id) {
+                        Some(func) => match db.function_data(func).params.first() {
+                            Some(TypeRef::Reference(_, Mutability::Mut)) => true,
+                            _ => false,
+                        },
+                        None => true,
+                    };
+                    if takes_mut_self {
+                        Some(*receiver)
+                    } else {
+                        None
+                    }
+                }
+                // Calling a `FnMut` closure needs a mutable binding.
+                Expr::Call { callee, .. } => Some(*callee),
+                Expr::Block { statements, .. } => {
+                    for stmt in statements {
+                        if let Statement::Let { pat, initializer, .. } = stmt {
+                            roots.push((*pat, false));
+                            if let Some(initializer) = initializer {
+                                if binds_by_mut_ref(body, *pat) {
+                                    mutated.extend(place_local(*initializer));
+                                }
+                            }
+                        }
+                    }
+                    None
+                }
+                Expr::Match { expr, arms } => {
+                    roots.extend(arms.iter().map(|arm| (arm.pat, false)));
+                    if arms.iter().any(|arm| binds_by_mut_ref(body, arm.pat)) {
+                        Some(*expr)
+                    } else {
+                        None
+                    }
+                }
+                Expr::Let { pat, expr } => {
+                    roots.push((*pat, false));
+                    if binds_by_mut_ref(body, *pat) {
+                        Some(*expr)
+                    } else {
+                        None
+                    }
+                }
+                Expr::For { pat, .. } => {
+                    roots.push((*pat, false));
+                    None
+                }
+                Expr::Lambda { args, .. } => {
+                    roots.extend(args.iter().map(|&pat| (pat, false)));
+                    None
+                }
+                _ => None,
+            };
+            mutated.extend(mut_place.and_then(place_local));
+        }
+        let (written, read): (Vec<_>, Vec<_>) =
+            local_paths.iter().partition(|(expr, _)| assigned.contains(*expr));
+        let written: FxHashSet<PatId> = written.into_iter().map(|(_, pat)| *pat).collect();
+        let read: FxHashSet<PatId> = read.into_iter().map(|(_, pat)| *pat).collect();
+
+        // Report in source order.
+        roots.sort_by_key(|(pat, _)| *pat);
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        for (root, is_param) in roots {
+            let mut bindings = Vec::new();
+            collect_bindings(body, root, &mut bindings);
+            // Renaming one binding of a destructuring pattern whose other
+            // bindings are used is more noise than help.
+            let any_read = bindings.iter().any(|(pat, _)| read.contains(pat));
+            let mut seen = FxHashSet::default();
+            for (pat, var_name) in bindings.iter() {
+                if !seen.insert(var_name)
+                    || *var_name == name![self]
+                    || var_name.to_string().starts_with('_')
+                {
+                    continue;
+                }
+                let source_ptr = match source_map.pat_syntax(*pat) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                let bind_pat = match source_ptr.value.left() {
+                    Some(ptr) => match ptr.to_node(&source_ptr.file_syntax(db)) {
+                        ast::Pat::BindPat(it) => it,
+                        _ => continue,
+                    },
+                    None => continue,
+                };
+                let name = match bind_pat.name() {
+                    Some(it) => it,
+                    None => continue,
+                };
+
+                // The alternatives of an or-pattern all bind the same names.
+                let same_name = || bindings.iter().filter(|(_, it)| it == var_name);
+                let is_mutated = same_name().any(|(it, _)| mutated.contains(it));
+                if let Pat::Bind { mode: BindingAnnotation::Mutable, .. } = &body[*pat] {
+                    if !is_mutated {
+                        self.sink.push(UnusedMut {
+                            file: source_ptr.file_id,
+                            bind_pat: AstPtr::new(&bind_pat),
+                            var_name: var_name.clone(),
+                        })
+                    }
+                }
+                // Unused parameters are reported separately.
+                if !is_param && !any_read {
+                    self.sink.push(UnusedVariable {
+                        file: source_ptr.file_id,
+                        name: AstPtr::new(&name),
+                        var_name: var_name.clone(),
+                        assigned: same_name().any(|(it, _)| written.contains(it)),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Whether the body contains macro calls, whose expansions may use locals
+    /// of the function. Functions without a body count as having some.
+    fn has_macro_calls(&self, db: &impl HirDatabase) -> bool {
+        match self.func.lookup(db).source(db).value.body() {
+            Some(block) => {
+                block.syntax().descendants().any(|it| it.kind() == SyntaxKind::MACRO_CALL)
+            }
+            None => true,
+        }
+    }
+
+    /// Maps the path expressions of the body referring to a local binding to
+    /// that binding.
+    fn local_paths(&self, body: &Body, db: &impl HirDatabase) -> FxHashMap<ExprId, PatId> {
+        let mut res = FxHashMap::default();
+        for (id, expr) in body.exprs.iter() {
+            if let Expr::Path(path) = expr {
+                let resolver = resolver_for_expr(db, self.func.into(), id);
+                if let Some(ValueNs::LocalBinding(pat)) =
+                    resolver.resolve_path_in_value_ns_fully(db, path.mod_path())
+                {
+                    res.insert(id, pat);
+                }
+            }
+        }
+        res
+    }
+
+    fn validate_env_macros(&mut self, db: &impl HirDatabase) {
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        for (macro_call, file_id) in source_map.macro_expansions() {
+            if let Some(name) = missing_env_var(db, file_id) {
+                self.sink.push(MissingEnvVar {
+                    file: macro_call.file_id,
+                    macro_call: macro_call.value,
+                    name,
+                });
+            }
+        }
+    }
+
+    fn validate_deprecated_uses(&mut self, body: &Body, db: &impl HirDatabase) {
+        // Deprecated items may freely use each other.
+        if db.attrs(self.func.into()).is_deprecated() {
+            return;
+        }
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        for (id, expr) in body.exprs.iter() {
+            let def: AttrDefId = match expr {
+                Expr::Path(path) => match self.infer.assoc_resolutions_for_expr(id) {
+                    Some(AssocItemId::FunctionId(it)) => it.into(),
+                    Some(AssocItemId::ConstId(it)) => it.into(),
+                    Some(AssocItemId::TypeAliasId(_)) => continue,
+                    None => {
+                        let resolver = resolver_for_expr(db, self.func.into(), id);
+                        match resolver.resolve_path_in_value_ns_fully(db, path.mod_path()) {
+                            Some(ValueNs::FunctionId(it)) => it.into(),
+                            Some(ValueNs::ConstId(it)) => it.into(),
+                            Some(ValueNs::StaticId(it)) => it.into(),
+                            Some(ValueNs::StructId(it)) => it.into(),
+                            Some(ValueNs::EnumVariantId(it)) => it.into(),
+                            Some(ValueNs::LocalBinding(_)) | None => continue,
+                        }
+                    }
+                },
+                Expr::MethodCall { .. } => match self.infer.method_resolution(id) {
+                    Some(it) => it.into(),
+                    None => continue,
+                },
+                Expr::RecordLit { .. } => match self.infer.variant_resolution_for_expr(id) {
+                    Some(VariantId::StructId(it)) => it.into(),
+                    Some(VariantId::EnumVariantId(it)) => it.into(),
+                    Some(VariantId::UnionId(it)) => AdtId::UnionId(it).into(),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let attrs = db.attrs(def);
+            if !attrs.is_deprecated() {
+                continue;
+            }
+            let note = attrs.deprecation_note();
+
+            if let Some(source_ptr) = source_map.expr_syntax(id) {
+                if let Some(expr) = source_ptr.value.left() {
+                    let root = source_ptr.file_syntax(db);
+                    let name_ref = match expr.to_node(&root) {
+                        ast::Expr::PathExpr(it) => {
+                            it.path().and_then(|it| it.segment()?.name_ref())
+                        }
+                        ast::Expr::RecordLit(it) => {
+                            it.path().and_then(|it| it.segment()?.name_ref())
+                        }
+                        ast::Expr::MethodCallExpr(it) => it.name_ref(),
+                        _ => None,
+                    };
+                    if let Some(name_ref) = name_ref {
+                        self.sink.push(DeprecatedItem {
+                            file: source_ptr.file_id,
+                            name_ref: AstPtr::new(&name_ref),
+                            name: name_ref.as_name(),
+                            note,
+                        })
+                    }
+                }
+            }
+        }
+    }
+
     fn validate_record_literal(
         &mut self,
         id: ExprId,
@@ -73,28 +407,23 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
             return;
         };
         let variant_def: VariantId = match self.infer.variant_resolution_for_expr(id) {
-            Some(VariantId::UnionId(_)) | None => return,
+            Some(VariantId::UnionId(_)) => {
+                if fields.len() != 1 {
+                    self.report_union_literal(id, db);
+                }
+                return;
+            }
             Some(it) => it,
+            None => return,
         };
-        if let VariantId::UnionId(_) = variant_def {
-            return;
-        }
 
         let variant_data = variant_data(db, variant_def);
 
-        let lit_fields: FxHashSet<_> = fields.iter().map(|f| &f.name).collect();
-        let missed_fields: Vec<Name> = variant_data
-            .fields()
-            .iter()
-            .filter_map(|(_f, d)| {
-                let name = d.name.clone();
-                if lit_fields.contains(&name) {
-                    None
-                } else {
-                    Some(name)
-                }
-            })
-            .collect();
+        let missed_fields: Vec<Name> =
+            missing_fields(db, variant_def, fields.iter().map(|f| &f.name))
+                .into_iter()
+                .map(|id| variant_data.fields()[id].name.clone())
+                .collect();
         if missed_fields.is_empty() {
             return;
         }
@@ -116,6 +445,80 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
         }
     }
 
+    fn report_union_literal(&mut self, id: ExprId, db: &impl HirDatabase) {
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        if let Some(source_ptr) = source_map.expr_syntax(id) {
+            if let Some(expr) = source_ptr.value.left() {
+                let root = source_ptr.file_syntax(db);
+                if let ast::Expr::RecordLit(record_lit) = expr.to_node(&root) {
+                    if let Some(field_list) = record_lit.record_field_list() {
+                        self.sink.push(UnionLiteralFieldCount {
+                            file: source_ptr.file_id,
+                            field_list: AstPtr::new(&field_list),
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reading a union through a pattern only makes sense for a single field.
+    fn validate_record_pat(&mut self, id: PatId, args: &[RecordFieldPat], db: &impl HirDatabase) {
+        match self.infer.variant_resolution_for_pat(id) {
+            Some(VariantId::UnionId(_)) if args.len() != 1 => {}
+            _ => return,
+        }
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+        if let Some(source_ptr) = source_map.pat_syntax(id) {
+            if let Some(ptr) = source_ptr.value.left() {
+                let root = source_ptr.file_syntax(db);
+                if let ast::Pat::RecordPat(record_pat) = ptr.to_node(&root) {
+                    if let Some(field_list) = record_pat.record_field_pat_list() {
+                        self.sink.push(UnionPatternFieldCount {
+                            file: source_ptr.file_id,
+                            field_list: AstPtr::new(&field_list),
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    fn validate_match(
+        &mut self,
+        id: ExprId,
+        match_expr: ExprId,
+        arms: &[MatchArm],
+        body: &Body,
+        db: &impl HirDatabase,
+    ) {
+        let scrutinee_ty = &self.infer[match_expr];
+        if let Ty::Unknown = scrutinee_ty {
+            return;
+        }
+        let missed_patterns = missing_patterns(db, body, &self.infer, scrutinee_ty, arms);
+        if missed_patterns.is_empty() {
+            return;
+        }
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+
+        if let Some(source_ptr) = source_map.expr_syntax(id) {
+            if let Some(expr) = source_ptr.value.left() {
+                let root = source_ptr.file_syntax(db);
+                if let ast::Expr::MatchExpr(match_expr) = expr.to_node(&root) {
+                    if let Some(arms) = match_expr.match_arm_list() {
+                        self.sink.push(MissingMatchArms {
+                            file: source_ptr.file_id,
+                            match_expr: expr,
+                            arms: AstPtr::new(&arms),
+                            missed_patterns,
+                        })
+                    }
+                }
+            }
+        }
+    }
+
     fn validate_results_in_tail_expr(
         &mut self,
         body_id: ExprId,
@@ -153,3 +556,36 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
         }
     }
 }
+
+/// Returns the fields of `variant` which are not among the `present` ones, in
+/// declaration order.
+pub fn missing_fields<'a>(
+    db: &impl HirDatabase,
+    variant: VariantId,
+    present: impl IntoIterator<Item = &'a Name>,
+) -> Vec<LocalStructFieldId> {
+    let present: FxHashSet<&Name> = present.into_iter().collect();
+    variant_data(db, variant)
+        .fields()
+        .iter()
+        .filter(|(_, data)| !present.contains(&data.name))
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// Whether matching `pat` mutably borrows (a part of) the scrutinee.
+fn binds_by_mut_ref(body: &Body, pat: PatId) -> bool {
+    let mut res = false;
+    if let Pat::Bind { mode: BindingAnnotation::RefMut, .. } = &body[pat] {
+        res = true;
+    }
+    body[pat].walk_child_pats(|child| res |= binds_by_mut_ref(body, child));
+    res
+}
+
+fn collect_bindings(body: &Body, pat: PatId, acc: &mut Vec<(PatId, Name)>) {
+    if let Pat::Bind { name, .. } = &body[pat] {
+        acc.push((pat, name.clone()));
+    }
+    body[pat].walk_child_pats(|child| collect_bindings(body, child, acc));
+}
@@ -25,12 +25,15 @@ use hir_def::{
     data::{ConstData, FunctionData},
     expr::{BindingAnnotation, ExprId, PatId},
     lang_item::LangItemTarget,
-    path::{path, Path},
+    path::{path, ModPath, Path},
     resolver::{HasResolver, Resolver, TypeNs},
     type_ref::{Mutability, TypeRef},
     AdtId, AssocItemId, DefWithBodyId, FunctionId, StructFieldId, TypeAliasId, VariantId,
 };
-use hir_expand::{diagnostics::DiagnosticSink, name::name};
+use hir_expand::{
+    diagnostics::DiagnosticSink,
+    name::{name, Name},
+};
 use ra_arena::map::ArenaMap;
 use ra_prof::profile;
 use ra_syntax::SmolStr;
@@ -45,6 +48,7 @@ use crate::{
     db::HirDatabase, infer::diagnostics::InferenceDiagnostic, lower::ImplTraitLoweringMode,
 };
 
+pub use dump::dump_body;
 pub(crate) use unify::unify;
 
 macro_rules! ty_app {
@@ -61,6 +65,8 @@ mod path;
 mod expr;
 mod pat;
 mod coerce;
+mod cast;
+mod dump;
 
 /// The entry point of type inference.
 pub fn do_infer_query(db: &impl HirDatabase, def: DefWithBodyId) -> Arc<InferenceResult> {
@@ -206,6 +212,45 @@ struct InferenceContext<'a, D: HirDatabase> {
     /// closures, but currently this is the only field that will change there,
     /// so it doesn't make sense.
     return_ty: Ty,
+    /// The casts of the body, with the types they cast from and to. They are
+    /// checked at the end, when the types are known.
+    deferred_cast_checks: Vec<(ExprId, Ty, Ty)>,
+    /// The loops and labelled blocks enclosing the expression being inferred,
+    /// innermost last. `break` and `continue` look up their target here.
+    breakables: Vec<BreakableContext>,
+}
+
+#[derive(Clone, Debug)]
+struct BreakableContext {
+    kind: BreakableKind,
+    label: Option<Name>,
+    /// Whether a `break` targeting this context was seen.
+    may_break: bool,
+}
+
+#[derive(Clone, Debug)]
+enum BreakableKind {
+    Loop,
+    /// A labelled block, whose type is unified with the values it is broken with.
+    Block {
+        break_ty: Ty,
+    },
+}
+
+/// Finds the context a `break` or `continue` with the given label refers to.
+/// Without a label, that is the innermost loop; labelled blocks can only be
+/// targeted by name.
+fn find_breakable<'c>(
+    ctxs: &'c mut [BreakableContext],
+    label: Option<&Name>,
+) -> Option<&'c mut BreakableContext> {
+    match label {
+        Some(label) => ctxs.iter_mut().rev().find(|ctx| ctx.label.as_ref() == Some(label)),
+        None => ctxs.iter_mut().rev().find(|ctx| match ctx.kind {
+            BreakableKind::Loop => true,
+            BreakableKind::Block { .. } => false,
+        }),
+    }
 }
 
 impl<'a, D: HirDatabase> InferenceContext<'a, D> {
@@ -215,6 +260,8 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             table: unify::InferenceTable::new(),
             obligations: Vec::default(),
             return_ty: Ty::Unknown, // set in collect_fn_signature
+            deferred_cast_checks: Vec::new(),
+            breakables: Vec::new(),
             trait_env: TraitEnvironment::lower(db, &resolver),
             db,
             owner,
@@ -224,6 +271,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
     }
 
     fn resolve_all(mut self) -> InferenceResult {
+        self.check_casts();
         // FIXME resolve obligations as well (use Guidance if necessary)
         let mut result = std::mem::take(&mut self.result);
         for ty in result.type_of_expr.values_mut() {
@@ -432,6 +480,15 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 let ty = self.insert_type_vars(ty.subst(&substs));
                 (ty, Some(strukt.into()))
             }
+            Some(TypeNs::AdtId(AdtId::UnionId(u))) => {
+                let segment =
+                    path.segments().last().expect("path should have at least one segment");
+                let substs =
+                    crate::lower::substs_from_path_segment(&ctx, segment, Some(u.into()), false);
+                let ty = self.db.ty(u.into());
+                let ty = self.insert_type_vars(ty.subst(&substs));
+                (ty, Some(u.into()))
+            }
             Some(TypeNs::EnumVariantId(var)) => {
                 let substs = Ty::substs_from_path(&ctx, path, var.into());
                 let ty = self.db.ty(var.parent.into());
@@ -458,7 +515,14 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
 
             self.infer_pat(*pat, &ty, BindingMode::default());
         }
-        let return_ty = self.make_ty_with_mode(&data.ret_type, ImplTraitLoweringMode::Disallowed); // FIXME implement RPIT
+        // FIXME implement RPIT
+        let return_ty = self.make_ty_with_mode(&data.ret_type, ImplTraitLoweringMode::Disallowed);
+        // The function defines the opaque type aliases it returns, so they're
+        // whatever type its body has.
+        let return_ty = return_ty.fold(&mut |ty| match ty {
+            Ty::Opaque(_) => self.table.new_type_var(),
+            ty => ty,
+        });
         self.return_ty = return_ty;
     }
 
@@ -478,6 +542,18 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         self.db.trait_data(trait_).associated_type_by_name(&name![Item])
     }
 
+    fn resolve_into_iter_into_iter(&self) -> Option<TypeAliasId> {
+        let path = path![std::iter::IntoIterator];
+        let trait_ = self.resolver.resolve_known_trait(self.db, &path)?;
+        self.db.trait_data(trait_).associated_type_by_name(&name![IntoIter])
+    }
+
+    fn resolve_iterator_item(&self) -> Option<TypeAliasId> {
+        let path = path![std::iter::Iterator];
+        let trait_ = self.resolver.resolve_known_trait(self.db, &path)?;
+        self.db.trait_data(trait_).associated_type_by_name(&name![Item])
+    }
+
     fn resolve_ops_try_ok(&self) -> Option<TypeAliasId> {
         let path = path![std::ops::Try];
         let trait_ = self.resolver.resolve_known_trait(self.db, &path)?;
@@ -504,40 +580,50 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         Some(struct_.into())
     }
 
-    fn resolve_range_full(&self) -> Option<AdtId> {
-        let path = path![std::ops::RangeFull];
-        let struct_ = self.resolver.resolve_known_struct(self.db, &path)?;
+    /// Resolves one of the `std::ops` range structs, by its lang item or, for
+    /// a std which doesn't declare it, by its path.
+    fn resolve_range_struct(&self, lang_item: &str, path: ModPath) -> Option<AdtId> {
+        let struct_ = match self.resolve_lang_item(lang_item) {
+            Some(target) => target.as_struct()?,
+            None => self.resolver.resolve_known_struct(self.db, &path)?,
+        };
         Some(struct_.into())
     }
 
+    fn resolve_range_full(&self) -> Option<AdtId> {
+        self.resolve_range_struct("RangeFull", path![std::ops::RangeFull])
+    }
+
     fn resolve_range(&self) -> Option<AdtId> {
-        let path = path![std::ops::Range];
-        let struct_ = self.resolver.resolve_known_struct(self.db, &path)?;
-        Some(struct_.into())
+        self.resolve_range_struct("Range", path![std::ops::Range])
     }
 
     fn resolve_range_inclusive(&self) -> Option<AdtId> {
-        let path = path![std::ops::RangeInclusive];
-        let struct_ = self.resolver.resolve_known_struct(self.db, &path)?;
-        Some(struct_.into())
+        self.resolve_range_struct("RangeInclusive", path![std::ops::RangeInclusive])
     }
 
     fn resolve_range_from(&self) -> Option<AdtId> {
-        let path = path![std::ops::RangeFrom];
-        let struct_ = self.resolver.resolve_known_struct(self.db, &path)?;
-        Some(struct_.into())
+        self.resolve_range_struct("RangeFrom", path![std::ops::RangeFrom])
     }
 
     fn resolve_range_to(&self) -> Option<AdtId> {
-        let path = path![std::ops::RangeTo];
-        let struct_ = self.resolver.resolve_known_struct(self.db, &path)?;
-        Some(struct_.into())
+        self.resolve_range_struct("RangeTo", path![std::ops::RangeTo])
     }
 
     fn resolve_range_to_inclusive(&self) -> Option<AdtId> {
-        let path = path![std::ops::RangeToInclusive];
-        let struct_ = self.resolver.resolve_known_struct(self.db, &path)?;
-        Some(struct_.into())
+        self.resolve_range_struct("RangeToInclusive", path![std::ops::RangeToInclusive])
+    }
+
+    fn is_range_struct(&self, adt: AdtId) -> bool {
+        let ranges = [
+            self.resolve_range_full(),
+            self.resolve_range(),
+            self.resolve_range_inclusive(),
+            self.resolve_range_from(),
+            self.resolve_range_to(),
+            self.resolve_range_to_inclusive(),
+        ];
+        ranges.contains(&Some(adt))
     }
 
     fn resolve_ops_index_output(&self) -> Option<TypeAliasId> {
@@ -602,17 +688,97 @@ impl Expectation {
 }
 
 mod diagnostics {
-    use hir_def::{expr::ExprId, src::HasSource, FunctionId, Lookup};
-    use hir_expand::diagnostics::DiagnosticSink;
-
-    use crate::{db::HirDatabase, diagnostics::NoSuchField};
+    use hir_def::{
+        expr::{BinaryOp, ExprId},
+        src::HasSource,
+        FunctionId, Lookup, TraitId,
+    };
+    use hir_expand::{diagnostics::DiagnosticSink, name::Name};
+
+    use crate::{
+        db::HirDatabase,
+        diagnostics::{
+            invalid_binary_op_message, unresolved_method_call_message, InvalidBinaryOp,
+            InvalidCast, MethodTraitNotInScope, NoSuchField, UnresolvedMethodCall,
+            UnresolvedMethodSuggestion,
+        },
+        display::HirDisplay,
+        op::binary_op_text,
+        Ty,
+    };
 
     #[derive(Debug, PartialEq, Eq, Clone)]
     pub(super) enum InferenceDiagnostic {
-        NoSuchField { expr: ExprId, field: usize },
+        NoSuchField {
+            expr: ExprId,
+            field: usize,
+        },
+        MethodTraitNotInScope {
+            expr: ExprId,
+            method: Name,
+            self_ty: Ty,
+            trait_: TraitId,
+        },
+        InvalidCast {
+            expr: ExprId,
+            from: Ty,
+            to: Ty,
+        },
+        InvalidBinaryOp {
+            expr: ExprId,
+            op: BinaryOp,
+            lhs_ty: Ty,
+        },
+        UnresolvedMethodCall {
+            expr: ExprId,
+            method: Name,
+            receiver_ty: Ty,
+            suggestion: Option<UnresolvedMethodSuggestion>,
+        },
     }
 
     impl InferenceDiagnostic {
+        pub(super) fn expr(&self) -> ExprId {
+            match self {
+                InferenceDiagnostic::NoSuchField { expr, .. }
+                | InferenceDiagnostic::MethodTraitNotInScope { expr, .. }
+                | InferenceDiagnostic::InvalidCast { expr, .. }
+                | InferenceDiagnostic::InvalidBinaryOp { expr, .. }
+                | InferenceDiagnostic::UnresolvedMethodCall { expr, .. } => *expr,
+            }
+        }
+
+        /// The message of the diagnostic, without resolving its source.
+        pub(super) fn message(&self, db: &impl HirDatabase) -> String {
+            match self {
+                InferenceDiagnostic::NoSuchField { .. } => "no such field".to_string(),
+                InferenceDiagnostic::MethodTraitNotInScope { method, self_ty, trait_, .. } => {
+                    format!(
+                        "method `{}` exists on `{}` but trait `{}` is not in scope",
+                        method,
+                        self_ty.display(db),
+                        db.trait_data(*trait_).name
+                    )
+                }
+                InferenceDiagnostic::InvalidCast { from, to, .. } => {
+                    format!("casting `{}` as `{}` is invalid", from.display(db), to.display(db))
+                }
+                InferenceDiagnostic::InvalidBinaryOp { op, lhs_ty, .. } => {
+                    invalid_binary_op_message(binary_op_text(*op), &lhs_ty.display(db).to_string())
+                }
+                InferenceDiagnostic::UnresolvedMethodCall {
+                    method,
+                    receiver_ty,
+                    suggestion,
+                    ..
+                } => unresolved_method_call_message(
+                    method,
+                    &receiver_ty.display(db).to_string(),
+                    *suggestion,
+                ),
+            }
+        }
+
         pub(super) fn add_to(
             &self,
             db: &impl HirDatabase,
@@ -626,6 +792,73 @@ mod diagnostics {
                     let field = source_map.field_syntax(*expr, *field);
                     sink.push(NoSuchField { file, field })
                 }
+                InferenceDiagnostic::MethodTraitNotInScope { expr, method, self_ty, trait_ } => {
+                    let (_, source_map) = db.body_with_source_map(owner.into());
+                    let source = match source_map.expr_syntax(*expr) {
+                        Some(it) => it,
+                        None => return,
+                    };
+                    if let Some(expr) = source.value.left() {
+                        sink.push(MethodTraitNotInScope {
+                            file: source.file_id,
+                            expr,
+                            method: method.clone(),
+                            self_ty: self_ty.display(db).to_string(),
+                            trait_: db.trait_data(*trait_).name.clone(),
+                        })
+                    }
+                }
+                InferenceDiagnostic::InvalidCast { expr, from, to } => {
+                    let (_, source_map) = db.body_with_source_map(owner.into());
+                    let source = match source_map.expr_syntax(*expr) {
+                        Some(it) => it,
+                        None => return,
+                    };
+                    if let Some(expr) = source.value.left() {
+                        sink.push(InvalidCast {
+                            file: source.file_id,
+                            expr,
+                            from: from.display(db).to_string(),
+                            to: to.display(db).to_string(),
+                        })
+                    }
+                }
+                InferenceDiagnostic::InvalidBinaryOp { expr, op, lhs_ty } => {
+                    let (_, source_map) = db.body_with_source_map(owner.into());
+                    let source = match source_map.expr_syntax(*expr) {
+                        Some(it) => it,
+                        None => return,
+                    };
+                    if let Some(expr) = source.value.left() {
+                        sink.push(InvalidBinaryOp {
+                            file: source.file_id,
+                            expr,
+                            op: binary_op_text(*op),
+                            lhs_ty: lhs_ty.display(db).to_string(),
+                        })
+                    }
+                }
+                InferenceDiagnostic::UnresolvedMethodCall {
+                    expr,
+                    method,
+                    receiver_ty,
+                    suggestion,
+                } => {
+                    let (_, source_map) = db.body_with_source_map(owner.into());
+                    let source = match source_map.expr_syntax(*expr) {
+                        Some(it) => it,
+                        None => return,
+                    };
+                    if let Some(expr) = source.value.left() {
+                        sink.push(UnresolvedMethodCall {
+                            file: source.file_id,
+                            expr,
+                            method: method.clone(),
+                            receiver_ty: receiver_ty.display(db).to_string(),
+                            suggestion: *suggestion,
+                        })
+                    }
+                }
             }
         }
     }
@@ -0,0 +1,213 @@
+//! Checking of `as` casts.
+//!
+//! The type of a cast expression is always the type it casts to; here we only
+//! check whether the cast is one rustc accepts, to report the invalid ones.
+//!
+//! See: https://doc.rust-lang.org/reference/expressions/operator-expr.html#type-cast-expressions
+
+use std::mem;
+
+use hir_def::{type_ref::Mutability, AdtId};
+
+use crate::{
+    db::HirDatabase,
+    primitive::{IntBitness, IntTy, Signedness},
+    Ty, TypeCtor, TypeWalk, Uncertain,
+};
+
+use super::{InferTy, InferenceContext, InferenceDiagnostic};
+
+/// The categories of types which matter for casts.
+#[derive(Debug, Clone)]
+enum CastTy {
+    /// An integer type, or `None` for a not yet known one.
+    Int(Option<IntTy>),
+    Float,
+    Bool,
+    Char,
+    /// An enum whose variants have no fields, cast to its discriminant.
+    FieldlessEnum,
+    /// A raw pointer, with the type it points to.
+    Ptr(Mutability, Ty),
+    /// A reference, with the type it points to.
+    Ref(Mutability, Ty),
+    /// A function pointer or a function item.
+    FnPtr,
+    Closure,
+    /// Types we don't know enough about to check the cast.
+    Unknown,
+    /// Types which can't be cast to anything but themselves.
+    Other,
+}
+
+/// The metadata a pointer to some type carries.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PointerKind {
+    Thin,
+    /// Slices and `str`.
+    Length,
+    /// Trait objects.
+    Vtable,
+    Unknown,
+}
+
+impl<'a, D: HirDatabase> InferenceContext<'a, D> {
+    /// Checks the casts of the body, once the types are known as far as
+    /// possible.
+    pub(super) fn check_casts(&mut self) {
+        for (expr, from_ty, to_ty) in mem::take(&mut self.deferred_cast_checks) {
+            let from_ty = self.table.resolve_ty_as_possible(from_ty);
+            let to_ty = self.table.resolve_ty_as_possible(to_ty);
+            if self.is_valid_cast(&from_ty, &to_ty) {
+                continue;
+            }
+            let from = self.table.resolve_ty_completely(from_ty);
+            let to = self.table.resolve_ty_completely(to_ty);
+            self.push_diagnostic(InferenceDiagnostic::InvalidCast { expr, from, to });
+        }
+    }
+
+    fn is_valid_cast(&self, from_ty: &Ty, to_ty: &Ty) -> bool {
+        if from_ty == to_ty || is_partially_unknown(from_ty) || is_partially_unknown(to_ty) {
+            return true;
+        }
+        match (from_ty, to_ty) {
+            // This might be an unsizing coercion, like `Box<T>` to
+            // `Box<dyn Trait>`.
+            (Ty::Apply(from), Ty::Apply(to)) if from.ctor == to.ctor => {
+                if let TypeCtor::Adt(_) = from.ctor {
+                    return true;
+                }
+            }
+            _ => (),
+        }
+        match (self.cast_ty(from_ty), self.cast_ty(to_ty)) {
+            (CastTy::Unknown, _) | (_, CastTy::Unknown) => true,
+            (CastTy::Int(_), CastTy::Int(_))
+            | (CastTy::Int(_), CastTy::Float)
+            | (CastTy::Float, CastTy::Int(_))
+            | (CastTy::Float, CastTy::Float) => true,
+            (CastTy::Bool, CastTy::Int(_))
+            | (CastTy::Char, CastTy::Int(_))
+            | (CastTy::FieldlessEnum, CastTy::Int(_)) => true,
+            (CastTy::Int(int_ty), CastTy::Char) => int_ty.map_or(true, is_u8),
+            (CastTy::Ptr(_, from), CastTy::Ptr(_, to)) => is_valid_pointer_cast(&from, &to),
+            (CastTy::Ptr(_, from), CastTy::Int(_)) => is_thin_or_unknown(pointer_kind(&from)),
+            (CastTy::Int(_), CastTy::Ptr(_, to)) | (CastTy::FnPtr, CastTy::Ptr(_, to)) => {
+                is_thin_or_unknown(pointer_kind(&to))
+            }
+            (CastTy::FnPtr, CastTy::Int(_)) => true,
+            (CastTy::FnPtr, CastTy::FnPtr) | (CastTy::Closure, CastTy::FnPtr) => true,
+            (CastTy::Ref(from_mut, from), CastTy::Ptr(to_mut, to)) => {
+                let is_unsizing =
+                    pointer_kind(&to) != PointerKind::Thin && is_valid_pointer_cast(&from, &to);
+                is_valid_mutability_cast(from_mut, to_mut)
+                    && (from == to || is_array_of(&from, &to) || is_unsizing)
+            }
+            (CastTy::Ref(from_mut, _), CastTy::Ref(to_mut, _)) => {
+                is_valid_mutability_cast(from_mut, to_mut)
+            }
+            _ => false,
+        }
+    }
+
+    fn cast_ty(&self, ty: &Ty) -> CastTy {
+        let a_ty = match ty {
+            Ty::Apply(a_ty) => a_ty,
+            Ty::Infer(InferTy::IntVar(_)) => return CastTy::Int(None),
+            Ty::Infer(InferTy::FloatVar(_)) => return CastTy::Float,
+            _ => return CastTy::Unknown,
+        };
+        match a_ty.ctor {
+            TypeCtor::Int(Uncertain::Known(int_ty)) => CastTy::Int(Some(int_ty)),
+            TypeCtor::Int(Uncertain::Unknown) => CastTy::Int(None),
+            TypeCtor::Float(_) => CastTy::Float,
+            TypeCtor::Bool => CastTy::Bool,
+            TypeCtor::Char => CastTy::Char,
+            TypeCtor::Adt(AdtId::EnumId(e)) => {
+                let enum_data = self.db.enum_data(e);
+                if enum_data.variants.iter().all(|(_, it)| it.variant_data.fields().is_empty()) {
+                    CastTy::FieldlessEnum
+                } else {
+                    CastTy::Other
+                }
+            }
+            TypeCtor::RawPtr(mutability) => {
+                CastTy::Ptr(mutability, a_ty.parameters.as_single().clone())
+            }
+            TypeCtor::Ref(mutability) => {
+                CastTy::Ref(mutability, a_ty.parameters.as_single().clone())
+            }
+            TypeCtor::FnDef(_) | TypeCtor::FnPtr { .. } => CastTy::FnPtr,
+            TypeCtor::Closure { .. } => CastTy::Closure,
+            TypeCtor::Never | TypeCtor::AssociatedType(_) => CastTy::Unknown,
+            _ => CastTy::Other,
+        }
+    }
+}
+
+/// Whether the type still contains parts we couldn't infer, in which case we
+/// don't report anything, to avoid cascading errors.
+fn is_partially_unknown(ty: &Ty) -> bool {
+    let mut res = false;
+    ty.walk(&mut |ty| match ty {
+        Ty::Unknown | Ty::Infer(InferTy::TypeVar(_)) => res = true,
+        _ => (),
+    });
+    res
+}
+
+fn pointer_kind(pointee: &Ty) -> PointerKind {
+    match pointee {
+        Ty::Apply(a_ty) => match a_ty.ctor {
+            TypeCtor::Str | TypeCtor::Slice => PointerKind::Length,
+            TypeCtor::AssociatedType(_) => PointerKind::Unknown,
+            _ => PointerKind::Thin,
+        },
+        Ty::Dyn(_) => PointerKind::Vtable,
+        _ => PointerKind::Unknown,
+    }
+}
+
+fn is_thin_or_unknown(kind: PointerKind) -> bool {
+    kind == PointerKind::Thin || kind == PointerKind::Unknown
+}
+
+/// Pointers can be cast to pointers with the same metadata, or to thin ones,
+/// which just drops the metadata. Pointers to arrays and other sized types
+/// can also be unsized.
+fn is_valid_pointer_cast(from: &Ty, to: &Ty) -> bool {
+    match (pointer_kind(from), pointer_kind(to)) {
+        (_, PointerKind::Thin) => true,
+        (PointerKind::Unknown, _) | (_, PointerKind::Unknown) => true,
+        (PointerKind::Thin, PointerKind::Vtable) => true,
+        (PointerKind::Thin, PointerKind::Length) => match from {
+            Ty::Apply(a_ty) => match a_ty.ctor {
+                TypeCtor::Array { .. } => true,
+                _ => false,
+            },
+            _ => false,
+        },
+        (from, to) => from == to,
+    }
+}
+
+fn is_valid_mutability_cast(from: Mutability, to: Mutability) -> bool {
+    !(from == Mutability::Shared && to == Mutability::Mut)
+}
+
+/// Whether `array` is an array of `elem`, as `&[T; N]` can be cast to
+/// `*const T`.
+fn is_array_of(array: &Ty, elem: &Ty) -> bool {
+    match array {
+        Ty::Apply(a_ty) => match a_ty.ctor {
+            TypeCtor::Array { .. } => a_ty.parameters.as_single() == elem,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_u8(int_ty: IntTy) -> bool {
+    int_ty.signedness == Signedness::Unsigned && int_ty.bitness == IntBitness::X8
+}
@@ -4,7 +4,13 @@
 //!
 //! See: https://doc.rust-lang.org/nomicon/coercions.html
 
-use hir_def::{lang_item::LangItemTarget, type_ref::Mutability};
+use hir_def::{
+    expr::{Expr, ExprId},
+    lang_item::LangItemTarget,
+    type_ref::Mutability,
+    DefWithBodyId,
+};
+use hir_expand::name::name;
 use test_utils::tested_by;
 
 use crate::{
@@ -32,6 +38,10 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             ty2.clone()
         } else if self.coerce(ty2, ty1) {
             ty1.clone()
+        } else if let Some(ty) = self.coerce_merge_fn_ptrs(ty1, ty2) {
+            // Two different function items, as in
+            // `match x { A => foo, B => bar }`.
+            ty
         } else {
             tested_by!(coerce_merge_fail_fallback);
             // For incompatible types, we use the latter one as result
@@ -82,21 +92,15 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             ) => return false,
 
             // `{function_type}` -> `fn()`
-            (ty_app!(TypeCtor::FnDef(_)), ty_app!(TypeCtor::FnPtr { .. })) => {
-                match from_ty.callable_sig(self.db) {
+            // `{closure}` -> `fn()`, if the closure doesn't capture anything
+            (ty_app!(TypeCtor::FnDef(_)), ty_app!(TypeCtor::FnPtr { .. }))
+            | (ty_app!(TypeCtor::Closure { .. }), ty_app!(TypeCtor::FnPtr { .. })) => {
+                match self.as_fn_ptr(&from_ty) {
                     None => return false,
-                    Some(sig) => {
-                        let num_args = sig.params_and_return.len() as u16 - 1;
-                        from_ty =
-                            Ty::apply(TypeCtor::FnPtr { num_args }, Substs(sig.params_and_return));
-                    }
+                    Some(ptr) => from_ty = ptr,
                 }
             }
 
-            (ty_app!(TypeCtor::Closure { .. }, params), ty_app!(TypeCtor::FnPtr { .. })) => {
-                from_ty = params[0].clone();
-            }
-
             _ => {}
         }
 
@@ -116,6 +120,39 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         }
     }
 
+    /// Coerces both types to function pointers and unifies them, if both are
+    /// either function items or closures without captures.
+    fn coerce_merge_fn_ptrs(&mut self, ty1: &Ty, ty2: &Ty) -> Option<Ty> {
+        let ty1 = self.resolve_ty_shallow(ty1).into_owned();
+        let ty2 = self.resolve_ty_shallow(ty2).into_owned();
+        let ptr1 = self.as_fn_ptr(&ty1)?;
+        let ptr2 = self.as_fn_ptr(&ty2)?;
+        if self.unify(&ptr1, &ptr2) {
+            Some(ptr1)
+        } else {
+            None
+        }
+    }
+
+    /// The function pointer type a function item or a closure coerces to.
+    fn as_fn_ptr(&self, ty: &Ty) -> Option<Ty> {
+        match ty {
+            ty_app!(TypeCtor::FnDef(_)) => {
+                let sig = ty.callable_sig(self.db)?;
+                let num_args = sig.params_and_return.len() as u16 - 1;
+                Some(Ty::apply(TypeCtor::FnPtr { num_args }, Substs(sig.params_and_return)))
+            }
+            ty_app!(TypeCtor::Closure { def, expr }, params) => {
+                if closure_captures(self.db, *def, *expr) {
+                    None
+                } else {
+                    Some(params[0].clone())
+                }
+            }
+            _ => None,
+        }
+    }
+
     /// Coerce a type using `from_ty: CoerceUnsized<ty_ty>`
     ///
     /// See: https://doc.rust-lang.org/nightly/std/marker/trait.CoerceUnsized.html
@@ -172,7 +209,9 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             let derefed_ty = canonicalized.decanonicalize_ty(derefed_ty.value);
             match (&*self.resolve_ty_shallow(&derefed_ty), &*to_ty) {
                 // Stop when constructor matches.
-                (ty_app!(from_ctor, st1), ty_app!(to_ctor, st2)) if from_ctor == to_ctor => {
+                (ty_app!(from_ctor, st1), ty_app!(to_ctor, st2))
+                    if from_ctor.unifies_with(to_ctor) =>
+                {
                     // It will not recurse to `coerce`.
                     return self.table.unify_substs(st1, st2, 0);
                 }
@@ -187,3 +226,47 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         false
     }
 }
+
+/// Whether the closure `closure` uses any local variable which is defined
+/// outside of it.
+fn closure_captures(db: &impl HirDatabase, def: DefWithBodyId, closure: ExprId) -> bool {
+    let body = db.body(def);
+    let scopes = db.expr_scopes(def);
+    let closure_body = match &body[closure] {
+        Expr::Lambda { body, .. } => *body,
+        _ => return false,
+    };
+    // The parameters of the closure live in the scope of its body.
+    let closure_scope = match scopes.scope_for(closure_body) {
+        Some(it) => it,
+        None => return false,
+    };
+
+    let mut stack = vec![closure_body];
+    while let Some(expr) = stack.pop() {
+        if let Expr::Path(path) = &body[expr] {
+            let name = if path.mod_path().is_self() {
+                name![self]
+            } else {
+                match path.mod_path().as_ident() {
+                    Some(it) => it.clone(),
+                    None => continue,
+                }
+            };
+            let mut outside_closure = false;
+            for scope in scopes.scope_chain(scopes.scope_for(expr)) {
+                if scopes.entries(scope).iter().any(|it| *it.name() == name) {
+                    if outside_closure {
+                        return true;
+                    }
+                    break;
+                }
+                if scope == closure_scope {
+                    outside_closure = true;
+                }
+            }
+        }
+        body[expr].walk_child_exprs(|it| stack.push(it));
+    }
+    false
+}
@@ -0,0 +1,257 @@
+//! Renders the lowered body of a function, const or static together with the
+//! types inferred for it. This is a debugging aid, the output format is not
+//! stable.
+
+use std::fmt::Write;
+
+use hir_def::{
+    body::{Body, BodySourceMap},
+    expr::{Array, Expr, ExprId, Pat, PatId, Statement},
+    DefWithBodyId, Lookup,
+};
+use hir_expand::{name::Name, HirFileId, InFile};
+use ra_arena::ArenaId;
+use ra_syntax::{ast, AstNode, SyntaxNodePtr};
+
+use super::InferenceResult;
+use crate::{db::HirDatabase, display::HirDisplay, utils::ellipsize};
+
+/// Renders the expression and pattern tree of the body of `def`, with the
+/// source range, inferred type and recorded mismatches of every node.
+pub fn dump_body(db: &impl HirDatabase, def: DefWithBodyId) -> String {
+    let (body, source_map) = db.body_with_source_map(def);
+    let infer = db.infer(def);
+
+    let (header, file_id) = match def {
+        DefWithBodyId::FunctionId(it) => {
+            let name = db.function_data(it).name.clone();
+            (format!("fn {}", name), it.lookup(db).ast_id.file_id)
+        }
+        DefWithBodyId::ConstId(it) => {
+            let name = db.const_data(it).name.clone();
+            (format!("const {}", display_name(name)), it.lookup(db).ast_id.file_id)
+        }
+        DefWithBodyId::StaticId(it) => {
+            let name = db.static_data(it).name.clone();
+            (format!("static {}", display_name(name)), it.lookup(db).ast_id.file_id)
+        }
+    };
+
+    let mut dumper = BodyDumper {
+        db,
+        body: &body,
+        source_map: &source_map,
+        infer: &infer,
+        file_id,
+        buf: header,
+    };
+    dumper.buf.push('\n');
+    for &param in body.params.iter() {
+        dumper.pat(param, 1);
+    }
+    dumper.expr(body.body_expr, 1);
+    dumper.buf
+}
+
+fn display_name(name: Option<Name>) -> String {
+    match name {
+        Some(name) => name.to_string(),
+        None => "_".to_string(),
+    }
+}
+
+struct BodyDumper<'a, DB> {
+    db: &'a DB,
+    body: &'a Body,
+    source_map: &'a BodySourceMap,
+    infer: &'a InferenceResult,
+    file_id: HirFileId,
+    buf: String,
+}
+
+impl<'a, DB: HirDatabase> BodyDumper<'a, DB> {
+    fn expr(&mut self, expr: ExprId, depth: usize) {
+        let (body, infer) = (self.body, self.infer);
+        let data = &body[expr];
+        let ptr = self.source_map.expr_syntax(expr).map(|src| {
+            src.map(|ast| ast.either(|it| it.syntax_node_ptr(), |it| it.syntax_node_ptr()))
+        });
+        let ty = infer.type_of_expr.get(expr).map(|ty| ty.display(self.db).to_string());
+        let label = format!("expr#{} {}", expr.into_raw(), expr_kind(data));
+        self.node(depth, &label, ptr, ty);
+
+        if let Some(mismatch) = infer.type_mismatch_for_expr(expr) {
+            let line = format!(
+                "mismatch: expected {}, got {}",
+                mismatch.expected.display(self.db),
+                mismatch.actual.display(self.db)
+            );
+            self.line(depth + 1, &line);
+        }
+        for diagnostic in infer.diagnostics.iter().filter(|it| it.expr() == expr) {
+            let line = format!("diagnostic: {}", diagnostic.message(self.db));
+            self.line(depth + 1, &line);
+        }
+
+        // Patterns are interleaved with the expressions in source order, so
+        // only the remaining cases can use `walk_child_exprs`.
+        match data {
+            Expr::Block { statements, tail, .. } => {
+                for stmt in statements {
+                    match stmt {
+                        Statement::Let { pat, initializer, else_branch, .. } => {
+                            self.pat(*pat, depth + 1);
+                            if let Some(initializer) = initializer {
+                                self.expr(*initializer, depth + 1);
+                            }
+                            if let Some(else_branch) = else_branch {
+                                self.expr(*else_branch, depth + 1);
+                            }
+                        }
+                        Statement::Expr(expr) => self.expr(*expr, depth + 1),
+                    }
+                }
+                if let Some(tail) = tail {
+                    self.expr(*tail, depth + 1);
+                }
+            }
+            Expr::For { iterable, pat, body, .. } => {
+                self.pat(*pat, depth + 1);
+                self.expr(*iterable, depth + 1);
+                self.expr(*body, depth + 1);
+            }
+            Expr::Match { expr, arms } => {
+                self.expr(*expr, depth + 1);
+                for arm in arms {
+                    self.pat(arm.pat, depth + 1);
+                    if let Some(guard) = arm.guard {
+                        self.expr(guard, depth + 1);
+                    }
+                    self.expr(arm.expr, depth + 1);
+                }
+            }
+            Expr::Lambda { args, body, .. } => {
+                for arg in args {
+                    self.pat(*arg, depth + 1);
+                }
+                self.expr(*body, depth + 1);
+            }
+            Expr::Let { pat, expr } => {
+                self.pat(*pat, depth + 1);
+                self.expr(*expr, depth + 1);
+            }
+            Expr::Range { lhs, rhs, .. } => {
+                for &it in lhs.iter().chain(rhs.iter()) {
+                    self.expr(it, depth + 1);
+                }
+            }
+            _ => data.walk_child_exprs(|it| self.expr(it, depth + 1)),
+        }
+    }
+
+    fn pat(&mut self, pat: PatId, depth: usize) {
+        let body = self.body;
+        let data = &body[pat];
+        let ptr = self.source_map.pat_syntax(pat).map(|src| {
+            src.map(|ast| ast.either(|it| it.syntax_node_ptr(), |it| it.syntax_node_ptr()))
+        });
+        let ty = self.infer.type_of_pat.get(pat).map(|ty| ty.display(self.db).to_string());
+        let label = format!("pat#{} {}", pat.into_raw(), pat_kind(data));
+        self.node(depth, &label, ptr, ty);
+
+        match data {
+            Pat::Range { start, end } => {
+                self.expr(*start, depth + 1);
+                self.expr(*end, depth + 1);
+            }
+            Pat::Lit(expr) => self.expr(*expr, depth + 1),
+            _ => data.walk_child_pats(|it| self.pat(it, depth + 1)),
+        }
+    }
+
+    fn node(
+        &mut self,
+        depth: usize,
+        label: &str,
+        ptr: Option<InFile<SyntaxNodePtr>>,
+        ty: Option<String>,
+    ) {
+        let mut line = label.to_string();
+        if let Some(ptr) = ptr {
+            let node = ptr.value.to_node(&ptr.file_syntax(self.db));
+            let (range, text) = match ast::SelfParam::cast(node.clone()) {
+                Some(self_param) => (self_param.self_kw_token().text_range(), "self".to_string()),
+                None => (ptr.value.range(), node.text().to_string().replace("\n", " ")),
+            };
+            // Nodes coming from macro expansions have ranges in the expansion.
+            let macro_prefix = if ptr.file_id != self.file_id { "!" } else { "" };
+            write!(line, " {}{} '{}'", macro_prefix, range, ellipsize(text, 15)).unwrap();
+        }
+        let ty = ty.unwrap_or_else(|| "?".to_string());
+        write!(line, ": {}", ty).unwrap();
+        self.line(depth, &line);
+    }
+
+    fn line(&mut self, depth: usize, text: &str) {
+        for _ in 0..depth {
+            self.buf.push_str("  ");
+        }
+        self.buf.push_str(text);
+        self.buf.push('\n');
+    }
+}
+
+fn expr_kind(expr: &Expr) -> &'static str {
+    match expr {
+        Expr::Missing => "Missing",
+        Expr::Path(_) => "Path",
+        Expr::If { .. } => "If",
+        Expr::Block { .. } => "Block",
+        Expr::Loop { .. } => "Loop",
+        Expr::While { .. } => "While",
+        Expr::For { .. } => "For",
+        Expr::Call { .. } => "Call",
+        Expr::MethodCall { .. } => "MethodCall",
+        Expr::Match { .. } => "Match",
+        Expr::Continue { .. } => "Continue",
+        Expr::Break { .. } => "Break",
+        Expr::Return { .. } => "Return",
+        Expr::Yield { .. } => "Yield",
+        Expr::RecordLit { .. } => "RecordLit",
+        Expr::Field { .. } => "Field",
+        Expr::Await { .. } => "Await",
+        Expr::Try { .. } => "Try",
+        Expr::TryBlock { .. } => "TryBlock",
+        Expr::Cast { .. } => "Cast",
+        Expr::Ref { .. } => "Ref",
+        Expr::Box { .. } => "Box",
+        Expr::Let { .. } => "Let",
+        Expr::UnaryOp { .. } => "UnaryOp",
+        Expr::BinaryOp { .. } => "BinaryOp",
+        Expr::Range { .. } => "Range",
+        Expr::Index { .. } => "Index",
+        Expr::Lambda { .. } => "Lambda",
+        Expr::Tuple { .. } => "Tuple",
+        Expr::Array(Array::ElementList(_)) => "Array",
+        Expr::Array(Array::Repeat { .. }) => "ArrayRepeat",
+        Expr::Literal(_) => "Literal",
+    }
+}
+
+fn pat_kind(pat: &Pat) -> &'static str {
+    match pat {
+        Pat::Missing => "Missing",
+        Pat::Wild => "Wild",
+        Pat::Tuple(_) => "Tuple",
+        Pat::Or(_) => "Or",
+        Pat::Record { .. } => "Record",
+        Pat::Range { .. } => "Range",
+        Pat::Slice { .. } => "Slice",
+        Pat::Path(_) => "Path",
+        Pat::Lit(_) => "Lit",
+        Pat::Bind { .. } => "Bind",
+        Pat::TupleStruct { .. } => "TupleStruct",
+        Pat::Ref { .. } => "Ref",
+        Pat::Box { .. } => "Box",
+    }
+}
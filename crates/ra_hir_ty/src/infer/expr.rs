@@ -8,22 +8,29 @@ use hir_def::{
     expr::{Array, BinaryOp, Expr, ExprId, Literal, Statement, UnaryOp},
     path::{GenericArg, GenericArgs},
     resolver::resolver_for_expr,
-    AdtId, AssocContainerId, Lookup, StructFieldId,
+    AdtId, AssocContainerId, AssocItemId, Lookup, StructFieldId, TraitId, VariantId,
 };
-use hir_expand::name::Name;
+use hir_expand::name::{name, Name};
 use ra_syntax::ast::RangeOp;
+use rustc_hash::FxHashSet;
 
 use crate::{
     autoderef,
     db::HirDatabase,
-    method_resolution, op,
+    diagnostics::UnresolvedMethodSuggestion,
+    lower::eval_const_path,
+    method_resolution::{self, LookupMode, RejectionReason},
+    op,
     traits::InEnvironment,
     utils::{generics, variant_data, Generics},
-    ApplicationTy, Binders, CallableDef, InferTy, IntTy, Mutability, Obligation, Substs, TraitRef,
-    Ty, TypeCtor, Uncertain,
+    ApplicationTy, Binders, CallableDef, Canonical, InferTy, IntTy, Mutability, Obligation, Substs,
+    TraitRef, Ty, TypeCtor, TypeWalk, Uncertain,
 };
 
-use super::{BindingMode, Expectation, InferenceContext, InferenceDiagnostic, TypeMismatch};
+use super::{
+    find_breakable, BindingMode, BreakableContext, BreakableKind, Expectation, InferenceContext,
+    InferenceDiagnostic, TypeMismatch,
+};
 
 impl<'a, D: HirDatabase> InferenceContext<'a, D> {
     pub(super) fn infer_expr(&mut self, tgt_expr: ExprId, expected: &Expectation) -> Ty {
@@ -63,7 +70,8 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         let ty = match &body[tgt_expr] {
             Expr::Missing => Ty::Unknown,
             Expr::If { condition, then_branch, else_branch } => {
-                // if let is desugared to match, so this is always simple if
+                // a single `if let` is desugared to match, while the `let`s of a
+                // chain are boolean `Expr::Let`s in the condition
                 self.infer_expr(*condition, &Expectation::has_type(Ty::simple(TypeCtor::Bool)));
 
                 let then_ty = self.infer_expr_inner(*then_branch, &expected);
@@ -74,31 +82,61 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
 
                 self.coerce_merge_branch(&then_ty, &else_ty)
             }
-            Expr::Block { statements, tail } => self.infer_block(statements, *tail, expected),
+            Expr::Block { statements, tail, label: None } => {
+                self.infer_block(statements, *tail, expected)
+            }
+            Expr::Block { statements, tail, label: Some(label) } => {
+                let break_ty = self.table.new_type_var();
+                self.breakables.push(BreakableContext {
+                    kind: BreakableKind::Block { break_ty: break_ty.clone() },
+                    label: Some(label.clone()),
+                    may_break: false,
+                });
+                let ty =
+                    self.infer_block(statements, *tail, &Expectation::has_type(break_ty.clone()));
+                let ctx = self.breakables.pop().expect("breakable stack broken");
+                if ctx.may_break {
+                    break_ty
+                } else {
+                    ty
+                }
+            }
             Expr::TryBlock { body } => {
                 let _inner = self.infer_expr(*body, expected);
                 // FIXME should be std::result::Result<{inner}, _>
                 Ty::Unknown
             }
-            Expr::Loop { body } => {
-                self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
+            Expr::Loop { body, label } => {
+                self.infer_loop_body(*body, label);
                 // FIXME handle break with value
                 Ty::simple(TypeCtor::Never)
             }
-            Expr::While { condition, body } => {
+            Expr::While { condition, body, label } => {
                 // while let is desugared to a match loop, so this is always simple while
                 self.infer_expr(*condition, &Expectation::has_type(Ty::simple(TypeCtor::Bool)));
-                self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
+                self.infer_loop_body(*body, label);
                 Ty::unit()
             }
-            Expr::For { iterable, body, pat } => {
+            Expr::For { iterable, body, pat, label } => {
                 let iterable_ty = self.infer_expr(*iterable, &Expectation::none());
 
                 let pat_ty =
-                    self.resolve_associated_type(iterable_ty, self.resolve_into_iter_item());
+                    match (self.resolve_into_iter_into_iter(), self.resolve_iterator_item()) {
+                        (Some(into_iter), Some(item)) => {
+                            // The loop calls `Iterator::next` on the result of
+                            // `IntoIterator::into_iter`, so the pattern has type
+                            // `<<I as IntoIterator>::IntoIter as Iterator>::Item`.
+                            let iter_ty =
+                                self.resolve_associated_type(iterable_ty, Some(into_iter));
+                            self.resolve_associated_type(iter_ty, Some(item))
+                        }
+                        _ => {
+                            self.resolve_associated_type(iterable_ty, self.resolve_into_iter_item())
+                        }
+                    };
 
                 self.infer_pat(*pat, &pat_ty, BindingMode::default());
-                self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
+                self.infer_loop_body(*body, label);
                 Ty::unit()
             }
             Expr::Lambda { body, args, ret_type, arg_types } => {
@@ -116,29 +154,48 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                     sig_tys.push(arg_ty);
                 }
 
-                // add return type
-                let ret_ty = match ret_type {
-                    Some(type_ref) => self.make_ty(type_ref),
-                    None => self.table.new_type_var(),
+                // add return type; we don't model generators, so the
+                // return type of a closure containing `yield` stays unknown
+                let ret_ty = if self.contains_yield(*body) {
+                    Ty::Unknown
+                } else {
+                    match ret_type {
+                        Some(type_ref) => self.make_ty(type_ref),
+                        None => self.table.new_type_var(),
+                    }
                 };
                 sig_tys.push(ret_ty.clone());
                 let sig_ty = Ty::apply(
                     TypeCtor::FnPtr { num_args: sig_tys.len() as u16 - 1 },
                     Substs(sig_tys.into()),
                 );
-                let closure_ty =
-                    Ty::apply_one(TypeCtor::Closure { def: self.owner, expr: tgt_expr }, sig_ty);
+                let closure_ty = Ty::apply_one(
+                    TypeCtor::Closure { def: self.owner, expr: tgt_expr },
+                    sig_ty.clone(),
+                );
 
                 // Eagerly try to relate the closure type with the expected
                 // type, otherwise we often won't have enough information to
-                // infer the body.
-                self.coerce(&closure_ty, &expected.ty);
+                // infer the body. A closure with captures doesn't coerce to a
+                // function pointer, but its signature still has to match.
+                if !self.coerce(&closure_ty, &expected.ty) {
+                    let expects_fn_ptr = match &*self.resolve_ty_shallow(&expected.ty) {
+                        ty_app!(TypeCtor::FnPtr { .. }) => true,
+                        _ => false,
+                    };
+                    if expects_fn_ptr {
+                        self.unify(&sig_ty, &expected.ty);
+                    }
+                }
 
                 let prev_ret_ty = std::mem::replace(&mut self.return_ty, ret_ty.clone());
+                // `break` and `continue` can't leave the closure
+                let prev_breakables = std::mem::take(&mut self.breakables);
 
                 self.infer_expr_coerce(*body, &Expectation::has_type(ret_ty));
 
                 self.return_ty = prev_ret_ty;
+                self.breakables = prev_breakables;
 
                 closure_ty
             }
@@ -187,11 +244,30 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 let resolver = resolver_for_expr(self.db, self.owner, tgt_expr);
                 self.infer_path(&resolver, p, tgt_expr.into()).unwrap_or(Ty::Unknown)
             }
-            Expr::Continue => Ty::simple(TypeCtor::Never),
-            Expr::Break { expr } => {
-                if let Some(expr) = expr {
-                    // FIXME handle break with value
-                    self.infer_expr(*expr, &Expectation::none());
+            Expr::Continue { .. } => Ty::simple(TypeCtor::Never),
+            Expr::Break { expr, label } => {
+                let break_ty = match find_breakable(&mut self.breakables, label.as_ref()) {
+                    Some(ctx) => {
+                        ctx.may_break = true;
+                        match &ctx.kind {
+                            BreakableKind::Block { break_ty } => Some(break_ty.clone()),
+                            BreakableKind::Loop => None,
+                        }
+                    }
+                    None => None,
+                };
+                match (expr, break_ty) {
+                    (Some(expr), Some(break_ty)) => {
+                        self.infer_expr_coerce(*expr, &Expectation::has_type(break_ty));
+                    }
+                    (None, Some(break_ty)) => {
+                        self.coerce(&Ty::unit(), &break_ty);
+                    }
+                    (Some(expr), None) => {
+                        // FIXME handle break with value from loops
+                        self.infer_expr(*expr, &Expectation::none());
+                    }
+                    (None, None) => {}
                 }
                 Ty::simple(TypeCtor::Never)
             }
@@ -204,6 +280,12 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 }
                 Ty::simple(TypeCtor::Never)
             }
+            Expr::Yield { expr } => {
+                if let Some(expr) = expr {
+                    self.infer_expr(*expr, &Expectation::none());
+                }
+                Ty::Unknown
+            }
             Expr::RecordLit { path, fields, spread } => {
                 let (ty, def_id) = self.resolve_variant(path.as_ref());
                 if let Some(variant) = def_id {
@@ -266,8 +348,15 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                                     .subst(&a_ty.parameters)
                             })
                         }
-                        // FIXME:
-                        TypeCtor::Adt(AdtId::UnionId(_)) => None,
+                        TypeCtor::Adt(AdtId::UnionId(u)) => {
+                            self.db.union_data(u).variant_data.field(name).map(|local_id| {
+                                let field = StructFieldId { parent: u.into(), local_id };
+                                self.write_field_resolution(tgt_expr, field);
+                                self.db.field_types(u.into())[field.local_id]
+                                    .clone()
+                                    .subst(&a_ty.parameters)
+                            })
+                        }
                         _ => None,
                     },
                     _ => None,
@@ -285,9 +374,9 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 self.resolve_associated_type(inner_ty, self.resolve_ops_try_ok())
             }
             Expr::Cast { expr, type_ref } => {
-                let _inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
+                let inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
                 let cast_ty = self.make_ty(type_ref);
-                // FIXME check the cast...
+                self.deferred_cast_checks.push((tgt_expr, inner_ty, cast_ty.clone()));
                 cast_ty
             }
             Expr::Ref { expr, mutability } => {
@@ -312,6 +401,12 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                     Ty::Unknown
                 }
             }
+            Expr::Let { pat, expr } => {
+                let ty = self.infer_expr(*expr, &Expectation::none());
+                let ty = self.resolve_ty_as_possible(ty);
+                self.infer_pat(*pat, &ty, BindingMode::default());
+                Ty::simple(TypeCtor::Bool)
+            }
             Expr::UnaryOp { expr, op } => {
                 let inner_ty = self.infer_expr_inner(*expr, &Expectation::none());
                 match op {
@@ -377,17 +472,15 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                         _ => Expectation::none(),
                     };
                     let lhs_ty = self.infer_expr(*lhs, &lhs_expectation);
-                    // FIXME: find implementation of trait corresponding to operation
-                    // symbol and resolve associated `Output` type
                     let rhs_expectation = op::binary_op_rhs_expectation(*op, lhs_ty.clone());
                     let rhs_ty = self.infer_expr(*rhs, &Expectation::has_type(rhs_expectation));
-
-                    // FIXME: similar as above, return ty is often associated trait type
-                    op::binary_op_return_ty(*op, lhs_ty, rhs_ty)
+                    self.infer_binary_op(tgt_expr, lhs_ty, *op, rhs_ty)
                 }
                 _ => Ty::Unknown,
             },
             Expr::Range { lhs, rhs, range_type } => {
+                // Both ends of the range have the same type: the end is checked
+                // against the type of the start, which is the type of the range.
                 let lhs_ty = lhs.map(|e| self.infer_expr_inner(e, &Expectation::none()));
                 let rhs_expect = lhs_ty
                     .as_ref()
@@ -408,11 +501,11 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                             None => Ty::Unknown,
                         }
                     }
-                    (RangeOp::Exclusive, Some(_), Some(ty)) => match self.resolve_range() {
+                    (RangeOp::Exclusive, Some(ty), Some(_)) => match self.resolve_range() {
                         Some(adt) => Ty::apply_one(TypeCtor::Adt(adt), ty),
                         None => Ty::Unknown,
                     },
-                    (RangeOp::Inclusive, Some(_), Some(ty)) => {
+                    (RangeOp::Inclusive, Some(ty), Some(_)) => {
                         match self.resolve_range_inclusive() {
                             Some(adt) => Ty::apply_one(TypeCtor::Adt(adt), ty),
                             None => Ty::Unknown,
@@ -429,11 +522,14 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 let base_ty = self.infer_expr_inner(*base, &Expectation::none());
                 let index_ty = self.infer_expr(*index, &Expectation::none());
 
-                self.resolve_associated_type_with_params(
-                    base_ty,
-                    self.resolve_ops_index_output(),
-                    &[index_ty],
-                )
+                match self.builtin_index(&base_ty, &index_ty) {
+                    Some(ty) => ty,
+                    None => self.resolve_associated_type_with_params(
+                        base_ty,
+                        self.resolve_ops_index_output(),
+                        &[index_ty],
+                    ),
+                }
             }
             Expr::Tuple { exprs } => {
                 let mut tys = match &expected.ty {
@@ -454,17 +550,18 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             }
             Expr::Array(array) => {
                 let elem_ty = match &expected.ty {
-                    ty_app!(TypeCtor::Array, st) | ty_app!(TypeCtor::Slice, st) => {
+                    ty_app!(TypeCtor::Array { .. }, st) | ty_app!(TypeCtor::Slice, st) => {
                         st.as_single().clone()
                     }
                     _ => self.table.new_type_var(),
                 };
 
-                match array {
+                let len = match array {
                     Array::ElementList(items) => {
                         for expr in items.iter() {
                             self.infer_expr_coerce(*expr, &Expectation::has_type(elem_ty.clone()));
                         }
+                        Some(items.len() as u64)
                     }
                     Array::Repeat { initializer, repeat } => {
                         self.infer_expr_coerce(
@@ -477,10 +574,15 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                                 IntTy::usize(),
                             )))),
                         );
+                        match &body[*repeat] {
+                            Expr::Literal(Literal::Int(len, _)) => Some(*len),
+                            Expr::Path(path) => eval_const_path(self.db, &self.resolver, path),
+                            _ => None,
+                        }
                     }
-                }
+                };
 
-                Ty::apply_one(TypeCtor::Array, elem_ty)
+                Ty::apply_one(TypeCtor::Array { len }, elem_ty)
             }
             Expr::Literal(lit) => match lit {
                 Literal::Bool(..) => Ty::simple(TypeCtor::Bool),
@@ -504,6 +606,16 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         ty
     }
 
+    fn infer_loop_body(&mut self, body: ExprId, label: &Option<Name>) {
+        self.breakables.push(BreakableContext {
+            kind: BreakableKind::Loop,
+            label: label.clone(),
+            may_break: false,
+        });
+        self.infer_expr(body, &Expectation::has_type(Ty::unit()));
+        self.breakables.pop().expect("breakable stack broken");
+    }
+
     fn infer_block(
         &mut self,
         statements: &[Statement],
@@ -513,7 +625,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         let mut diverges = false;
         for stmt in statements {
             match stmt {
-                Statement::Let { pat, type_ref, initializer } => {
+                Statement::Let { pat, type_ref, initializer, else_branch } => {
                     let decl_ty =
                         type_ref.as_ref().map(|tr| self.make_ty(tr)).unwrap_or(Ty::Unknown);
 
@@ -528,6 +640,11 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                         }
                     }
 
+                    // The bindings are not in scope in the else block, which must diverge.
+                    if let Some(expr) = else_branch {
+                        self.infer_expr(*expr, &Expectation::has_type(Ty::simple(TypeCtor::Never)));
+                    }
+
                     let ty = self.resolve_ty_as_possible(ty);
                     self.infer_pat(*pat, &ty, BindingMode::default());
                 }
@@ -542,7 +659,10 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         let ty = if let Some(expr) = tail {
             self.infer_expr_coerce(expr, expected)
         } else {
-            self.coerce(&Ty::unit(), &expected.ty);
+            // a diverging block without a tail can have any type
+            if !diverges {
+                self.coerce(&Ty::unit(), &expected.ty);
+            }
             Ty::unit()
         };
         if diverges {
@@ -552,6 +672,90 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         }
     }
 
+    fn infer_binary_op(&mut self, expr: ExprId, lhs_ty: Ty, op: BinaryOp, rhs_ty: Ty) -> Ty {
+        let lhs_ty = self.resolve_ty_shallow(&lhs_ty).into_owned();
+        let rhs_ty = self.resolve_ty_shallow(&rhs_ty).into_owned();
+        // Fast path for builtins
+        if op::is_builtin_binary_op(&lhs_ty, &rhs_ty) {
+            return op::binary_op_return_ty(op, lhs_ty, rhs_ty);
+        }
+        // Otherwise we resolve via the `std::ops` or `std::cmp` trait
+        let trait_ = match op::binary_op_lang_item(op)
+            .and_then(|it| self.resolve_lang_item(it))
+            .and_then(|it| it.as_trait())
+        {
+            Some(it) => it,
+            None => return op::binary_op_return_ty(op, lhs_ty, rhs_ty),
+        };
+        if let BinaryOp::ArithOp(_) = op {
+            let output = self.db.trait_data(trait_).associated_type_by_name(&name![Output]);
+            return self.resolve_associated_type_with_params(lhs_ty, output, &[rhs_ty]);
+        }
+
+        // Comparisons and compound assignments have a fixed type, we only check
+        // that the trait is implemented.
+        let canonicalized = self.canonicalizer().canonicalize_ty(lhs_ty.clone());
+        let implemented = match self.resolver.krate() {
+            Some(krate) if canonicalized.value.num_vars == 0 => {
+                method_resolution::implements_trait(
+                    &canonicalized.value,
+                    self.db,
+                    self.trait_env.clone(),
+                    krate,
+                    trait_,
+                )
+            }
+            _ => true,
+        };
+        if implemented {
+            let substs = Substs::build_for_def(self.db, trait_)
+                .push(lhs_ty.clone())
+                .fill(repeat(rhs_ty.clone()))
+                .build();
+            self.obligations.push(Obligation::Trait(TraitRef { trait_, substs }));
+        } else {
+            self.push_diagnostic(InferenceDiagnostic::InvalidBinaryOp {
+                expr,
+                op,
+                lhs_ty: lhs_ty.clone(),
+            });
+        }
+        op::binary_op_return_ty(op, lhs_ty, rhs_ty)
+    }
+
+    /// Indexing arrays and slices, possibly behind references, by `usize` or
+    /// by a range of `usize` is built into the compiler; the `Index` impls of
+    /// std only cover the other cases.
+    fn builtin_index(&mut self, base_ty: &Ty, index_ty: &Ty) -> Option<Ty> {
+        let mut base_ty = self.resolve_ty_shallow(base_ty).into_owned();
+        while let ty_app!(TypeCtor::Ref(_), st) = &base_ty {
+            let inner = st.as_single().clone();
+            base_ty = self.resolve_ty_shallow(&inner).into_owned();
+        }
+        let elem_ty = match &base_ty {
+            ty_app!(TypeCtor::Array { .. }, st) | ty_app!(TypeCtor::Slice, st) => {
+                st.as_single().clone()
+            }
+            _ => return None,
+        };
+        let usize_ty = Ty::simple(TypeCtor::Int(Uncertain::Known(IntTy::usize())));
+        let index_ty = self.resolve_ty_shallow(index_ty).into_owned();
+        match &index_ty {
+            ty_app!(TypeCtor::Adt(adt), st) if self.is_range_struct(*adt) => {
+                // `RangeFull` has no bound.
+                if let Some(bound_ty) = st.iter().next() {
+                    if !self.unify(bound_ty, &usize_ty) {
+                        return None;
+                    }
+                }
+                Some(Ty::apply_one(TypeCtor::Slice, elem_ty))
+            }
+            ty_app!(TypeCtor::Adt(_)) => None,
+            _ if self.unify(&index_ty, &usize_ty) => Some(elem_ty),
+            _ => None,
+        }
+    }
+
     fn infer_method_call(
         &mut self,
         tgt_expr: ExprId,
@@ -575,13 +779,33 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 method_name,
             )
         });
+        let mut derefed_shared_ref = false;
         let (derefed_receiver_ty, method_ty, def_generics) = match resolved {
             Some((ty, func)) => {
+                derefed_shared_ref = ty != canonicalized_receiver.value.value
+                    && canonicalized_receiver.value.value.as_reference().map(|(_, m)| m)
+                        == Some(Mutability::Shared);
                 let ty = canonicalized_receiver.decanonicalize_ty(ty);
                 self.write_method_resolution(tgt_expr, func);
                 (ty, self.db.value_ty(func.into()), Some(generics(self.db, func.into())))
             }
-            None => (receiver_ty, Binders::new(0, Ty::Unknown), None),
+            None => {
+                let trait_not_in_scope = self.check_method_trait_not_in_scope(
+                    tgt_expr,
+                    &canonicalized_receiver.value,
+                    &traits_in_scope,
+                    method_name,
+                );
+                if !trait_not_in_scope {
+                    self.check_unresolved_method_call(
+                        tgt_expr,
+                        &canonicalized_receiver.value,
+                        &traits_in_scope,
+                        method_name,
+                    );
+                }
+                (receiver_ty, Binders::new(0, Ty::Unknown), None)
+            }
         };
         let substs = self.substs_for_method_call(def_generics, generic_args, &derefed_receiver_ty);
         let method_ty = method_ty.subst(&substs);
@@ -597,6 +821,16 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             }
             None => (Ty::Unknown, Vec::new(), Ty::Unknown),
         };
+        if derefed_shared_ref {
+            if let Some((_, Mutability::Mut)) = expected_receiver_ty.as_reference() {
+                self.push_diagnostic(InferenceDiagnostic::UnresolvedMethodCall {
+                    expr: tgt_expr,
+                    method: method_name.clone(),
+                    receiver_ty: canonicalized_receiver.value.value.clone(),
+                    suggestion: Some(UnresolvedMethodSuggestion::MutableReceiver),
+                });
+            }
+        }
         // Apply autoref so the below unification works correctly
         // FIXME: return correct autorefs from lookup_method
         let actual_receiver_ty = match expected_receiver_ty.as_reference() {
@@ -609,6 +843,130 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         self.normalize_associated_types_in(ret_ty)
     }
 
+    /// Reports unresolved method calls to methods of traits which are
+    /// implemented for the receiver, but which are not in scope. Returns
+    /// whether such a method was found.
+    fn check_method_trait_not_in_scope(
+        &mut self,
+        tgt_expr: ExprId,
+        receiver_ty: &Canonical<Ty>,
+        traits_in_scope: &FxHashSet<TraitId>,
+        method_name: &Name,
+    ) -> bool {
+        match receiver_ty.value {
+            Ty::Unknown | Ty::Bound(_) => return false,
+            _ => (),
+        }
+        let (krate, module) = match (self.resolver.krate(), self.resolver.module()) {
+            (Some(krate), Some(module)) => (krate, module),
+            _ => return false,
+        };
+        let candidate = method_resolution::iterate_method_candidates_with_reasons(
+            receiver_ty,
+            self.db,
+            self.trait_env.clone(),
+            krate,
+            traits_in_scope,
+            module,
+            Some(method_name),
+            LookupMode::MethodCall,
+            |ty, item, reason| match (item, reason) {
+                (AssocItemId::FunctionId(f), Some(RejectionReason::TraitNotInScope)) => {
+                    Some((ty.clone(), f))
+                }
+                _ => None,
+            },
+        );
+        if let Some((self_ty, func)) = candidate {
+            if let AssocContainerId::TraitId(trait_) = func.lookup(self.db).container {
+                self.push_diagnostic(InferenceDiagnostic::MethodTraitNotInScope {
+                    expr: tgt_expr,
+                    method: method_name.clone(),
+                    self_ty,
+                    trait_,
+                });
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Reports unresolved method calls on receivers which are, or deref to,
+    /// an ADT, telling whether the receiver has a callable field named like
+    /// the method. Without the impls of the standard library, we can't tell
+    /// whether a method of a primitive type is missing, so these calls are
+    /// not reported.
+    ///
+    /// The method may also be missing because we failed to find its impl: it
+    /// may come from a macro we don't expand, like a proc-macro derive, or
+    /// from a trait in scope which Chalk couldn't solve. These calls aren't
+    /// reported either.
+    fn check_unresolved_method_call(
+        &mut self,
+        tgt_expr: ExprId,
+        receiver_ty: &Canonical<Ty>,
+        traits_in_scope: &FxHashSet<TraitId>,
+        method_name: &Name,
+    ) {
+        let mut has_unknown = false;
+        receiver_ty.value.walk(&mut |ty| {
+            if let Ty::Unknown = ty {
+                has_unknown = true;
+            }
+        });
+        if has_unknown {
+            return;
+        }
+        let in_trait_in_scope = traits_in_scope
+            .iter()
+            .any(|&trait_| self.db.trait_data(trait_).method_by_name(method_name).is_some());
+        if in_trait_in_scope {
+            return;
+        }
+
+        let mut is_adt = false;
+        let mut suggestion = None;
+        let derefs = autoderef::autoderef(
+            self.db,
+            self.resolver.krate(),
+            InEnvironment { value: receiver_ty.clone(), environment: self.trait_env.clone() },
+        );
+        for derefed_ty in derefs {
+            let (a_ty, adt) = match &derefed_ty.value {
+                Ty::Apply(a_ty @ ApplicationTy { ctor: TypeCtor::Adt(adt), .. }) => (a_ty, *adt),
+                _ => continue,
+            };
+            if self.db.attrs(adt.into()).has_unexpanded_macros() {
+                return;
+            }
+            is_adt = true;
+            let variant = match a_ty.ctor {
+                TypeCtor::Adt(AdtId::StructId(s)) => VariantId::from(s),
+                TypeCtor::Adt(AdtId::UnionId(u)) => VariantId::from(u),
+                _ => continue,
+            };
+            let field = match variant_data(self.db, variant).field(method_name) {
+                Some(it) => it,
+                None => continue,
+            };
+            let field_ty = self.db.field_types(variant)[field].clone().subst(&a_ty.parameters);
+            if field_ty.callable_sig(self.db).is_some() {
+                suggestion = Some(UnresolvedMethodSuggestion::CallableField);
+            }
+            break;
+        }
+        if !is_adt {
+            return;
+        }
+
+        self.push_diagnostic(InferenceDiagnostic::UnresolvedMethodCall {
+            expr: tgt_expr,
+            method: method_name.clone(),
+            receiver_ty: receiver_ty.value.clone(),
+            suggestion,
+        });
+    }
+
     fn check_call_arguments(&mut self, args: &[ExprId], param_tys: &[Ty]) {
         // Quoting https://github.com/rust-lang/rust/blob/6ef275e6c3cb1384ec78128eceeb4963ff788dca/src/librustc_typeck/check/mod.rs#L3325 --
         // We do this in a pretty awful way: first we type-check any arguments
@@ -699,4 +1057,18 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             }
         }
     }
+
+    /// Whether the closure body `body` is the body of a generator, that is it
+    /// contains `yield` outside of nested closures.
+    fn contains_yield(&self, body: ExprId) -> bool {
+        let mut stack = vec![body];
+        while let Some(expr) = stack.pop() {
+            match &self.body[expr] {
+                Expr::Yield { .. } => return true,
+                Expr::Lambda { .. } => continue,
+                it => it.walk_child_exprs(|it| stack.push(it)),
+            }
+        }
+        false
+    }
 }
@@ -11,7 +11,7 @@ use hir_def::{
 use hir_expand::name::Name;
 use test_utils::tested_by;
 
-use super::{BindingMode, InferenceContext};
+use super::{BindingMode, Expectation, InferenceContext};
 use crate::{db::HirDatabase, utils::variant_data, Substs, Ty, TypeCtor};
 
 impl<'a, D: HirDatabase> InferenceContext<'a, D> {
@@ -86,7 +86,8 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             | Pat::TupleStruct { .. }
             | Pat::Record { .. }
             | Pat::Range { .. }
-            | Pat::Slice { .. } => true,
+            | Pat::Slice { .. }
+            | Pat::Box { .. } => true,
             // FIXME: Path/Lit might actually evaluate to ref, but inference is unimplemented.
             Pat::Path(..) | Pat::Lit(..) => true,
             Pat::Wild | Pat::Bind { .. } | Pat::Ref { .. } | Pat::Missing => false,
@@ -185,6 +186,51 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 self.write_pat_ty(pat, bound_ty);
                 return inner_ty;
             }
+            Pat::Slice { prefix, slice, suffix } => {
+                let (container_ctor, elem_ty) = match expected {
+                    Ty::Apply(a_ty) => match a_ty.ctor {
+                        TypeCtor::Array { .. } | TypeCtor::Slice => {
+                            (a_ty.ctor, a_ty.parameters.as_single().clone())
+                        }
+                        _ => (TypeCtor::Slice, Ty::Unknown),
+                    },
+                    _ => (TypeCtor::Slice, Ty::Unknown),
+                };
+
+                for &pat_id in prefix.iter().chain(suffix) {
+                    self.infer_pat(pat_id, &elem_ty, default_bm);
+                }
+
+                // The rest pattern, like `tail @ ..`, matches the remaining
+                // elements: a slice, or an array of the remaining length.
+                if let Some(slice_pat_id) = slice {
+                    let rest_ctor = match container_ctor {
+                        TypeCtor::Array { len } => TypeCtor::Array {
+                            len: len.and_then(|len| {
+                                len.checked_sub((prefix.len() + suffix.len()) as u64)
+                            }),
+                        },
+                        ctor => ctor,
+                    };
+                    let rest_ty = Ty::apply_one(rest_ctor, elem_ty.clone());
+                    self.infer_pat(*slice_pat_id, &rest_ty, default_bm);
+                }
+
+                Ty::apply_one(container_ctor, elem_ty)
+            }
+            Pat::Lit(expr) => self.infer_expr(*expr, &Expectation::none()),
+            Pat::Box { inner } => match self.resolve_boxed_box() {
+                Some(box_adt) => {
+                    let inner_expected = match expected.as_adt() {
+                        Some((adt, substs)) if adt == box_adt => substs.as_single().clone(),
+                        _ => Ty::Unknown,
+                    };
+
+                    let inner_ty = self.infer_pat(*inner, &inner_expected, default_bm);
+                    Ty::apply_one(TypeCtor::Adt(box_adt), inner_ty)
+                }
+                None => Ty::Unknown,
+            },
             _ => Ty::Unknown,
         };
         // use a new type variable if we got Ty::Unknown here
@@ -5,11 +5,13 @@ use std::iter;
 use hir_def::{
     path::{Path, PathSegment},
     resolver::{ResolveValueResult, Resolver, TypeNs, ValueNs},
-    AssocContainerId, AssocItemId, Lookup,
+    AssocContainerId, AssocItemId, Lookup, TypeParamId,
 };
 use hir_expand::name::Name;
 
-use crate::{db::HirDatabase, method_resolution, Substs, Ty, ValueTyDefId};
+use crate::{
+    db::HirDatabase, method_resolution, utils::all_super_trait_refs, Substs, Ty, ValueTyDefId,
+};
 
 use super::{ExprOrPatId, InferenceContext, TraitRef};
 
@@ -99,13 +101,30 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         let remaining_segments = path.segments().skip(remaining_index);
         let is_before_last = remaining_segments.len() == 1;
 
+        if let (TypeNs::GenericParam(param_id), true) = (&def, is_before_last) {
+            // `T::foo` with `T: Trait` in the environment: look for `foo` in
+            // the bounds of `T` first, so that the trait's parameters are
+            // known and the trait doesn't need to be in scope.
+            let segment =
+                remaining_segments.last().expect("there should be at least one segment here");
+            if let Some(res) = self.resolve_param_bound_assoc_item(*param_id, segment, id) {
+                return Some(res);
+            }
+        }
+
         match (def, is_before_last) {
             (TypeNs::TraitId(trait_), true) => {
                 let segment =
                     remaining_segments.last().expect("there should be at least one segment here");
                 let ctx = crate::lower::TyLoweringContext::new(self.db, &self.resolver);
                 let trait_ref = TraitRef::from_resolved_path(&ctx, trait_, resolved_segment, None);
-                self.resolve_trait_assoc_item(trait_ref, segment, id)
+                // items of super traits can be accessed through the sub trait,
+                // like `Sub::SUPER_CONST`, but the sub trait's items win
+                iter::once(trait_ref.clone())
+                    .chain(all_super_trait_refs(self.db, trait_ref))
+                    .find_map(|trait_ref| {
+                        self.resolve_trait_assoc_item(trait_ref, segment.clone(), id)
+                    })
             }
             (def, _) => {
                 // Either we already have a type (e.g. `Vec::new`), or we have a
@@ -180,6 +199,26 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         Some((def, Some(trait_ref.substs)))
     }
 
+    fn resolve_param_bound_assoc_item(
+        &mut self,
+        param_id: TypeParamId,
+        segment: PathSegment<'_>,
+        id: ExprOrPatId,
+    ) -> Option<(ValueNs, Option<Substs>)> {
+        let param_ty = Ty::Placeholder(param_id);
+        let trait_refs: Vec<TraitRef> = self
+            .trait_env
+            .trait_predicates_for_self_ty(&param_ty)
+            .cloned()
+            .flat_map(|trait_ref| {
+                iter::once(trait_ref.clone()).chain(all_super_trait_refs(self.db, trait_ref))
+            })
+            .collect();
+        trait_refs
+            .into_iter()
+            .find_map(|trait_ref| self.resolve_trait_assoc_item(trait_ref, segment.clone(), id))
+    }
+
     fn resolve_ty_assoc_item(
         &mut self,
         ty: Ty,
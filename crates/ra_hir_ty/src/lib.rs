@@ -32,6 +32,7 @@ pub(crate) mod utils;
 pub mod db;
 pub mod diagnostics;
 pub mod expr;
+mod match_check;
 
 #[cfg(test)]
 mod tests;
@@ -57,12 +58,13 @@ use crate::{
 use display::HirDisplay;
 
 pub use autoderef::autoderef;
-pub use infer::{do_infer_query, InferTy, InferenceResult};
+pub use infer::{do_infer_query, dump_body, InferTy, InferenceResult};
 pub use lower::CallableDef;
 pub use lower::{
     callable_item_sig, ImplTraitLoweringMode, TyDefId, TyLoweringContext, ValueTyDefId,
 };
 pub use traits::{InEnvironment, Obligation, ProjectionPredicate, TraitEnvironment};
+pub use utils::all_super_traits;
 
 /// A type constructor or type name: this might be something like the primitive
 /// type `bool`, a struct like `Vec`, or things like function pointers or
@@ -91,8 +93,9 @@ pub enum TypeCtor {
     /// The pointee of an array slice.  Written as `[T]`.
     Slice,
 
-    /// An array with the given length. Written as `[T; n]`.
-    Array,
+    /// An array with the given length. Written as `[T; n]`. The length is
+    /// `None` if it couldn't be evaluated.
+    Array { len: Option<u64> },
 
     /// A raw pointer. Written as `*mut T` or `*const T`
     RawPtr(Mutability),
@@ -161,7 +164,7 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never => 0,
             TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array { .. }
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::Closure { .. } // 1 param representing the signature of the closure
@@ -192,7 +195,7 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never
             | TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array { .. }
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::FnPtr { .. }
@@ -214,7 +217,7 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never
             | TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array { .. }
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::FnPtr { .. }
@@ -225,6 +228,18 @@ impl TypeCtor {
             TypeCtor::AssociatedType(type_alias) => Some(type_alias.into()),
         }
     }
+
+    /// Whether types with these constructors may be unified. An array of
+    /// unknown length is compatible with arrays of any length.
+    pub(crate) fn unifies_with(self, other: TypeCtor) -> bool {
+        match (self, other) {
+            (TypeCtor::Array { len: Some(len1) }, TypeCtor::Array { len: Some(len2) }) => {
+                len1 == len2
+            }
+            (TypeCtor::Array { .. }, TypeCtor::Array { .. }) => true,
+            _ => self == other,
+        }
+    }
 }
 
 /// A nominal type with (maybe 0) type parameters. This might be a primitive
@@ -574,6 +589,16 @@ pub struct Canonical<T> {
     pub num_vars: usize,
 }
 
+impl<T: TypeWalk> Canonical<T> {
+    /// Returns the value with the variables replaced by `Ty::Unknown`. The
+    /// variables are only meaningful together with the `Canonical`, so this is
+    /// needed before passing the value on, e.g. to show it to the user.
+    pub fn into_value_with_unknowns(self) -> T {
+        let unknowns = Substs(iter::repeat(Ty::Unknown).take(self.num_vars).collect());
+        self.value.subst_bound_vars(&unknowns)
+    }
+}
+
 /// A function signature as seen by type inference: Several parameter types and
 /// one return type.
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -11,10 +11,11 @@ use std::sync::Arc;
 use hir_def::{
     adt::StructKind,
     builtin_type::BuiltinType,
+    expr::{Expr, Literal},
     generics::{TypeParamProvenance, WherePredicate, WherePredicateTarget},
     path::{GenericArg, Path, PathSegment, PathSegments},
-    resolver::{HasResolver, Resolver, TypeNs},
-    type_ref::{TypeBound, TypeRef},
+    resolver::{HasResolver, Resolver, TypeNs, ValueNs},
+    type_ref::{ArrayLen, TypeBound, TypeRef},
     AdtId, AssocContainerId, ConstId, EnumId, EnumVariantId, FunctionId, GenericDefId, HasModule,
     ImplId, LocalStructFieldId, Lookup, StaticId, StructId, TraitId, TypeAliasId, TypeParamId,
     UnionId, VariantId,
@@ -30,7 +31,7 @@ use crate::{
         variant_data,
     },
     Binders, FnSig, GenericPredicate, PolyFnSig, ProjectionPredicate, ProjectionTy, Substs,
-    TraitEnvironment, TraitRef, Ty, TypeCtor,
+    TraitEnvironment, TraitRef, Ty, TypeCtor, TypeWalk,
 };
 
 #[derive(Debug)]
@@ -44,6 +45,10 @@ pub struct TyLoweringContext<'a, DB: HirDatabase> {
     pub type_param_mode: TypeParamLoweringMode,
     pub impl_trait_mode: ImplTraitLoweringMode,
     pub impl_trait_counter: std::cell::Cell<u16>,
+    /// The number of binders we're currently inside of, e.g. the self type
+    /// binders of `dyn Trait` and `impl Trait`. Type parameters lowered as
+    /// bound variables need to be shifted by this amount.
+    pub in_binders: std::cell::Cell<u32>,
 }
 
 impl<'a, DB: HirDatabase> TyLoweringContext<'a, DB> {
@@ -51,7 +56,22 @@ impl<'a, DB: HirDatabase> TyLoweringContext<'a, DB> {
         let impl_trait_counter = std::cell::Cell::new(0);
         let impl_trait_mode = ImplTraitLoweringMode::Disallowed;
         let type_param_mode = TypeParamLoweringMode::Placeholder;
-        Self { db, resolver, impl_trait_mode, impl_trait_counter, type_param_mode }
+        let in_binders = std::cell::Cell::new(0);
+        Self { db, resolver, impl_trait_mode, impl_trait_counter, type_param_mode, in_binders }
+    }
+
+    /// Runs `f` with one more binder in scope.
+    pub fn with_shifted_in<T>(&self, f: impl FnOnce(&Self) -> T) -> T {
+        self.in_binders.set(self.in_binders.get() + 1);
+        let res = f(self);
+        self.in_binders.set(self.in_binders.get() - 1);
+        res
+    }
+
+    /// The bound variable for the generic parameter with index `idx`, taking
+    /// into account the binders we're currently in.
+    fn bound_var(&self, idx: u32) -> Ty {
+        Ty::Bound(idx + self.in_binders.get())
     }
 
     pub fn with_impl_trait_mode(self, impl_trait_mode: ImplTraitLoweringMode) -> Self {
@@ -70,9 +90,10 @@ pub enum ImplTraitLoweringMode {
     /// i.e. for arguments of the function we're currently checking, and return
     /// types of functions we're calling.
     Opaque,
-    /// `impl Trait` gets lowered into a type variable. Used for argument
-    /// position impl Trait when inside the respective function, since it allows
-    /// us to support that without Chalk.
+    /// `impl Trait` gets lowered into a placeholder for the anonymous type
+    /// parameter it desugars to, whose bounds are part of the function's where
+    /// clauses and hence of the `TraitEnvironment`. Used for argument position
+    /// impl Trait when inside the respective function.
     Param,
     /// `impl Trait` gets lowered into a variable that can unify with some
     /// type. This is used in places where values flow 'in', i.e. for arguments
@@ -105,9 +126,14 @@ impl Ty {
                 let inner_ty = Ty::from_hir(ctx, inner);
                 Ty::apply_one(TypeCtor::RawPtr(*mutability), inner_ty)
             }
-            TypeRef::Array(inner) => {
+            TypeRef::Array(inner, len) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
-                Ty::apply_one(TypeCtor::Array, inner_ty)
+                let len = match len {
+                    ArrayLen::Literal(len) => Some(*len),
+                    ArrayLen::Path(path) => eval_const_path(ctx.db, ctx.resolver, path),
+                    ArrayLen::Unknown => None,
+                };
+                Ty::apply_one(TypeCtor::Array { len }, inner_ty)
             }
             TypeRef::Slice(inner) => {
                 let inner_ty = Ty::from_hir(ctx, inner);
@@ -124,22 +150,29 @@ impl Ty {
             }
             TypeRef::DynTrait(bounds) => {
                 let self_ty = Ty::Bound(0);
-                let predicates = bounds
-                    .iter()
-                    .flat_map(|b| GenericPredicate::from_type_bound(ctx, b, self_ty.clone()))
-                    .collect();
+                let predicates = ctx.with_shifted_in(|ctx| {
+                    bounds
+                        .iter()
+                        .flat_map(|b| GenericPredicate::from_type_bound(ctx, b, self_ty.clone()))
+                        .collect()
+                });
                 Ty::Dyn(predicates)
             }
             TypeRef::ImplTrait(bounds) => {
                 match ctx.impl_trait_mode {
                     ImplTraitLoweringMode::Opaque => {
+                        // the bounds may mention the generic parameters of the
+                        // function (or `Self` in a trait method), which have to be
+                        // shifted past the binder for the opaque self type
                         let self_ty = Ty::Bound(0);
-                        let predicates = bounds
-                            .iter()
-                            .flat_map(|b| {
-                                GenericPredicate::from_type_bound(ctx, b, self_ty.clone())
-                            })
-                            .collect();
+                        let predicates = ctx.with_shifted_in(|ctx| {
+                            bounds
+                                .iter()
+                                .flat_map(|b| {
+                                    GenericPredicate::from_type_bound(ctx, b, self_ty.clone())
+                                })
+                                .collect()
+                        });
                         Ty::Opaque(predicates)
                     }
                     ImplTraitLoweringMode::Param => {
@@ -169,7 +202,7 @@ impl Ty {
                             } else {
                                 (0, 0, 0, 0)
                             };
-                        Ty::Bound(
+                        ctx.bound_var(
                             idx as u32
                                 + parent_params as u32
                                 + self_params as u32
@@ -277,7 +310,7 @@ impl Ty {
                     TypeParamLoweringMode::Placeholder => Ty::Placeholder(param_id),
                     TypeParamLoweringMode::Variable => {
                         let idx = generics.param_idx(param_id).expect("matching generics");
-                        Ty::Bound(idx)
+                        ctx.bound_var(idx)
                     }
                 }
             }
@@ -287,7 +320,9 @@ impl Ty {
                     TypeParamLoweringMode::Placeholder => {
                         Substs::type_params_for_generics(&generics)
                     }
-                    TypeParamLoweringMode::Variable => Substs::bound_vars(&generics),
+                    TypeParamLoweringMode::Variable => {
+                        Substs::bound_vars(&generics).shift_bound_vars(ctx.in_binders.get() as i32)
+                    }
                 };
                 ctx.db.impl_self_ty(impl_id).subst(&substs)
             }
@@ -297,7 +332,9 @@ impl Ty {
                     TypeParamLoweringMode::Placeholder => {
                         Substs::type_params_for_generics(&generics)
                     }
-                    TypeParamLoweringMode::Variable => Substs::bound_vars(&generics),
+                    TypeParamLoweringMode::Variable => {
+                        Substs::bound_vars(&generics).shift_bound_vars(ctx.in_binders.get() as i32)
+                    }
                 };
                 ctx.db.ty(adt.into()).subst(&substs)
             }
@@ -346,6 +383,10 @@ impl Ty {
             Ty::Placeholder(id) if ctx.type_param_mode == TypeParamLoweringMode::Placeholder => id,
             Ty::Bound(idx) if ctx.type_param_mode == TypeParamLoweringMode::Variable => {
                 let generics = generics(ctx.db, def);
+                let idx = match idx.checked_sub(ctx.in_binders.get()) {
+                    Some(idx) => idx,
+                    None => return Ty::Unknown,
+                };
                 let param_id = if let Some((id, _)) = generics.iter().nth(idx as usize) {
                     id
                 } else {
@@ -549,7 +590,7 @@ impl GenericPredicate {
                     TypeParamLoweringMode::Placeholder => Ty::Placeholder(param_id),
                     TypeParamLoweringMode::Variable => {
                         let idx = generics.param_idx(param_id).expect("matching generics");
-                        Ty::Bound(idx)
+                        ctx.bound_var(idx)
                     }
                 }
             }
@@ -600,6 +641,24 @@ fn assoc_type_bindings_from_type_bound<'a>(
         })
 }
 
+/// Evaluates a path to a constant whose body is an integer literal, like the
+/// `N` in `[u8; N]`.
+pub(crate) fn eval_const_path(
+    db: &impl HirDatabase,
+    resolver: &Resolver,
+    path: &Path,
+) -> Option<u64> {
+    let const_id = match resolver.resolve_path_in_value_ns_fully(db, path.mod_path())? {
+        ValueNs::ConstId(it) => it,
+        _ => return None,
+    };
+    let body = db.body(const_id.into());
+    match &body[body.body_expr] {
+        Expr::Literal(Literal::Int(value, _)) => Some(*value),
+        _ => None,
+    }
+}
+
 /// Build the signature of a callable item (function, struct or enum variant).
 pub fn callable_item_sig(db: &impl HirDatabase, def: CallableDef) -> PolyFnSig {
     match def {
@@ -679,6 +738,7 @@ impl TraitEnvironment {
             .flat_map(|pred| GenericPredicate::from_where_predicate(&ctx, pred))
             .collect::<Vec<_>>();
 
+        let mut self_trait_ref = None;
         if let Some(def) = resolver.generic_def() {
             let container: Option<AssocContainerId> = match def {
                 // FIXME: is there a function for this?
@@ -697,16 +757,75 @@ impl TraitEnvironment {
                 test_utils::tested_by!(trait_self_implements_self);
                 let substs = Substs::type_params(db, trait_id);
                 let trait_ref = TraitRef { trait_: trait_id, substs };
-                let pred = GenericPredicate::Implemented(trait_ref);
+                let pred = GenericPredicate::Implemented(trait_ref.clone());
 
                 predicates.push(pred);
+                self_trait_ref = Some(trait_ref);
             }
         }
 
+        let normalize_clauses =
+            associated_type_normalize_clauses(&ctx, &predicates, self_trait_ref.as_ref());
+        predicates.extend(normalize_clauses);
+
         Arc::new(TraitEnvironment { predicates })
     }
 }
 
+/// For every `T: Trait` in the environment where `T` is a type parameter, adds
+/// `Normalize(<T as Trait>::Assoc -> Trait::Assoc<T>)` for the associated types
+/// of `Trait` that aren't already constrained by the environment. Chalk can't
+/// come up with these placeholder types by itself.
+///
+/// Inside the items of a trait, the associated types of the trait itself
+/// instead normalize to their default (`type Assoc = Foo;`), if they have one.
+fn associated_type_normalize_clauses(
+    ctx: &TyLoweringContext<'_, impl HirDatabase>,
+    predicates: &[GenericPredicate],
+    self_trait_ref: Option<&TraitRef>,
+) -> Vec<GenericPredicate> {
+    let mut result = Vec::new();
+    for pred in predicates {
+        let trait_ref = match pred {
+            GenericPredicate::Implemented(tr) => tr,
+            _ => continue,
+        };
+        match trait_ref.self_ty() {
+            Ty::Placeholder(_) => {}
+            _ => continue,
+        }
+        for associated_ty in ctx.db.trait_data(trait_ref.trait_).associated_types() {
+            let projection_ty =
+                ProjectionTy { associated_ty, parameters: trait_ref.substs.clone() };
+            let already_constrained =
+                predicates.iter().chain(result.iter()).any(|pred| match pred {
+                    GenericPredicate::Projection(proj) => proj.projection_ty == projection_ty,
+                    _ => false,
+                });
+            if already_constrained {
+                continue;
+            }
+            let default = if Some(trait_ref) == self_trait_ref {
+                ctx.db.type_alias_data(associated_ty).type_ref.as_ref()
+            } else {
+                None
+            };
+            let ty = match default {
+                Some(type_ref) => {
+                    test_utils::tested_by!(trait_assoc_type_default);
+                    Ty::from_hir(ctx, type_ref)
+                }
+                None => Ty::apply(
+                    TypeCtor::AssociatedType(associated_ty),
+                    projection_ty.parameters.clone(),
+                ),
+            };
+            result.push(GenericPredicate::Projection(ProjectionPredicate { projection_ty, ty }));
+        }
+    }
+    result
+}
+
 /// Resolve the where clause(s) of an item with generics.
 pub(crate) fn generic_predicates_query(
     db: &impl HirDatabase,
@@ -849,8 +968,10 @@ fn type_for_adt(db: &impl HirDatabase, adt: AdtId) -> Binders<Ty> {
 fn type_for_type_alias(db: &impl HirDatabase, t: TypeAliasId) -> Binders<Ty> {
     let generics = generics(db, t.into());
     let resolver = t.resolver(db);
-    let ctx =
-        TyLoweringContext::new(db, &resolver).with_type_param_mode(TypeParamLoweringMode::Variable);
+    // `type Foo = impl Trait;` defines an opaque type
+    let ctx = TyLoweringContext::new(db, &resolver)
+        .with_type_param_mode(TypeParamLoweringMode::Variable)
+        .with_impl_trait_mode(ImplTraitLoweringMode::Opaque);
     let type_ref = &db.type_alias_data(t).type_ref;
     let substs = Substs::bound_vars(&generics);
     let inner = Ty::from_hir(&ctx, type_ref.as_ref().unwrap_or(&TypeRef::Error));
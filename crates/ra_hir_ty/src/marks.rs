@@ -8,4 +8,5 @@ test_utils::marks!(
     match_ergonomics_ref
     coerce_merge_fail_fallback
     trait_self_implements_self
+    trait_assoc_type_default
 );
@@ -0,0 +1,366 @@
+//! Exhaustiveness checking for `match` expressions.
+//!
+//! This is a simplified version of the usefulness algorithm used by rustc
+//! (see `librustc_mir_build/hair/pattern/_match.rs`). Patterns are lowered to
+//! a constructor applied to sub-patterns, and the matrix formed by the arms is
+//! specialized constructor by constructor until we either cover everything or
+//! find a combination of constructors that no arm matches; those
+//! combinations are the missing patterns.
+//!
+//! We only know how to enumerate the constructors of enums, structs, tuples
+//! and `bool`. Patterns of any other type (integers, strings, slices, ...)
+//! are assumed to be exhaustive, as are bindings and unresolved patterns, so
+//! that we never report a false positive.
+
+use std::{iter, sync::Arc};
+
+use hir_def::{
+    adt::VariantData,
+    body::Body,
+    expr::{Expr, Literal, MatchArm, Pat, PatId},
+    AdtId, EnumVariantId, VariantId,
+};
+
+use crate::{
+    db::HirDatabase, utils::variant_data, ApplicationTy, InferenceResult, Substs, Ty, TypeCtor,
+};
+
+/// Returns the patterns which are not covered by `arms`, rendered as source
+/// text, or an empty list if the match is exhaustive. Arms with a guard don't
+/// count as covering their pattern.
+pub(crate) fn missing_patterns(
+    db: &impl HirDatabase,
+    body: &Body,
+    infer: &InferenceResult,
+    scrutinee_ty: &Ty,
+    arms: &[MatchArm],
+) -> Vec<String> {
+    let cx = MatchCheckCtx { db, body, infer };
+    let matrix: Vec<Vec<LoweredPat>> = arms
+        .iter()
+        .filter(|arm| arm.guard.is_none())
+        .map(|arm| vec![cx.lower_pat(arm.pat, scrutinee_ty)])
+        .collect();
+    cx.missing(matrix, &[scrutinee_ty.clone()]).into_iter().flatten().collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Constructor {
+    Bool(bool),
+    Variant(EnumVariantId),
+    /// The only constructor of a struct or tuple type.
+    Single,
+}
+
+/// A pattern reduced to what matters for exhaustiveness checking.
+#[derive(Debug, Clone)]
+enum LoweredPat {
+    Wild,
+    Ctor(Constructor, Vec<LoweredPat>),
+    Or(Vec<LoweredPat>),
+}
+
+struct MatchCheckCtx<'a, DB> {
+    db: &'a DB,
+    body: &'a Body,
+    infer: &'a InferenceResult,
+}
+
+impl<DB: HirDatabase> MatchCheckCtx<'_, DB> {
+    fn lower_pat(&self, pat: PatId, ty: &Ty) -> LoweredPat {
+        // References are transparent for our purposes: match ergonomics
+        // allow matching `&Option<T>` against `Some(_)`.
+        let ty = strip_references(ty);
+        match &self.body[pat] {
+            Pat::Wild | Pat::Missing | Pat::Bind { subpat: None, .. } => LoweredPat::Wild,
+            Pat::Bind { subpat: Some(subpat), .. } => self.lower_pat(*subpat, ty),
+            Pat::Ref { pat, .. } => self.lower_pat(*pat, ty),
+            Pat::Or(pats) => {
+                LoweredPat::Or(pats.iter().map(|it| self.lower_pat(*it, ty)).collect())
+            }
+            Pat::Lit(expr) => match (&self.body[*expr], ty) {
+                (
+                    Expr::Literal(Literal::Bool(b)),
+                    Ty::Apply(ApplicationTy { ctor: TypeCtor::Bool, .. }),
+                ) => LoweredPat::Ctor(Constructor::Bool(*b), Vec::new()),
+                _ => LoweredPat::Wild,
+            },
+            Pat::Tuple(args) => match ty.as_tuple() {
+                Some(field_tys) if field_tys.len() == args.len() => {
+                    let fields =
+                        args.iter().zip(field_tys.iter()).map(|(p, t)| self.lower_pat(*p, t));
+                    LoweredPat::Ctor(Constructor::Single, fields.collect())
+                }
+                _ => LoweredPat::Wild,
+            },
+            Pat::TupleStruct { args, .. } => {
+                let (ctor, field_tys) = match self.resolve_ctor(pat, ty) {
+                    Some(it) => it,
+                    None => return LoweredPat::Wild,
+                };
+                if args.len() != field_tys.len() {
+                    // FIXME: handle `..` in tuple struct patterns
+                    return LoweredPat::Wild;
+                }
+                let fields = args.iter().zip(field_tys.iter()).map(|(p, t)| self.lower_pat(*p, t));
+                LoweredPat::Ctor(ctor, fields.collect())
+            }
+            Pat::Record { args, .. } => {
+                let (ctor, field_tys) = match self.resolve_ctor(pat, ty) {
+                    Some(it) => it,
+                    None => return LoweredPat::Wild,
+                };
+                let variant = match self.infer.variant_resolution_for_pat(pat) {
+                    Some(it) => it,
+                    None => return LoweredPat::Wild,
+                };
+                let variant_data = variant_data(self.db, variant);
+                let fields = variant_data
+                    .fields()
+                    .iter()
+                    .zip(field_tys.iter())
+                    .map(|((_, field), field_ty)| {
+                        match args.iter().find(|arg| arg.name == field.name) {
+                            Some(arg) => self.lower_pat(arg.pat, field_ty),
+                            None => LoweredPat::Wild,
+                        }
+                    })
+                    .collect();
+                LoweredPat::Ctor(ctor, fields)
+            }
+            Pat::Path(_) => match self.resolve_ctor(pat, ty) {
+                Some((ctor, field_tys)) => {
+                    LoweredPat::Ctor(ctor, field_tys.iter().map(|_| LoweredPat::Wild).collect())
+                }
+                None => LoweredPat::Wild,
+            },
+            Pat::Range { .. } | Pat::Slice { .. } | Pat::Box { .. } => LoweredPat::Wild,
+        }
+    }
+
+    /// Finds the constructor a struct or enum variant pattern refers to, if it
+    /// is a constructor of `ty`.
+    fn resolve_ctor(&self, pat: PatId, ty: &Ty) -> Option<(Constructor, Vec<Ty>)> {
+        let ctor = match (self.infer.variant_resolution_for_pat(pat)?, ty.as_adt()?.0) {
+            (VariantId::EnumVariantId(variant), AdtId::EnumId(e)) if variant.parent == e => {
+                Constructor::Variant(variant)
+            }
+            (VariantId::StructId(s), AdtId::StructId(s2)) if s == s2 => Constructor::Single,
+            _ => return None,
+        };
+        Some((ctor, self.field_tys(ctor, ty)))
+    }
+
+    /// Returns all constructors of `ty`, or `None` if we can't enumerate them.
+    fn all_constructors(&self, ty: &Ty) -> Option<Vec<Constructor>> {
+        match strip_references(ty) {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Bool, .. }) => {
+                Some(vec![Constructor::Bool(true), Constructor::Bool(false)])
+            }
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Tuple { .. }, .. }) => {
+                Some(vec![Constructor::Single])
+            }
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Adt(AdtId::StructId(_)), .. }) => {
+                Some(vec![Constructor::Single])
+            }
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Adt(AdtId::EnumId(e)), .. }) => {
+                let enum_data = self.db.enum_data(*e);
+                let variants = enum_data.variants.iter().map(|(local_id, _)| {
+                    Constructor::Variant(EnumVariantId { parent: *e, local_id })
+                });
+                Some(variants.collect())
+            }
+            _ => None,
+        }
+    }
+
+    fn field_tys(&self, ctor: Constructor, ty: &Ty) -> Vec<Ty> {
+        let ty = strip_references(ty);
+        let variant: VariantId = match (ctor, ty) {
+            (Constructor::Bool(_), _) => return Vec::new(),
+            (Constructor::Variant(variant), _) => variant.into(),
+            (
+                Constructor::Single,
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::Tuple { .. }, parameters }),
+            ) => {
+                return parameters.iter().cloned().collect();
+            }
+            (
+                Constructor::Single,
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::Adt(AdtId::StructId(s)), .. }),
+            ) => (*s).into(),
+            (Constructor::Single, _) => return Vec::new(),
+        };
+        let parameters = match ty.as_adt() {
+            Some((_, parameters)) => parameters.clone(),
+            None => Substs::empty(),
+        };
+        let field_types = self.db.field_types(variant);
+        variant_data(self.db, variant)
+            .fields()
+            .iter()
+            .map(|(local_id, _)| field_types[local_id].clone().subst(&parameters))
+            .collect()
+    }
+
+    /// Computes the rows of patterns (one pattern per column of `tys`) that
+    /// are not covered by any row of `matrix`.
+    fn missing(&self, matrix: Vec<Vec<LoweredPat>>, tys: &[Ty]) -> Vec<Vec<String>> {
+        let (ty, rest_tys) = match tys.split_first() {
+            Some(it) => it,
+            None => {
+                // No columns left: we're covered iff any row got this far.
+                return if matrix.is_empty() { vec![Vec::new()] } else { Vec::new() };
+            }
+        };
+        let matrix = expand_or_patterns(matrix);
+
+        let ctors = match self.all_constructors(ty) {
+            Some(it) => it,
+            None => {
+                // We can't enumerate this type, so assume it's fully covered.
+                let matrix = matrix.into_iter().map(|row| row[1..].to_vec()).collect();
+                return with_head("_".to_string(), self.missing(matrix, rest_tys));
+            }
+        };
+
+        let used_ctors: Vec<Constructor> = matrix
+            .iter()
+            .filter_map(|row| match &row[0] {
+                LoweredPat::Ctor(ctor, _) => Some(*ctor),
+                _ => None,
+            })
+            .collect();
+        let missing_ctors: Vec<Constructor> =
+            ctors.iter().copied().filter(|ctor| !used_ctors.contains(ctor)).collect();
+
+        // Rows starting with a wildcard are the only ones that can match a
+        // constructor which doesn't appear in the first column.
+        let default_matrix = || {
+            matrix
+                .iter()
+                .filter(|row| match &row[0] {
+                    LoweredPat::Wild => true,
+                    _ => false,
+                })
+                .map(|row| row[1..].to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        if used_ctors.is_empty() && !ctors.is_empty() {
+            return with_head("_".to_string(), self.missing(default_matrix(), rest_tys));
+        }
+
+        let mut res = Vec::new();
+        if !missing_ctors.is_empty() {
+            let witnesses = self.missing(default_matrix(), rest_tys);
+            if !witnesses.is_empty() {
+                for ctor in missing_ctors {
+                    let fields = vec!["_".to_string(); self.field_tys(ctor, ty).len()];
+                    let head = self.render(ctor, ty, fields);
+                    res.extend(with_head(head, witnesses.clone()));
+                }
+            }
+        }
+        for ctor in ctors.into_iter().filter(|ctor| used_ctors.contains(ctor)) {
+            let field_tys = self.field_tys(ctor, ty);
+            let arity = field_tys.len();
+            let specialized =
+                matrix.iter().filter_map(|row| specialize(row, ctor, arity)).collect::<Vec<_>>();
+            let sub_tys: Vec<Ty> = field_tys.into_iter().chain(rest_tys.iter().cloned()).collect();
+            for mut witness in self.missing(specialized, &sub_tys) {
+                let rest = witness.split_off(arity);
+                let head = self.render(ctor, ty, witness);
+                res.push(iter::once(head).chain(rest).collect());
+            }
+        }
+        res
+    }
+
+    fn render(&self, ctor: Constructor, ty: &Ty, fields: Vec<String>) -> String {
+        match (ctor, strip_references(ty)) {
+            (Constructor::Bool(b), _) => b.to_string(),
+            (
+                Constructor::Single,
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::Tuple { .. }, .. }),
+            ) => {
+                if fields.len() == 1 {
+                    format!("({},)", fields[0])
+                } else {
+                    format!("({})", fields.join(", "))
+                }
+            }
+            (
+                Constructor::Single,
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::Adt(AdtId::StructId(s)), .. }),
+            ) => {
+                let struct_data = self.db.struct_data(*s);
+                render_variant(struct_data.name.to_string(), &struct_data.variant_data, fields)
+            }
+            (Constructor::Variant(variant), _) => {
+                let enum_data = self.db.enum_data(variant.parent);
+                let variant_data = &enum_data.variants[variant.local_id];
+                let path = format!("{}::{}", enum_data.name, variant_data.name);
+                render_variant(path, &variant_data.variant_data, fields)
+            }
+            (Constructor::Single, _) => "_".to_string(),
+        }
+    }
+}
+
+fn render_variant(path: String, variant_data: &Arc<VariantData>, fields: Vec<String>) -> String {
+    match &**variant_data {
+        VariantData::Unit => path,
+        VariantData::Tuple(_) => format!("{}({})", path, fields.join(", ")),
+        VariantData::Record(field_data) => {
+            let fields: Vec<String> = field_data
+                .iter()
+                .zip(fields)
+                .filter(|(_, pat)| pat != "_")
+                .map(|((_, field), pat)| format!("{}: {}", field.name, pat))
+                .chain(iter::once("..".to_string()))
+                .collect();
+            format!("{} {{ {} }}", path, fields.join(", "))
+        }
+    }
+}
+
+fn strip_references(mut ty: &Ty) -> &Ty {
+    while let Some((inner, _)) = ty.as_reference() {
+        ty = inner;
+    }
+    ty
+}
+
+/// Replaces rows starting with an or-pattern by one row per alternative.
+fn expand_or_patterns(matrix: Vec<Vec<LoweredPat>>) -> Vec<Vec<LoweredPat>> {
+    let mut res = Vec::with_capacity(matrix.len());
+    for row in matrix {
+        match &row[0] {
+            LoweredPat::Or(alternatives) => {
+                let rows = alternatives
+                    .iter()
+                    .map(|alt| iter::once(alt.clone()).chain(row[1..].iter().cloned()).collect())
+                    .collect();
+                res.extend(expand_or_patterns(rows));
+            }
+            _ => res.push(row),
+        }
+    }
+    res
+}
+
+/// Specializes `row` for `ctor`: the first pattern is replaced by its
+/// sub-patterns if it matches `ctor`, and the row is dropped otherwise.
+fn specialize(row: &[LoweredPat], ctor: Constructor, arity: usize) -> Option<Vec<LoweredPat>> {
+    let head: Vec<LoweredPat> = match &row[0] {
+        LoweredPat::Wild => vec![LoweredPat::Wild; arity],
+        LoweredPat::Ctor(c, fields) if *c == ctor => fields.clone(),
+        _ => return None,
+    };
+    Some(head.into_iter().chain(row[1..].iter().cloned()).collect())
+}
+
+fn with_head(head: String, witnesses: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    witnesses.into_iter().map(|rest| iter::once(head.clone()).chain(rest).collect()).collect()
+}
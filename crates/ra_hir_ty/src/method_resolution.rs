@@ -6,18 +6,27 @@ use std::sync::Arc;
 
 use arrayvec::ArrayVec;
 use hir_def::{
-    lang_item::LangItemTarget, type_ref::Mutability, AssocContainerId, AssocItemId, FunctionId,
-    HasModule, ImplId, Lookup, TraitId,
+    lang_item::LangItemTarget, resolver::HasResolver, src::HasSource, type_ref::Mutability,
+    AssocContainerId, AssocItemId, FunctionId, HasModule, ImplId, Lookup, ModuleId, TraitId,
+};
+use hir_expand::{
+    diagnostics::DiagnosticSink,
+    name::{AsName, Name},
+    InFile,
 };
-use hir_expand::name::Name;
 use ra_db::CrateId;
 use ra_prof::profile;
+use ra_syntax::{
+    ast::{self, NameOwner},
+    AstPtr,
+};
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use super::Substs;
 use crate::{
     autoderef,
     db::HirDatabase,
+    diagnostics::DuplicateInherentItem,
     primitive::{FloatBitness, Uncertain},
     utils::all_super_traits,
     Canonical, InEnvironment, TraitEnvironment, TraitRef, Ty, TypeCtor, TypeWalk,
@@ -27,68 +36,305 @@ use crate::{
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum TyFingerprint {
     Apply(TypeCtor),
+    /// A reference to a type with the given constructor, like `&S`.
+    Ref(Mutability, TypeCtor),
+    /// A raw pointer to a type with the given constructor, like `*const S`.
+    RawPtr(Mutability, TypeCtor),
 }
 
 impl TyFingerprint {
     /// Creates a TyFingerprint for looking up an impl. Only certain types can
     /// have impls: if we have some `struct S`, we can have an `impl S`, but not
-    /// `impl &S`. Hence, this will return `None` for reference types and such.
-    fn for_impl(ty: &Ty) -> Option<TyFingerprint> {
-        match ty {
-            Ty::Apply(a_ty) => Some(TyFingerprint::Apply(a_ty.ctor)),
+    /// `impl T` for a type parameter. Hence, this will return `None` for type
+    /// parameters, inference variables and such. References and raw pointers
+    /// are keyed on their pointee if it is known, so that `impl Trait for &S`
+    /// and `impl Trait for &U` can be told apart.
+    pub(crate) fn for_impl(ty: &Ty) -> Option<TyFingerprint> {
+        let a_ty = match ty {
+            Ty::Apply(a_ty) if is_fingerprintable(a_ty.ctor) => a_ty,
+            _ => return None,
+        };
+        let pointee_ctor = || match a_ty.parameters.as_single() {
+            Ty::Apply(pointee) if is_fingerprintable(pointee.ctor) => {
+                Some(fingerprint_ctor(pointee.ctor))
+            }
             _ => None,
+        };
+        let fingerprint = match a_ty.ctor {
+            TypeCtor::Ref(mutability) => match pointee_ctor() {
+                Some(pointee) => TyFingerprint::Ref(mutability, pointee),
+                None => TyFingerprint::Apply(a_ty.ctor),
+            },
+            TypeCtor::RawPtr(mutability) => match pointee_ctor() {
+                Some(pointee) => TyFingerprint::RawPtr(mutability, pointee),
+                None => TyFingerprint::Apply(a_ty.ctor),
+            },
+            ctor => TyFingerprint::Apply(fingerprint_ctor(ctor)),
+        };
+        Some(fingerprint)
+    }
+
+    /// Checks whether an impl for a type with the fingerprint `impl_fp` may
+    /// apply to a type with the fingerprint `ty_fp`. A reference with an
+    /// unknown pointee, like `&T`, may be any reference of the same mutability.
+    pub(crate) fn may_apply(impl_fp: Option<TyFingerprint>, ty_fp: Option<TyFingerprint>) -> bool {
+        let (impl_fp, ty_fp) = match (impl_fp, ty_fp) {
+            (Some(impl_fp), Some(ty_fp)) => (impl_fp, ty_fp),
+            _ => return true,
+        };
+        if impl_fp == ty_fp {
+            return true;
         }
+        match (impl_fp, ty_fp) {
+            (TyFingerprint::Apply(ctor), other) | (other, TyFingerprint::Apply(ctor)) => {
+                ctor == other.outer_ctor()
+            }
+            _ => false,
+        }
+    }
+
+    /// The constructor of the type itself, ignoring the pointee of references
+    /// and raw pointers.
+    fn outer_ctor(self) -> TypeCtor {
+        match self {
+            TyFingerprint::Apply(ctor) => ctor,
+            TyFingerprint::Ref(mutability, _) => TypeCtor::Ref(mutability),
+            TyFingerprint::RawPtr(mutability, _) => TypeCtor::RawPtr(mutability),
+        }
+    }
+}
+
+/// Associated types and uncertain integer or float types may still turn into
+/// any other type, so they don't have a meaningful fingerprint.
+fn is_fingerprintable(ctor: TypeCtor) -> bool {
+    match ctor {
+        TypeCtor::AssociatedType(_)
+        | TypeCtor::Int(Uncertain::Unknown)
+        | TypeCtor::Float(Uncertain::Unknown) => false,
+        _ => true,
+    }
+}
+
+/// Impls for arrays apply to arrays of any length, so the length isn't part of
+/// the fingerprint.
+fn fingerprint_ctor(ctor: TypeCtor) -> TypeCtor {
+    match ctor {
+        TypeCtor::Array { .. } => TypeCtor::Array { len: None },
+        ctor => ctor,
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Impl blocks of a crate, or of a single module, indexed for lookup.
+#[derive(Debug, Default, PartialEq, Eq)]
 pub struct CrateImplBlocks {
     impls: FxHashMap<TyFingerprint, Vec<ImplId>>,
     impls_by_trait: FxHashMap<TraitId, Vec<ImplId>>,
+    /// The implemented traits by the fingerprint of the self type of their
+    /// impls, which is `None` for blanket impls.
+    traits_by_self_ty: FxHashMap<Option<TyFingerprint>, Vec<TraitId>>,
 }
 
 impl CrateImplBlocks {
+    /// Collects the impls of a single module, so that changing one file only
+    /// invalidates the contribution of that file's module.
+    pub(crate) fn impls_in_module_query(
+        db: &impl HirDatabase,
+        module: ModuleId,
+    ) -> Arc<CrateImplBlocks> {
+        let _p = profile("impls_in_module_query");
+        let mut res = CrateImplBlocks::default();
+
+        let crate_def_map = db.crate_def_map(module.krate);
+        for impl_id in crate_def_map[module.local_id].scope.impls() {
+            match db.impl_trait(impl_id) {
+                Some(tr) => {
+                    res.impls_by_trait.entry(tr.value.trait_).or_default().push(impl_id);
+                    let self_ty = db.impl_self_ty(impl_id);
+                    let traits = res
+                        .traits_by_self_ty
+                        .entry(TyFingerprint::for_impl(&self_ty.value))
+                        .or_default();
+                    if !traits.contains(&tr.value.trait_) {
+                        traits.push(tr.value.trait_);
+                    }
+                }
+                None => {
+                    let self_ty = db.impl_self_ty(impl_id);
+                    if let Some(self_ty_fp) = TyFingerprint::for_impl(&self_ty.value) {
+                        res.impls.entry(self_ty_fp).or_default().push(impl_id);
+                    }
+                }
+            }
+        }
+
+        Arc::new(res)
+    }
+
     pub(crate) fn impls_in_crate_query(
         db: &impl HirDatabase,
         krate: CrateId,
     ) -> Arc<CrateImplBlocks> {
         let _p = profile("impls_in_crate_query");
-        let mut res =
-            CrateImplBlocks { impls: FxHashMap::default(), impls_by_trait: FxHashMap::default() };
+        let mut res = CrateImplBlocks::default();
 
         let crate_def_map = db.crate_def_map(krate);
-        for (_module_id, module_data) in crate_def_map.modules.iter() {
-            for impl_id in module_data.scope.impls() {
-                match db.impl_trait(impl_id) {
-                    Some(tr) => {
-                        res.impls_by_trait.entry(tr.value.trait_).or_default().push(impl_id);
+        for (local_id, _module_data) in crate_def_map.modules.iter() {
+            let module_impls = db.impls_in_module(ModuleId { krate, local_id });
+            for (fp, impls) in module_impls.impls.iter() {
+                res.impls.entry(*fp).or_default().extend(impls.iter().copied());
+            }
+            for (tr, impls) in module_impls.impls_by_trait.iter() {
+                res.impls_by_trait.entry(*tr).or_default().extend(impls.iter().copied());
+            }
+            for (fp, traits) in module_impls.traits_by_self_ty.iter() {
+                let res_traits = res.traits_by_self_ty.entry(*fp).or_default();
+                for tr in traits {
+                    if !res_traits.contains(tr) {
+                        res_traits.push(*tr);
                     }
-                    None => {
-                        let self_ty = db.impl_self_ty(impl_id);
-                        if let Some(self_ty_fp) = TyFingerprint::for_impl(&self_ty.value) {
-                            res.impls.entry(self_ty_fp).or_default().push(impl_id);
+                }
+            }
+        }
+
+        Arc::new(res)
+    }
+
+    /// Finds the items of inherent impls which were already defined by an
+    /// inherent impl for the same type, which rustc rejects: methods with the
+    /// same name, or constants with the same name. Returns the pairs of the
+    /// first definition and its duplicate.
+    pub(crate) fn duplicate_inherent_items_query(
+        db: &impl HirDatabase,
+        krate: CrateId,
+    ) -> Arc<[(AssocItemId, AssocItemId)]> {
+        let _p = profile("duplicate_inherent_items_query");
+        let crate_impl_blocks = db.impls_in_crate(krate);
+        let mut res = Vec::new();
+
+        for impls in crate_impl_blocks.impls.values() {
+            let mut defs_by_name: FxHashMap<(Name, bool), Vec<(Ty, AssocItemId)>> =
+                FxHashMap::default();
+            for &impl_id in impls {
+                let self_ty = db.impl_self_ty(impl_id).value;
+                for &item in db.impl_data(impl_id).items.iter() {
+                    let key = match item {
+                        AssocItemId::FunctionId(f) => {
+                            let data = db.function_data(f);
+                            if !data.has_self_param {
+                                continue;
+                            }
+                            (data.name.clone(), true)
                         }
+                        AssocItemId::ConstId(c) => match &db.const_data(c).name {
+                            Some(name) => (name.clone(), false),
+                            None => continue,
+                        },
+                        AssocItemId::TypeAliasId(_) => continue,
+                    };
+                    let defs = defs_by_name.entry(key).or_default();
+                    // Impls for different instantiations of a generic type may
+                    // define the same names.
+                    match defs.iter().find(|(ty, _)| *ty == self_ty) {
+                        Some(&(_, first)) => res.push((first, item)),
+                        None => defs.push((self_ty.clone(), item)),
                     }
                 }
             }
         }
 
-        Arc::new(res)
+        res.into()
     }
+
     pub fn lookup_impl_blocks(&self, ty: &Ty) -> impl Iterator<Item = ImplId> + '_ {
         let fingerprint = TyFingerprint::for_impl(ty);
-        fingerprint.and_then(|f| self.impls.get(&f)).into_iter().flatten().copied()
+        // Impls for pointers to generic types, like `impl<T> *const T`, are
+        // stored under the pointer itself.
+        let outer = match fingerprint {
+            Some(TyFingerprint::Ref(mutability, _)) => {
+                Some(TyFingerprint::Apply(TypeCtor::Ref(mutability)))
+            }
+            Some(TyFingerprint::RawPtr(mutability, _)) => {
+                Some(TyFingerprint::Apply(TypeCtor::RawPtr(mutability)))
+            }
+            _ => None,
+        };
+        fingerprint
+            .and_then(|f| self.impls.get(&f))
+            .into_iter()
+            .chain(outer.and_then(|f| self.impls.get(&f)))
+            .flatten()
+            .copied()
     }
 
     pub fn lookup_impl_blocks_for_trait(&self, tr: TraitId) -> impl Iterator<Item = ImplId> + '_ {
         self.impls_by_trait.get(&tr).into_iter().flatten().copied()
     }
 
+    /// The traits with an impl which may apply to a type with the fingerprint
+    /// `fp`, possibly more than once.
+    pub fn traits_implemented_for(
+        &self,
+        fp: Option<TyFingerprint>,
+    ) -> impl Iterator<Item = TraitId> + '_ {
+        self.traits_by_self_ty
+            .iter()
+            .filter(move |(impl_fp, _)| TyFingerprint::may_apply(**impl_fp, fp))
+            .flat_map(|(_, traits)| traits.iter().copied())
+    }
+
     pub fn all_impls<'a>(&'a self) -> impl Iterator<Item = ImplId> + 'a {
         self.impls.values().chain(self.impls_by_trait.values()).flatten().copied()
     }
 }
 
+/// Reports the duplicate inherent items defined in `module`.
+pub fn add_duplicate_item_diagnostics(
+    db: &impl HirDatabase,
+    module: ModuleId,
+    sink: &mut DiagnosticSink,
+) {
+    let mut diagnostics = Vec::new();
+    for &(first, duplicate) in db.duplicate_inherent_items(module.krate).iter() {
+        if assoc_item_module(db, duplicate) != module {
+            continue;
+        }
+        let (first, name) = match (assoc_item_name(db, first), assoc_item_name(db, duplicate)) {
+            (Some(first), Some(name)) => (first, name),
+            _ => continue,
+        };
+        diagnostics.push(DuplicateInherentItem {
+            file: name.file_id,
+            name: name.value.1,
+            item_name: name.value.0,
+            first: first.map(|(_, ptr)| ptr),
+        });
+    }
+    diagnostics.sort_by_key(|it| it.name.syntax_node_ptr().range().start());
+    for diagnostic in diagnostics {
+        sink.push(diagnostic);
+    }
+}
+
+fn assoc_item_module(db: &impl HirDatabase, item: AssocItemId) -> ModuleId {
+    match item {
+        AssocItemId::FunctionId(it) => it.lookup(db).module(db),
+        AssocItemId::ConstId(it) => it.lookup(db).module(db),
+        AssocItemId::TypeAliasId(it) => it.lookup(db).module(db),
+    }
+}
+
+fn assoc_item_name(
+    db: &impl HirDatabase,
+    item: AssocItemId,
+) -> Option<InFile<(Name, AstPtr<ast::Name>)>> {
+    let src = match item {
+        AssocItemId::FunctionId(it) => it.lookup(db).source(db).map(|it| it.name()),
+        AssocItemId::ConstId(it) => it.lookup(db).source(db).map(|it| it.name()),
+        AssocItemId::TypeAliasId(it) => it.lookup(db).source(db).map(|it| it.name()),
+    };
+    src.transpose().map(|src| src.map(|name| (name.as_name(), AstPtr::new(&name))))
+}
+
 impl Ty {
     pub fn def_crates(
         &self,
@@ -176,6 +422,26 @@ pub enum LookupMode {
     Path,
 }
 
+/// Why a candidate found by `iterate_method_candidates_with_reasons` can't be
+/// used.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// The item is not visible from the module the lookup happens in.
+    NotVisible,
+    /// The item belongs to a trait which is implemented for the type, but
+    /// which is not in scope.
+    TraitNotInScope,
+    /// The `self` parameter of the method doesn't fit the receiver.
+    SelfTypeMismatch,
+}
+
+/// Candidates which were rejected during a lookup, reported once the lookup is
+/// done.
+struct Rejected {
+    visible_from: ModuleId,
+    candidates: Vec<(Ty, AssocItemId, RejectionReason)>,
+}
+
 // This would be nicer if it just returned an iterator, but that runs into
 // lifetime problems, because we need to borrow temp `CrateImplBlocks`.
 // FIXME add a context type here?
@@ -187,6 +453,80 @@ pub fn iterate_method_candidates<T>(
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
     mode: LookupMode,
+    callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
+) -> Option<T> {
+    iterate_method_candidates_impl(
+        ty,
+        db,
+        env,
+        krate,
+        traits_in_scope,
+        name,
+        mode,
+        &mut None,
+        callback,
+    )
+}
+
+/// Like `iterate_method_candidates`, but also offers the candidates which
+/// can't be used to the callback, together with the reason why.
+///
+/// The usable candidates come first, in lookup order. The rejected ones are
+/// reported after the lookup is exhausted, each one once, and only if it
+/// wasn't usable through another autoderef step.
+pub fn iterate_method_candidates_with_reasons<T>(
+    ty: &Canonical<Ty>,
+    db: &impl HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    visible_from: ModuleId,
+    name: Option<&Name>,
+    mode: LookupMode,
+    mut callback: impl FnMut(&Ty, AssocItemId, Option<RejectionReason>) -> Option<T>,
+) -> Option<T> {
+    let mut rejected = Some(Rejected { visible_from, candidates: Vec::new() });
+    let mut seen = FxHashSet::default();
+    let res = iterate_method_candidates_impl(
+        ty,
+        db,
+        env.clone(),
+        krate,
+        traits_in_scope,
+        name,
+        mode,
+        &mut rejected,
+        |ty, item| {
+            seen.insert(item);
+            callback(ty, item, None)
+        },
+    );
+    if res.is_some() {
+        return res;
+    }
+
+    let mut rejected = rejected.map_or_else(Vec::new, |it| it.candidates);
+    rejected.extend(out_of_scope_trait_candidates(ty, db, env, krate, traits_in_scope, name, mode));
+    for (self_ty, item, reason) in rejected {
+        if !seen.insert(item) {
+            continue;
+        }
+        if let Some(result) = callback(&self_ty, item, Some(reason)) {
+            return Some(result);
+        }
+    }
+    None
+}
+
+fn iterate_method_candidates_impl<T>(
+    ty: &Canonical<Ty>,
+    db: &impl HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+    mode: LookupMode,
+    rejected: &mut Option<Rejected>,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
 ) -> Option<T> {
     match mode {
@@ -214,7 +554,7 @@ pub fn iterate_method_candidates<T>(
             // the methods by autoderef order of *receiver types*, not *self
             // types*.
 
-            let deref_chain: Vec<_> = autoderef::autoderef(db, Some(krate), ty).collect();
+            let deref_chain = autoderef_method_receiver(db, krate, ty);
             for i in 0..deref_chain.len() {
                 if let Some(result) = iterate_method_candidates_with_autoref(
                     &deref_chain[i..],
@@ -223,6 +563,7 @@ pub fn iterate_method_candidates<T>(
                     krate,
                     traits_in_scope,
                     name,
+                    rejected,
                     &mut callback,
                 ) {
                     return Some(result);
@@ -239,12 +580,38 @@ pub fn iterate_method_candidates<T>(
                 krate,
                 traits_in_scope,
                 name,
+                rejected,
                 &mut callback,
             )
         }
     }
 }
 
+/// Unlike field accesses, method calls don't autoderef through raw pointers:
+/// with `ptr: *const S`, `ptr.foo()` doesn't find `S::foo` (rustc requires an
+/// explicit `(*ptr).foo()`), but it still finds methods of `*const S` itself.
+fn autoderef_method_receiver(
+    db: &impl HirDatabase,
+    krate: CrateId,
+    ty: InEnvironment<Canonical<Ty>>,
+) -> Vec<Canonical<Ty>> {
+    let mut deref_chain = Vec::new();
+    for ty in autoderef::autoderef(db, Some(krate), ty) {
+        let is_raw_ptr = match &ty.value {
+            Ty::Apply(a_ty) => match a_ty.ctor {
+                TypeCtor::RawPtr(..) => true,
+                _ => false,
+            },
+            _ => false,
+        };
+        deref_chain.push(ty);
+        if is_raw_ptr {
+            break;
+        }
+    }
+    deref_chain
+}
+
 fn iterate_method_candidates_with_autoref<T>(
     deref_chain: &[Canonical<Ty>],
     db: &impl HirDatabase,
@@ -252,6 +619,7 @@ fn iterate_method_candidates_with_autoref<T>(
     krate: CrateId,
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
+    rejected: &mut Option<Rejected>,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
 ) -> Option<T> {
     if let Some(result) = iterate_method_candidates_by_receiver(
@@ -262,6 +630,7 @@ fn iterate_method_candidates_with_autoref<T>(
         krate,
         &traits_in_scope,
         name,
+        rejected,
         &mut callback,
     ) {
         return Some(result);
@@ -278,6 +647,7 @@ fn iterate_method_candidates_with_autoref<T>(
         krate,
         &traits_in_scope,
         name,
+        rejected,
         &mut callback,
     ) {
         return Some(result);
@@ -294,6 +664,7 @@ fn iterate_method_candidates_with_autoref<T>(
         krate,
         &traits_in_scope,
         name,
+        rejected,
         &mut callback,
     ) {
         return Some(result);
@@ -309,15 +680,22 @@ fn iterate_method_candidates_by_receiver<T>(
     krate: CrateId,
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
+    rejected: &mut Option<Rejected>,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
 ) -> Option<T> {
     // We're looking for methods with *receiver* type receiver_ty. These could
     // be found in any of the derefs of receiver_ty, so we have to go through
     // that.
     for self_ty in std::iter::once(receiver_ty).chain(rest_of_deref_chain) {
-        if let Some(result) =
-            iterate_inherent_methods(self_ty, db, name, Some(receiver_ty), krate, &mut callback)
-        {
+        if let Some(result) = iterate_inherent_methods(
+            self_ty,
+            db,
+            name,
+            Some(receiver_ty),
+            krate,
+            rejected,
+            &mut callback,
+        ) {
             return Some(result);
         }
     }
@@ -330,6 +708,7 @@ fn iterate_method_candidates_by_receiver<T>(
             &traits_in_scope,
             name,
             Some(receiver_ty),
+            rejected,
             &mut callback,
         ) {
             return Some(result);
@@ -345,9 +724,12 @@ fn iterate_method_candidates_for_self_ty<T>(
     krate: CrateId,
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
+    rejected: &mut Option<Rejected>,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
 ) -> Option<T> {
-    if let Some(result) = iterate_inherent_methods(self_ty, db, name, None, krate, &mut callback) {
+    if let Some(result) =
+        iterate_inherent_methods(self_ty, db, name, None, krate, rejected, &mut callback)
+    {
         return Some(result);
     }
     if let Some(result) = iterate_trait_method_candidates(
@@ -358,6 +740,7 @@ fn iterate_method_candidates_for_self_ty<T>(
         traits_in_scope,
         name,
         None,
+        rejected,
         &mut callback,
     ) {
         return Some(result);
@@ -373,10 +756,13 @@ fn iterate_trait_method_candidates<T>(
     traits_in_scope: &FxHashSet<TraitId>,
     name: Option<&Name>,
     receiver_ty: Option<&Canonical<Ty>>,
+    rejected: &mut Option<Rejected>,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
 ) -> Option<T> {
-    // if ty is `impl Trait` or `dyn Trait`, the trait doesn't need to be in scope
-    let inherent_trait = self_ty.value.inherent_trait().into_iter();
+    // if ty is `impl Trait` or `dyn Trait`, the trait and its super traits
+    // don't need to be in scope
+    let inherent_trait =
+        self_ty.value.inherent_trait().into_iter().flat_map(|t| all_super_traits(db, t));
     let env_traits = if let Ty::Placeholder(_) = self_ty.value {
         // if we have `T: Trait` in the param env, the trait doesn't need to be in scope
         env.trait_predicates_for_self_ty(&self_ty.value)
@@ -388,6 +774,11 @@ fn iterate_trait_method_candidates<T>(
     };
     let traits =
         inherent_trait.chain(env_traits.into_iter()).chain(traits_in_scope.iter().copied());
+    // the items found so far, so that in paths like `T::foo`, the items of a
+    // sub trait shadow the items of the same name in its super traits (and
+    // traits which are both in the environment and in scope are only
+    // searched once)
+    let mut found: Vec<(TraitId, Name)> = Vec::new();
     'traits: for t in traits {
         let data = db.trait_data(t);
 
@@ -395,8 +786,15 @@ fn iterate_trait_method_candidates<T>(
         // trait, but if we find out it doesn't, we'll skip the rest of the
         // iteration
         let mut known_implemented = false;
-        for (_name, item) in data.items.iter() {
-            if !is_valid_candidate(db, name, receiver_ty, *item, self_ty) {
+        for (item_name, item) in data.items.iter() {
+            let reason = match check_candidate(db, name, receiver_ty, *item, self_ty) {
+                CandidateCheck::Valid => None,
+                CandidateCheck::SelfTypeMismatch if rejected.is_some() => {
+                    Some(RejectionReason::SelfTypeMismatch)
+                }
+                _ => continue,
+            };
+            if receiver_ty.is_none() && is_shadowed_by_sub_trait(db, t, item_name, &found) {
                 continue;
             }
             if !known_implemented {
@@ -406,20 +804,38 @@ fn iterate_trait_method_candidates<T>(
                 }
             }
             known_implemented = true;
-            if let Some(result) = callback(&self_ty.value, *item) {
-                return Some(result);
+            match reason {
+                Some(reason) => reject(rejected, &self_ty.value, *item, reason),
+                None => {
+                    if let Some(result) = callback(&self_ty.value, *item) {
+                        return Some(result);
+                    }
+                    found.push((t, item_name.clone()));
+                }
             }
         }
     }
     None
 }
 
+fn is_shadowed_by_sub_trait(
+    db: &impl HirDatabase,
+    trait_: TraitId,
+    name: &Name,
+    found: &[(TraitId, Name)],
+) -> bool {
+    found.iter().any(|(sub_trait, found_name)| {
+        found_name == name && all_super_traits(db, *sub_trait).contains(&trait_)
+    })
+}
+
 fn iterate_inherent_methods<T>(
     self_ty: &Canonical<Ty>,
     db: &impl HirDatabase,
     name: Option<&Name>,
     receiver_ty: Option<&Canonical<Ty>>,
     krate: CrateId,
+    rejected: &mut Option<Rejected>,
     mut callback: impl FnMut(&Ty, AssocItemId) -> Option<T>,
 ) -> Option<T> {
     for krate in self_ty.value.def_crates(db, krate)? {
@@ -427,20 +843,34 @@ fn iterate_inherent_methods<T>(
 
         for impl_block in impls.lookup_impl_blocks(&self_ty.value) {
             for &item in db.impl_data(impl_block).items.iter() {
-                if !is_valid_candidate(db, name, receiver_ty, item, self_ty) {
-                    continue;
-                }
+                let mut reason = match check_candidate(db, name, receiver_ty, item, self_ty) {
+                    CandidateCheck::Valid => None,
+                    CandidateCheck::SelfTypeMismatch if rejected.is_some() => {
+                        Some(RejectionReason::SelfTypeMismatch)
+                    }
+                    _ => continue,
+                };
                 // we have to check whether the self type unifies with the type
                 // that the impl is for. If we have a receiver type, this
-                // already happens in `is_valid_candidate` above; if not, we
+                // already happens in `check_candidate` above; if not, we
                 // check it here
                 if receiver_ty.is_none() && inherent_impl_substs(db, impl_block, self_ty).is_none()
                 {
                     test_utils::tested_by!(impl_self_type_match_without_receiver);
                     continue;
                 }
-                if let Some(result) = callback(&self_ty.value, item) {
-                    return Some(result);
+                if let Some(rejected) = rejected {
+                    if reason.is_none() && !is_visible_from(db, item, rejected.visible_from) {
+                        reason = Some(RejectionReason::NotVisible);
+                    }
+                }
+                match reason {
+                    Some(reason) => reject(rejected, &self_ty.value, item, reason),
+                    None => {
+                        if let Some(result) = callback(&self_ty.value, item) {
+                            return Some(result);
+                        }
+                    }
                 }
             }
         }
@@ -448,40 +878,162 @@ fn iterate_inherent_methods<T>(
     None
 }
 
-fn is_valid_candidate(
+/// Methods of traits which are implemented for the type (or, for method calls,
+/// one of the types it derefs to), but which are not in scope.
+fn out_of_scope_trait_candidates(
+    ty: &Canonical<Ty>,
+    db: &impl HirDatabase,
+    env: Arc<TraitEnvironment>,
+    krate: CrateId,
+    traits_in_scope: &FxHashSet<TraitId>,
+    name: Option<&Name>,
+    mode: LookupMode,
+) -> Vec<(Ty, AssocItemId, RejectionReason)> {
+    let self_tys = match mode {
+        LookupMode::MethodCall => autoderef_method_receiver(
+            db,
+            krate,
+            InEnvironment { value: ty.clone(), environment: env.clone() },
+        ),
+        LookupMode::Path => vec![ty.clone()],
+    };
+    let fingerprints: Vec<_> =
+        self_tys.iter().map(|self_ty| TyFingerprint::for_impl(&self_ty.value)).collect();
+    let mut res = Vec::new();
+    for trait_ in implemented_traits(db, krate, &fingerprints) {
+        if traits_in_scope.contains(&trait_) {
+            continue;
+        }
+        let items: Vec<AssocItemId> = db
+            .trait_data(trait_)
+            .items
+            .iter()
+            .map(|(_name, item)| *item)
+            .filter(|&item| check_candidate(db, name, None, item, ty) == CandidateCheck::Valid)
+            .filter(|&item| match (mode, item) {
+                (LookupMode::MethodCall, AssocItemId::FunctionId(f)) => {
+                    db.function_data(f).has_self_param
+                }
+                (LookupMode::MethodCall, _) => false,
+                (LookupMode::Path, _) => true,
+            })
+            .collect();
+        if items.is_empty() {
+            continue;
+        }
+        let self_ty = self_tys.iter().find(|self_ty| {
+            let goal = generic_implements_goal(db, env.clone(), trait_, (*self_ty).clone());
+            db.trait_solve(krate, goal).is_some()
+        });
+        if let Some(self_ty) = self_ty {
+            res.extend(
+                items
+                    .into_iter()
+                    .map(|item| (self_ty.value.clone(), item, RejectionReason::TraitNotInScope)),
+            );
+        }
+    }
+    res
+}
+
+/// The traits which have an impl in `krate` or one of its dependencies that may
+/// apply to a type with one of the `fingerprints`.
+fn implemented_traits(
+    db: &impl HirDatabase,
+    krate: CrateId,
+    fingerprints: &[Option<TyFingerprint>],
+) -> Vec<TraitId> {
+    let crate_graph = db.crate_graph();
+    let mut crates = vec![krate];
+    let mut seen_crates = FxHashSet::default();
+    let mut seen_traits = FxHashSet::default();
+    let mut res = Vec::new();
+    while let Some(krate) = crates.pop() {
+        if !seen_crates.insert(krate) {
+            continue;
+        }
+        crates.extend(crate_graph.dependencies(krate).map(|dep| dep.crate_id));
+        let impls = db.impls_in_crate(krate);
+        for &fp in fingerprints {
+            for trait_ in impls.traits_implemented_for(fp) {
+                if seen_traits.insert(trait_) {
+                    res.push(trait_);
+                }
+            }
+        }
+    }
+    res
+}
+
+fn reject(
+    rejected: &mut Option<Rejected>,
+    self_ty: &Ty,
+    item: AssocItemId,
+    reason: RejectionReason,
+) {
+    if let Some(rejected) = rejected {
+        rejected.candidates.push((self_ty.clone(), item, reason));
+    }
+}
+
+fn is_visible_from(db: &impl HirDatabase, item: AssocItemId, module: ModuleId) -> bool {
+    match item {
+        AssocItemId::FunctionId(f) => {
+            let visibility = db.function_data(f).visibility.resolve(db, &f.resolver(db));
+            visibility.is_visible_from(db, module)
+        }
+        _ => true,
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CandidateCheck {
+    /// The item doesn't match the lookup at all, e.g. because of its name.
+    Invalid,
+    /// The item is a method with the right name, but its `self` parameter
+    /// doesn't fit the receiver.
+    SelfTypeMismatch,
+    Valid,
+}
+
+fn check_candidate(
     db: &impl HirDatabase,
     name: Option<&Name>,
     receiver_ty: Option<&Canonical<Ty>>,
     item: AssocItemId,
     self_ty: &Canonical<Ty>,
-) -> bool {
+) -> CandidateCheck {
     match item {
         AssocItemId::FunctionId(m) => {
             let data = db.function_data(m);
             if let Some(name) = name {
                 if &data.name != name {
-                    return false;
+                    return CandidateCheck::Invalid;
                 }
             }
             if let Some(receiver_ty) = receiver_ty {
                 if !data.has_self_param {
-                    return false;
+                    return CandidateCheck::Invalid;
                 }
                 let transformed_receiver_ty = match transform_receiver_ty(db, m, self_ty) {
                     Some(ty) => ty,
-                    None => return false,
+                    None => return CandidateCheck::SelfTypeMismatch,
                 };
                 if transformed_receiver_ty != receiver_ty.value {
-                    return false;
+                    return CandidateCheck::SelfTypeMismatch;
                 }
             }
-            true
+            CandidateCheck::Valid
         }
         AssocItemId::ConstId(c) => {
             let data = db.const_data(c);
-            name.map_or(true, |name| data.name.as_ref() == Some(name)) && receiver_ty.is_none()
+            if name.map_or(true, |name| data.name.as_ref() == Some(name)) && receiver_ty.is_none() {
+                CandidateCheck::Valid
+            } else {
+                CandidateCheck::Invalid
+            }
         }
-        _ => false,
+        _ => CandidateCheck::Invalid,
     }
 }
 
@@ -520,7 +1072,7 @@ pub fn implements_trait(
     krate: CrateId,
     trait_: TraitId,
 ) -> bool {
-    if ty.value.inherent_trait() == Some(trait_) {
+    if ty.value.inherent_trait().map_or(false, |t| all_super_traits(db, t).contains(&trait_)) {
         // FIXME this is a bit of a hack, since Chalk should say the same thing
         // anyway, but currently Chalk doesn't implement `dyn/impl Trait` yet
         return true;
@@ -1,5 +1,5 @@
 //! Helper functions for binary operator type inference.
-use hir_def::expr::{ArithOp, BinaryOp, CmpOp};
+use hir_def::expr::{ArithOp, BinaryOp, CmpOp, LogicOp, Ordering};
 
 use super::{InferTy, Ty, TypeCtor};
 use crate::ApplicationTy;
@@ -27,6 +27,109 @@ pub(super) fn binary_op_return_ty(op: BinaryOp, lhs_ty: Ty, rhs_ty: Ty) -> Ty {
     }
 }
 
+/// Whether the operands are primitives, whose operators are built into the
+/// language rather than implemented with the `std::ops` traits.
+pub(super) fn is_builtin_binary_op(lhs_ty: &Ty, rhs_ty: &Ty) -> bool {
+    is_builtin_operand(lhs_ty) && is_builtin_operand(rhs_ty)
+}
+
+fn is_builtin_operand(ty: &Ty) -> bool {
+    match ty {
+        Ty::Apply(ApplicationTy { ctor, .. }) => match ctor {
+            TypeCtor::Int(..)
+            | TypeCtor::Float(..)
+            | TypeCtor::Bool
+            | TypeCtor::Char
+            | TypeCtor::Str => true,
+            _ => false,
+        },
+        Ty::Infer(InferTy::IntVar(..)) | Ty::Infer(InferTy::FloatVar(..)) => true,
+        // We can't do better than the builtin rules for unknown operands.
+        Ty::Unknown => true,
+        _ => false,
+    }
+}
+
+/// The operator as written in the source, e.g. `+=` or `<`.
+pub(super) fn binary_op_text(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::LogicOp(LogicOp::And) => "&&",
+        BinaryOp::LogicOp(LogicOp::Or) => "||",
+        BinaryOp::CmpOp(CmpOp::Eq { negated: false }) => "==",
+        BinaryOp::CmpOp(CmpOp::Eq { negated: true }) => "!=",
+        BinaryOp::CmpOp(CmpOp::Ord { ordering, strict }) => match (ordering, strict) {
+            (Ordering::Less, true) => "<",
+            (Ordering::Less, false) => "<=",
+            (Ordering::Greater, true) => ">",
+            (Ordering::Greater, false) => ">=",
+        },
+        BinaryOp::ArithOp(op) => arith_op_text(op),
+        BinaryOp::Assignment { op: None } => "=",
+        BinaryOp::Assignment { op: Some(op) } => match op {
+            ArithOp::Add => "+=",
+            ArithOp::Mul => "*=",
+            ArithOp::Sub => "-=",
+            ArithOp::Div => "/=",
+            ArithOp::Rem => "%=",
+            ArithOp::Shl => "<<=",
+            ArithOp::Shr => ">>=",
+            ArithOp::BitXor => "^=",
+            ArithOp::BitOr => "|=",
+            ArithOp::BitAnd => "&=",
+        },
+    }
+}
+
+fn arith_op_text(op: ArithOp) -> &'static str {
+    match op {
+        ArithOp::Add => "+",
+        ArithOp::Mul => "*",
+        ArithOp::Sub => "-",
+        ArithOp::Div => "/",
+        ArithOp::Rem => "%",
+        ArithOp::Shl => "<<",
+        ArithOp::Shr => ">>",
+        ArithOp::BitXor => "^",
+        ArithOp::BitOr => "|",
+        ArithOp::BitAnd => "&",
+    }
+}
+
+/// The lang item of the trait overloading the operator, if it can be
+/// overloaded.
+pub(super) fn binary_op_lang_item(op: BinaryOp) -> Option<&'static str> {
+    let lang_item = match op {
+        BinaryOp::LogicOp(_) | BinaryOp::Assignment { op: None } => return None,
+        BinaryOp::CmpOp(CmpOp::Eq { .. }) => "eq",
+        BinaryOp::CmpOp(CmpOp::Ord { .. }) => "partial_ord",
+        BinaryOp::ArithOp(op) => match op {
+            ArithOp::Add => "add",
+            ArithOp::Mul => "mul",
+            ArithOp::Sub => "sub",
+            ArithOp::Div => "div",
+            ArithOp::Rem => "rem",
+            ArithOp::Shl => "shl",
+            ArithOp::Shr => "shr",
+            ArithOp::BitXor => "bitxor",
+            ArithOp::BitOr => "bitor",
+            ArithOp::BitAnd => "bitand",
+        },
+        BinaryOp::Assignment { op: Some(op) } => match op {
+            ArithOp::Add => "add_assign",
+            ArithOp::Mul => "mul_assign",
+            ArithOp::Sub => "sub_assign",
+            ArithOp::Div => "div_assign",
+            ArithOp::Rem => "rem_assign",
+            ArithOp::Shl => "shl_assign",
+            ArithOp::Shr => "shr_assign",
+            ArithOp::BitXor => "bitxor_assign",
+            ArithOp::BitOr => "bitor_assign",
+            ArithOp::BitAnd => "bitand_assign",
+        },
+    };
+    Some(lang_item)
+}
+
 pub(super) fn binary_op_rhs_expectation(op: BinaryOp, lhs_ty: Ty) -> Ty {
     match op {
         BinaryOp::LogicOp(..) => Ty::simple(TypeCtor::Bool),
@@ -9,7 +9,9 @@ use hir_def::{db::DefDatabase, AssocItemId, ModuleDefId, ModuleId};
 use hir_expand::diagnostics::DiagnosticSink;
 use ra_db::{salsa, CrateId, FileId, FileLoader, FileLoaderDelegate, RelativePath, SourceDatabase};
 
-use crate::{db::HirDatabase, expr::ExprValidator};
+use crate::{
+    db::HirDatabase, expr::ExprValidator, method_resolution::add_duplicate_item_diagnostics,
+};
 
 #[salsa::database(
     ra_db::SourceDatabaseExtStorage,
@@ -116,6 +118,13 @@ impl TestDB {
                 let mut validator = ExprValidator::new(f, infer, &mut sink);
                 validator.validate_body(self);
             }
+
+            for (local_id, _) in crate_def_map.modules.iter() {
+                let mut sink = DiagnosticSink::new(|d| {
+                    buf += &format!("{:?}: {}\n", d.syntax_node(self).text(), d.message());
+                });
+                add_duplicate_item_diagnostics(self, ModuleId { krate, local_id }, &mut sink);
+            }
         }
         buf
     }
@@ -6,6 +6,7 @@ mod patterns;
 mod traits;
 mod method_resolution;
 mod macros;
+mod display;
 
 use std::fmt::Write;
 use std::sync::Arc;
@@ -22,13 +23,19 @@ use ra_syntax::{
     ast::{self, AstNode},
 };
 
-use crate::{db::HirDatabase, display::HirDisplay, test_db::TestDB, InferenceResult};
+use crate::{
+    db::HirDatabase, display::HirDisplay, test_db::TestDB, utils::ellipsize, InferenceResult, Ty,
+};
 
 // These tests compare the inference results for all expressions in a file
 // against snapshots of the expected results using insta. Use cargo-insta to
 // update the snapshots.
 
 fn type_at_pos(db: &TestDB, pos: FilePosition) -> String {
+    ty_at_pos(db, pos).display(db).to_string()
+}
+
+fn ty_at_pos(db: &TestDB, pos: FilePosition) -> Ty {
     let file = db.parse(pos.file_id).ok().unwrap();
     let expr = algo::find_node_at_offset::<ast::Expr>(file.syntax(), pos.offset).unwrap();
     let fn_def = expr.syntax().ancestors().find_map(ast::FnDef::cast).unwrap();
@@ -40,8 +47,7 @@ fn type_at_pos(db: &TestDB, pos: FilePosition) -> String {
     let (_body, source_map) = db.body_with_source_map(func.into());
     if let Some(expr_id) = source_map.node_expr(InFile::new(pos.file_id.into(), &expr)) {
         let infer = db.infer(func.into());
-        let ty = &infer[expr_id];
-        return ty.display(db).to_string();
+        return infer[expr_id].clone();
     }
     panic!("Can't find expression")
 }
@@ -228,24 +234,6 @@ fn visit_module(
     }
 }
 
-fn ellipsize(mut text: String, max_len: usize) -> String {
-    if text.len() <= max_len {
-        return text;
-    }
-    let ellipsis = "...";
-    let e_len = ellipsis.len();
-    let mut prefix_len = (max_len - e_len) / 2;
-    while !text.is_char_boundary(prefix_len) {
-        prefix_len += 1;
-    }
-    let mut suffix_len = max_len - e_len - prefix_len;
-    while !text.is_char_boundary(text.len() - suffix_len) {
-        suffix_len += 1;
-    }
-    text.replace_range(prefix_len..text.len() - suffix_len, ellipsis);
-    text
-}
-
 #[test]
 fn typing_whitespace_inside_a_function_should_not_invalidate_types() {
     let (mut db, pos) = TestDB::with_position(
@@ -290,6 +278,116 @@ fn typing_whitespace_inside_a_function_should_not_invalidate_types() {
     }
 }
 
+#[test]
+fn unrelated_edit_should_not_resolve_failed_trait_goals() {
+    let (mut db, pos) = TestDB::with_position(
+        "
+        //- /lib.rs
+        trait Trait {}
+        struct S;
+        fn takes<T: Trait>(t: T) {}
+        fn foo() {
+            takes::<S>(S);
+        }
+        fn bar() -> i32 {
+            <|>1
+        }
+    ",
+    );
+    let infer_all = |db: &TestDB| {
+        let module = db.module_for_file(pos.file_id);
+        let crate_def_map = db.crate_def_map(module.krate);
+        visit_module(db, &crate_def_map, module.local_id, &mut |def| {
+            db.infer(def);
+        });
+    };
+    let is_solver = |event: &String| event.contains("trait_solver");
+    let is_solve = |event: &String| event.contains("trait_solve") && !is_solver(event);
+    {
+        let events = db.log_executed(|| infer_all(&db));
+        assert!(events.iter().any(is_solver), "{:#?}", events)
+    }
+
+    let new_text = "
+        trait Trait {}
+        struct S;
+        fn takes<T: Trait>(t: T) {}
+        fn foo() {
+            takes::<S>(S);
+        }
+        fn bar() -> i32 {
+            2
+        }
+    "
+    .to_string();
+
+    db.query_mut(ra_db::FileTextQuery).set(pos.file_id, Arc::new(new_text));
+
+    {
+        let events = db.log_executed(|| infer_all(&db));
+        assert!(events.iter().any(is_solve), "{:#?}", events);
+        assert!(!events.iter().any(is_solver), "{:#?}", events)
+    }
+}
+
+#[test]
+fn changing_an_impl_in_one_module_should_not_recollect_impls_of_other_modules() {
+    let (mut db, pos) = TestDB::with_position(
+        "
+        //- /lib.rs
+        mod foo;
+        mod bar;
+
+        //- /foo.rs
+        struct Foo;
+        struct Baz;
+        impl <|>Foo {}
+
+        //- /bar.rs
+        struct Bar;
+        impl Bar {}
+    ",
+    );
+    let krate = db.module_for_file(pos.file_id).krate;
+    let module = |name: &str| {
+        let crate_def_map = db.crate_def_map(krate);
+        let (_, &local_id) = crate_def_map[crate_def_map.root]
+            .children
+            .iter()
+            .find(|(it, _)| it.to_string() == name)
+            .unwrap();
+        format!("{:?}", hir_def::ModuleId { krate, local_id })
+    };
+    let (foo, bar) = (module("foo"), module("bar"));
+    let collects_impls_of =
+        |module: &str, event: &String| event.contains("impls_in_module") && event.contains(module);
+    {
+        let events = db.log_executed(|| {
+            db.impls_in_crate(krate);
+        });
+        assert!(events.iter().any(|it| collects_impls_of(&bar, it)), "{:#?}", events)
+    }
+
+    // The impl now has another self type, but the items of the modules stay
+    // the same.
+    let new_text = "
+        struct Foo;
+        struct Baz;
+        impl Baz {}
+    "
+    .to_string();
+
+    db.query_mut(ra_db::FileTextQuery).set(pos.file_id, Arc::new(new_text));
+
+    {
+        let events = db.log_executed(|| {
+            db.impls_in_crate(krate);
+        });
+        assert!(events.iter().any(|it| collects_impls_of(&foo, it)), "{:#?}", events);
+        assert!(!events.iter().any(|it| collects_impls_of(&bar, it)), "{:#?}", events)
+    }
+}
+
 #[test]
 fn no_such_field_diagnostics() {
     let diagnostics = TestDB::with_files(
@@ -315,3 +413,265 @@ fn no_such_field_diagnostics() {
     "###
     );
 }
+
+#[test]
+fn union_field_count_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        union U { a: u32, b: f32 }
+        fn test(u: U) {
+            let _ = U { a: 1 };
+            let _ = U { a: 1, b: 2.0 };
+            let U { a: _, b: _ } = u;
+            let U { b: _ } = u;
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "{ a: 1, b: 2.0 }": union expressions should have exactly one field
+    "{ a: _, b: _ }": union patterns should have exactly one field
+    "###
+    );
+}
+
+#[test]
+fn method_trait_not_in_scope_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        mod m {
+            pub trait Bar {
+                fn foo(&self) {}
+            }
+            impl Bar for super::S {}
+        }
+        struct S;
+        fn test() {
+            S.foo();
+        }
+        mod n {
+            use super::{m::Bar, S};
+            fn test() {
+                S.foo();
+            }
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "S.foo()": method `foo` exists on `S` but trait `Bar` is not in scope
+    "###
+    );
+}
+
+#[test]
+fn unresolved_method_call_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        struct S { f: fn() -> u32, g: u32 }
+        impl S {
+            fn inc(&mut self) {}
+        }
+        #[derive(Serialize)]
+        struct D;
+        trait Tr { fn tr(&self); }
+        fn test(s: S, r: &S, mut m: S, d: D) {
+            s.f();
+            s.g();
+            s.missing();
+            r.inc();
+            m.inc();
+            1u32.missing();
+            d.serialize();
+            s.tr();
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "s.f()": no method `f` found for `S`, but it has a callable field with that name
+    "s.g()": no method `g` found for `S`
+    "s.missing()": no method `missing` found for `S`
+    "r.inc()": method `inc` takes `&mut self`, but the receiver `&S` is a shared reference; consider making the binding a mutable reference
+    "###
+    );
+}
+
+#[test]
+fn invalid_cast_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        fn test(x: u32, s: &[u8]) {
+            let _a = &x as *const u32;
+            let _b = &x as *mut u32;
+            let _c = b'a' as char;
+            let _d = x as char;
+            let _e = s as *const [u8] as *const u8;
+            let _f = s as *const [u8] as usize;
+            let _g = &[1u8, 2] as *const u8;
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "&x as *mut u32": casting `&u32` as `*mut u32` is invalid
+    "x as char": casting `u32` as `char` is invalid
+    "s as *const [u8] as usize": casting `*const [u8]` as `usize` is invalid
+    "###
+    );
+}
+
+#[test]
+fn invalid_binary_op_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r#"
+        //- /lib.rs
+        #[lang = "eq"]
+        pub trait PartialEq<Rhs> {}
+        #[lang = "partial_ord"]
+        pub trait PartialOrd<Rhs> {}
+        #[lang = "add_assign"]
+        pub trait AddAssign<Rhs> {}
+
+        struct A;
+        struct B;
+        impl PartialEq<B> for B {}
+
+        fn test(a1: A, mut a2: A, b1: B, b2: B) {
+            let _lt = a1 < a2;
+            let _eq = b1 == b2;
+            a2 += b1;
+        }
+        "#,
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "a1 < a2": binary operation `<` cannot be applied to type `A`
+    "a2 += b1": binary operation `+=` cannot be applied to type `A`
+    "###
+    );
+}
+
+#[test]
+fn duplicate_inherent_item_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        mod a;
+        mod b;
+        struct S;
+        struct G<T>(T);
+        impl S {
+            fn new() -> S { S }
+            fn get(&self) {}
+            const C: u32 = 0;
+        }
+        impl G<u32> { fn get(&self) {} }
+        impl G<i64> { fn get(&self) {} }
+
+        //- /a.rs
+        impl super::S {
+            fn new() -> super::S { super::S }
+            fn get(&self) {}
+            const C: u32 = 1;
+            fn other(&self) {}
+        }
+
+        //- /b.rs
+        impl super::S {
+            fn get(&mut self) {}
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "get": duplicate definitions with name `get`
+    "C": duplicate definitions with name `C`
+    "get": duplicate definitions with name `get`
+    "###
+    );
+}
+
+#[test]
+fn missing_env_var_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r#"
+        //- /lib.rs
+        #[rustc_builtin_macro]
+        macro_rules! env {() => {}}
+
+        fn main() {
+            let x = env!("FOO");
+        }
+        "#,
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "env!(\"FOO\")": environment variable `FOO` not defined
+    "###
+    );
+}
+
+#[test]
+fn missing_match_arms_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        enum Either { A, B }
+        fn test(e: Either) {
+            match e {
+                Either::A => {}
+                Either::B if true => {}
+            }
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "match e {\n        Either::A => {}\n        Either::B if true => {}\n    }": Missing match arms:
+    - Either::B
+    "###
+    );
+}
+
+#[test]
+fn missing_match_arms_nested_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r"
+        //- /lib.rs
+        enum Option<T> { Some(T), None }
+        enum Result<T, E> { Ok(T), Err(E) }
+        fn test(x: Option<Result<u32, bool>>) {
+            match x {
+                Option::Some(Result::Ok(_)) => {}
+                Option::Some(Result::Err(true)) => {}
+                Option::None => {}
+            }
+            match (true, x) {
+                (true, _) => {}
+                (false, Option::Some(_)) => {}
+                (false, Option::None) => {}
+            }
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "match x {\n        Option::Some(Result::Ok(_)) => {}\n        Option::Some(Result::Err(true)) => {}\n        Option::None => {}\n    }": Missing match arms:
+    - Option::Some(Result::Err(false))
+    "###
+    );
+}
@@ -71,42 +71,42 @@ fn test2() {
     [82; 93) '{ loop {} }': T
     [84; 91) 'loop {}': !
     [89; 91) '{}': ()
-    [122; 133) '{ loop {} }': *mut [T; _]
+    [122; 133) '{ loop {} }': *mut [T; 2]
     [124; 131) 'loop {}': !
     [129; 131) '{}': ()
     [160; 173) '{     gen() }': *mut [U]
-    [166; 169) 'gen': fn gen<U>() -> *mut [U; _]
-    [166; 171) 'gen()': *mut [U; _]
+    [166; 169) 'gen': fn gen<U>() -> *mut [U; 2]
+    [166; 171) 'gen()': *mut [U; 2]
     [186; 420) '{     ...rr); }': ()
-    [196; 199) 'arr': &[u8; _]
-    [212; 216) '&[1]': &[u8; _]
-    [213; 216) '[1]': [u8; _]
+    [196; 199) 'arr': &[u8; 1]
+    [212; 216) '&[1]': &[u8; 1]
+    [213; 216) '[1]': [u8; 1]
     [214; 215) '1': u8
     [227; 228) 'a': &[u8]
-    [237; 240) 'arr': &[u8; _]
+    [237; 240) 'arr': &[u8; 1]
     [250; 251) 'b': u8
     [254; 255) 'f': fn f<u8>(&[u8]) -> u8
     [254; 260) 'f(arr)': u8
-    [256; 259) 'arr': &[u8; _]
+    [256; 259) 'arr': &[u8; 1]
     [270; 271) 'c': &[u8]
     [280; 287) '{ arr }': &[u8]
-    [282; 285) 'arr': &[u8; _]
+    [282; 285) 'arr': &[u8; 1]
     [297; 298) 'd': u8
     [301; 302) 'g': fn g<u8>(S<&[u8]>) -> u8
     [301; 316) 'g(S { a: arr })': u8
     [303; 315) 'S { a: arr }': S<&[u8]>
-    [310; 313) 'arr': &[u8; _]
-    [326; 327) 'e': [&[u8]; _]
-    [341; 346) '[arr]': [&[u8]; _]
-    [342; 345) 'arr': &[u8; _]
-    [356; 357) 'f': [&[u8]; _]
-    [371; 379) '[arr; 2]': [&[u8]; _]
-    [372; 375) 'arr': &[u8; _]
+    [310; 313) 'arr': &[u8; 1]
+    [326; 327) 'e': [&[u8]; 1]
+    [341; 346) '[arr]': [&[u8]; 1]
+    [342; 345) 'arr': &[u8; 1]
+    [356; 357) 'f': [&[u8]; 2]
+    [371; 379) '[arr; 2]': [&[u8]; 2]
+    [372; 375) 'arr': &[u8; 1]
     [377; 378) '2': usize
     [389; 390) 'g': (&[u8], &[u8])
     [407; 417) '(arr, arr)': (&[u8], &[u8])
-    [408; 411) 'arr': &[u8; _]
-    [413; 416) 'arr': &[u8; _]
+    [408; 411) 'arr': &[u8; 1]
+    [413; 416) 'arr': &[u8; 1]
     "###
     );
 }
@@ -122,8 +122,8 @@ fn test() {
         @r###"
     [11; 40) '{     ...[1]; }': ()
     [21; 22) 'x': &[i32]
-    [33; 37) '&[1]': &[i32; _]
-    [34; 37) '[1]': [i32; _]
+    [33; 37) '&[1]': &[i32; 1]
+    [34; 37) '[1]': [i32; 1]
     [35; 36) '1': i32
     "###);
 }
@@ -159,22 +159,22 @@ fn test(a: A<[u8; 2]>, b: B<[u8; 2]>, c: C<[u8; 2]>) {
     [334; 335) 'x': C<[T]>
     [355; 360) '{ x }': C<[T]>
     [357; 358) 'x': C<[T]>
-    [370; 371) 'a': A<[u8; _]>
-    [385; 386) 'b': B<[u8; _]>
-    [400; 401) 'c': C<[u8; _]>
+    [370; 371) 'a': A<[u8; 2]>
+    [385; 386) 'b': B<[u8; 2]>
+    [400; 401) 'c': C<[u8; 2]>
     [415; 481) '{     ...(c); }': ()
     [425; 426) 'd': A<[{unknown}]>
     [429; 433) 'foo1': fn foo1<{unknown}>(A<[{unknown}]>) -> A<[{unknown}]>
     [429; 436) 'foo1(a)': A<[{unknown}]>
-    [434; 435) 'a': A<[u8; _]>
+    [434; 435) 'a': A<[u8; 2]>
     [446; 447) 'e': B<[u8]>
     [450; 454) 'foo2': fn foo2<u8>(B<[u8]>) -> B<[u8]>
     [450; 457) 'foo2(b)': B<[u8]>
-    [455; 456) 'b': B<[u8; _]>
+    [455; 456) 'b': B<[u8; 2]>
     [467; 468) 'f': C<[u8]>
     [471; 475) 'foo3': fn foo3<u8>(C<[u8]>) -> C<[u8]>
     [471; 478) 'foo3(c)': C<[u8]>
-    [476; 477) 'c': C<[u8; _]>
+    [476; 477) 'c': C<[u8; 2]>
     "###
     );
 }
@@ -204,12 +204,12 @@ fn test() {
     [72; 97) '{     ...     }': &[i32]
     [82; 85) 'foo': fn foo<i32>(&[i32]) -> &[i32]
     [82; 91) 'foo(&[1])': &[i32]
-    [86; 90) '&[1]': &[i32; _]
-    [87; 90) '[1]': [i32; _]
+    [86; 90) '&[1]': &[i32; 1]
+    [87; 90) '[1]': [i32; 1]
     [88; 89) '1': i32
-    [103; 123) '{     ...     }': &[i32; _]
-    [113; 117) '&[1]': &[i32; _]
-    [114; 117) '[1]': [i32; _]
+    [103; 123) '{     ...     }': &[i32; 1]
+    [113; 117) '&[1]': &[i32; 1]
+    [114; 117) '[1]': [i32; 1]
     [115; 116) '1': i32
     "###
     );
@@ -237,15 +237,15 @@ fn test() {
     [60; 61) 'x': &[i32]
     [64; 123) 'if tru...     }': &[i32]
     [67; 71) 'true': bool
-    [72; 92) '{     ...     }': &[i32; _]
-    [82; 86) '&[1]': &[i32; _]
-    [83; 86) '[1]': [i32; _]
+    [72; 92) '{     ...     }': &[i32; 1]
+    [82; 86) '&[1]': &[i32; 1]
+    [83; 86) '[1]': [i32; 1]
     [84; 85) '1': i32
     [98; 123) '{     ...     }': &[i32]
     [108; 111) 'foo': fn foo<i32>(&[i32]) -> &[i32]
     [108; 117) 'foo(&[1])': &[i32]
-    [112; 116) '&[1]': &[i32; _]
-    [113; 116) '[1]': [i32; _]
+    [112; 116) '&[1]': &[i32; 1]
+    [113; 116) '[1]': [i32; 1]
     [114; 115) '1': i32
     "###
     );
@@ -277,16 +277,16 @@ fn test(i: i32) {
     [88; 89) '2': i32
     [93; 96) 'foo': fn foo<i32>(&[i32]) -> &[i32]
     [93; 102) 'foo(&[2])': &[i32]
-    [97; 101) '&[2]': &[i32; _]
-    [98; 101) '[2]': [i32; _]
+    [97; 101) '&[2]': &[i32; 1]
+    [98; 101) '[2]': [i32; 1]
     [99; 100) '2': i32
     [112; 113) '1': i32
-    [117; 121) '&[1]': &[i32; _]
-    [118; 121) '[1]': [i32; _]
+    [117; 121) '&[1]': &[i32; 1]
+    [118; 121) '[1]': [i32; 1]
     [119; 120) '1': i32
     [131; 132) '_': i32
-    [136; 140) '&[3]': &[i32; _]
-    [137; 140) '[3]': [i32; _]
+    [136; 140) '&[3]': &[i32; 1]
+    [137; 140) '[3]': [i32; 1]
     [138; 139) '3': i32
     "###
     );
@@ -316,18 +316,18 @@ fn test(i: i32) {
     [70; 147) 'match ...     }': &[i32]
     [76; 77) 'i': i32
     [88; 89) '1': i32
-    [93; 97) '&[1]': &[i32; _]
-    [94; 97) '[1]': [i32; _]
+    [93; 97) '&[1]': &[i32; 1]
+    [94; 97) '[1]': [i32; 1]
     [95; 96) '1': i32
     [107; 108) '2': i32
     [112; 115) 'foo': fn foo<i32>(&[i32]) -> &[i32]
     [112; 121) 'foo(&[2])': &[i32]
-    [116; 120) '&[2]': &[i32; _]
-    [117; 120) '[2]': [i32; _]
+    [116; 120) '&[2]': &[i32; 1]
+    [117; 120) '[2]': [i32; 1]
     [118; 119) '2': i32
     [131; 132) '_': i32
-    [136; 140) '&[3]': &[i32; _]
-    [137; 140) '[3]': [i32; _]
+    [136; 140) '&[3]': &[i32; 1]
+    [137; 140) '[3]': [i32; 1]
     [138; 139) '3': i32
     "###
     );
@@ -527,6 +527,70 @@ fn test() {
     );
 }
 
+#[test]
+fn coerce_closure_with_captures_to_fn_ptr() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+fn test() {
+    let n = 1u32;
+    let f: fn(u32) -> u32 = |x| x * 2;
+    let g: fn(u32) -> u32 = |x| x + n;
+}
+"#, true),
+        @r###"
+    [11; 110) '{     ...+ n; }': ()
+    [21; 22) 'n': u32
+    [25; 29) '1u32': u32
+    [39; 40) 'f': fn(u32) -> u32
+    [59; 68) '|x| x * 2': |u32| -> u32
+    [60; 61) 'x': u32
+    [63; 64) 'x': u32
+    [63; 68) 'x * 2': u32
+    [67; 68) '2': u32
+    [78; 79) 'g': fn(u32) -> u32
+    [98; 107) '|x| x + n': |u32| -> u32
+    [99; 100) 'x': u32
+    [102; 103) 'x': u32
+    [102; 107) 'x + n': u32
+    [106; 107) 'n': u32
+    [98; 107): expected fn(u32) -> u32, got |u32| -> u32
+    "###
+    );
+}
+
+#[test]
+fn coerce_fn_items_in_match_arms_to_fn_ptr() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+fn foo(x: u32) -> isize { 1 }
+fn bar(x: u32) -> isize { 2 }
+fn test(b: bool) {
+    let f = match b {
+        true => foo,
+        false => bar,
+    };
+}
+"#, true),
+        @r###"
+    [8; 9) 'x': u32
+    [25; 30) '{ 1 }': isize
+    [27; 28) '1': isize
+    [38; 39) 'x': u32
+    [55; 60) '{ 2 }': isize
+    [57; 58) '2': isize
+    [69; 70) 'b': bool
+    [78; 153) '{     ...  }; }': ()
+    [88; 89) 'f': fn(u32) -> isize
+    [92; 150) 'match ...     }': fn(u32) -> isize
+    [98; 99) 'b': bool
+    [110; 114) 'true': bool
+    [118; 121) 'foo': fn foo(u32) -> isize
+    [131; 136) 'false': bool
+    [140; 143) 'bar': fn bar(u32) -> isize
+    "###
+    );
+}
+
 #[test]
 fn coerce_placeholder_ref() {
     // placeholders should unify, even behind references
@@ -567,8 +631,8 @@ fn test() {
         @r###"
     [162; 199) '{     ... 3]; }': ()
     [172; 173) 'f': &[usize]
-    [186; 196) '&[1, 2, 3]': &[usize; _]
-    [187; 196) '[1, 2, 3]': [usize; _]
+    [186; 196) '&[1, 2, 3]': &[usize; 3]
+    [187; 196) '[1, 2, 3]': [usize; 3]
     [188; 189) '1': usize
     [191; 192) '2': usize
     [194; 195) '3': usize
@@ -686,3 +750,51 @@ fn test() {
     "###
     );
 }
+
+#[test]
+fn array_lengths_must_match() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+const N: usize = 2;
+
+fn take(a: [u8; 2]) {}
+
+fn test() {
+    let a: [u8; 2] = [1, 2, 3];
+    let b: [u8; N] = [0; 2];
+    let c = [0u8; N];
+    take(c);
+    take([0; 3]);
+}
+"#, true),
+        @r###"
+    [18; 19) '2': usize
+    [30; 31) 'a': [u8; 2]
+    [42; 44) '{}': ()
+    [56; 173) '{     ...3]); }': ()
+    [66; 67) 'a': [u8; 2]
+    [79; 88) '[1, 2, 3]': [u8; 3]
+    [80; 81) '1': u8
+    [83; 84) '2': u8
+    [86; 87) '3': u8
+    [98; 99) 'b': [u8; 2]
+    [111; 117) '[0; 2]': [u8; 2]
+    [112; 113) '0': u8
+    [115; 116) '2': usize
+    [127; 128) 'c': [u8; 2]
+    [131; 139) '[0u8; N]': [u8; 2]
+    [132; 135) '0u8': u8
+    [137; 138) 'N': usize
+    [145; 149) 'take': fn take([u8; 2]) -> ()
+    [145; 152) 'take(c)': ()
+    [150; 151) 'c': [u8; 2]
+    [158; 162) 'take': fn take([u8; 2]) -> ()
+    [158; 170) 'take([0; 3])': ()
+    [163; 169) '[0; 3]': [u8; 3]
+    [164; 165) '0': u8
+    [167; 168) '3': usize
+    [79; 88): expected [u8; 2], got [u8; 3]
+    [163; 169): expected [u8; 2], got [u8; 3]
+    "###
+    );
+}
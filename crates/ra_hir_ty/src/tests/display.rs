@@ -0,0 +1,128 @@
+use ra_db::fixture::WithFixture;
+
+use super::ty_at_pos;
+use crate::{
+    display::{ClosureStyle, HirDisplay},
+    test_db::TestDB,
+};
+
+fn truncated_type_at(content: &str, max_size: Option<usize>) -> String {
+    let (db, pos) = TestDB::with_position(content);
+    ty_at_pos(&db, pos).display_truncated(&db, max_size).to_string()
+}
+
+fn impl_fn_type_at(content: &str) -> String {
+    let (db, pos) = TestDB::with_position(content);
+    ty_at_pos(&db, pos)
+        .display_truncated(&db, None)
+        .with_closure_style(ClosureStyle::ImplFn)
+        .to_string()
+}
+
+#[test]
+fn truncation_keeps_outermost_constructor() {
+    let t = truncated_type_at(
+        r#"
+//- /main.rs
+struct VeryLongOuterName<T>(T);
+fn test() {
+    let a = VeryLongOuterName(0u32);
+    a<|>;
+}
+"#,
+        Some(0),
+    );
+    assert_eq!(t, "VeryLongOuterName<…>");
+}
+
+#[test]
+fn truncation_does_not_split_names() {
+    let t = truncated_type_at(
+        r#"
+//- /main.rs
+struct Smol<T>(T);
+struct VeryLongInnerName<T>(T);
+fn test() {
+    let a = Smol(VeryLongInnerName(0u32));
+    a<|>;
+}
+"#,
+        Some(7),
+    );
+    assert_eq!(t, "Smol<VeryLongInnerName<…>>");
+}
+
+#[test]
+fn truncation_elides_remaining_arguments_at_once() {
+    let t = truncated_type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let a = (0u32, 0u64, 0u8);
+    a<|>;
+}
+"#,
+        Some(4),
+    );
+    assert_eq!(t, "(u32, …)");
+}
+
+#[test]
+fn truncated_display_shows_unknown_as_placeholder() {
+    let t = truncated_type_at(
+        r#"
+//- /main.rs
+struct S<T>(T);
+fn test() {
+    let a = S(no_such_thing);
+    a<|>;
+}
+"#,
+        None,
+    );
+    assert_eq!(t, "S<_>");
+}
+
+#[test]
+fn closure_as_impl_fn() {
+    let t = impl_fn_type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let f = |a: u64, b: u64| -> i32 { 1 };
+    f<|>;
+}
+"#,
+    );
+    assert_eq!(t, "impl Fn(u64, u64) -> i32");
+}
+
+#[test]
+fn closure_as_impl_fn_without_return_type() {
+    let t = impl_fn_type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let f = |a: u32| {};
+    f<|>;
+}
+"#,
+    );
+    assert_eq!(t, "impl Fn(u32)");
+}
+
+#[test]
+fn closure_with_pipes() {
+    let t = truncated_type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let f = || 1u8;
+    let g = |a: u64| f;
+    g<|>;
+}
+"#,
+        None,
+    );
+    assert_eq!(t, "|…| -> || -> u8");
+}
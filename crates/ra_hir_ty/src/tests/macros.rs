@@ -333,6 +333,34 @@ pub fn baz() -> usize { 31usize }
     assert_eq!("(i32, usize)", type_at_pos(&db, pos));
 }
 
+#[test]
+fn infer_macro_with_local_inner_macros_is_correct_in_expr() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:foo
+fn test() {
+    let x = (foo::foo!(1), foo::foo!(2));
+    x<|>;
+}
+
+//- /lib.rs crate:foo
+#[macro_export(local_inner_macros)]
+macro_rules! foo {
+    (1) => { bar!() };
+    (2) => { 1 + $crate::baz() };
+}
+
+#[macro_export]
+macro_rules! bar {
+    () => { 42 }
+}
+
+pub fn baz() -> usize { 31usize }
+"#,
+    );
+    assert_eq!("(i32, usize)", type_at_pos(&db, pos));
+}
+
 #[test]
 fn infer_type_value_non_legacy_macro_use_as() {
     assert_snapshot!(
@@ -419,6 +447,48 @@ fn main() {
     );
 }
 
+#[test]
+fn infer_builtin_macros_include() {
+    let t = type_at(
+        r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! include {() => {}}
+
+include!("foo.rs");
+
+fn main() {
+    bar()<|>;
+}
+
+//- /foo.rs
+fn bar() -> u32 {0}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn infer_builtin_macros_concat_with_env() {
+    assert_snapshot!(
+        infer(r#"
+#[rustc_builtin_macro]
+macro_rules! concat {() => {}}
+#[rustc_builtin_macro]
+macro_rules! env {() => {}}
+
+fn main() {
+    let x = concat!(env!("X"), "/y");
+}
+"#),
+        @r###"
+    ![0; 4) '"/y"': &str
+    [117; 158) '{     ...y"); }': ()
+    [127; 128) 'x': &str
+    "###
+    );
+}
+
 #[test]
 fn infer_derive_clone_simple() {
     let (db, pos) = TestDB::with_position(
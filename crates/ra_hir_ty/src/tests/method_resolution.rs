@@ -660,6 +660,98 @@ fn test() {
     );
 }
 
+#[test]
+fn infer_trait_assoc_item_on_generic_param() {
+    assert_snapshot!(
+        infer(r#"
+trait Default {
+    fn default() -> Self;
+}
+trait Trait<U>: Default {
+    const CONST: U;
+    fn make(u: U) -> Self;
+}
+fn test<T: Trait<u64>>() {
+    let a = T::default();
+    let b = T::CONST;
+    let c = T::make(1);
+}
+"#),
+        @r###"
+    [103; 104) 'u': U
+    [145; 220) '{     ...(1); }': ()
+    [155; 156) 'a': T
+    [159; 169) 'T::default': fn default<T>() -> T
+    [159; 171) 'T::default()': T
+    [181; 182) 'b': u64
+    [185; 193) 'T::CONST': u64
+    [203; 204) 'c': T
+    [207; 214) 'T::make': fn make<T, u64>(u64) -> T
+    [207; 217) 'T::make(1)': T
+    [215; 216) '1': u64
+    "###
+    );
+}
+
+#[test]
+fn infer_super_trait_assoc_item_through_sub_trait() {
+    assert_snapshot!(
+        infer(r#"
+trait Super<U> {
+    const SUPER: U;
+    fn make() -> i64;
+}
+trait Sub: Super<u32> {
+    fn make() -> u8;
+}
+fn test<T: Sub>() {
+    let a = <T as Sub>::SUPER;
+    let b = Sub::SUPER;
+    let c = T::make();
+    let d = <T as Sub>::make();
+}
+"#),
+        @r###"
+    [127; 240) '{     ...e(); }': ()
+    [137; 138) 'a': u32
+    [141; 158) '<T as ...:SUPER': u32
+    [168; 169) 'b': u32
+    [172; 182) 'Sub::SUPER': u32
+    [192; 193) 'c': u8
+    [196; 203) 'T::make': fn make<T>() -> u8
+    [196; 205) 'T::make()': u8
+    [215; 216) 'd': u8
+    [219; 235) '<T as ...::make': fn make<T>() -> u8
+    [219; 237) '<T as ...make()': u8
+    "###
+    );
+}
+
+#[test]
+fn infer_assoc_type_assoc_method_on_generic_param() {
+    assert_snapshot!(
+        infer(r#"
+mod m {
+    pub trait New {
+        fn new() -> Self;
+    }
+}
+trait Trait {
+    type Assoc: m::New;
+}
+fn test<T: Trait>() {
+    let x = T::Assoc::new();
+}
+"#),
+        @r###"
+    [123; 155) '{     ...w(); }': ()
+    [133; 134) 'x': {unknown}
+    [137; 150) 'T::Assoc::new': {unknown}
+    [137; 152) 'T::Assoc::new()': {unknown}
+    "###
+    );
+}
+
 #[test]
 fn infer_call_trait_method_on_generic_param_1() {
     assert_snapshot!(
@@ -1058,3 +1150,62 @@ fn test() { (S {}).method()<|>; }
     );
     assert_eq!(t, "()");
 }
+
+#[test]
+fn method_resolution_does_not_autoderef_raw_pointers() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct S;
+impl S { fn foo(&self) -> u8 {} }
+fn test(ptr: *const S) { (ptr.foo(), (*ptr).foo())<|>; }
+"#,
+    );
+    assert_eq!(t, "({unknown}, u8)");
+}
+
+#[test]
+fn method_resolution_raw_pointer_inherent_impls() {
+    let t = type_at(
+        r#"
+//- /main.rs
+#[lang = "const_ptr"]
+impl<T> *const T { fn foo(self) -> i8 {} }
+#[lang = "mut_ptr"]
+impl<T> *mut T { fn foo(self) -> i16 {} }
+struct S;
+impl S { fn foo(&self) -> u8 {} }
+fn test(a: *const S, b: *mut S) { (a.foo(), b.foo(), (*a).foo())<|>; }
+"#,
+    );
+    assert_eq!(t, "(i8, i16, u8)");
+}
+
+#[test]
+fn method_resolution_trait_impl_only_for_reference() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait { fn foo(self) -> u128; }
+struct S;
+impl Trait for &S { fn foo(self) -> u128 { 0 } }
+fn test() { (S.foo(), (&S).foo())<|>; }
+"#,
+    );
+    assert_eq!(t, "(u128, u128)");
+}
+
+#[test]
+fn method_resolution_trait_impls_for_shared_and_mut_references() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait { type Out; fn foo(self) -> Self::Out; }
+struct S;
+impl Trait for &S { type Out = u8; fn foo(self) -> u8 { 0 } }
+impl Trait for &mut S { type Out = u16; fn foo(self) -> u16 { 0 } }
+fn test() { ((&S).foo(), (&mut S).foo())<|>; }
+"#,
+    );
+    assert_eq!(t, "(u8, u16)");
+}
@@ -53,7 +53,7 @@ fn test(x: &i32) {
     [140; 141) 'g': {unknown}
     [144; 145) 'e': {unknown}
     [158; 205) 'if let...     }': ()
-    [165; 170) '[val]': {unknown}
+    [165; 170) '[val]': [{unknown}]
     [173; 176) 'opt': {unknown}
     [177; 205) '{     ...     }': ()
     [191; 192) 'h': {unknown}
@@ -136,6 +136,70 @@ fn test() {
     );
 }
 
+#[test]
+fn infer_pattern_slice() {
+    assert_snapshot!(
+        infer(r#"
+fn test(arr: &[i32; 3], slice: &[u8]) {
+    let [head, tail @ ..] = arr;
+    let [first, .., last] = slice;
+    let &[x, ref rest @ ..] = arr;
+}
+"#),
+    @r###"
+    [9; 12) 'arr': &[i32; 3]
+    [25; 30) 'slice': &[u8]
+    [39; 145) '{     ...arr; }': ()
+    [49; 66) '[head,... @ ..]': [i32; 3]
+    [50; 54) 'head': &i32
+    [56; 65) 'tail @ ..': &[i32; 2]
+    [63; 65) '..': [i32; 2]
+    [69; 72) 'arr': &[i32; 3]
+    [82; 99) '[first... last]': [u8]
+    [83; 88) 'first': &u8
+    [90; 92) '..': [u8]
+    [94; 98) 'last': &u8
+    [102; 107) 'slice': &[u8]
+    [117; 136) '&[x, r... @ ..]': &[i32; 3]
+    [118; 136) '[x, re... @ ..]': [i32; 3]
+    [119; 120) 'x': i32
+    [122; 135) 'ref rest @ ..': &[i32; 2]
+    [133; 135) '..': [i32; 2]
+    [139; 142) 'arr': &[i32; 3]
+    "###
+    );
+}
+
+#[test]
+fn infer_pattern_box() {
+    assert_snapshot!(
+        infer(r#"
+#[lang = "owned_box"]
+pub struct Box<T: ?Sized> {
+    inner: *mut T,
+}
+
+fn test(b: Box<(u32, i64)>, r: &Box<u8>) {
+    let box (x, y) = b;
+    let box c = r;
+}
+"#),
+    @r###"
+    [81; 82) 'b': Box<(u32, i64)>
+    [101; 102) 'r': &Box<u8>
+    [114; 160) '{     ...= r; }': ()
+    [124; 134) 'box (x, y)': Box<(u32, i64)>
+    [128; 134) '(x, y)': (u32, i64)
+    [129; 130) 'x': u32
+    [132; 133) 'y': i64
+    [137; 138) 'b': Box<(u32, i64)>
+    [148; 153) 'box c': Box<u8>
+    [152; 153) 'c': &u8
+    [156; 157) 'r': &Box<u8>
+    "###
+    );
+}
+
 #[test]
 fn infer_adt_pattern() {
     assert_snapshot!(
@@ -237,6 +301,66 @@ fn test(a1: A<u32>, o: Option<u64>) {
     );
 }
 
+#[test]
+fn infer_let_chain() {
+    assert_snapshot!(
+        infer(r#"
+enum Option<T> { Some(T), None }
+
+fn test(a: Option<Option<u32>>, flag: bool) {
+    if flag && let Option::Some(b) = a && let Option::Some(c) = b {
+        let d = c;
+    }
+}
+"#),
+        @r###"
+    [43; 44) 'a': Option<Option<u32>>
+    [67; 71) 'flag': bool
+    [79; 175) '{     ...   } }': ()
+    [85; 173) 'if fla...     }': ()
+    [88; 92) 'flag': bool
+    [88; 119) 'flag &...b) = a': bool
+    [88; 146) 'flag &...c) = b': bool
+    [96; 119) 'let Op...b) = a': bool
+    [100; 115) 'Option::Some(b)': Option<Option<u32>>
+    [113; 114) 'b': Option<u32>
+    [118; 119) 'a': Option<Option<u32>>
+    [123; 146) 'let Op...c) = b': bool
+    [127; 142) 'Option::Some(c)': Option<u32>
+    [140; 141) 'c': u32
+    [145; 146) 'b': Option<u32>
+    [147; 173) '{     ...     }': ()
+    [161; 162) 'd': u32
+    [165; 166) 'c': u32
+    "###
+    );
+}
+
+#[test]
+fn infer_let_else() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+enum Option<T> { Some(T), None }
+
+fn test(a: Option<u32>) -> u32 {
+    let Option::Some(b) = a else { return 0; };
+    b
+}
+"#, true),
+        @r###"
+    [43; 44) 'a': Option<u32>
+    [66; 123) '{     ...   b }': u32
+    [76; 91) 'Option::Some(b)': Option<u32>
+    [89; 90) 'b': u32
+    [94; 95) 'a': Option<u32>
+    [101; 114) '{ return 0; }': !
+    [103; 111) 'return 0': !
+    [110; 111) '0': u32
+    [120; 121) 'b': u32
+    "###
+    );
+}
+
 #[test]
 fn infer_const_pattern() {
     assert_snapshot!(
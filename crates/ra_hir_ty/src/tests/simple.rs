@@ -1,4 +1,4 @@
-use super::{infer, type_at, type_at_pos};
+use super::{infer, infer_with_mismatches, type_at, type_at_pos};
 use crate::test_db::TestDB;
 use insta::assert_snapshot;
 use ra_db::fixture::WithFixture;
@@ -28,7 +28,7 @@ mod boxed {
 
 "#,
     );
-    assert_eq!("(Box<i32>, Box<Box<i32>>, Box<&i32>, Box<[i32; _]>)", type_at_pos(&db, pos));
+    assert_eq!("(Box<i32>, Box<Box<i32>>, Box<&i32>, Box<[i32; 1]>)", type_at_pos(&db, pos));
 }
 
 #[test]
@@ -100,6 +100,103 @@ pub mod ops {
     );
 }
 
+#[test]
+fn infer_ranges_by_lang_items() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+#[lang = "RangeFull"]
+struct RangeFull;
+#[lang = "Range"]
+struct Range<Idx> { start: Idx, end: Idx }
+#[lang = "RangeFrom"]
+struct RangeFrom<Idx> { start: Idx }
+#[lang = "RangeTo"]
+struct RangeTo<Idx> { end: Idx }
+#[lang = "RangeInclusive"]
+struct RangeInclusive<Idx> { start: Idx, end: Idx }
+#[lang = "RangeToInclusive"]
+struct RangeToInclusive<Idx> { end: Idx }
+
+fn test(x: u8) {
+    ..;
+    x..;
+    ..x;
+    0..x;
+    ..=x;
+    x..=5;
+    1u32..2i64;
+}
+"#, true),
+        @r###"
+    [373; 374) 'x': u8
+    [380; 456) '{     ...i64; }': ()
+    [386; 388) '..': RangeFull
+    [394; 395) 'x': u8
+    [394; 397) 'x..': RangeFrom<u8>
+    [403; 406) '..x': RangeTo<u8>
+    [405; 406) 'x': u8
+    [412; 413) '0': u8
+    [412; 416) '0..x': Range<u8>
+    [415; 416) 'x': u8
+    [422; 426) '..=x': RangeToInclusive<u8>
+    [425; 426) 'x': u8
+    [432; 433) 'x': u8
+    [432; 437) 'x..=5': RangeInclusive<u8>
+    [436; 437) '5': u8
+    [443; 447) '1u32': u32
+    [443; 453) '1u32..2i64': Range<u32>
+    [449; 453) '2i64': i64
+    [449; 453): expected u32, got i64
+    "###
+    );
+}
+
+#[test]
+fn infer_slice_index_by_range() {
+    assert_snapshot!(
+        infer(r#"
+#[lang = "RangeFull"]
+struct RangeFull;
+#[lang = "Range"]
+struct Range<Idx> { start: Idx, end: Idx }
+#[lang = "RangeFrom"]
+struct RangeFrom<Idx> { start: Idx }
+
+fn test(a: &[u8]) {
+    let b = [1u16, 2, 3];
+    a[1..3];
+    a[..];
+    b[2..];
+    a[0];
+}
+"#),
+        @r###"
+    [170; 171) 'a': &[u8]
+    [180; 255) '{     ...[0]; }': ()
+    [190; 191) 'b': [u16; 3]
+    [194; 206) '[1u16, 2, 3]': [u16; 3]
+    [195; 199) '1u16': u16
+    [201; 202) '2': u16
+    [204; 205) '3': u16
+    [212; 213) 'a': &[u8]
+    [212; 219) 'a[1..3]': [u8]
+    [214; 215) '1': usize
+    [214; 218) '1..3': Range<usize>
+    [217; 218) '3': usize
+    [225; 226) 'a': &[u8]
+    [225; 230) 'a[..]': [u8]
+    [227; 229) '..': RangeFull
+    [236; 237) 'b': [u16; 3]
+    [236; 242) 'b[2..]': [u16]
+    [238; 239) '2': usize
+    [238; 241) '2..': RangeFrom<usize>
+    [248; 249) 'a': &[u8]
+    [248; 252) 'a[0]': u8
+    [250; 251) '0': usize
+    "###
+    );
+}
+
 #[test]
 fn infer_while_let() {
     let (db, pos) = TestDB::with_position(
@@ -119,6 +216,72 @@ fn test() {
     assert_eq!("f32", type_at_pos(&db, pos));
 }
 
+#[test]
+fn infer_labelled_block() {
+    assert_snapshot!(
+        infer(r#"
+fn test(c: bool) {
+    let x = 'a: {
+        if c {
+            break 'a 1;
+        }
+        if !c {
+            break 'a 2u8;
+        }
+        3
+    };
+}
+"#),
+        @r###"
+    [9; 10) 'c': bool
+    [18; 157) '{     ...  }; }': ()
+    [28; 29) 'x': u8
+    [32; 154) ''a: { ...     }': u8
+    [46; 86) 'if c {...     }': ()
+    [49; 50) 'c': bool
+    [51; 86) '{     ...     }': !
+    [65; 75) 'break 'a 1': !
+    [74; 75) '1': u8
+    [95; 138) 'if !c ...     }': ()
+    [98; 100) '!c': bool
+    [99; 100) 'c': bool
+    [101; 138) '{     ...     }': !
+    [115; 127) 'break 'a 2u8': !
+    [124; 127) '2u8': u8
+    [147; 148) '3': u8
+    "###
+    );
+}
+
+#[test]
+fn infer_labelled_block_mismatch() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+fn test(c: bool) {
+    let x: u32 = 'a: {
+        if c {
+            break 'a 1u32;
+        }
+        2i64
+    };
+}
+"#, true),
+        @r###"
+    [9; 10) 'c': bool
+    [18; 116) '{     ...  }; }': ()
+    [28; 29) 'x': u32
+    [37; 113) ''a: { ...     }': u32
+    [51; 94) 'if c {...     }': ()
+    [54; 55) 'c': bool
+    [56; 94) '{     ...     }': !
+    [70; 83) 'break 'a 1u32': !
+    [79; 83) '1u32': u32
+    [103; 107) '2i64': i64
+    [103; 107): expected u32, got i64
+    "###
+    );
+}
+
 #[test]
 fn infer_basics() {
     assert_snapshot!(
@@ -276,6 +439,39 @@ fn test() {
     );
 }
 
+#[test]
+fn infer_union() {
+    assert_snapshot!(
+        infer(r#"
+union U {
+    a: u32,
+    b: f32,
+}
+
+fn test(u: U) {
+    let v = U { a: 1 };
+    u.a;
+    v.b;
+    let U { a } = u;
+}
+"#),
+        @r###"
+    [46; 47) 'u': U
+    [52; 118) '{     ...= u; }': ()
+    [62; 63) 'v': U
+    [66; 76) 'U { a: 1 }': U
+    [73; 74) '1': u32
+    [82; 83) 'u': U
+    [82; 85) 'u.a': u32
+    [91; 92) 'v': U
+    [91; 94) 'v.b': f32
+    [104; 111) 'U { a }': U
+    [108; 109) 'a': u32
+    [114; 115) 'u': U
+    "###
+    );
+}
+
 #[test]
 fn infer_enum() {
     assert_snapshot!(
@@ -1061,55 +1257,55 @@ fn test(x: &str, y: isize) {
     [9; 10) 'x': &str
     [18; 19) 'y': isize
     [28; 293) '{     ... []; }': ()
-    [38; 39) 'a': [&str; _]
-    [42; 45) '[x]': [&str; _]
+    [38; 39) 'a': [&str; 1]
+    [42; 45) '[x]': [&str; 1]
     [43; 44) 'x': &str
-    [55; 56) 'b': [[&str; _]; _]
-    [59; 65) '[a, a]': [[&str; _]; _]
-    [60; 61) 'a': [&str; _]
-    [63; 64) 'a': [&str; _]
-    [75; 76) 'c': [[[&str; _]; _]; _]
-    [79; 85) '[b, b]': [[[&str; _]; _]; _]
-    [80; 81) 'b': [[&str; _]; _]
-    [83; 84) 'b': [[&str; _]; _]
-    [96; 97) 'd': [isize; _]
-    [100; 112) '[y, 1, 2, 3]': [isize; _]
+    [55; 56) 'b': [[&str; 1]; 2]
+    [59; 65) '[a, a]': [[&str; 1]; 2]
+    [60; 61) 'a': [&str; 1]
+    [63; 64) 'a': [&str; 1]
+    [75; 76) 'c': [[[&str; 1]; 2]; 2]
+    [79; 85) '[b, b]': [[[&str; 1]; 2]; 2]
+    [80; 81) 'b': [[&str; 1]; 2]
+    [83; 84) 'b': [[&str; 1]; 2]
+    [96; 97) 'd': [isize; 4]
+    [100; 112) '[y, 1, 2, 3]': [isize; 4]
     [101; 102) 'y': isize
     [104; 105) '1': isize
     [107; 108) '2': isize
     [110; 111) '3': isize
-    [122; 123) 'd': [isize; _]
-    [126; 138) '[1, y, 2, 3]': [isize; _]
+    [122; 123) 'd': [isize; 4]
+    [126; 138) '[1, y, 2, 3]': [isize; 4]
     [127; 128) '1': isize
     [130; 131) 'y': isize
     [133; 134) '2': isize
     [136; 137) '3': isize
-    [148; 149) 'e': [isize; _]
-    [152; 155) '[y]': [isize; _]
+    [148; 149) 'e': [isize; 1]
+    [152; 155) '[y]': [isize; 1]
     [153; 154) 'y': isize
-    [165; 166) 'f': [[isize; _]; _]
-    [169; 175) '[d, d]': [[isize; _]; _]
-    [170; 171) 'd': [isize; _]
-    [173; 174) 'd': [isize; _]
-    [185; 186) 'g': [[isize; _]; _]
-    [189; 195) '[e, e]': [[isize; _]; _]
-    [190; 191) 'e': [isize; _]
-    [193; 194) 'e': [isize; _]
-    [206; 207) 'h': [i32; _]
-    [210; 216) '[1, 2]': [i32; _]
+    [165; 166) 'f': [[isize; 4]; 2]
+    [169; 175) '[d, d]': [[isize; 4]; 2]
+    [170; 171) 'd': [isize; 4]
+    [173; 174) 'd': [isize; 4]
+    [185; 186) 'g': [[isize; 1]; 2]
+    [189; 195) '[e, e]': [[isize; 1]; 2]
+    [190; 191) 'e': [isize; 1]
+    [193; 194) 'e': [isize; 1]
+    [206; 207) 'h': [i32; 2]
+    [210; 216) '[1, 2]': [i32; 2]
     [211; 212) '1': i32
     [214; 215) '2': i32
-    [226; 227) 'i': [&str; _]
-    [230; 240) '["a", "b"]': [&str; _]
+    [226; 227) 'i': [&str; 2]
+    [230; 240) '["a", "b"]': [&str; 2]
     [231; 234) '"a"': &str
     [236; 239) '"b"': &str
-    [251; 252) 'b': [[&str; _]; _]
-    [255; 265) '[a, ["b"]]': [[&str; _]; _]
-    [256; 257) 'a': [&str; _]
-    [259; 264) '["b"]': [&str; _]
+    [251; 252) 'b': [[&str; 1]; 2]
+    [255; 265) '[a, ["b"]]': [[&str; 1]; 2]
+    [256; 257) 'a': [&str; 1]
+    [259; 264) '["b"]': [&str; 1]
     [260; 263) '"b"': &str
-    [275; 276) 'x': [u8; _]
-    [288; 290) '[]': [u8; _]
+    [275; 276) 'x': [u8; 0]
+    [288; 290) '[]': [u8; 0]
     "###
     );
 }
@@ -1578,6 +1774,33 @@ fn test() {
     );
 }
 
+#[test]
+fn infer_local_fn() {
+    assert_snapshot!(
+        infer(r#"
+fn test() {
+    fn local(x: u32) -> u32 {
+        x
+    }
+    let f = local;
+    let r = local(1);
+}
+"#),
+        @r###"
+    [11; 101) '{     ...(1); }': ()
+    [67; 68) 'f': fn local(u32) -> u32
+    [71; 76) 'local': fn local(u32) -> u32
+    [86; 87) 'r': u32
+    [90; 95) 'local': fn local(u32) -> u32
+    [90; 98) 'local(1)': u32
+    [96; 97) '1': u32
+    [26; 27) 'x': u32
+    [41; 58) '{     ...     }': u32
+    [51; 52) 'x': u32
+    "###
+    );
+}
+
 #[test]
 fn shadowing_primitive() {
     let t = type_at(
@@ -1596,6 +1819,105 @@ fn main() {
     assert_eq!(t, "Foo");
 }
 
+#[test]
+fn shadowing_primitive_by_type_alias() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct MyType;
+struct Foo;
+
+impl MyType { fn foo(&self) -> Foo { Foo } }
+
+type i32 = MyType;
+
+fn main() {
+    let x: i32 = MyType;
+    x.foo()<|>;
+}"#,
+    );
+    assert_eq!(t, "Foo");
+}
+
+#[test]
+fn shadowing_primitive_by_imported_type_alias() {
+    let t = type_at(
+        r#"
+//- /main.rs
+mod a;
+struct MyType;
+struct Foo;
+
+impl MyType { fn foo(&self) -> Foo { Foo } }
+
+type u64 = MyType;
+
+//- /a.rs
+use crate::u64;
+
+fn test(x: u64) {
+    x.foo()<|>;
+}"#,
+    );
+    assert_eq!(t, "Foo");
+}
+
+#[test]
+fn shadowing_primitive_by_use_rename() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct MyType;
+struct Foo;
+
+impl MyType { fn foo(&self) -> Foo { Foo } }
+
+use crate::MyType as bool;
+
+fn test(x: bool) {
+    x.foo()<|>;
+}"#,
+    );
+    assert_eq!(t, "Foo");
+}
+
+#[test]
+fn shadowing_primitive_by_imported_use_rename() {
+    let t = type_at(
+        r#"
+//- /main.rs
+mod a;
+struct MyType;
+struct Foo;
+
+impl MyType { fn foo(&self) -> Foo { Foo } }
+
+pub use crate::MyType as f32;
+
+//- /a.rs
+use crate::f32;
+
+fn test(x: f32) {
+    x.foo()<|>;
+}"#,
+    );
+    assert_eq!(t, "Foo");
+}
+
+#[test]
+fn type_alias_shadowing_primitive_by_itself() {
+    let t = type_at(
+        r#"
+//- /main.rs
+type u8 = u8;
+
+fn test(x: u8) {
+    x<|>;
+}"#,
+    );
+    assert_eq!(t, "{unknown}");
+}
+
 #[test]
 fn not_shadowing_primitive_by_module() {
     let t = type_at(
@@ -1686,3 +2008,35 @@ fn foo() -> u32 {
     "###
     );
 }
+
+#[test]
+fn generator_closure_return_unknown() {
+    assert_snapshot!(
+        infer(r#"
+fn test() {
+    let a = 1u32;
+    let g = || {
+        yield a;
+        "done"
+    };
+    let b = a;
+    let c = "s";
+}
+"#),
+        @r###"
+    [11; 120) '{     ..."s"; }': ()
+    [21; 22) 'a': u32
+    [25; 29) '1u32': u32
+    [39; 40) 'g': || -> {unknown}
+    [43; 85) '|| {  ...     }': || -> {unknown}
+    [46; 85) '{     ...     }': &str
+    [56; 63) 'yield a': {unknown}
+    [62; 63) 'a': u32
+    [73; 79) '"done"': &str
+    [95; 96) 'b': u32
+    [99; 100) 'a': u32
+    [110; 111) 'c': &str
+    [114; 117) '"s"': &str
+    "###
+    );
+}
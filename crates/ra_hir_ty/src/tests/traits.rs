@@ -1,9 +1,13 @@
+use std::sync::Arc;
+
 use insta::assert_snapshot;
 
 use ra_db::fixture::WithFixture;
 
-use super::{infer, infer_with_mismatches, type_at, type_at_pos};
-use crate::test_db::TestDB;
+use super::{infer, infer_with_mismatches, ty_at_pos, type_at, type_at_pos};
+use crate::{
+    autoderef, display::HirDisplay, test_db::TestDB, Canonical, InEnvironment, TraitEnvironment,
+};
 
 #[test]
 fn infer_await() {
@@ -176,6 +180,107 @@ mod collections {
     assert_eq!("&str", type_at_pos(&db, pos));
 }
 
+/// A `std` with `Vec`, its slice iterator and the blanket `IntoIterator` impl
+/// for iterators, for the `for` loop tests below.
+const FOR_LOOP_STD: &str = r#"
+//- /std.rs crate:std
+
+#[prelude_import] use iter::*;
+mod iter {
+    trait IntoIterator {
+        type Item;
+        type IntoIter: Iterator<Item = Self::Item>;
+    }
+    trait Iterator {
+        type Item;
+    }
+    impl<I: Iterator> IntoIterator for I {
+        type Item = I::Item;
+        type IntoIter = I;
+    }
+}
+
+mod slice {
+    pub struct Iter<'a, T> {}
+    impl<'a, T> crate::iter::Iterator for Iter<'a, T> {
+        type Item = &'a T;
+    }
+}
+
+mod collections {
+    struct Vec<T> {}
+    impl<T> Vec<T> {
+        fn new() -> Self { Vec {} }
+        fn push(&mut self, t: T) { }
+        fn iter(&self) -> crate::slice::Iter<'_, T> { loop {} }
+    }
+
+    impl<'a, T> crate::iter::IntoIterator for &'a Vec<T> {
+        type Item = &'a T;
+        type IntoIter = crate::slice::Iter<'a, T>;
+    }
+}
+"#;
+
+fn type_at_in_for_loop(main: &str) -> String {
+    type_at(&format!("//- /main.rs crate:main deps:std\n{}\n{}", main, FOR_LOOP_STD))
+}
+
+#[test]
+fn infer_for_loop_over_vec_ref() {
+    let t = type_at_in_for_loop(
+        r#"
+use std::collections::Vec;
+
+fn test() {
+    let v = Vec::new();
+    v.push(1u32);
+    for x in &v {
+        x<|>;
+    }
+}
+"#,
+    );
+    assert_eq!(t, "&u32");
+}
+
+#[test]
+fn infer_for_loop_over_iter_call() {
+    let t = type_at_in_for_loop(
+        r#"
+use std::collections::Vec;
+
+fn test() {
+    let v = Vec::new();
+    v.push(1u32);
+    for x in v.iter() {
+        x<|>;
+    }
+}
+"#,
+    );
+    assert_eq!(t, "&u32");
+}
+
+#[test]
+fn infer_for_loop_over_iterator() {
+    let t = type_at_in_for_loop(
+        r#"
+struct Counter;
+impl Iterator for Counter {
+    type Item = u64;
+}
+
+fn test() {
+    for x in Counter {
+        x<|>;
+    }
+}
+"#,
+    );
+    assert_eq!(t, "u64");
+}
+
 #[test]
 fn infer_ops_neg() {
     let (db, pos) = TestDB::with_position(
@@ -242,6 +347,90 @@ mod ops {
     assert_eq!("Foo", type_at_pos(&db, pos));
 }
 
+#[test]
+fn infer_ops_add() {
+    assert_snapshot!(
+        infer(r#"
+#[lang = "add"]
+pub trait Add<Rhs> {
+    type Output;
+}
+
+#[lang = "add_assign"]
+pub trait AddAssign<Rhs> {}
+
+struct Meters(f64);
+
+impl Add<u32> for Meters {
+    type Output = f64;
+}
+
+impl AddAssign<u32> for Meters {}
+
+fn test(m: Meters, mut n: Meters) {
+    let sum = m + 1u32;
+    let unit = n += 2u32;
+    let builtin = 1 + 2u8;
+}
+"#),
+        @r###"
+    [227; 228) 'm': Meters
+    [238; 243) 'mut n': Meters
+    [253; 333) '{     ...2u8; }': ()
+    [263; 266) 'sum': f64
+    [269; 270) 'm': Meters
+    [269; 277) 'm + 1u32': f64
+    [273; 277) '1u32': u32
+    [287; 291) 'unit': ()
+    [294; 295) 'n': Meters
+    [294; 303) 'n += 2u32': ()
+    [299; 303) '2u32': u32
+    [313; 320) 'builtin': u8
+    [323; 324) '1': u8
+    [323; 330) '1 + 2u8': u8
+    [327; 330) '2u8': u8
+    "###
+    );
+}
+
+#[test]
+fn infer_ops_cmp_without_impl() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+#[lang = "eq"]
+pub trait PartialEq<Rhs> {}
+
+#[lang = "partial_ord"]
+pub trait PartialOrd<Rhs> {}
+
+struct A;
+struct B;
+
+impl PartialEq<B> for B {}
+
+fn test(a1: A, a2: A, b1: B, b2: B) {
+    let lt = a1 < a2;
+    let eq = b1 == b2;
+}
+"#, true),
+        @r###"
+    [156; 158) 'a1': A
+    [163; 165) 'a2': A
+    [170; 172) 'b1': B
+    [177; 179) 'b2': B
+    [184; 232) '{     ... b2; }': ()
+    [194; 196) 'lt': bool
+    [199; 201) 'a1': A
+    [199; 206) 'a1 < a2': bool
+    [204; 206) 'a2': A
+    [216; 218) 'eq': bool
+    [221; 223) 'b1': B
+    [221; 229) 'b1 == b2': bool
+    [227; 229) 'b2': B
+    "###
+    );
+}
+
 #[test]
 fn infer_from_bound_1() {
     assert_snapshot!(
@@ -349,7 +538,6 @@ trait Trait: SuperTrait {
 
 #[test]
 fn infer_project_associated_type() {
-    // y, z, a don't yet work because of https://github.com/rust-lang/chalk/issues/234
     assert_snapshot!(
         infer(r#"
 trait Iterable {
@@ -368,11 +556,11 @@ fn test<T: Iterable>() {
     [108; 261) '{     ...ter; }': ()
     [118; 119) 'x': u32
     [145; 146) '1': u32
-    [156; 157) 'y': {unknown}
+    [156; 157) 'y': Iterable::Item<T>
     [183; 192) 'no_matter': {unknown}
-    [202; 203) 'z': {unknown}
+    [202; 203) 'z': Iterable::Item<T>
     [215; 224) 'no_matter': {unknown}
-    [234; 235) 'a': {unknown}
+    [234; 235) 'a': Iterable::Item<T>
     [249; 258) 'no_matter': {unknown}
     "###
     );
@@ -519,17 +707,30 @@ fn test() -> u64 {
     );
 }
 
+#[test]
+fn trait_impl_for_array_applies_to_unknown_lengths() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Foo { fn foo(&self) -> u8; }
+impl<T> Foo for [T; 2] { fn foo(&self) -> u8 { 0 } }
+fn test(n: usize) { ([1, 2].foo(), [0; n].foo())<|>; }
+"#,
+    );
+    assert_eq!(t, "(u8, u8)");
+}
+
 #[test]
 fn indexing_arrays() {
     assert_snapshot!(
         infer("fn main() { &mut [9][2]; }"),
         @r###"
     [10; 26) '{ &mut...[2]; }': ()
-    [12; 23) '&mut [9][2]': &mut {unknown}
-    [17; 20) '[9]': [i32; _]
-    [17; 23) '[9][2]': {unknown}
+    [12; 23) '&mut [9][2]': &mut i32
+    [17; 20) '[9]': [i32; 1]
+    [17; 23) '[9][2]': i32
     [18; 19) '9': i32
-    [21; 22) '2': i32
+    [21; 22) '2': usize
     "###
     )
 }
@@ -651,6 +852,73 @@ fn test(s: S) {
     assert_eq!(t, "{unknown}");
 }
 
+#[test]
+fn deref_trait_nested_generic_wrappers_around_unknown() {
+    let t = type_at(
+        r#"
+//- /main.rs
+#[lang = "deref"]
+trait Deref {
+    type Target;
+    fn deref(&self) -> &Self::Target;
+}
+
+struct Outer<T>(T);
+impl<T> Deref for Outer<T> {
+    type Target = T;
+}
+
+struct Wrapper<T>(T);
+impl<T> Deref for Wrapper<T> {
+    type Target = T;
+}
+impl<T> Wrapper<T> {
+    fn wrapped(&self) -> u32 { 0 }
+}
+
+fn test(x: Outer<Outer<Wrapper<Outer<Unknown>>>>) {
+    (x.wrapped(), x.missing())<|>;
+}
+"#,
+    );
+    assert_eq!(t, "(u32, {unknown})");
+}
+
+#[test]
+fn autoderef_stops_at_repeated_type() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[lang = "deref"]
+trait Deref {
+    type Target;
+    fn deref(&self) -> &Self::Target;
+}
+
+struct S;
+impl Deref for S {
+    type Target = S;
+}
+
+struct Wrapper<T>(T);
+impl<T> Deref for Wrapper<T> {
+    type Target = T;
+}
+
+fn test(x: Wrapper<Wrapper<S>>) {
+    x<|>;
+}
+"#,
+    );
+    let krate = db.module_for_file(pos.file_id).krate;
+    let ty = Canonical { value: ty_at_pos(&db, pos), num_vars: 0 };
+    let env = Arc::new(TraitEnvironment { predicates: Vec::new() });
+    let steps = autoderef(&db, Some(krate), InEnvironment::new(env, ty))
+        .map(|it| it.value.display(&db).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(steps, vec!["Wrapper<Wrapper<S>>", "Wrapper<S>", "S"]);
+}
+
 #[test]
 fn deref_trait_with_question_mark_size() {
     let t = type_at(
@@ -888,11 +1156,52 @@ fn test<T: ApplyL>(t: T) {
 }
 "#,
     );
-    // FIXME here Chalk doesn't normalize the type to a placeholder. I think we
-    // need to add a rule like Normalize(<T as ApplyL>::Out -> ApplyL::Out<T>)
-    // to the trait env ourselves here; probably Chalk can't do this by itself.
-    // assert_eq!(t, "ApplyL::Out<[missing name]>");
-    assert_eq!(t, "{unknown}");
+    // Chalk doesn't normalize the type to a placeholder by itself, so we add
+    // Normalize(<T as ApplyL>::Out -> ApplyL::Out<T>) to the trait env.
+    assert_eq!(t, "ApplyL::Out<T>");
+}
+
+#[test]
+fn associated_type_default_in_trait_default_method() {
+    test_utils::covers!(trait_assoc_type_default);
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Make {
+    fn make() -> Self;
+}
+struct Foo;
+impl Make for Foo {
+    fn make() -> Self { Foo }
+}
+trait Trait {
+    type Assoc: Make = Foo;
+    fn get(&self) -> Self::Assoc {
+        let x = Self::Assoc::make();
+        x<|>
+    }
+}
+"#,
+    );
+    assert_eq!(t, "Foo");
+}
+
+#[test]
+fn associated_type_placeholder_in_trait_default_method() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait {
+    type Assoc;
+    fn assoc(&self) -> Self::Assoc;
+    fn get(&self) {
+        let x = self.assoc();
+        x<|>;
+    }
+}
+"#,
+    );
+    assert_eq!(t, "Trait::Assoc<Self>");
 }
 
 #[test]
@@ -1137,6 +1446,273 @@ fn test(x: impl Trait<u64>, y: &impl Trait<u64>) {
     );
 }
 
+#[test]
+fn argument_impl_trait_supertrait_methods() {
+    assert_snapshot!(
+        infer(r#"
+trait Base {
+    fn base(&self) -> u32;
+}
+trait Trait: Base {
+    fn foo(&self) -> u64;
+}
+fn test(x: impl Trait, y: &impl Trait) {
+    x.base();
+    y.base();
+}
+"#),
+        @r###"
+    [27; 31) 'self': &Self
+    [75; 79) 'self': &Self
+    [99; 100) 'x': impl Trait
+    [114; 115) 'y': &impl Trait
+    [130; 161) '{     ...e(); }': ()
+    [136; 137) 'x': impl Trait
+    [136; 144) 'x.base()': u32
+    [150; 151) 'y': &impl Trait
+    [150; 158) 'y.base()': u32
+    "###
+    );
+}
+
+#[test]
+fn argument_impl_trait_blanket_impl_methods() {
+    assert_snapshot!(
+        infer(r#"
+trait Trait<T> {
+    fn foo(&self) -> T;
+}
+trait Ext {
+    fn ext(&self) -> u8;
+}
+impl<T: Trait<u64>> Ext for T {}
+
+fn test(x: impl Trait<u64>, y: &impl Trait<u64>) {
+    x.ext();
+    y.ext();
+}
+"#),
+        @r###"
+    [30; 34) 'self': &Self
+    [68; 72) 'self': &Self
+    [125; 126) 'x': impl Trait<u64>
+    [145; 146) 'y': &impl Trait<u64>
+    [166; 195) '{     ...t(); }': ()
+    [172; 173) 'x': impl Trait<u64>
+    [172; 179) 'x.ext()': u8
+    [185; 186) 'y': &impl Trait<u64>
+    [185; 192) 'y.ext()': u8
+    "###
+    );
+}
+
+#[test]
+fn type_alias_impl_trait() {
+    assert_snapshot!(
+        infer(r#"
+trait Iterator {
+    type Item;
+    fn next(&mut self) -> Option<Self::Item>;
+}
+enum Option<T> { Some(T), None }
+type Foo = impl Iterator<Item = u32>;
+
+fn test(x: Foo) {
+    x;
+    let y = x.next();
+}
+"#),
+        @r###"
+    [50; 54) 'self': &mut Self
+    [161; 162) 'x': impl Iterator<Item = u32>
+    [169; 201) '{     ...t(); }': ()
+    [175; 176) 'x': impl Iterator<Item = u32>
+    [186; 187) 'y': Option<u32>
+    [190; 191) 'x': impl Iterator<Item = u32>
+    [190; 198) 'x.next()': Option<u32>
+    "###
+    );
+}
+
+#[test]
+fn type_alias_impl_trait_defining_fn() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+trait Iterator {
+    type Item;
+    fn next(&mut self) -> Option<Self::Item>;
+}
+enum Option<T> { Some(T), None }
+type Foo = impl Iterator<Item = u32>;
+
+struct S;
+impl Iterator for S {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { Option::None }
+}
+
+fn foo() -> Foo {
+    S
+}
+
+fn test() {
+    let x = foo();
+    x.next();
+}
+"#, true),
+        @r###"
+    [50; 54) 'self': &mut Self
+    [223; 227) 'self': &mut S
+    [244; 260) '{ Opti...None }': Option<u32>
+    [246; 258) 'Option::None': Option<u32>
+    [280; 289) '{     S }': S
+    [286; 287) 'S': S
+    [301; 337) '{     ...t(); }': ()
+    [311; 312) 'x': impl Iterator<Item = u32>
+    [315; 318) 'foo': fn foo() -> impl Iterator<Item = u32>
+    [315; 320) 'foo()': impl Iterator<Item = u32>
+    [326; 327) 'x': impl Iterator<Item = u32>
+    [326; 334) 'x.next()': Option<u32>
+    "###
+    );
+}
+
+#[test]
+fn trait_method_impl_trait_return_through_bound() {
+    assert_snapshot!(
+        infer(r#"
+trait Iterator {
+    type Item;
+    fn next(&mut self) -> Option<Self::Item>;
+}
+enum Option<T> { Some(T), None }
+trait Collection {
+    fn items(&self) -> impl Iterator<Item = Self>;
+}
+
+fn test<T: Collection>(t: T) {
+    let it = t.items();
+    it.next();
+}
+"#),
+        @r###"
+    [50; 54) 'self': &mut Self
+    [147; 151) 'self': &Self
+    [210; 211) 't': T
+    [216; 258) '{     ...t(); }': ()
+    [226; 228) 'it': impl Iterator<Item = T>
+    [231; 232) 't': T
+    [231; 240) 't.items()': impl Iterator<Item = T>
+    [246; 248) 'it': impl Iterator<Item = T>
+    [246; 255) 'it.next()': Option<T>
+    "###
+    );
+}
+
+#[test]
+fn trait_method_impl_trait_return_through_impl() {
+    assert_snapshot!(
+        infer_with_mismatches(r#"
+trait Iterator {
+    type Item;
+    fn next(&mut self) -> Option<Self::Item>;
+}
+enum Option<T> { Some(T), None }
+trait Collection {
+    fn items(&self) -> impl Iterator<Item = u32>;
+}
+
+struct Items;
+impl Iterator for Items {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { Option::None }
+}
+
+struct S;
+impl Collection for S {
+    fn items(&self) -> Items { Items }
+}
+
+fn test(s: S) {
+    let it = s.items();
+    it.next();
+}
+"#, true),
+        @r###"
+    [50; 54) 'self': &mut Self
+    [147; 151) 'self': &Self
+    [264; 268) 'self': &mut Items
+    [285; 301) '{ Opti...None }': Option<u32>
+    [287; 299) 'Option::None': Option<u32>
+    [353; 357) 'self': &S
+    [368; 377) '{ Items }': Items
+    [370; 375) 'Items': Items
+    [389; 390) 's': S
+    [395; 437) '{     ...t(); }': ()
+    [405; 407) 'it': Items
+    [410; 411) 's': S
+    [410; 419) 's.items()': Items
+    [425; 427) 'it': Items
+    [425; 434) 'it.next()': Option<u32>
+    "###
+    );
+}
+
+#[test]
+fn existential_type() {
+    assert_snapshot!(
+        infer(r#"
+trait Iterator {
+    type Item;
+    fn next(&mut self) -> Option<Self::Item>;
+}
+enum Option<T> { Some(T), None }
+existential type Foo: Iterator<Item = u32>;
+
+fn test(x: Foo) {
+    x.next();
+}
+"#),
+        @r###"
+    [50; 54) 'self': &mut Self
+    [167; 168) 'x': impl Iterator<Item = u32>
+    [175; 192) '{     ...t(); }': ()
+    [181; 182) 'x': impl Iterator<Item = u32>
+    [181; 189) 'x.next()': Option<u32>
+    "###
+    );
+}
+
+#[test]
+fn type_alias_impl_trait_is_not_a_trait_object() {
+    assert_snapshot!(
+        infer(r#"
+trait Trait {}
+trait Foo {
+    fn foo(&self) -> u8;
+}
+impl Foo for dyn Trait {
+    fn foo(&self) -> u8 { 0 }
+}
+type Alias = impl Trait;
+
+fn test(x: Alias) {
+    x.foo();
+}
+"#),
+        @r###"
+    [40; 44) 'self': &Self
+    [92; 96) 'self': &dyn Trait
+    [104; 109) '{ 0 }': u8
+    [106; 107) '0': u8
+    [146; 147) 'x': impl Trait
+    [156; 172) '{     ...o(); }': ()
+    [162; 163) 'x': impl Trait
+    [162; 169) 'x.foo()': {unknown}
+    "###
+    );
+}
+
 #[test]
 fn dyn_trait() {
     assert_snapshot!(
@@ -1246,6 +1822,24 @@ fn test(a: impl Trait + 'lifetime, b: impl 'lifetime, c: impl (Trait), d: impl (
     );
 }
 
+#[test]
+fn multiple_bounds_in_any_position() {
+    assert_snapshot!(
+        infer(r#"
+trait Trait<'a> {}
+trait Send {}
+struct S<T: ?Sized>(T);
+fn test(a: &(dyn for<'a> Trait<'a> + Send), b: &S<for<'a> Trait<'a> + Send>, c: impl for<'a> Trait<'a> + Send) {}
+"#),
+        @r###"
+    [66; 67) 'a': &dyn Trait + Send
+    [102; 103) 'b': &S<dyn Trait + Send>
+    [135; 136) 'c': impl Trait + Send
+    [169; 171) '{}': ()
+    "###
+    );
+}
+
 #[test]
 #[ignore]
 fn error_bound_chalk() {
@@ -1475,6 +2069,64 @@ fn test(x: &impl Trait1) {
     );
 }
 
+#[test]
+fn super_trait_dyn_trait_method_resolution() {
+    assert_snapshot!(
+        infer(r#"
+trait Super {
+    fn foo(&self) -> u32 {}
+}
+trait Sub: Super {}
+
+fn test(x: &dyn Sub) {
+    x.foo();
+}
+"#),
+        @r###"
+    [27; 31) 'self': &Self
+    [40; 42) '{}': ()
+    [74; 75) 'x': &dyn Sub
+    [87; 103) '{     ...o(); }': ()
+    [93; 94) 'x': &dyn Sub
+    [93; 100) 'x.foo()': u32
+    "###
+    );
+}
+
+#[test]
+fn super_trait_boxed_dyn_trait_method_resolution() {
+    assert_snapshot!(
+        infer(r#"
+#[lang = "deref"]
+trait Deref {
+    type Target;
+    fn deref(&self) -> &Self::Target;
+}
+struct Box<T: ?Sized>;
+impl<T: ?Sized> Deref for Box<T> {
+    type Target = T;
+}
+trait Super {
+    fn foo(&self) -> u32 {}
+}
+trait Sub: Super {}
+
+fn test(x: Box<dyn Sub>) {
+    x.foo();
+}
+"#),
+        @r###"
+    [64; 68) 'self': &Self
+    [197; 201) 'self': &Self
+    [210; 212) '{}': ()
+    [244; 245) 'x': Box<dyn Sub>
+    [261; 277) '{     ...o(); }': ()
+    [267; 268) 'x': Box<dyn Sub>
+    [267; 274) 'x.foo()': u32
+    "###
+    );
+}
+
 #[test]
 fn super_trait_cycle() {
     // This just needs to not crash
@@ -1952,3 +2604,35 @@ fn main() {
     "###
     );
 }
+
+#[test]
+fn trait_impl_for_reference_is_found_by_chalk() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait { type Out; }
+struct S;
+impl Trait for &S { type Out = u8; }
+impl Trait for &mut S { type Out = i8; }
+impl Trait for *const S { type Out = u16; }
+fn get<T: Trait>(t: T) -> T::Out { loop {} }
+fn test(p: *const S) { (get(&S), get(&mut S), get(p))<|>; }
+"#,
+    );
+    assert_eq!(t, "(u8, i8, u16)");
+}
+
+#[test]
+fn generic_trait_impl_for_reference_is_found_by_chalk() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait { type Out; }
+struct S;
+impl<T> Trait for &T { type Out = T; }
+fn get<T: Trait>(t: T) -> T::Out { loop {} }
+fn test() { get(&S)<|>; }
+"#,
+    );
+    assert_eq!(t, "S");
+}
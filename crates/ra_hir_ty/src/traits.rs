@@ -1,18 +1,25 @@
 //! Trait solving using Chalk.
 use std::{
+    hash::{Hash, Hasher},
     panic,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, TryLockError},
+    thread,
+    time::Duration,
 };
 
 use chalk_ir::cast::Cast;
-use hir_def::{expr::ExprId, DefWithBodyId, ImplId, TraitId, TypeAliasId};
+use hir_def::{
+    expr::ExprId, AssocItemId, DefWithBodyId, ImplId, TraitId, TypeAliasId, TypeParamId,
+};
 use ra_db::{impl_intern_key, salsa, Canceled, CrateId};
 use ra_prof::profile;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
 
-use crate::db::HirDatabase;
+use crate::{db::HirDatabase, utils::all_super_trait_refs};
 
-use super::{Canonical, GenericPredicate, HirDisplay, ProjectionTy, TraitRef, Ty, TypeWalk};
+use super::{
+    Binders, Canonical, GenericPredicate, HirDisplay, ProjectionTy, Substs, TraitRef, Ty, TypeWalk,
+};
 
 use self::chalk::{from_chalk, Interner, ToChalk};
 
@@ -42,12 +49,19 @@ impl TraitSolver {
     ) -> Option<chalk_solve::Solution<Interner>> {
         let context = ChalkContext { db, krate: self.krate };
         log::debug!("solve goal: {:?}", goal);
-        let mut solver = match self.inner.lock() {
-            Ok(it) => it,
-            // Our cancellation works via unwinding, but, as chalk is not
-            // panic-safe, we need to make sure to propagate the cancellation.
-            // Ideally, we should also make chalk panic-safe.
-            Err(_) => ra_db::Canceled::throw(),
+        // Another request may be solving a goal for this crate. Rather than
+        // block on the lock, we poll it so that a canceled request gives up
+        // waiting right away.
+        let mut solver = loop {
+            db.check_canceled();
+            match self.inner.try_lock() {
+                Ok(it) => break it,
+                Err(TryLockError::WouldBlock) => thread::sleep(SOLVER_LOCK_POLL_INTERVAL),
+                // Our cancellation works via unwinding, but, as chalk is not
+                // panic-safe, we need to make sure to propagate the cancellation.
+                // Ideally, we should also make chalk panic-safe.
+                Err(TryLockError::Poisoned(_)) => ra_db::Canceled::throw(),
+            }
         };
 
         let fuel = std::cell::Cell::new(CHALK_SOLVER_FUEL);
@@ -93,6 +107,9 @@ impl TraitSolver {
 const CHALK_SOLVER_MAX_SIZE: usize = 10;
 /// This controls how much 'time' we give the Chalk solver before giving up.
 const CHALK_SOLVER_FUEL: i32 = 100;
+/// How often a request waiting for the solver of another one checks whether it
+/// was canceled.
+const SOLVER_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(1);
 
 #[derive(Debug, Copy, Clone)]
 struct ChalkContext<'a, DB> {
@@ -136,6 +153,148 @@ pub(crate) fn impls_for_trait_query(
     impls.into_iter().collect()
 }
 
+pub(crate) fn trait_impls_fingerprint_query(
+    db: &impl HirDatabase,
+    krate: CrateId,
+    trait_: TraitId,
+) -> Option<u64> {
+    let _p = profile("trait_impls_fingerprint_query");
+    let mut impl_hashes = Vec::new();
+    let mut visited = FxHashSet::default();
+    let mut queue = vec![trait_];
+    while let Some(trait_) = queue.pop() {
+        if !visited.insert(trait_) {
+            continue;
+        }
+        if db.trait_data(trait_).auto || builtin::has_builtin_impls(db, krate, trait_) {
+            return None;
+        }
+        let mut hasher = FxHasher::default();
+        trait_.hash(&mut hasher);
+        hash_predicates(db, &db.generic_predicates(trait_.into()), &mut hasher, &mut queue);
+        impl_hashes.push(hasher.finish());
+
+        for &impl_id in db.impls_for_trait(krate, trait_).iter() {
+            let mut hasher = FxHasher::default();
+            impl_id.hash(&mut hasher);
+            db.impl_trait(impl_id).map(|it| it.value).hash(&mut hasher);
+            db.impl_self_ty(impl_id).value.hash(&mut hasher);
+            hash_predicates(db, &db.generic_predicates(impl_id.into()), &mut hasher, &mut queue);
+            for &item in db.impl_data(impl_id).items.iter() {
+                if let AssocItemId::TypeAliasId(type_alias) = item {
+                    db.ty(type_alias.into()).value.hash(&mut hasher);
+                }
+            }
+            impl_hashes.push(hasher.finish());
+        }
+    }
+    // `impls_for_trait` doesn't have a stable order.
+    impl_hashes.sort();
+    let mut hasher = FxHasher::default();
+    impl_hashes.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+fn hash_predicates(
+    db: &impl HirDatabase,
+    predicates: &[Binders<GenericPredicate>],
+    hasher: &mut FxHasher,
+    traits: &mut Vec<TraitId>,
+) {
+    for pred in predicates {
+        pred.num_binders.hash(hasher);
+        pred.value.hash(hasher);
+        traits.extend(predicate_trait(db, &pred.value));
+    }
+}
+
+fn predicate_trait(db: &impl HirDatabase, pred: &GenericPredicate) -> Option<TraitId> {
+    match pred {
+        GenericPredicate::Implemented(tr) => Some(tr.trait_),
+        GenericPredicate::Projection(pred) => Some(pred.projection_ty.trait_(db)),
+        GenericPredicate::Error => None,
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NegativeTraitSolveCache {
+    inner: Arc<Mutex<FxHashMap<Canonical<InEnvironment<Obligation>>, u64>>>,
+}
+
+/// We need eq for salsa
+impl PartialEq for NegativeTraitSolveCache {
+    fn eq(&self, other: &NegativeTraitSolveCache) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for NegativeTraitSolveCache {}
+
+impl NegativeTraitSolveCache {
+    fn contains(&self, goal: &Canonical<InEnvironment<Obligation>>, fingerprint: u64) -> bool {
+        self.inner.lock().unwrap().get(goal) == Some(&fingerprint)
+    }
+
+    fn insert(&self, goal: Canonical<InEnvironment<Obligation>>, fingerprint: u64) {
+        let mut goals = self.inner.lock().unwrap();
+        // Entries are never invalidated, only overwritten, so we occasionally
+        // start over to not grow without bound.
+        if goals.len() >= NEGATIVE_TRAIT_SOLVE_CACHE_CAPACITY {
+            goals.clear();
+        }
+        goals.insert(goal, fingerprint);
+    }
+}
+
+const NEGATIVE_TRAIT_SOLVE_CACHE_CAPACITY: usize = 10_000;
+
+pub(crate) fn negative_trait_solve_cache_query(
+    _db: &impl HirDatabase,
+    _krate: CrateId,
+) -> NegativeTraitSolveCache {
+    // krate parameter is just so we cache a unique set of goals per crate
+    NegativeTraitSolveCache::default()
+}
+
+/// The fingerprint under which an unsolvable `goal` is remembered, or `None`
+/// if its result can depend on more than the impls of the traits it mentions.
+fn negative_cache_fingerprint(
+    db: &impl HirDatabase,
+    krate: CrateId,
+    goal: &Canonical<InEnvironment<Obligation>>,
+) -> Option<u64> {
+    let trait_ref = match &goal.value.value {
+        Obligation::Trait(it) => it,
+        // Normalizing depends on associated type values all over the place.
+        Obligation::Projection(_) => return None,
+    };
+    let mut traits = vec![trait_ref.trait_];
+    let mut has_projections = false;
+    let mut collect_traits = |ty: &Ty| match ty {
+        Ty::Projection(_) => has_projections = true,
+        Ty::Dyn(bounds) | Ty::Opaque(bounds) => {
+            traits.extend(bounds.iter().filter_map(|pred| predicate_trait(db, pred)))
+        }
+        _ => (),
+    };
+    trait_ref.walk(&mut collect_traits);
+    for pred in goal.value.environment.predicates.iter() {
+        pred.walk(&mut collect_traits);
+    }
+    if has_projections {
+        return None;
+    }
+    traits.extend(
+        goal.value.environment.predicates.iter().filter_map(|pred| predicate_trait(db, pred)),
+    );
+
+    let mut hasher = FxHasher::default();
+    for trait_ in traits {
+        db.trait_impls_fingerprint(krate, trait_)?.hash(&mut hasher);
+    }
+    Some(hasher.finish())
+}
+
 /// A set of clauses that we assume to be true. E.g. if we are inside this function:
 /// ```rust
 /// fn foo<T: Default>(t: T) {}
@@ -231,15 +390,94 @@ pub(crate) fn trait_solve_query(
         }
     }
 
+    let goal = add_dyn_super_trait_bounds(db, goal);
+    let goal = add_opaque_bounds(goal);
+
+    // The solver is thrown away on every change, so remember the goals it
+    // couldn't solve for as long as the relevant impls stay the same. Goals
+    // which fail are the expensive ones, e.g. during completion.
+    let negative_cache = db.negative_trait_solve_cache(krate);
+    let fingerprint = negative_cache_fingerprint(db, krate, &goal);
+    if let Some(fingerprint) = fingerprint {
+        if negative_cache.contains(&goal, fingerprint) {
+            ra_prof::count("negative trait solve cache hit");
+            return None;
+        }
+        ra_prof::count("negative trait solve cache miss");
+    }
+    let cache_key = fingerprint.map(|it| (goal.clone(), it));
+
     let canonical = goal.to_chalk(db).cast();
 
     // We currently don't deal with universes (I think / hope they're not yet
     // relevant for our use cases?)
     let u_canonical = chalk_ir::UCanonical { canonical, universes: 1 };
     let solution = db.trait_solver(krate).solve(db, &u_canonical);
+    if let (None, Some((goal, fingerprint))) = (&solution, cache_key) {
+        negative_cache.insert(goal, fingerprint);
+    }
     solution.map(|solution| solution_from_chalk(db, solution))
 }
 
+/// `dyn Trait` implements all the super traits of `Trait`, but Chalk doesn't
+/// know that yet; so if the self type of the goal is a trait object, we add
+/// the implied super trait bounds as assumptions to the environment.
+fn add_dyn_super_trait_bounds(
+    db: &impl HirDatabase,
+    mut goal: Canonical<InEnvironment<Obligation>>,
+) -> Canonical<InEnvironment<Obligation>> {
+    let self_ty = match &goal.value.value {
+        Obligation::Trait(tr) => tr.self_ty(),
+        Obligation::Projection(pred) => &pred.projection_ty.parameters[0],
+    };
+    let trait_ref = match self_ty.dyn_trait_ref() {
+        // the self type of the bounds of a `dyn` type is bound in the type itself
+        Some(tr) => tr.clone().subst_bound_vars(&Substs::single(self_ty.clone())),
+        None => return goal,
+    };
+    let super_trait_refs = all_super_trait_refs(db, trait_ref);
+    if super_trait_refs.is_empty() {
+        return goal;
+    }
+    let mut predicates = goal.value.environment.predicates.clone();
+    predicates.extend(super_trait_refs.into_iter().map(GenericPredicate::Implemented));
+    goal.value.environment = Arc::new(TraitEnvironment { predicates });
+    goal
+}
+
+/// Opaque types are passed to Chalk as placeholders, like type parameters
+/// inside their item; so just like the bounds of those come from the
+/// environment, we add the bounds of all opaque types in the goal to it.
+fn add_opaque_bounds(
+    mut goal: Canonical<InEnvironment<Obligation>>,
+) -> Canonical<InEnvironment<Obligation>> {
+    let mut opaque_bounds = Vec::new();
+    let mut collect_bounds = |ty: &Ty| {
+        if let Ty::Opaque(bounds) = ty {
+            // the self type of the bounds of an `impl` type is bound in the
+            // type itself
+            let substs = Substs::single(ty.clone());
+            opaque_bounds.extend(
+                bounds
+                    .iter()
+                    .filter(|pred| !pred.is_error())
+                    .map(|pred| pred.clone().subst_bound_vars(&substs)),
+            );
+        }
+    };
+    match &goal.value.value {
+        Obligation::Trait(tr) => tr.walk(&mut collect_bounds),
+        Obligation::Projection(pred) => pred.walk(&mut collect_bounds),
+    }
+    if opaque_bounds.is_empty() {
+        return goal;
+    }
+    let mut predicates = goal.value.environment.predicates.clone();
+    predicates.extend(opaque_bounds);
+    goal.value.environment = Arc::new(TraitEnvironment { predicates });
+    goal
+}
+
 fn solution_from_chalk(
     db: &impl HirDatabase,
     solution: chalk_solve::Solution<Interner>,
@@ -350,7 +588,7 @@ pub enum Impl {
     /// Closure types implement the Fn traits synthetically.
     ClosureFnTraitImpl(ClosureFnTraitImplData),
     /// [T; n]: Unsize<[T]>
-    UnsizeArray,
+    UnsizeArray(Option<u64>),
     /// T: Unsize<dyn Trait> where T: Trait
     UnsizeToTraitObject(TraitId),
     /// dyn Trait: Unsize<dyn SuperTrait> if Trait: SuperTrait
@@ -361,6 +599,21 @@ pub enum Impl {
 pub struct GlobalImplId(salsa::InternId);
 impl_intern_key!(GlobalImplId);
 
+/// What a Chalk placeholder type stands for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Placeholder {
+    /// A type parameter, as seen from inside its item.
+    TypeParam(TypeParamId),
+    /// An opaque type. All we know about it are its bounds, which are added
+    /// to the environment of any goal that mentions it.
+    Opaque(Arc<[GenericPredicate]>),
+}
+/// This exists just for Chalk, because placeholders are identified by a
+/// single index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaceholderId(salsa::InternId);
+impl_intern_key!(PlaceholderId);
+
 /// An associated type value. Usually this comes from a `type` declaration
 /// inside an impl block, but for built-in impls we have to synthesize it.
 /// (We only need this because Chalk wants a unique ID for each of these.)
@@ -70,8 +70,8 @@ fn get_builtin_unsize_impls(
         return;
     }
 
-    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Array, .. }) = ty {
-        callback(Impl::UnsizeArray);
+    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Array { len }, .. }) = ty {
+        callback(Impl::UnsizeArray(*len));
         return; // array is unsized, the rest of the impls shouldn't apply
     }
 
@@ -98,7 +98,7 @@ pub(super) fn impl_datum(db: &impl HirDatabase, krate: CrateId, impl_: Impl) ->
     match impl_ {
         Impl::ImplBlock(_) => unreachable!(),
         Impl::ClosureFnTraitImpl(data) => closure_fn_trait_impl_datum(db, krate, data),
-        Impl::UnsizeArray => array_unsize_impl_datum(db, krate),
+        Impl::UnsizeArray(len) => array_unsize_impl_datum(db, krate, len),
         Impl::UnsizeToTraitObject(trait_) => trait_object_unsize_impl_datum(db, krate, trait_),
         Impl::UnsizeToSuperTraitObject(data) => {
             super_trait_object_unsize_impl_datum(db, krate, data)
@@ -233,9 +233,13 @@ fn check_unsize_impl_prerequisites(db: &impl HirDatabase, krate: CrateId) -> boo
     generic_params.len() == 2
 }
 
-fn array_unsize_impl_datum(db: &impl HirDatabase, krate: CrateId) -> BuiltinImplData {
-    // impl<T> Unsize<[T]> for [T; _]
-    // (this can be a single impl because we don't distinguish array sizes currently)
+fn array_unsize_impl_datum(
+    db: &impl HirDatabase,
+    krate: CrateId,
+    len: Option<u64>,
+) -> BuiltinImplData {
+    // impl<T> Unsize<[T]> for [T; len]
+    // (there is one impl per array length)
 
     let trait_ = get_unsize_trait(db, krate) // get unsize trait
         // the existence of the Unsize trait has been checked before
@@ -243,7 +247,7 @@ fn array_unsize_impl_datum(db: &impl HirDatabase, krate: CrateId) -> BuiltinImpl
 
     let var = Ty::Bound(0);
     let substs = Substs::builder(2)
-        .push(Ty::apply_one(TypeCtor::Array, var.clone()))
+        .push(Ty::apply_one(TypeCtor::Array { len }, var.clone()))
         .push(Ty::apply_one(TypeCtor::Slice, var))
         .build();
 
@@ -344,6 +348,15 @@ fn super_trait_object_unsize_impl_datum(
     BuiltinImplData { num_vars, trait_ref, where_clauses: Vec::new(), assoc_ty_values: Vec::new() }
 }
 
+/// Whether Chalk gets impls for `trait_` which don't come from the source, so
+/// the solutions for it can change without any impl changing.
+pub(super) fn has_builtin_impls(db: &impl HirDatabase, krate: CrateId, trait_: TraitId) -> bool {
+    [super::FnTrait::FnOnce, super::FnTrait::FnMut, super::FnTrait::Fn]
+        .iter()
+        .any(|&fn_trait| get_fn_trait(db, krate, fn_trait) == Some(trait_))
+        || get_unsize_trait(db, krate) == Some(trait_)
+}
+
 fn get_fn_trait(
     db: &impl HirDatabase,
     krate: CrateId,
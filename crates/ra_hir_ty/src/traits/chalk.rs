@@ -11,10 +11,12 @@ use ra_db::{
     CrateId,
 };
 
-use super::{builtin, AssocTyValue, Canonical, ChalkContext, Impl, Obligation};
+use super::{
+    builtin, AssocTyValue, Canonical, ChalkContext, Impl, Obligation, Placeholder, PlaceholderId,
+};
 use crate::{
-    db::HirDatabase, display::HirDisplay, utils::generics, ApplicationTy, GenericPredicate,
-    ProjectionTy, Substs, TraitRef, Ty, TypeCtor,
+    db::HirDatabase, display::HirDisplay, method_resolution::TyFingerprint, utils::generics,
+    ApplicationTy, GenericPredicate, ProjectionTy, Substs, TraitRef, Ty, TypeCtor,
 };
 
 #[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
@@ -151,14 +153,8 @@ impl ToChalk for Ty {
                 let substitution = proj_ty.parameters.to_chalk(db);
                 chalk_ir::AliasTy { associated_ty_id, substitution }.cast().intern()
             }
-            Ty::Placeholder(id) => {
-                let interned_id = db.intern_type_param_id(id);
-                PlaceholderIndex {
-                    ui: UniverseIndex::ROOT,
-                    idx: interned_id.as_intern_id().as_usize(),
-                }
-                .to_ty::<Interner>()
-            }
+            Ty::Placeholder(id) => placeholder_to_chalk(db, Placeholder::TypeParam(id)),
+            Ty::Opaque(predicates) => placeholder_to_chalk(db, Placeholder::Opaque(predicates)),
             Ty::Bound(idx) => chalk_ir::TyData::BoundVar(idx as usize).intern(),
             Ty::Infer(_infer_ty) => panic!("uncanonicalized infer ty"),
             Ty::Dyn(predicates) => {
@@ -171,7 +167,7 @@ impl ToChalk for Ty {
                 let bounded_ty = chalk_ir::DynTy { bounds: make_binders(where_clauses, 1) };
                 chalk_ir::TyData::Dyn(bounded_ty).intern()
             }
-            Ty::Opaque(_) | Ty::Unknown => {
+            Ty::Unknown => {
                 let substitution = chalk_ir::Substitution::empty();
                 let name = TypeName::Error;
                 chalk_ir::ApplicationTy { name, substitution }.cast().intern()
@@ -190,10 +186,12 @@ impl ToChalk for Ty {
             },
             chalk_ir::TyData::Placeholder(idx) => {
                 assert_eq!(idx.ui, UniverseIndex::ROOT);
-                let interned_id = crate::db::GlobalTypeParamId::from_intern_id(
-                    crate::salsa::InternId::from(idx.idx),
-                );
-                Ty::Placeholder(db.lookup_intern_type_param_id(interned_id))
+                let interned_id =
+                    PlaceholderId::from_intern_id(crate::salsa::InternId::from(idx.idx));
+                match db.lookup_intern_placeholder(interned_id) {
+                    Placeholder::TypeParam(id) => Ty::Placeholder(id),
+                    Placeholder::Opaque(predicates) => Ty::Opaque(predicates),
+                }
             }
             chalk_ir::TyData::Alias(proj) => {
                 let associated_ty = from_chalk(db, proj.associated_ty_id);
@@ -213,6 +211,12 @@ impl ToChalk for Ty {
     }
 }
 
+fn placeholder_to_chalk(db: &impl HirDatabase, placeholder: Placeholder) -> chalk_ir::Ty<Interner> {
+    let interned_id = db.intern_placeholder(placeholder);
+    PlaceholderIndex { ui: UniverseIndex::ROOT, idx: interned_id.as_intern_id().as_usize() }
+        .to_ty::<Interner>()
+}
+
 impl ToChalk for Substs {
     type Chalk = chalk_ir::Substitution<Interner>;
 
@@ -269,6 +273,13 @@ impl ToChalk for TypeCtor {
                 let type_id = type_alias.to_chalk(db);
                 TypeName::AssociatedType(type_id)
             }
+            // Chalk doesn't know about array lengths, so arrays of all lengths
+            // share one type name; otherwise, impls for arrays wouldn't apply
+            // to arrays of unknown length.
+            TypeCtor::Array { .. } => {
+                let struct_id = db.intern_type_ctor(TypeCtor::Array { len: None }).into();
+                TypeName::Struct(struct_id)
+            }
             _ => {
                 // other TypeCtors get interned and turned into a chalk StructId
                 let struct_id = db.intern_type_ctor(self).into();
@@ -568,6 +579,9 @@ where
         debug!("impls_for_trait {:?}", trait_id);
         let trait_: hir_def::TraitId = from_chalk(self.db, trait_id);
 
+        let ty: Ty = from_chalk(self.db, parameters[0].assert_ty_ref().clone());
+        let self_ty_fp = TyFingerprint::for_impl(&ty);
+
         // Note: Since we're using impls_for_trait, only impls where the trait
         // can be resolved should ever reach Chalk. `impl_datum` relies on that
         // and will panic if the trait can't be resolved.
@@ -576,11 +590,14 @@ where
             .impls_for_trait(self.krate, trait_)
             .iter()
             .copied()
+            .filter(|&impl_id| {
+                let impl_self_ty_fp = TyFingerprint::for_impl(&self.db.impl_self_ty(impl_id).value);
+                TyFingerprint::may_apply(impl_self_ty_fp, self_ty_fp)
+            })
             .map(Impl::ImplBlock)
             .map(|impl_| impl_.to_chalk(self.db))
             .collect();
 
-        let ty: Ty = from_chalk(self.db, parameters[0].assert_ty_ref().clone());
         let arg: Option<Ty> =
             parameters.get(1).map(|p| from_chalk(self.db, p.assert_ty_ref().clone()));
 
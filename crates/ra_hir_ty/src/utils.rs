@@ -14,6 +14,8 @@ use hir_def::{
 };
 use hir_expand::name::{name, Name};
 
+use crate::{db::HirDatabase, Binders, GenericPredicate, TraitRef, Ty};
+
 fn direct_super_traits(db: &impl DefDatabase, trait_: TraitId) -> Vec<TraitId> {
     let resolver = trait_.resolver(db);
     // returning the iterator directly doesn't easily work because of
@@ -43,7 +45,7 @@ fn direct_super_traits(db: &impl DefDatabase, trait_: TraitId) -> Vec<TraitId> {
 
 /// Returns an iterator over the whole super trait hierarchy (including the
 /// trait itself).
-pub(super) fn all_super_traits(db: &impl DefDatabase, trait_: TraitId) -> Vec<TraitId> {
+pub fn all_super_traits(db: &impl DefDatabase, trait_: TraitId) -> Vec<TraitId> {
     // we need to take care a bit here to avoid infinite loops in case of cycles
     // (i.e. if we have `trait A: B; trait B: A;`)
     let mut result = vec![trait_];
@@ -62,6 +64,37 @@ pub(super) fn all_super_traits(db: &impl DefDatabase, trait_: TraitId) -> Vec<Tr
     result
 }
 
+/// Given a trait ref (`Self: Trait`), builds all the implied trait refs for
+/// super traits (not including the trait itself), with the trait's parameters
+/// substituted.
+pub(super) fn all_super_trait_refs(db: &impl HirDatabase, trait_ref: TraitRef) -> Vec<TraitRef> {
+    // same cycle protection as in `all_super_traits`
+    let mut result = vec![trait_ref];
+    let mut i = 0;
+    while i < result.len() {
+        let current = result[i].clone();
+        let super_traits = direct_super_traits(db, current.trait_);
+        for pred in db.generic_predicates(current.trait_.into()).iter() {
+            let tr = match &pred.value {
+                GenericPredicate::Implemented(tr)
+                    if tr.substs[0] == Ty::Bound(0) && super_traits.contains(&tr.trait_) =>
+                {
+                    tr
+                }
+                _ => continue,
+            };
+            if result.iter().any(|it| it.trait_ == tr.trait_) {
+                continue;
+            }
+            let tr = Binders { value: tr, num_binders: pred.num_binders }.cloned();
+            result.push(tr.subst(&current.substs));
+        }
+        i += 1;
+    }
+    result.remove(0);
+    result
+}
+
 /// Finds a path from a trait to one of its super traits. Returns an empty
 /// vector if there is no path.
 pub(super) fn find_super_trait_path(
@@ -123,6 +156,25 @@ pub(crate) fn make_mut_slice<T: Clone>(a: &mut Arc<[T]>) -> &mut [T] {
     Arc::get_mut(a).unwrap()
 }
 
+/// Shortens `text` to `max_len` bytes by replacing its middle with `...`.
+pub(crate) fn ellipsize(mut text: String, max_len: usize) -> String {
+    if text.len() <= max_len {
+        return text;
+    }
+    let ellipsis = "...";
+    let e_len = ellipsis.len();
+    let mut prefix_len = (max_len - e_len) / 2;
+    while !text.is_char_boundary(prefix_len) {
+        prefix_len += 1;
+    }
+    let mut suffix_len = max_len - e_len - prefix_len;
+    while !text.is_char_boundary(text.len() - suffix_len) {
+        suffix_len += 1;
+    }
+    text.replace_range(prefix_len..text.len() - suffix_len, ellipsis);
+    text
+}
+
 pub(crate) fn generics(db: &impl DefDatabase, def: GenericDefId) -> Generics {
     let parent_generics = parent_generic_def(db, def).map(|def| Box::new(generics(db, def)));
     Generics { def, params: db.generic_params(def), parent_generics }
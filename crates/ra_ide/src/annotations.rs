@@ -0,0 +1,221 @@
+//! Computes the code-lens-like annotations shown above items in the editor:
+//! runnables, the number of implementations of traits and ADTs and, on demand,
+//! the number of references to public items.
+//!
+//! Implementations and references are expensive to compute, so the
+//! annotations only carry the position of the item, and the data is filled in
+//! by `resolve_annotation` when the client asks for it.
+
+use hir::Semantics;
+use ra_db::{FilePosition, FileRange};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    algo::find_covering_element,
+    ast::{self, AstNode, NameOwner, VisibilityOwner},
+    match_ast, NodeOrToken, SyntaxNode, TextRange,
+};
+
+use crate::{impls, references, runnables, FileId, NavigationTarget, Runnable};
+
+#[derive(Debug)]
+pub struct Annotation {
+    /// The range of the name of the annotated item.
+    pub range: TextRange,
+    pub kind: AnnotationKind,
+}
+
+#[derive(Debug)]
+pub enum AnnotationKind {
+    Runnable { runnable: Runnable },
+    HasImpls { position: FilePosition, data: Option<Vec<NavigationTarget>> },
+    HasReferences { position: FilePosition, data: Option<Vec<FileRange>> },
+}
+
+pub(crate) fn annotations(
+    db: &RootDatabase,
+    file_id: FileId,
+    with_references: bool,
+) -> Vec<Annotation> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(file_id);
+    let mut res = Vec::new();
+
+    for runnable in runnables::runnables(db, file_id) {
+        let range = match name_of_item_at(file.syntax(), runnable.range) {
+            Some(name) => name.syntax().text_range(),
+            None => runnable.range,
+        };
+        res.push(Annotation { range, kind: AnnotationKind::Runnable { runnable } });
+    }
+
+    for node in file.syntax().descendants() {
+        if let Some(name) = impl_target_name(&node) {
+            let range = name.syntax().text_range();
+            let position = FilePosition { file_id, offset: range.start() };
+            res.push(Annotation { range, kind: AnnotationKind::HasImpls { position, data: None } });
+        }
+        if with_references {
+            if let Some(name) = public_item_name(&node) {
+                let range = name.syntax().text_range();
+                let position = FilePosition { file_id, offset: range.start() };
+                res.push(Annotation {
+                    range,
+                    kind: AnnotationKind::HasReferences { position, data: None },
+                });
+            }
+        }
+    }
+
+    res
+}
+
+/// Computes the implementations or references of an annotation returned by
+/// `annotations`.
+pub(crate) fn resolve_annotation(db: &RootDatabase, mut annotation: Annotation) -> Annotation {
+    match &mut annotation.kind {
+        AnnotationKind::Runnable { .. } => (),
+        AnnotationKind::HasImpls { position, data } => {
+            *data = impls::goto_implementation(db, *position).map(|it| it.info);
+        }
+        AnnotationKind::HasReferences { position, data } => {
+            *data = references::find_all_refs(db, *position, None).map(|it| {
+                it.info.references().iter().map(|reference| reference.file_range).collect()
+            });
+        }
+    }
+    annotation
+}
+
+fn name_of_item_at(file: &SyntaxNode, range: TextRange) -> Option<ast::Name> {
+    let node = match find_covering_element(file, range) {
+        NodeOrToken::Node(it) => it,
+        NodeOrToken::Token(_) => return None,
+    };
+    match_ast! {
+        match node {
+            ast::FnDef(it) => { it.name() },
+            ast::Module(it) => { it.name() },
+            _ => None,
+        }
+    }
+}
+
+fn impl_target_name(node: &SyntaxNode) -> Option<ast::Name> {
+    match_ast! {
+        match node {
+            ast::StructDef(it) => { it.name() },
+            ast::EnumDef(it) => { it.name() },
+            ast::UnionDef(it) => { it.name() },
+            ast::TraitDef(it) => { it.name() },
+            _ => None,
+        }
+    }
+}
+
+fn public_item_name(node: &SyntaxNode) -> Option<ast::Name> {
+    fn name_if_public(it: impl NameOwner + VisibilityOwner) -> Option<ast::Name> {
+        it.visibility()?;
+        it.name()
+    }
+
+    match_ast! {
+        match node {
+            ast::FnDef(it) => { name_if_public(it) },
+            ast::StructDef(it) => { name_if_public(it) },
+            ast::EnumDef(it) => { name_if_public(it) },
+            ast::UnionDef(it) => { name_if_public(it) },
+            ast::TraitDef(it) => { name_if_public(it) },
+            ast::TypeAliasDef(it) => { name_if_public(it) },
+            ast::ConstDef(it) => { name_if_public(it) },
+            ast::StaticDef(it) => { name_if_public(it) },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mock_analysis::analysis_and_position, Annotation, AnnotationKind, FilePosition};
+
+    fn resolved_annotations(fixture: &str, with_references: bool) -> Vec<(String, String)> {
+        let (analysis, FilePosition { file_id, .. }) = analysis_and_position(fixture);
+        let text = analysis.file_text(file_id).unwrap();
+        analysis
+            .annotations(file_id, with_references)
+            .unwrap()
+            .into_iter()
+            .map(|annotation| analysis.resolve_annotation(annotation).unwrap())
+            .map(|Annotation { range, kind }| {
+                let label = match kind {
+                    AnnotationKind::Runnable { .. } => "runnable".to_string(),
+                    AnnotationKind::HasImpls { data, .. } => {
+                        format!("{} implementations", data.unwrap().len())
+                    }
+                    AnnotationKind::HasReferences { data, .. } => {
+                        format!("{} references", data.unwrap().len())
+                    }
+                };
+                (text[range].to_string(), label)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_impls_across_files() {
+        let annotations = resolved_annotations(
+            r#"
+            //- /lib.rs
+            mod a;
+            mod b;
+            pub trait Shape<|> {}
+            pub struct Circle;
+
+            //- /a.rs
+            use crate::{Circle, Shape};
+            impl Shape for Circle {}
+            impl Circle {}
+
+            //- /b.rs
+            use crate::Shape;
+            struct Square;
+            impl Shape for Square {}
+            "#,
+            false,
+        );
+        assert_eq!(
+            annotations,
+            vec![
+                ("Shape".to_string(), "2 implementations".to_string()),
+                ("Circle".to_string(), "2 implementations".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn annotates_runnables_and_references() {
+        let annotations = resolved_annotations(
+            r#"
+            //- /lib.rs
+            <|>pub fn helper() {}
+
+            fn main() {
+                helper();
+            }
+
+            #[test]
+            fn test_helper() {
+                helper();
+            }
+            "#,
+            true,
+        );
+        assert_eq!(
+            annotations,
+            vec![
+                ("main".to_string(), "runnable".to_string()),
+                ("test_helper".to_string(), "runnable".to_string()),
+                ("helper".to_string(), "2 references".to_string()),
+            ]
+        );
+    }
+}
@@ -1,8 +1,9 @@
 //! FIXME: write short doc here
 
-use ra_assists::{resolved_assists, AssistAction, AssistLabel};
+use ra_assists::{resolved_assists, AssistAction, AssistLabel, ResolvedAssist};
 use ra_db::{FilePosition, FileRange};
 use ra_ide_db::RootDatabase;
+use ra_syntax::TextRange;
 
 use crate::{FileId, SourceChange, SourceFileEdit};
 
@@ -16,22 +17,51 @@ pub struct Assist {
     pub source_change: SourceChange,
 }
 
+/// An assist without its edit, which `resolve_assist` computes.
+#[derive(Debug)]
+pub struct UnresolvedAssist {
+    pub id: AssistId,
+    pub label: String,
+    pub group_label: Option<String>,
+    pub target: TextRange,
+}
+
 pub(crate) fn assists(db: &RootDatabase, frange: FileRange) -> Vec<Assist> {
-    resolved_assists(db, frange)
+    resolved_assists(db, frange).into_iter().map(|assist| to_assist(assist, frange)).collect()
+}
+
+pub(crate) fn unresolved_assists(db: &RootDatabase, frange: FileRange) -> Vec<UnresolvedAssist> {
+    ra_assists::unresolved_assists(db, frange)
         .into_iter()
-        .map(|assist| {
-            let file_id = frange.file_id;
-            let assist_label = &assist.label;
-            Assist {
-                id: assist_label.id,
-                label: assist_label.label.clone(),
-                group_label: assist.group_label.map(|it| it.0),
-                source_change: action_to_edit(assist.action, file_id, assist_label),
-            }
+        .map(|assist| UnresolvedAssist {
+            id: assist.label.id,
+            label: assist.label.label,
+            group_label: assist.group_label.map(|it| it.0),
+            target: assist.label.target,
         })
         .collect()
 }
 
+pub(crate) fn resolve_assist(
+    db: &RootDatabase,
+    frange: FileRange,
+    id: &str,
+    label: &str,
+) -> Option<Assist> {
+    let assist = ra_assists::resolve_assist(db, frange, id, label)?;
+    Some(to_assist(assist, frange))
+}
+
+fn to_assist(assist: ResolvedAssist, frange: FileRange) -> Assist {
+    let assist_label = &assist.label;
+    Assist {
+        id: assist_label.id,
+        label: assist_label.label.clone(),
+        group_label: assist.group_label.map(|it| it.0),
+        source_change: action_to_edit(assist.action, frange.file_id, assist_label),
+    }
+}
+
 fn action_to_edit(
     action: AssistAction,
     file_id: FileId,
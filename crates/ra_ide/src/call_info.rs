@@ -1,25 +1,57 @@
 //! FIXME: write short doc here
-use hir::Semantics;
+use hir::{HasSource, Semantics};
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
     ast::{self, ArgListOwner},
-    match_ast, AstNode, SyntaxNode,
+    match_ast, AstNode, SyntaxKind, SyntaxNode, TextUnit, T,
 };
 use test_utils::tested_by;
 
 use crate::{CallInfo, FilePosition, FunctionSignature};
 
-/// Computes parameter information for the given call expression.
+/// Computes parameter information for the given call expression or record
+/// literal.
 pub(crate) fn call_info(db: &RootDatabase, position: FilePosition) -> Option<CallInfo> {
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id);
     let file = file.syntax();
-    let token = file.token_at_offset(position.offset).next()?;
-    let token = sema.descend_into_macros(token);
-
-    // Find the calling expression and it's NameRef
-    let calling_node = FnCallNode::with_node(&token.parent())?;
+    // At a boundary, the token before the cursor is the one inside an
+    // unclosed argument list, like in `foo(<|>`.
+    let original_token = file.token_at_offset(position.offset).left_biased()?;
+    let token = sema.descend_into_macros(original_token.clone());
+    // The token may be in a macro expansion, so the cursor is mapped into its
+    // file at the same distance from the token's start.
+    let offset =
+        token.text_range().start() + (position.offset - original_token.text_range().start());
+
+    // Find the innermost call or record literal around the cursor
+    for node in token.parent().ancestors() {
+        if let Some(field_list) = ast::RecordFieldList::cast(node.clone()) {
+            let range = field_list.syntax().text_range();
+            if range.start() < offset && range.contains_inclusive(offset) {
+                return record_literal_info(&sema, field_list, offset);
+            }
+            continue;
+        }
+        if let Some(calling_node) = FnCallNode::with_node_exact(&node) {
+            if let Some(arg_list) = calling_node.arg_list() {
+                if !arg_list.syntax().text_range().contains_inclusive(offset) {
+                    tested_by!(call_info_bad_offset);
+                    continue;
+                }
+            }
+            return fn_call_info(&sema, calling_node, offset);
+        }
+    }
+    None
+}
 
+fn fn_call_info(
+    sema: &Semantics<RootDatabase>,
+    calling_node: FnCallNode,
+    offset: TextUnit,
+) -> Option<CallInfo> {
+    let db = sema.db;
     let (mut call_info, has_self) = match &calling_node {
         FnCallNode::CallExpr(call) => {
             //FIXME: Type::as_callable is broken
@@ -41,6 +73,11 @@ pub(crate) fn call_info(db: &RootDatabase, position: FilePosition) -> Option<Cal
         }
         FnCallNode::MacroCallExpr(macro_call) => {
             let macro_def = sema.resolve_macro_call(&macro_call)?;
+            // There's no telling which argument is which, so don't guess
+            if is_variadic_macro(db, macro_def) {
+                tested_by!(call_info_variadic_macro);
+                return None;
+            }
             (CallInfo::with_macro(db, macro_def)?, false)
         }
     };
@@ -57,22 +94,13 @@ pub(crate) fn call_info(db: &RootDatabase, position: FilePosition) -> Option<Cal
         }
         _ => {
             if let Some(arg_list) = calling_node.arg_list() {
-                // Number of arguments specified at the call site
-                let num_args_at_callsite = arg_list.args().count();
-
-                let arg_list_range = arg_list.syntax().text_range();
-                if !arg_list_range.contains_inclusive(position.offset) {
-                    tested_by!(call_info_bad_offset);
-                    return None;
-                }
-
-                let mut param = std::cmp::min(
-                    num_args_at_callsite,
-                    arg_list
-                        .args()
-                        .take_while(|arg| arg.syntax().text_range().end() < position.offset)
-                        .count(),
-                );
+                // Commas in nested calls and closures belong to other nodes,
+                // so only the top-level ones are counted.
+                let mut param = arg_list
+                    .syntax()
+                    .children_with_tokens()
+                    .filter(|it| it.kind() == T![,] && it.text_range().end() <= offset)
+                    .count();
 
                 // If we are in a method account for `self`
                 if has_self {
@@ -87,6 +115,53 @@ pub(crate) fn call_info(db: &RootDatabase, position: FilePosition) -> Option<Cal
     Some(call_info)
 }
 
+/// Lists the fields of the record literal as parameters, with the one at the
+/// cursor or else the first one which isn't set yet being active.
+fn record_literal_info(
+    sema: &Semantics<RootDatabase>,
+    field_list: ast::RecordFieldList,
+    offset: TextUnit,
+) -> Option<CallInfo> {
+    let record_lit = field_list.syntax().parent().and_then(ast::RecordLit::cast)?;
+    let variant = sema.resolve_record_literal(&record_lit)?;
+    let mut call_info = CallInfo::with_record_literal(sema.db, variant);
+
+    let mut field_at_cursor = None;
+    for field in field_list.fields() {
+        let name = match field.name_ref() {
+            Some(it) => it.text().to_string(),
+            None => continue,
+        };
+        let idx = match call_info.signature.parameter_names.iter().position(|it| *it == name) {
+            Some(it) => it,
+            None => continue,
+        };
+        if field.syntax().text_range().contains_inclusive(offset) {
+            field_at_cursor = Some(idx);
+        } else {
+            call_info.provided_parameters.push(idx);
+        }
+    }
+    call_info.provided_parameters.sort();
+
+    call_info.active_parameter = field_at_cursor.or_else(|| {
+        (0..call_info.parameters().len()).find(|it| !call_info.provided_parameters.contains(it))
+    });
+    Some(call_info)
+}
+
+fn is_variadic_macro(db: &RootDatabase, macro_def: hir::MacroDef) -> bool {
+    let token_tree = match macro_def.source(db).value.token_tree() {
+        Some(it) => it,
+        None => return false,
+    };
+    // Repetitions look like `$(...)*`
+    token_tree.syntax().descendants_with_tokens().any(|it| {
+        it.kind() == T![$]
+            && it.next_sibling_or_token().map_or(false, |it| it.kind() == SyntaxKind::TOKEN_TREE)
+    })
+}
+
 #[derive(Debug)]
 pub(crate) enum FnCallNode {
     CallExpr(ast::CallExpr),
@@ -95,19 +170,6 @@ pub(crate) enum FnCallNode {
 }
 
 impl FnCallNode {
-    fn with_node(syntax: &SyntaxNode) -> Option<FnCallNode> {
-        syntax.ancestors().find_map(|node| {
-            match_ast! {
-                match node {
-                    ast::CallExpr(it) => { Some(FnCallNode::CallExpr(it)) },
-                    ast::MethodCallExpr(it) => { Some(FnCallNode::MethodCallExpr(it)) },
-                    ast::MacroCall(it) => { Some(FnCallNode::MacroCallExpr(it)) },
-                    _ => { None },
-                }
-            }
-        })
-    }
-
     pub(crate) fn with_node_exact(node: &SyntaxNode) -> Option<FnCallNode> {
         match_ast! {
             match node {
@@ -147,25 +209,31 @@ impl CallInfo {
     fn with_fn(db: &RootDatabase, function: hir::Function) -> Self {
         let signature = FunctionSignature::from_hir(db, function);
 
-        CallInfo { signature, active_parameter: None }
+        CallInfo { signature, active_parameter: None, provided_parameters: Vec::new() }
     }
 
     fn with_struct(db: &RootDatabase, st: hir::Struct) -> Option<Self> {
         let signature = FunctionSignature::from_struct(db, st)?;
 
-        Some(CallInfo { signature, active_parameter: None })
+        Some(CallInfo { signature, active_parameter: None, provided_parameters: Vec::new() })
     }
 
     fn with_enum_variant(db: &RootDatabase, variant: hir::EnumVariant) -> Option<Self> {
         let signature = FunctionSignature::from_enum_variant(db, variant)?;
 
-        Some(CallInfo { signature, active_parameter: None })
+        Some(CallInfo { signature, active_parameter: None, provided_parameters: Vec::new() })
+    }
+
+    fn with_record_literal(db: &RootDatabase, variant: hir::VariantDef) -> Self {
+        let signature = FunctionSignature::from_record_literal(db, variant);
+
+        CallInfo { signature, active_parameter: None, provided_parameters: Vec::new() }
     }
 
     fn with_macro(db: &RootDatabase, macro_def: hir::MacroDef) -> Option<Self> {
         let signature = FunctionSignature::from_macro(db, macro_def)?;
 
-        Some(CallInfo { signature, active_parameter: None })
+        Some(CallInfo { signature, active_parameter: None, provided_parameters: Vec::new() })
     }
 
     fn parameters(&self) -> &[String] {
@@ -623,4 +691,142 @@ fn f() {
 
         assert_eq!(info.label(), "fn foo()");
     }
+
+    #[test]
+    fn active_parameter_counts_top_level_commas() {
+        let info = call_info(
+            r#"fn foo(x: u32, y: u32, z: u32) {}
+fn bar(a: u32, b: u32) -> u32 { a }
+fn baz() { foo(bar(1, 2), |a: u32, b: u32| a, <|>); }"#,
+        );
+
+        assert_eq!(info.label(), "fn foo(x: u32, y: u32, z: u32)");
+        assert_eq!(info.active_parameter, Some(2));
+    }
+
+    #[test]
+    fn active_parameter_after_trailing_comma() {
+        let info = call_info(
+            r#"fn foo(x: u32, y: u32, z: u32) {}
+fn bar() { foo(1, 2,<|>) }"#,
+        );
+
+        assert_eq!(info.active_parameter, Some(2));
+    }
+
+    #[test]
+    fn innermost_call_wins() {
+        let info = call_info(
+            r#"fn foo(x: u32, y: u32) {}
+fn bar(a: u32, b: u32) -> u32 { a }
+fn baz() { foo(1, bar(<|>)); }"#,
+        );
+
+        assert_eq!(info.label(), "fn bar(a: u32, b: u32) -> u32");
+        assert_eq!(info.active_parameter, Some(0));
+    }
+
+    #[test]
+    fn outer_call_wins_on_inner_callee() {
+        let info = call_info(
+            r#"fn foo(x: u32, y: u32) {}
+fn bar(a: u32, b: u32) -> u32 { a }
+fn baz() { foo(1, ba<|>r(2, 3)); }"#,
+        );
+
+        assert_eq!(info.label(), "fn foo(x: u32, y: u32)");
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn active_parameter_for_method_with_args() {
+        let info = call_info(
+            r#"struct F;
+impl F {
+    fn do_it(&self, x: u32, y: u32) {}
+}
+fn bar(f: F) { f.do_it(1, <|>); }"#,
+        );
+
+        assert_eq!(info.parameters(), ["&self", "x: u32", "y: u32"]);
+        assert_eq!(info.active_parameter, Some(2));
+    }
+
+    #[test]
+    fn works_for_record_literals() {
+        let info = call_info(
+            r#"
+/// A struct
+struct S { a: u32, b: i32, c: bool }
+fn main() {
+    let s = S { a: 1, <|> };
+}"#,
+        );
+
+        assert_eq!(info.label(), "S { a: u32, b: i32, c: bool }");
+        assert_eq!(info.doc().map(|it| it.into()), Some("A struct".to_string()));
+        assert_eq!(info.provided_parameters, vec![0]);
+        assert_eq!(info.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn record_literal_field_at_cursor_is_active() {
+        let info = call_info(
+            r#"
+struct S { a: u32, b: i32, c: bool }
+fn main() {
+    let s = S { c<|>: true, a: 1 };
+}"#,
+        );
+
+        assert_eq!(info.provided_parameters, vec![0]);
+        assert_eq!(info.active_parameter, Some(2));
+    }
+
+    #[test]
+    fn works_for_enum_variant_record_literals() {
+        let info = call_info(
+            r#"
+enum E {
+    C { a: i32, b: i32 }
+}
+fn main() {
+    let e = E::C { b: 1, <|> };
+}"#,
+        );
+
+        assert_eq!(info.label(), "E::C { a: i32, b: i32 }");
+        assert_eq!(info.provided_parameters, vec![1]);
+        assert_eq!(info.active_parameter, Some(0));
+    }
+
+    #[test]
+    fn call_in_record_literal_wins() {
+        let info = call_info(
+            r#"
+struct S { a: u32, b: u32 }
+fn foo(x: u32) -> u32 { x }
+fn main() {
+    let s = S { a: foo(<|>) };
+}"#,
+        );
+
+        assert_eq!(info.label(), "fn foo(x: u32) -> u32");
+    }
+
+    #[test]
+    fn no_call_info_for_variadic_macros() {
+        covers!(call_info_variadic_macro);
+        let (analysis, position) = single_file_with_position(
+            r#"
+macro_rules! my_vec {
+    ($($x:expr),*) => { [$($x),*] }
+}
+fn f() {
+    my_vec!(1, <|>);
+}"#,
+        );
+        let call_info = analysis.call_info(position).unwrap();
+        assert!(call_info.is_none());
+    }
 }
@@ -13,14 +13,16 @@ mod complete_keyword;
 mod complete_snippet;
 mod complete_path;
 mod complete_scope;
+mod complete_flyimport;
 mod complete_postfix;
 mod complete_macro_in_item_position;
 mod complete_trait_impl;
+mod complete_crate_name;
 
 use ra_ide_db::RootDatabase;
 
 #[cfg(test)]
-use crate::completion::completion_item::do_completion;
+use crate::completion::completion_item::{do_completion, do_ranked_completion};
 use crate::{
     completion::{
         completion_context::CompletionContext,
@@ -63,10 +65,13 @@ pub(crate) fn completions(db: &RootDatabase, position: FilePosition) -> Option<C
     complete_fn_param::complete_fn_param(&mut acc, &ctx);
     complete_keyword::complete_expr_keyword(&mut acc, &ctx);
     complete_keyword::complete_use_tree_keyword(&mut acc, &ctx);
+    complete_crate_name::complete_crate_name(&mut acc, &ctx);
+    complete_keyword::complete_positional_keyword(&mut acc, &ctx);
     complete_snippet::complete_expr_snippet(&mut acc, &ctx);
     complete_snippet::complete_item_snippet(&mut acc, &ctx);
     complete_path::complete_path(&mut acc, &ctx);
     complete_scope::complete_scope(&mut acc, &ctx);
+    complete_flyimport::complete_flyimport(&mut acc, &ctx);
     complete_dot::complete_dot(&mut acc, &ctx);
     complete_record_literal::complete_record_literal(&mut acc, &ctx);
     complete_record_pattern::complete_record_pattern(&mut acc, &ctx);
@@ -0,0 +1,136 @@
+//! Completes the names of the dependencies of the current crate in `use` and
+//! `extern crate` items.
+
+use rustc_hash::FxHashSet;
+
+use crate::completion::{
+    completion_item::CompletionSource, CompletionContext, CompletionItem, CompletionItemKind,
+    CompletionKind, Completions,
+};
+
+pub(super) fn complete_crate_name(acc: &mut Completions, ctx: &CompletionContext) {
+    if !ctx.is_use_tree_start && !ctx.is_extern_crate_name {
+        return;
+    }
+    let module = match ctx.module {
+        Some(it) => it,
+        None => return,
+    };
+    // Skip the crates which are already imported into this module.
+    let imported: FxHashSet<hir::Name> =
+        module.scope(ctx.db, None).into_iter().map(|(name, _)| name).collect();
+    for dep in module.krate().dependencies(ctx.db) {
+        if imported.contains(&dep.name) {
+            continue;
+        }
+        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), dep.name.to_string())
+            .kind(CompletionItemKind::Module)
+            .source(CompletionSource::Item)
+            .add_to(acc);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{do_completion, CompletionItem, CompletionKind};
+    use insta::assert_debug_snapshot;
+
+    fn do_reference_completion(code: &str) -> Vec<CompletionItem> {
+        do_completion(code, CompletionKind::Reference)
+    }
+
+    #[test]
+    fn completes_crate_names_in_use() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                "
+                //- /main.rs
+                use <|>
+
+                //- /foo/lib.rs
+                pub struct Foo;
+
+                //- /bar/lib.rs
+                pub struct Bar;
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "bar",
+                source_range: [4; 4),
+                delete: [4; 4),
+                insert: "bar",
+                kind: Module,
+            },
+            CompletionItem {
+                label: "foo",
+                source_range: [4; 4),
+                delete: [4; 4),
+                insert: "foo",
+                kind: Module,
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn completes_crate_names_in_extern_crate() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                "
+                //- /main.rs
+                extern crate foo;
+                extern crate <|>
+
+                //- /foo/lib.rs
+                pub struct Foo;
+
+                //- /bar/lib.rs
+                pub struct Bar;
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "bar",
+                source_range: [31; 31),
+                delete: [31; 31),
+                insert: "bar",
+                kind: Module,
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn completes_crate_root_items_after_crate_name() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                "
+                //- /main.rs
+                use foo::<|>
+
+                //- /foo/lib.rs
+                pub struct Foo;
+
+                //- /bar/lib.rs
+                pub struct Bar;
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "Foo",
+                source_range: [9; 9),
+                delete: [9; 9),
+                insert: "Foo",
+                kind: Struct,
+            },
+        ]
+        "###
+        );
+    }
+}
@@ -1,13 +1,13 @@
 //! FIXME: write short doc here
 
-use hir::{HasVisibility, Type};
+use hir::{HasVisibility, ModPath, ModuleDef, RejectionReason, Trait, Type};
 
 use crate::completion::completion_item::CompletionKind;
 use crate::{
     completion::{completion_context::CompletionContext, completion_item::Completions},
     CompletionItem,
 };
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 /// Complete dot accesses, i.e. fields or methods (and .await syntax).
 pub(super) fn complete_dot(acc: &mut Completions, ctx: &CompletionContext) {
@@ -53,21 +53,62 @@ fn complete_fields(acc: &mut Completions, ctx: &CompletionContext, receiver: &Ty
 }
 
 fn complete_methods(acc: &mut Completions, ctx: &CompletionContext, receiver: &Type) {
-    if let Some(krate) = ctx.module.map(|it| it.krate()) {
-        let mut seen_methods = FxHashSet::default();
-        let traits_in_scope = ctx.scope().traits_in_scope();
-        receiver.iterate_method_candidates(ctx.db, krate, &traits_in_scope, None, |_ty, func| {
-            if func.has_self_param(ctx.db) && seen_methods.insert(func.name(ctx.db)) {
-                acc.add_function(ctx, func);
-            }
-            None::<()>
-        });
+    let module = match ctx.module {
+        Some(it) => it,
+        None => return,
+    };
+    let traits_in_scope = ctx.scope().traits_in_scope();
+
+    let mut seen_methods = FxHashSet::default();
+    let mut needs_import = Vec::new();
+    let mut inaccessible = Vec::new();
+    receiver.iterate_method_candidates_with_trait(
+        ctx.db,
+        module,
+        &traits_in_scope,
+        None,
+        |_ty, func, trait_, reason| {
+            if !func.has_self_param(ctx.db) {
+                return None::<()>;
+            }
+            match (reason, trait_) {
+                (Some(RejectionReason::NotVisible), _) => inaccessible.push(func),
+                (Some(RejectionReason::TraitNotInScope), Some(trait_)) => {
+                    needs_import.push((func, trait_))
+                }
+                (Some(_), _) => (),
+                (None, _) => {
+                    if seen_methods.insert(func.name(ctx.db)) {
+                        acc.add_method(ctx, func, trait_.is_some());
+                    }
+                }
+            }
+            None
+        },
+    );
+    // Methods of traits which are in scope shadow the ones we'd have to import.
+    let mut import_paths: FxHashMap<Trait, Option<ModPath>> = FxHashMap::default();
+    for (func, trait_) in needs_import {
+        let path = import_paths
+            .entry(trait_)
+            .or_insert_with(|| module.find_use_path(ctx.db, ModuleDef::Trait(trait_)));
+        if let Some(path) = path {
+            if seen_methods.insert(func.name(ctx.db)) {
+                acc.add_method_with_import(ctx, func, path);
+            }
+        }
+    }
+    // Private methods are only shown when nothing usable has the same name.
+    for func in inaccessible {
+        if seen_methods.insert(func.name(ctx.db)) {
+            acc.add_inaccessible_method(ctx, func);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::completion::{do_completion, CompletionItem, CompletionKind};
+    use crate::completion::{do_completion, do_ranked_completion, CompletionItem, CompletionKind};
     use insta::assert_debug_snapshot;
 
     fn do_ref_completion(code: &str) -> Vec<CompletionItem> {
@@ -100,6 +141,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_struct_field_completion_in_generic_impl() {
+        let completions = do_ref_completion(
+            r"
+            enum Option<T> { None, Some(T) }
+            struct Foo<T> { inner: Option<T> }
+            impl<T> Foo<T> {
+                fn foo(&self) {
+                    self.<|>
+                }
+            }
+            ",
+        );
+        let inner = completions.iter().find(|it| it.label() == "inner").unwrap();
+        assert_eq!(inner.detail(), Some("Option<T>"));
+    }
+
+    #[test]
+    fn test_struct_field_completion_through_deref_to_unconstrained_type() {
+        let completions = do_ref_completion(
+            r#"
+            #[lang = "deref"]
+            trait Deref {
+                type Target;
+                fn deref(&self) -> &Self::Target;
+            }
+            enum Option<T> { None, Some(T) }
+            struct Foo<T> { inner: Option<T> }
+            struct Wrapper;
+            impl<T> Deref for Wrapper {
+                type Target = Foo<T>;
+                fn deref(&self) -> &Foo<T> { loop {} }
+            }
+            fn foo(w: Wrapper) {
+                w.<|>
+            }
+            "#,
+        );
+        let inner = completions.iter().find(|it| it.label() == "inner").unwrap();
+        assert_eq!(inner.detail(), Some("Option<_>"));
+    }
+
     #[test]
     fn test_struct_field_completion_self() {
         assert_debug_snapshot!(
@@ -307,6 +390,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_private_method_completion_is_inaccessible() {
+        assert_debug_snapshot!(
+            do_ref_completion(
+                r"
+            mod inner {
+                pub struct A {}
+                impl A {
+                    fn private_method(&self) {}
+                    pub fn pub_method(&self) {}
+                }
+            }
+            fn foo(a: inner::A) {
+               a.<|>
+            }
+            ",
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "private_method()",
+                source_range: [261; 261),
+                delete: [261; 261),
+                insert: "private_method()$0",
+                kind: Method,
+                lookup: "private_method",
+                detail: "fn private_method(&self)",
+                inaccessible: true,
+            },
+            CompletionItem {
+                label: "pub_method()",
+                source_range: [261; 261),
+                delete: [261; 261),
+                insert: "pub_method()$0",
+                kind: Method,
+                lookup: "pub_method",
+                detail: "pub fn pub_method(&self)",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn test_private_method_completion_in_same_module() {
+        let completions = do_ref_completion(
+            r"
+            struct A {}
+            impl A {
+                fn private_method(&self) {}
+            }
+            fn foo(a: A) {
+               a.<|>
+            }
+            ",
+        );
+        assert_eq!(completions.len(), 1);
+        assert!(!completions[0].inaccessible());
+    }
+
     #[test]
     fn test_trait_method_completion() {
         assert_debug_snapshot!(
@@ -365,6 +508,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trait_method_completion_with_import() {
+        assert_debug_snapshot!(
+            do_ref_completion(
+                r"
+                //- /main.rs
+                struct A {}
+                impl A {
+                    fn inherent(&self) {}
+                }
+                fn foo(a: A) {
+                    a.<|>
+                }
+
+                //- /dep/lib.rs
+                pub trait Trait {
+                    fn the_method(&self) {}
+                }
+                impl<T> Trait for T {}
+                ",
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "inherent()",
+                source_range: [70; 70),
+                delete: [70; 70),
+                insert: "inherent()$0",
+                kind: Method,
+                lookup: "inherent",
+                detail: "fn inherent(&self)",
+            },
+            CompletionItem {
+                label: "the_method()",
+                source_range: [70; 70),
+                text_edit: TextEdit {
+                    atoms: [
+                        AtomTextEdit {
+                            delete: [0; 0),
+                            insert: "use dep::Trait;\n\n",
+                        },
+                        AtomTextEdit {
+                            delete: [70; 70),
+                            insert: "the_method()$0",
+                        },
+                    ],
+                },
+                kind: Method,
+                lookup: "the_method",
+                detail: "fn the_method(&self) (use dep::Trait)",
+            },
+        ]
+        "###
+        );
+    }
+
     #[test]
     fn test_no_non_self_method() {
         assert_debug_snapshot!(
@@ -545,4 +744,71 @@ mod tests {
         "###
         )
     }
+
+    fn ranked_labels(code: &str) -> Vec<String> {
+        do_ranked_completion(code, CompletionKind::Reference)
+            .into_iter()
+            .map(|it| it.label().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn ranks_field_above_method_with_the_same_name() {
+        let labels = ranked_labels(
+            r"
+            struct A { len: u32 }
+            impl A { fn len(&self) -> u32 { self.len } }
+            fn foo(a: A) {
+                a.<|>
+            }
+            ",
+        );
+        assert_eq!(labels, vec!["len", "len()"]);
+    }
+
+    #[test]
+    fn ranks_inherent_methods_above_trait_methods() {
+        let labels = ranked_labels(
+            r"
+            struct A {}
+            trait Trait { fn a_trait_method(&self); }
+            impl Trait for A { fn a_trait_method(&self) {} }
+            impl A { fn b_inherent_method(&self) {} }
+            fn foo(a: A) {
+                a.<|>
+            }
+            ",
+        );
+        assert_eq!(labels, vec!["b_inherent_method()", "a_trait_method()"]);
+    }
+
+    #[test]
+    fn ranks_fields_of_the_expected_type_first() {
+        let labels = ranked_labels(
+            r"
+            struct A { a: u32, b: bool, c: u32 }
+            fn foo(a: A) {
+                let x: bool = a.<|>
+            }
+            ",
+        );
+        assert_eq!(labels, vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn deduplicates_fields_reachable_through_deref() {
+        let labels = ranked_labels(
+            r#"
+            #[lang = "deref"]
+            trait Deref { type Target; fn deref(&self) -> &Self::Target; }
+            struct B { x: u32 }
+            struct A { x: u32, b: B }
+            impl Deref for A { type Target = B; fn deref(&self) -> &B { &self.b } }
+            fn foo(a: A) {
+                a.<|>
+            }
+            "#,
+        );
+        assert_eq!(labels, vec!["b", "x", "deref()"]);
+    }
 }
@@ -0,0 +1,130 @@
+//! Completes items which are not in scope, adding a `use` for them.
+//!
+//! The items are looked up in the `ImportMap`s of the current crate and of its
+//! dependencies, by fuzzy-matching the identifier typed so far.
+
+use hir::{ModPath, PathKind};
+use ra_syntax::SyntaxKind::IDENT;
+use rustc_hash::FxHashSet;
+
+use crate::completion::{CompletionContext, Completions};
+
+/// Upper bound on the number of completions requiring an import, so that
+/// short prefixes don't flood the completion list.
+const MAX_IMPORT_CANDIDATES: usize = 40;
+
+pub(super) fn complete_flyimport(acc: &mut Completions, ctx: &CompletionContext) {
+    if !ctx.is_trivial_path || ctx.use_item_syntax.is_some() || ctx.token.kind() != IDENT {
+        return;
+    }
+    let krate = match ctx.module {
+        Some(module) => module.krate(),
+        None => return,
+    };
+    let token_start = ctx.token.text_range().start();
+    let query = &ctx.token.text()[..(ctx.offset - token_start).to_usize()];
+    if query.is_empty() {
+        return;
+    }
+
+    let mut in_scope = FxHashSet::default();
+    ctx.scope().process_all_names(&mut |name, _| {
+        in_scope.insert(name);
+    });
+
+    let local_items = krate
+        .search_importable_items(ctx.db, query)
+        .into_iter()
+        .map(|(def, path)| (def, ModPath::from_segments(PathKind::Crate, path.segments)));
+    let dependency_items = krate.dependencies(ctx.db).into_iter().flat_map(|dep| {
+        dep.krate.search_importable_items(ctx.db, query).into_iter().map(move |(def, path)| {
+            let segments = std::iter::once(dep.name.clone()).chain(path.segments);
+            (def, ModPath::from_segments(PathKind::Plain, segments))
+        })
+    });
+
+    let mut seen = FxHashSet::default();
+    local_items
+        .chain(dependency_items)
+        .filter(|(_, path)| path.segments.last().map_or(false, |name| !in_scope.contains(name)))
+        .filter(|(def, _)| seen.insert(*def))
+        .take(MAX_IMPORT_CANDIDATES)
+        .for_each(|(def, path)| acc.add_module_def_with_import(ctx, def, &path));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{do_ranked_completion, CompletionItem, CompletionKind};
+
+    fn do_reference_completion(code: &str) -> Vec<CompletionItem> {
+        do_ranked_completion(code, CompletionKind::Reference)
+    }
+
+    fn import_of(item: &CompletionItem) -> Option<&str> {
+        let atoms = item.text_edit().as_atoms();
+        if atoms.len() < 2 {
+            return None;
+        }
+        Some(atoms[0].insert.as_str())
+    }
+
+    #[test]
+    fn imports_reexport_by_shortest_path() {
+        let completions = do_reference_completion(
+            r"
+            //- /main.rs
+            fn main() {
+                HashMa<|>
+            }
+
+            //- /dep/lib.rs
+            pub mod collections {
+                pub mod hash_map {
+                    pub struct HashMap {}
+                }
+                pub use self::hash_map::HashMap;
+            }
+            ",
+        );
+        let hash_map = completions.iter().find(|it| it.label() == "HashMap").unwrap();
+        assert_eq!(import_of(hash_map), Some("use dep::collections::HashMap;\n\n"));
+        assert_eq!(hash_map.detail(), Some("(use dep::collections::HashMap)"));
+    }
+
+    #[test]
+    fn in_scope_items_come_first() {
+        let completions = do_reference_completion(
+            r"
+            //- /main.rs
+            struct HashMapLike {}
+            fn main() {
+                HashMa<|>
+            }
+
+            //- /dep/lib.rs
+            pub struct HashMap {}
+            #[doc(hidden)]
+            pub struct HashMapImpl {}
+            ",
+        );
+        let labels: Vec<&str> = completions.iter().map(|it| it.label()).collect();
+        let local = labels.iter().position(|&it| it == "HashMapLike").unwrap();
+        let imported = labels.iter().position(|&it| it == "HashMap").unwrap();
+        assert!(local < imported);
+        assert!(!labels.contains(&"HashMapImpl"));
+    }
+
+    #[test]
+    fn no_import_in_use_item() {
+        let completions = do_reference_completion(
+            r"
+            //- /main.rs
+            use HashMa<|>
+
+            //- /dep/lib.rs
+            pub struct HashMap {}
+            ",
+        );
+        assert!(completions.iter().all(|it| import_of(it).is_none()));
+    }
+}
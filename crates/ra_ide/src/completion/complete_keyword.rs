@@ -48,8 +48,25 @@ fn keyword(ctx: &CompletionContext, kw: &str, snippet: &str) -> CompletionItem {
         .build()
 }
 
+pub(super) fn complete_positional_keyword(acc: &mut Completions, ctx: &CompletionContext) {
+    if ctx.after_for_pattern {
+        acc.add(keyword(ctx, "in", "in "));
+    }
+    if ctx.after_item_signature {
+        acc.add(keyword(ctx, "where", "where "));
+    }
+    if ctx.is_mut_position {
+        acc.add(keyword(ctx, "mut", "mut "));
+    }
+    if ctx.is_path_type && ctx.path_prefix.is_none() {
+        acc.add(keyword(ctx, "dyn", "dyn "));
+        acc.add(keyword(ctx, "impl", "impl "));
+    }
+}
+
 pub(super) fn complete_expr_keyword(acc: &mut Completions, ctx: &CompletionContext) {
-    if !ctx.is_trivial_path {
+    if !ctx.is_trivial_path || ctx.is_path_type || ctx.after_for_pattern || ctx.after_item_signature
+    {
         return;
     }
 
@@ -778,4 +795,235 @@ mod tests {
         "###
         )
     }
+
+    #[test]
+    fn completes_else_only_after_if_block() {
+        assert!(!do_keyword_completion(
+            r"
+                fn quux() {
+                    if true {
+                        ()
+                    } else {
+                        ()
+                    }
+                    <|>
+                }
+            ",
+        )
+        .iter()
+        .any(|it| it.label() == "else"));
+
+        assert!(!do_keyword_completion(
+            r"
+                fn quux() {
+                    if true {
+                        ()
+                    }
+                    quux();
+                    <|>
+                }
+            ",
+        )
+        .iter()
+        .any(|it| it.label() == "else"));
+    }
+
+    #[test]
+    fn completes_in_after_for_pattern() {
+        assert_debug_snapshot!(
+            do_keyword_completion(
+                r"
+                fn quux() {
+                    for x <|>
+                }
+                ",
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "in",
+                source_range: [55; 55),
+                delete: [55; 55),
+                insert: "in ",
+                kind: Keyword,
+            },
+        ]
+        "###
+        );
+
+        assert!(!do_keyword_completion(
+            r"
+                fn quux() {
+                    for x in <|>
+                }
+            ",
+        )
+        .iter()
+        .any(|it| it.label() == "in"));
+    }
+
+    #[test]
+    fn completes_where_after_item_signature() {
+        assert_debug_snapshot!(
+            do_keyword_completion(
+                r"
+                fn foo<T>(t: T) <|> {}
+                ",
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "where",
+                source_range: [33; 33),
+                delete: [33; 33),
+                insert: "where ",
+                kind: Keyword,
+            },
+        ]
+        "###
+        );
+
+        assert_debug_snapshot!(
+            do_keyword_completion(
+                r"
+                struct Foo<T> <|> { f: T }
+                ",
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "where",
+                source_range: [31; 31),
+                delete: [31; 31),
+                insert: "where ",
+                kind: Keyword,
+            },
+        ]
+        "###
+        );
+
+        assert!(!do_keyword_completion(
+            r"
+                fn foo<T>(t: T) where T: Copy <|> {}
+            ",
+        )
+        .iter()
+        .any(|it| it.label() == "where"));
+
+        assert!(!do_keyword_completion(
+            r"
+                fn foo<T>(t: T) {
+                    <|>
+                }
+            ",
+        )
+        .iter()
+        .any(|it| it.label() == "where"));
+    }
+
+    #[test]
+    fn completes_mut_after_ref_or_let() {
+        assert_debug_snapshot!(
+            do_keyword_completion(
+                r"
+                fn quux() {
+                    let <|>
+                }
+                ",
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "mut",
+                source_range: [53; 53),
+                delete: [53; 53),
+                insert: "mut ",
+                kind: Keyword,
+            },
+        ]
+        "###
+        );
+
+        assert_debug_snapshot!(
+            do_keyword_completion(
+                r"
+                fn foo(x: &<|>) {}
+                ",
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "dyn",
+                source_range: [28; 28),
+                delete: [28; 28),
+                insert: "dyn ",
+                kind: Keyword,
+            },
+            CompletionItem {
+                label: "impl",
+                source_range: [28; 28),
+                delete: [28; 28),
+                insert: "impl ",
+                kind: Keyword,
+            },
+            CompletionItem {
+                label: "mut",
+                source_range: [28; 28),
+                delete: [28; 28),
+                insert: "mut ",
+                kind: Keyword,
+            },
+        ]
+        "###
+        );
+
+        assert!(!do_keyword_completion(
+            r"
+                fn quux(x: i32) {
+                    let y = x & <|>;
+                }
+            ",
+        )
+        .iter()
+        .any(|it| it.label() == "mut"));
+    }
+
+    #[test]
+    fn completes_dyn_and_impl_in_type_position() {
+        assert_debug_snapshot!(
+            do_keyword_completion(
+                r"
+                fn foo(x: <|>) {}
+                ",
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "dyn",
+                source_range: [27; 27),
+                delete: [27; 27),
+                insert: "dyn ",
+                kind: Keyword,
+            },
+            CompletionItem {
+                label: "impl",
+                source_range: [27; 27),
+                delete: [27; 27),
+                insert: "impl ",
+                kind: Keyword,
+            },
+        ]
+        "###
+        );
+
+        assert!(!do_keyword_completion(
+            r"
+                fn quux() {
+                    <|>
+                }
+            ",
+        )
+        .iter()
+        .any(|it| it.label() == "dyn"));
+    }
 }
@@ -1,6 +1,6 @@
 //! Completion of paths, including when writing a single name.
 
-use hir::{Adt, PathResolution, ScopeDef};
+use hir::{Adt, PathResolution, ScopeDef, TypeParam};
 use ra_syntax::AstNode;
 use test_utils::tested_by;
 
@@ -13,11 +13,15 @@ pub(super) fn complete_path(acc: &mut Completions, ctx: &CompletionContext) {
     };
     let def = match ctx.scope().resolve_hir_path(&path) {
         Some(PathResolution::Def(def)) => def,
+        Some(PathResolution::TypeParam(param)) => {
+            complete_type_param_items(acc, ctx, param);
+            return;
+        }
         _ => return,
     };
     match def {
         hir::ModuleDef::Module(module) => {
-            let module_scope = module.scope(ctx.db);
+            let module_scope = module.scope(ctx.db, ctx.module);
             for (name, def) in module_scope {
                 if ctx.use_item_syntax.is_some() {
                     if let ScopeDef::Unknown = def {
@@ -73,7 +77,7 @@ pub(super) fn complete_path(acc: &mut Completions, ctx: &CompletionContext) {
             }
         }
         hir::ModuleDef::Trait(t) => {
-            for item in t.items(ctx.db) {
+            for item in t.items_with_supertraits(ctx.db) {
                 match item {
                     hir::AssocItem::Function(func) => {
                         if !func.has_self_param(ctx.db) {
@@ -89,6 +93,28 @@ pub(super) fn complete_path(acc: &mut Completions, ctx: &CompletionContext) {
     };
 }
 
+/// Completes `T::` with the items of the bounds of `T` and of their super
+/// traits.
+fn complete_type_param_items(acc: &mut Completions, ctx: &CompletionContext, param: TypeParam) {
+    let krate = match ctx.module {
+        Some(module) => module.krate(),
+        None => return,
+    };
+    let traits_in_scope = ctx.scope().traits_in_scope();
+    param.ty(ctx.db).iterate_path_candidates(ctx.db, krate, &traits_in_scope, None, |_ty, item| {
+        match item {
+            hir::AssocItem::Function(func) => {
+                if !func.has_self_param(ctx.db) {
+                    acc.add_function(ctx, func);
+                }
+            }
+            hir::AssocItem::Const(ct) => acc.add_const(ctx, ct),
+            hir::AssocItem::TypeAlias(ty) => acc.add_type_alias(ctx, ty),
+        }
+        None::<()>
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use test_utils::covers;
@@ -177,7 +203,7 @@ mod tests {
                 use self::m::<|>;
 
                 mod m {
-                    struct Bar;
+                    pub struct Bar;
                 }
                 "
             ),
@@ -336,6 +362,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn completes_enum_variant_with_discriminants() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                "
+                //- /lib.rs
+                #[repr(u8)]
+                enum Flags {
+                    A = 1,
+                    B = 1 << 4,
+                    C,
+                    D = 255,
+                    E,
+                }
+                fn foo() { let _ = Flags::<|> }
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "A",
+                source_range: [107; 107),
+                delete: [107; 107),
+                insert: "A",
+                kind: EnumVariant,
+                detail: "= 1",
+            },
+            CompletionItem {
+                label: "B",
+                source_range: [107; 107),
+                delete: [107; 107),
+                insert: "B",
+                kind: EnumVariant,
+                detail: "= 16",
+            },
+            CompletionItem {
+                label: "C",
+                source_range: [107; 107),
+                delete: [107; 107),
+                insert: "C",
+                kind: EnumVariant,
+                detail: "= 17",
+            },
+            CompletionItem {
+                label: "D",
+                source_range: [107; 107),
+                delete: [107; 107),
+                insert: "D",
+                kind: EnumVariant,
+                detail: "= 255",
+            },
+            CompletionItem {
+                label: "E",
+                source_range: [107; 107),
+                delete: [107; 107),
+                insert: "E",
+                kind: EnumVariant,
+                detail: "= ?",
+            },
+        ]
+        "###
+        );
+    }
+
     #[test]
     fn completes_enum_variant_with_details() {
         assert_debug_snapshot!(
@@ -706,6 +796,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn completes_super_trait_items_of_type_param() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                "
+                //- /lib.rs
+                trait Super {
+                    const C: u32;
+                    fn shadowed() -> i64;
+                    fn m();
+                }
+                trait Sub: Super {
+                    fn shadowed() -> u8;
+                    fn n();
+                }
+
+                fn foo<T: Sub>() { T::<|> }
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "C",
+                source_range: [153; 153),
+                delete: [153; 153),
+                insert: "C",
+                kind: Const,
+                detail: "const C: u32;",
+            },
+            CompletionItem {
+                label: "m()",
+                source_range: [153; 153),
+                delete: [153; 153),
+                insert: "m()$0",
+                kind: Function,
+                lookup: "m",
+                detail: "fn m()",
+            },
+            CompletionItem {
+                label: "n()",
+                source_range: [153; 153),
+                delete: [153; 153),
+                insert: "n()$0",
+                kind: Function,
+                lookup: "n",
+                detail: "fn n()",
+            },
+            CompletionItem {
+                label: "shadowed()",
+                source_range: [153; 153),
+                delete: [153; 153),
+                insert: "shadowed()$0",
+                kind: Function,
+                lookup: "shadowed",
+                detail: "fn shadowed() -> u8",
+            },
+        ]
+        "###
+        );
+    }
+
     #[test]
     fn completes_type_alias() {
         assert_debug_snapshot!(
@@ -784,4 +935,120 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn does_not_complete_private_items_from_other_modules() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                r"
+                mod foo {
+                    pub fn public() {}
+                    fn private() {}
+                }
+                fn main() { foo::<|> }
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "public()",
+                source_range: [153; 153),
+                delete: [153; 153),
+                insert: "public()$0",
+                kind: Function,
+                lookup: "public",
+                detail: "pub fn public()",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn completes_private_items_in_own_module() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                r"
+                mod foo {
+                    pub fn public() {}
+                    fn private() {}
+                    fn bar() { self::<|> }
+                }
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "bar()",
+                source_range: [139; 139),
+                delete: [139; 139),
+                insert: "bar()$0",
+                kind: Function,
+                lookup: "bar",
+                detail: "fn bar()",
+            },
+            CompletionItem {
+                label: "private()",
+                source_range: [139; 139),
+                delete: [139; 139),
+                insert: "private()$0",
+                kind: Function,
+                lookup: "private",
+                detail: "fn private()",
+            },
+            CompletionItem {
+                label: "public()",
+                source_range: [139; 139),
+                delete: [139; 139),
+                insert: "public()$0",
+                kind: Function,
+                lookup: "public",
+                detail: "pub fn public()",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn dont_complete_doc_hidden_items_of_other_crates() {
+        let completions = do_reference_completion(
+            r#"
+            //- /lib.rs
+            fn main() { dep::<|> }
+
+            //- /dep/lib.rs
+            #[doc(hidden)]
+            pub mod __private {}
+            pub mod public {}
+            #[deprecated(since = "1.0.0", note = "use `bar` instead")]
+            pub fn foo() {}
+            "#,
+        );
+        let labels: Vec<&str> = completions.iter().map(|it| it.label()).collect();
+        assert_eq!(labels, vec!["foo()", "public"]);
+
+        let foo = &completions[0];
+        assert!(foo.deprecated());
+        assert_eq!(foo.detail(), Some("pub fn foo() (deprecated: use `bar` instead)"));
+    }
+
+    #[test]
+    fn completes_doc_hidden_items_of_own_crate() {
+        let completions = do_reference_completion(
+            r#"
+            #[doc(hidden)]
+            pub mod __private {}
+            #[deprecated = "no longer needed"]
+            pub struct Foo;
+            fn main() { crate::<|> }
+            "#,
+        );
+        let labels: Vec<&str> = completions.iter().map(|it| it.label()).collect();
+        assert_eq!(labels, vec!["Foo", "__private", "main()"]);
+
+        let foo = &completions[0];
+        assert!(foo.deprecated());
+        assert_eq!(foo.detail(), Some("deprecated: no longer needed"));
+    }
 }
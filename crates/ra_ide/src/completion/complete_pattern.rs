@@ -1,5 +1,7 @@
 //! FIXME: write short doc here
 
+use hir::StructKind;
+
 use crate::completion::{CompletionContext, Completions};
 
 /// Completes constats and paths in patterns.
@@ -7,24 +9,71 @@ pub(super) fn complete_pattern(acc: &mut Completions, ctx: &CompletionContext) {
     if !ctx.is_pat_binding {
         return;
     }
-    // FIXME: ideally, we should look at the type we are matching against and
-    // suggest variants + auto-imports
+    let scrutinee_ty = ctx.pat_scrutinee_ty.as_ref();
+    // Variants of the matched enum are completed with their qualified path
+    // below, so don't suggest the ones in scope a second time.
+    let scrutinee_enum = scrutinee_ty.and_then(|ty| {
+        ty.autoderef(ctx.db).find_map(|ty| match ty.as_adt() {
+            Some(hir::Adt::Enum(it)) => Some(it),
+            _ => None,
+        })
+    });
+    let qualified_enum = match scrutinee_enum {
+        Some(enum_) if complete_enum_variants(acc, ctx, enum_) => Some(enum_),
+        _ => None,
+    };
+
+    // FIXME: suggest auto-imports
     ctx.scope().process_all_names(&mut |name, res| {
         let def = match &res {
             hir::ScopeDef::ModuleDef(def) => def,
             _ => return,
         };
         match def {
-            hir::ModuleDef::Adt(hir::Adt::Enum(..))
-            | hir::ModuleDef::EnumVariant(..)
-            | hir::ModuleDef::Const(..)
-            | hir::ModuleDef::Module(..) => (),
+            hir::ModuleDef::EnumVariant(variant) => {
+                if Some(variant.parent_enum(ctx.db)) == qualified_enum {
+                    return;
+                }
+            }
+            hir::ModuleDef::Const(konst) => {
+                if let Some(ty) = scrutinee_ty {
+                    if !konst.ty(ctx.db).is_equal_for_find_impls(ty) {
+                        return;
+                    }
+                }
+            }
+            hir::ModuleDef::Adt(hir::Adt::Struct(strukt)) => {
+                if strukt.kind(ctx.db) != StructKind::Unit {
+                    return;
+                }
+            }
+            hir::ModuleDef::Adt(hir::Adt::Enum(..)) | hir::ModuleDef::Module(..) => (),
             _ => return,
         }
         acc.add_resolution(ctx, name.to_string(), &res)
     });
 }
 
+/// Completes all variants of `enum_` as qualified patterns. Returns `false` if
+/// the enum can't be named from the current module.
+fn complete_enum_variants(
+    acc: &mut Completions,
+    ctx: &CompletionContext,
+    enum_: hir::Enum,
+) -> bool {
+    let enum_path = match ctx
+        .module
+        .and_then(|module| module.find_use_path(ctx.db, hir::ModuleDef::from(enum_)))
+    {
+        Some(it) => it,
+        None => return false,
+    };
+    for variant in enum_.variants(ctx.db) {
+        acc.add_qualified_enum_variant_pat(ctx, variant, &enum_path);
+    }
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use crate::completion::{do_completion, CompletionItem, CompletionKind};
@@ -63,11 +112,13 @@ mod tests {
                 kind: Enum,
             },
             CompletionItem {
-                label: "X",
+                label: "E::X",
                 source_range: [246; 246),
                 delete: [246; 246),
-                insert: "X",
+                insert: "E::X",
                 kind: EnumVariant,
+                detail: "()",
+                sort_text: "0E::X",
             },
             CompletionItem {
                 label: "Z",
@@ -86,4 +137,205 @@ mod tests {
         ]
         "###);
     }
+
+    #[test]
+    fn completes_variants_of_matched_enum() {
+        let completions = complete(
+            r"
+            enum Op { Add(i32, i32), Neg { val: i32 }, Nop }
+            struct Unit;
+            struct NotUnit(u32);
+            const ZERO: u32 = 0;
+            const NOP: Op = Op::Nop;
+
+            fn eval(op: Op) {
+                match op {
+                    Op::Nop => (),
+                    <|>
+                }
+            }
+            ",
+        );
+        assert_debug_snapshot!(completions, @r###"
+        [
+            CompletionItem {
+                label: "NOP",
+                source_range: [303; 303),
+                delete: [303; 303),
+                insert: "NOP",
+                kind: Const,
+            },
+            CompletionItem {
+                label: "Op",
+                source_range: [303; 303),
+                delete: [303; 303),
+                insert: "Op",
+                kind: Enum,
+            },
+            CompletionItem {
+                label: "Op::Add(..)",
+                source_range: [303; 303),
+                delete: [303; 303),
+                insert: "Op::Add($0)",
+                kind: EnumVariant,
+                lookup: "Op::Add",
+                detail: "(i32, i32)",
+                sort_text: "0Op::Add(..)",
+            },
+            CompletionItem {
+                label: "Op::Neg {..}",
+                source_range: [303; 303),
+                delete: [303; 303),
+                insert: "Op::Neg { $0 }",
+                kind: EnumVariant,
+                lookup: "Op::Neg",
+                detail: "{ val: i32 }",
+                sort_text: "0Op::Neg {..}",
+            },
+            CompletionItem {
+                label: "Op::Nop",
+                source_range: [303; 303),
+                delete: [303; 303),
+                insert: "Op::Nop",
+                kind: EnumVariant,
+                detail: "()",
+                sort_text: "0Op::Nop",
+            },
+            CompletionItem {
+                label: "Unit",
+                source_range: [303; 303),
+                delete: [303; 303),
+                insert: "Unit",
+                kind: Struct,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn completes_variants_after_pipe_in_let() {
+        let completions = complete(
+            r"
+            enum Op { Add(i32, i32), Neg { val: i32 }, Nop }
+            struct Unit;
+            struct NotUnit(u32);
+            const ZERO: u32 = 0;
+            const NOP: Op = Op::Nop;
+
+            fn eval(op: &Op) {
+                let Op::Nop | <|> = op;
+            }
+            ",
+        );
+        assert_debug_snapshot!(completions, @r###"
+        [
+            CompletionItem {
+                label: "NOP",
+                source_range: [252; 252),
+                delete: [252; 252),
+                insert: "NOP",
+                kind: Const,
+            },
+            CompletionItem {
+                label: "Op",
+                source_range: [252; 252),
+                delete: [252; 252),
+                insert: "Op",
+                kind: Enum,
+            },
+            CompletionItem {
+                label: "Op::Add(..)",
+                source_range: [252; 252),
+                delete: [252; 252),
+                insert: "Op::Add($0)",
+                kind: EnumVariant,
+                lookup: "Op::Add",
+                detail: "(i32, i32)",
+                sort_text: "0Op::Add(..)",
+            },
+            CompletionItem {
+                label: "Op::Neg {..}",
+                source_range: [252; 252),
+                delete: [252; 252),
+                insert: "Op::Neg { $0 }",
+                kind: EnumVariant,
+                lookup: "Op::Neg",
+                detail: "{ val: i32 }",
+                sort_text: "0Op::Neg {..}",
+            },
+            CompletionItem {
+                label: "Op::Nop",
+                source_range: [252; 252),
+                delete: [252; 252),
+                insert: "Op::Nop",
+                kind: EnumVariant,
+                detail: "()",
+                sort_text: "0Op::Nop",
+            },
+            CompletionItem {
+                label: "Unit",
+                source_range: [252; 252),
+                delete: [252; 252),
+                insert: "Unit",
+                kind: Struct,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn completes_variants_of_enum_from_dependency() {
+        let completions = complete(
+            r"
+            //- /main.rs
+            use dep::Option;
+
+            fn foo(x: Option<i32>) {
+                match x {
+                    <|>
+                }
+            }
+
+            //- /dep/lib.rs
+            pub enum Option<T> { None, Some(T) }
+            ",
+        );
+        assert_debug_snapshot!(completions, @r###"
+        [
+            CompletionItem {
+                label: "Option",
+                source_range: [65; 65),
+                delete: [65; 65),
+                insert: "Option",
+                kind: Enum,
+            },
+            CompletionItem {
+                label: "Option::None",
+                source_range: [65; 65),
+                delete: [65; 65),
+                insert: "Option::None",
+                kind: EnumVariant,
+                detail: "()",
+                sort_text: "0Option::None",
+            },
+            CompletionItem {
+                label: "Option::Some(..)",
+                source_range: [65; 65),
+                delete: [65; 65),
+                insert: "Option::Some($0)",
+                kind: EnumVariant,
+                lookup: "Option::Some",
+                detail: "(T)",
+                sort_text: "0Option::Some(..)",
+            },
+            CompletionItem {
+                label: "dep",
+                source_range: [65; 65),
+                delete: [65; 65),
+                insert: "dep",
+                kind: Module,
+            },
+        ]
+        "###);
+    }
 }
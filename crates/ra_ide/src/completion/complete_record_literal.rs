@@ -1,12 +1,22 @@
 //! FIXME: write short doc here
 
-use crate::completion::{CompletionContext, Completions};
+use itertools::Itertools;
+
+use crate::completion::{
+    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
+};
 
 /// Complete fields in fields literals.
 pub(super) fn complete_record_literal(acc: &mut Completions, ctx: &CompletionContext) {
-    let (ty, variant) = match ctx.record_lit_syntax.as_ref().and_then(|it| {
-        Some((ctx.sema.type_of_expr(&it.clone().into())?, ctx.sema.resolve_record_literal(it)?))
-    }) {
+    let record_lit = match &ctx.record_lit_syntax {
+        Some(it) => it,
+        None => return,
+    };
+    let (ty, variant) = match ctx
+        .sema
+        .type_of_expr(&record_lit.clone().into())
+        .and_then(|ty| Some((ty, ctx.sema.resolve_record_literal(record_lit)?)))
+    {
         Some(it) => it,
         _ => return,
     };
@@ -14,6 +24,21 @@ pub(super) fn complete_record_literal(acc: &mut Completions, ctx: &CompletionCon
     for (field, field_ty) in ty.variant_fields(ctx.db, variant) {
         acc.add_field(ctx, field, &field_ty);
     }
+
+    // Magic completion which fills in all the fields not written yet, each with
+    // a tab stop for its value.
+    let missing_fields = ctx.sema.record_literal_missing_fields(record_lit);
+    if missing_fields.is_empty() {
+        return;
+    }
+    let names: Vec<_> = missing_fields.iter().map(|it| it.name(ctx.db)).collect();
+    let snippet =
+        names.iter().enumerate().map(|(idx, name)| format!("{}: ${}", name, idx + 1)).join(", ");
+    CompletionItem::new(CompletionKind::Magic, ctx.source_range(), "fill missing fields")
+        .kind(CompletionItemKind::Snippet)
+        .detail(names.iter().join(", "))
+        .insert_snippet(snippet)
+        .add_to(acc);
 }
 
 #[cfg(test)]
@@ -153,4 +178,70 @@ mod tests {
         ]
         "###);
     }
+
+    #[test]
+    fn test_record_literal_fill_missing_fields() {
+        let completions = do_completion(
+            r"
+            struct S { foo: u32, bar: i32, baz: u8 }
+            fn foo() {
+               S { bar: 92, <|> }
+            }
+            ",
+            CompletionKind::Magic,
+        );
+        assert_debug_snapshot!(completions, @r###"
+        [
+            CompletionItem {
+                label: "fill missing fields",
+                source_range: [105; 105),
+                delete: [105; 105),
+                insert: "foo: $1, baz: $2",
+                kind: Snippet,
+                detail: "foo, baz",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_record_literal_fill_missing_fields_of_enum_variant() {
+        let completions = do_completion(
+            r"
+            enum E {
+                A { a: u32, b: bool }
+            }
+            fn foo() {
+                let _ = E::A { <|> }
+            }
+            ",
+            CompletionKind::Magic,
+        );
+        assert_debug_snapshot!(completions, @r###"
+        [
+            CompletionItem {
+                label: "fill missing fields",
+                source_range: [128; 128),
+                delete: [128; 128),
+                insert: "a: $1, b: $2",
+                kind: Snippet,
+                detail: "a, b",
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_record_literal_no_fill_missing_fields_with_spread() {
+        let completions = do_completion(
+            r"
+            struct S { foo: u32, bar: i32 }
+            fn foo(s: S) {
+               S { <|> ..s }
+            }
+            ",
+            CompletionKind::Magic,
+        );
+        assert!(completions.is_empty());
+    }
 }
@@ -14,7 +14,7 @@ pub(super) fn complete_scope(acc: &mut Completions, ctx: &CompletionContext) {
 mod tests {
     use insta::assert_debug_snapshot;
 
-    use crate::completion::{do_completion, CompletionItem, CompletionKind};
+    use crate::completion::{do_completion, do_ranked_completion, CompletionItem, CompletionKind};
 
     fn do_reference_completion(code: &str) -> Vec<CompletionItem> {
         do_completion(code, CompletionKind::Reference)
@@ -441,7 +441,7 @@ mod tests {
                 delete: [25; 25),
                 insert: "self",
                 kind: Binding,
-                detail: "&{unknown}",
+                detail: "&_",
             },
         ]
         "###
@@ -797,4 +797,36 @@ mod tests {
         "###
         )
     }
+
+    fn ranked_labels(code: &str) -> Vec<String> {
+        do_ranked_completion(code, CompletionKind::Reference)
+            .into_iter()
+            .map(|it| it.label().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn ranks_locals_of_the_expected_type_first() {
+        let labels = ranked_labels(
+            r"
+            fn quux(x: i32, y: bool) {
+                let z: bool = <|>
+            }
+            ",
+        );
+        assert_eq!(labels, vec!["y", "x", "quux(…)"]);
+    }
+
+    #[test]
+    fn ranks_locals_of_the_record_field_type_first() {
+        let labels = ranked_labels(
+            r"
+            struct S { a: u32, b: bool }
+            fn foo(x: u32, y: bool) {
+                S { a: 1, b: <|> }
+            }
+            ",
+        );
+        assert_eq!(labels, vec!["y", "x", "S", "foo(…)"]);
+    }
 }
@@ -5,9 +5,10 @@ use ra_db::SourceDatabase;
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
     algo::{find_covering_element, find_node_at_offset},
-    ast, AstNode, SourceFile,
+    ast::{self, NameOwner, TypeBoundsOwner, TypeParamsOwner},
+    AstNode, SourceFile,
     SyntaxKind::*,
-    SyntaxNode, SyntaxToken, TextRange, TextUnit,
+    SyntaxNode, SyntaxToken, TextRange, TextUnit, T,
 };
 use ra_text_edit::AtomTextEdit;
 
@@ -29,14 +30,27 @@ pub(crate) struct CompletionContext<'a> {
     pub(super) record_lit_pat: Option<ast::RecordPat>,
     pub(super) impl_block: Option<ast::ImplBlock>,
     pub(super) is_param: bool,
-    /// If a name-binding or reference to a const in a pattern.
-    /// Irrefutable patterns (like let) are excluded.
+    /// If a name-binding or reference to a const in a pattern of a match arm,
+    /// an `if let`/`while let` condition or a `let` statement.
     pub(super) is_pat_binding: bool,
+    /// The type of the expression the pattern is matched against, if known.
+    pub(super) pat_scrutinee_ty: Option<hir::Type>,
+    /// The type the completed expression should have, like the declared type
+    /// of a `let` or the type of a record field, if known.
+    pub(super) expected_type: Option<hir::Type>,
     /// A single-indent path, like `foo`. `::foo` should not be considered a trivial path.
     pub(super) is_trivial_path: bool,
     /// If not a trivial path, the prefix (qualifier).
     pub(super) path_prefix: Option<hir::Path>,
+    /// Directly after the `}` of an `if` block without an `else` branch.
     pub(super) after_if: bool,
+    /// After the pattern of a `for` loop which has no `in` yet.
+    pub(super) after_for_pattern: bool,
+    /// After the signature of a fn, struct, enum, union, trait or impl, where
+    /// a `where` clause may start.
+    pub(super) after_item_signature: bool,
+    /// After `&`, `ref` or `let`, where a `mut` may follow.
+    pub(super) is_mut_position: bool,
     /// `true` if we are a statement or a last expr in the block.
     pub(super) can_be_stmt: bool,
     /// Something is typed at the "top" level, in module or impl/trait.
@@ -47,6 +61,11 @@ pub(crate) struct CompletionContext<'a> {
     /// If this is a call (method or function) in particular, i.e. the () are already there.
     pub(super) is_call: bool,
     pub(super) is_path_type: bool,
+    /// The first segment of a top-level path in a `use` item, like `use foo<|>`,
+    /// where crate names can go.
+    pub(super) is_use_tree_start: bool,
+    /// The name in an `extern crate` item.
+    pub(super) is_extern_crate_name: bool,
     pub(super) has_type_args: bool,
 }
 
@@ -84,18 +103,31 @@ impl<'a> CompletionContext<'a> {
             impl_block: None,
             is_param: false,
             is_pat_binding: false,
+            pat_scrutinee_ty: None,
+            expected_type: None,
             is_trivial_path: false,
             path_prefix: None,
             after_if: false,
+            after_for_pattern: false,
+            after_item_signature: false,
+            is_mut_position: false,
             can_be_stmt: false,
             is_new_item: false,
             dot_receiver: None,
             is_call: false,
             is_path_type: false,
+            is_use_tree_start: false,
+            is_extern_crate_name: false,
             has_type_args: false,
             dot_receiver_is_ambiguous_float_literal: false,
         };
         ctx.fill(&original_file, file_with_fake_ident, position.offset);
+        if let Some(prev) = previous_token(&ctx.token, position.offset) {
+            ctx.after_if = is_after_if(&prev);
+            ctx.after_for_pattern = is_after_for_pattern(&prev);
+            ctx.after_item_signature = is_after_item_signature(&prev);
+            ctx.is_mut_position = is_mut_position(&prev);
+        }
         Some(ctx)
     }
 
@@ -136,11 +168,28 @@ impl<'a> CompletionContext<'a> {
         if let Some(name) = find_node_at_offset::<ast::Name>(file_with_fake_ident.syntax(), offset)
         {
             if let Some(bind_pat) = name.syntax().ancestors().find_map(ast::BindPat::cast) {
-                let parent = bind_pat.syntax().parent();
-                if parent.clone().and_then(ast::MatchArm::cast).is_some()
-                    || parent.and_then(ast::Condition::cast).is_some()
-                {
+                // Skip over `|` alternatives, like in `Foo | Ba<|>`.
+                let parent = bind_pat.syntax().ancestors().skip(1).find(|it| it.kind() != OR_PAT);
+                let scrutinee = match parent.map(|it| it.kind()) {
+                    Some(MATCH_ARM) => Some(
+                        find_node_at_offset::<ast::MatchExpr>(original_file.syntax(), offset)
+                            .and_then(|it| it.expr()),
+                    ),
+                    Some(CONDITION) => Some(
+                        find_node_at_offset::<ast::Condition>(original_file.syntax(), offset)
+                            .and_then(|it| it.expr()),
+                    ),
+                    Some(LET_STMT) => Some(
+                        find_node_at_offset::<ast::LetStmt>(original_file.syntax(), offset)
+                            .and_then(|it| it.initializer()),
+                    ),
+                    _ => None,
+                };
+                if let Some(scrutinee) = scrutinee {
                     self.is_pat_binding = true;
+                    self.pat_scrutinee_ty = scrutinee
+                        .and_then(|it| self.sema.type_of_expr(&it))
+                        .filter(|it| !it.is_unknown());
                 }
             }
             if is_node::<ast::Param>(name.syntax()) {
@@ -183,6 +232,9 @@ impl<'a> CompletionContext<'a> {
             _ => (),
         }
 
+        self.expected_type = expected_type(&self.sema, original_file, &name_ref, self.offset)
+            .filter(|it| !it.is_unknown());
+
         self.use_item_syntax = self.token.parent().ancestors().find_map(ast::UseItem::cast);
 
         self.function_syntax = self
@@ -197,6 +249,11 @@ impl<'a> CompletionContext<'a> {
             None => return,
         };
 
+        if parent.kind() == EXTERN_CRATE_ITEM {
+            self.is_extern_crate_name = true;
+            return;
+        }
+
         if let Some(segment) = ast::PathSegment::cast(parent.clone()) {
             let path = segment.parent_path();
             self.is_call = path
@@ -208,6 +265,14 @@ impl<'a> CompletionContext<'a> {
 
             self.is_path_type = path.syntax().parent().and_then(ast::PathType::cast).is_some();
             self.has_type_args = segment.type_arg_list().is_some();
+            self.is_use_tree_start = path.qualifier().is_none()
+                && segment.syntax().first_token().map_or(false, |it| it.kind() != T![::])
+                && path
+                    .syntax()
+                    .parent()
+                    .and_then(ast::UseTree::cast)
+                    .and_then(|it| it.syntax().parent())
+                    .map_or(false, |it| it.kind() == USE_ITEM);
 
             if let Some(path) = hir::Path::from_ast(path.clone()) {
                 if let Some(path_prefix) = path.qualifier() {
@@ -239,18 +304,6 @@ impl<'a> CompletionContext<'a> {
                         None
                     })
                     .unwrap_or(false);
-
-                if let Some(off) = name_ref.syntax().text_range().start().checked_sub(2.into()) {
-                    if let Some(if_expr) =
-                        find_node_at_offset::<ast::IfExpr>(original_file.syntax(), off)
-                    {
-                        if if_expr.syntax().text_range().end()
-                            < name_ref.syntax().text_range().start()
-                        {
-                            self.after_if = true;
-                        }
-                    }
-                }
             }
         }
         if let Some(field_expr) = ast::FieldExpr::cast(parent.clone()) {
@@ -285,9 +338,165 @@ fn find_node_with_range<N: AstNode>(syntax: &SyntaxNode, range: TextRange) -> Op
     find_covering_element(syntax, range).ancestors().find_map(N::cast)
 }
 
+/// The type the expression ending with `name_ref`, in the file with the fake
+/// ident, should have according to its surroundings.
+fn expected_type(
+    sema: &Semantics<RootDatabase>,
+    original_file: &SourceFile,
+    name_ref: &ast::NameRef,
+    offset: TextUnit,
+) -> Option<hir::Type> {
+    let end = name_ref.syntax().text_range().end();
+    let top_node =
+        name_ref.syntax().ancestors().take_while(|it| it.text_range().end() == end).find(|it| {
+            it.parent().map_or(false, |p| p.kind() == LET_STMT || p.kind() == RECORD_FIELD)
+        })?;
+    let parent = top_node.parent()?;
+    if let Some(let_stmt) = ast::LetStmt::cast(parent.clone()) {
+        if let_stmt.initializer()?.syntax() != &top_node {
+            return None;
+        }
+        let let_stmt = find_node_at_offset::<ast::LetStmt>(original_file.syntax(), offset)?;
+        // Without an ascription, the type of the pattern is inferred from the
+        // very expression that is being completed.
+        let_stmt.ascribed_type()?;
+        return sema.type_of_pat(&let_stmt.pat()?);
+    }
+    if let Some(field) = ast::RecordField::cast(parent) {
+        if field.expr()?.syntax() != &top_node {
+            return None;
+        }
+        let name = field.name_ref()?;
+        // The expression may be missing from the original file, so look the
+        // field up in the type of the record literal instead.
+        let record_lit = find_node_at_offset::<ast::RecordLit>(original_file.syntax(), offset)?;
+        let record_ty = sema.type_of_expr(&ast::Expr::RecordLit(record_lit))?;
+        return record_ty
+            .fields(sema.db)
+            .into_iter()
+            .find(|(field, _)| field.name(sema.db).to_string() == name.text().as_str())
+            .map(|(_, ty)| ty);
+    }
+    None
+}
+
 fn is_node<N: AstNode>(node: &SyntaxNode) -> bool {
     match node.ancestors().find_map(N::cast) {
         None => false,
         Some(n) => n.syntax().text_range() == node.text_range(),
     }
 }
+
+/// The last non-trivia token before `offset`, skipping the identifier or
+/// keyword which is being completed.
+fn previous_token(token: &SyntaxToken, offset: TextUnit) -> Option<SyntaxToken> {
+    if token.kind() == COMMENT || token.kind() == STRING || token.kind() == RAW_STRING {
+        return None;
+    }
+    let mut token = token.clone();
+    if (token.kind() == IDENT || token.kind().is_keyword()) && token.text_range().end() == offset {
+        token = token.prev_token()?;
+    }
+    while token.kind().is_trivia() {
+        token = token.prev_token()?;
+    }
+    Some(token)
+}
+
+fn is_after_if(prev: &SyntaxToken) -> bool {
+    if prev.kind() != T!['}'] || prev.parent().kind() != BLOCK {
+        return false;
+    }
+    match prev.parent().parent().and_then(|it| it.parent()).and_then(ast::IfExpr::cast) {
+        Some(if_expr) => if_expr.else_branch().is_none(),
+        None => false,
+    }
+}
+
+fn is_after_for_pattern(prev: &SyntaxToken) -> bool {
+    let for_expr = match prev.parent().ancestors().find_map(ast::ForExpr::cast) {
+        Some(it) => it,
+        None => return false,
+    };
+    if for_expr.syntax().children_with_tokens().any(|it| it.kind() == T![in]) {
+        return false;
+    }
+    match for_expr.pat() {
+        Some(pat) => pat.syntax().text_range().end() == prev.text_range().end(),
+        None => false,
+    }
+}
+
+fn is_after_item_signature(prev: &SyntaxToken) -> bool {
+    let item = match prev.parent().ancestors().find(|it| match it.kind() {
+        FN_DEF | STRUCT_DEF | ENUM_DEF | UNION_DEF | TRAIT_DEF | IMPL_BLOCK => true,
+        _ => false,
+    }) {
+        Some(it) => it,
+        None => return false,
+    };
+    let signature_end = if let Some(it) = ast::FnDef::cast(item.clone()) {
+        if it.where_clause().is_some() {
+            return false;
+        }
+        match it.ret_type() {
+            Some(ret_type) => ret_type.type_ref().map(|it| it.syntax().text_range().end()),
+            None => it.param_list().map(|it| it.syntax().text_range().end()),
+        }
+    } else if let Some(it) = ast::StructDef::cast(item.clone()) {
+        if it.where_clause().is_some() {
+            return false;
+        }
+        match it.kind() {
+            ast::StructKind::Tuple(fields) => Some(fields.syntax().text_range().end()),
+            _ => generic_signature_end(it.name(), it.type_param_list()),
+        }
+    } else if let Some(it) = ast::EnumDef::cast(item.clone()) {
+        if it.where_clause().is_some() {
+            return false;
+        }
+        generic_signature_end(it.name(), it.type_param_list())
+    } else if let Some(it) = ast::UnionDef::cast(item.clone()) {
+        if it.where_clause().is_some() {
+            return false;
+        }
+        generic_signature_end(it.name(), it.type_param_list())
+    } else if let Some(it) = ast::TraitDef::cast(item.clone()) {
+        if it.where_clause().is_some() {
+            return false;
+        }
+        match it.type_bound_list() {
+            Some(bounds) => Some(bounds.syntax().text_range().end()),
+            None => generic_signature_end(it.name(), it.type_param_list()),
+        }
+    } else if let Some(it) = ast::ImplBlock::cast(item) {
+        if it.where_clause().is_some() {
+            return false;
+        }
+        it.target_type().map(|it| it.syntax().text_range().end())
+    } else {
+        None
+    };
+    signature_end == Some(prev.text_range().end())
+}
+
+fn generic_signature_end(
+    name: Option<ast::Name>,
+    type_params: Option<ast::TypeParamList>,
+) -> Option<TextUnit> {
+    match type_params {
+        Some(it) => Some(it.syntax().text_range().end()),
+        None => name.map(|it| it.syntax().text_range().end()),
+    }
+}
+
+fn is_mut_position(prev: &SyntaxToken) -> bool {
+    match prev.kind() {
+        T![&] => match prev.parent().kind() {
+            REF_EXPR | REF_TYPE | REF_PAT => true,
+            _ => false,
+        },
+        T![ref] | T![let] => true,
+        _ => false,
+    }
+}
@@ -4,7 +4,8 @@ use std::fmt;
 
 use hir::Documentation;
 use ra_syntax::TextRange;
-use ra_text_edit::TextEdit;
+use ra_text_edit::{TextEdit, TextEditBuilder};
+use rustc_hash::FxHashMap;
 
 /// `CompletionItem` describes a single completion variant in the editor pop-up.
 /// It is basically a POD with various properties. To construct a
@@ -47,6 +48,17 @@ pub struct CompletionItem {
 
     /// Whether this item is marked as deprecated
     deprecated: bool,
+
+    /// Used by the client to order completions instead of the label, so that
+    /// more relevant items come first.
+    sort_text: Option<String>,
+
+    /// How relevant this item is at the completion site, higher is better.
+    relevance: u8,
+
+    /// Whether the item exists but can't be used at the completion site, like
+    /// a private method. Clients may show such items greyed out.
+    inaccessible: bool,
 }
 
 // We use custom debug for CompletionItem to make `insta`'s diffs more readable.
@@ -76,11 +88,17 @@ impl fmt::Debug for CompletionItem {
         if self.deprecated {
             s.field("deprecated", &true);
         }
+        if self.inaccessible {
+            s.field("inaccessible", &true);
+        }
+        if let Some(sort_text) = self.sort_text() {
+            s.field("sort_text", &sort_text);
+        }
         s.finish()
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CompletionItemKind {
     Snippet,
     Keyword,
@@ -114,6 +132,27 @@ pub(crate) enum CompletionKind {
     BuiltinType,
 }
 
+/// Where a completion comes from, from the least to the most relevant one.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub(crate) enum CompletionSource {
+    /// An item that is not in scope and gets imported on completion.
+    NeedsImport = 1,
+    /// An item in scope.
+    Item,
+    /// A method of a trait that is in scope.
+    TraitMethod,
+    /// An inherent method.
+    InherentMethod,
+    /// A field of a struct or a tuple.
+    Field,
+    /// A local variable or a parameter.
+    Local,
+}
+
+/// Added to the relevance of items whose type is the expected one, so that
+/// they go before all the other items.
+const TYPE_MATCH_BONUS: u8 = 8;
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum InsertTextFormat {
     PlainText,
@@ -138,7 +177,13 @@ impl CompletionItem {
             lookup: None,
             kind: None,
             text_edit: None,
+            additional_text_edit: None,
             deprecated: None,
+            deprecation_note: None,
+            sort_text: None,
+            source: None,
+            type_matches: false,
+            inaccessible: false,
         }
     }
     /// What user sees in pop-up in the UI.
@@ -177,6 +222,19 @@ impl CompletionItem {
     pub fn deprecated(&self) -> bool {
         self.deprecated
     }
+
+    pub fn sort_text(&self) -> Option<&str> {
+        self.sort_text.as_deref()
+    }
+
+    /// Items with a higher relevance should be shown first.
+    pub fn relevance(&self) -> u8 {
+        self.relevance
+    }
+
+    pub fn inaccessible(&self) -> bool {
+        self.inaccessible
+    }
 }
 
 /// A helper to make `CompletionItem`s.
@@ -192,7 +250,13 @@ pub(crate) struct Builder {
     lookup: Option<String>,
     kind: Option<CompletionItemKind>,
     text_edit: Option<TextEdit>,
+    additional_text_edit: Option<TextEdit>,
     deprecated: Option<bool>,
+    deprecation_note: Option<String>,
+    sort_text: Option<String>,
+    source: Option<CompletionSource>,
+    type_matches: bool,
+    inaccessible: bool,
 }
 
 impl Builder {
@@ -209,18 +273,43 @@ impl Builder {
                 self.insert_text.unwrap_or_else(|| label.clone()),
             ),
         };
+        let text_edit = match self.additional_text_edit {
+            Some(additional) => {
+                let mut builder = TextEditBuilder::default();
+                for atom in text_edit.as_atoms().iter().chain(additional.as_atoms()) {
+                    builder.replace(atom.delete, atom.insert.clone());
+                }
+                builder.finish()
+            }
+            None => text_edit,
+        };
+        let relevance = if self.inaccessible {
+            0
+        } else {
+            self.source.map_or(0, |it| it as u8)
+                + if self.type_matches { TYPE_MATCH_BONUS } else { 0 }
+        };
+
+        let detail = match (self.detail, self.deprecation_note) {
+            (Some(detail), Some(note)) => Some(format!("{} (deprecated: {})", detail, note)),
+            (None, Some(note)) => Some(format!("deprecated: {}", note)),
+            (detail, None) => detail,
+        };
 
         CompletionItem {
             source_range: self.source_range,
             label,
             insert_text_format: self.insert_text_format,
             text_edit,
-            detail: self.detail,
+            detail,
             documentation: self.documentation,
             lookup: self.lookup,
             kind: self.kind,
             completion_kind: self.completion_kind,
             deprecated: self.deprecated.unwrap_or(false),
+            sort_text: self.sort_text,
+            relevance,
+            inaccessible: self.inaccessible,
         }
     }
     pub(crate) fn lookup_by(mut self, lookup: impl Into<String>) -> Builder {
@@ -247,6 +336,12 @@ impl Builder {
         self.text_edit = Some(edit);
         self
     }
+    /// An edit outside of the completed range, applied together with the
+    /// completion (like inserting an import).
+    pub(crate) fn additional_text_edit(mut self, edit: TextEdit) -> Builder {
+        self.additional_text_edit = Some(edit);
+        self
+    }
     pub(crate) fn snippet_edit(mut self, edit: TextEdit) -> Builder {
         self.insert_text_format = InsertTextFormat::Snippet;
         self.text_edit(edit)
@@ -271,6 +366,29 @@ impl Builder {
         self.deprecated = Some(deprecated);
         self
     }
+    /// The note of the `#[deprecated]` attribute, shown after the detail.
+    pub(crate) fn set_deprecation_note(mut self, note: Option<impl Into<String>>) -> Builder {
+        self.deprecation_note = note.map(Into::into);
+        self
+    }
+    pub(crate) fn sort_text(mut self, sort_text: impl Into<String>) -> Builder {
+        self.sort_text = Some(sort_text.into());
+        self
+    }
+    pub(crate) fn source(mut self, source: CompletionSource) -> Builder {
+        self.source = Some(source);
+        self
+    }
+    /// Whether the type of the item is the one expected at the completion site.
+    pub(crate) fn set_type_matches(mut self, type_matches: bool) -> Builder {
+        self.type_matches = type_matches;
+        self
+    }
+    /// Inaccessible items go after all the other ones.
+    pub(crate) fn set_inaccessible(mut self, inaccessible: bool) -> Builder {
+        self.inaccessible = inaccessible;
+        self
+    }
 }
 
 impl<'a> Into<CompletionItem> for Builder {
@@ -283,11 +401,30 @@ impl<'a> Into<CompletionItem> for Builder {
 #[derive(Debug, Default)]
 pub(crate) struct Completions {
     buf: Vec<CompletionItem>,
+    /// Positions in `buf` of the items with a kind, by label and kind.
+    index: FxHashMap<(String, CompletionItemKind), usize>,
 }
 
 impl Completions {
+    /// Adds an item, unless an item with the same label and kind is already
+    /// there. Of the two, the more relevant one is kept.
     pub(crate) fn add(&mut self, item: impl Into<CompletionItem>) {
-        self.buf.push(item.into())
+        let item = item.into();
+        let kind = match item.kind {
+            Some(it) => it,
+            None => return self.buf.push(item),
+        };
+        match self.index.get(&(item.label.clone(), kind)) {
+            Some(&idx) => {
+                if item.relevance > self.buf[idx].relevance {
+                    self.buf[idx] = item;
+                }
+            }
+            None => {
+                self.index.insert((item.label.clone(), kind), self.buf.len());
+                self.buf.push(item);
+            }
+        }
     }
     pub(crate) fn add_all<I>(&mut self, items: I)
     where
@@ -320,3 +457,12 @@ pub(crate) fn do_completion(code: &str, kind: CompletionKind) -> Vec<CompletionI
     kind_completions.sort_by_key(|c| c.label.clone());
     kind_completions
 }
+
+/// Like `do_completion`, but orders the items the way a client would, by
+/// decreasing relevance and then by label.
+#[cfg(test)]
+pub(crate) fn do_ranked_completion(code: &str, kind: CompletionKind) -> Vec<CompletionItem> {
+    let mut kind_completions = do_completion(code, kind);
+    kind_completions.sort_by_key(|c| std::cmp::Reverse(c.relevance));
+    kind_completions
+}
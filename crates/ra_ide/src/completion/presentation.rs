@@ -1,15 +1,20 @@
 //! This modules takes care of rendering various definitions as completion items.
 
-use hir::{db::HirDatabase, Docs, HasAttrs, HasSource, HirDisplay, ScopeDef, StructKind, Type};
+use hir::{
+    db::HirDatabase, AttrDef, Docs, HasAttrs, HasSource, HirDisplay, ScopeDef, StructKind, Type,
+};
 use join_to_string::join;
+use ra_assists::insert_use_statement;
 use ra_syntax::ast::NameOwner;
+use ra_text_edit::TextEditBuilder;
 use test_utils::tested_by;
 
 use crate::completion::{
+    completion_item::{Builder, CompletionSource},
     CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
 };
 
-use crate::display::{const_label, function_label, macro_label, type_label};
+use crate::display::{const_label, discriminant_label, function_label, macro_label, type_label};
 
 impl Completions {
     pub(crate) fn add_field(
@@ -18,6 +23,9 @@ impl Completions {
         field: hir::StructField,
         ty: &Type,
     ) {
+        if is_hidden(ctx, field) {
+            return;
+        }
         let is_deprecated = is_deprecated(field, ctx.db);
         CompletionItem::new(
             CompletionKind::Reference,
@@ -25,16 +33,21 @@ impl Completions {
             field.name(ctx.db).to_string(),
         )
         .kind(CompletionItemKind::Field)
-        .detail(ty.display(ctx.db).to_string())
+        .detail(ty.display_truncated(ctx.db, None).to_string())
         .set_documentation(field.docs(ctx.db))
         .set_deprecated(is_deprecated)
+        .set_deprecation_note(deprecation_note(field, ctx.db))
+        .source(CompletionSource::Field)
+        .set_type_matches(type_matches(ctx, ty))
         .add_to(self);
     }
 
     pub(crate) fn add_tuple_field(&mut self, ctx: &CompletionContext, field: usize, ty: &Type) {
         CompletionItem::new(CompletionKind::Reference, ctx.source_range(), field.to_string())
             .kind(CompletionItemKind::Field)
-            .detail(ty.display(ctx.db).to_string())
+            .detail(ty.display_truncated(ctx.db, None).to_string())
+            .source(CompletionSource::Field)
+            .set_type_matches(type_matches(ctx, ty))
             .add_to(self);
     }
 
@@ -75,32 +88,45 @@ impl Completions {
                 return self.add_macro(ctx, Some(local_name), *mac);
             }
             ScopeDef::Unknown => {
-                return self.add(CompletionItem::new(
-                    CompletionKind::Reference,
-                    ctx.source_range(),
-                    local_name,
-                ));
+                return self.add(
+                    CompletionItem::new(CompletionKind::Reference, ctx.source_range(), local_name)
+                        .source(CompletionSource::Item),
+                );
             }
         };
 
-        let docs = match resolution {
-            ScopeDef::ModuleDef(Module(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(Adt(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(EnumVariant(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(Const(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(Static(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(Trait(it)) => it.docs(ctx.db),
-            ScopeDef::ModuleDef(TypeAlias(it)) => it.docs(ctx.db),
+        let attr_def: Option<AttrDef> = match resolution {
+            ScopeDef::ModuleDef(Module(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(Adt(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(EnumVariant(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(Const(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(Static(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(Trait(it)) => Some((*it).into()),
+            ScopeDef::ModuleDef(TypeAlias(it)) => Some((*it).into()),
             _ => None,
         };
+        if attr_def.map_or(false, |it| is_hidden(ctx, it)) {
+            return;
+        }
+        let docs = attr_def.and_then(|it| it.docs(ctx.db));
 
         let mut completion_item =
-            CompletionItem::new(completion_kind, ctx.source_range(), local_name.clone());
+            CompletionItem::new(completion_kind, ctx.source_range(), local_name.clone())
+                .source(CompletionSource::Item);
+        if let Some(def) = attr_def {
+            completion_item = completion_item
+                .set_deprecated(is_deprecated(def, ctx.db))
+                .set_deprecation_note(deprecation_note(def, ctx.db));
+        }
         if let ScopeDef::Local(local) = resolution {
             let ty = local.ty(ctx.db);
             if !ty.is_unknown() {
-                completion_item = completion_item.detail(ty.display(ctx.db).to_string());
+                completion_item =
+                    completion_item.detail(ty.display_truncated(ctx.db, None).to_string());
             }
+            completion_item = completion_item
+                .source(CompletionSource::Local)
+                .set_type_matches(type_matches(ctx, &ty));
         };
 
         // If not an import, add parenthesis automatically.
@@ -162,6 +188,9 @@ impl Completions {
             Some(it) => it,
             None => return,
         };
+        if is_hidden(ctx, macro_) {
+            return;
+        }
 
         let ast_node = macro_.source(ctx.db).value;
         let detail = macro_label(&ast_node);
@@ -172,8 +201,10 @@ impl Completions {
         let mut builder =
             CompletionItem::new(CompletionKind::Reference, ctx.source_range(), &macro_declaration)
                 .kind(CompletionItemKind::Macro)
+                .source(CompletionSource::Item)
                 .set_documentation(docs.clone())
                 .set_deprecated(is_deprecated(macro_, ctx.db))
+                .set_deprecation_note(deprecation_note(macro_, ctx.db))
                 .detail(detail);
 
         builder = if ctx.use_item_syntax.is_some() {
@@ -193,43 +224,123 @@ impl Completions {
         name: Option<String>,
         func: hir::Function,
     ) {
-        let func_name = func.name(ctx.db);
-        let has_self_param = func.has_self_param(ctx.db);
-        let params = func.params(ctx.db);
-
-        let name = name.unwrap_or_else(|| func_name.to_string());
-        let ast_node = func.source(ctx.db).value;
-        let detail = function_label(&ast_node);
+        if is_hidden(ctx, func) {
+            return;
+        }
+        self.add(function_item(ctx, name, func))
+    }
 
-        let mut builder =
-            CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.clone())
-                .kind(if has_self_param {
-                    CompletionItemKind::Method
-                } else {
-                    CompletionItemKind::Function
-                })
-                .set_documentation(func.docs(ctx.db))
-                .set_deprecated(is_deprecated(func, ctx.db))
-                .detail(detail);
+    /// Adds a method of the receiver, either an inherent one or one of a trait
+    /// which is in scope.
+    pub(crate) fn add_method(
+        &mut self,
+        ctx: &CompletionContext,
+        func: hir::Function,
+        from_trait: bool,
+    ) {
+        let source = if from_trait {
+            CompletionSource::TraitMethod
+        } else {
+            CompletionSource::InherentMethod
+        };
+        if is_hidden(ctx, func) {
+            return;
+        }
+        self.add(function_item(ctx, None, func).source(source))
+    }
 
-        // Add `<>` for generic types
-        if ctx.use_item_syntax.is_none()
-            && !ctx.is_call
-            && ctx.db.feature_flags.get("completion.insertion.add-call-parenthesis")
-        {
-            tested_by!(inserts_parens_for_function_calls);
-            let (snippet, label) = if params.is_empty() || has_self_param && params.len() == 1 {
-                (format!("{}()$0", func_name), format!("{}()", name))
-            } else {
-                (format!("{}($0)", func_name), format!("{}(…)", name))
-            };
-            builder = builder.lookup_by(name).label(label).insert_snippet(snippet);
+    /// Adds a method of the receiver which is not visible at the completion
+    /// site.
+    pub(crate) fn add_inaccessible_method(&mut self, ctx: &CompletionContext, func: hir::Function) {
+        if is_hidden(ctx, func) {
+            return;
         }
+        self.add(function_item(ctx, None, func).set_inaccessible(true))
+    }
 
+    /// Adds a method of a trait which is not in scope, together with an edit
+    /// importing the trait.
+    pub(crate) fn add_method_with_import(
+        &mut self,
+        ctx: &CompletionContext,
+        func: hir::Function,
+        trait_path: &hir::ModPath,
+    ) {
+        if is_hidden(ctx, func) {
+            return;
+        }
+        let detail = function_label(&func.source(ctx.db).value);
+        let mut import_edit = TextEditBuilder::default();
+        let position = ctx.token.parent();
+        insert_use_statement(&position, &position, trait_path, &mut import_edit);
+
+        let builder = function_item(ctx, None, func)
+            .detail(format!("{} (use {})", detail, trait_path))
+            .additional_text_edit(import_edit.finish())
+            .source(CompletionSource::NeedsImport);
         self.add(builder)
     }
 
+    /// Adds an item which is not in scope, together with an edit importing it
+    /// by `path`.
+    pub(crate) fn add_module_def_with_import(
+        &mut self,
+        ctx: &CompletionContext,
+        def: hir::ModuleDef,
+        path: &hir::ModPath,
+    ) {
+        use hir::ModuleDef::*;
+
+        let name = match path.segments.last() {
+            Some(it) => it.to_string(),
+            None => return,
+        };
+        let builder = match def {
+            Function(func) => {
+                if is_hidden(ctx, func) {
+                    return;
+                }
+                let detail = function_label(&func.source(ctx.db).value);
+                function_item(ctx, Some(name), func).detail(format!("{} (use {})", detail, path))
+            }
+            _ => {
+                let (kind, attr_def): (_, AttrDef) = match def {
+                    Module(it) => (CompletionItemKind::Module, it.into()),
+                    Adt(it @ hir::Adt::Enum(_)) => (CompletionItemKind::Enum, it.into()),
+                    Adt(it) => (CompletionItemKind::Struct, it.into()),
+                    EnumVariant(it) => (CompletionItemKind::EnumVariant, it.into()),
+                    Const(it) => (CompletionItemKind::Const, it.into()),
+                    Static(it) => (CompletionItemKind::Static, it.into()),
+                    Trait(it) => (CompletionItemKind::Trait, it.into()),
+                    TypeAlias(it) => (CompletionItemKind::TypeAlias, it.into()),
+                    Function(_) | BuiltinType(_) => return,
+                };
+                if is_hidden(ctx, attr_def) {
+                    return;
+                }
+                CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name)
+                    .kind(kind)
+                    .detail(format!("(use {})", path))
+                    .set_documentation(attr_def.docs(ctx.db))
+                    .set_deprecated(is_deprecated(attr_def, ctx.db))
+                    .set_deprecation_note(deprecation_note(attr_def, ctx.db))
+            }
+        };
+
+        let mut import_edit = TextEditBuilder::default();
+        let position = ctx.token.parent();
+        insert_use_statement(&position, &position, path, &mut import_edit);
+        self.add(
+            builder
+                .additional_text_edit(import_edit.finish())
+                .source(CompletionSource::NeedsImport),
+        )
+    }
+
     pub(crate) fn add_const(&mut self, ctx: &CompletionContext, constant: hir::Const) {
+        if is_hidden(ctx, constant) {
+            return;
+        }
         let ast_node = constant.source(ctx.db).value;
         let name = match ast_node.name() {
             Some(name) => name,
@@ -239,13 +350,18 @@ impl Completions {
 
         CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.text().to_string())
             .kind(CompletionItemKind::Const)
+            .source(CompletionSource::Item)
             .set_documentation(constant.docs(ctx.db))
             .set_deprecated(is_deprecated(constant, ctx.db))
+            .set_deprecation_note(deprecation_note(constant, ctx.db))
             .detail(detail)
             .add_to(self);
     }
 
     pub(crate) fn add_type_alias(&mut self, ctx: &CompletionContext, type_alias: hir::TypeAlias) {
+        if is_hidden(ctx, type_alias) {
+            return;
+        }
         let type_def = type_alias.source(ctx.db).value;
         let name = match type_def.name() {
             Some(name) => name,
@@ -255,42 +371,144 @@ impl Completions {
 
         CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.text().to_string())
             .kind(CompletionItemKind::TypeAlias)
+            .source(CompletionSource::Item)
             .set_documentation(type_alias.docs(ctx.db))
             .set_deprecated(is_deprecated(type_alias, ctx.db))
+            .set_deprecation_note(deprecation_note(type_alias, ctx.db))
             .detail(detail)
             .add_to(self);
     }
 
     pub(crate) fn add_enum_variant(&mut self, ctx: &CompletionContext, variant: hir::EnumVariant) {
+        if is_hidden(ctx, variant) {
+            return;
+        }
         let is_deprecated = is_deprecated(variant, ctx.db);
         let name = variant.name(ctx.db);
-        let detail_types =
-            variant.fields(ctx.db).into_iter().map(|field| (field.name(ctx.db), field.ty(ctx.db)));
-        let detail = match variant.kind(ctx.db) {
-            StructKind::Tuple | StructKind::Unit => {
-                join(detail_types.map(|(_, t)| t.display(ctx.db).to_string()))
-                    .separator(", ")
-                    .surround_with("(", ")")
-                    .to_string()
-            }
-            StructKind::Record => {
-                join(detail_types.map(|(n, t)| format!("{}: {}", n, t.display(ctx.db).to_string())))
-                    .separator(", ")
-                    .surround_with("{ ", " }")
-                    .to_string()
-            }
-        };
         CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.to_string())
             .kind(CompletionItemKind::EnumVariant)
+            .source(CompletionSource::Item)
             .set_documentation(variant.docs(ctx.db))
             .set_deprecated(is_deprecated)
-            .detail(detail)
+            .set_deprecation_note(deprecation_note(variant, ctx.db))
+            .detail(enum_variant_detail(ctx, variant))
             .add_to(self);
     }
+
+    /// Adds a pattern for `variant`, qualified with the path to its enum, like
+    /// `Option::Some(..)`. These are ranked above other completions, as they
+    /// are variants of the type being matched on.
+    pub(crate) fn add_qualified_enum_variant_pat(
+        &mut self,
+        ctx: &CompletionContext,
+        variant: hir::EnumVariant,
+        enum_path: &hir::ModPath,
+    ) {
+        if is_hidden(ctx, variant) {
+            return;
+        }
+        let is_deprecated = is_deprecated(variant, ctx.db);
+        let path = format!("{}::{}", enum_path, variant.name(ctx.db));
+        let (label, snippet) = match variant.kind(ctx.db) {
+            StructKind::Tuple => (format!("{}(..)", path), format!("{}($0)", path)),
+            StructKind::Record => (format!("{} {{..}}", path), format!("{} {{ $0 }}", path)),
+            StructKind::Unit => (path.clone(), path.clone()),
+        };
+        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), label.clone())
+            .kind(CompletionItemKind::EnumVariant)
+            .source(CompletionSource::Item)
+            .lookup_by(path)
+            .insert_snippet(snippet)
+            .set_documentation(variant.docs(ctx.db))
+            .set_deprecated(is_deprecated)
+            .set_deprecation_note(deprecation_note(variant, ctx.db))
+            .detail(enum_variant_detail(ctx, variant))
+            .sort_text(format!("0{}", label))
+            .add_to(self);
+    }
+}
+
+fn enum_variant_detail(ctx: &CompletionContext, variant: hir::EnumVariant) -> String {
+    let detail_types =
+        variant.fields(ctx.db).into_iter().map(|field| (field.name(ctx.db), field.ty(ctx.db)));
+    if variant.kind(ctx.db) == StructKind::Unit {
+        if let Some(discriminant) = discriminant_label(ctx.db, variant) {
+            return discriminant;
+        }
+    }
+    match variant.kind(ctx.db) {
+        StructKind::Tuple | StructKind::Unit => {
+            join(detail_types.map(|(_, t)| t.display_truncated(ctx.db, None).to_string()))
+                .separator(", ")
+                .surround_with("(", ")")
+                .to_string()
+        }
+        StructKind::Record => {
+            join(detail_types.map(|(n, t)| format!("{}: {}", n, t.display_truncated(ctx.db, None))))
+                .separator(", ")
+                .surround_with("{ ", " }")
+                .to_string()
+        }
+    }
+}
+
+fn function_item(ctx: &CompletionContext, name: Option<String>, func: hir::Function) -> Builder {
+    let func_name = func.name(ctx.db);
+    let has_self_param = func.has_self_param(ctx.db);
+    let params = func.params(ctx.db);
+
+    let name = name.unwrap_or_else(|| func_name.to_string());
+    let ast_node = func.source(ctx.db).value;
+    let detail = function_label(&ast_node);
+
+    let mut builder =
+        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.clone())
+            .kind(if has_self_param {
+                CompletionItemKind::Method
+            } else {
+                CompletionItemKind::Function
+            })
+            .source(CompletionSource::Item)
+            .set_documentation(func.docs(ctx.db))
+            .set_deprecated(is_deprecated(func, ctx.db))
+            .set_deprecation_note(deprecation_note(func, ctx.db))
+            .detail(detail);
+
+    // Add `<>` for generic types
+    if ctx.use_item_syntax.is_none()
+        && !ctx.is_call
+        && ctx.db.feature_flags.get("completion.insertion.add-call-parenthesis")
+    {
+        tested_by!(inserts_parens_for_function_calls);
+        let (snippet, label) = if params.is_empty() || has_self_param && params.len() == 1 {
+            (format!("{}()$0", func_name), format!("{}()", name))
+        } else {
+            (format!("{}($0)", func_name), format!("{}(…)", name))
+        };
+        builder = builder.lookup_by(name).label(label).insert_snippet(snippet);
+    }
+
+    builder
+}
+
+/// Whether `ty` is the type expected at the completion site.
+fn type_matches(ctx: &CompletionContext, ty: &Type) -> bool {
+    ctx.expected_type.as_ref() == Some(ty)
 }
 
 fn is_deprecated(node: impl HasAttrs, db: &impl HirDatabase) -> bool {
-    node.attrs(db).by_key("deprecated").exists()
+    node.attrs(db).is_deprecated()
+}
+
+fn deprecation_note(node: impl HasAttrs, db: &impl HirDatabase) -> Option<String> {
+    node.attrs(db).deprecation_note().map(|it| it.to_string())
+}
+
+/// `#[doc(hidden)]` items are only completed inside their own crate.
+fn is_hidden(ctx: &CompletionContext, def: impl Into<AttrDef>) -> bool {
+    let def = def.into();
+    let krate = def.module(ctx.db).map(|it| it.krate());
+    krate != ctx.module.map(|it| it.krate()) && def.attrs(ctx.db).is_doc_hidden()
 }
 
 #[cfg(test)]
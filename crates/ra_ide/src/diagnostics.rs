@@ -1,30 +1,83 @@
 //! FIXME: write short doc here
 
-use std::cell::RefCell;
+use std::{cell::RefCell, iter::FromIterator};
 
 use hir::{
-    diagnostics::{AstDiagnostic, Diagnostic as _, DiagnosticSink},
-    Semantics,
+    db::AstDatabase,
+    diagnostics::{AstDiagnostic, Diagnostic as _, DiagnosticSink, UnresolvedMethodSuggestion},
+    InFile, Semantics,
 };
 use itertools::Itertools;
-use ra_db::{RelativePath, SourceDatabase, SourceDatabaseExt};
-use ra_ide_db::RootDatabase;
+use ra_db::{FileLoader, RelativePath, SourceDatabase, SourceDatabaseExt, SourceRootId};
+use ra_ide_db::{LineIndexDatabase, RootDatabase};
 use ra_prof::profile;
 use ra_syntax::{
     algo,
-    ast::{self, make, AstNode},
+    ast::{self, edit::IndentLevel, make, AstNode},
+    AstPtr, Direction,
+    SyntaxKind::WHITESPACE,
     SyntaxNode, TextRange, T,
 };
 use ra_text_edit::{TextEdit, TextEditBuilder};
+use rustc_hash::FxHashSet;
 
 use crate::{Diagnostic, FileId, FileSystemEdit, SourceChange, SourceFileEdit};
 
 #[derive(Debug, Copy, Clone)]
 pub enum Severity {
     Error,
+    Warning,
     WeakWarning,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiagnosticTag {
+    /// Unused or unnecessary code, which clients may render faded out.
+    Unnecessary,
+    /// Uses of deprecated items, which clients may render struck through.
+    Deprecated,
+}
+
+/// The source roots the diagnostics of a file depend on: the one of the file
+/// itself and the ones of the crates it belongs to and of all their
+/// dependencies. The diagnostics only need to be recomputed after changes to
+/// these source roots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticsDependencies {
+    source_roots: FxHashSet<SourceRootId>,
+}
+
+impl DiagnosticsDependencies {
+    pub fn depends_on(&self, source_root: SourceRootId) -> bool {
+        self.source_roots.contains(&source_root)
+    }
+}
+
+impl FromIterator<SourceRootId> for DiagnosticsDependencies {
+    fn from_iter<I: IntoIterator<Item = SourceRootId>>(iter: I) -> DiagnosticsDependencies {
+        DiagnosticsDependencies { source_roots: iter.into_iter().collect() }
+    }
+}
+
+pub(crate) fn diagnostics_dependencies(
+    db: &RootDatabase,
+    file_id: FileId,
+) -> DiagnosticsDependencies {
+    let crate_graph = db.crate_graph();
+    let mut source_roots = FxHashSet::default();
+    source_roots.insert(db.file_source_root(file_id));
+    let mut visited = FxHashSet::default();
+    let mut stack: Vec<_> = db.relevant_crates(file_id).iter().copied().collect();
+    while let Some(krate) = stack.pop() {
+        if !visited.insert(krate) {
+            continue;
+        }
+        source_roots.insert(db.file_source_root(crate_graph.crate_root(krate)));
+        stack.extend(crate_graph.dependencies(krate).map(|dep| dep.crate_id));
+    }
+    DiagnosticsDependencies { source_roots }
+}
+
 pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic> {
     let _p = profile("diagnostics");
     let sema = Semantics::new(db);
@@ -34,7 +87,9 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
     res.extend(parse.errors().iter().map(|err| Diagnostic {
         range: err.range(),
         message: format!("Syntax Error: {}", err),
+        code: "syntax-error",
         severity: Severity::Error,
+        tags: Vec::new(),
         fix: None,
     }));
 
@@ -46,8 +101,10 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
     let mut sink = DiagnosticSink::new(|d| {
         res.borrow_mut().push(Diagnostic {
             message: d.message(),
+            code: d.code(),
             range: d.highlight_range(),
             severity: Severity::Error,
+            tags: Vec::new(),
             fix: None,
         })
     })
@@ -64,7 +121,9 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
         res.borrow_mut().push(Diagnostic {
             range: d.highlight_range(),
             message: d.message(),
+            code: d.code(),
             severity: Severity::Error,
+            tags: Vec::new(),
             fix: Some(fix),
         })
     })
@@ -97,7 +156,22 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
         res.borrow_mut().push(Diagnostic {
             range: d.highlight_range(),
             message: d.message(),
+            code: d.code(),
+            severity: Severity::Error,
+            tags: Vec::new(),
+            fix,
+        })
+    })
+    .on::<hir::diagnostics::MissingMatchArms, _>(|d| {
+        let fix = text_edit_for_missing_match_arms(&d.ast(db), &d.missed_patterns).map(|edit| {
+            SourceChange::source_file_edit_from("add missing match arms", file_id, edit)
+        });
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            code: d.code(),
             severity: Severity::Error,
+            tags: Vec::new(),
             fix,
         })
     })
@@ -109,9 +183,113 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
         res.borrow_mut().push(Diagnostic {
             range: d.highlight_range(),
             message: d.message(),
+            code: d.code(),
             severity: Severity::Error,
+            tags: Vec::new(),
             fix: Some(fix),
         })
+    })
+    .on::<hir::diagnostics::DeprecatedItem, _>(|d| {
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            code: d.code(),
+            severity: Severity::Warning,
+            tags: vec![DiagnosticTag::Deprecated],
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::InvalidCast, _>(|d| {
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            code: d.code(),
+            severity: Severity::Warning,
+            tags: Vec::new(),
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::InvalidBinaryOp, _>(|d| {
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            code: d.code(),
+            severity: Severity::Warning,
+            tags: Vec::new(),
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::UnresolvedMethodCall, _>(|d| {
+        let fix = match d.suggestion {
+            Some(UnresolvedMethodSuggestion::CallableField) => {
+                text_edit_for_callable_field(&d.ast(db)).map(|edit| {
+                    SourceChange::source_file_edit_from("call the field", file_id, edit)
+                })
+            }
+            Some(UnresolvedMethodSuggestion::MutableReceiver) | None => None,
+        };
+        // Our method resolution is incomplete, so this may be a false positive.
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            code: d.code(),
+            severity: Severity::WeakWarning,
+            tags: Vec::new(),
+            fix,
+        })
+    })
+    .on::<hir::diagnostics::DuplicateInherentItem, _>(|d| {
+        let message = match name_location(db, d.first) {
+            Some(first) => format!("{}, first defined at {}", d.message(), first),
+            None => d.message(),
+        };
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message,
+            code: d.code(),
+            severity: Severity::Error,
+            tags: Vec::new(),
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::UnusedParam, _>(|d| {
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            code: d.code(),
+            severity: Severity::WeakWarning,
+            tags: vec![DiagnosticTag::Unnecessary],
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::UnusedVariable, _>(|d| {
+        let fix = if d.assigned {
+            // Renaming the binding alone would break the assignments to it.
+            None
+        } else {
+            let edit = text_edit_for_unused_variable(&d.ast(db));
+            Some(SourceChange::source_file_edit_from("prefix with `_`", file_id, edit))
+        };
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            code: d.code(),
+            severity: Severity::Warning,
+            tags: vec![DiagnosticTag::Unnecessary],
+            fix,
+        })
+    })
+    .on::<hir::diagnostics::UnusedMut, _>(|d| {
+        let fix = text_edit_for_unused_mut(&d.ast(db))
+            .map(|edit| SourceChange::source_file_edit_from("remove `mut`", file_id, edit));
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            code: d.code(),
+            severity: Severity::Warning,
+            tags: vec![DiagnosticTag::Unnecessary],
+            fix,
+        })
     });
     if let Some(m) = sema.to_module_def(file_id) {
         m.diagnostics(db, &mut sink);
@@ -120,6 +298,81 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
     res.into_inner()
 }
 
+/// Formats the location of `name` as `path:line`.
+fn name_location(db: &RootDatabase, name: InFile<AstPtr<ast::Name>>) -> Option<String> {
+    let root = db.parse_or_expand(name.file_id)?;
+    let node = name.value.to_node(&root);
+    let frange = hir::original_range(db, name.with_value(node.syntax()));
+    let line = db.line_index(frange.file_id).line_col(frange.range.start()).line + 1;
+    Some(format!("{}:{}", db.file_relative_path(frange.file_id), line))
+}
+
+fn text_edit_for_unused_variable(name: &ast::Name) -> TextEdit {
+    let mut edit_builder = TextEditBuilder::default();
+    // In a record pattern shorthand the binding also names the field.
+    if let Some(bind_pat) = name.syntax().parent().and_then(ast::BindPat::cast) {
+        if bind_pat.syntax().parent().and_then(ast::RecordFieldPatList::cast).is_some() {
+            let field = format!("{}: ", name.text());
+            edit_builder.insert(bind_pat.syntax().text_range().start(), field);
+        }
+    }
+    edit_builder.insert(name.syntax().text_range().start(), "_".to_string());
+    edit_builder.finish()
+}
+
+/// Turns `receiver.field(args)` into `(receiver.field)(args)`.
+fn text_edit_for_callable_field(call: &ast::MethodCallExpr) -> Option<TextEdit> {
+    let receiver = call.expr()?;
+    let name_ref = call.name_ref()?;
+    let mut edit_builder = TextEditBuilder::default();
+    edit_builder.insert(receiver.syntax().text_range().start(), "(".to_string());
+    edit_builder.insert(name_ref.syntax().text_range().end(), ")".to_string());
+    Some(edit_builder.finish())
+}
+
+fn text_edit_for_unused_mut(bind_pat: &ast::BindPat) -> Option<TextEdit> {
+    let mut_token = bind_pat.syntax().children_with_tokens().find(|it| it.kind() == T![mut])?;
+    let delete_to = match mut_token.next_sibling_or_token() {
+        Some(it) if it.kind() == WHITESPACE => it.text_range().end(),
+        _ => mut_token.text_range().end(),
+    };
+    Some(TextEdit::delete(TextRange::from_to(mut_token.text_range().start(), delete_to)))
+}
+
+fn text_edit_for_missing_match_arms(
+    arm_list: &ast::MatchArmList,
+    missed_patterns: &[String],
+) -> Option<TextEdit> {
+    let r_curly = arm_list.syntax().last_token().filter(|it| it.kind() == T!['}'])?;
+    let mut edit_builder = TextEditBuilder::default();
+
+    if let Some(last_arm) = arm_list.arms().last() {
+        let needs_comma = last_arm.expr().map_or(false, |it| !it.is_block_like())
+            && algo::non_trivia_sibling(last_arm.syntax().clone().into(), Direction::Next)
+                .map_or(true, |it| it.kind() != T![,]);
+        if needs_comma {
+            edit_builder.insert(last_arm.syntax().text_range().end(), ",".to_string());
+        }
+    }
+
+    match r_curly.prev_token().filter(|it| it.kind() == WHITESPACE && it.text().contains('\n')) {
+        Some(ws) => {
+            let indent = IndentLevel::from_node(arm_list.syntax()).0 as usize + 1;
+            let arms: String = missed_patterns
+                .iter()
+                .map(|pat| format!("\n{:width$}{} => {{}}", "", pat, width = indent * 4))
+                .collect();
+            edit_builder.insert(ws.text_range().start(), arms);
+        }
+        None => {
+            let arms: String =
+                missed_patterns.iter().map(|pat| format!("{} => {{}} ", pat)).collect();
+            edit_builder.insert(r_curly.text_range().start(), arms);
+        }
+    }
+    Some(edit_builder.finish())
+}
+
 fn check_unnecessary_braces_in_use_statement(
     acc: &mut Vec<Diagnostic>,
     file_id: FileId,
@@ -141,7 +394,9 @@ fn check_unnecessary_braces_in_use_statement(
         acc.push(Diagnostic {
             range,
             message: "Unnecessary braces in use statement".to_string(),
+            code: "unnecessary-braces",
             severity: Severity::WeakWarning,
+            tags: Vec::new(),
             fix: Some(SourceChange::source_file_edit(
                 "Remove unnecessary braces",
                 SourceFileEdit { file_id, edit },
@@ -185,7 +440,9 @@ fn check_struct_shorthand_initialization(
                 acc.push(Diagnostic {
                     range: record_field.syntax().text_range(),
                     message: "Shorthand struct initialization".to_string(),
+                    code: "struct-shorthand",
                     severity: Severity::WeakWarning,
+                    tags: Vec::new(),
                     fix: Some(SourceChange::source_file_edit(
                         "use struct shorthand initialization",
                         SourceFileEdit { file_id, edit },
@@ -204,7 +461,7 @@ mod tests {
     use ra_syntax::SourceFile;
     use test_utils::assert_eq_text;
 
-    use crate::mock_analysis::{analysis_and_position, single_file};
+    use crate::mock_analysis::{analysis_and_position, single_file, MockAnalysis};
 
     use super::*;
 
@@ -284,6 +541,25 @@ mod tests {
         assert_eq_text!(after, &actual);
     }
 
+    /// Takes a multi-file input fixture with the cursor in a file with an
+    /// unresolved `mod` declaration, and checks that the diagnostic fix creates
+    /// `path`, after which the declaration resolves.
+    fn check_apply_create_file_fix(fixture: &str, path: &str) {
+        let (analysis, file_position) = analysis_and_position(fixture);
+        let diagnostic = analysis.diagnostics(file_position.file_id).unwrap().pop().unwrap();
+        let mut fix = diagnostic.fix.unwrap();
+        let created = match fix.file_system_edits.pop().unwrap() {
+            FileSystemEdit::CreateFile { path, .. } => path,
+            it => panic!("expected a file to be created, got {:?}", it),
+        };
+        assert_eq!(created.as_str(), path);
+
+        let (mut mock, file_position) = MockAnalysis::with_files_and_position(fixture);
+        mock.add_file(&format!("/{}", created), "");
+        let diagnostics = mock.analysis().diagnostics(file_position.file_id).unwrap();
+        assert!(diagnostics.is_empty(), "{:#?}", diagnostics);
+    }
+
     /// Takes a multi-file input fixture with annotated cursor position and checks that no diagnostics
     /// apply to the file containing the cursor.
     fn check_no_diagnostic_for_target_file(fixture: &str) {
@@ -536,7 +812,7 @@ mod tests {
 
             fn test_fn() {
                 let one = 1;
-                let s = TestStruct{ one, two: 2 };
+                let _s = TestStruct{ one, two: 2 };
             }
         ";
 
@@ -552,11 +828,76 @@ mod tests {
             }
 
             fn test_fn() {
-                let one = 1;
-                let s = TestStruct{ ..a };
+                let _one = 1;
+                let _s = TestStruct{ ..a };
+            }
+        ";
+
+        check_no_diagnostic(content);
+    }
+
+    #[test]
+    fn test_fill_missing_match_arms() {
+        let before = r"
+            enum Either { A, B, C }
+
+            fn main() {
+                match Either::A {
+                    Either::A => (),
+                }
+            }
+        ";
+        let after = r"
+            enum Either { A, B, C }
+
+            fn main() {
+                match Either::A {
+                    Either::A => (),
+                    Either::B => {}
+                    Either::C => {}
+                }
+            }
+        ";
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_fill_missing_match_arms_adds_comma() {
+        let before = r"
+            fn main() {
+                match true {
+                    true => ()
+                }
+            }
+        ";
+        let after = r"
+            fn main() {
+                match true {
+                    true => (),
+                    false => {}
+                }
             }
         ";
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_missing_match_arms_no_diagnostic() {
+        let content = r"
+            enum Either { A, B }
 
+            fn main() {
+                let x = 92;
+                match Either::A {
+                    Either::A if x > 0 => (),
+                    Either::A | Either::B => (),
+                }
+                match (Either::A, x) {
+                    (Either::A, 1) => (),
+                    (Either::B, _) | (_, _) => (),
+                }
+            }
+        ";
         check_no_diagnostic(content);
     }
 
@@ -568,6 +909,7 @@ mod tests {
         [
             Diagnostic {
                 message: "unresolved module",
+                code: "unresolved-module",
                 range: [0; 8),
                 fix: Some(
                     SourceChange {
@@ -585,11 +927,499 @@ mod tests {
                     },
                 ),
                 severity: Error,
+                tags: [],
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_macro_error_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r#"
+macro_rules! m {
+    ($i:ident) => { fn $i() {} };
+}
+macro_rules! nested {
+    ($($i:ident);*) => { $i };
+}
+#[rustc_builtin_macro]
+macro_rules! concat {() => {}}
+
+m!(1);
+
+fn main() {
+    let _x = nested!(a);
+    let _y = concat!(a);
+}
+"#,
+        );
+        let text = analysis.file_text(file_id).unwrap();
+        let diagnostics: Vec<(String, String)> = analysis
+            .diagnostics(file_id)
+            .unwrap()
+            .into_iter()
+            .map(|it| (text[it.range].to_string(), it.message))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                ("m".to_string(), "no rules expected this macro input".to_string()),
+                (
+                    "nested".to_string(),
+                    "expected simple binding, found nested binding `i`".to_string()
+                ),
+                ("concat".to_string(), "expected a literal".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deprecated_item_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r#"
+#[deprecated(since = "0.1.0", note = "use `bar` instead")]
+fn foo() {}
+#[deprecated]
+struct S;
+impl S {
+    #[deprecated = "no longer needed"]
+    fn method(&self) {}
+}
+fn main() {
+    foo();
+    S.method();
+}
+"#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "use of deprecated item `foo`: use `bar` instead",
+                code: "deprecated-item",
+                range: [186; 189),
+                fix: None,
+                severity: Warning,
+                tags: [
+                    Deprecated,
+                ],
+            },
+            Diagnostic {
+                message: "use of deprecated item `S`",
+                code: "deprecated-item",
+                range: [197; 198),
+                fix: None,
+                severity: Warning,
+                tags: [
+                    Deprecated,
+                ],
+            },
+            Diagnostic {
+                message: "use of deprecated item `method`: no longer needed",
+                code: "deprecated-item",
+                range: [199; 205),
+                fix: None,
+                severity: Warning,
+                tags: [
+                    Deprecated,
+                ],
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_callable_field_fix() {
+        let before = r"
+            struct S { f: fn(u32) -> u32 }
+            fn test(s: S) -> u32 {
+                s.f(1)
+            }
+        ";
+        let after = r"
+            struct S { f: fn(u32) -> u32 }
+            fn test(s: S) -> u32 {
+                (s.f)(1)
+            }
+        ";
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_invalid_cast_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r#"
+enum Ordering { Less, Equal, Greater }
+fn foo() {}
+fn main() {
+    let _a = Ordering::Less as i8;
+    let _b = "foo" as u32;
+    let _c = foo as fn() as usize;
+}
+"#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "casting `&str` as `u32` is invalid",
+                code: "invalid-cast",
+                range: [112; 124),
+                fix: None,
+                severity: Warning,
+                tags: [],
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_invalid_binary_op_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r#"
+#[lang = "partial_ord"]
+pub trait PartialOrd<Rhs> {}
+struct A;
+fn main() {
+    let _a = A < A;
+    let _b = 1 < 2;
+}
+"#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "binary operation `<` cannot be applied to type `A`",
+                code: "invalid-binary-op",
+                range: [89; 94),
+                fix: None,
+                severity: Warning,
+                tags: [],
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_duplicate_inherent_item_diagnostic() {
+        let (analysis, position) = analysis_and_position(
+            r"
+            //- /lib.rs
+            mod a;
+            struct S;
+            impl S {
+                fn get(&self) {}
+            }
+
+            //- /a.rs
+            impl super::S {
+                <|>fn get(&self) {}
+            }
+            ",
+        );
+        let diagnostics = analysis.diagnostics(position.file_id).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "duplicate definitions with name `get`, first defined at lib.rs:4",
+                code: "duplicate-inherent-item",
+                range: [23; 26),
+                fix: None,
+                severity: Error,
+                tags: [],
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_unused_param_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+trait Tr {
+    fn tr(&self, x: i32);
+}
+impl Tr for S {
+    fn tr(&self, x: i32) {}
+}
+impl S {
+    fn new(self, a: i32, _b: i32, (c, d): (i32, i32)) -> i32 {
+        let f = |y: i32| y + d;
+        f(a)
+    }
+}
+"#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "unused parameter: `c`",
+                code: "unused-param",
+                range: [140; 141),
+                fix: None,
+                severity: WeakWarning,
+                tags: [
+                    Unnecessary,
+                ],
             },
         ]
         "###);
     }
 
+    #[test]
+    fn test_unused_param_diagnostic_skips_bodies_with_macro_calls() {
+        check_no_diagnostic(
+            r#"
+            fn foo(x: i32) {
+                println!("{}", x);
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_unused_variable_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S { a: i32, b: i32 }
+fn main() {
+    let x = 1;
+    let _y = 2;
+    let (a, b) = (1, 2);
+    let S { a: c, b: d } = S { a, b: 0 };
+    for i in 0..3 {}
+    let mut z = 0;
+    z = 1;
+}
+"#,
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        let diagnostics: Vec<_> = diagnostics.iter().map(|it| &it.message).collect();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            "unused variable: `x`",
+            "unused variable: `c`",
+            "unused variable: `d`",
+            "unused variable: `i`",
+            "variable `z` is assigned to, but never used",
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_unused_variable_fix() {
+        check_apply_diagnostic_fix(
+            r"
+            fn main() {
+                let x = 1;
+            }
+            ",
+            r"
+            fn main() {
+                let _x = 1;
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_unused_variable_fix_in_record_pattern_shorthand() {
+        check_apply_diagnostic_fix(
+            r"
+            struct S { a: i32 }
+            fn main() {
+                let S { a } = S { a: 1 };
+            }
+            ",
+            r"
+            struct S { a: i32 }
+            fn main() {
+                let S { a: _a } = S { a: 1 };
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_unused_mut_fix() {
+        check_apply_diagnostic_fix(
+            r"
+            fn main() -> i32 {
+                let mut x = 1;
+                x
+            }
+            ",
+            r"
+            fn main() -> i32 {
+                let x = 1;
+                x
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_unused_mut_fix_on_param() {
+        check_apply_diagnostic_fix(
+            r"
+            fn id(mut x: i32) -> i32 {
+                x
+            }
+            ",
+            r"
+            fn id(x: i32) -> i32 {
+                x
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_no_unused_mut_on_compound_assignment() {
+        check_no_diagnostic(
+            r"
+            fn main() -> i32 {
+                let mut count = 0;
+                count += 1;
+                count
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_no_unused_mut_on_ref_mut_pattern() {
+        check_no_diagnostic(
+            r"
+            enum E { A(i32), B }
+            fn main() -> E {
+                let mut e = E::A(1);
+                match e {
+                    E::A(ref mut n) => *n += 1,
+                    E::B => {}
+                }
+                let mut f = E::B;
+                let E::A(ref mut m) = f else { return e };
+                *m = 2;
+                f
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_no_unused_mut_on_mutable_borrows() {
+        check_no_diagnostic(
+            r"
+            struct V(i32);
+            impl V {
+                fn push(&mut self, x: i32) {
+                    self.0 += x;
+                }
+            }
+            fn main() -> i32 {
+                let mut v = V(0);
+                v.push(1);
+                let mut w = V(0);
+                let r = &mut w.0;
+                *r = 1;
+                v.0 + w.0
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_no_unused_mut_on_closure_captures() {
+        check_no_diagnostic(
+            r"
+            fn main() -> i32 {
+                let mut captured = 0;
+                let mut inc = move || captured += 1;
+                inc();
+                captured
+            }
+            ",
+        );
+    }
+
+    #[test]
+    fn test_unused_variable_in_closure() {
+        let (analysis, file_id) = single_file(
+            r"
+fn main() -> i32 {
+    let moved = 1;
+    let f = move |unused: i32| moved;
+    f(0)
+}
+",
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        let diagnostics: Vec<_> = diagnostics.iter().map(|it| &it.message).collect();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            "unused variable: `unused`",
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_unused_locals_skip_bodies_with_macro_calls() {
+        check_no_diagnostic(
+            r#"
+            fn foo() {
+                let mut x = 1;
+                println!("{}", x);
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_unresolved_module_in_named_file() {
+        check_apply_create_file_fix(
+            r"
+            //- /lib.rs
+            mod foo;
+
+            //- /foo.rs
+            <|>mod bar;
+            ",
+            "foo/bar.rs",
+        );
+    }
+
+    #[test]
+    fn test_unresolved_module_in_mod_rs() {
+        check_apply_create_file_fix(
+            r"
+            //- /lib.rs
+            mod foo;
+
+            //- /foo/mod.rs
+            <|>mod bar;
+            ",
+            "foo/bar.rs",
+        );
+    }
+
+    #[test]
+    fn test_unresolved_module_in_inline_module() {
+        check_apply_create_file_fix(
+            r"
+            //- /lib.rs
+            mod foo {
+                <|>mod bar;
+            }
+            ",
+            "foo/bar.rs",
+        );
+    }
+
     #[test]
     fn test_check_unnecessary_braces_in_use_statement() {
         check_not_applicable(
@@ -688,4 +1518,49 @@ fn main() {
             check_struct_shorthand_initialization,
         );
     }
+
+    #[test]
+    fn test_diagnostics_dependencies() {
+        use std::sync::Arc;
+
+        use ra_cfg::CfgOptions;
+        use ra_db::{CrateGraph, CrateName, Edition::Edition2018, Env, RelativePathBuf};
+
+        use crate::{AnalysisChange, AnalysisHost};
+
+        let mut change = AnalysisChange::new();
+        let mut crate_graph = CrateGraph::default();
+        let mut crates = Vec::new();
+        for i in 0..3 {
+            let source_root = SourceRootId(i);
+            let file_id = FileId(i + 1);
+            change.add_root(source_root, true);
+            change.add_file(
+                source_root,
+                file_id,
+                RelativePathBuf::from("lib.rs"),
+                Arc::new(String::new()),
+            );
+            crates.push(crate_graph.add_crate_root(
+                file_id,
+                Edition2018,
+                CfgOptions::default(),
+                Env::default(),
+            ));
+        }
+        crate_graph.add_dep(crates[0], CrateName::new("dep").unwrap(), crates[1]).unwrap();
+        change.set_crate_graph(crate_graph);
+        let mut host = AnalysisHost::default();
+        host.apply_change(change);
+        let analysis = host.analysis();
+
+        let (_, dependencies) = analysis.diagnostics_with_dependencies(FileId(1)).unwrap();
+        assert!(dependencies.depends_on(SourceRootId(0)));
+        assert!(dependencies.depends_on(SourceRootId(1)));
+        assert!(!dependencies.depends_on(SourceRootId(2)));
+
+        let (_, dependencies) = analysis.diagnostics_with_dependencies(FileId(2)).unwrap();
+        assert!(!dependencies.depends_on(SourceRootId(0)));
+        assert!(dependencies.depends_on(SourceRootId(1)));
+    }
 }
@@ -6,6 +6,7 @@ mod navigation_target;
 mod structure;
 mod short_label;
 
+use ra_ide_db::RootDatabase;
 use ra_syntax::{
     ast::{self, AstNode, AttrsOwner, NameOwner, TypeParamsOwner},
     SyntaxKind::{ATTR, COMMENT},
@@ -15,7 +16,7 @@ pub use function_signature::FunctionSignature;
 pub use navigation_target::NavigationTarget;
 pub use structure::{file_structure, StructureNode};
 
-pub(crate) use navigation_target::{ToNav, TryToNav};
+pub(crate) use navigation_target::{builtin_docs, ToNav, TryToNav};
 pub(crate) use short_label::ShortLabel;
 
 pub(crate) fn function_label(node: &ast::FnDef) -> String {
@@ -67,6 +68,18 @@ pub(crate) fn macro_label(node: &ast::MacroCall) -> String {
     format!("{}macro_rules! {}", vis, name)
 }
 
+/// The value of the discriminant of `variant`, like `= 17`, for enums with
+/// explicit discriminants. Values which can't be evaluated are shown as `= ?`.
+pub(crate) fn discriminant_label(db: &RootDatabase, variant: hir::EnumVariant) -> Option<String> {
+    if !variant.parent_enum(db).has_explicit_discriminants(db) {
+        return None;
+    }
+    match variant.discriminant(db) {
+        Some(value) => Some(format!("= {}", value)),
+        None => Some("= ?".to_string()),
+    }
+}
+
 pub(crate) fn rust_code_markup<CODE: AsRef<str>>(val: CODE) -> String {
     rust_code_markup_with_doc::<_, &str>(val, None)
 }
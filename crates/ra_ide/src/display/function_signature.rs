@@ -16,6 +16,7 @@ pub enum CallableKind {
     StructConstructor,
     VariantConstructor,
     Macro,
+    RecordLiteral,
 }
 
 /// Contains information about a function signature
@@ -124,6 +125,41 @@ impl FunctionSignature {
         )
     }
 
+    pub(crate) fn from_record_literal(db: &RootDatabase, variant: hir::VariantDef) -> Self {
+        let (name, doc) = match variant {
+            hir::VariantDef::Struct(it) => (it.name(db).to_string(), it.docs(db)),
+            hir::VariantDef::Union(it) => (it.name(db).to_string(), it.docs(db)),
+            hir::VariantDef::EnumVariant(it) => {
+                let parent_name = it.parent_enum(db).name(db);
+                (format!("{}::{}", parent_name, it.name(db)), it.docs(db))
+            }
+        };
+
+        let (params, param_names) = variant
+            .fields(db)
+            .into_iter()
+            .map(|field: hir::StructField| {
+                let name = field.name(db);
+                let ty = field.ty(db);
+                (format!("{}: {}", name, ty.display(db)), name.to_string())
+            })
+            .unzip();
+
+        FunctionSignature {
+            kind: CallableKind::RecordLiteral,
+            visibility: None,
+            name: Some(name),
+            ret_type: None,
+            parameters: params,
+            parameter_names: param_names,
+            generic_parameters: vec![],
+            where_predicates: vec![],
+            doc: None,
+            has_self_param: false,
+        }
+        .with_doc_opt(doc)
+    }
+
     pub(crate) fn from_macro(db: &RootDatabase, macro_def: hir::MacroDef) -> Option<Self> {
         let node: ast::MacroCall = macro_def.source(db).value;
 
@@ -223,6 +259,7 @@ impl Display for FunctionSignature {
                 CallableKind::StructConstructor => write!(f, "struct {}", name)?,
                 CallableKind::VariantConstructor => write!(f, "{}", name)?,
                 CallableKind::Macro => write!(f, "{}!", name)?,
+                CallableKind::RecordLiteral => write!(f, "{}", name)?,
             }
         }
 
@@ -233,7 +270,11 @@ impl Display for FunctionSignature {
                 .to_fmt(f)?;
         }
 
-        join(self.parameters.iter()).separator(", ").surround_with("(", ")").to_fmt(f)?;
+        if let CallableKind::RecordLiteral = self.kind {
+            join(self.parameters.iter()).separator(", ").surround_with(" { ", " }").to_fmt(f)?;
+        } else {
+            join(self.parameters.iter()).separator(", ").surround_with("(", ")").to_fmt(f)?;
+        }
 
         if let Some(t) = &self.ret_type {
             write!(f, " -> {}", t)?;
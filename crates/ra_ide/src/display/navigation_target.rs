@@ -1,14 +1,17 @@
 //! FIXME: write short doc here
 
 use either::Either;
-use hir::{original_range, AssocItem, FieldSource, HasSource, InFile, ModuleSource};
+use hir::{
+    original_macro_call_range, original_range, original_token_range, AssocItem, BuiltinType,
+    FieldSource, HasSource, InFile, ModuleSource,
+};
 use ra_db::{FileId, SourceDatabase};
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
     ast::{self, DocCommentsOwner, NameOwner},
     match_ast, AstNode, SmolStr,
-    SyntaxKind::{self, BIND_PAT, TYPE_PARAM},
-    TextRange,
+    SyntaxKind::{self, BIND_PAT, STRUCT_DEF, TYPE_PARAM},
+    TextRange, TextUnit,
 };
 
 use crate::{
@@ -23,10 +26,11 @@ use super::short_label::ShortLabel;
 /// click on to navigate to a particular piece of code.
 ///
 /// Typically, a `NavigationTarget` corresponds to some element in the source
-/// code, like a function or a struct, but this is not strictly required.
+/// code, like a function or a struct, but this is not strictly required:
+/// builtin types are represented by targets without a file.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct NavigationTarget {
-    file_id: FileId,
+    file_id: Option<FileId>,
     name: SmolStr,
     kind: SyntaxKind,
     full_range: TextRange,
@@ -63,7 +67,9 @@ impl NavigationTarget {
         self.kind
     }
 
-    pub fn file_id(&self) -> FileId {
+    /// Returns `None` for synthetic targets, like builtin types, which have no
+    /// source to navigate to.
+    pub fn file_id(&self) -> Option<FileId> {
         self.file_id
     }
 
@@ -104,6 +110,20 @@ impl NavigationTarget {
         module.to_nav(db)
     }
 
+    pub(crate) fn from_builtin(ty: BuiltinType) -> NavigationTarget {
+        let empty = TextRange::offset_len(TextUnit::from(0), TextUnit::from(0));
+        NavigationTarget {
+            file_id: None,
+            name: ty.to_string().into(),
+            kind: STRUCT_DEF,
+            full_range: empty,
+            focus_range: None,
+            container_name: None,
+            description: Some(ty.to_string()),
+            docs: Some(builtin_docs(ty)),
+        }
+    }
+
     #[cfg(test)]
     pub(crate) fn assert_match(&self, expected: &str) {
         let actual = self.debug_render();
@@ -112,13 +132,11 @@ impl NavigationTarget {
 
     #[cfg(test)]
     pub(crate) fn debug_render(&self) -> String {
-        let mut buf = format!(
-            "{} {:?} {:?} {:?}",
-            self.name(),
-            self.kind(),
-            self.file_id(),
-            self.full_range()
-        );
+        let file = match self.file_id() {
+            Some(file_id) => format!("{:?}", file_id),
+            None => "builtin".to_string(),
+        };
+        let mut buf = format!("{} {:?} {} {:?}", self.name(), self.kind(), file, self.full_range());
         if let Some(focus_range) = self.focus_range() {
             buf.push_str(&format!(" {:?}", focus_range))
         }
@@ -137,9 +155,27 @@ impl NavigationTarget {
     ) -> NavigationTarget {
         //FIXME: use `_` instead of empty string
         let name = node.value.name().map(|it| it.text().clone()).unwrap_or_default();
-        let focus_range =
-            node.value.name().map(|it| original_range(db, node.with_value(it.syntax())).range);
-        let frange = original_range(db, node.map(|it| it.syntax()));
+        let (frange, focus_range) = match original_macro_call_range(db, node.file_id) {
+            // An item produced by a macro points at the macro call, and focuses
+            // its name only if the name was written in the call.
+            Some(call) => {
+                let focus_range = node
+                    .value
+                    .name()
+                    .and_then(|it| it.syntax().first_token())
+                    .and_then(|it| original_token_range(db, node.with_value(it)))
+                    .filter(|it| it.file_id == call.file_id && it.range.is_subrange(&call.range))
+                    .map(|it| it.range);
+                (call, focus_range)
+            }
+            None => {
+                let focus_range = node
+                    .value
+                    .name()
+                    .map(|it| original_range(db, node.with_value(it.syntax())).range);
+                (original_range(db, node.map(|it| it.syntax())), focus_range)
+            }
+        };
 
         NavigationTarget::from_syntax(
             frange.file_id,
@@ -152,7 +188,7 @@ impl NavigationTarget {
         )
     }
 
-    fn from_syntax(
+    pub(crate) fn from_syntax(
         file_id: FileId,
         name: SmolStr,
         focus_range: Option<TextRange>,
@@ -162,7 +198,7 @@ impl NavigationTarget {
         description: Option<String>,
     ) -> NavigationTarget {
         NavigationTarget {
-            file_id,
+            file_id: Some(file_id),
             name,
             kind,
             full_range,
@@ -177,7 +213,7 @@ impl NavigationTarget {
 impl ToNav for FileSymbol {
     fn to_nav(&self, db: &RootDatabase) -> NavigationTarget {
         NavigationTarget {
-            file_id: self.file_id,
+            file_id: Some(self.file_id),
             name: self.name.clone(),
             kind: self.ptr.kind(),
             full_range: self.ptr.range(),
@@ -365,7 +401,7 @@ impl ToNav for hir::Local {
             None => "".into(),
         };
         NavigationTarget {
-            file_id: full_range.file_id,
+            file_id: Some(full_range.file_id),
             name,
             kind: BIND_PAT,
             full_range: full_range.range,
@@ -385,7 +421,7 @@ impl ToNav for hir::TypeParam {
             Either::Right(it) => it.syntax().text_range(),
         };
         NavigationTarget {
-            file_id: src.file_id.original_file(db),
+            file_id: Some(src.file_id.original_file(db)),
             name: self.name(db).to_string().into(),
             kind: TYPE_PARAM,
             full_range: range,
@@ -397,6 +433,22 @@ impl ToNav for hir::TypeParam {
     }
 }
 
+pub(crate) fn builtin_docs(ty: BuiltinType) -> String {
+    let name = ty.to_string();
+    match name.as_str() {
+        "char" => "A character type.".to_string(),
+        "bool" => "The boolean type.".to_string(),
+        "str" => "String slices.".to_string(),
+        "isize" => "The pointer-sized signed integer type.".to_string(),
+        "usize" => "The pointer-sized unsigned integer type.".to_string(),
+        _ => match name.split_at(1) {
+            ("i", bits) => format!("The {}-bit signed integer type.", bits),
+            ("u", bits) => format!("The {}-bit unsigned integer type.", bits),
+            (_, bits) => format!("A {}-bit floating point type.", bits),
+        },
+    }
+}
+
 pub(crate) fn docs_from_symbol(db: &RootDatabase, symbol: &FileSymbol) -> Option<String> {
     let parse = db.parse(symbol.file_id);
     let node = symbol.ptr.to_node(parse.tree().syntax());
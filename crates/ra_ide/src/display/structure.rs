@@ -426,4 +426,97 @@ fn very_obsolete() {}
         "###
                 );
     }
+
+    #[test]
+    fn test_nested_file_structure() {
+        let file = SourceFile::parse(
+            r#"
+mod a {
+    mod b {
+        struct S;
+    }
+    impl Clone for b::S {
+        fn clone(&self) -> Self { b::S }
+    }
+}
+
+macro_rules! m {
+    () => {}
+}
+"#,
+        )
+        .ok()
+        .unwrap();
+        let structure = file_structure(&file);
+        assert_debug_snapshot!(structure,
+        @r###"
+        [
+            StructureNode {
+                parent: None,
+                label: "a",
+                navigation_range: [5; 6),
+                node_range: [1; 119),
+                kind: MODULE,
+                detail: None,
+                deprecated: false,
+            },
+            StructureNode {
+                parent: Some(
+                    0,
+                ),
+                label: "b",
+                navigation_range: [17; 18),
+                node_range: [13; 44),
+                kind: MODULE,
+                detail: None,
+                deprecated: false,
+            },
+            StructureNode {
+                parent: Some(
+                    1,
+                ),
+                label: "S",
+                navigation_range: [36; 37),
+                node_range: [29; 38),
+                kind: STRUCT_DEF,
+                detail: None,
+                deprecated: false,
+            },
+            StructureNode {
+                parent: Some(
+                    0,
+                ),
+                label: "impl Clone for b::S",
+                navigation_range: [64; 68),
+                node_range: [49; 117),
+                kind: IMPL_BLOCK,
+                detail: None,
+                deprecated: false,
+            },
+            StructureNode {
+                parent: Some(
+                    3,
+                ),
+                label: "clone",
+                navigation_range: [82; 87),
+                node_range: [79; 111),
+                kind: FN_DEF,
+                detail: Some(
+                    "fn(&self) -> Self",
+                ),
+                deprecated: false,
+            },
+            StructureNode {
+                parent: None,
+                label: "m",
+                navigation_range: [134; 135),
+                node_range: [121; 152),
+                kind: MACRO_CALL,
+                detail: None,
+                deprecated: false,
+            },
+        ]
+        "###
+                );
+    }
 }
@@ -0,0 +1,354 @@
+//! Computes the location of the rustdoc documentation for the item under the
+//! cursor, following the URL scheme of the pages rustdoc generates.
+
+use hir::{
+    Adt, AsAssocItem, AssocItem, AssocItemContainer, Crate, HasSource, Module, ModuleDef,
+    Semantics, VariantDef,
+};
+use ra_ide_db::{
+    defs::{classify_name, NameDefinition},
+    RootDatabase,
+};
+use ra_syntax::{ast, match_ast, AstNode, SyntaxKind::*, SyntaxToken, TokenAtOffset};
+
+use crate::{references::classify_name_ref, FileId, FilePosition};
+
+/// Where rustdoc puts the documentation of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalDocs {
+    /// The root file of the crate the item is defined in.
+    pub crate_root: FileId,
+    /// The item's page relative to the rustdoc output directory, like
+    /// `foo/bar/struct.Baz.html#method.new`.
+    pub path: String,
+    /// The item's page on docs.rs.
+    pub docs_rs_url: String,
+}
+
+pub(crate) fn external_docs(db: &RootDatabase, position: FilePosition) -> Option<ExternalDocs> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id).syntax().clone();
+    let token = pick_best(file.token_at_offset(position.offset))?;
+    let token = sema.descend_into_macros(token);
+
+    let def = match_ast! {
+        match (token.parent()) {
+            ast::NameRef(name_ref) => classify_name_ref(&sema, &name_ref)?.definition(),
+            ast::Name(name) => classify_name(&sema, &name)?,
+            _ => return None,
+        }
+    };
+
+    let (krate, path) = doc_path(db, def)?;
+    let docs_rs_url = docs_rs_url(db, krate, &path)?;
+    Some(ExternalDocs { crate_root: krate.root_file(db), path, docs_rs_url })
+}
+
+fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
+    return tokens.max_by_key(priority);
+    fn priority(n: &SyntaxToken) -> usize {
+        match n.kind() {
+            IDENT => 2,
+            kind if kind.is_trivia() => 0,
+            _ => 1,
+        }
+    }
+}
+
+fn docs_rs_url(db: &RootDatabase, krate: Crate, path: &str) -> Option<String> {
+    let crate_graph = db.crate_graph();
+    let env = crate_graph.env(krate.into());
+    let package = match env.get("CARGO_PKG_NAME") {
+        Some(package) => package.to_string(),
+        None => crate_name(db, krate)?,
+    };
+    let version = env.get("CARGO_PKG_VERSION").unwrap_or("*");
+    Some(format!("https://docs.rs/{}/{}/{}", package, version, path))
+}
+
+/// The name rustdoc uses for the crate's output directory.
+fn crate_name(db: &RootDatabase, krate: Crate) -> Option<String> {
+    let crate_graph = db.crate_graph();
+    let crate_id = krate.into();
+    if let Some(package) = crate_graph.env(crate_id).get("CARGO_PKG_NAME") {
+        return Some(package.replace('-', "_"));
+    }
+    // Without cargo metadata, fall back to the name dependent crates use.
+    crate_graph.iter().find_map(|it| {
+        crate_graph
+            .dependencies(it)
+            .find(|dep| dep.crate_id == crate_id)
+            .map(|dep| dep.name.to_string())
+    })
+}
+
+fn doc_path(db: &RootDatabase, def: NameDefinition) -> Option<(Crate, String)> {
+    match def {
+        NameDefinition::ModuleDef(def) => module_def_path(db, def),
+        NameDefinition::StructField(field) => {
+            let name = field.name(db);
+            let (krate, page, anchor) = match field.parent_def(db) {
+                VariantDef::Struct(it) => {
+                    let (krate, page) = adt_page(db, it.into())?;
+                    (krate, page, format!("structfield.{}", name))
+                }
+                VariantDef::Union(it) => {
+                    let (krate, page) = adt_page(db, it.into())?;
+                    (krate, page, format!("structfield.{}", name))
+                }
+                VariantDef::EnumVariant(it) => {
+                    let (krate, page) = adt_page(db, it.parent_enum(db).into())?;
+                    (krate, page, format!("variant.{}.field.{}", it.name(db), name))
+                }
+            };
+            Some((krate, format!("{}#{}", page, anchor)))
+        }
+        // Exported macros are documented at the crate root.
+        NameDefinition::Macro(it) => {
+            let name = it.source(db).value.name()?;
+            let crate_root = it.module(db)?.krate().root_module(db)?;
+            item_page(db, crate_root, "macro", name.text())
+        }
+        NameDefinition::SelfType(it) => adt_page(db, it.target_ty(db).as_adt()?),
+        NameDefinition::Local(_) | NameDefinition::TypeParam(_) => None,
+    }
+}
+
+fn module_def_path(db: &RootDatabase, def: ModuleDef) -> Option<(Crate, String)> {
+    let assoc_item = match def {
+        ModuleDef::Function(it) => it.as_assoc_item(db),
+        ModuleDef::Const(it) => it.as_assoc_item(db),
+        ModuleDef::TypeAlias(it) => it.as_assoc_item(db),
+        _ => None,
+    };
+    if let Some(assoc_item) = assoc_item {
+        return assoc_item_path(db, assoc_item);
+    }
+
+    match def {
+        ModuleDef::Module(it) => Some((it.krate(), format!("{}index.html", module_dir(db, it)?))),
+        ModuleDef::Adt(it) => adt_page(db, it),
+        ModuleDef::EnumVariant(it) => {
+            let (krate, page) = adt_page(db, it.parent_enum(db).into())?;
+            Some((krate, format!("{}#variant.{}", page, it.name(db))))
+        }
+        ModuleDef::Function(it) => item_page(db, it.module(db), "fn", &it.name(db)),
+        ModuleDef::Const(it) => item_page(db, it.module(db), "constant", &it.name(db)?),
+        ModuleDef::Static(it) => item_page(db, it.module(db), "static", &it.name(db)?),
+        ModuleDef::Trait(it) => item_page(db, it.module(db), "trait", &it.name(db)),
+        ModuleDef::TypeAlias(it) => item_page(db, it.module(db), "type", &it.name(db)),
+        ModuleDef::BuiltinType(_) => None,
+    }
+}
+
+/// Associated items are documented on the page of their trait, or of the type
+/// of the impl they are in.
+fn assoc_item_path(db: &RootDatabase, item: AssocItem) -> Option<(Crate, String)> {
+    let ((krate, page), in_trait) = match item.container(db) {
+        AssocItemContainer::Trait(it) => {
+            (item_page(db, it.module(db), "trait", &it.name(db))?, true)
+        }
+        AssocItemContainer::ImplBlock(it) => (adt_page(db, it.target_ty(db).as_adt()?)?, false),
+    };
+    let anchor = match item {
+        AssocItem::Function(it) => {
+            // Trait methods without a default body are "required" methods.
+            let kind = if in_trait && it.source(db).value.body().is_none() {
+                "tymethod"
+            } else {
+                "method"
+            };
+            format!("{}.{}", kind, it.name(db))
+        }
+        AssocItem::Const(it) => format!("associatedconstant.{}", it.name(db)?),
+        AssocItem::TypeAlias(it) => format!("associatedtype.{}", it.name(db)),
+    };
+    Some((krate, format!("{}#{}", page, anchor)))
+}
+
+fn adt_page(db: &RootDatabase, adt: Adt) -> Option<(Crate, String)> {
+    match adt {
+        Adt::Struct(it) => item_page(db, it.module(db), "struct", &it.name(db)),
+        Adt::Union(it) => item_page(db, it.module(db), "union", &it.name(db)),
+        Adt::Enum(it) => item_page(db, it.module(db), "enum", &it.name(db)),
+    }
+}
+
+fn item_page(
+    db: &RootDatabase,
+    module: Module,
+    kind: &str,
+    name: &impl std::fmt::Display,
+) -> Option<(Crate, String)> {
+    Some((module.krate(), format!("{}{}.{}.html", module_dir(db, module)?, kind, name)))
+}
+
+/// The directory of a module's pages, like `foo/bar/`.
+fn module_dir(db: &RootDatabase, module: Module) -> Option<String> {
+    let mut res = crate_name(db, module.krate())?;
+    res.push('/');
+    for module in module.path_to_root(db).into_iter().rev() {
+        if let Some(name) = module.name(db) {
+            res.push_str(&name.to_string());
+            res.push('/');
+        }
+    }
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::analysis_and_position;
+
+    fn check_external_docs(fixture: &str, expected_path: &str) {
+        let (analysis, position) = analysis_and_position(fixture);
+        let docs = analysis.external_docs(position).unwrap().unwrap();
+        assert_eq!(docs.path, expected_path);
+        assert_eq!(docs.docs_rs_url, format!("https://docs.rs/foo/*/{}", expected_path));
+    }
+
+    #[test]
+    fn external_docs_nested_modules() {
+        check_external_docs(
+            r#"
+            //- /main.rs
+            use foo::bar::baz::Qux<|>;
+            //- /foo/lib.rs
+            pub mod bar {
+                pub mod baz {
+                    pub struct Qux;
+                }
+            }
+            "#,
+            "foo/bar/baz/struct.Qux.html",
+        );
+        check_external_docs(
+            r#"
+            //- /main.rs
+            use foo::bar::baz<|>;
+            //- /foo/lib.rs
+            pub mod bar {
+                pub mod baz {}
+            }
+            "#,
+            "foo/bar/baz/index.html",
+        );
+        check_external_docs(
+            r#"
+            //- /main.rs
+            fn main() { foo::bar::quux<|>(); }
+            //- /foo/lib.rs
+            pub mod bar {
+                pub fn quux() {}
+            }
+            "#,
+            "foo/bar/fn.quux.html",
+        );
+    }
+
+    #[test]
+    fn external_docs_enum_variant() {
+        check_external_docs(
+            r#"
+            //- /main.rs
+            fn main() { let _ = foo::E::V<|>; }
+            //- /foo/lib.rs
+            pub enum E { V }
+            "#,
+            "foo/enum.E.html#variant.V",
+        );
+    }
+
+    #[test]
+    fn external_docs_assoc_items() {
+        check_external_docs(
+            r#"
+            //- /main.rs
+            fn main() { foo::S::new<|>(); }
+            //- /foo/lib.rs
+            pub struct S;
+            impl S {
+                pub fn new() -> S { S }
+            }
+            "#,
+            "foo/struct.S.html#method.new",
+        );
+        check_external_docs(
+            r#"
+            //- /main.rs
+            use foo::m::Trait;
+            fn main(s: foo::S) { s.required<|>(); }
+            //- /foo/lib.rs
+            pub mod m {
+                pub trait Trait {
+                    fn required(&self);
+                    fn provided(&self) {}
+                }
+            }
+            pub struct S;
+            impl m::Trait for S {
+                fn required(&self) {}
+            }
+            "#,
+            "foo/m/trait.Trait.html#tymethod.required",
+        );
+        check_external_docs(
+            r#"
+            //- /main.rs
+            use foo::m::Trait;
+            fn main(s: foo::S) { s.provided<|>(); }
+            //- /foo/lib.rs
+            pub mod m {
+                pub trait Trait {
+                    fn required(&self);
+                    fn provided(&self) {}
+                }
+            }
+            pub struct S;
+            impl m::Trait for S {
+                fn required(&self) {}
+            }
+            "#,
+            "foo/m/trait.Trait.html#method.provided",
+        );
+        check_external_docs(
+            r#"
+            //- /main.rs
+            fn main() { let _ = foo::S::C<|>; }
+            //- /foo/lib.rs
+            pub struct S;
+            impl S {
+                pub const C: u32 = 0;
+            }
+            "#,
+            "foo/struct.S.html#associatedconstant.C",
+        );
+    }
+
+    #[test]
+    fn external_docs_exported_macro() {
+        check_external_docs(
+            r#"
+            //- /main.rs
+            fn main() { foo::mac<|>!(); }
+            //- /foo/lib.rs
+            pub mod m {
+                #[macro_export]
+                macro_rules! mac { () => {} }
+            }
+            "#,
+            "foo/macro.mac.html",
+        );
+    }
+
+    #[test]
+    fn external_docs_needs_crate_name() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+            //- /main.rs
+            struct S<|>;
+            "#,
+        );
+        assert_eq!(analysis.external_docs(position).unwrap(), None);
+    }
+}
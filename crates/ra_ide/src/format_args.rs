@@ -0,0 +1,236 @@
+//! Resolves the arguments referred to by name in the format strings of the
+//! formatting macros, like `x` and `y` in `format!("{x} {y}", y = 92)`.
+//!
+//! FIXME: the formatting macros are recognized by name, as we don't expand
+//! `format_args!` ourselves.
+
+use hir::{Local, ScopeDef, Semantics};
+use ra_db::FileId;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    ast, AstNode, SmolStr,
+    SyntaxKind::{RAW_STRING, STRING},
+    SyntaxToken, TextRange, TextUnit, T,
+};
+
+use crate::display::{NavigationTarget, ToNav};
+
+const FORMAT_MACROS: &[&str] = &[
+    "format",
+    "format_args",
+    "print",
+    "println",
+    "eprint",
+    "eprintln",
+    "write",
+    "writeln",
+    "panic",
+    "unreachable",
+];
+
+/// What a named placeholder of a format string refers to.
+#[derive(Debug, Clone)]
+pub(crate) enum FormatArgTarget {
+    /// A local variable captured by the format string, like `x` in
+    /// `format!("{x}")`.
+    Local(Local),
+    /// The `name` token of a `name = expr` argument of the macro call.
+    Named(SyntaxToken),
+}
+
+impl FormatArgTarget {
+    /// `file_id` is the file of the macro call.
+    pub(crate) fn to_nav(&self, db: &RootDatabase, file_id: FileId) -> NavigationTarget {
+        match self {
+            FormatArgTarget::Local(it) => it.to_nav(db),
+            FormatArgTarget::Named(it) => NavigationTarget::from_syntax(
+                file_id,
+                it.text().clone(),
+                None,
+                it.text_range(),
+                it.kind(),
+                None,
+                None,
+            ),
+        }
+    }
+}
+
+/// A placeholder which refers to an argument by name.
+#[derive(Debug, Clone)]
+pub(crate) struct FormatArg {
+    pub(crate) range: TextRange,
+    pub(crate) name: SmolStr,
+}
+
+/// Returns the format string's named placeholders, if `token` is the format
+/// string of a formatting macro call.
+pub(crate) fn format_args(token: &SyntaxToken) -> Option<Vec<FormatArg>> {
+    format_macro_call(token)?;
+    let text = token.text().as_str();
+    let start = text.find('"')? + 1;
+    let end = text.rfind('"')?;
+    if end < start {
+        return None;
+    }
+    let offset = token.text_range().start() + TextUnit::from_usize(start);
+    let res = named_placeholders(&text[start..end])
+        .into_iter()
+        .map(|(range, name)| FormatArg { range: range + offset, name })
+        .collect();
+    Some(res)
+}
+
+/// Resolves the named placeholder of the format string `token` at `offset`.
+pub(crate) fn resolve_format_arg(
+    sema: &Semantics<RootDatabase>,
+    token: &SyntaxToken,
+    offset: TextUnit,
+) -> Option<(FormatArg, FormatArgTarget)> {
+    let arg = format_args(token)?.into_iter().find(|it| it.range.contains_inclusive(offset))?;
+    let target = resolve(sema, token, &arg.name)?;
+    Some((arg, target))
+}
+
+fn resolve(
+    sema: &Semantics<RootDatabase>,
+    token: &SyntaxToken,
+    name: &str,
+) -> Option<FormatArgTarget> {
+    let (macro_call, tt) = format_macro_call(token)?;
+
+    // Explicitly named arguments take precedence over captured variables.
+    let named = tt
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !it.kind().is_trivia())
+        .collect::<Vec<_>>();
+    let named_arg = named.windows(3).find_map(|window| match window {
+        [comma, ident, eq]
+            if comma.kind() == T![,]
+                && ident.text() == name
+                && eq.kind() == T![=]
+                && eq.next_token().map(|it| it.kind()) != Some(T![=]) =>
+        {
+            Some(ident.clone())
+        }
+        _ => None,
+    });
+    if let Some(ident) = named_arg {
+        return Some(FormatArgTarget::Named(ident));
+    }
+
+    let scope = sema.scope_at_offset(macro_call.syntax(), macro_call.syntax().text_range().start());
+    let mut res = None;
+    scope.process_all_names(&mut |it, def| {
+        if res.is_some() || it.to_string() != name {
+            return;
+        }
+        if let ScopeDef::Local(local) = def {
+            res = Some(local);
+        }
+    });
+    res.map(FormatArgTarget::Local)
+}
+
+/// Returns the macro call whose format string is `token`, and its token tree.
+fn format_macro_call(token: &SyntaxToken) -> Option<(ast::MacroCall, ast::TokenTree)> {
+    if token.kind() != STRING && token.kind() != RAW_STRING {
+        return None;
+    }
+    let tt = ast::TokenTree::cast(token.parent())?;
+    let macro_call = ast::MacroCall::cast(tt.syntax().parent()?)?;
+    let name_ref = macro_call.path()?.segment()?.name_ref()?;
+    if !FORMAT_MACROS.contains(&name_ref.text().as_str()) {
+        return None;
+    }
+    // `write!` and `writeln!` take the destination first, so we use the first
+    // string literal rather than the first argument.
+    let format_string = tt
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|it| it.kind() == STRING || it.kind() == RAW_STRING)?;
+    if format_string != *token {
+        return None;
+    }
+    Some((macro_call, tt))
+}
+
+/// Returns the ranges and names of the placeholders of `format_string` which
+/// refer to an argument by name, skipping escaped braces and positional
+/// placeholders.
+fn named_placeholders(format_string: &str) -> Vec<(TextRange, SmolStr)> {
+    let mut res = Vec::new();
+    let mut chars = format_string.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '{' => {
+                if let Some((_, '{')) = chars.peek() {
+                    chars.next();
+                    continue;
+                }
+                let start = idx + 1;
+                let end = match format_string[start..].find('}') {
+                    Some(len) => start + len,
+                    None => break,
+                };
+                for (idx, _) in &mut chars {
+                    if idx == end {
+                        break;
+                    }
+                }
+                // Only the argument before the format spec can be a name.
+                let name = format_string[start..end].split(':').next().unwrap_or_default();
+                if is_identifier(name) {
+                    let range =
+                        TextRange::offset_len(TextUnit::from_usize(start), TextUnit::of_str(name));
+                    res.push((range, name.into()));
+                }
+            }
+            '}' => {
+                if let Some((_, '}')) = chars.peek() {
+                    chars.next();
+                }
+            }
+            _ => (),
+        }
+    }
+    res
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_alphabetic() => {}
+        _ => return false,
+    }
+    text != "_" && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::named_placeholders;
+
+    fn check(format_string: &str, expected: &[&str]) {
+        let actual = named_placeholders(format_string)
+            .into_iter()
+            .map(|(range, name)| {
+                assert_eq!(&format_string[range], name.as_str());
+                name.to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn named_placeholders_of_format_string() {
+        check("{x} and {y}", &["x", "y"]);
+        check("{x:?} {y:>width$}", &["x", "y"]);
+        check("{} {0} {1:?}", &[]);
+        check("{{x}} {{{y}}}", &["y"]);
+        check("{_x} {_}", &["_x"]);
+        check("{x", &[]);
+    }
+}
@@ -11,7 +11,8 @@ use ra_syntax::{
 
 use crate::{
     display::{ToNav, TryToNav},
-    references::classify_name_ref,
+    format_args::resolve_format_arg,
+    references::{classify_name_ref, classify_pat_field_shorthand, NameDefinition, NameRefClass},
     FilePosition, NavigationTarget, RangeInfo,
 };
 
@@ -22,6 +23,10 @@ pub(crate) fn goto_definition(
     let sema = Semantics::new(db);
     let file = sema.parse(position.file_id).syntax().clone();
     let original_token = pick_best(file.token_at_offset(position.offset))?;
+    if let Some((arg, target)) = resolve_format_arg(&sema, &original_token, position.offset) {
+        let nav = target.to_nav(db, position.file_id);
+        return Some(RangeInfo::new(arg.range, vec![nav]));
+    }
     let token = sema.descend_into_macros(original_token.clone());
 
     let nav_targets = match_ast! {
@@ -53,6 +58,11 @@ fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
 #[derive(Debug)]
 pub(crate) enum ReferenceResult {
     Exact(NavigationTarget),
+    /// A field init shorthand refers both to the field and to the local.
+    FieldShorthand {
+        field: NavigationTarget,
+        local: NavigationTarget,
+    },
     Approximate(Vec<NavigationTarget>),
 }
 
@@ -61,6 +71,7 @@ impl ReferenceResult {
         use self::ReferenceResult::*;
         match self {
             Exact(target) => vec![target],
+            FieldShorthand { field, local } => vec![field, local],
             Approximate(vec) => vec,
         }
     }
@@ -73,7 +84,19 @@ pub(crate) fn reference_definition(
     use self::ReferenceResult::*;
 
     let name_kind = classify_name_ref(sema, name_ref);
-    if let Some(def) = name_kind {
+    if let Some(class) = name_kind {
+        let def = match class {
+            NameRefClass::FieldShorthand { field, local } => {
+                return FieldShorthand {
+                    field: field.to_nav(sema.db),
+                    local: local.to_nav(sema.db),
+                };
+            }
+            NameRefClass::Definition(def) => def,
+        };
+        if let NameDefinition::ModuleDef(hir::ModuleDef::BuiltinType(it)) = def {
+            return Exact(NavigationTarget::from_builtin(it));
+        }
         return match def.try_to_nav(sema.db) {
             Some(nav) => ReferenceResult::Exact(nav),
             None => ReferenceResult::Approximate(Vec::new()),
@@ -94,7 +117,11 @@ fn name_definition(
 ) -> Option<Vec<NavigationTarget>> {
     let def = classify_name(sema, name)?;
     let nav = def.try_to_nav(sema.db)?;
-    Some(vec![nav])
+    // A record pattern shorthand also refers to the field it binds.
+    match classify_pat_field_shorthand(sema, name) {
+        Some(field) => Some(vec![field.to_nav(sema.db), nav]),
+        None => Some(vec![nav]),
+    }
 }
 
 #[cfg(test)]
@@ -110,7 +137,7 @@ mod tests {
         assert_eq!(navs.len(), 1);
 
         let nav = navs.pop().unwrap();
-        let file_text = analysis.file_text(nav.file_id()).unwrap();
+        let file_text = analysis.file_text(nav.file_id().unwrap()).unwrap();
 
         let mut actual = file_text[nav.full_range()].to_string();
         if let Some(focus) = nav.focus_range() {
@@ -135,6 +162,16 @@ mod tests {
         nav.assert_match(expected);
     }
 
+    fn check_goto_all(fixture: &str, expected: &[&str]) {
+        let (analysis, pos) = analysis_and_position(fixture);
+
+        let navs = analysis.goto_definition(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), expected.len());
+        for (nav, expected) in navs.iter().zip(expected) {
+            nav.assert_match(expected);
+        }
+    }
+
     #[test]
     fn goto_def_in_items() {
         check_goto(
@@ -297,9 +334,44 @@ mod tests {
                <|>foo();
             }
             ",
-            "foo FN_DEF FileId(1) [51; 64) [51; 64)",
-            "define_fn!();|define_fn!();",
+            "foo FN_DEF FileId(1) [51; 64)",
+            "define_fn!();",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_macro_generated_struct() {
+        let (analysis, pos) = analysis_and_position(
+            "
+            //- /lib.rs
+            macro_rules! m {
+                ($i:ident) => { struct $i; }
+            }
+
+            m!(Foo);
+
+            fn bar(x: Fo<|>o) {}
+            ",
+        );
+        let navs = analysis.goto_definition(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        navs[0].assert_match("Foo STRUCT_DEF FileId(1) [52; 60) [55; 58)");
+        assert_eq!(navs[0].description(), Some("struct Foo"));
+    }
+
+    #[test]
+    fn goto_def_for_builtin_type() {
+        let (analysis, pos) = analysis_and_position(
+            "
+            //- /lib.rs
+            fn foo(x: u3<|>2) {}
+            ",
         );
+        let navs = analysis.goto_definition(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        assert_eq!(navs[0].file_id(), None);
+        navs[0].assert_match("u32 STRUCT_DEF builtin [0; 0)");
+        assert_eq!(navs[0].docs(), Some("The 32-bit unsigned integer type."));
     }
 
     #[test]
@@ -793,7 +865,7 @@ mod tests {
     #[test]
     fn goto_def_for_field_init_shorthand() {
         covers!(goto_def_for_field_init_shorthand);
-        check_goto(
+        check_goto_all(
             "
             //- /lib.rs
             struct Foo { x: i32 }
@@ -802,8 +874,93 @@ mod tests {
                 Foo { x<|> };
             }
             ",
+            &["x RECORD_FIELD_DEF FileId(1) [13; 19) [13; 14)", "x BIND_PAT FileId(1) [42; 43)"],
+        )
+    }
+
+    #[test]
+    fn goto_def_for_record_field_with_expr() {
+        check_goto(
+            "
+            //- /lib.rs
+            struct Foo { x: i32 }
+            fn main() {
+                let x = 92;
+                Foo { x<|>: x };
+            }
+            ",
             "x RECORD_FIELD_DEF FileId(1) [13; 19) [13; 14)",
             "x: i32|x",
         )
     }
+
+    #[test]
+    fn goto_def_for_record_pat_shorthand() {
+        check_goto_all(
+            "
+            //- /lib.rs
+            struct Foo { x: i32 }
+            fn main(foo: Foo) {
+                let Foo { x<|> } = foo;
+            }
+            ",
+            &["x RECORD_FIELD_DEF FileId(1) [13; 19) [13; 14)", "x BIND_PAT FileId(1) [56; 57)"],
+        )
+    }
+
+    #[test]
+    fn goto_def_for_format_string_capture() {
+        check_goto(
+            "
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                format!(\"{x<|>} and {y}\", y = 1);
+            }
+            ",
+            "x BIND_PAT FileId(1) [20; 21)",
+            "x",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_format_string_named_arg() {
+        check_goto(
+            "
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                format!(\"{x} and {y<|>}\", y = 1);
+            }
+            ",
+            "y IDENT FileId(1) [55; 56)",
+            "y",
+        );
+    }
+
+    #[test]
+    fn goto_def_range_in_format_string() {
+        let (analysis, pos) = analysis_and_position(
+            "
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                println!(\"{{x}} {x<|>:?}\");
+            }
+            ",
+        );
+        let range = analysis.goto_definition(pos).unwrap().unwrap().range;
+        assert_eq!(format!("{:?}", range), "[49; 50)");
+
+        let (analysis, pos) = analysis_and_position(
+            "
+            //- /lib.rs
+            fn main() {
+                let x = 92;
+                println!(\"{{x<|>}} {0}\", x);
+            }
+            ",
+        );
+        assert!(analysis.goto_definition(pos).unwrap().is_none());
+    }
 }
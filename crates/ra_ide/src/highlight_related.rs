@@ -0,0 +1,339 @@
+//! Highlights the syntax related to the keyword under the cursor: the exit
+//! points of a function, the `.await` points of an async function or block,
+//! and the `break`s and `continue`s of a loop.
+
+use ra_syntax::{
+    ast::{self, AstNode},
+    SourceFile, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextUnit, T,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelatedHighlightKind {
+    /// A `return`, a `?` or the tail expression of a function, closure or
+    /// async block.
+    ExitPoint,
+    /// An `.await` in an async function, closure or block.
+    AwaitPoint,
+    /// A `break` or `continue` of a loop.
+    LoopControl,
+}
+
+#[derive(Debug)]
+pub struct RelatedHighlight {
+    pub range: TextRange,
+    pub kind: RelatedHighlightKind,
+}
+
+pub(crate) fn highlight_related(file: &SourceFile, offset: TextUnit) -> Vec<RelatedHighlight> {
+    let token = match file.syntax().token_at_offset(offset).find(|it| is_related_keyword(it.kind()))
+    {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let highlights = match token.kind() {
+        T![fn] | T![return] | T![?] => exit_points(&token),
+        T![async] | T![await] => await_points(&token),
+        _ => loop_controls(&token),
+    };
+    let mut highlights = highlights.unwrap_or_default();
+    highlights.sort_by_key(|it| it.range.start());
+    highlights
+}
+
+fn is_related_keyword(kind: SyntaxKind) -> bool {
+    match kind {
+        T![fn]
+        | T![return]
+        | T![?]
+        | T![async]
+        | T![await]
+        | T![loop]
+        | T![while]
+        | T![for]
+        | T![break]
+        | T![continue] => true,
+        _ => false,
+    }
+}
+
+fn exit_points(token: &SyntaxToken) -> Option<Vec<RelatedHighlight>> {
+    let owner = match token.kind() {
+        T![fn] => token.parent(),
+        // `?Sized`
+        T![?] if token.parent().kind() != SyntaxKind::TRY_EXPR => return None,
+        _ => token.parent().ancestors().find(is_body_owner)?,
+    };
+    let body = if let Some(fn_def) = ast::FnDef::cast(owner.clone()) {
+        ast::Expr::from(fn_def.body()?)
+    } else if let Some(lambda) = ast::LambdaExpr::cast(owner.clone()) {
+        lambda.body()?
+    } else {
+        ast::Expr::cast(owner.clone())?
+    };
+
+    let mut res = Vec::new();
+    let mut push =
+        |range| res.push(RelatedHighlight { range, kind: RelatedHighlightKind::ExitPoint });
+    let keyword = child_token(&owner, |kind| kind == T![fn])
+        .or_else(|| child_token(&owner, |kind| kind == T![async]));
+    if let Some(keyword) = keyword {
+        push(keyword.text_range());
+    }
+    walk_body(&owner, &mut |node| {
+        if let Some(return_expr) = ast::ReturnExpr::cast(node.clone()) {
+            push(return_expr.syntax().text_range());
+        } else if let Some(question) = ast::TryExpr::cast(node.clone())
+            .and_then(|it| child_token(it.syntax(), |kind| kind == T![?]))
+        {
+            push(question.text_range());
+        }
+    });
+    let tail = match &body {
+        ast::Expr::BlockExpr(block) => block.block().and_then(|it| it.expr()),
+        _ => Some(body),
+    };
+    match tail {
+        Some(ast::Expr::ReturnExpr(_)) | None => (),
+        Some(tail) => push(tail.syntax().text_range()),
+    }
+    Some(res)
+}
+
+fn await_points(token: &SyntaxToken) -> Option<Vec<RelatedHighlight>> {
+    let owner = match token.kind() {
+        T![async] => token.parent(),
+        _ => token.parent().ancestors().find(is_body_owner)?,
+    };
+    let async_token = child_token(&owner, |kind| kind == T![async])?;
+
+    let mut res = vec![RelatedHighlight {
+        range: async_token.text_range(),
+        kind: RelatedHighlightKind::AwaitPoint,
+    }];
+    walk_body(&owner, &mut |node| {
+        if let Some(await_token) = ast::AwaitExpr::cast(node.clone())
+            .and_then(|it| child_token(it.syntax(), |kind| kind == T![await]))
+        {
+            res.push(RelatedHighlight {
+                range: await_token.text_range(),
+                kind: RelatedHighlightKind::AwaitPoint,
+            });
+        }
+    });
+    Some(res)
+}
+
+fn loop_controls(token: &SyntaxToken) -> Option<Vec<RelatedHighlight>> {
+    let loop_ = match token.kind() {
+        T![break] | T![continue] => loop_target(&token.parent())?,
+        _ => token.parent(),
+    };
+    if !is_loop(&loop_) {
+        return None;
+    }
+    let keyword =
+        child_token(&loop_, |kind| kind == T![loop] || kind == T![while] || kind == T![for])?;
+
+    let mut res = vec![RelatedHighlight {
+        range: keyword.text_range(),
+        kind: RelatedHighlightKind::LoopControl,
+    }];
+    walk_body(&loop_, &mut |node| {
+        let is_control =
+            node.kind() == SyntaxKind::BREAK_EXPR || node.kind() == SyntaxKind::CONTINUE_EXPR;
+        if is_control && loop_target(node).as_ref() == Some(&loop_) {
+            res.push(RelatedHighlight {
+                range: node.text_range(),
+                kind: RelatedHighlightKind::LoopControl,
+            });
+        }
+    });
+    Some(res)
+}
+
+/// Finds the loop a `break` or `continue` refers to: the innermost one, or the
+/// one with the matching label.
+fn loop_target(control: &SyntaxNode) -> Option<SyntaxNode> {
+    let label = child_token(control, |kind| kind == SyntaxKind::LIFETIME);
+    for node in control.ancestors().skip(1) {
+        if is_body_owner(&node) {
+            return None;
+        }
+        if !is_loop(&node) {
+            continue;
+        }
+        match &label {
+            None => return Some(node),
+            Some(label) => {
+                let loop_label = node
+                    .children()
+                    .find(|it| it.kind() == SyntaxKind::LABEL)
+                    .and_then(|it| child_token(&it, |kind| kind == SyntaxKind::LIFETIME));
+                if loop_label.map_or(false, |it| it.text() == label.text()) {
+                    return Some(node);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Calls `cb` for all nodes in `node`, except for those inside of nested
+/// functions, closures and async blocks, as `return`s and `.await`s in them
+/// don't affect the outer body.
+fn walk_body(node: &SyntaxNode, cb: &mut impl FnMut(&SyntaxNode)) {
+    for child in node.children() {
+        if is_body_owner(&child) || ast::ModuleItem::can_cast(child.kind()) {
+            continue;
+        }
+        cb(&child);
+        walk_body(&child, cb);
+    }
+}
+
+fn is_body_owner(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        SyntaxKind::FN_DEF | SyntaxKind::LAMBDA_EXPR => true,
+        SyntaxKind::BLOCK_EXPR => child_token(node, |kind| kind == T![async]).is_some(),
+        _ => false,
+    }
+}
+
+fn is_loop(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        SyntaxKind::LOOP_EXPR | SyntaxKind::WHILE_EXPR | SyntaxKind::FOR_EXPR => true,
+        _ => false,
+    }
+}
+
+fn child_token(node: &SyntaxNode, pred: impl Fn(SyntaxKind) -> bool) -> Option<SyntaxToken> {
+    node.children_with_tokens().filter_map(|it| it.into_token()).find(|it| pred(it.kind()))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::extract_offset;
+
+    use super::*;
+
+    fn check(ra_fixture: &str, expected: &[&str]) {
+        let (offset, text) = extract_offset(ra_fixture);
+        let file = SourceFile::parse(&text).tree();
+        let actual: Vec<&str> = highlight_related(&file, offset)
+            .iter()
+            .map(|it| &text[it.range.start().to_usize()..it.range.end().to_usize()])
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn highlights_exit_points() {
+        check(
+            r#"
+fn foo(x: Option<i32>) -> i32 {
+    if x.is_none() {
+        return 0;
+    }
+    let y = bar()?;
+    <|>return y;
+}
+"#,
+            &["fn", "return 0", "?", "return y"],
+        );
+    }
+
+    #[test]
+    fn highlights_tail_expression_from_fn_keyword() {
+        check(
+            r#"
+<|>fn foo(x: bool) -> i32 {
+    if x {
+        return 1;
+    }
+    2
+}
+"#,
+            &["fn", "return 1", "2"],
+        );
+    }
+
+    #[test]
+    fn exit_points_skip_nested_closures() {
+        check(
+            r#"
+fn foo() -> i32 {
+    let f = |x: i32| {
+        if x > 0 {
+            return x;
+        }
+        0
+    };
+    <|>return f(1);
+}
+"#,
+            &["fn", "return f(1)"],
+        );
+        check(
+            r#"
+fn foo() -> i32 {
+    let f = |x: i32| {
+        if x > 0 {
+            <|>return x;
+        }
+        0
+    };
+    return f(1);
+}
+"#,
+            &["return x", "0"],
+        );
+    }
+
+    #[test]
+    fn highlights_await_points() {
+        check(
+            r#"
+async fn foo() {
+    bar().await;
+    let f = async { baz().await };
+    qux().<|>await
+}
+"#,
+            &["async", "await", "await"],
+        );
+    }
+
+    #[test]
+    fn highlights_controls_of_labeled_nested_loops() {
+        check(
+            r#"
+fn foo() {
+    'outer: <|>loop {
+        while true {
+            break;
+            continue 'outer;
+            break 'outer;
+        }
+        continue;
+    }
+}
+"#,
+            &["loop", "continue 'outer", "break 'outer", "continue"],
+        );
+        check(
+            r#"
+fn foo() {
+    'outer: loop {
+        while true {
+            <|>break;
+            continue 'outer;
+            break 'outer;
+        }
+        continue;
+    }
+}
+"#,
+            &["while", "break"],
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! FIXME: write short doc here
 
-use hir::{Adt, HasSource, HirDisplay, Semantics};
+use hir::{Adt, AsAssocItem, AssocItemContainer, HasSource, HirDisplay, ImplBlock, Semantics};
 use ra_ide_db::{
     defs::{classify_name, NameDefinition},
     RootDatabase,
@@ -8,13 +8,16 @@ use ra_ide_db::{
 use ra_syntax::{
     algo::find_covering_element,
     ast::{self, DocCommentsOwner},
-    match_ast, AstNode,
+    match_ast, AstNode, AstToken,
     SyntaxKind::*,
-    SyntaxToken, TokenAtOffset,
+    SyntaxNode, SyntaxToken, TokenAtOffset,
 };
 
 use crate::{
-    display::{macro_label, rust_code_markup, rust_code_markup_with_doc, ShortLabel},
+    display::{
+        builtin_docs, discriminant_label, macro_label, rust_code_markup, rust_code_markup_with_doc,
+        ShortLabel,
+    },
     references::classify_name_ref,
     FilePosition, FileRange, RangeInfo,
 };
@@ -93,36 +96,66 @@ fn hover_text(docs: Option<String>, desc: Option<String>) -> Option<String> {
     }
 }
 
-fn hover_text_from_name_kind(db: &RootDatabase, def: NameDefinition) -> Option<String> {
+fn hover_text_from_name_kind(
+    db: &RootDatabase,
+    def: NameDefinition,
+    impl_header: Option<String>,
+) -> Option<String> {
+    let module = def.module(db);
     return match def {
         NameDefinition::Macro(it) => {
             let src = it.source(db);
-            hover_text(src.value.doc_comment_text(), Some(macro_label(&src.value)))
+            let docs = src.value.doc_comment_text().map(|it| resolve_doc_links(db, module, &it));
+            hover_text(docs, Some(macro_label(&src.value)))
         }
         NameDefinition::StructField(it) => {
             let src = it.source(db);
             match src.value {
-                hir::FieldSource::Named(it) => hover_text(it.doc_comment_text(), it.short_label()),
+                hir::FieldSource::Named(it) => {
+                    let docs = it.doc_comment_text().map(|it| resolve_doc_links(db, module, &it));
+                    hover_text(docs, it.short_label())
+                }
                 _ => None,
             }
         }
         NameDefinition::ModuleDef(it) => match it {
             hir::ModuleDef::Module(it) => match it.definition_source(db).value {
                 hir::ModuleSource::Module(it) => {
-                    hover_text(it.doc_comment_text(), it.short_label())
+                    let docs = it.doc_comment_text().map(|it| resolve_doc_links(db, module, &it));
+                    hover_text(docs, it.short_label())
                 }
                 _ => None,
             },
-            hir::ModuleDef::Function(it) => from_def_source(db, it),
-            hir::ModuleDef::Adt(Adt::Struct(it)) => from_def_source(db, it),
-            hir::ModuleDef::Adt(Adt::Union(it)) => from_def_source(db, it),
-            hir::ModuleDef::Adt(Adt::Enum(it)) => from_def_source(db, it),
-            hir::ModuleDef::EnumVariant(it) => from_def_source(db, it),
-            hir::ModuleDef::Const(it) => from_def_source(db, it),
-            hir::ModuleDef::Static(it) => from_def_source(db, it),
-            hir::ModuleDef::Trait(it) => from_def_source(db, it),
-            hir::ModuleDef::TypeAlias(it) => from_def_source(db, it),
-            hir::ModuleDef::BuiltinType(it) => Some(it.to_string()),
+            hir::ModuleDef::Function(it) => {
+                let src = it.source(db);
+                let docs =
+                    src.value.doc_comment_text().map(|it| resolve_doc_links(db, module, &it));
+                let desc = src.value.short_label().map(|label| match impl_header {
+                    Some(header) => format!("{}\n{}", header, label),
+                    None => label,
+                });
+                hover_text(docs, desc)
+            }
+            hir::ModuleDef::Adt(Adt::Struct(it)) => from_def_source(db, it, module),
+            hir::ModuleDef::Adt(Adt::Union(it)) => from_def_source(db, it, module),
+            hir::ModuleDef::Adt(Adt::Enum(it)) => from_def_source(db, it, module),
+            hir::ModuleDef::EnumVariant(it) => {
+                let src = it.source(db);
+                let docs =
+                    src.value.doc_comment_text().map(|it| resolve_doc_links(db, module, &it));
+                let desc = src.value.short_label().map(|label| match discriminant_label(db, it) {
+                    Some(discriminant) => format!("{} {}", label, discriminant),
+                    None => label,
+                });
+                hover_text(docs, desc)
+            }
+            hir::ModuleDef::Const(it) => from_def_source(db, it, module),
+            hir::ModuleDef::Static(it) => from_def_source(db, it, module),
+            hir::ModuleDef::Trait(it) => from_def_source(db, it, module),
+            hir::ModuleDef::TypeAlias(it) => from_def_source(db, it, module),
+            hir::ModuleDef::BuiltinType(it) => {
+                hover_text(Some(builtin_docs(it)), Some(it.to_string()))
+            }
         },
         NameDefinition::Local(it) => {
             Some(rust_code_markup(it.ty(db).display_truncated(db, None).to_string()))
@@ -133,14 +166,91 @@ fn hover_text_from_name_kind(db: &RootDatabase, def: NameDefinition) -> Option<S
         }
     };
 
-    fn from_def_source<A, D>(db: &RootDatabase, def: D) -> Option<String>
+    fn from_def_source<A, D>(
+        db: &RootDatabase,
+        def: D,
+        module: Option<hir::Module>,
+    ) -> Option<String>
     where
         D: HasSource<Ast = A>,
         A: ast::DocCommentsOwner + ast::NameOwner + ShortLabel,
     {
         let src = def.source(db);
-        hover_text(src.value.doc_comment_text(), src.value.short_label())
+        let docs = src.value.doc_comment_text().map(|it| resolve_doc_links(db, module, &it));
+        hover_text(docs, src.value.short_label())
+    }
+}
+
+/// For methods and associated functions, describes the impl they come from,
+/// like `impl Foo` or `impl Iterator for Foo`.
+///
+/// Methods resolved through a trait are looked up in the impls of the trait
+/// for the (autoderefed) receiver type.
+fn impl_header(
+    sema: &Semantics<RootDatabase>,
+    func: hir::Function,
+    name_ref: &SyntaxNode,
+) -> Option<String> {
+    let db = sema.db;
+    let trait_ = match func.as_assoc_item(db)?.container(db) {
+        AssocItemContainer::ImplBlock(imp) => return Some(impl_block_header(db, imp)),
+        AssocItemContainer::Trait(it) => it,
+    };
+    let imp = name_ref.parent().and_then(ast::MethodCallExpr::cast).and_then(|call| {
+        let receiver_ty = sema.type_of_expr(&call.expr()?)?;
+        let mut krates = vec![trait_.module(db).krate()];
+        krates.extend(sema.scope(name_ref).module().map(|it| it.krate()));
+        let impls: Vec<ImplBlock> =
+            krates.into_iter().flat_map(|krate| ImplBlock::for_trait(db, krate, trait_)).collect();
+        receiver_ty.autoderef(db).find_map(|ty| {
+            impls.iter().copied().find(|imp| imp.target_ty(db).is_equal_for_find_impls(&ty))
+        })
+    });
+    match imp {
+        Some(imp) => Some(impl_block_header(db, imp)),
+        None => Some(format!("trait {}", trait_.name(db))),
+    }
+}
+
+fn impl_block_header(db: &RootDatabase, imp: ImplBlock) -> String {
+    let target_ty = imp.target_ty(db);
+    match imp.target_trait_def(db) {
+        Some(trait_) => format!("impl {} for {}", trait_.name(db), target_ty.display(db)),
+        None => format!("impl {}", target_ty.display(db)),
+    }
+}
+
+/// Replaces intra-doc links like [`Foo`] which resolve in the scope of
+/// `module` with plain code spans, so that they don't show up as dead links.
+fn resolve_doc_links(db: &RootDatabase, module: Option<hir::Module>, docs: &str) -> String {
+    let scope = match module {
+        Some(it) => it.scope(db, None),
+        None => return docs.to_string(),
+    };
+    let mut res = String::with_capacity(docs.len());
+    let mut rest = docs;
+    while let Some(start) = rest.find("[`") {
+        res.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest[2..].find("`]") {
+            Some(it) => it + 2,
+            None => break,
+        };
+        let name = &rest[2..end];
+        let link_end = end + 2;
+        let is_explicit_link =
+            rest[link_end..].starts_with('(') || rest[link_end..].starts_with('[');
+        if !is_explicit_link && scope.iter().any(|(it, _)| it.to_string() == name) {
+            res.push('`');
+            res.push_str(name);
+            res.push('`');
+        } else {
+            res.push_str(&rest[..link_end]);
+        }
+        rest = &rest[link_end..];
     }
+    res.push_str(rest);
+    res
 }
 
 pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeInfo<HoverResult>> {
@@ -154,7 +264,8 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
     if let Some((node, name_kind)) = match_ast! {
         match (token.parent()) {
             ast::NameRef(name_ref) => {
-                classify_name_ref(&sema, &name_ref).map(|d| (name_ref.syntax().clone(), d))
+                classify_name_ref(&sema, &name_ref)
+                    .map(|d| (name_ref.syntax().clone(), d.definition()))
             },
             ast::Name(name) => {
                 classify_name(&sema, &name).map(|d| (name.syntax().clone(), d))
@@ -163,7 +274,13 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
         }
     } {
         let range = sema.original_range(&node).range;
-        res.extend(hover_text_from_name_kind(db, name_kind));
+        let impl_header = match &name_kind {
+            NameDefinition::ModuleDef(hir::ModuleDef::Function(it)) => {
+                impl_header(&sema, *it, &node)
+            }
+            _ => None,
+        };
+        res.extend(hover_text_from_name_kind(db, name_kind, impl_header));
 
         if !res.is_empty() {
             return Some(RangeInfo::new(range, res));
@@ -180,6 +297,9 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
     // See also `test_hover_through_literal_string_in_macro`
     let frange = sema.original_range(&node);
     res.extend(type_of(db, frange).map(rust_code_markup));
+    if let Some(number) = ast::IntNumber::cast(token) {
+        res.extend(int_number_description(&number));
+    }
     if res.is_empty() {
         return None;
     }
@@ -188,6 +308,20 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
     Some(RangeInfo::new(range, res))
 }
 
+/// Describes the value of an integer literal like `0xFF_FF` in several bases.
+fn int_number_description(number: &ast::IntNumber) -> Option<String> {
+    let value = number.value()?;
+    let bits = 128 - value.leading_zeros();
+    Some(format!(
+        "`{}` = `{:#x}` = `{:#b}` ({} bit{})",
+        value,
+        value,
+        value,
+        bits,
+        if bits == 1 { "" } else { "s" }
+    ))
+}
+
 fn pick_best(tokens: TokenAtOffset<SyntaxToken>) -> Option<SyntaxToken> {
     return tokens.max_by_key(priority);
     fn priority(n: &SyntaxToken) -> usize {
@@ -311,7 +445,7 @@ mod tests {
                 let foo_test = fo<|>o();
             }
         "#,
-            &["{unknown}"],
+            &["_"],
         );
     }
 
@@ -486,6 +620,49 @@ The Some variant
         );
     }
 
+    #[test]
+    fn hover_enum_variant_discriminant() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            enum Flags {
+                A = 1,
+                B = 2 << 3,
+                C<|>,
+                D = -(1 | 4),
+            }
+        "#,
+            &["C = 17"],
+        );
+        check_hover_result(
+            r#"
+            //- /main.rs
+            enum Flags {
+                A = 1,
+                B = 2 << 3,
+                C,
+                D<|> = -(1 | 4),
+            }
+        "#,
+            &["D = -5"],
+        );
+    }
+
+    #[test]
+    fn hover_enum_variant_discriminant_overflowing_repr() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            #[repr(u8)]
+            enum Byte {
+                Max = 255,
+                Over<|>,
+            }
+        "#,
+            &["Over = ?"],
+        );
+    }
+
     #[test]
     fn hover_for_local_variable() {
         let (analysis, position) = single_file_with_position("fn func(foo: i32) { fo<|>o; }");
@@ -588,7 +765,7 @@ fn func(foo: i32) { if true { <|>foo; }; }
             ",
         );
         let hover = analysis.hover(position).unwrap().unwrap();
-        assert_eq!(trim_markup_opt(hover.info.first()), Some("fn new() -> Thing"));
+        assert_eq!(trim_markup_opt(hover.info.first()), Some("impl Thing\nfn new() -> Thing"));
         assert_eq!(hover.info.is_exact(), true);
     }
 
@@ -801,6 +978,35 @@ fn func(foo: i32) { if true { <|>foo; }; }
         );
     }
 
+    #[test]
+    fn test_hover_int_literal_value() {
+        let hover_on = check_hover_result(
+            r#"
+            //- /main.rs
+            fn main() {
+                let x = 0xFF<|>_FFu16;
+            }
+            "#,
+            &["`65535` = `0xffff` = `0b1111111111111111` (16 bits)", "u16"],
+        );
+        assert_eq!(hover_on, "0xFF_FFu16");
+    }
+
+    #[test]
+    fn test_hover_int_literal_max_u128() {
+        let max = std::u128::MAX;
+        let description = format!("`{}` = `{:#x}` = `{:#b}` (128 bits)", max, max, max);
+        check_hover_result(
+            r#"
+            //- /main.rs
+            fn main() {
+                let x = 340282366920938463463374607431768211455<|>u128;
+            }
+            "#,
+            &[&description, "u128"],
+        );
+    }
+
     #[test]
     fn test_hover_non_ascii_space_doc() {
         check_hover_result(
@@ -817,4 +1023,78 @@ fn func(foo: i32) { if true { <|>foo; }; }
             &["fn foo()\n```\n\n<- `\u{3000}` here"],
         );
     }
+
+    #[test]
+    fn test_hover_trait_method_through_deref() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            #[lang = "deref"]
+            trait Deref {
+                type Target;
+                fn deref(&self) -> &Self::Target;
+            }
+
+            struct A;
+            struct B;
+            struct C;
+
+            impl Deref for A {
+                type Target = B;
+                fn deref(&self) -> &B { &B }
+            }
+
+            impl Deref for B {
+                type Target = C;
+                fn deref(&self) -> &C { &C }
+            }
+
+            trait Greet {
+                fn greet(&self) -> u32;
+            }
+
+            impl Greet for C {
+                fn greet(&self) -> u32 { 0 }
+            }
+
+            fn main() {
+                A.gre<|>et();
+            }
+            "#,
+            &["impl Greet for C\nfn greet(&self) -> u32"],
+        );
+    }
+
+    #[test]
+    fn test_hover_resolves_intra_doc_links() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            struct Other;
+
+            struct Foo;
+
+            impl Foo {
+                /// Converts into an [`Other`], unlike [`Missing`].
+                fn to_other(&self) -> Other { Other }
+            }
+
+            fn main() {
+                Foo.to_ot<|>her();
+            }
+            "#,
+            &["impl Foo\nfn to_other(&self) -> Other\n```\n\nConverts into an `Other`, unlike [`Missing`]."],
+        );
+    }
+
+    #[test]
+    fn test_hover_builtin_type_shows_docs() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            fn foo() -> u3<|>2 { 0 }
+            "#,
+            &["u32\n```\n\nThe 32-bit unsigned integer type."],
+        );
+    }
 }
@@ -1,4 +1,5 @@
-//! FIXME: write short doc here
+//! Finds the impl blocks of a type or a trait, in its own crate and in all
+//! crates depending on it.
 
 use hir::{Crate, ImplBlock, Semantics};
 use ra_ide_db::RootDatabase;
@@ -42,11 +43,13 @@ fn impls_for_def(
         ast::NominalDef::UnionDef(def) => sema.to_def(def)?.ty(sema.db),
     };
 
-    let impls = ImplBlock::all_in_crate(sema.db, krate);
+    let impls = krate
+        .transitive_reverse_dependencies(sema.db)
+        .into_iter()
+        .flat_map(|krate| ImplBlock::all_in_crate(sema.db, krate));
 
     Some(
         impls
-            .into_iter()
             .filter(|impl_block| ty.is_equal_for_find_impls(&impl_block.target_ty(sema.db)))
             .map(|imp| imp.to_nav(sema.db))
             .collect(),
@@ -60,9 +63,12 @@ fn impls_for_trait(
 ) -> Option<Vec<NavigationTarget>> {
     let tr = sema.to_def(node)?;
 
-    let impls = ImplBlock::for_trait(sema.db, krate, tr);
+    let impls = krate
+        .transitive_reverse_dependencies(sema.db)
+        .into_iter()
+        .flat_map(|krate| ImplBlock::for_trait(sema.db, krate, tr));
 
-    Some(impls.into_iter().map(|imp| imp.to_nav(sema.db)).collect())
+    Some(impls.map(|imp| imp.to_nav(sema.db)).collect())
 }
 
 #[cfg(test)]
@@ -187,6 +193,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn goto_implementation_for_trait_in_dependency() {
+        check_goto(
+            "
+            //- /main.rs
+            struct Foo;
+            impl dep::T for Foo {}
+            //- /dep/lib.rs
+            pub trait T<|> {}
+            pub struct Bar;
+            impl T for Bar {}
+            ",
+            &["impl IMPL_BLOCK FileId(1) [12; 34)", "impl IMPL_BLOCK FileId(2) [31; 48)"],
+        );
+    }
+
+    #[test]
+    fn goto_implementation_for_type_in_dependency() {
+        check_goto(
+            "
+            //- /main.rs
+            trait T {}
+            impl T for dep::Bar {}
+            //- /dep/lib.rs
+            pub struct Bar<|>;
+            impl Bar {}
+            ",
+            &["impl IMPL_BLOCK FileId(1) [11; 33)", "impl IMPL_BLOCK FileId(2) [16; 27)"],
+        );
+    }
+
     #[test]
     fn goto_implementation_to_builtin_derive() {
         check_goto(
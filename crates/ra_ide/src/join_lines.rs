@@ -68,6 +68,11 @@ fn remove_newline(edit: &mut TextEditBuilder, token: &SyntaxToken, offset: TextU
         edit.delete(TextRange::from_to(prev.text_range().start(), token.text_range().end()));
         return;
     }
+    if is_empty_delimiters(prev.kind(), next.kind()) {
+        // Removes: newline (incl. surrounding whitespace) between `{` and `}`
+        edit.delete(token.text_range());
+        return;
+    }
     if prev.kind() == T![,] && next.kind() == T!['}'] {
         // Removes: comma, newline (incl. surrounding whitespace)
         let space = if let Some(left) = prev.prev_sibling_or_token() {
@@ -162,6 +167,13 @@ fn is_trailing_comma(left: SyntaxKind, right: SyntaxKind) -> bool {
     }
 }
 
+fn is_empty_delimiters(left: SyntaxKind, right: SyntaxKind) -> bool {
+    match (left, right) {
+        (T!['{'], T!['}']) | (T!['('], T![')']) | (T!['['], T![']']) => true,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::test_utils::{assert_eq_text, check_action, extract_range};
@@ -211,6 +223,66 @@ pub fn reparse(&self, edit: &AtomTextEdit) -> File {
         );
     }
 
+    #[test]
+    fn test_join_lines_empty_fn_body() {
+        check_join_lines(
+            r"
+fn foo() {<|>
+}
+",
+            r"
+fn foo() {<|>}
+",
+        );
+    }
+
+    #[test]
+    fn test_join_lines_empty_struct_body() {
+        check_join_lines(
+            r"
+struct S {<|>
+}
+",
+            r"
+struct S {<|>}
+",
+        );
+    }
+
+    #[test]
+    fn test_join_lines_empty_match_block() {
+        check_join_lines(
+            r"
+fn foo() {
+    match x {<|>
+    }
+}
+",
+            r"
+fn foo() {
+    match x {<|>}
+}
+",
+        );
+    }
+
+    #[test]
+    fn test_join_lines_empty_parens() {
+        check_join_lines(
+            r"
+fn foo() {
+    foo(<|>
+    )
+}
+",
+            r"
+fn foo() {
+    foo(<|>)
+}
+",
+        );
+    }
+
     #[test]
     fn test_join_lines_block() {
         check_join_lines(
@@ -16,9 +16,12 @@ mod source_change;
 mod status;
 mod completion;
 mod runnables;
+mod annotations;
+mod related_tests;
 mod goto_definition;
 mod goto_type_definition;
 mod extend_selection;
+mod selection_range;
 mod hover;
 mod call_hierarchy;
 mod call_info;
@@ -33,10 +36,17 @@ mod folding_ranges;
 mod join_lines;
 mod typing;
 mod matching_brace;
+mod move_item;
+mod highlight_related;
 mod display;
 mod inlay_hints;
 mod expand_macro;
+mod external_docs;
+mod format_args;
 mod ssr;
+mod view_hir;
+mod view_crate_graph;
+mod type_of_range;
 
 #[cfg(test)]
 mod marks;
@@ -48,7 +58,7 @@ use std::sync::Arc;
 use ra_cfg::CfgOptions;
 use ra_db::{
     salsa::{self, ParallelDatabase},
-    CheckCanceled, Env, FileLoader, SourceDatabase,
+    CheckCanceled, Env, FileLoader, RelativePath, SourceDatabase,
 };
 use ra_ide_db::{
     symbol_index::{self, FileSymbol},
@@ -59,20 +69,25 @@ use ra_syntax::{SourceFile, TextRange, TextUnit};
 use crate::display::ToNav;
 
 pub use crate::{
-    assists::{Assist, AssistId},
+    annotations::{Annotation, AnnotationKind},
+    assists::{Assist, AssistId, UnresolvedAssist},
     call_hierarchy::CallItem,
     completion::{CompletionItem, CompletionItemKind, InsertTextFormat},
-    diagnostics::Severity,
+    diagnostics::{DiagnosticTag, DiagnosticsDependencies, Severity},
     display::{file_structure, FunctionSignature, NavigationTarget, StructureNode},
     expand_macro::ExpandedMacro,
+    external_docs::ExternalDocs,
     folding_ranges::{Fold, FoldKind},
+    highlight_related::{RelatedHighlight, RelatedHighlightKind},
     hover::HoverResult,
     inlay_hints::{InlayHint, InlayKind},
+    move_item::MoveDirection,
     references::{
-        Declaration, Reference, ReferenceAccess, ReferenceKind, ReferenceSearchResult, SearchScope,
+        Declaration, Reference, ReferenceAccess, ReferenceKind, ReferenceSearchResult, RenameError,
+        SearchScope,
     },
     runnables::{Runnable, RunnableKind, TestId},
-    source_change::{FileSystemEdit, SourceChange, SourceFileEdit},
+    source_change::{FileSystemEdit, SourceChange, SourceFileEdit, SourceFileEditConflict},
     ssr::SsrError,
     syntax_highlighting::{
         Highlight, HighlightModifier, HighlightModifiers, HighlightTag, HighlightedRange,
@@ -86,7 +101,7 @@ pub use ra_db::{
 pub use ra_ide_db::{
     change::{AnalysisChange, LibraryData},
     feature_flags::FeatureFlags,
-    line_index::{LineCol, LineIndex},
+    line_index::{LineCol, LineColUtf8, LineIndex},
     line_index_utils::translate_offset_with_edit,
     symbol_index::Query,
     RootDatabase,
@@ -97,9 +112,12 @@ pub type Cancelable<T> = Result<T, Canceled>;
 #[derive(Debug)]
 pub struct Diagnostic {
     pub message: String,
+    /// A stable identifier of the kind of diagnostic, like `unresolved-module`.
+    pub code: &'static str,
     pub range: TextRange,
     pub fix: Option<SourceChange>,
     pub severity: Severity,
+    pub tags: Vec<DiagnosticTag>,
 }
 
 /// Info associated with a text range.
@@ -121,6 +139,9 @@ impl<T> RangeInfo<T> {
 pub struct CallInfo {
     pub signature: FunctionSignature,
     pub active_parameter: Option<usize>,
+    /// Parameters which are already given, like the fields which are set in a
+    /// record literal.
+    pub provided_parameters: Vec<usize>,
 }
 
 /// `AnalysisHost` stores the current state of the world.
@@ -149,6 +170,11 @@ impl AnalysisHost {
         &self.db.feature_flags
     }
 
+    /// Replaces the feature flags. Existing snapshots keep the old ones.
+    pub fn update_feature_flags(&mut self, feature_flags: FeatureFlags) {
+        self.db.feature_flags = Arc::new(feature_flags);
+    }
+
     /// Applies changes to the current state of the world. If there are
     /// outstanding snapshots, they will be canceled.
     pub fn apply_change(&mut self, change: AnalysisChange) {
@@ -228,11 +254,23 @@ impl Analysis {
         self.with_db(|db| status::status(&*db))
     }
 
+    /// Renders the crate graph in the DOT format of graphviz. Unless `full`
+    /// is set, only the workspace crates and their direct dependencies are
+    /// shown.
+    pub fn view_crate_graph(&self, full: bool) -> Cancelable<String> {
+        self.with_db(|db| view_crate_graph::view_crate_graph(db, full))
+    }
+
     /// Gets the text of the source file.
     pub fn file_text(&self, file_id: FileId) -> Cancelable<Arc<String>> {
         self.with_db(|db| db.file_text(file_id))
     }
 
+    /// Gets the source root the file belongs to.
+    pub fn file_source_root(&self, file_id: FileId) -> Cancelable<SourceRootId> {
+        self.with_db(|db| ra_db::SourceDatabaseExt::file_source_root(db, file_id))
+    }
+
     /// Gets the syntax tree of the file.
     pub fn parse(&self, file_id: FileId) -> Cancelable<SourceFile> {
         self.with_db(|db| db.parse(file_id).tree())
@@ -249,6 +287,12 @@ impl Analysis {
         self.with_db(|db| extend_selection::extend_selection(db, frange))
     }
 
+    /// Returns the ranges a selection at the position can be extended to,
+    /// from the innermost to the whole file.
+    pub fn selection_ranges(&self, position: FilePosition) -> Cancelable<Vec<TextRange>> {
+        self.with_db(|db| selection_range::selection_ranges(db, position))
+    }
+
     /// Returns position of the matching brace (all types of braces are
     /// supported).
     pub fn matching_brace(&self, position: FilePosition) -> Cancelable<Option<TextUnit>> {
@@ -259,6 +303,16 @@ impl Analysis {
         })
     }
 
+    /// Returns the ranges related to the keyword at the position: the exit
+    /// points of a function, the `.await`s of an async function or block, or
+    /// the `break`s and `continue`s of a loop.
+    pub fn highlight_related(&self, position: FilePosition) -> Cancelable<Vec<RelatedHighlight>> {
+        self.with_db(|db| {
+            let parse = db.parse(position.file_id);
+            highlight_related::highlight_related(&parse.tree(), position.offset)
+        })
+    }
+
     /// Returns a syntax tree represented as `String`, for debug purposes.
     // FIXME: use a better name here.
     pub fn syntax_tree(
@@ -273,6 +327,17 @@ impl Analysis {
         self.with_db(|db| expand_macro::expand_macro(db, position))
     }
 
+    /// Returns the lowered body of the function, const or static at the given
+    /// position together with the inferred types, for debug purposes.
+    pub fn view_hir(&self, position: FilePosition) -> Cancelable<Option<String>> {
+        self.with_db(|db| view_hir::view_hir(db, position))
+    }
+
+    /// Computes where rustdoc documents the item at the given position.
+    pub fn external_docs(&self, position: FilePosition) -> Cancelable<Option<ExternalDocs>> {
+        self.with_db(|db| external_docs::external_docs(db, position))
+    }
+
     /// Returns an edit to remove all newlines in the range, cleaning up minor
     /// stuff like trailing commas.
     pub fn join_lines(&self, frange: FileRange) -> Cancelable<SourceChange> {
@@ -286,6 +351,20 @@ impl Analysis {
         })
     }
 
+    /// Returns an edit which swaps the item, statement, match arm or field at
+    /// the range with its previous or next sibling.
+    pub fn move_item(
+        &self,
+        frange: FileRange,
+        direction: MoveDirection,
+    ) -> Cancelable<Option<SourceChange>> {
+        self.with_db(|db| {
+            let parse = db.parse(frange.file_id);
+            move_item::move_item(&parse.tree(), frange.range, direction)
+                .map(|change| change.into_source_change(frange.file_id))
+        })
+    }
+
     /// Returns an edit which should be applied when opening a new line, fixing
     /// up minor stuff like continuing the comment.
     pub fn on_enter(&self, position: FilePosition) -> Cancelable<Option<SourceChange>> {
@@ -362,7 +441,9 @@ impl Analysis {
         self.with_db(|db| goto_type_definition::goto_type_definition(db, position))
     }
 
-    /// Finds all usages of the reference at point.
+    /// Finds all usages of the reference at point. Only the files from which
+    /// the definition is visible are searched, `search_scope` can restrict
+    /// the search further.
     pub fn find_all_refs(
         &self,
         position: FilePosition,
@@ -424,6 +505,28 @@ impl Analysis {
         self.with_db(|db| runnables::runnables(db, file_id))
     }
 
+    /// Returns the annotations (runnables, implementation and reference
+    /// counts) to show above the items of the file. Computing references is
+    /// expensive, so they are only included when `with_references` is set.
+    pub fn annotations(
+        &self,
+        file_id: FileId,
+        with_references: bool,
+    ) -> Cancelable<Vec<Annotation>> {
+        self.with_db(|db| annotations::annotations(db, file_id, with_references))
+    }
+
+    /// Fills in the implementations or references of an annotation.
+    pub fn resolve_annotation(&self, annotation: Annotation) -> Cancelable<Annotation> {
+        self.with_db(|db| annotations::resolve_annotation(db, annotation))
+    }
+
+    /// Returns the tests which call the function at the given position,
+    /// directly or through a few other functions.
+    pub fn related_tests(&self, position: FilePosition) -> Cancelable<Vec<(FileId, Runnable)>> {
+        self.with_db(|db| related_tests::related_tests(db, position))
+    }
+
     /// Computes syntax highlighting for the given file
     pub fn highlight(&self, file_id: FileId) -> Cancelable<Vec<HighlightedRange>> {
         self.with_db(|db| syntax_highlighting::highlight(db, file_id, None))
@@ -450,16 +553,59 @@ impl Analysis {
         self.with_db(|db| assists::assists(db, frange))
     }
 
+    /// Computes the assists for the given position, without their edits.
+    pub fn unresolved_assists(&self, frange: FileRange) -> Cancelable<Vec<UnresolvedAssist>> {
+        self.with_db(|db| assists::unresolved_assists(db, frange))
+    }
+
+    /// Computes the edit of an assist returned by `unresolved_assists` for the
+    /// same range. Returns `None` if the assist is not applicable anymore.
+    pub fn resolve_assist(
+        &self,
+        frange: FileRange,
+        id: &str,
+        label: &str,
+    ) -> Cancelable<Option<Assist>> {
+        self.with_db(|db| assists::resolve_assist(db, frange, id, label))
+    }
+
     /// Computes the set of diagnostics for the given file.
     pub fn diagnostics(&self, file_id: FileId) -> Cancelable<Vec<Diagnostic>> {
         self.with_db(|db| diagnostics::diagnostics(db, file_id))
     }
 
+    /// Computes the diagnostics of the file along with the source roots they
+    /// depend on, so that they are only recomputed when one of these changes.
+    pub fn diagnostics_with_dependencies(
+        &self,
+        file_id: FileId,
+    ) -> Cancelable<(Vec<Diagnostic>, DiagnosticsDependencies)> {
+        self.with_db(|db| {
+            let dependencies = diagnostics::diagnostics_dependencies(db, file_id);
+            (diagnostics::diagnostics(db, file_id), dependencies)
+        })
+    }
+
     /// Computes the type of the expression at the given position.
     pub fn type_of(&self, frange: FileRange) -> Cancelable<Option<String>> {
         self.with_db(|db| hover::type_of(db, frange))
     }
 
+    /// Computes the type of the smallest expression or pattern covering the
+    /// selection, and returns it together with the range of that expression.
+    pub fn type_of_range(&self, frange: FileRange) -> Cancelable<Option<RangeInfo<String>>> {
+        self.with_db(|db| type_of_range::type_of_range(db, frange))
+    }
+
+    /// Checks that the identifier at `position` can be renamed and returns its
+    /// range and current text.
+    pub fn prepare_rename(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Result<RangeInfo<String>, RenameError>> {
+        self.with_db(|db| references::prepare_rename(db, position))
+    }
+
     /// Returns the edit required to rename reference at the position to the new
     /// name.
     pub fn rename(
@@ -470,6 +616,16 @@ impl Analysis {
         self.with_db(|db| references::rename(db, position, new_name))
     }
 
+    /// Returns the edits required to keep the module tree intact when the
+    /// file is renamed to `new_path`, relative to its source root.
+    pub fn will_rename_file(
+        &self,
+        file_id: FileId,
+        new_path: &RelativePath,
+    ) -> Cancelable<Option<SourceChange>> {
+        self.with_db(|db| references::rename_file(db, file_id, new_path))
+    }
+
     pub fn structural_search_replace(
         &self,
         query: &str,
@@ -9,6 +9,7 @@ test_utils::marks!(
     goto_def_for_record_fields
     goto_def_for_field_init_shorthand
     call_info_bad_offset
+    call_info_variadic_macro
     dont_complete_current_use
     test_resolve_parent_module_on_module_decl
     search_filters_by_range
@@ -14,6 +14,14 @@ pub fn matching_brace(file: &SourceFile, offset: TextUnit) -> Option<TextUnit> {
         })
         .next()?;
     let parent = brace_node.parent();
+    // `<` and `>` are braces only in generic lists, elsewhere they are
+    // comparison operators.
+    if brace_node.kind() == T![<] || brace_node.kind() == T![>] {
+        match parent.kind() {
+            SyntaxKind::TYPE_ARG_LIST | SyntaxKind::TYPE_PARAM_LIST => (),
+            _ => return None,
+        }
+    }
     let matching_kind = BRACES[brace_idx ^ 1];
     let matching_node = parent.children_with_tokens().find(|node| node.kind() == matching_kind)?;
     Some(matching_node.text_range().start())
@@ -25,19 +33,38 @@ mod tests {
 
     use super::*;
 
+    fn do_check(before: &str, after: &str) {
+        let (pos, before) = extract_offset(before);
+        let parse = SourceFile::parse(&before);
+        let new_pos = match matching_brace(&parse.tree(), pos) {
+            None => pos,
+            Some(pos) => pos,
+        };
+        let actual = add_cursor(&before, new_pos);
+        assert_eq_text!(after, &actual);
+    }
+
     #[test]
     fn test_matching_brace() {
-        fn do_check(before: &str, after: &str) {
-            let (pos, before) = extract_offset(before);
-            let parse = SourceFile::parse(&before);
-            let new_pos = match matching_brace(&parse.tree(), pos) {
-                None => pos,
-                Some(pos) => pos,
-            };
-            let actual = add_cursor(&before, new_pos);
-            assert_eq_text!(after, &actual);
-        }
-
         do_check("struct Foo { a: i32, }<|>", "struct Foo <|>{ a: i32, }");
+        do_check("fn foo<|><T, U>() {}", "fn foo<T, U<|>>() {}");
+        do_check("fn foo<T, U<|>>() {}", "fn foo<|><T, U>() {}");
+    }
+
+    #[test]
+    fn test_matching_angle_brackets_of_nested_generics() {
+        do_check("type T = Vec<|><HashMap<K, V>>;", "type T = Vec<HashMap<K, V><|>>;");
+        do_check("type T = Vec<HashMap<|><K, V>>;", "type T = Vec<HashMap<K, V<|>>>;");
+        do_check("type T = Vec<HashMap<K, V>><|>;", "type T = Vec<|><HashMap<K, V>>;");
+    }
+
+    #[test]
+    fn test_matching_angle_brackets_of_turbofish() {
+        do_check("fn main() { foo::<|><Vec<u8>>(); }", "fn main() { foo::<Vec<u8><|>>(); }");
+    }
+
+    #[test]
+    fn test_less_than_is_not_a_brace() {
+        do_check("fn main() { if a <|>< b && c > d {} }", "fn main() { if a <|>< b && c > d {} }");
     }
 }
@@ -7,8 +7,8 @@ use ra_db::{CrateName, Env, RelativePathBuf};
 use test_utils::{extract_offset, extract_range, parse_fixture, CURSOR_MARKER};
 
 use crate::{
-    Analysis, AnalysisChange, AnalysisHost, CrateGraph, Edition::Edition2018, FileId, FilePosition,
-    FileRange, SourceRootId,
+    Analysis, AnalysisChange, AnalysisHost, CrateGraph, Edition::Edition2018, FeatureFlags, FileId,
+    FilePosition, FileRange, SourceRootId,
 };
 
 /// Mock analysis is used in test to bootstrap an AnalysisHost/Analysis
@@ -84,7 +84,10 @@ impl MockAnalysis {
         FileId(idx as u32 + 1)
     }
     pub fn analysis_host(self) -> AnalysisHost {
-        let mut host = AnalysisHost::default();
+        self.analysis_host_with_feature_flags(FeatureFlags::default())
+    }
+    pub fn analysis_host_with_feature_flags(self, feature_flags: FeatureFlags) -> AnalysisHost {
+        let mut host = AnalysisHost::new(None, feature_flags);
         let source_root = SourceRootId(0);
         let mut change = AnalysisChange::new();
         change.add_root(source_root, true);
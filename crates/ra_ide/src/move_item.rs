@@ -0,0 +1,328 @@
+//! Moves an item, a statement, a match arm or a field up or down, by swapping
+//! it with its neighbour.
+//!
+//! Doc comments and attributes move along with the item, as the parser already
+//! attaches them to the item's node.
+
+use ra_syntax::{
+    algo::find_covering_element,
+    ast::{self, AstNode},
+    Direction, NodeOrToken, SourceFile,
+    SyntaxKind::{self, *},
+    SyntaxNode, TextRange, TextUnit, T,
+};
+use ra_text_edit::TextEditBuilder;
+
+use crate::source_change::SingleFileChange;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveDirection {
+    Up,
+    Down,
+}
+
+pub(crate) fn move_item(
+    file: &SourceFile,
+    range: TextRange,
+    direction: MoveDirection,
+) -> Option<SingleFileChange> {
+    let covering = match find_covering_element(file.syntax(), range) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent(),
+    };
+    let node = covering.ancestors().find(|it| is_movable(it.kind()))?;
+    let (first, second) = match direction {
+        MoveDirection::Up => (neighbour(&node, Direction::Prev)?, node.clone()),
+        MoveDirection::Down => (node.clone(), neighbour(&node, Direction::Next)?),
+    };
+
+    let (first_range, first_has_comma) = slot(&first);
+    let (second_range, second_has_comma) = slot(&second);
+    let has_next = neighbour(&second, Direction::Next).is_some();
+
+    // An element keeps its own comma, and gets one if it can't do without it,
+    // except when it becomes the last one, which keeps the list's trailing
+    // comma style.
+    let comma = |yes| if yes { "," } else { "" };
+    let new_first = format!("{}{}", second.text(), comma(second_has_comma || needs_comma(&second)));
+    let new_second = if has_next {
+        format!("{}{}", first.text(), comma(first_has_comma || needs_comma(&first)))
+    } else {
+        format!("{}{}", first.text(), comma(second_has_comma))
+    };
+
+    let offset_in_node = range.start() - node.text_range().start();
+    let cursor_position = match direction {
+        MoveDirection::Up => first_range.start() + offset_in_node,
+        MoveDirection::Down => {
+            second_range.start() + TextUnit::of_str(&new_first) + offset_in_node - first_range.len()
+        }
+    };
+
+    let mut edit = TextEditBuilder::default();
+    edit.replace(first_range, new_first);
+    edit.replace(second_range, new_second);
+
+    let label = match direction {
+        MoveDirection::Up => "move item up",
+        MoveDirection::Down => "move item down",
+    };
+    Some(SingleFileChange {
+        label: label.to_string(),
+        edit: edit.finish(),
+        cursor_position: Some(cursor_position),
+    })
+}
+
+fn is_movable(kind: SyntaxKind) -> bool {
+    match kind {
+        FN_DEF | STRUCT_DEF | ENUM_DEF | UNION_DEF | TRAIT_DEF | IMPL_BLOCK | MODULE
+        | CONST_DEF | STATIC_DEF | TYPE_ALIAS_DEF | USE_ITEM | EXTERN_CRATE_ITEM | LET_STMT
+        | EXPR_STMT | MATCH_ARM | RECORD_FIELD_DEF | TUPLE_FIELD_DEF | ENUM_VARIANT => true,
+        _ => false,
+    }
+}
+
+/// Finds the movable sibling of `node` in the given direction, skipping over
+/// trivia and separating commas.
+fn neighbour(node: &SyntaxNode, direction: Direction) -> Option<SyntaxNode> {
+    node.siblings_with_tokens(direction)
+        .skip(1)
+        .find(|it| !it.kind().is_trivia() && it.kind() != T![,])?
+        .into_node()
+        .filter(|it| is_movable(it.kind()))
+}
+
+/// Returns the range of `node` together with its trailing comma, if any.
+fn slot(node: &SyntaxNode) -> (TextRange, bool) {
+    let comma = node
+        .siblings_with_tokens(Direction::Next)
+        .skip(1)
+        .find(|it| !it.kind().is_trivia())
+        .filter(|it| it.kind() == T![,]);
+    match comma {
+        Some(comma) => {
+            (TextRange::from_to(node.text_range().start(), comma.text_range().end()), true)
+        }
+        None => (node.text_range(), false),
+    }
+}
+
+fn needs_comma(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        RECORD_FIELD_DEF | TUPLE_FIELD_DEF | ENUM_VARIANT => true,
+        MATCH_ARM => ast::MatchArm::cast(node.clone())
+            .and_then(|arm| arm.expr())
+            .map_or(true, |expr| !expr.is_block_like()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_utils::{add_cursor, assert_eq_text, extract_offset};
+
+    use super::*;
+
+    fn check_move(direction: MoveDirection, before: &str, after: &str) {
+        let (offset, before) = extract_offset(before);
+        let file = SourceFile::parse(&before).tree();
+        let change = move_item(&file, TextRange::offset_len(offset, 0.into()), direction)
+            .unwrap_or_else(|| panic!("nothing to move in:\n{}", before));
+        let actual = change.edit.apply(&before);
+        let actual = add_cursor(&actual, change.cursor_position.unwrap());
+        assert_eq_text!(after, &actual);
+    }
+
+    fn check_not_movable(direction: MoveDirection, before: &str) {
+        let (offset, before) = extract_offset(before);
+        let file = SourceFile::parse(&before).tree();
+        assert!(move_item(&file, TextRange::offset_len(offset, 0.into()), direction).is_none());
+    }
+
+    #[test]
+    fn moves_doc_commented_fn_down() {
+        check_move(
+            MoveDirection::Down,
+            r"
+/// Docs of foo.
+#[inline]
+fn f<|>oo() {}
+
+/// Docs of bar.
+fn bar() {
+    foo();
+}
+",
+            r"
+/// Docs of bar.
+fn bar() {
+    foo();
+}
+
+/// Docs of foo.
+#[inline]
+fn f<|>oo() {}
+",
+        );
+    }
+
+    #[test]
+    fn moves_doc_commented_fn_up() {
+        check_move(
+            MoveDirection::Up,
+            r"
+/// Docs of foo.
+fn foo() {}
+
+/// Docs of bar.
+fn b<|>ar() {}
+",
+            r"
+/// Docs of bar.
+fn b<|>ar() {}
+
+/// Docs of foo.
+fn foo() {}
+",
+        );
+    }
+
+    #[test]
+    fn moves_statements() {
+        check_move(
+            MoveDirection::Up,
+            r"
+fn main() {
+    let x = 1;
+    foo(<|>x);
+    x
+}
+",
+            r"
+fn main() {
+    foo(<|>x);
+    let x = 1;
+    x
+}
+",
+        );
+        check_not_movable(
+            MoveDirection::Down,
+            r"
+fn main() {
+    let x = 1;
+    foo(<|>x);
+    x
+}
+",
+        );
+    }
+
+    #[test]
+    fn moves_match_arms_with_trailing_commas() {
+        check_move(
+            MoveDirection::Down,
+            r"
+fn main() {
+    match x {
+        1 => <|>foo(),
+        2 => bar(),
+    }
+}
+",
+            r"
+fn main() {
+    match x {
+        2 => bar(),
+        1 => <|>foo(),
+    }
+}
+",
+        );
+    }
+
+    #[test]
+    fn moves_match_arms_without_trailing_commas() {
+        check_move(
+            MoveDirection::Down,
+            r"
+fn main() {
+    match x {
+        1 => <|>foo(),
+        2 => bar()
+    }
+}
+",
+            r"
+fn main() {
+    match x {
+        2 => bar(),
+        1 => <|>foo()
+    }
+}
+",
+        );
+        check_move(
+            MoveDirection::Up,
+            r"
+fn main() {
+    match x {
+        1 => {}
+        2 => <|>bar(),
+        _ => {}
+    }
+}
+",
+            r"
+fn main() {
+    match x {
+        2 => <|>bar(),
+        1 => {}
+        _ => {}
+    }
+}
+",
+        );
+        check_move(
+            MoveDirection::Down,
+            r"
+fn main() {
+    match x {
+        1 => <|>foo(),
+        2 => {}
+        _ => {}
+    }
+}
+",
+            r"
+fn main() {
+    match x {
+        2 => {}
+        1 => <|>foo(),
+        _ => {}
+    }
+}
+",
+        );
+    }
+
+    #[test]
+    fn moves_struct_fields() {
+        check_move(
+            MoveDirection::Down,
+            r"
+struct S {
+    <|>a: u32,
+    b: bool
+}
+",
+            r"
+struct S {
+    b: bool,
+    <|>a: u32
+}
+",
+        );
+    }
+}
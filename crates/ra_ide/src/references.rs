@@ -8,9 +8,14 @@
 //! for text occurrences of the identifier. If there's an `ast::NameRef`
 //! at the index that the match starts at and its tree parent is
 //! resolved to the search element definition, we get a reference.
+//! With the `references.search-macro-text` feature flag, the files of the
+//! defining crate are also searched for the identifier in macro calls and doc
+//! comments. Occurrences that can't be verified by expanding the macro are
+//! returned as possibly related.
 
 mod classify;
 mod rename;
+mod rename_file;
 mod search_scope;
 
 use hir::Semantics;
@@ -21,21 +26,32 @@ use ra_prof::profile;
 use ra_syntax::{
     algo::find_node_at_offset,
     ast::{self, NameOwner},
-    match_ast, AstNode, SyntaxKind, SyntaxNode, TextRange, TextUnit, TokenAtOffset,
+    match_ast, AstNode, AstToken, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextUnit,
+    TokenAtOffset,
 };
 use test_utils::tested_by;
 
-use crate::{display::TryToNav, FilePosition, FileRange, NavigationTarget, RangeInfo};
+use self::classify::resolve_record_pat_field;
+use crate::{
+    display::TryToNav,
+    format_args::{format_args, resolve_format_arg, FormatArg, FormatArgTarget},
+    FilePosition, FileRange, NavigationTarget, RangeInfo,
+};
 
-pub(crate) use self::{classify::classify_name_ref, rename::rename};
+pub(crate) use self::{
+    classify::{classify_name_ref, classify_pat_field_shorthand, NameRefClass},
+    rename::{prepare_rename, rename},
+    rename_file::rename_file,
+};
 pub(crate) use ra_ide_db::defs::{classify_name, NameDefinition};
 
-pub use self::search_scope::SearchScope;
+pub use self::{rename::RenameError, search_scope::SearchScope};
 
 #[derive(Debug, Clone)]
 pub struct ReferenceSearchResult {
     declaration: Declaration,
     references: Vec<Reference>,
+    possibly_related: Vec<Reference>,
 }
 
 #[derive(Debug, Clone)]
@@ -77,6 +93,12 @@ impl ReferenceSearchResult {
         &self.references
     }
 
+    /// Textual occurrences in macro calls and doc comments which could not be
+    /// verified to refer to the declaration.
+    pub fn possibly_related(&self) -> &[Reference] {
+        &self.possibly_related
+    }
+
     /// Total number of references
     /// At least 1 since all valid references should
     /// Have a declaration
@@ -93,14 +115,13 @@ impl IntoIterator for ReferenceSearchResult {
 
     fn into_iter(mut self) -> Self::IntoIter {
         let mut v = Vec::with_capacity(self.len());
-        v.push(Reference {
-            file_range: FileRange {
-                file_id: self.declaration.nav.file_id(),
-                range: self.declaration.nav.range(),
-            },
-            kind: self.declaration.kind,
-            access: self.declaration.access,
-        });
+        if let Some(file_id) = self.declaration.nav.file_id() {
+            v.push(Reference {
+                file_range: FileRange { file_id, range: self.declaration.nav.range() },
+                kind: self.declaration.kind,
+                access: self.declaration.access,
+            });
+        }
         v.append(&mut self.references);
         v.into_iter()
     }
@@ -121,8 +142,21 @@ pub(crate) fn find_all_refs(
             (find_node_at_offset::<ast::Name>(&syntax, position.offset), ReferenceKind::Other)
         };
 
-    let RangeInfo { range, info: (name, def) } = find_name(&sema, &syntax, position, opt_name)?;
+    let format_arg = syntax
+        .token_at_offset(position.offset)
+        .find_map(|token| Some((resolve_format_arg(&sema, &token, position.offset)?, token)));
+    let RangeInfo { range, info: (name, def) } = match format_arg {
+        Some(((arg, FormatArgTarget::Local(local)), _)) => {
+            RangeInfo::new(arg.range, (arg.name.to_string(), NameDefinition::Local(local)))
+        }
+        Some(((arg, target @ FormatArgTarget::Named(_)), token)) => {
+            let nav = target.to_nav(db, position.file_id);
+            return Some(named_format_arg_refs(position, arg, nav, &token));
+        }
+        None => find_name(&sema, &syntax, position, opt_name)?,
+    };
     let declaration = def.try_to_nav(db)?;
+    let decl_file_id = declaration.file_id()?;
 
     let search_scope = {
         let base = SearchScope::for_def(&def, db);
@@ -140,12 +174,42 @@ pub(crate) fn find_all_refs(
         access: decl_access(&def, &name, &syntax, decl_range),
     };
 
-    let references = process_definition(db, def, name, search_scope)
+    let references = process_definition(db, &def, &name, search_scope);
+    let possibly_related = if db.feature_flags.get("references.search-macro-text") {
+        let decl_range = FileRange { file_id: decl_file_id, range: decl_range };
+        process_macro_text(db, &def, &name, decl_range, &references)
+    } else {
+        Vec::new()
+    };
+    let references = references
         .into_iter()
         .filter(|r| search_kind == ReferenceKind::Other || search_kind == r.kind)
         .collect();
 
-    Some(RangeInfo::new(range, ReferenceSearchResult { declaration, references }))
+    Some(RangeInfo::new(range, ReferenceSearchResult { declaration, references, possibly_related }))
+}
+
+/// The references of a `name = expr` argument of a formatting macro are the
+/// placeholders of its format string.
+fn named_format_arg_refs(
+    position: FilePosition,
+    arg: FormatArg,
+    nav: NavigationTarget,
+    format_string: &SyntaxToken,
+) -> RangeInfo<ReferenceSearchResult> {
+    let declaration = Declaration { nav, kind: ReferenceKind::Other, access: None };
+    let references = format_args(format_string)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|it| it.name == arg.name)
+        .map(|it| Reference {
+            file_range: FileRange { file_id: position.file_id, range: it.range },
+            kind: ReferenceKind::Other,
+            access: Some(ReferenceAccess::Read),
+        })
+        .collect();
+    let possibly_related = Vec::new();
+    RangeInfo::new(arg.range, ReferenceSearchResult { declaration, references, possibly_related })
 }
 
 fn find_name(
@@ -160,20 +224,20 @@ fn find_name(
         return Some(RangeInfo::new(range, (name.text().to_string(), def)));
     }
     let name_ref = find_node_at_offset::<ast::NameRef>(&syntax, position.offset)?;
-    let def = classify_name_ref(sema, &name_ref)?;
+    let def = classify_name_ref(sema, &name_ref)?.definition();
     let range = name_ref.syntax().text_range();
     Some(RangeInfo::new(range, (name_ref.text().to_string(), def)))
 }
 
 fn process_definition(
     db: &RootDatabase,
-    def: NameDefinition,
-    name: String,
+    def: &NameDefinition,
+    name: &str,
     scope: SearchScope,
 ) -> Vec<Reference> {
     let _p = profile("process_definition");
 
-    let pat = name.as_str();
+    let pat = name;
     let mut refs = vec![];
 
     for (file_id, search_range) in scope {
@@ -191,6 +255,37 @@ fn process_definition(
                 continue;
             }
 
+            if let NameDefinition::StructField(field) = def {
+                // Fields in record patterns are `Name`s, not `NameRef`s
+                if let Some(name) = find_node_at_offset::<ast::Name>(&tree, offset) {
+                    if resolve_record_pat_field(&sema, &name) == Some(*field) {
+                        refs.push(Reference {
+                            file_range: sema.original_range(name.syntax()),
+                            kind: ReferenceKind::Other,
+                            access: Some(ReferenceAccess::Read),
+                        });
+                    }
+                    continue;
+                }
+            }
+
+            if let NameDefinition::Local(local) = def {
+                // Locals can be captured by name in format strings.
+                let format_arg = tree
+                    .token_at_offset(offset)
+                    .find_map(|token| resolve_format_arg(&sema, &token, offset));
+                if let Some((arg, FormatArgTarget::Local(it))) = format_arg {
+                    if it == *local && arg.range.start() == offset && arg.name.as_str() == pat {
+                        refs.push(Reference {
+                            file_range: FileRange { file_id, range: arg.range },
+                            kind: ReferenceKind::Other,
+                            access: Some(ReferenceAccess::Read),
+                        });
+                    }
+                    continue;
+                }
+            }
+
             let name_ref =
                 if let Some(name_ref) = find_node_at_offset::<ast::NameRef>(&tree, offset) {
                     name_ref
@@ -211,8 +306,8 @@ fn process_definition(
             // FIXME: reuse sb
             // See https://github.com/rust-lang/rust/pull/68198#issuecomment-574269098
 
-            if let Some(d) = classify_name_ref(&sema, &name_ref) {
-                if d == def {
+            if let Some(class) = classify_name_ref(&sema, &name_ref) {
+                if class.definitions().contains(def) {
                     let kind =
                         if is_record_lit_name_ref(&name_ref) || is_call_expr_name_ref(&name_ref) {
                             ReferenceKind::StructLiteral
@@ -224,7 +319,7 @@ fn process_definition(
                     refs.push(Reference {
                         file_range,
                         kind,
-                        access: reference_access(&d, &name_ref),
+                        access: reference_access(def, &name_ref),
                     });
                 }
             }
@@ -233,6 +328,82 @@ fn process_definition(
     refs
 }
 
+/// Searches the files of the crate defining `def` for occurrences of `name`
+/// in macro calls and doc comments, which name resolution doesn't see.
+/// `process_definition` already found the occurrences in macro calls which
+/// expand to a reference to `def`, so all others are unverified.
+fn process_macro_text(
+    db: &RootDatabase,
+    def: &NameDefinition,
+    name: &str,
+    decl_range: FileRange,
+    refs: &[Reference],
+) -> Vec<Reference> {
+    let _p = profile("process_macro_text");
+
+    // Locals can't be named outside of their body, which was searched already.
+    if let NameDefinition::Local(_) = def {
+        return Vec::new();
+    }
+    let file_id = match def.module(db) {
+        Some(module) => module.definition_source(db).file_id.original_file(db),
+        None => return Vec::new(),
+    };
+    let source_root = db.source_root(db.file_source_root(file_id));
+
+    let sema = Semantics::new(db);
+    let mut possibly_related = Vec::new();
+    for file_id in source_root.walk() {
+        let text = db.file_text(file_id);
+        let tree = Lazy::new(|| sema.parse(file_id).syntax().clone());
+
+        for (idx, _) in text.match_indices(name) {
+            let range = TextRange::offset_len(TextUnit::from_usize(idx), TextUnit::of_str(name));
+            let is_known = |it: &FileRange| it.file_id == file_id && it.range == range;
+            if is_known(&decl_range) || refs.iter().any(|it| is_known(&it.file_range)) {
+                continue;
+            }
+            let token = match tree.token_at_offset(range.start()).right_biased() {
+                Some(it) => it,
+                None => continue,
+            };
+            let reference = Reference {
+                file_range: FileRange { file_id, range },
+                kind: ReferenceKind::Other,
+                access: None,
+            };
+            match token.kind() {
+                SyntaxKind::IDENT if token.text_range() == range && is_in_macro_call(&token) => {
+                    possibly_related.push(reference)
+                }
+                SyntaxKind::COMMENT if is_word_at(&text, range) => {
+                    let is_doc = ast::Comment::cast(token).and_then(|it| it.kind().doc).is_some();
+                    if is_doc {
+                        possibly_related.push(reference);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    possibly_related
+}
+
+fn is_in_macro_call(token: &SyntaxToken) -> bool {
+    token
+        .ancestors()
+        .find_map(ast::TokenTree::cast)
+        .and_then(|tt| tt.syntax().ancestors().find_map(ast::MacroCall::cast))
+        .is_some()
+}
+
+fn is_word_at(text: &str, range: TextRange) -> bool {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let before = text[..range.start().to_usize()].chars().next_back();
+    let after = text[range.end().to_usize()..].chars().next();
+    !before.map_or(false, is_ident_char) && !after.map_or(false, is_ident_char)
+}
+
 fn decl_access(
     def: &NameDefinition,
     name: &str,
@@ -333,11 +504,14 @@ fn is_call_expr_name_ref(name_ref: &ast::NameRef) -> bool {
 
 #[cfg(test)]
 mod tests {
+    use hir::Semantics;
+    use ra_syntax::{algo::find_node_at_offset, ast, AstNode, TextRange};
     use test_utils::covers;
 
+    use super::classify_name;
     use crate::{
         mock_analysis::{analysis_and_position, single_file_with_position, MockAnalysis},
-        Declaration, Reference, ReferenceSearchResult, SearchScope,
+        Declaration, FeatureFlags, Reference, ReferenceSearchResult, SearchScope,
     };
 
     #[test]
@@ -645,6 +819,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_search_scope_of_local_is_its_function() {
+        let code = r#"
+            //- /lib.rs
+            mod a;
+            mod b;
+
+            //- /a.rs
+            fn f() {
+                let x<|> = 1;
+                x;
+            }
+
+            //- /b.rs
+            fn g() {
+                let x = 1;
+                x;
+            }
+        "#;
+
+        let (analysis, pos) = analysis_and_position(code);
+        let scope = analysis
+            .with_db(|db| {
+                let sema = Semantics::new(db);
+                let file = sema.parse(pos.file_id);
+                let name = find_node_at_offset::<ast::Name>(file.syntax(), pos.offset).unwrap();
+                let def = classify_name(&sema, &name).unwrap();
+                SearchScope::for_def(&def, db).into_iter().collect::<Vec<_>>()
+            })
+            .unwrap();
+        assert_eq!(scope, vec![(pos.file_id, Some(TextRange::from_to(0.into(), 32.into())))]);
+
+        let refs = analysis.find_all_refs(pos, None).unwrap().unwrap();
+        check_result(
+            refs,
+            "x BIND_PAT FileId(2) [17; 18) Other Write",
+            &["FileId(2) [28; 29) Other Read"],
+        );
+    }
+
+    #[test]
+    fn test_find_all_refs_trait_item_in_other_module() {
+        let code = r#"
+            //- /lib.rs
+            mod a;
+            mod b;
+
+            //- /a.rs
+            pub trait Tr {
+                fn foo<|>(&self);
+            }
+
+            //- /b.rs
+            use crate::a::Tr;
+            fn f(t: &dyn Tr) {
+                t.foo();
+            }
+        "#;
+
+        let (analysis, pos) = analysis_and_position(code);
+        let refs = analysis.find_all_refs(pos, None).unwrap().unwrap();
+        check_result(
+            refs,
+            "foo FN_DEF FileId(2) [19; 33) [22; 25) Other",
+            &["FileId(3) [43; 46) Other"],
+        );
+    }
+
+    #[test]
+    fn test_find_all_refs_enum_variant_field_in_other_module() {
+        let code = r#"
+            //- /lib.rs
+            mod a;
+            mod b;
+
+            //- /a.rs
+            pub enum E {
+                V { x<|>: u32 },
+            }
+
+            //- /b.rs
+            use crate::a::E;
+            fn f() -> E {
+                E::V { x: 1 }
+            }
+        "#;
+
+        let (analysis, pos) = analysis_and_position(code);
+        let refs = analysis.find_all_refs(pos, None).unwrap().unwrap();
+        check_result(
+            refs,
+            "x RECORD_FIELD_DEF FileId(2) [21; 27) [21; 22) Other",
+            &["FileId(3) [42; 43) Other Read"],
+        );
+    }
+
+    #[test]
+    fn test_find_all_refs_pub_fn_in_dependent_crate() {
+        let code = r#"
+            //- /lib.rs
+            fn main() { dep::foo(); }
+
+            //- /dep/lib.rs
+            pub fn foo<|>() {}
+        "#;
+
+        let (analysis, pos) = analysis_and_position(code);
+        let refs = analysis.find_all_refs(pos, None).unwrap().unwrap();
+        check_result(
+            refs,
+            "foo FN_DEF FileId(2) [0; 15) [7; 10) Other",
+            &["FileId(1) [17; 20) StructLiteral"],
+        );
+    }
+
     #[test]
     fn test_find_all_refs_with_scope() {
         let code = r#"
@@ -736,6 +1025,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_all_refs_macro_text() {
+        let code = r#"
+macro_rules! forward {
+    ($f:ident) => { $f() };
+}
+
+/// Calls [call_me] twice.
+fn call_me<|>() {}
+
+fn main() {
+    forward!(call_me);
+    unknown!(call_me);
+}"#;
+
+        let refs = get_all_refs_with_macro_text(code);
+        let possibly_related = refs.possibly_related().to_vec();
+        check_result(
+            refs,
+            "call_me FN_DEF FileId(1) [55; 97) [85; 92) Other",
+            &["FileId(1) [124; 131) StructLiteral"],
+        );
+        assert_eq!(possibly_related.len(), 2);
+        possibly_related[0].assert_match("FileId(1) [66; 73) Other");
+        possibly_related[1].assert_match("FileId(1) [147; 154) Other");
+
+        let refs = get_all_refs(code);
+        assert!(refs.possibly_related().is_empty());
+    }
+
+    #[test]
+    fn test_find_all_refs_macro_text_skips_locals() {
+        let code = r#"
+fn main() {
+    let x<|> = 1;
+    unknown!(x);
+}"#;
+
+        let refs = get_all_refs_with_macro_text(code);
+        assert!(refs.possibly_related().is_empty());
+    }
+
     #[test]
     fn test_basic_highlight_decl_no_write() {
         let code = r#"
@@ -752,11 +1083,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_all_refs_for_format_string_capture() {
+        let code = r#"
+    fn main() {
+        let x = 92;
+        let y = x + 1;
+        println!("{x<|>} {{x}} {y}");
+        println!("{x:?}", x = y);
+    }"#;
+
+        let refs = get_all_refs(code);
+        check_result(
+            refs,
+            "x BIND_PAT FileId(1) [29; 30) Other Write",
+            &["FileId(1) [53; 54) Other Read", "FileId(1) [79; 80) Other Read"],
+        );
+    }
+
+    #[test]
+    fn test_find_all_refs_for_format_string_named_arg() {
+        let code = r#"
+    fn main() {
+        println!("{value<|>} and {value:?}", value = 92);
+    }"#;
+
+        let refs = get_all_refs(code);
+        check_result(
+            refs,
+            "value IDENT FileId(1) [59; 64) Other",
+            &["FileId(1) [36; 41) Other Read", "FileId(1) [48; 53) Other Read"],
+        );
+    }
+
     fn get_all_refs(text: &str) -> ReferenceSearchResult {
         let (analysis, position) = single_file_with_position(text);
         analysis.find_all_refs(position, None).unwrap().unwrap()
     }
 
+    fn get_all_refs_with_macro_text(text: &str) -> ReferenceSearchResult {
+        let mut feature_flags = FeatureFlags::default();
+        feature_flags.set("references.search-macro-text", true).unwrap();
+        let mut mock = MockAnalysis::new();
+        let position = mock.add_file_with_position("/main.rs", text);
+        let analysis = mock.analysis_host_with_feature_flags(feature_flags).analysis();
+        analysis.find_all_refs(position, None).unwrap().unwrap()
+    }
+
     fn check_result(res: ReferenceSearchResult, expected_decl: &str, expected_refs: &[&str]) {
         res.declaration().assert_match(expected_decl);
         assert_eq!(res.references.len(), expected_refs.len());
@@ -1,6 +1,6 @@
 //! Functions that are used to classify an element from its definition or reference.
 
-use hir::{PathResolution, Semantics};
+use hir::{Local, PathResolution, Semantics, StructField};
 use ra_ide_db::defs::NameDefinition;
 use ra_ide_db::RootDatabase;
 use ra_prof::profile;
@@ -9,10 +9,43 @@ use test_utils::tested_by;
 
 pub use ra_ide_db::defs::{from_module_def, from_struct_field};
 
+/// What a name reference refers to.
+#[derive(Debug)]
+pub(crate) enum NameRefClass {
+    Definition(NameDefinition),
+    /// `spam` in the record literal `Foo { spam }`, which refers both to the
+    /// field and to the local variable.
+    FieldShorthand {
+        field: StructField,
+        local: Local,
+    },
+}
+
+impl NameRefClass {
+    /// The definition the reference is primarily about, that is the field for
+    /// shorthands.
+    pub(crate) fn definition(self) -> NameDefinition {
+        match self {
+            NameRefClass::Definition(def) => def,
+            NameRefClass::FieldShorthand { field, local: _ } => from_struct_field(field),
+        }
+    }
+
+    /// All the definitions the reference refers to.
+    pub(crate) fn definitions(self) -> Vec<NameDefinition> {
+        match self {
+            NameRefClass::Definition(def) => vec![def],
+            NameRefClass::FieldShorthand { field, local } => {
+                vec![from_struct_field(field), NameDefinition::Local(local)]
+            }
+        }
+    }
+}
+
 pub(crate) fn classify_name_ref(
     sema: &Semantics<RootDatabase>,
     name_ref: &ast::NameRef,
-) -> Option<NameDefinition> {
+) -> Option<NameRefClass> {
     let _p = profile("classify_name_ref");
 
     let parent = name_ref.syntax().parent()?;
@@ -20,29 +53,33 @@ pub(crate) fn classify_name_ref(
     if let Some(method_call) = ast::MethodCallExpr::cast(parent.clone()) {
         tested_by!(goto_def_for_methods);
         if let Some(func) = sema.resolve_method_call(&method_call) {
-            return Some(from_module_def(func.into()));
+            return Some(NameRefClass::Definition(from_module_def(func.into())));
         }
     }
 
     if let Some(field_expr) = ast::FieldExpr::cast(parent.clone()) {
         tested_by!(goto_def_for_fields);
         if let Some(field) = sema.resolve_field(&field_expr) {
-            return Some(from_struct_field(field));
+            return Some(NameRefClass::Definition(from_struct_field(field)));
         }
     }
 
     if let Some(record_field) = ast::RecordField::cast(parent.clone()) {
         tested_by!(goto_def_for_record_fields);
         tested_by!(goto_def_for_field_init_shorthand);
-        if let Some(field_def) = sema.resolve_record_field(&record_field) {
-            return Some(from_struct_field(field_def));
+        if let Some((field, local)) = sema.resolve_record_field(&record_field) {
+            let res = match local {
+                Some(local) => NameRefClass::FieldShorthand { field, local },
+                None => NameRefClass::Definition(from_struct_field(field)),
+            };
+            return Some(res);
         }
     }
 
     if let Some(macro_call) = parent.ancestors().find_map(ast::MacroCall::cast) {
         tested_by!(goto_def_for_macros);
         if let Some(macro_def) = sema.resolve_macro_call(&macro_call) {
-            return Some(NameDefinition::Macro(macro_def));
+            return Some(NameRefClass::Definition(NameDefinition::Macro(macro_def)));
         }
     }
 
@@ -63,5 +100,34 @@ pub(crate) fn classify_name_ref(
         PathResolution::Macro(def) => NameDefinition::Macro(def),
         PathResolution::SelfType(impl_block) => NameDefinition::SelfType(impl_block),
     };
-    Some(res)
+    Some(NameRefClass::Definition(res))
+}
+
+/// Resolves the field of a record pattern field, like `spam` in both
+/// `let Foo { spam } = foo;` and `let Foo { spam: x } = foo;`.
+pub(crate) fn resolve_record_pat_field(
+    sema: &Semantics<RootDatabase>,
+    name: &ast::Name,
+) -> Option<StructField> {
+    let parent = name.syntax().parent()?;
+    if !ast::RecordFieldPat::can_cast(parent.kind()) && !ast::BindPat::can_cast(parent.kind()) {
+        return None;
+    }
+    let field_list = ast::RecordFieldPatList::cast(parent.parent()?)?;
+    let record_pat = ast::RecordPat::cast(field_list.syntax().parent()?)?;
+    let variant = sema.resolve_record_pattern(&record_pat)?;
+    variant
+        .fields(sema.db)
+        .into_iter()
+        .find(|field| field.name(sema.db).to_string() == name.text().as_str())
+}
+
+/// Resolves the field bound by a record pattern shorthand, like `spam` in
+/// `let Foo { spam } = foo;`, whose name also defines a local variable.
+pub(crate) fn classify_pat_field_shorthand(
+    sema: &Semantics<RootDatabase>,
+    name: &ast::Name,
+) -> Option<StructField> {
+    ast::BindPat::cast(name.syntax().parent()?)?;
+    resolve_record_pat_field(sema, name)
 }
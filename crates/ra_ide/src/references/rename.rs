@@ -1,6 +1,8 @@
 //! FIXME: write short doc here
 
-use hir::{ModuleSource, Semantics};
+use std::fmt;
+
+use hir::{ModuleDef, ModuleSource, Semantics};
 use ra_db::{RelativePath, RelativePathBuf, SourceDatabaseExt};
 use ra_ide_db::RootDatabase;
 use ra_syntax::{
@@ -9,10 +11,65 @@ use ra_syntax::{
 use ra_text_edit::TextEdit;
 
 use crate::{
-    FileId, FilePosition, FileSystemEdit, RangeInfo, SourceChange, SourceFileEdit, TextRange,
+    FileId, FilePosition, FileRange, FileSystemEdit, RangeInfo, SourceChange, SourceFileEdit,
+    TextRange,
+};
+
+use super::{
+    find_all_refs, find_name, get_struct_def_name_for_struc_litetal_search, resolve_format_arg,
+    NameDefinition, Reference,
 };
 
-use super::find_all_refs;
+#[derive(Debug, PartialEq)]
+pub struct RenameError(String);
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RenameError {}
+
+/// Checks that the identifier under the cursor can be renamed and returns its
+/// range and current text.
+pub(crate) fn prepare_rename(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Result<RangeInfo<String>, RenameError> {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let syntax = source_file.syntax();
+
+    if let Some((ast_name, _)) = find_name_and_module_at_offset(syntax, position) {
+        let range = ast_name.syntax().text_range();
+        return Ok(RangeInfo::new(range, ast_name.text().to_string()));
+    }
+
+    // Placeholders of format strings always refer to the current file.
+    let format_arg = syntax
+        .token_at_offset(position.offset)
+        .find_map(|token| resolve_format_arg(&sema, &token, position.offset));
+    if let Some((arg, _)) = format_arg {
+        return Ok(RangeInfo::new(arg.range, arg.name.to_string()));
+    }
+
+    let opt_name = get_struct_def_name_for_struc_litetal_search(syntax, position)
+        .or_else(|| find_node_at_offset::<ast::Name>(syntax, position.offset));
+    let RangeInfo { range, info: (name, def) } = find_name(&sema, syntax, position, opt_name)
+        .ok_or_else(|| RenameError("no references found at position".to_string()))?;
+
+    if let NameDefinition::ModuleDef(ModuleDef::BuiltinType(_)) = def {
+        return Err(RenameError("cannot rename builtin type".to_string()));
+    }
+    let def_krate = def.module(db).map(|it| it.krate());
+    let krate = sema.to_module_def(position.file_id).map(|it| it.krate());
+    if def_krate != krate {
+        return Err(RenameError("cannot rename items from other crates".to_string()));
+    }
+
+    Ok(RangeInfo::new(range, name))
+}
 
 pub(crate) fn rename(
     db: &RootDatabase,
@@ -23,6 +80,7 @@ pub(crate) fn rename(
         SyntaxKind::IDENT | SyntaxKind::UNDERSCORE => (),
         _ => return None,
     }
+    prepare_rename(db, position).ok()?;
 
     let sema = Semantics::new(db);
     let source_file = sema.parse(position.file_id);
@@ -33,7 +91,7 @@ pub(crate) fn rename(
         rename_mod(&sema, &ast_name, &ast_module, position, new_name)
             .map(|info| RangeInfo::new(range, info))
     } else {
-        rename_reference(sema.db, position, new_name)
+        rename_reference(&sema, position, new_name)
     }
 }
 
@@ -113,28 +171,98 @@ fn rename_mod(
 }
 
 fn rename_reference(
-    db: &RootDatabase,
+    sema: &Semantics<RootDatabase>,
     position: FilePosition,
     new_name: &str,
 ) -> Option<RangeInfo<SourceChange>> {
-    let RangeInfo { range, info: refs } = find_all_refs(db, position, None)?;
+    let RangeInfo { range, info: refs } = find_all_refs(sema.db, position, None)?;
+    let renaming_field = refs.decl_target().kind() == SyntaxKind::RECORD_FIELD_DEF;
+    // Unverified occurrences are left for the user to review.
+    let label = match refs.possibly_related().len() {
+        0 => "rename".to_string(),
+        n => format!("rename ({} possibly related occurrence(s) in macros or docs not renamed)", n),
+    };
 
     let edit = refs
         .into_iter()
-        .map(|reference| {
-            source_edit_from_file_id_range(
-                reference.file_range.file_id,
-                reference.file_range.range,
-                new_name,
-            )
-        })
+        .map(|reference| source_edit_from_reference(sema, reference, new_name, renaming_field))
         .collect::<Vec<_>>();
 
     if edit.is_empty() {
         return None;
     }
 
-    Some(RangeInfo::new(range, SourceChange::source_file_edits("rename", edit)))
+    Some(RangeInfo::new(range, SourceChange::source_file_edits(label, edit)))
+}
+
+/// How a reference is written, which determines how it is renamed.
+enum ReferenceSite {
+    /// `spam` in the record literal `Foo { spam }`.
+    RecordLitShorthand(ast::NameRef),
+    /// `spam` in the record pattern `Foo { spam }`.
+    RecordPatShorthand(ast::BindPat),
+    /// Everything else, like field accesses and non-shorthand record fields.
+    Other,
+}
+
+fn classify_reference_site(syntax: &SyntaxNode, range: TextRange) -> ReferenceSite {
+    if let Some(name_ref) = find_node_at_offset::<ast::NameRef>(syntax, range.start()) {
+        let is_shorthand = name_ref
+            .syntax()
+            .parent()
+            .and_then(ast::RecordField::cast)
+            .map_or(false, |field| field.expr().is_none());
+        if is_shorthand && name_ref.syntax().text_range() == range {
+            return ReferenceSite::RecordLitShorthand(name_ref);
+        }
+    } else if let Some(name) = find_node_at_offset::<ast::Name>(syntax, range.start()) {
+        let bind_pat = name.syntax().parent().and_then(ast::BindPat::cast);
+        if let Some(bind_pat) = bind_pat {
+            let in_field_list = bind_pat.syntax().parent().map(|it| it.kind())
+                == Some(SyntaxKind::RECORD_FIELD_PAT_LIST);
+            if in_field_list && name.syntax().text_range() == range {
+                return ReferenceSite::RecordPatShorthand(bind_pat);
+            }
+        }
+    }
+    ReferenceSite::Other
+}
+
+/// Renames a single reference. Shorthand fields are expanded so that the
+/// field and the local variable, only one of which is renamed, stay connected.
+fn source_edit_from_reference(
+    sema: &Semantics<RootDatabase>,
+    reference: Reference,
+    new_name: &str,
+    renaming_field: bool,
+) -> SourceFileEdit {
+    let FileRange { file_id, range } = reference.file_range;
+    let source_file = sema.parse(file_id);
+    let (range, replacement) = match classify_reference_site(source_file.syntax(), range) {
+        ReferenceSite::RecordLitShorthand(name_ref) => {
+            let old_name = name_ref.text();
+            let replacement = if renaming_field {
+                format!("{}: {}", new_name, old_name)
+            } else {
+                format!("{}: {}", old_name, new_name)
+            };
+            (range, replacement)
+        }
+        ReferenceSite::RecordPatShorthand(bind_pat) => {
+            let pat_range = bind_pat.syntax().text_range();
+            let pat_text = bind_pat.syntax().text().to_string();
+            let name_start = (range.start() - pat_range.start()).to_usize();
+            let (modifiers, old_name) = pat_text.split_at(name_start);
+            let replacement = if renaming_field {
+                format!("{}: {}{}", new_name, modifiers, old_name)
+            } else {
+                format!("{}: {}{}", old_name, modifiers, new_name)
+            };
+            (pat_range, replacement)
+        }
+        ReferenceSite::Other => (range, new_name.to_string()),
+    };
+    SourceFileEdit { file_id, edit: TextEdit::replace(range, replacement) }
 }
 
 #[cfg(test)]
@@ -144,7 +272,8 @@ mod tests {
     use test_utils::assert_eq_text;
 
     use crate::{
-        mock_analysis::analysis_and_position, mock_analysis::single_file_with_position, FileId,
+        mock_analysis::analysis_and_position, mock_analysis::single_file_with_position,
+        mock_analysis::MockAnalysis, Analysis, FeatureFlags, FileId, FilePosition,
     };
 
     #[test]
@@ -285,6 +414,104 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rename_struct_field_for_shorthand_literal() {
+        test_rename(
+            r#"
+    struct Foo {
+        i<|>: i32,
+    }
+
+    fn new(i: i32) -> Foo {
+        Foo { i }
+    }"#,
+            "j",
+            r#"
+    struct Foo {
+        j: i32,
+    }
+
+    fn new(i: i32) -> Foo {
+        Foo { j: i }
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_local_for_shorthand_literal() {
+        test_rename(
+            r#"
+    struct Foo {
+        i: i32,
+    }
+
+    fn new(i<|>: i32) -> Foo {
+        Foo { i }
+    }"#,
+            "j",
+            r#"
+    struct Foo {
+        i: i32,
+    }
+
+    fn new(j: i32) -> Foo {
+        Foo { i: j }
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_struct_field_for_shorthand_pattern() {
+        test_rename(
+            r#"
+    struct Foo {
+        i<|>: i32,
+    }
+
+    fn foo(foo: Foo) -> i32 {
+        let Foo { i } = foo;
+        let Foo { i: x } = foo;
+        i + x + foo.i
+    }"#,
+            "j",
+            r#"
+    struct Foo {
+        j: i32,
+    }
+
+    fn foo(foo: Foo) -> i32 {
+        let Foo { j: i } = foo;
+        let Foo { j: x } = foo;
+        i + x + foo.j
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_local_for_shorthand_pattern() {
+        test_rename(
+            r#"
+    struct Foo {
+        i: i32,
+    }
+
+    fn foo(foo: &Foo) -> i32 {
+        let Foo { ref i } = foo;
+        *i<|>
+    }"#,
+            "j",
+            r#"
+    struct Foo {
+        i: i32,
+    }
+
+    fn foo(foo: &Foo) -> i32 {
+        let Foo { i: ref j } = foo;
+        *j
+    }"#,
+        );
+    }
+
     #[test]
     fn test_rename_mod() {
         let (analysis, position) = analysis_and_position(
@@ -489,8 +716,123 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_rename_with_macro_text() {
+        let mut feature_flags = FeatureFlags::default();
+        feature_flags.set("references.search-macro-text", true).unwrap();
+        let mut mock = MockAnalysis::new();
+        let position = mock.add_file_with_position(
+            "/main.rs",
+            r#"
+    macro_rules! forward {
+        ($f:ident) => { $f() };
+    }
+
+    /// Calls [call_me].
+    fn call_me<|>() {}
+
+    fn main() {
+        forward!(call_me);
+        unknown!(call_me);
+    }"#,
+        );
+        let analysis = mock.analysis_host_with_feature_flags(feature_flags).analysis();
+
+        let label = analysis.rename(position, "renamed").unwrap().unwrap().info.label;
+        assert_eq!(
+            label,
+            "rename (2 possibly related occurrence(s) in macros or docs not renamed)"
+        );
+        assert_eq_text!(
+            r#"
+    macro_rules! forward {
+        ($f:ident) => { $f() };
+    }
+
+    /// Calls [call_me].
+    fn renamed() {}
+
+    fn main() {
+        forward!(renamed);
+        unknown!(call_me);
+    }"#,
+            &*apply_rename(&analysis, position, "renamed")
+        );
+    }
+
+    #[test]
+    fn test_prepare_rename_local() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn main() {
+        let foo<|> = 1;
+        foo + 1;
+    }"#,
+        );
+        let res = analysis.prepare_rename(position).unwrap().unwrap();
+        assert_eq!(res.info, "foo");
+        assert_eq!(&analysis.file_text(position.file_id).unwrap()[res.range], "foo");
+    }
+
+    #[test]
+    fn test_prepare_rename_mod() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    mod fo<|>o {}
+    "#,
+        );
+        let res = analysis.prepare_rename(position).unwrap().unwrap();
+        assert_eq!(res.info, "foo");
+        assert_eq!(&analysis.file_text(position.file_id).unwrap()[res.range], "foo");
+    }
+
+    #[test]
+    fn test_prepare_rename_builtin_type() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn foo() -> u3<|>2 { 0 }
+    "#,
+        );
+        let err = analysis.prepare_rename(position).unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "cannot rename builtin type");
+        assert!(analysis.rename(position, "bar").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prepare_rename_item_of_other_crate() {
+        let (analysis, position) = analysis_and_position(
+            r#"
+            //- /lib.rs
+            fn main() { dep::fo<|>o(); }
+
+            //- /dep/lib.rs
+            pub fn foo() {}
+            "#,
+        );
+        let err = analysis.prepare_rename(position).unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "cannot rename items from other crates");
+        assert!(analysis.rename(position, "bar").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prepare_rename_without_name() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn main() {
+        let foo = 9<|>2;
+    }"#,
+        );
+        let err = analysis.prepare_rename(position).unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "no references found at position");
+    }
+
     fn test_rename(text: &str, new_name: &str, expected: &str) {
         let (analysis, position) = single_file_with_position(text);
+        let result = apply_rename(&analysis, position, new_name);
+        assert_eq_text!(expected, &*result);
+    }
+
+    fn apply_rename(analysis: &Analysis, position: FilePosition, new_name: &str) -> String {
         let source_change = analysis.rename(position, new_name).unwrap();
         let mut text_edit_builder = TextEditBuilder::default();
         let mut file_id: Option<FileId> = None;
@@ -502,8 +844,6 @@ mod tests {
                 }
             }
         }
-        let result =
-            text_edit_builder.finish().apply(&*analysis.file_text(file_id.unwrap()).unwrap());
-        assert_eq_text!(expected, &*result);
+        text_edit_builder.finish().apply(&*analysis.file_text(file_id.unwrap()).unwrap())
     }
 }
@@ -0,0 +1,397 @@
+//! Computes the edits which keep the module tree intact when a file is renamed
+//! or moved: the `mod` declaration of the file's module is renamed or moved to
+//! the new parent module, and the paths referring to the module are rewritten.
+
+use hir::{Module, ModuleDef, ModuleSource, PathResolution, Semantics};
+use ra_db::{RelativePath, RelativePathBuf, SourceDatabaseExt};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, ModuleItemOwner, NameOwner, VisibilityOwner},
+    lex_single_valid_syntax_kind, AstNode, SyntaxKind, TextRange,
+};
+use ra_text_edit::TextEditBuilder;
+use rustc_hash::FxHashMap;
+
+use crate::{FileId, FilePosition, SourceChange, SourceFileEdit};
+
+use super::find_all_refs;
+
+/// Returns the edits required when the file `file_id` is renamed to
+/// `new_path`, relative to the same source root. Returns `None` if the move
+/// can't be expressed in the module tree, like a move to a directory without
+/// a parent module, or out of the crate.
+pub(crate) fn rename_file(
+    db: &RootDatabase,
+    file_id: FileId,
+    new_path: &RelativePath,
+) -> Option<SourceChange> {
+    let sema = Semantics::new(db);
+    let module = sema.to_module_def(file_id)?;
+    let decl = module.declaration_source(db)?;
+    let decl_file = decl.file_id.original_file(db);
+    if decl.file_id != decl_file.into() {
+        return None;
+    }
+    let decl_name = decl.value.name()?;
+    let old_parent = module.parent(db)?;
+
+    let (new_name, new_dir) = module_name_and_dir(new_path)?;
+    match lex_single_valid_syntax_kind(&new_name)? {
+        SyntaxKind::IDENT => (),
+        _ => return None,
+    }
+    let new_parent = find_module_with_children_dir(db, module.crate_root(db), &new_dir)?;
+    if new_parent.path_to_root(db).contains(&module) {
+        return None;
+    }
+    if new_parent
+        .children(db)
+        .any(|it| it != module && it.name(db).map_or(false, |it| it.to_string() == new_name))
+    {
+        return None;
+    }
+    if new_parent == old_parent && decl_name.text().as_str() == new_name {
+        return None;
+    }
+
+    let mut edits: FxHashMap<FileId, TextEditBuilder> = FxHashMap::default();
+    if new_parent == old_parent {
+        edits
+            .entry(decl_file)
+            .or_default()
+            .replace(decl_name.syntax().text_range(), new_name.clone());
+    } else {
+        let mut range = decl.value.syntax().text_range();
+        if let Some(ws) = decl.value.syntax().next_sibling_or_token() {
+            if ws.kind() == SyntaxKind::WHITESPACE {
+                range = range.extend_to(&ws.text_range());
+            }
+        }
+        edits.entry(decl_file).or_default().delete(range);
+
+        let visibility =
+            decl.value.visibility().map(|it| format!("{} ", it.syntax())).unwrap_or_default();
+        let new_decl = format!("{}mod {};", visibility, new_name);
+        let parent_file = new_parent.definition_source(db).file_id.original_file(db);
+        let source_file = sema.parse(parent_file);
+        let last_mod = source_file
+            .items()
+            .filter_map(|it| match it {
+                ast::ModuleItem::Module(it) => Some(it),
+                _ => None,
+            })
+            .last();
+        let (offset, text) = match (last_mod, source_file.items().next()) {
+            (Some(it), _) => (it.syntax().text_range().end(), format!("\n{}", new_decl)),
+            (None, Some(it)) => (it.syntax().text_range().start(), format!("{}\n\n", new_decl)),
+            (None, None) => (source_file.syntax().text_range().end(), format!("{}\n", new_decl)),
+        };
+        edits.entry(parent_file).or_default().insert(offset, text);
+    }
+
+    let position =
+        FilePosition { file_id: decl_file, offset: decl_name.syntax().text_range().start() };
+    if let Some(refs) = find_all_refs(db, position, None) {
+        for reference in refs.info.references() {
+            let file_id = reference.file_range.file_id;
+            let source_file = sema.parse(file_id);
+            let name_ref = match find_node_at_offset::<ast::NameRef>(
+                source_file.syntax(),
+                reference.file_range.range.start(),
+            ) {
+                Some(it) => it,
+                None => continue,
+            };
+            if let Some((range, text)) =
+                rewrite_reference(&sema, &name_ref, module, new_parent, &new_name)
+            {
+                edits.entry(file_id).or_default().replace(range, text);
+            }
+        }
+    }
+
+    let mut source_file_edits = edits
+        .into_iter()
+        .map(|(file_id, edit)| SourceFileEdit { file_id, edit: edit.finish() })
+        .collect::<Vec<_>>();
+    source_file_edits.sort_by_key(|it| it.file_id);
+    Some(SourceChange::source_file_edits("rename file", source_file_edits))
+}
+
+/// The name of the module defined by the file at `path`, and the directory of
+/// the files of its sibling modules.
+fn module_name_and_dir(path: &RelativePath) -> Option<(String, RelativePathBuf)> {
+    if path.extension() != Some("rs") {
+        return None;
+    }
+    let parent = path.parent()?;
+    if path.file_stem()? == "mod" {
+        let name = parent.file_name()?;
+        let dir = parent.parent()?;
+        Some((name.to_string(), dir.to_relative_path_buf()))
+    } else {
+        Some((path.file_stem()?.to_string(), parent.to_relative_path_buf()))
+    }
+}
+
+/// The directory in which the files of `module`'s children live, or `None`
+/// for inline modules.
+fn children_dir(db: &RootDatabase, module: Module) -> Option<RelativePathBuf> {
+    let src = module.definition_source(db);
+    match src.value {
+        ModuleSource::SourceFile(_) => (),
+        ModuleSource::Module(_) => return None,
+    }
+    let path = db.file_relative_path(src.file_id.original_file(db));
+    let dir = path.parent()?;
+    if module.parent(db).is_none() || path.file_stem() == Some("mod") {
+        Some(dir.to_relative_path_buf())
+    } else {
+        Some(dir.join(path.file_stem()?))
+    }
+}
+
+fn find_module_with_children_dir(
+    db: &RootDatabase,
+    module: Module,
+    dir: &RelativePath,
+) -> Option<Module> {
+    if children_dir(db, module).as_deref() == Some(dir) {
+        return Some(module);
+    }
+    module.children(db).find_map(|it| find_module_with_children_dir(db, it, dir))
+}
+
+/// Rewrites the path containing `name_ref`, a reference to the moved
+/// `module`, so that it refers to the module at its new place.
+fn rewrite_reference(
+    sema: &Semantics<RootDatabase>,
+    name_ref: &ast::NameRef,
+    module: Module,
+    new_parent: Module,
+    new_name: &str,
+) -> Option<(TextRange, String)> {
+    let db = sema.db;
+    let segment = ast::PathSegment::cast(name_ref.syntax().parent()?)?;
+    let path = segment.parent_path();
+
+    // The module the reference is resolved relative to.
+    let mut top = path.clone();
+    while let Some(it) = top.syntax().parent().and_then(ast::Path::cast) {
+        top = it;
+    }
+    let nested_prefix = ast::UseTree::cast(top.syntax().parent()?)
+        .and_then(|it| ast::UseTreeList::cast(it.syntax().parent()?))
+        .map(|it| it.parent_use_tree());
+    let base = match (path.qualifier(), &nested_prefix) {
+        (Some(qualifier), _) => resolve_module(sema, &qualifier)?,
+        (None, Some(use_tree)) => {
+            if use_tree.syntax().parent().and_then(ast::UseTreeList::cast).is_some() {
+                return None;
+            }
+            resolve_module(sema, &use_tree.path()?)?
+        }
+        (None, None) => sema.scope(path.syntax()).module()?,
+    };
+
+    let to_new_parent = new_parent.path_to_root(db);
+    if let Some(idx) = to_new_parent.iter().position(|it| *it == base) {
+        let mut segments = to_new_parent[..idx]
+            .iter()
+            .rev()
+            .filter_map(|it| it.name(db))
+            .map(|it| it.to_string())
+            .collect::<Vec<_>>();
+        segments.push(new_name.to_string());
+        return Some((segment.syntax().text_range(), segments.join("::")));
+    }
+
+    // The base isn't an ancestor of the new parent: fall back to an absolute
+    // path, if the reference is in the same crate.
+    if nested_prefix.is_some() || base.krate() != module.krate() {
+        return None;
+    }
+    let mut segments = to_new_parent
+        .iter()
+        .rev()
+        .filter_map(|it| it.name(db))
+        .map(|it| it.to_string())
+        .collect::<Vec<_>>();
+    segments.insert(0, "crate".to_string());
+    segments.push(new_name.to_string());
+    Some((path.syntax().text_range(), segments.join("::")))
+}
+
+fn resolve_module(sema: &Semantics<RootDatabase>, path: &ast::Path) -> Option<Module> {
+    match sema.resolve_path(path)? {
+        PathResolution::Def(ModuleDef::Module(it)) => Some(it),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::RelativePath;
+    use test_utils::assert_eq_text;
+
+    use crate::mock_analysis::MockAnalysis;
+
+    fn check_rename_file(fixture: &str, file: &str, new_path: &str, expected: &[(&str, &str)]) {
+        let mock = MockAnalysis::with_files(fixture);
+        let file_id = mock.id_of(file);
+        let expected =
+            expected.iter().map(|(path, text)| (mock.id_of(path), *text)).collect::<Vec<_>>();
+        let analysis = mock.analysis();
+        let source_change =
+            analysis.will_rename_file(file_id, RelativePath::new(new_path)).unwrap().unwrap();
+        let actual = source_change
+            .source_file_edits
+            .iter()
+            .map(|it| (it.file_id, it.edit.apply(&analysis.file_text(it.file_id).unwrap())))
+            .collect::<Vec<_>>();
+        assert_eq!(actual.len(), expected.len());
+        for ((actual_id, actual_text), (expected_id, expected_text)) in actual.iter().zip(expected)
+        {
+            assert_eq!(*actual_id, expected_id);
+            assert_eq_text!(expected_text.trim(), actual_text.trim());
+        }
+    }
+
+    fn check_no_rename_file(fixture: &str, file: &str, new_path: &str) {
+        let mock = MockAnalysis::with_files(fixture);
+        let file_id = mock.id_of(file);
+        let analysis = mock.analysis();
+        let source_change = analysis.will_rename_file(file_id, RelativePath::new(new_path));
+        assert!(source_change.unwrap().is_none());
+    }
+
+    #[test]
+    fn rename_file_in_same_dir() {
+        check_rename_file(
+            r"
+            //- /lib.rs
+            mod foo;
+            fn f() { foo::g(); }
+
+            //- /foo.rs
+            pub fn g() {}
+            ",
+            "/foo.rs",
+            "bar.rs",
+            &[("/lib.rs", "mod bar;\nfn f() { bar::g(); }")],
+        );
+    }
+
+    #[test]
+    fn move_file_into_child_module_dir() {
+        check_rename_file(
+            r"
+            //- /lib.rs
+            mod sub;
+            mod foo;
+            use crate::foo::S;
+            use crate::{foo::T, sub::h};
+
+            //- /sub.rs
+            pub fn h() {}
+
+            //- /foo.rs
+            pub struct S;
+            pub struct T;
+            ",
+            "/foo.rs",
+            "sub/bar.rs",
+            &[
+                ("/lib.rs", "mod sub;\nuse crate::sub::bar::S;\nuse crate::{sub::bar::T, sub::h};"),
+                ("/sub.rs", "mod bar;\n\npub fn h() {}"),
+            ],
+        );
+    }
+
+    #[test]
+    fn move_file_out_of_mod_rs_dir() {
+        check_rename_file(
+            r"
+            //- /lib.rs
+            mod sub;
+            fn f() { sub::foo::g(); }
+
+            //- /sub/mod.rs
+            pub mod foo;
+            fn h() { foo::g(); }
+
+            //- /sub/foo.rs
+            pub fn g() {}
+            ",
+            "/sub/foo.rs",
+            "foo.rs",
+            &[
+                ("/lib.rs", "mod sub;\npub mod foo;\nfn f() { crate::foo::g(); }"),
+                ("/sub/mod.rs", "fn h() { crate::foo::g(); }"),
+            ],
+        );
+    }
+
+    #[test]
+    fn rename_mod_rs_dir() {
+        check_rename_file(
+            r"
+            //- /lib.rs
+            mod foo;
+            use foo::S;
+
+            //- /foo/mod.rs
+            pub struct S;
+            ",
+            "/foo/mod.rs",
+            "bar/mod.rs",
+            &[("/lib.rs", "mod bar;\nuse bar::S;")],
+        );
+    }
+
+    #[test]
+    fn no_rename_file_without_parent_module() {
+        check_no_rename_file(
+            r"
+            //- /lib.rs
+            mod foo;
+
+            //- /foo.rs
+            pub fn g() {}
+            ",
+            "/foo.rs",
+            "nowhere/foo.rs",
+        );
+    }
+
+    #[test]
+    fn no_rename_crate_root() {
+        check_no_rename_file(
+            r"
+            //- /lib.rs
+            mod foo;
+
+            //- /foo.rs
+            pub fn g() {}
+            ",
+            "/lib.rs",
+            "main.rs",
+        );
+    }
+
+    #[test]
+    fn no_rename_file_to_invalid_module_name() {
+        check_no_rename_file(
+            r"
+            //- /lib.rs
+            mod foo;
+
+            //- /foo.rs
+            pub fn g() {}
+            ",
+            "/foo.rs",
+            "foo-bar.rs",
+        );
+    }
+}
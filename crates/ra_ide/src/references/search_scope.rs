@@ -2,10 +2,14 @@
 //! For `pub(crate)` things it's a crate, for `pub` things it's a crate and dependant crates.
 //! In some cases, the location of the references is known to within a `TextRange`,
 //! e.g. for things like local variables.
-use std::mem;
-
-use hir::{DefWithBody, HasSource, ModuleSource};
-use ra_db::{FileId, SourceDatabaseExt};
+use std::{collections::hash_map::Entry, mem};
+
+use either::Either;
+use hir::{
+    original_range, Adt, AsAssocItem, AssocItemContainer, Crate, DefWithBody, EnumVariant,
+    HasSource, Module, ModuleDef, ModuleSource, VariantDef,
+};
+use ra_db::{FileId, FileRange};
 use ra_prof::profile;
 use ra_syntax::{AstNode, TextRange};
 use rustc_hash::FxHashMap;
@@ -23,88 +27,153 @@ impl SearchScope {
         SearchScope { entries: FxHashMap::default() }
     }
 
+    /// Computes the files, or parts of files, from which `def` is visible:
+    ///
+    /// * locals and generic parameters are limited to the item defining them,
+    /// * private items to their module and its descendants,
+    /// * `pub(crate)` items to the defining crate,
+    /// * `pub` items to the defining crate and the crates depending on it.
     pub(crate) fn for_def(def: &NameDefinition, db: &RootDatabase) -> SearchScope {
         let _p = profile("search_scope");
         let module = match def.module(db) {
             Some(it) => it,
             None => return SearchScope::empty(),
         };
-        let module_src = module.definition_source(db);
-        let file_id = module_src.file_id.original_file(db);
-
-        if let NameDefinition::Local(var) = def {
-            let range = match var.parent(db) {
-                DefWithBody::Function(f) => f.source(db).value.syntax().text_range(),
-                DefWithBody::Const(c) => c.source(db).value.syntax().text_range(),
-                DefWithBody::Static(s) => s.source(db).value.syntax().text_range(),
-            };
-            let mut res = FxHashMap::default();
-            res.insert(file_id, Some(range));
-            return SearchScope::new(res);
-        }
 
-        let vis = def.visibility(db).as_ref().map(|v| v.syntax().to_string()).unwrap_or_default();
-
-        if vis.as_str() == "pub(super)" {
-            if let Some(parent_module) = module.parent(db) {
-                let mut res = FxHashMap::default();
-                let parent_src = parent_module.definition_source(db);
-                let file_id = parent_src.file_id.original_file(db);
-
-                match parent_src.value {
-                    ModuleSource::Module(m) => {
-                        let range = Some(m.syntax().text_range());
-                        res.insert(file_id, range);
+        match def {
+            NameDefinition::Local(var) => {
+                let frange = match var.parent(db) {
+                    DefWithBody::Function(f) => {
+                        let src = f.source(db);
+                        original_range(db, src.as_ref().map(|it| it.syntax()))
                     }
-                    ModuleSource::SourceFile(_) => {
-                        res.insert(file_id, None);
-                        res.extend(parent_module.children(db).map(|m| {
-                            let src = m.definition_source(db);
-                            (src.file_id.original_file(db), None)
-                        }));
+                    DefWithBody::Const(c) => {
+                        let src = c.source(db);
+                        original_range(db, src.as_ref().map(|it| it.syntax()))
                     }
-                }
-                return SearchScope::new(res);
-            }
-        }
-
-        if vis.as_str() != "" {
-            let source_root_id = db.file_source_root(file_id);
-            let source_root = db.source_root(source_root_id);
-            let mut res = source_root.walk().map(|id| (id, None)).collect::<FxHashMap<_, _>>();
-
-            // FIXME: add "pub(in path)"
-
-            if vis.as_str() == "pub(crate)" {
-                return SearchScope::new(res);
+                    DefWithBody::Static(s) => {
+                        let src = s.source(db);
+                        original_range(db, src.as_ref().map(|it| it.syntax()))
+                    }
+                };
+                return SearchScope::file_range(frange);
             }
-            if vis.as_str() == "pub" {
-                let krate = module.krate();
-                for rev_dep in krate.reverse_dependencies(db) {
-                    let root_file = rev_dep.root_file(db);
-                    let source_root_id = db.file_source_root(root_file);
-                    let source_root = db.source_root(source_root_id);
-                    res.extend(source_root.walk().map(|id| (id, None)));
+            NameDefinition::TypeParam(param) => {
+                let src = param.source(db);
+                let owner = match &src.value {
+                    // `Self` of a trait
+                    Either::Left(it) => Some(it.syntax().clone()),
+                    Either::Right(it) => {
+                        it.syntax().parent().and_then(|param_list| param_list.parent())
+                    }
+                };
+                if let Some(owner) = owner {
+                    return SearchScope::file_range(original_range(db, src.with_value(&owner)));
                 }
-                return SearchScope::new(res);
             }
+            NameDefinition::SelfType(imp) => {
+                let src = imp.source(db);
+                return SearchScope::file_range(original_range(
+                    db,
+                    src.as_ref().map(|it| it.syntax()),
+                ));
+            }
+            _ => (),
         }
 
-        let mut res = FxHashMap::default();
-        let range = match module_src.value {
-            ModuleSource::Module(m) => Some(m.syntax().text_range()),
-            ModuleSource::SourceFile(_) => None,
+        let vis = match def {
+            // Exported macros are usable from other crates, and the textual
+            // scope of the other ones is not tracked.
+            NameDefinition::Macro(_) => "pub".to_string(),
+            _ => match visibility_parent(def, db) {
+                Some(parent) => visibility_text(&parent, db),
+                None => visibility_text(def, db),
+            },
         };
-        res.insert(file_id, range);
-        SearchScope::new(res)
+
+        match vis.as_str() {
+            "" | "pub(self)" => SearchScope::module(db, module),
+            "pub(super)" => SearchScope::module(db, module.parent(db).unwrap_or(module)),
+            "pub" => module
+                .krate()
+                .transitive_reverse_dependencies(db)
+                .into_iter()
+                .map(|krate| SearchScope::krate(db, krate))
+                .fold(SearchScope::empty(), SearchScope::union),
+            // FIXME: `pub(in path)` could be limited to the given module
+            _ => SearchScope::krate(db, module.krate()),
+        }
     }
 
     fn new(entries: FxHashMap<FileId, Option<TextRange>>) -> SearchScope {
         SearchScope { entries }
     }
+
     pub fn single_file(file: FileId) -> SearchScope {
         SearchScope::new(std::iter::once((file, None)).collect())
     }
+
+    pub fn files(files: impl IntoIterator<Item = FileId>) -> SearchScope {
+        SearchScope::new(files.into_iter().map(|file| (file, None)).collect())
+    }
+
+    pub fn file_range(range: FileRange) -> SearchScope {
+        SearchScope::new(std::iter::once((range.file_id, Some(range.range))).collect())
+    }
+
+    /// The files of all modules of `krate`.
+    pub(crate) fn krate(db: &RootDatabase, krate: Crate) -> SearchScope {
+        match krate.root_module(db) {
+            Some(root) => SearchScope::module(db, root),
+            None => SearchScope::empty(),
+        }
+    }
+
+    /// The files of `module` and of all its descendants. Inline modules only
+    /// cover their own range of the file.
+    pub(crate) fn module(db: &RootDatabase, module: Module) -> SearchScope {
+        let mut res = SearchScope::empty();
+        let mut stack = vec![module];
+        while let Some(module) = stack.pop() {
+            let src = module.definition_source(db);
+            let frange = match &src.value {
+                ModuleSource::Module(it) => original_range(db, src.with_value(it.syntax())),
+                ModuleSource::SourceFile(_) => {
+                    let file_id = src.file_id.original_file(db);
+                    res.add(file_id, None);
+                    stack.extend(module.children(db));
+                    continue;
+                }
+            };
+            res.add(frange.file_id, Some(frange.range));
+            stack.extend(module.children(db));
+        }
+        res
+    }
+
+    /// Returns a scope containing the files of both `self` and `other`.
+    pub fn union(mut self, other: SearchScope) -> SearchScope {
+        for (file_id, range) in other.entries {
+            self.add(file_id, range);
+        }
+        self
+    }
+
+    fn add(&mut self, file_id: FileId, range: Option<TextRange>) {
+        match self.entries.entry(file_id) {
+            Entry::Vacant(it) => {
+                it.insert(range);
+            }
+            Entry::Occupied(mut it) => {
+                let merged = match (*it.get(), range) {
+                    (Some(r1), Some(r2)) => Some(r1.extend_to(&r2)),
+                    _ => None,
+                };
+                it.insert(merged);
+            }
+        }
+    }
+
     pub(crate) fn intersection(&self, other: &SearchScope) -> SearchScope {
         let (mut small, mut large) = (&self.entries, &other.entries);
         if small.len() > large.len() {
@@ -136,6 +205,32 @@ impl SearchScope {
     }
 }
 
+fn visibility_text(def: &NameDefinition, db: &RootDatabase) -> String {
+    def.visibility(db).map(|it| it.syntax().to_string()).unwrap_or_default()
+}
+
+/// Enum variants, their fields and trait items can't have a visibility of
+/// their own, they are as visible as the enum or trait containing them.
+fn visibility_parent(def: &NameDefinition, db: &RootDatabase) -> Option<NameDefinition> {
+    let parent_enum =
+        |it: EnumVariant| NameDefinition::ModuleDef(ModuleDef::Adt(Adt::Enum(it.parent_enum(db))));
+    let assoc_item = match def {
+        NameDefinition::ModuleDef(ModuleDef::EnumVariant(it)) => return Some(parent_enum(*it)),
+        NameDefinition::StructField(it) => match it.parent_def(db) {
+            VariantDef::EnumVariant(it) => return Some(parent_enum(it)),
+            VariantDef::Struct(_) | VariantDef::Union(_) => return None,
+        },
+        NameDefinition::ModuleDef(ModuleDef::Function(it)) => it.as_assoc_item(db)?,
+        NameDefinition::ModuleDef(ModuleDef::Const(it)) => it.as_assoc_item(db)?,
+        NameDefinition::ModuleDef(ModuleDef::TypeAlias(it)) => it.as_assoc_item(db)?,
+        _ => return None,
+    };
+    match assoc_item.container(db) {
+        AssocItemContainer::Trait(it) => Some(NameDefinition::ModuleDef(ModuleDef::Trait(it))),
+        AssocItemContainer::ImplBlock(_) => None,
+    }
+}
+
 impl IntoIterator for SearchScope {
     type Item = (FileId, Option<TextRange>);
     type IntoIter = std::collections::hash_map::IntoIter<FileId, Option<TextRange>>;
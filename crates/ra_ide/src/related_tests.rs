@@ -0,0 +1,140 @@
+//! Finds the tests which call the function at the cursor, directly or through
+//! a few other functions, so that they can be run after changing it.
+
+use hir::Semantics;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, NameOwner},
+    AstNode,
+};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    references,
+    runnables::{has_test_related_attribute, runnable_fn, Runnable},
+    FileId, FilePosition,
+};
+
+/// How many levels of callers are searched for tests.
+const MAX_DEPTH: usize = 2;
+
+pub(crate) fn related_tests(db: &RootDatabase, position: FilePosition) -> Vec<(FileId, Runnable)> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let fn_def = match find_node_at_offset::<ast::FnDef>(file.syntax(), position.offset) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+
+    let mut res = Vec::new();
+    let mut visited = FxHashSet::default();
+    let mut callees = vec![(position.file_id, fn_def)];
+    for _ in 0..MAX_DEPTH {
+        let mut callers = Vec::new();
+        for (file_id, callee) in callees {
+            let name = match callee.name() {
+                Some(it) => it,
+                None => continue,
+            };
+            let position = FilePosition { file_id, offset: name.syntax().text_range().start() };
+            let refs = match references::find_all_refs(db, position, None) {
+                Some(it) => it.info,
+                None => continue,
+            };
+            for reference in refs.references() {
+                let file_id = reference.file_range.file_id;
+                let file = sema.parse(file_id);
+                let caller = match find_node_at_offset::<ast::FnDef>(
+                    file.syntax(),
+                    reference.file_range.range.start(),
+                ) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                if !visited.insert((file_id, caller.syntax().text_range())) {
+                    continue;
+                }
+                if has_test_related_attribute(&caller) {
+                    res.extend(runnable_fn(&sema, caller).map(|it| (file_id, it)));
+                } else {
+                    callers.push((file_id, caller));
+                }
+            }
+        }
+        callees = callers;
+    }
+    res.sort_by_key(|(file_id, runnable)| (*file_id, runnable.range.start()));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::analysis_and_position;
+
+    fn related_test_ids(fixture: &str) -> Vec<String> {
+        let (analysis, position) = analysis_and_position(fixture);
+        analysis
+            .related_tests(position)
+            .unwrap()
+            .into_iter()
+            .map(|(_, runnable)| match runnable.kind {
+                crate::RunnableKind::Test { test_id } => test_id.to_string(),
+                kind => panic!("not a test: {:?}", kind),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_related_tests_of_helper() {
+        let ids = related_test_ids(
+            r#"
+            //- /lib.rs
+            fn helper<|>() {}
+
+            fn caller() {
+                helper();
+            }
+
+            fn unrelated() {}
+
+            #[cfg(test)]
+            mod tests {
+                use super::*;
+
+                #[test]
+                fn test_direct() {
+                    helper();
+                }
+
+                #[tokio::test]
+                async fn test_through_caller() {
+                    caller();
+                }
+
+                #[test]
+                fn test_unrelated() {
+                    unrelated();
+                }
+            }
+            "#,
+        );
+        assert_eq!(ids, vec!["tests::test_direct", "tests::test_through_caller"]);
+    }
+
+    #[test]
+    fn test_related_tests_depth_is_limited() {
+        let ids = related_test_ids(
+            r#"
+            //- /lib.rs
+            fn helper<|>() {}
+            fn caller1() { helper(); }
+            fn caller2() { caller1(); }
+
+            #[test]
+            fn test_far() { caller2(); }
+            "#,
+        );
+        assert!(ids.is_empty());
+    }
+}
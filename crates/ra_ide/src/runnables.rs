@@ -56,7 +56,7 @@ fn runnable(sema: &Semantics<RootDatabase>, item: SyntaxNode) -> Option<Runnable
     }
 }
 
-fn runnable_fn(sema: &Semantics<RootDatabase>, fn_def: ast::FnDef) -> Option<Runnable> {
+pub(crate) fn runnable_fn(sema: &Semantics<RootDatabase>, fn_def: ast::FnDef) -> Option<Runnable> {
     let name_string = fn_def.name()?.text().to_string();
 
     let kind = if name_string == "main" {
@@ -93,7 +93,7 @@ fn runnable_fn(sema: &Semantics<RootDatabase>, fn_def: ast::FnDef) -> Option<Run
 ///
 /// It may produce false positives, for example, `#[wasm_bindgen_test]` requires a different command to run the test,
 /// but it's better than not to have the runnables for the tests at all.
-fn has_test_related_attribute(fn_def: &ast::FnDef) -> bool {
+pub(crate) fn has_test_related_attribute(fn_def: &ast::FnDef) -> bool {
     fn_def
         .attrs()
         .filter_map(|attr| attr.path())
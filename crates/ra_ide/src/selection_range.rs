@@ -0,0 +1,126 @@
+//! Computes the chain of ranges for `textDocument/selectionRange`: the ranges
+//! `extend_selection` goes through from the cursor up to the whole file, plus a
+//! few "logical" ranges which don't correspond to a syntax node, like the
+//! contents of a string literal or a link `.foo()` of a method call chain.
+
+use ra_db::SourceDatabase;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    algo::find_covering_element,
+    ast::{self, AstNode},
+    NodeOrToken,
+    SyntaxKind::*,
+    SyntaxNode, TextRange, TextUnit, T,
+};
+
+use crate::{extend_selection::extend_selection, FilePosition, FileRange};
+
+/// Returns the strictly growing ranges containing the position, from the
+/// innermost to the whole file.
+pub(crate) fn selection_ranges(db: &RootDatabase, position: FilePosition) -> Vec<TextRange> {
+    let root = db.parse(position.file_id).tree().syntax().clone();
+    let mut res = Vec::new();
+    let mut range = TextRange::offset_len(position.offset, 0.into());
+    loop {
+        let next = extend_selection(db, FileRange { file_id: position.file_id, range });
+        if next == range {
+            break;
+        }
+        if !range.is_empty() {
+            res.extend(logical_range(&root, range, next));
+        }
+        res.push(next);
+        range = next;
+    }
+    res
+}
+
+/// A range between `range` and its syntactic extension `next`.
+fn logical_range(root: &SyntaxNode, range: TextRange, next: TextRange) -> Option<TextRange> {
+    let res = match find_covering_element(root, next) {
+        NodeOrToken::Token(token) => match token.kind() {
+            STRING | RAW_STRING | BYTE_STRING | RAW_BYTE_STRING if token.text_range() == next => {
+                let text = token.text().as_str();
+                let start = text.find('"')? + 1;
+                let end = text.rfind('"')?;
+                if end < start {
+                    return None;
+                }
+                let offset = token.text_range().start();
+                TextRange::from_to(
+                    offset + TextUnit::from_usize(start),
+                    offset + TextUnit::from_usize(end),
+                )
+            }
+            _ => return None,
+        },
+        NodeOrToken::Node(node) => {
+            let call = node
+                .ancestors()
+                .take_while(|it| it.text_range() == next)
+                .find_map(ast::MethodCallExpr::cast)?;
+            let dot = call.syntax().children_with_tokens().find(|it| it.kind() == T![.])?;
+            TextRange::from_to(dot.text_range().start(), next.end())
+        }
+    };
+    if range.is_subrange(&res) && res != range && res != next {
+        Some(res)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::single_file_with_position;
+
+    fn check_selection_ranges(fixture: &str, expected: &[&str]) {
+        let (analysis, position) = single_file_with_position(fixture);
+        let text = analysis.file_text(position.file_id).unwrap();
+        let actual = analysis
+            .selection_ranges(position)
+            .unwrap()
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn selection_ranges_in_nested_closure_argument() {
+        check_selection_ranges(
+            "fn foo() {\n    xs.iter().map(|x| x.add(1<|>0)).count();\n}\n",
+            &[
+                "10",
+                "(10)",
+                ".add(10)",
+                "x.add(10)",
+                "|x| x.add(10)",
+                "(|x| x.add(10))",
+                ".map(|x| x.add(10))",
+                "xs.iter().map(|x| x.add(10))",
+                "xs.iter().map(|x| x.add(10)).count()",
+                "xs.iter().map(|x| x.add(10)).count();",
+                "{\n    xs.iter().map(|x| x.add(10)).count();\n}",
+                "fn foo() {\n    xs.iter().map(|x| x.add(10)).count();\n}",
+                "fn foo() {\n    xs.iter().map(|x| x.add(10)).count();\n}\n",
+            ],
+        );
+    }
+
+    #[test]
+    fn selection_ranges_in_string_literal() {
+        check_selection_ranges(
+            "fn foo() {\n    let s = \"hello wor<|>ld\";\n}\n",
+            &[
+                "world",
+                "hello world",
+                "\"hello world\"",
+                "let s = \"hello world\";",
+                "{\n    let s = \"hello world\";\n}",
+                "fn foo() {\n    let s = \"hello world\";\n}",
+                "fn foo() {\n    let s = \"hello world\";\n}\n",
+            ],
+        );
+    }
+}
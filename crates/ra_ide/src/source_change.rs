@@ -3,8 +3,10 @@
 //!
 //! It can be viewed as a dual for `AnalysisChange`.
 
+use std::{fmt, mem};
+
 use ra_db::RelativePathBuf;
-use ra_text_edit::TextEdit;
+use ra_text_edit::{TextEdit, TextEditConflict};
 
 use crate::{FileId, FilePosition, SourceRootId, TextUnit};
 
@@ -87,8 +89,44 @@ impl SourceChange {
         self.cursor_position = cursor_position;
         self
     }
+
+    /// Merges all `SourceFileEdit`s of the same file into one, so that they
+    /// can be applied (and the cursor position translated) in one go.
+    /// Identical edits are only applied once, overlapping ones are an error.
+    pub fn merge_source_file_edits(mut self) -> Result<SourceChange, SourceFileEditConflict> {
+        let mut merged: Vec<SourceFileEdit> = Vec::with_capacity(self.source_file_edits.len());
+        for SourceFileEdit { file_id, edit } in self.source_file_edits {
+            match merged.iter_mut().find(|it| it.file_id == file_id) {
+                Some(existing) => {
+                    let existing_edit = mem::replace(&mut existing.edit, TextEdit::default());
+                    existing.edit = existing_edit
+                        .union(edit)
+                        .map_err(|conflict| SourceFileEditConflict { file_id, conflict })?;
+                }
+                None => merged.push(SourceFileEdit { file_id, edit }),
+            }
+        }
+        self.source_file_edits = merged;
+        Ok(self)
+    }
+}
+
+/// Two `SourceFileEdit`s of a `SourceChange` which change overlapping parts of
+/// a file.
+#[derive(Debug)]
+pub struct SourceFileEditConflict {
+    pub file_id: FileId,
+    pub conflict: TextEditConflict,
+}
+
+impl fmt::Display for SourceFileEditConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "conflicting edits in {:?}: {}", self.file_id, self.conflict)
+    }
 }
 
+impl std::error::Error for SourceFileEditConflict {}
+
 #[derive(Debug)]
 pub struct SourceFileEdit {
     pub file_id: FileId,
@@ -117,3 +155,51 @@ impl SingleFileChange {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ra_syntax::TextRange;
+    use ra_text_edit::TextEdit;
+
+    use crate::{FileId, SourceChange, SourceFileEdit};
+
+    fn replace(file_id: u32, start: u32, end: u32, text: &str) -> SourceFileEdit {
+        let range = TextRange::from_to(start.into(), end.into());
+        SourceFileEdit {
+            file_id: FileId(file_id),
+            edit: TextEdit::replace(range, text.to_string()),
+        }
+    }
+
+    #[test]
+    fn merges_edits_of_the_same_file() {
+        let change = SourceChange::source_file_edits(
+            "rename",
+            vec![replace(1, 4, 7, "bar"), replace(2, 0, 3, "bar"), replace(1, 0, 3, "bar")],
+        );
+        let change = change.merge_source_file_edits().unwrap();
+        let texts: Vec<_> = change
+            .source_file_edits
+            .iter()
+            .map(|it| (it.file_id, it.edit.apply("foo foo")))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![(FileId(1), "bar bar".to_string()), (FileId(2), "bar foo".to_string())]
+        );
+    }
+
+    #[test]
+    fn reports_conflicting_edits() {
+        let change = SourceChange::source_file_edits(
+            "fix",
+            vec![replace(1, 0, 7, "bar"), replace(1, 4, 7, "baz")],
+        );
+        let conflict = change.merge_source_file_edits().unwrap_err();
+        assert_eq!(conflict.file_id, FileId(1));
+        assert_eq!(
+            conflict.to_string(),
+            "conflicting edits in FileId(1): edits of [0; 7) and [4; 7) overlap"
+        );
+    }
+}
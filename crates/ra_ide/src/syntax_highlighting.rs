@@ -5,7 +5,7 @@ mod html;
 #[cfg(test)]
 mod tests;
 
-use hir::{Name, Semantics};
+use hir::{Local, Semantics};
 use ra_ide_db::{
     defs::{classify_name, NameDefinition},
     RootDatabase,
@@ -14,7 +14,6 @@ use ra_prof::profile;
 use ra_syntax::{
     ast, AstNode, Direction, NodeOrToken, SyntaxElement, SyntaxKind::*, TextRange, WalkEvent, T,
 };
-use rustc_hash::FxHashMap;
 
 use crate::{references::classify_name_ref, FileId};
 
@@ -51,7 +50,6 @@ pub(crate) fn highlight(
         }
     };
 
-    let mut bindings_shadow_count: FxHashMap<Name, u32> = FxHashMap::default();
     let mut res = Vec::new();
 
     let mut current_macro_call: Option<ast::MacroCall> = None;
@@ -113,9 +111,7 @@ pub(crate) fn highlight(
             element
         };
 
-        if let Some((highlight, binding_hash)) =
-            highlight_element(&sema, &mut bindings_shadow_count, element_to_highlight)
-        {
+        if let Some((highlight, binding_hash)) = highlight_element(&sema, element_to_highlight) {
             res.push(HighlightedRange { range, highlight, binding_hash });
         }
     }
@@ -141,28 +137,18 @@ fn macro_call_range(macro_call: &ast::MacroCall) -> Option<TextRange> {
 
 fn highlight_element(
     sema: &Semantics<RootDatabase>,
-    bindings_shadow_count: &mut FxHashMap<Name, u32>,
     element: SyntaxElement,
 ) -> Option<(Highlight, Option<u64>)> {
     let db = sema.db;
     let mut binding_hash = None;
     let highlight: Highlight = match element.kind() {
-        FN_DEF => {
-            bindings_shadow_count.clear();
-            return None;
-        }
-
         // Highlight definitions depending on the "type" of the definition.
         NAME => {
             let name = element.into_node().and_then(ast::Name::cast).unwrap();
             let name_kind = classify_name(sema, &name);
 
             if let Some(NameDefinition::Local(local)) = &name_kind {
-                if let Some(name) = local.name(db) {
-                    let shadow_count = bindings_shadow_count.entry(name.clone()).or_default();
-                    *shadow_count += 1;
-                    binding_hash = Some(calc_binding_hash(&name, *shadow_count))
-                }
+                binding_hash = calc_binding_hash(db, *local);
             };
 
             match name_kind {
@@ -180,13 +166,14 @@ fn highlight_element(
         NAME_REF if element.ancestors().any(|it| it.kind() == ATTR) => return None,
         NAME_REF => {
             let name_ref = element.into_node().and_then(ast::NameRef::cast).unwrap();
-            let name_kind = classify_name_ref(sema, &name_ref)?;
+            let name_kind = classify_name_ref(sema, &name_ref)?.definition();
+
+            if is_unsafe_op(db, &name_ref, &name_kind) {
+                return Some((HighlightTag::UnsafeOp.into(), None));
+            }
 
             if let NameDefinition::Local(local) = &name_kind {
-                if let Some(name) = local.name(db) {
-                    let shadow_count = bindings_shadow_count.entry(name.clone()).or_default();
-                    binding_hash = Some(calc_binding_hash(&name, *shadow_count))
-                }
+                binding_hash = calc_binding_hash(db, *local);
             };
 
             highlight_name(db, name_kind)
@@ -201,6 +188,16 @@ fn highlight_element(
         CHAR => HighlightTag::LiteralChar.into(),
         LIFETIME => HighlightTag::TypeLifetime.into(),
 
+        // Dereference of a raw pointer
+        T![*] => {
+            let prefix_expr = element.parent().and_then(ast::PrefixExpr::cast)?;
+            let ty = sema.type_of_expr(&prefix_expr.expr()?)?;
+            if !ty.is_raw_ptr() {
+                return None;
+            }
+            HighlightTag::UnsafeOp.into()
+        }
+
         k if k.is_keyword() => {
             let h = Highlight::new(HighlightTag::Keyword);
             match k {
@@ -223,7 +220,10 @@ fn highlight_element(
 
     return Some((highlight, binding_hash));
 
-    fn calc_binding_hash(name: &Name, shadow_count: u32) -> u64 {
+    /// Bindings are told apart by the start of their defining pattern, so that
+    /// all references to a binding share its hash, while distinct bindings of
+    /// the same name (shadowed or in sibling scopes) get different ones.
+    fn calc_binding_hash(db: &RootDatabase, local: Local) -> Option<u64> {
         fn hash<T: std::hash::Hash + std::fmt::Debug>(x: T) -> u64 {
             use std::{collections::hash_map::DefaultHasher, hash::Hasher};
 
@@ -232,7 +232,47 @@ fn highlight_element(
             hasher.finish()
         }
 
-        hash((name, shadow_count))
+        let name = local.name(db)?;
+        let def_range = local.source(db).value.either(
+            |bind_pat| bind_pat.syntax().text_range(),
+            |self_param| self_param.syntax().text_range(),
+        );
+        Some(hash((name, u32::from(def_range.start()))))
+    }
+}
+
+/// Checks whether `name_ref` calls an `unsafe fn` or accesses a field of a
+/// union.
+fn is_unsafe_op(db: &RootDatabase, name_ref: &ast::NameRef, def: &NameDefinition) -> bool {
+    let parent = match name_ref.syntax().parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    match def {
+        NameDefinition::ModuleDef(hir::ModuleDef::Function(func)) if func.is_unsafe(db) => {
+            if ast::MethodCallExpr::can_cast(parent.kind()) {
+                return true;
+            }
+            // `foo()` or `S::foo()`, but not `foo` used as a value
+            let path = match ast::PathSegment::cast(parent) {
+                Some(it) => it.parent_path(),
+                None => return false,
+            };
+            path.syntax()
+                .parent()
+                .and_then(ast::PathExpr::cast)
+                .and_then(|it| it.syntax().parent())
+                .and_then(ast::CallExpr::cast)
+                .is_some()
+        }
+        NameDefinition::StructField(field) => {
+            ast::FieldExpr::can_cast(parent.kind())
+                && match field.parent_def(db) {
+                    hir::VariantDef::Union(_) => true,
+                    _ => false,
+                }
+        }
+        _ => false,
     }
 }
 
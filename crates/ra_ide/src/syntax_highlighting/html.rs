@@ -100,5 +100,6 @@ pre                 { color: #DCDCCC; background: #3F3F3F; font-size: 22px; padd
 .keyword            { color: #F0DFAF; }
 .keyword.unsafe     { color: #DFAF8F; }
 .keyword.control    { color: #F0DFAF; font-weight: bold; }
+.unsafe_op          { color: #BC8383; }
 </style>
 ";
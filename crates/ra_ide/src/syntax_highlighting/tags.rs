@@ -35,6 +35,10 @@ pub enum HighlightTag {
     Attribute,
 
     Keyword,
+
+    /// A raw pointer dereference, a call to an `unsafe fn` or an access to a
+    /// union field.
+    UnsafeOp,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -67,6 +71,7 @@ impl HighlightTag {
             HighlightTag::LiteralString => "string",
             HighlightTag::Attribute => "attribute",
             HighlightTag::Keyword => "keyword",
+            HighlightTag::UnsafeOp => "unsafe_op",
         }
     }
 }
@@ -66,7 +66,7 @@ fn syntax_tree_for_token(node: &SyntaxToken, text_range: TextRange) -> Option<St
     let len = len.min(node_len);
 
     // Ensure our slice is inside the actual string
-    let end = if start + len < text.len() { start + len } else { text.len() - start };
+    let end = (start + len).min(text.len());
 
     let text = &text[start..end];
 
@@ -352,6 +352,35 @@ SOURCE_FILE@[0; 25)
         L_CURLY@[22; 23) "{"
         WHITESPACE@[23; 24) "\n"
         R_CURLY@[24; 25) "}"
+"#
+            .trim()
+        );
+
+        // With the selection ending after the closing quote
+        let (analysis, range) = single_file_with_range(
+            r#"fn test() {
+    assert!("  <|>fn foo() {}"<|>, "");
+}"#
+            .trim(),
+        );
+        let syn = analysis.syntax_tree(range.file_id, Some(range.range)).unwrap();
+        assert_eq_text!(
+            syn.trim(),
+            r#"
+SOURCE_FILE@[0; 11)
+  FN_DEF@[0; 11)
+    FN_KW@[0; 2) "fn"
+    WHITESPACE@[2; 3) " "
+    NAME@[3; 6)
+      IDENT@[3; 6) "foo"
+    PARAM_LIST@[6; 8)
+      L_PAREN@[6; 7) "("
+      R_PAREN@[7; 8) ")"
+    WHITESPACE@[8; 9) " "
+    BLOCK_EXPR@[9; 11)
+      BLOCK@[9; 11)
+        L_CURLY@[9; 10) "{"
+        R_CURLY@[10; 11) "}"
 "#
             .trim()
         );
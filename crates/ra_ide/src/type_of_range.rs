@@ -0,0 +1,177 @@
+//! Computes the type of the expression or pattern selected in the editor.
+
+use hir::Semantics;
+use ra_db::FileRange;
+use ra_ide_db::RootDatabase;
+use ra_syntax::{
+    algo::find_covering_element,
+    ast::{self, AstNode},
+    NodeOrToken,
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+
+use crate::RangeInfo;
+
+/// Returns the type of the smallest expression or pattern covering the
+/// selection, together with the range of that expression. Leading and
+/// trailing whitespace and comments of the selection are ignored.
+pub(crate) fn type_of_range(db: &RootDatabase, frange: FileRange) -> Option<RangeInfo<String>> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(frange.file_id);
+    let range = trim_trivia(file.syntax(), frange.range)?;
+    let node = covering_node(file.syntax(), range);
+
+    let in_macro_call =
+        node.ancestors().take_while(|it| !is_boundary(it)).any(|it| it.kind() == TOKEN_TREE);
+    if in_macro_call {
+        return type_of_macro_arg(&sema, file.syntax(), range);
+    }
+
+    let node = expr_or_pat(&node)?;
+    let ty = type_of_node(&sema, &node)?;
+    Some(RangeInfo::new(node.text_range(), ty))
+}
+
+/// The selection is inside a macro call: find the expression in the expansion
+/// which the selected tokens were mapped to.
+fn type_of_macro_arg(
+    sema: &Semantics<RootDatabase>,
+    file: &SyntaxNode,
+    range: TextRange,
+) -> Option<RangeInfo<String>> {
+    let first = file.token_at_offset(range.start()).right_biased()?;
+    let last = file.token_at_offset(range.end()).left_biased()?;
+    let first = sema.descend_into_macros(first);
+    let last = sema.descend_into_macros(last);
+
+    // Both ends of the selection must be mapped into the same expansion.
+    let root = first.parent().ancestors().last()?;
+    if &root == file || last.parent().ancestors().last()? != root {
+        return None;
+    }
+
+    let node = covering_node(&root, first.text_range().extend_to(&last.text_range()));
+    let node = expr_or_pat(&node)?;
+    let ty = type_of_node(sema, &node)?;
+    Some(RangeInfo::new(sema.original_range(&node).range, ty))
+}
+
+fn trim_trivia(root: &SyntaxNode, range: TextRange) -> Option<TextRange> {
+    let node = covering_node(root, range);
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|it| !it.kind().is_trivia())
+        .filter(|it| it.text_range().end() > range.start() && it.text_range().start() < range.end())
+        .map(|it| it.text_range());
+    let first = tokens.next()?;
+    let last = tokens.last().unwrap_or(first);
+    Some(TextRange::from_to(first.start().max(range.start()), last.end().min(range.end())))
+}
+
+fn covering_node(root: &SyntaxNode, range: TextRange) -> SyntaxNode {
+    match find_covering_element(root, range) {
+        NodeOrToken::Node(it) => it,
+        NodeOrToken::Token(it) => it.parent(),
+    }
+}
+
+/// Finds the smallest expression or pattern containing `node`, without
+/// leaving the statement it is in.
+fn expr_or_pat(node: &SyntaxNode) -> Option<SyntaxNode> {
+    node.ancestors()
+        .take_while(|it| !is_boundary(it))
+        .find(|it| ast::Expr::can_cast(it.kind()) || ast::Pat::can_cast(it.kind()))
+}
+
+fn is_boundary(node: &SyntaxNode) -> bool {
+    match node.kind() {
+        LET_STMT | EXPR_STMT | BLOCK | ITEM_LIST | SOURCE_FILE => true,
+        kind => ast::ModuleItem::can_cast(kind),
+    }
+}
+
+fn type_of_node(sema: &Semantics<RootDatabase>, node: &SyntaxNode) -> Option<String> {
+    let ty = if let Some(expr) = ast::Expr::cast(node.clone()) {
+        sema.type_of_expr(&expr)?
+    } else {
+        sema.type_of_pat(&ast::Pat::cast(node.clone())?)?
+    };
+    Some(ty.display_truncated(sema.db, None).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::single_file_with_range;
+
+    fn check(fixture: &str) -> Option<(String, String)> {
+        let (analysis, frange) = single_file_with_range(fixture);
+        let text = analysis.file_text(frange.file_id).unwrap();
+        let res = analysis.type_of_range(frange).unwrap()?;
+        Some((res.info, text[res.range].to_string()))
+    }
+
+    #[test]
+    fn type_of_binary_subexpression() {
+        let res = check(
+            "
+            fn main() {
+                let x = 1 + <|> 2u32 * 3 <|>;
+            }
+            ",
+        );
+        assert_eq!(res, Some(("u32".to_string(), "2u32 * 3".to_string())));
+    }
+
+    #[test]
+    fn type_of_operator_sequence_is_covering_expression() {
+        let res = check(
+            "
+            fn main() {
+                let x = 1i64 <|>+ 2 +<|> 3;
+            }
+            ",
+        );
+        assert_eq!(res, Some(("i64".to_string(), "1i64 + 2 + 3".to_string())));
+    }
+
+    #[test]
+    fn type_of_statement_fails() {
+        let res = check(
+            "
+            fn main() {
+                <|>let x = 1 + 2;<|>
+            }
+            ",
+        );
+        assert_eq!(res, None);
+    }
+
+    #[test]
+    fn type_of_pattern() {
+        let res = check(
+            "
+            fn main() {
+                let (<|>a<|>, b) = (1u16, 2u8);
+            }
+            ",
+        );
+        assert_eq!(res, Some(("u16".to_string(), "a".to_string())));
+    }
+
+    #[test]
+    fn type_of_macro_argument() {
+        let res = check(
+            "
+            macro_rules! id {
+                ($e:expr) => { $e }
+            }
+            fn main() {
+                let x = id!(<|>1u64 * 2<|>);
+            }
+            ",
+        );
+        assert_eq!(res, Some(("u64".to_string(), "1u64 * 2".to_string())));
+    }
+}
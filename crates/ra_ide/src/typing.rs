@@ -105,6 +105,9 @@ fn on_char_typed_inner(
     char_typed: char,
 ) -> Option<SingleFileChange> {
     assert!(TRIGGER_CHARS.contains(char_typed));
+    if is_in_string_or_comment(file, offset) {
+        return None;
+    }
     match char_typed {
         '.' => on_dot_typed(file, offset),
         '=' => on_eq_typed(file, offset),
@@ -113,6 +116,18 @@ fn on_char_typed_inner(
     }
 }
 
+/// Characters typed inside of strings and comments are just text, and should
+/// never trigger any typing assists.
+fn is_in_string_or_comment(file: &SourceFile, offset: TextUnit) -> bool {
+    match file.syntax().token_at_offset(offset).right_biased() {
+        Some(token) => match token.kind() {
+            COMMENT | STRING | RAW_STRING | BYTE_STRING | RAW_BYTE_STRING | CHAR | BYTE => true,
+            _ => false,
+        },
+        None => false,
+    }
+}
+
 /// Returns an edit which should be applied after `=` was typed. Primarily,
 /// this works when adding `let =`.
 // FIXME: use a snippet completion instead of this hack here.
@@ -501,4 +516,26 @@ fn foo() {
     fn adds_space_after_return_type() {
         type_char('>', "fn foo() -<|>{ 92 }", "fn foo() -><|> { 92 }")
     }
+
+    #[test]
+    fn does_nothing_in_strings_and_comments() {
+        type_char_noop(
+            '=',
+            r"
+fn foo() {
+    let foo = 1 + 1 // let bar <|> 92
+}
+",
+        );
+        type_char_noop(
+            '.',
+            r#"
+fn foo() {
+    let foo = "foo
+    <|>bar";
+}
+"#,
+        );
+        type_char_noop('>', r#"fn foo() { "-<|>{ 92 }" }"#);
+    }
 }
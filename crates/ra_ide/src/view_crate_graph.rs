@@ -0,0 +1,189 @@
+//! Renders the crate graph in the DOT format of graphviz, to debug project
+//! loading: "why is my dependency not found?".
+
+use std::fmt::Write;
+
+use ra_db::{CrateGraph, CrateId, SourceDatabase, SourceDatabaseExt};
+use ra_ide_db::RootDatabase;
+use rustc_hash::FxHashSet;
+
+pub(crate) fn view_crate_graph(db: &RootDatabase, full: bool) -> String {
+    let crate_graph = db.crate_graph();
+    let mut crates = crate_graph.iter().collect::<Vec<_>>();
+    crates.sort_by_key(|it| it.0);
+    if !full {
+        let shown = crates
+            .iter()
+            .copied()
+            .filter(|&it| is_workspace_crate(db, &crate_graph, it))
+            .flat_map(|it| {
+                let deps = crate_graph.dependencies(it).map(|dep| dep.crate_id);
+                Some(it).into_iter().chain(deps)
+            })
+            .collect::<FxHashSet<_>>();
+        crates.retain(|it| shown.contains(it));
+    }
+
+    let mut buf = String::new();
+    buf.push_str("digraph rust_analyzer_crate_graph {\n");
+    for &crate_id in &crates {
+        let mut label = format!(
+            "{}\nedition {}",
+            crate_name(db, &crate_graph, crate_id),
+            crate_graph.edition(crate_id)
+        );
+        let cfg = cfg_options(&crate_graph, crate_id);
+        if !cfg.is_empty() {
+            write!(label, "\ncfg: {}", cfg.join(", ")).unwrap();
+        }
+        // The `Debug` escapes of a string are valid in a DOT string
+        writeln!(buf, "    {} [label={:?}];", node_id(crate_id), label).unwrap();
+    }
+    for &crate_id in &crates {
+        for dep in crate_graph.dependencies(crate_id) {
+            if crates.contains(&dep.crate_id) {
+                writeln!(
+                    buf,
+                    "    {} -> {} [label={:?}];",
+                    node_id(crate_id),
+                    node_id(dep.crate_id),
+                    dep.name.as_str()
+                )
+                .unwrap();
+            }
+        }
+    }
+    buf.push_str("}\n");
+    buf
+}
+
+fn is_workspace_crate(db: &RootDatabase, crate_graph: &CrateGraph, crate_id: CrateId) -> bool {
+    let source_root = db.file_source_root(crate_graph.crate_root(crate_id));
+    !db.source_root(source_root).is_library
+}
+
+fn crate_name(db: &RootDatabase, crate_graph: &CrateGraph, crate_id: CrateId) -> String {
+    if let Some(name) = db.debug_crate_name(crate_id) {
+        return name.to_string();
+    }
+    // Without a name from the project model, fall back to the name dependent
+    // crates use, or to the id.
+    crate_graph
+        .iter()
+        .find_map(|it| {
+            crate_graph
+                .dependencies(it)
+                .find(|dep| dep.crate_id == crate_id)
+                .map(|dep| dep.name.to_string())
+        })
+        .unwrap_or_else(|| node_id(crate_id))
+}
+
+fn cfg_options(crate_graph: &CrateGraph, crate_id: CrateId) -> Vec<String> {
+    let cfg_options = crate_graph.cfg_options(crate_id);
+    let mut atoms = cfg_options.atoms().map(|it| it.to_string()).collect::<Vec<_>>();
+    atoms.sort();
+    let mut key_values = cfg_options
+        .key_values()
+        .map(|(key, value)| format!("{}={:?}", key, value.as_str()))
+        .collect::<Vec<_>>();
+    key_values.sort();
+    atoms.extend(key_values);
+    atoms
+}
+
+fn node_id(crate_id: CrateId) -> String {
+    format!("crate{}", crate_id.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ra_cfg::CfgOptions;
+    use ra_db::{CrateName, Env, RelativePathBuf};
+    use test_utils::assert_eq_text;
+
+    use crate::{AnalysisChange, AnalysisHost, CrateGraph, Edition, FileId, SourceRootId};
+
+    /// Two workspace crates `a` and `b`, which both depend on the library
+    /// `shared`, which depends on the library `deep`.
+    fn analysis_host() -> AnalysisHost {
+        let crates = [
+            ("a", Edition::Edition2018, true),
+            ("b", Edition::Edition2015, true),
+            ("shared", Edition::Edition2018, false),
+            ("deep", Edition::Edition2018, false),
+        ];
+
+        let mut change = AnalysisChange::new();
+        let mut crate_graph = CrateGraph::default();
+        let mut ids = Vec::new();
+        for (i, &(name, edition, is_local)) in crates.iter().enumerate() {
+            let root = SourceRootId(i as u32);
+            let file_id = FileId(i as u32 + 1);
+            change.add_root(root, is_local);
+            change.add_file(
+                root,
+                file_id,
+                RelativePathBuf::from("src/lib.rs"),
+                Arc::new(String::new()),
+            );
+            let mut cfg_options = CfgOptions::default();
+            match name {
+                "a" => cfg_options.insert_atom("test".into()),
+                "shared" => cfg_options.insert_features(vec!["std".into()]),
+                _ => (),
+            }
+            let crate_id =
+                crate_graph.add_crate_root(file_id, edition, cfg_options, Env::default());
+            change.set_debug_crate_name(crate_id, name.to_string());
+            ids.push(crate_id);
+        }
+        let deps = [(0, 2, "shared"), (1, 0, "a"), (1, 2, "shared_renamed"), (2, 3, "deep")];
+        for &(from, to, name) in deps.iter() {
+            crate_graph.add_dep(ids[from], CrateName::new(name).unwrap(), ids[to]).unwrap();
+        }
+        change.set_crate_graph(crate_graph);
+
+        let mut host = AnalysisHost::default();
+        host.apply_change(change);
+        host
+    }
+
+    #[test]
+    fn view_crate_graph_of_workspace() {
+        let dot = analysis_host().analysis().view_crate_graph(false).unwrap();
+        assert_eq_text!(
+            r#"digraph rust_analyzer_crate_graph {
+    crate0 [label="a\nedition 2018\ncfg: test"];
+    crate1 [label="b\nedition 2015"];
+    crate2 [label="shared\nedition 2018\ncfg: feature=\"std\""];
+    crate0 -> crate2 [label="shared"];
+    crate1 -> crate0 [label="a"];
+    crate1 -> crate2 [label="shared_renamed"];
+}
+"#,
+            &dot
+        );
+    }
+
+    #[test]
+    fn view_full_crate_graph() {
+        let dot = analysis_host().analysis().view_crate_graph(true).unwrap();
+        assert_eq_text!(
+            r#"digraph rust_analyzer_crate_graph {
+    crate0 [label="a\nedition 2018\ncfg: test"];
+    crate1 [label="b\nedition 2015"];
+    crate2 [label="shared\nedition 2018\ncfg: feature=\"std\""];
+    crate3 [label="deep\nedition 2018"];
+    crate0 -> crate2 [label="shared"];
+    crate1 -> crate0 [label="a"];
+    crate1 -> crate2 [label="shared_renamed"];
+    crate2 -> crate3 [label="deep"];
+}
+"#,
+            &dot
+        );
+    }
+}
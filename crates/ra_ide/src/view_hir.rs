@@ -0,0 +1,86 @@
+//! Renders the lowered body of the function, const or static at the cursor
+//! together with the inferred types, to debug type inference.
+
+use hir::{DefWithBody, Semantics};
+use ra_ide_db::RootDatabase;
+use ra_syntax::{ast, match_ast, AstNode};
+
+use crate::FilePosition;
+
+pub(crate) fn view_hir(db: &RootDatabase, position: FilePosition) -> Option<String> {
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let token = file.syntax().token_at_offset(position.offset).left_biased()?;
+    let def = token.parent().ancestors().find_map(|node| {
+        match_ast! {
+            match node {
+                ast::FnDef(it) => { sema.to_def(&it).map(DefWithBody::from) },
+                ast::ConstDef(it) => { sema.to_def(&it).map(DefWithBody::from) },
+                ast::StaticDef(it) => { sema.to_def(&it).map(DefWithBody::from) },
+                _ => None,
+            }
+        }
+    })?;
+    Some(def.debug_hir(db))
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    use crate::mock_analysis::single_file_with_position;
+
+    fn check_view_hir(code: &str) -> String {
+        let (analysis, position) = single_file_with_position(code);
+        analysis.view_hir(position).unwrap().unwrap()
+    }
+
+    #[test]
+    fn view_hir_of_function() {
+        let res = check_view_hir(
+            r#"
+fn foo(x: u32) -> u32 {
+    let y = x<|> + 1;
+    let z: u64 = y;
+    y
+}
+"#,
+        );
+
+        assert_snapshot!(res, @r###"
+fn foo
+  pat#0 Bind [8; 9) 'x': u32
+  expr#5 Block [23; 71) '{     ...   y }': u32
+    pat#1 Bind [33; 34) 'y': u32
+    expr#2 BinaryOp [37; 42) 'x + 1': u32
+      expr#0 Path [37; 38) 'x': u32
+      expr#1 Literal [41; 42) '1': u32
+    pat#2 Bind [52; 53) 'z': u64
+    expr#3 Path [61; 62) 'y': u32
+      mismatch: expected u64, got u32
+    expr#4 Path [68; 69) 'y': u32
+"###);
+    }
+
+    #[test]
+    fn view_hir_of_const() {
+        let res = check_view_hir(
+            r#"
+const FOO: u8 = 1 +<|> 2;
+"#,
+        );
+
+        assert_snapshot!(res, @r###"
+const FOO
+  expr#2 BinaryOp [17; 22) '1 + 2': u8
+    expr#0 Literal [17; 18) '1': u8
+    expr#1 Literal [21; 22) '2': u8
+"###);
+    }
+
+    #[test]
+    fn view_hir_outside_of_body() {
+        let (analysis, position) = single_file_with_position("struct Foo<|>;");
+        assert!(analysis.view_hir(position).unwrap().is_none());
+    }
+}
@@ -337,6 +337,7 @@ impl RootDatabase {
             hir::db::CrateLangItemsQuery
             hir::db::LangItemQuery
             hir::db::DocumentationQuery
+            hir::db::ImportMapQuery
 
             // InternDatabase
             hir::db::InternFunctionQuery
@@ -360,9 +361,12 @@ impl RootDatabase {
             hir::db::GenericPredicatesForParamQuery
             hir::db::GenericPredicatesQuery
             hir::db::GenericDefaultsQuery
+            hir::db::ImplsInModuleQuery
             hir::db::ImplsInCrateQuery
             hir::db::ImplsForTraitQuery
             hir::db::TraitSolverQuery
+            hir::db::TraitImplsFingerprintQuery
+            hir::db::NegativeTraitSolveCacheQuery
             hir::db::InternTypeCtorQuery
             hir::db::InternChalkImplQuery
             hir::db::InternAssocTyValueQuery
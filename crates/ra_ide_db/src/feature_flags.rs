@@ -14,7 +14,7 @@ use rustc_hash::FxHashMap;
 ///
 /// Also note that, at the moment, `FeatureFlags` also store features for
 /// `rust-analyzer`. This should be benign layering violation.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FeatureFlags {
     flags: FxHashMap<String, bool>,
 }
@@ -53,8 +53,10 @@ impl Default for FeatureFlags {
     fn default() -> FeatureFlags {
         FeatureFlags::new(&[
             ("lsp.diagnostics", true),
+            ("lsp.code-lens.references", false),
             ("completion.insertion.add-call-parenthesis", true),
             ("completion.enable-postfix", true),
+            ("references.search-macro-text", false),
             ("notifications.workspace-loaded", true),
             ("notifications.cargo-toml-not-found", true),
         ])
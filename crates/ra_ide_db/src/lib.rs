@@ -102,6 +102,11 @@ impl RootDatabase {
         db.query_mut(hir::db::MacroExpandQuery).set_lru_capacity(lru_capacity);
         db
     }
+
+    /// The name the project model gave to the crate, if any.
+    pub fn debug_crate_name(&self, crate_id: CrateId) -> Option<&str> {
+        self.debug_data.crate_names.get(&crate_id).map(String::as_str)
+    }
 }
 
 impl salsa::ParallelDatabase for RootDatabase {
@@ -19,6 +19,15 @@ pub struct LineCol {
     pub col_utf16: u32,
 }
 
+/// Like `LineCol`, but with the column counted in UTF-8 code units, i.e. bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LineColUtf8 {
+    /// Zero-based
+    pub line: u32,
+    /// Zero-based
+    pub col: u32,
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub(crate) struct Utf16Char {
     pub(crate) start: TextUnit,
@@ -29,6 +38,16 @@ impl Utf16Char {
     fn len(&self) -> TextUnit {
         self.end - self.start
     }
+
+    /// Characters outside of the Basic Multilingual Plane, which take four
+    /// bytes in UTF-8, are encoded as surrogate pairs in UTF-16.
+    fn len_utf16(&self) -> TextUnit {
+        if self.len() == TextUnit::from_usize(4) {
+            TextUnit::from_usize(2)
+        } else {
+            TextUnit::from_usize(1)
+        }
+    }
 }
 
 impl LineIndex {
@@ -81,10 +100,27 @@ impl LineIndex {
         LineCol { line: line as u32, col_utf16: self.utf8_to_utf16_col(line as u32, col) as u32 }
     }
 
+    /// Lines past the end of the text are clamped to the last line.
     pub fn offset(&self, line_col: LineCol) -> TextUnit {
         //FIXME: return Result
-        let col = self.utf16_to_utf8_col(line_col.line, line_col.col_utf16);
-        self.newlines[line_col.line as usize] + col
+        let line = self.clamp_line(line_col.line);
+        let col = self.utf16_to_utf8_col(line, line_col.col_utf16);
+        self.newlines[line as usize] + col
+    }
+
+    pub fn line_col_utf8(&self, offset: TextUnit) -> LineColUtf8 {
+        let line = self.newlines.upper_bound(&offset) - 1;
+        let col = offset - self.newlines[line];
+        LineColUtf8 { line: line as u32, col: col.to_usize() as u32 }
+    }
+
+    /// Lines past the end of the text are clamped to the last line.
+    pub fn offset_utf8(&self, line_col: LineColUtf8) -> TextUnit {
+        self.newlines[self.clamp_line(line_col.line) as usize] + TextUnit::from(line_col.col)
+    }
+
+    fn clamp_line(&self, line: u32) -> u32 {
+        line.min(self.newlines.len() as u32 - 1)
     }
 
     fn utf8_to_utf16_col(&self, line: u32, mut col: TextUnit) -> usize {
@@ -92,7 +128,7 @@ impl LineIndex {
             let mut correction = TextUnit::from_usize(0);
             for c in utf16_chars {
                 if col >= c.end {
-                    correction += c.len() - TextUnit::from_usize(1);
+                    correction += c.len() - c.len_utf16();
                 } else {
                     // From here on, all utf16 characters come *after* the character we are mapping,
                     // so we don't need to take them into account
@@ -110,8 +146,9 @@ impl LineIndex {
         let mut col: TextUnit = col.into();
         if let Some(utf16_chars) = self.utf16_lines.get(&line) {
             for c in utf16_chars {
-                if col >= c.start {
-                    col += c.len() - TextUnit::from_usize(1);
+                // The character comes before `col` only if `col` is at or past its end.
+                if col >= c.start + c.len_utf16() {
+                    col += c.len() - c.len_utf16();
                 } else {
                     // From here on, all utf16 characters come *after* the character we are mapping,
                     // so we don't need to take them into account
@@ -151,6 +188,13 @@ mod test_line_index {
         assert_eq!(index.line_col(7.into()), LineCol { line: 2, col_utf16: 0 });
     }
 
+    #[test]
+    fn test_offset_past_last_line() {
+        let index = LineIndex::new("hello\nworld");
+        assert_eq!(index.offset(LineCol { line: 2, col_utf16: 0 }), 6.into());
+        assert_eq!(index.offset_utf8(LineColUtf8 { line: 5, col: 1 }), 7.into());
+    }
+
     #[test]
     fn test_char_len() {
         assert_eq!('メ'.len_utf8(), 3);
@@ -217,8 +261,42 @@ const C: char = \"メ メ\";
         assert_eq!(col_index.utf16_to_utf8_col(1, 15), TextUnit::from_usize(15));
 
         assert_eq!(col_index.utf16_to_utf8_col(1, 18), TextUnit::from_usize(20));
-        assert_eq!(col_index.utf16_to_utf8_col(1, 19), TextUnit::from_usize(23));
+        assert_eq!(col_index.utf16_to_utf8_col(1, 19), TextUnit::from_usize(21));
+        assert_eq!(col_index.utf16_to_utf8_col(1, 20), TextUnit::from_usize(24));
 
         assert_eq!(col_index.utf16_to_utf8_col(2, 15), TextUnit::from_usize(15));
     }
+
+    #[test]
+    fn test_surrogate_pair() {
+        let col_index = LineIndex::new(
+            "
+const C: &str = \"🦀x🦀\";
+",
+        );
+
+        assert_eq!(col_index.utf16_lines[&1].len(), 2);
+        assert_eq!(col_index.utf16_lines[&1][0], Utf16Char { start: 17.into(), end: 21.into() });
+
+        // UTF-8 to UTF-16
+        assert_eq!(col_index.utf8_to_utf16_col(1, 17.into()), 17);
+        assert_eq!(col_index.utf8_to_utf16_col(1, 21.into()), 19);
+        assert_eq!(col_index.utf8_to_utf16_col(1, 22.into()), 20);
+        assert_eq!(col_index.utf8_to_utf16_col(1, 27.into()), 23);
+
+        // UTF-16 to UTF-8
+        assert_eq!(col_index.utf16_to_utf8_col(1, 17), TextUnit::from_usize(17));
+        assert_eq!(col_index.utf16_to_utf8_col(1, 19), TextUnit::from_usize(21));
+        assert_eq!(col_index.utf16_to_utf8_col(1, 20), TextUnit::from_usize(22));
+        assert_eq!(col_index.utf16_to_utf8_col(1, 23), TextUnit::from_usize(27));
+    }
+
+    #[test]
+    fn test_utf8_columns() {
+        let index = LineIndex::new("メ\n🦀x");
+        assert_eq!(index.line_col_utf8(3.into()), LineColUtf8 { line: 0, col: 3 });
+        assert_eq!(index.line_col_utf8(8.into()), LineColUtf8 { line: 1, col: 4 });
+        assert_eq!(index.offset_utf8(LineColUtf8 { line: 1, col: 4 }), TextUnit::from(8));
+        assert_eq!(index.line_col(8.into()), LineCol { line: 1, col_utf16: 2 });
+    }
 }
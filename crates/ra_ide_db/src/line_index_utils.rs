@@ -294,6 +294,8 @@ impl RunningLineCol {
     }
 
     fn adjust_col(&mut self, range: TextRange) {
-        self.col_adjust += range.len() - TextUnit::from(1);
+        // Characters taking four bytes in UTF-8 are surrogate pairs in UTF-16.
+        let len_utf16 = if range.len() == TextUnit::from(4) { 2 } else { 1 };
+        self.col_adjust += range.len() - TextUnit::from(len_utf16);
     }
 }
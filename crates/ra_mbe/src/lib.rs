@@ -9,6 +9,8 @@ mod syntax_bridge;
 mod tt_iter;
 mod subtree_source;
 
+use std::fmt;
+
 pub use tt::{Delimiter, Punct};
 
 use crate::{
@@ -30,6 +32,18 @@ pub enum ExpandError {
     InvalidRepeat,
 }
 
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpandError::NoMatchingRule => f.write_str("no rules expected this macro input"),
+            ExpandError::UnexpectedToken => f.write_str("unexpected token in macro input"),
+            ExpandError::BindingError(msg) => f.write_str(msg),
+            ExpandError::ConversionError => f.write_str("could not convert tokens"),
+            ExpandError::InvalidRepeat => f.write_str("invalid macro repeat"),
+        }
+    }
+}
+
 pub use crate::syntax_bridge::{
     ast_to_token_tree, syntax_node_to_token_tree, token_tree_to_syntax_node, TokenMap,
 };
@@ -14,13 +14,22 @@ pub(crate) fn expand(
     rules: &crate::MacroRules,
     input: &tt::Subtree,
 ) -> Result<tt::Subtree, ExpandError> {
-    rules.rules.iter().find_map(|it| expand_rule(it, input).ok()).ok_or(ExpandError::NoMatchingRule)
-}
-
-fn expand_rule(rule: &crate::Rule, input: &tt::Subtree) -> Result<tt::Subtree, ExpandError> {
-    let bindings = matcher::match_(&rule.lhs, input)?;
-    let res = transcriber::transcribe(&rule.rhs, &bindings)?;
-    Ok(res)
+    // If the input matches some rules, but none of them can be transcribed,
+    // the error of the first such rule is more useful than `NoMatchingRule`.
+    let mut transcription_error = None;
+    for rule in rules.rules.iter() {
+        let bindings = match matcher::match_(&rule.lhs, input) {
+            Ok(it) => it,
+            Err(_) => continue,
+        };
+        match transcriber::transcribe(&rule.rhs, &bindings) {
+            Ok(it) => return Ok(it),
+            Err(err) => {
+                transcription_error.get_or_insert(err);
+            }
+        }
+    }
+    Err(transcription_error.unwrap_or(ExpandError::NoMatchingRule))
 }
 
 /// The actual algorithm for expansion is not too hard, but is pretty tricky.
@@ -146,6 +155,25 @@ mod tests {
         let (invocation_tt, _) =
             ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
 
-        expand_rule(&rules.rules[0], &invocation_tt)
+        let rule = &rules.rules[0];
+        let bindings = matcher::match_(&rule.lhs, &invocation_tt)?;
+        transcriber::transcribe(&rule.rhs, &bindings)
+    }
+
+    #[test]
+    fn test_expand_reports_transcription_error() {
+        let rules = create_rules(&format_macro("($($i:ident);*) => ($i)"));
+        let source_file = ast::SourceFile::parse("foo!{a}").ok().unwrap();
+        let macro_invocation =
+            source_file.syntax().descendants().find_map(ast::MacroCall::cast).unwrap();
+        let (invocation_tt, _) =
+            ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
+
+        assert_eq!(
+            expand(&rules, &invocation_tt),
+            Err(ExpandError::BindingError(String::from(
+                "expected simple binding, found nested binding `i`"
+            )))
+        );
     }
 }
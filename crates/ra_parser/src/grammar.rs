@@ -150,7 +150,17 @@ pub(crate) fn reparser(
         MATCH_ARM_LIST => items::match_arm_list,
         USE_TREE_LIST => items::use_tree_list,
         EXTERN_ITEM_LIST => items::extern_item_list,
-        TOKEN_TREE if first_child? == T!['{'] => items::token_tree,
+        RECORD_FIELD_PAT_LIST => patterns::record_field_pat_list,
+        TOKEN_TREE => match first_child? {
+            T!['{'] | T!['('] | T!['['] => items::token_tree,
+            _ => return None,
+        },
+        PARAM_LIST if first_child? == T!['('] => match parent? {
+            FN_DEF => params::param_list_fn_def,
+            FN_POINTER_TYPE => params::param_list_fn_ptr,
+            PATH_SEGMENT => params::param_list_fn_trait,
+            _ => return None,
+        },
         ITEM_LIST => match parent? {
             IMPL_BLOCK => items::impl_item_list,
             TRAIT_DEF => items::trait_item_list,
@@ -15,7 +15,7 @@ pub(super) enum StmtWithSemi {
 const EXPR_FIRST: TokenSet = LHS_FIRST;
 
 pub(super) fn expr(p: &mut Parser) -> (Option<CompletedMarker>, BlockLike) {
-    let r = Restrictions { forbid_structs: false, prefer_stmt: false };
+    let r = Restrictions { forbid_structs: false, prefer_stmt: false, allow_let: false };
     expr_bp(p, r, 1)
 }
 
@@ -40,12 +40,12 @@ pub(super) fn expr_with_attrs(p: &mut Parser) -> bool {
 }
 
 pub(super) fn expr_stmt(p: &mut Parser) -> (Option<CompletedMarker>, BlockLike) {
-    let r = Restrictions { forbid_structs: false, prefer_stmt: true };
+    let r = Restrictions { forbid_structs: false, prefer_stmt: true, allow_let: false };
     expr_bp(p, r, 1)
 }
 
 fn expr_no_struct(p: &mut Parser) {
-    let r = Restrictions { forbid_structs: true, prefer_stmt: false };
+    let r = Restrictions { forbid_structs: true, prefer_stmt: false, allow_let: false };
     expr_bp(p, r, 1);
 }
 
@@ -179,6 +179,9 @@ pub(super) fn stmt(p: &mut Parser, with_semi: StmtWithSemi) {
         }
         if p.eat(T![=]) {
             expressions::expr(p);
+            if p.at(T![else]) {
+                let_else(p);
+            }
         }
 
         match with_semi {
@@ -194,6 +197,19 @@ pub(super) fn stmt(p: &mut Parser, with_semi: StmtWithSemi) {
         }
         m.complete(p, LET_STMT);
     }
+
+    // test let_else
+    // fn foo() {
+    //     let Some(x) = opt else { return };
+    //     let Ok(y): Result<i32, ()> = res else { panic!() };
+    // }
+    fn let_else(p: &mut Parser) {
+        assert!(p.at(T![else]));
+        let m = p.start();
+        p.bump(T![else]);
+        block(p);
+        m.complete(p, LET_ELSE);
+    }
 }
 
 pub(crate) fn expr_block_contents(p: &mut Parser) {
@@ -229,6 +245,8 @@ pub(crate) fn expr_block_contents(p: &mut Parser) {
 struct Restrictions {
     forbid_structs: bool,
     prefer_stmt: bool,
+    /// `let` is an expression only in the conditions of `if` and `while`.
+    allow_let: bool,
 }
 
 /// Binding powers of operators for a Pratt parser.
@@ -359,6 +377,7 @@ fn lhs(p: &mut Parser, r: Restrictions) -> Option<(CompletedMarker, BlockLike)>
             p.bump_any();
             PREFIX_EXPR
         }
+        T![let] if r.allow_let => return Some((atom::let_expr(p), BlockLike::NotBlock)),
         _ => {
             // test full_range_expr
             // fn foo() { xs[..]; }
@@ -52,6 +52,7 @@ pub(super) const ATOM_EXPR_FIRST: TokenSet =
         T![match],
         T![unsafe],
         T![return],
+        T![yield],
         T![break],
         T![continue],
         T![async],
@@ -126,6 +127,7 @@ pub(super) fn atom_expr(p: &mut Parser, r: Restrictions) -> Option<(CompletedMar
             block_expr(p, None)
         }
         T![return] => return_expr(p),
+        T![yield] => yield_expr(p),
         T![continue] => continue_expr(p),
         T![break] => break_expr(p, r),
         _ => {
@@ -333,14 +335,31 @@ fn for_expr(p: &mut Parser, m: Option<Marker>) -> CompletedMarker {
 // }
 fn cond(p: &mut Parser) {
     let m = p.start();
-    if p.eat(T![let]) {
-        patterns::pattern_top(p);
-        p.expect(T![=]);
+    let r = Restrictions { forbid_structs: true, prefer_stmt: false, allow_let: true };
+    match expr_bp(p, r, 1) {
+        // A single `let` keeps the shape of a plain `if let` condition.
+        (Some(cm), _) if cm.kind() == LET_EXPR => cm.undo_completion(p).abandon(p),
+        _ => (),
     }
-    expr_no_struct(p);
     m.complete(p, CONDITION);
 }
 
+// test let_chain
+// fn foo() {
+//     if let Some(x) = a && let Some(y) = x.b && y > 0 {}
+//     while a && let Some(x) = b() {}
+// }
+pub(super) fn let_expr(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(T![let]));
+    let m = p.start();
+    p.bump(T![let]);
+    patterns::pattern_top(p);
+    p.expect(T![=]);
+    // Stop before `&&` and `||`, which join the `let` with the rest of the chain.
+    expr_bp(p, Restrictions { forbid_structs: true, prefer_stmt: false, allow_let: false }, 5);
+    m.complete(p, LET_EXPR)
+}
+
 // test match_expr
 // fn foo() {
 //     match () { };
@@ -480,6 +499,23 @@ fn return_expr(p: &mut Parser) -> CompletedMarker {
     m.complete(p, RETURN_EXPR)
 }
 
+// test yield_expr
+// fn foo() {
+//     let g = || {
+//         yield;
+//         yield 92;
+//     };
+// }
+fn yield_expr(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(T![yield]));
+    let m = p.start();
+    p.bump(T![yield]);
+    if p.at_ts(EXPR_FIRST) {
+        expr(p);
+    }
+    m.complete(p, YIELD_EXPR)
+}
+
 // test continue_expr
 // fn foo() {
 //     loop {
@@ -556,7 +592,7 @@ fn meta_var_expr(p: &mut Parser) -> CompletedMarker {
     let m = p.start();
     p.bump(L_DOLLAR);
     let (completed, _is_block) =
-        expr_bp(p, Restrictions { forbid_structs: false, prefer_stmt: false }, 1);
+        expr_bp(p, Restrictions { forbid_structs: false, prefer_stmt: false, allow_let: false }, 1);
 
     match (completed, p.current()) {
         (Some(it), R_DOLLAR) => {
@@ -63,6 +63,10 @@ fn list_(p: &mut Parser, flavor: Flavor) {
             _ => (),
         }
 
+        // The missing `)` is reported below.
+        if is_fn_def_and_at_item(p, flavor) {
+            break;
+        }
         if !p.at_ts(VALUE_PARAMETER_FIRST) {
             p.error("expected value parameter");
             break;
@@ -79,6 +83,22 @@ fn list_(p: &mut Parser, flavor: Flavor) {
 
 const VALUE_PARAMETER_FIRST: TokenSet = patterns::PATTERN_FIRST.union(types::TYPE_FIRST);
 
+/// In an unfinished `fn` signature, like `fn foo(x:` followed by more items,
+/// we'd rather stop at the next item than swallow it into the parameter list.
+fn is_fn_def_and_at_item(p: &Parser, flavor: Flavor) -> bool {
+    if let Flavor::FnDef = flavor {
+        match p.current() {
+            // `fn(...)` is a function pointer type
+            T![fn] => p.nth(1) == IDENT,
+            // `crate::Foo` is a path, `impl Trait` is a type
+            T![crate] | T![impl] => false,
+            _ => p.at_ts(items::ITEM_RECOVERY_SET),
+        }
+    } else {
+        false
+    }
+}
+
 fn value_parameter(p: &mut Parser, flavor: Flavor) {
     let m = p.start();
     match flavor {
@@ -102,9 +122,20 @@ fn value_parameter(p: &mut Parser, flavor: Flavor) {
         //     fn f3(NewType(a): NewType) {}
         //     fn f4(&&a: &&usize) {}
         // }
+        // test_err fn_param_list_recovery
+        // impl S {
+        //     fn foo(x:
+        //     fn bar() {}
+        //     fn baz(&self) {}
+        // }
         Flavor::FnDef => {
             patterns::pattern(p);
-            types::ascription(p);
+            p.expect(T![:]);
+            if is_fn_def_and_at_item(p, flavor) {
+                p.error("expected type");
+            } else {
+                types::type_(p);
+            }
         }
         // test value_parameters_no_patterns
         // type F = Box<Fn(i32, &i32, &i32, ())>;
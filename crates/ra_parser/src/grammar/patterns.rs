@@ -185,7 +185,7 @@ fn tuple_pat_fields(p: &mut Parser) {
 //     let S { h: _, ..} = ();
 //     let S { h: _, } = ();
 // }
-fn record_field_pat_list(p: &mut Parser) {
+pub(super) fn record_field_pat_list(p: &mut Parser) {
     assert!(p.at(T!['{']));
     let m = p.start();
     p.bump(T!['{']);
@@ -26,7 +26,7 @@ fn type_with_bounds_cond(p: &mut Parser, allow_bounds: bool) {
         T![&] => reference_type(p),
         T![_] => placeholder_type(p),
         T![fn] | T![unsafe] | T![extern] => fn_pointer_type(p),
-        T![for] => for_type(p),
+        T![for] => for_type(p, allow_bounds),
         T![impl] => impl_trait_type(p),
         T![dyn] => dyn_trait_type(p),
         // Some path types are not allowed to have bounds (no plus)
@@ -208,21 +208,42 @@ pub(super) fn for_binder(p: &mut Parser) {
 // fn foo<T>(_t: &T) where for<'a> &'a T: Iterator {}
 // fn bar<T>(_t: &T) where for<'a> &'a mut T: Iterator {}
 // fn baz<T>(_t: &T) where for<'a> <&'a T as Baz>::Foo: Iterator {}
-pub(super) fn for_type(p: &mut Parser) {
+pub(super) fn for_type(p: &mut Parser, allow_bounds: bool) {
     assert!(p.at(T![for]));
     let m = p.start();
     for_binder(p);
-    match p.current() {
-        T![fn] | T![unsafe] | T![extern] => fn_pointer_type(p),
-        T![&] => reference_type(p),
-        _ if paths::is_path_start(p) => path_type_(p, false),
-        _ => p.error("expected a path"),
+    let is_path = match p.current() {
+        T![fn] | T![unsafe] | T![extern] => {
+            fn_pointer_type(p);
+            false
+        }
+        T![&] => {
+            reference_type(p);
+            false
+        }
+        _ if paths::is_path_start(p) => {
+            path_type_(p, false);
+            true
+        }
+        _ => {
+            p.error("expected a path");
+            false
+        }
+    };
+    let for_type = m.complete(p, FOR_TYPE);
+
+    // test for_type_with_bounds
+    // type A = Box<for<'a> Fn(&'a u8) + Send + 'static>;
+    if allow_bounds && is_path {
+        opt_type_bounds_as_dyn_trait_type(p, for_type);
     }
-    m.complete(p, FOR_TYPE);
 }
 
 // test impl_trait_type
 // type A = impl Iterator<Item=Foo<'a>> + 'a;
+
+// test impl_trait_type_in_param
+// fn foo(x: impl Iterator<Item = u32> + Send + 'static, y: impl (Clone) + ?Sized) {}
 fn impl_trait_type(p: &mut Parser) {
     assert!(p.at(T![impl]));
     let m = p.start();
@@ -233,6 +254,14 @@ fn impl_trait_type(p: &mut Parser) {
 
 // test dyn_trait_type
 // type A = dyn Iterator<Item=Foo<'a>> + 'a;
+
+// test dyn_trait_type_in_ret_type
+// fn foo() -> dyn Iterator<Item = u32> + Send + 'static {}
+
+// test paren_dyn_trait_type_with_bounds
+// type A = &(dyn Trait + Send);
+// type B = &'a mut (Trait + Send + 'a);
+// type C = *const (dyn for<'a> Trait<'a> + Send);
 fn dyn_trait_type(p: &mut Parser) {
     assert!(p.at(T![dyn]));
     let m = p.start();
@@ -268,7 +297,7 @@ fn path_or_macro_type_(p: &mut Parser, allow_bounds: bool) {
     let path = m.complete(p, kind);
 
     if allow_bounds {
-        opt_path_type_bounds_as_dyn_trait_type(p, path);
+        opt_type_bounds_as_dyn_trait_type(p, path);
     }
 }
 
@@ -282,19 +311,20 @@ pub(super) fn path_type_(p: &mut Parser, allow_bounds: bool) {
     // fn foo() -> Box<dyn T + 'f> {}
     let path = m.complete(p, PATH_TYPE);
     if allow_bounds {
-        opt_path_type_bounds_as_dyn_trait_type(p, path);
+        opt_type_bounds_as_dyn_trait_type(p, path);
     }
 }
 
-/// This turns a parsed PATH_TYPE optionally into a DYN_TRAIT_TYPE
-/// with a TYPE_BOUND_LIST
-fn opt_path_type_bounds_as_dyn_trait_type(p: &mut Parser, path_type_marker: CompletedMarker) {
+/// This turns a parsed PATH_TYPE or FOR_TYPE optionally into a DYN_TRAIT_TYPE
+/// with a TYPE_BOUND_LIST, so that the bounds have the same shape as the
+/// ones after `dyn`.
+fn opt_type_bounds_as_dyn_trait_type(p: &mut Parser, type_marker: CompletedMarker) {
     if !p.at(T![+]) {
         return;
     }
 
-    // First create a TYPE_BOUND from the completed PATH_TYPE
-    let m = path_type_marker.precede(p).complete(p, TYPE_BOUND);
+    // First create a TYPE_BOUND from the completed PATH_TYPE or FOR_TYPE
+    let m = type_marker.precede(p).complete(p, TYPE_BOUND);
 
     // Next setup a marker for the TYPE_BOUND_LIST
     let m = m.precede(p);
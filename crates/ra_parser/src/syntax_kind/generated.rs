@@ -101,6 +101,7 @@ pub enum SyntaxKind {
     USE_KW,
     WHERE_KW,
     WHILE_KW,
+    YIELD_KW,
     AUTO_KW,
     DEFAULT_KW,
     EXISTENTIAL_KW,
@@ -175,6 +176,7 @@ pub enum SyntaxKind {
     IF_EXPR,
     WHILE_EXPR,
     CONDITION,
+    LET_EXPR,
     LOOP_EXPR,
     FOR_EXPR,
     CONTINUE_EXPR,
@@ -182,6 +184,7 @@ pub enum SyntaxKind {
     LABEL,
     BLOCK_EXPR,
     RETURN_EXPR,
+    YIELD_EXPR,
     MATCH_EXPR,
     MATCH_ARM_LIST,
     MATCH_ARM,
@@ -227,6 +230,7 @@ pub enum SyntaxKind {
     NAME,
     NAME_REF,
     LET_STMT,
+    LET_ELSE,
     EXPR_STMT,
     TYPE_PARAM_LIST,
     LIFETIME_PARAM,
@@ -257,7 +261,7 @@ impl SyntaxKind {
             | IMPL_KW | IN_KW | LET_KW | LOOP_KW | MACRO_KW | MATCH_KW | MOD_KW | MOVE_KW
             | MUT_KW | PUB_KW | REF_KW | RETURN_KW | SELF_KW | STATIC_KW | STRUCT_KW | SUPER_KW
             | TRAIT_KW | TRUE_KW | TRY_KW | TYPE_KW | UNSAFE_KW | USE_KW | WHERE_KW | WHILE_KW
-            | AUTO_KW | DEFAULT_KW | EXISTENTIAL_KW | UNION_KW => true,
+            | YIELD_KW | AUTO_KW | DEFAULT_KW | EXISTENTIAL_KW | UNION_KW => true,
             _ => false,
         }
     }
@@ -321,6 +325,7 @@ impl SyntaxKind {
             "use" => USE_KW,
             "where" => WHERE_KW,
             "while" => WHILE_KW,
+            "yield" => YIELD_KW,
             _ => return None,
         };
         Some(kw)
@@ -638,6 +643,9 @@ macro_rules! T {
     ( while ) => {
         $crate::SyntaxKind::WHILE_KW
     };
+    ( yield ) => {
+        $crate::SyntaxKind::YIELD_KW
+    };
     ( auto ) => {
         $crate::SyntaxKind::AUTO_KW
     };
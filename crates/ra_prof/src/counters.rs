@@ -0,0 +1,37 @@
+//! Named event counters, for things which happen too often to be measured with
+//! `profile`, like the hits and misses of a cache.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use once_cell::sync::Lazy;
+
+use crate::Label;
+
+static COUNTERS: Lazy<Mutex<BTreeMap<Label, usize>>> = Lazy::new(Default::default);
+
+/// Increments the counter with the given label.
+///
+/// # Example
+/// ```
+/// use ra_prof::{count, counters};
+///
+/// count("cache hit");
+/// count("cache hit");
+/// assert!(counters().contains(&("cache hit", 2)));
+/// ```
+pub fn count(label: Label) {
+    count_by(label, 1)
+}
+
+/// Adds `n` to the counter with the given label, for counting quantities like
+/// milliseconds rather than events.
+pub fn count_by(label: Label, n: usize) {
+    assert!(!label.is_empty());
+    *COUNTERS.lock().unwrap().entry(label).or_insert(0) += n;
+}
+
+/// Returns the values of all counters which were incremented so far, sorted by
+/// label.
+pub fn counters() -> Vec<(Label, usize)> {
+    COUNTERS.lock().unwrap().iter().map(|(&label, &count)| (label, count)).collect()
+}
@@ -1,13 +1,14 @@
 //! FIXME: write short doc here
 
+mod counters;
 mod memory_usage;
+mod trace;
 #[cfg(feature = "cpu_profiler")]
 mod google_cpu_profiler;
 
 use std::{
     cell::RefCell,
-    collections::BTreeMap,
-    collections::HashSet,
+    collections::{BTreeMap, HashMap, HashSet},
     io::{stderr, Write},
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -18,7 +19,11 @@ use std::{
 
 use once_cell::sync::Lazy;
 
-pub use crate::memory_usage::{Bytes, MemoryUsage};
+pub use crate::{
+    counters::{count, count_by, counters},
+    memory_usage::{Bytes, MemoryUsage},
+    trace::{chrome_trace, install_sink, remove_sink, Frame, Sink, TraceRecorder},
+};
 
 // We use jemalloc mainly to get heap usage statistics, actual performance
 // difference is not measures.
@@ -51,6 +56,7 @@ pub fn set_filter(f: Filter) {
         depth: f.depth,
         allowed: set,
         longer_than: f.longer_than,
+        label_longer_than: f.label_longer_than,
         version: old.version + 1,
     };
     *old = filter_data;
@@ -61,7 +67,8 @@ pub type Label = &'static str;
 /// This function starts a profiling scope in the current execution stack with a given description.
 /// It returns a Profile structure and measure elapsed time between this method invocation and Profile structure drop.
 /// It supports nested profiling scopes in case when this function invoked multiple times at the execution stack. In this case the profiling information will be nested at the output.
-/// Profiling information is being printed in the stderr.
+/// Profiling information is being printed in the stderr, or passed to the
+/// [`Sink`] installed with [`install_sink`].
 ///
 /// # Example
 /// ```
@@ -136,6 +143,7 @@ pub struct Filter {
     depth: usize,
     allowed: Vec<String>,
     longer_than: Duration,
+    label_longer_than: HashMap<String, Duration>,
 }
 
 impl Filter {
@@ -143,13 +151,18 @@ impl Filter {
     // env RA_PROFILE=*             // dump everything
     // env RA_PROFILE=foo|bar|baz   // enabled only selected entries
     // env RA_PROFILE=*@3>10        // dump everything, up to depth 3, if it takes more than 10 ms
+    // env RA_PROFILE=foo>5ms|bar   // dump `foo` if it takes more than 5 ms, and all of `bar`
+    //
+    // A threshold without a unit applies to all entries, one with a unit (`us`, `ms` or `s`)
+    // only to the entry it follows.
     pub fn from_spec(mut spec: &str) -> Filter {
-        let longer_than = if let Some(idx) = spec.rfind('>') {
-            let longer_than = spec[idx + 1..].parse().expect("invalid profile longer_than");
-            spec = &spec[..idx];
-            Duration::from_millis(longer_than)
-        } else {
-            Duration::new(0, 0)
+        let longer_than = match spec.rfind('>') {
+            Some(idx) if is_number(&spec[idx + 1..]) => {
+                let longer_than = spec[idx + 1..].parse().expect("invalid profile longer_than");
+                spec = &spec[..idx];
+                Duration::from_millis(longer_than)
+            }
+            _ => Duration::new(0, 0),
         };
 
         let depth = if let Some(idx) = spec.rfind('@') {
@@ -159,9 +172,26 @@ impl Filter {
         } else {
             999
         };
-        let allowed =
-            if spec == "*" { Vec::new() } else { spec.split('|').map(String::from).collect() };
-        Filter::new(depth, allowed, longer_than)
+        let mut label_longer_than = HashMap::new();
+        let allowed = if spec == "*" {
+            Vec::new()
+        } else {
+            spec.split('|')
+                .map(|entry| match entry.find('>') {
+                    Some(idx) => {
+                        let label = entry[..idx].to_string();
+                        let longer_than =
+                            parse_duration(&entry[idx + 1..]).expect("invalid profile longer_than");
+                        label_longer_than.insert(label.clone(), longer_than);
+                        label
+                    }
+                    None => entry.to_string(),
+                })
+                .collect()
+        };
+        let mut res = Filter::new(depth, allowed, longer_than);
+        res.label_longer_than = label_longer_than;
+        res
     }
 
     pub fn disabled() -> Filter {
@@ -169,13 +199,29 @@ impl Filter {
     }
 
     pub fn new(depth: usize, allowed: Vec<String>, longer_than: Duration) -> Filter {
-        Filter { depth, allowed, longer_than }
+        Filter { depth, allowed, longer_than, label_longer_than: HashMap::new() }
+    }
+}
+
+fn is_number(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|it| it.is_ascii_digit())
+}
+
+fn parse_duration(text: &str) -> Option<Duration> {
+    let unit_start = text.find(|c: char| !c.is_ascii_digit())?;
+    let value: u64 = text[..unit_start].parse().ok()?;
+    match &text[unit_start..] {
+        "us" => Some(Duration::from_micros(value)),
+        "ms" => Some(Duration::from_millis(value)),
+        "s" => Some(Duration::from_secs(value)),
+        _ => None,
     }
 }
 
 struct ProfileStack {
     starts: Vec<Instant>,
     messages: Vec<Message>,
+    frames: Vec<Frame>,
     filter_data: FilterData,
 }
 
@@ -187,7 +233,12 @@ struct Message {
 
 impl ProfileStack {
     fn new() -> ProfileStack {
-        ProfileStack { starts: Vec::new(), messages: Vec::new(), filter_data: Default::default() }
+        ProfileStack {
+            starts: Vec::new(),
+            messages: Vec::new(),
+            frames: Vec::new(),
+            filter_data: Default::default(),
+        }
     }
 }
 
@@ -197,6 +248,13 @@ struct FilterData {
     version: usize,
     allowed: HashSet<String>,
     longer_than: Duration,
+    label_longer_than: HashMap<String, Duration>,
+}
+
+impl FilterData {
+    fn longer_than(&self, label: Label) -> Duration {
+        self.label_longer_than.get(label).copied().unwrap_or(self.longer_than)
+    }
 }
 
 static PROFILING_ENABLED: AtomicBool = AtomicBool::new(false);
@@ -215,16 +273,25 @@ impl Drop for Profiler {
                     let duration = start.elapsed();
                     let level = stack.starts.len();
                     stack.messages.push(Message { level, duration, label });
+                    let thread = trace::current_thread();
+                    stack.frames.push(Frame { label, start, duration, depth: level, thread });
                     if level == 0 {
-                        let stdout = stderr();
-                        let longer_than = stack.filter_data.longer_than;
                         // Convert to millis for comparison to avoid problems with rounding
                         // (otherwise we could print `0ms` despite user's `>0` filter when
                         // `duration` is just a few nanos).
-                        if duration.as_millis() > longer_than.as_millis() {
-                            print(&stack.messages, longer_than, &mut stdout.lock());
+                        let root_longer_than = stack.filter_data.longer_than(label);
+                        if duration.as_millis() > root_longer_than.as_millis() {
+                            match trace::sink() {
+                                Some(sink) => sink.record(std::mem::take(&mut stack.frames)),
+                                None => {
+                                    let stdout = stderr();
+                                    let longer_than = stack.filter_data.longer_than;
+                                    print(&stack.messages, longer_than, &mut stdout.lock());
+                                }
+                            }
                         }
                         stack.messages.clear();
+                        stack.frames.clear();
                     }
                 });
             }
@@ -413,6 +480,35 @@ mod tests {
         let _p = profile("profile2");
     }
 
+    #[test]
+    fn test_filter_from_spec() {
+        let f = Filter::from_spec("*@3>10");
+        assert_eq!(f.depth, 3);
+        assert!(f.allowed.is_empty());
+        assert_eq!(f.longer_than, Duration::from_millis(10));
+        assert!(f.label_longer_than.is_empty());
+
+        let f = Filter::from_spec("foo>5ms|bar|baz>100us@2");
+        assert_eq!(f.depth, 2);
+        assert_eq!(f.allowed, vec!["foo", "bar", "baz"]);
+        assert_eq!(f.longer_than, Duration::new(0, 0));
+        assert_eq!(f.label_longer_than.len(), 2);
+        assert_eq!(f.label_longer_than["foo"], Duration::from_millis(5));
+        assert_eq!(f.label_longer_than["baz"], Duration::from_micros(100));
+
+        let f = Filter::from_spec("foo>1s|bar>20");
+        assert_eq!(f.depth, 999);
+        assert_eq!(f.allowed, vec!["foo", "bar"]);
+        assert_eq!(f.longer_than, Duration::from_millis(20));
+        assert_eq!(f.label_longer_than["foo"], Duration::from_secs(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid profile longer_than")]
+    fn test_filter_from_spec_with_invalid_unit() {
+        Filter::from_spec("foo>5min");
+    }
+
     #[test]
     fn test_longer_than() {
         let mut result = vec![];
@@ -0,0 +1,177 @@
+//! Machine-readable profiling output.
+//!
+//! Instead of printing a tree to stderr, completed profiling frames can be
+//! handed to a [`Sink`]. [`TraceRecorder`] is a sink which collects frames and
+//! serializes them in the Chrome trace-event format, which can be loaded into
+//! `chrome://tracing` or https://ui.perfetto.dev.
+
+use std::{
+    cell::Cell,
+    fmt::Write as _,
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+
+use crate::Label;
+
+/// A single completed profiling scope.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub label: Label,
+    pub start: Instant,
+    pub duration: Duration,
+    /// Nesting level of the frame, `0` for the root of a profiling tree.
+    pub depth: usize,
+    /// Index of the thread the frame was recorded on.
+    pub thread: usize,
+}
+
+/// Receives trees of completed frames, in the order the frames finish.
+pub trait Sink: Send + Sync {
+    fn record(&self, frames: Vec<Frame>);
+}
+
+/// Routes profiling output to `sink` instead of stderr.
+pub fn install_sink(sink: Arc<dyn Sink>) {
+    *SINK.write().unwrap() = Some(sink);
+}
+
+/// Removes the installed sink, if any, returning to printing to stderr.
+pub fn remove_sink() -> Option<Arc<dyn Sink>> {
+    SINK.write().unwrap().take()
+}
+
+pub(crate) fn sink() -> Option<Arc<dyn Sink>> {
+    SINK.read().unwrap().clone()
+}
+
+static SINK: Lazy<RwLock<Option<Arc<dyn Sink>>>> = Lazy::new(Default::default);
+
+pub(crate) fn current_thread() -> usize {
+    static NEXT_THREAD: AtomicUsize = AtomicUsize::new(0);
+    thread_local!(static THREAD: Cell<Option<usize>> = Cell::new(None));
+    THREAD.with(|it| match it.get() {
+        Some(idx) => idx,
+        None => {
+            let idx = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+            it.set(Some(idx));
+            idx
+        }
+    })
+}
+
+/// A sink which keeps all frames in memory.
+#[derive(Debug, Default)]
+pub struct TraceRecorder {
+    frames: Mutex<Vec<Frame>>,
+}
+
+impl TraceRecorder {
+    pub fn frames(&self) -> Vec<Frame> {
+        self.frames.lock().unwrap().clone()
+    }
+
+    pub fn to_chrome_trace(&self) -> String {
+        chrome_trace(&self.frames.lock().unwrap())
+    }
+
+    pub fn write_chrome_trace(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_chrome_trace())
+    }
+}
+
+impl Sink for TraceRecorder {
+    fn record(&self, frames: Vec<Frame>) {
+        self.frames.lock().unwrap().extend(frames)
+    }
+}
+
+/// Serializes `frames` as Chrome trace-event JSON, using complete (`"X"`)
+/// events. Timestamps are in microseconds, relative to the earliest frame.
+pub fn chrome_trace(frames: &[Frame]) -> String {
+    let mut frames = frames.to_vec();
+    frames.sort_by_key(|it| (it.start, it.depth));
+    let epoch = match frames.first() {
+        Some(it) => it.start,
+        None => return r#"{"traceEvents":[]}"#.to_string(),
+    };
+
+    let mut buf = String::from(r#"{"traceEvents":["#);
+    for (idx, frame) in frames.iter().enumerate() {
+        if idx > 0 {
+            buf.push(',');
+        }
+        write!(
+            buf,
+            r#"{{"name":"{}","cat":"ra","ph":"X","ts":{},"dur":{},"pid":1,"tid":{},"args":{{"depth":{}}}}}"#,
+            escape(frame.label),
+            (frame.start - epoch).as_micros(),
+            frame.duration.as_micros(),
+            frame.thread,
+            frame.depth,
+        )
+        .unwrap();
+    }
+    buf.push_str("]}");
+    buf
+}
+
+fn escape(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            c if c.is_control() => write!(res, "\\u{:04x}", c as u32).unwrap(),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrome_trace_of_nested_profile() {
+        let t0 = Instant::now();
+        let frame = |label, start, duration, depth| Frame {
+            label,
+            start: t0 + Duration::from_micros(start),
+            duration: Duration::from_micros(duration),
+            depth,
+            thread: 0,
+        };
+        // Frames are recorded in the order they finish, children first.
+        let frames = vec![
+            frame("baz", 20, 30, 2),
+            frame("bar", 10, 50, 1),
+            frame("qu\"x", 70, 10, 1),
+            frame("textDocument/completion", 0, 100, 0),
+        ];
+        assert_eq!(
+            chrome_trace(&frames),
+            concat!(
+                r#"{"traceEvents":["#,
+                r#"{"name":"textDocument/completion","cat":"ra","ph":"X","ts":0,"dur":100,"pid":1,"tid":0,"args":{"depth":0}},"#,
+                r#"{"name":"bar","cat":"ra","ph":"X","ts":10,"dur":50,"pid":1,"tid":0,"args":{"depth":1}},"#,
+                r#"{"name":"baz","cat":"ra","ph":"X","ts":20,"dur":30,"pid":1,"tid":0,"args":{"depth":2}},"#,
+                r#"{"name":"qu\"x","cat":"ra","ph":"X","ts":70,"dur":10,"pid":1,"tid":0,"args":{"depth":1}}"#,
+                r#"]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_empty_chrome_trace() {
+        assert_eq!(chrome_trace(&[]), r#"{"traceEvents":[]}"#);
+    }
+}
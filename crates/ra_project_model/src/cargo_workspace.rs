@@ -21,6 +21,7 @@ pub struct CargoWorkspace {
     packages: Arena<Package, PackageData>,
     targets: Arena<Target, TargetData>,
     workspace_root: PathBuf,
+    target_directory: PathBuf,
 }
 
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -54,6 +55,7 @@ impl_arena_id!(Target);
 #[derive(Debug, Clone)]
 struct PackageData {
     name: String,
+    version: String,
     manifest: PathBuf,
     targets: Vec<Target>,
     is_member: bool,
@@ -109,6 +111,9 @@ impl Package {
     pub fn name(self, ws: &CargoWorkspace) -> &str {
         ws.packages[self].name.as_str()
     }
+    pub fn version(self, ws: &CargoWorkspace) -> &str {
+        ws.packages[self].version.as_str()
+    }
     pub fn root(self, ws: &CargoWorkspace) -> &Path {
         ws.packages[self].manifest.parent().unwrap()
     }
@@ -180,13 +185,15 @@ impl CargoWorkspace {
         let ws_members = &meta.workspace_members;
 
         for meta_pkg in meta.packages {
-            let cargo_metadata::Package { id, edition, name, manifest_path, .. } = meta_pkg;
+            let cargo_metadata::Package { id, edition, name, version, manifest_path, .. } =
+                meta_pkg;
             let is_member = ws_members.contains(&id);
             let edition = edition
                 .parse::<Edition>()
                 .with_context(|| format!("Failed to parse edition {}", edition))?;
             let pkg = packages.alloc(PackageData {
                 name,
+                version: version.to_string(),
                 manifest: manifest_path,
                 targets: Vec::new(),
                 is_member,
@@ -237,7 +244,12 @@ impl CargoWorkspace {
             packages[source].features.extend(node.features);
         }
 
-        Ok(CargoWorkspace { packages, targets, workspace_root: meta.workspace_root })
+        Ok(CargoWorkspace {
+            packages,
+            targets,
+            workspace_root: meta.workspace_root,
+            target_directory: meta.target_directory,
+        })
     }
 
     pub fn packages<'a>(&'a self) -> impl Iterator<Item = Package> + ExactSizeIterator + 'a {
@@ -251,4 +263,8 @@ impl CargoWorkspace {
     pub fn workspace_root(&self) -> &Path {
         &self.workspace_root
     }
+
+    pub fn target_directory(&self) -> &Path {
+        &self.target_directory
+    }
 }
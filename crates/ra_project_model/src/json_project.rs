@@ -22,6 +22,9 @@ pub struct Crate {
     pub(crate) deps: Vec<Dep>,
     pub(crate) atom_cfgs: FxHashSet<String>,
     pub(crate) key_value_cfgs: FxHashMap<String, String>,
+    /// Environment variables visible to `env!` in this crate.
+    #[serde(default)]
+    pub(crate) env: FxHashMap<String, String>,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize)]
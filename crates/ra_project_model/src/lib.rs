@@ -178,14 +178,16 @@ impl ProjectWorkspace {
                             }
                             opts
                         };
+                        let env = {
+                            let mut env = Env::default();
+                            for (key, value) in &krate.env {
+                                env.set(key, value.clone());
+                            }
+                            env
+                        };
                         crates.insert(
                             crate_id,
-                            crate_graph.add_crate_root(
-                                file_id,
-                                edition,
-                                cfg_options,
-                                Env::default(),
-                            ),
+                            crate_graph.add_crate_root(file_id, edition, cfg_options, env),
                         );
                     }
                 }
@@ -266,12 +268,18 @@ impl ProjectWorkspace {
                                 opts.insert_features(pkg.features(&cargo).iter().map(Into::into));
                                 opts
                             };
-                            let crate_id = crate_graph.add_crate_root(
-                                file_id,
-                                edition,
-                                cfg_options,
-                                Env::default(),
-                            );
+                            let env = {
+                                let mut env = Env::default();
+                                env.set("CARGO_PKG_NAME", pkg.name(&cargo).to_string());
+                                env.set("CARGO_PKG_VERSION", pkg.version(&cargo).to_string());
+                                env.set(
+                                    "CARGO_MANIFEST_DIR",
+                                    pkg.root(&cargo).to_string_lossy().to_string(),
+                                );
+                                env
+                            };
+                            let crate_id =
+                                crate_graph.add_crate_root(file_id, edition, cfg_options, env);
                             names.insert(crate_id, pkg.name(&cargo).to_string());
                             if tgt.kind(&cargo) == TargetKind::Lib {
                                 lib_tgt = Some(crate_id);
@@ -387,6 +395,21 @@ impl ProjectWorkspace {
                 .map(|root| root.path.as_ref()),
         }
     }
+
+    /// Returns the directory `cargo doc` puts the documentation of the
+    /// workspace member with the given crate root in.
+    pub fn doc_dir_for(&self, crate_root: &Path) -> Option<PathBuf> {
+        match self {
+            ProjectWorkspace::Cargo { cargo, .. } => {
+                let pkg = cargo.target_by_root(crate_root)?.package(cargo);
+                if !pkg.is_member(cargo) {
+                    return None;
+                }
+                Some(cargo.target_directory().join("doc"))
+            }
+            ProjectWorkspace::Json { .. } => None,
+        }
+    }
 }
 
 fn find_rust_project_json(path: &Path) -> Option<PathBuf> {
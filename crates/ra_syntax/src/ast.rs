@@ -228,6 +228,24 @@ fn test_comments_preserve_trailing_whitespace() {
     );
 }
 
+#[test]
+fn test_doc_comment_ignores_trailing_comments() {
+    let file = SourceFile::parse(
+        r#"
+        enum E {
+            A = 1, /// not the doc of B
+            /// Docs of B.
+            B = 2 /// not the doc of B either
+        }
+        "#,
+    )
+    .ok()
+    .unwrap();
+    let variants: Vec<_> = file.syntax().descendants().filter_map(EnumVariant::cast).collect();
+    assert!(variants[0].doc_comment_text().is_none());
+    assert_eq!("Docs of B.", variants[1].doc_comment_text().unwrap());
+}
+
 #[test]
 fn test_where_predicates() {
     fn assert_bound(text: &str, bound: Option<TypeBound>) {
@@ -13,7 +13,7 @@ use crate::{
         make::{self, tokens},
         AstNode, TypeBoundsOwner,
     },
-    AstToken, Direction, InsertPosition, SmolStr, SyntaxElement, SyntaxKind,
+    AstToken, Direction, InsertPosition, NodeOrToken, SmolStr, SyntaxElement, SyntaxKind,
     SyntaxKind::{ATTR, COMMENT, WHITESPACE},
     SyntaxNode, SyntaxToken, T,
 };
@@ -25,6 +25,44 @@ impl ast::BinExpr {
         let to_insert: Option<SyntaxElement> = Some(make::token(op).into());
         Some(replace_children(self, single_node(op_node), to_insert))
     }
+
+    /// Swaps the operands, keeping the operator and the trivia around it.
+    #[must_use]
+    pub fn swap_operands(&self) -> Option<ast::BinExpr> {
+        let lhs = self.lhs()?.syntax().clone();
+        let rhs = self.rhs()?.syntax().clone();
+        let between = lhs
+            .siblings_with_tokens(Direction::Next)
+            .skip(1)
+            .take_while(|it| it.as_node() != Some(&rhs))
+            .collect::<Vec<_>>();
+        let lhs: SyntaxElement = lhs.into();
+        let rhs: SyntaxElement = rhs.into();
+        let to_insert = iter::once(rhs.clone()).chain(between).chain(iter::once(lhs.clone()));
+        Some(replace_children(self, lhs..=rhs, to_insert))
+    }
+}
+
+impl ast::Block {
+    /// Inserts `stmt` before `anchor`, a statement or the tail expression of
+    /// the block. If `anchor` starts a line, `stmt` gets a line of its own,
+    /// indented like `anchor`.
+    #[must_use]
+    pub fn insert_stmt_before(&self, anchor: &SyntaxNode, stmt: &ast::Stmt) -> ast::Block {
+        let ws = match anchor.prev_sibling_or_token() {
+            Some(NodeOrToken::Token(ws)) if ws.kind() == WHITESPACE => {
+                match ws.text().rfind('\n') {
+                    Some(pos) => format!("\n{}", &ws.text()[pos + 1..]),
+                    None => ws.text().to_string(),
+                }
+            }
+            _ => " ".to_string(),
+        };
+        let ws = tokens::WsBuilder::new(&ws);
+        let to_insert: ArrayVec<[SyntaxElement; 2]> =
+            [stmt.syntax().clone().into(), ws.ws().into()].into();
+        insert_children(self, InsertPosition::Before(anchor.clone().into()), to_insert)
+    }
 }
 
 impl ast::FnDef {
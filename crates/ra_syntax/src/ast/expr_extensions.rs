@@ -1,7 +1,9 @@
 //! Various extension methods to ast Expr Nodes, which are hard to code-generate.
 
+use std::convert::TryFrom;
+
 use crate::{
-    ast::{self, child_opt, children, AstChildren, AstNode},
+    ast::{self, child_opt, children, AstChildren, AstNode, AstToken},
     SmolStr,
     SyntaxKind::*,
     SyntaxToken, T,
@@ -362,6 +364,12 @@ impl ast::Literal {
             _ => unreachable!(),
         }
     }
+
+    /// The value of an integer literal, like `0x1_000u32`.
+    pub fn int_value(&self) -> Option<u64> {
+        let value = ast::IntNumber::cast(self.token())?.value()?;
+        u64::try_from(value).ok()
+    }
 }
 
 impl ast::BlockExpr {
@@ -391,6 +399,19 @@ fn test_literal_with_attr() {
     assert_eq!(lit.token().text(), r#""Hello""#);
 }
 
+#[test]
+fn test_literal_int_value() {
+    let parse = ast::SourceFile::parse("const _: [u8; 4] = [1_0, 0x1F, 0b11u8, 0o7usize, 1f32];");
+    let values: Vec<_> = parse
+        .tree()
+        .syntax()
+        .descendants()
+        .filter_map(ast::Literal::cast)
+        .map(|it| it.int_value())
+        .collect();
+    assert_eq!(values, vec![Some(4), Some(10), Some(31), Some(3), Some(7), None]);
+}
+
 impl ast::RecordField {
     pub fn parent_record_lit(&self) -> ast::RecordLit {
         self.syntax().ancestors().find_map(ast::RecordLit::cast).unwrap()
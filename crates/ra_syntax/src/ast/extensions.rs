@@ -4,7 +4,7 @@
 use itertools::Itertools;
 
 use crate::{
-    ast::{self, child_opt, children, AstNode, AttrInput, SyntaxNode},
+    ast::{self, child_opt, children, AstNode, AttrInput, AttrsOwner, SyntaxNode},
     SmolStr, SyntaxElement,
     SyntaxKind::*,
     SyntaxToken, T,
@@ -154,6 +154,20 @@ impl ast::Module {
     }
 }
 
+impl ast::MacroCall {
+    /// Whether this is a `#[macro_export(local_inner_macros)]` macro
+    /// definition, whose bare inner macro calls refer to its own crate.
+    pub fn has_local_inner_macros(&self) -> bool {
+        self.attrs().filter_map(|it| it.as_simple_call()).any(|(name, tt)| {
+            name == "macro_export"
+                && tt
+                    .syntax()
+                    .children_with_tokens()
+                    .any(|it| it.as_token().map_or(false, |it| it.text() == "local_inner_macros"))
+        })
+    }
+}
+
 impl ast::UseTree {
     pub fn has_star(&self) -> bool {
         self.syntax().children_with_tokens().any(|it| it.kind() == T![*])
@@ -245,6 +259,10 @@ impl ast::FnDef {
     pub fn is_async(&self) -> bool {
         self.syntax().children_with_tokens().any(|it| it.kind() == T![async])
     }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![unsafe])
+    }
 }
 
 impl ast::LetStmt {
@@ -407,6 +425,33 @@ impl ast::LifetimeParam {
     }
 }
 
+impl ast::Label {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
+impl ast::BreakExpr {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
+impl ast::ContinueExpr {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
 impl ast::TypeParam {
     pub fn colon_token(&self) -> Option<SyntaxToken> {
         self.syntax()
@@ -29,6 +29,7 @@ impl AstNode for SourceFile {
 }
 impl ast::ModuleItemOwner for SourceFile {}
 impl ast::FnDefOwner for SourceFile {}
+impl ast::AttrsOwner for SourceFile {}
 impl SourceFile {
     pub fn modules(&self) -> AstChildren<Module> {
         AstChildren::new(&self.syntax)
@@ -447,6 +448,7 @@ impl AstNode for ItemList {
 }
 impl ast::FnDefOwner for ItemList {}
 impl ast::ModuleItemOwner for ItemList {}
+impl ast::AttrsOwner for ItemList {}
 impl ItemList {
     pub fn impl_items(&self) -> AstChildren<ImplItem> {
         AstChildren::new(&self.syntax)
@@ -1297,6 +1299,9 @@ impl AstNode for BlockExpr {
     }
 }
 impl BlockExpr {
+    pub fn label(&self) -> Option<Label> {
+        AstChildren::new(&self.syntax).next()
+    }
     pub fn block(&self) -> Option<Block> {
         AstChildren::new(&self.syntax).next()
     }
@@ -1329,6 +1334,33 @@ impl ReturnExpr {
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct YieldExpr {
+    pub(crate) syntax: SyntaxNode,
+}
+impl AstNode for YieldExpr {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        match kind {
+            YIELD_EXPR => true,
+            _ => false,
+        }
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+impl YieldExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        AstChildren::new(&self.syntax).next()
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CallExpr {
     pub(crate) syntax: SyntaxNode,
 }
@@ -1609,6 +1641,36 @@ impl BoxExpr {
     }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LetExpr {
+    pub(crate) syntax: SyntaxNode,
+}
+impl AstNode for LetExpr {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        match kind {
+            LET_EXPR => true,
+            _ => false,
+        }
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+impl LetExpr {
+    pub fn pat(&self) -> Option<Pat> {
+        AstChildren::new(&self.syntax).next()
+    }
+    pub fn expr(&self) -> Option<Expr> {
+        AstChildren::new(&self.syntax).next()
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RangeExpr {
     pub(crate) syntax: SyntaxNode,
 }
@@ -2752,6 +2814,36 @@ impl LetStmt {
     pub fn initializer(&self) -> Option<Expr> {
         AstChildren::new(&self.syntax).next()
     }
+    pub fn let_else(&self) -> Option<LetElse> {
+        AstChildren::new(&self.syntax).next()
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LetElse {
+    pub(crate) syntax: SyntaxNode,
+}
+impl AstNode for LetElse {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        match kind {
+            LET_ELSE => true,
+            _ => false,
+        }
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+impl LetElse {
+    pub fn block_expr(&self) -> Option<BlockExpr> {
+        AstChildren::new(&self.syntax).next()
+    }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Condition {
@@ -3698,6 +3790,7 @@ pub enum Expr {
     Label(Label),
     BlockExpr(BlockExpr),
     ReturnExpr(ReturnExpr),
+    YieldExpr(YieldExpr),
     MatchExpr(MatchExpr),
     RecordLit(RecordLit),
     CallExpr(CallExpr),
@@ -3715,6 +3808,7 @@ pub enum Expr {
     Literal(Literal),
     MacroCall(MacroCall),
     BoxExpr(BoxExpr),
+    LetExpr(LetExpr),
 }
 impl From<TupleExpr> for Expr {
     fn from(node: TupleExpr) -> Expr {
@@ -3786,6 +3880,11 @@ impl From<ReturnExpr> for Expr {
         Expr::ReturnExpr(node)
     }
 }
+impl From<YieldExpr> for Expr {
+    fn from(node: YieldExpr) -> Expr {
+        Expr::YieldExpr(node)
+    }
+}
 impl From<MatchExpr> for Expr {
     fn from(node: MatchExpr) -> Expr {
         Expr::MatchExpr(node)
@@ -3871,15 +3970,20 @@ impl From<BoxExpr> for Expr {
         Expr::BoxExpr(node)
     }
 }
+impl From<LetExpr> for Expr {
+    fn from(node: LetExpr) -> Expr {
+        Expr::LetExpr(node)
+    }
+}
 impl AstNode for Expr {
     fn can_cast(kind: SyntaxKind) -> bool {
         match kind {
             TUPLE_EXPR | ARRAY_EXPR | PAREN_EXPR | PATH_EXPR | LAMBDA_EXPR | IF_EXPR
             | LOOP_EXPR | FOR_EXPR | WHILE_EXPR | CONTINUE_EXPR | BREAK_EXPR | LABEL
-            | BLOCK_EXPR | RETURN_EXPR | MATCH_EXPR | RECORD_LIT | CALL_EXPR | INDEX_EXPR
-            | METHOD_CALL_EXPR | FIELD_EXPR | AWAIT_EXPR | TRY_EXPR | TRY_BLOCK_EXPR
-            | CAST_EXPR | REF_EXPR | PREFIX_EXPR | RANGE_EXPR | BIN_EXPR | LITERAL | MACRO_CALL
-            | BOX_EXPR => true,
+            | BLOCK_EXPR | RETURN_EXPR | YIELD_EXPR | MATCH_EXPR | RECORD_LIT | CALL_EXPR
+            | INDEX_EXPR | METHOD_CALL_EXPR | FIELD_EXPR | AWAIT_EXPR | TRY_EXPR
+            | TRY_BLOCK_EXPR | CAST_EXPR | REF_EXPR | PREFIX_EXPR | RANGE_EXPR | BIN_EXPR
+            | LITERAL | MACRO_CALL | BOX_EXPR | LET_EXPR => true,
             _ => false,
         }
     }
@@ -3899,6 +4003,7 @@ impl AstNode for Expr {
             LABEL => Expr::Label(Label { syntax }),
             BLOCK_EXPR => Expr::BlockExpr(BlockExpr { syntax }),
             RETURN_EXPR => Expr::ReturnExpr(ReturnExpr { syntax }),
+            YIELD_EXPR => Expr::YieldExpr(YieldExpr { syntax }),
             MATCH_EXPR => Expr::MatchExpr(MatchExpr { syntax }),
             RECORD_LIT => Expr::RecordLit(RecordLit { syntax }),
             CALL_EXPR => Expr::CallExpr(CallExpr { syntax }),
@@ -3916,6 +4021,7 @@ impl AstNode for Expr {
             LITERAL => Expr::Literal(Literal { syntax }),
             MACRO_CALL => Expr::MacroCall(MacroCall { syntax }),
             BOX_EXPR => Expr::BoxExpr(BoxExpr { syntax }),
+            LET_EXPR => Expr::LetExpr(LetExpr { syntax }),
             _ => return None,
         };
         Some(res)
@@ -3936,6 +4042,7 @@ impl AstNode for Expr {
             Expr::Label(it) => &it.syntax,
             Expr::BlockExpr(it) => &it.syntax,
             Expr::ReturnExpr(it) => &it.syntax,
+            Expr::YieldExpr(it) => &it.syntax,
             Expr::MatchExpr(it) => &it.syntax,
             Expr::RecordLit(it) => &it.syntax,
             Expr::CallExpr(it) => &it.syntax,
@@ -3953,6 +4060,7 @@ impl AstNode for Expr {
             Expr::Literal(it) => &it.syntax,
             Expr::MacroCall(it) => &it.syntax,
             Expr::BoxExpr(it) => &it.syntax,
+            Expr::LetExpr(it) => &it.syntax,
         }
     }
 }
@@ -22,6 +22,31 @@ fn path_from_text(text: &str) -> ast::Path {
     ast_from_text(text)
 }
 
+pub fn use_tree(
+    path: ast::Path,
+    use_tree_list: Option<ast::UseTreeList>,
+    alias: Option<ast::Alias>,
+) -> ast::UseTree {
+    let mut buf = "use ".to_string();
+    buf += &path.syntax().to_string();
+    if let Some(use_tree_list) = use_tree_list {
+        buf += &format!("::{}", use_tree_list.syntax());
+    }
+    if let Some(alias) = alias {
+        buf += &format!(" {}", alias.syntax());
+    }
+    ast_from_text(&buf)
+}
+
+pub fn use_tree_list(use_trees: impl IntoIterator<Item = ast::UseTree>) -> ast::UseTreeList {
+    let use_trees = use_trees.into_iter().map(|it| it.syntax().clone()).join(", ");
+    ast_from_text(&format!("use {{{}}};", use_trees))
+}
+
+pub fn use_item(use_tree: ast::UseTree) -> ast::UseItem {
+    ast_from_text(&format!("use {};", use_tree.syntax()))
+}
+
 pub fn record_field(name: ast::NameRef, expr: Option<ast::Expr>) -> ast::RecordField {
     return match expr {
         Some(expr) => from_text(&format!("{}: {}", name.syntax(), expr.syntax())),
@@ -219,8 +244,9 @@ pub mod tokens {
     use crate::{AstNode, Parse, SourceFile, SyntaxKind::*, SyntaxToken, T};
     use once_cell::sync::Lazy;
 
-    pub(super) static SOURCE_FILE: Lazy<Parse<SourceFile>> =
-        Lazy::new(|| SourceFile::parse("const C: <()>::Item = (1 != 1, 2 == 2, !true)\n;"));
+    pub(super) static SOURCE_FILE: Lazy<Parse<SourceFile>> = Lazy::new(|| {
+        SourceFile::parse("const C: <()>::Item = (1 != 1, 2 == 2, 3 <= 3, 4 >= 4, !true)\n;")
+    });
 
     pub fn comma() -> SyntaxToken {
         SOURCE_FILE
@@ -269,3 +295,17 @@ pub mod tokens {
         }
     }
 }
+
+#[test]
+fn test_use_item() {
+    let path = |segments: &[&str]| {
+        segments[1..].iter().fold(path_from_name_ref(name_ref(segments[0])), |qual, it| {
+            path_qualified(qual, name_ref(it))
+        })
+    };
+    let alias: ast::Alias = ast_from_text("use a as Map;");
+    let hash_map = use_tree(path(&["collections", "HashMap"]), None, Some(alias));
+    let fmt = use_tree(path(&["fmt"]), None, None);
+    let tree = use_tree(path(&["std"]), Some(use_tree_list(vec![hash_map, fmt])), None);
+    assert_eq!(use_item(tree).syntax().to_string(), "use std::{collections::HashMap as Map, fmt};");
+}
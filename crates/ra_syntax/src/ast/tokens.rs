@@ -2,7 +2,7 @@
 
 use crate::{
     ast::AstToken,
-    SyntaxKind::{COMMENT, RAW_STRING, STRING, WHITESPACE},
+    SyntaxKind::{COMMENT, INT_NUMBER, RAW_STRING, STRING, WHITESPACE},
     SyntaxToken, TextRange, TextUnit,
 };
 
@@ -173,6 +173,95 @@ impl RawString {
     }
 }
 
+pub struct IntNumber(SyntaxToken);
+
+impl AstToken for IntNumber {
+    fn cast(token: SyntaxToken) -> Option<Self> {
+        match token.kind() {
+            INT_NUMBER => Some(IntNumber(token)),
+            _ => None,
+        }
+    }
+    fn syntax(&self) -> &SyntaxToken {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Radix {
+    Binary = 2,
+    Octal = 8,
+    Decimal = 10,
+    Hexadecimal = 16,
+}
+
+impl Radix {
+    pub const ALL: &'static [Radix] =
+        &[Radix::Binary, Radix::Octal, Radix::Decimal, Radix::Hexadecimal];
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Radix::Binary => "0b",
+            Radix::Octal => "0o",
+            Radix::Decimal => "",
+            Radix::Hexadecimal => "0x",
+        }
+    }
+}
+
+impl IntNumber {
+    pub const SUFFIXES: &'static [&'static str] =
+        &["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize"];
+    /// The lexer treats e.g. `1f64` as an integer literal, so these can show
+    /// up as well.
+    pub const FLOAT_SUFFIXES: &'static [&'static str] = &["f32", "f64"];
+
+    pub fn radix(&self) -> Radix {
+        match self.text().get(..2).unwrap_or_default() {
+            "0b" => Radix::Binary,
+            "0o" => Radix::Octal,
+            "0x" => Radix::Hexadecimal,
+            _ => Radix::Decimal,
+        }
+    }
+
+    /// Splits the literal into its base prefix, its digits (still including
+    /// `_` separators) and its suffix, like `("0x", "FF_FF", "u16")`.
+    pub fn split_into_parts(&self) -> (&str, &str, &str) {
+        let radix = self.radix();
+        let (prefix, rest) = self.text().split_at(radix.prefix().len());
+        let is_digit = |c: char| c == '_' || c.is_digit(radix as u32);
+        let digits_len = rest.find(|c: char| !is_digit(c)).unwrap_or_else(|| rest.len());
+        let (digits, suffix) = rest.split_at(digits_len);
+        (prefix, digits, suffix)
+    }
+
+    pub fn suffix(&self) -> Option<&str> {
+        let (_, _, suffix) = self.split_into_parts();
+        if suffix.is_empty() {
+            None
+        } else {
+            Some(suffix)
+        }
+    }
+
+    /// Whether this is actually a float literal with a suffix, like `1f64`.
+    pub fn is_float(&self) -> bool {
+        self.suffix().map_or(false, |suffix| Self::FLOAT_SUFFIXES.contains(&suffix))
+    }
+
+    /// The value of the literal, or `None` if it's a float, has an unknown
+    /// suffix or doesn't fit into a `u128`.
+    pub fn value(&self) -> Option<u128> {
+        let (_, digits, suffix) = self.split_into_parts();
+        if !suffix.is_empty() && !Self::SUFFIXES.contains(&suffix) {
+            return None;
+        }
+        let digits = digits.replace('_', "");
+        u128::from_str_radix(&digits, self.radix() as u32).ok()
+    }
+}
+
 fn find_usual_string_range(s: &str) -> Option<TextRange> {
     let left_quote = s.find('"')?;
     let right_quote = s.rfind('"')?;
@@ -186,3 +275,39 @@ fn find_usual_string_range(s: &str) -> Option<TextRange> {
         ))
     }
 }
+
+#[test]
+fn test_int_number_parts_and_value() {
+    use crate::{ast, AstNode};
+
+    let parse = ast::SourceFile::parse(
+        "const _: () = (0xFF_FFu16, 1_000_000u64, 0b1010, 0o17i8, 1f64, \
+         0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffffu128, 340282366920938463463374607431768211456);",
+    );
+    let numbers: Vec<_> = parse
+        .tree()
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter_map(IntNumber::cast)
+        .map(|it| {
+            let (prefix, digits, suffix) = it.split_into_parts();
+            (prefix.to_string(), digits.to_string(), suffix.to_string(), it.value())
+        })
+        .collect();
+    let part = |prefix: &str, digits: &str, suffix: &str, value| {
+        (prefix.to_string(), digits.to_string(), suffix.to_string(), value)
+    };
+    assert_eq!(
+        numbers,
+        vec![
+            part("0x", "FF_FF", "u16", Some(0xFFFF)),
+            part("", "1_000_000", "u64", Some(1_000_000)),
+            part("0b", "1010", "", Some(10)),
+            part("0o", "17", "i8", Some(15)),
+            part("", "1", "f64", None),
+            part("0x", "ffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff", "u128", Some(std::u128::MAX)),
+            part("", "340282366920938463463374607431768211456", "", None),
+        ]
+    );
+}
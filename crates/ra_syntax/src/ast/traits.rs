@@ -2,6 +2,8 @@
 //!
 //! The implementations are usually trivial, and live in generated.rs
 
+use std::iter::successors;
+
 use itertools::Itertools;
 
 use crate::{
@@ -32,6 +34,10 @@ pub trait LoopBodyOwner: AstNode {
     fn loop_body(&self) -> Option<ast::BlockExpr> {
         child_opt(self)
     }
+
+    fn label(&self) -> Option<ast::Label> {
+        child_opt(self)
+    }
 }
 
 pub trait ArgListOwner: AstNode {
@@ -106,6 +112,9 @@ pub trait AttrsOwner: AstNode {
 }
 
 pub trait DocCommentsOwner: AstNode {
+    /// Comments attached to the node, except for a trailing comment on the
+    /// same line as its end, like in `A = 1 // first variant`, which is
+    /// never documentation.
     fn doc_comments(&self) -> CommentIter {
         CommentIter { iter: self.syntax().children_with_tokens() }
     }
@@ -160,6 +169,15 @@ pub struct CommentIter {
 impl Iterator for CommentIter {
     type Item = ast::Comment;
     fn next(&mut self) -> Option<ast::Comment> {
-        self.iter.by_ref().find_map(|el| el.into_token().and_then(ast::Comment::cast))
+        self.iter
+            .by_ref()
+            .find_map(|el| el.into_token().and_then(ast::Comment::cast))
+            .filter(|comment| !is_trailing(comment))
     }
 }
+
+/// Whether only trivia follow `comment` in its parent.
+fn is_trailing(comment: &ast::Comment) -> bool {
+    successors(comment.syntax().next_sibling_or_token(), |it| it.next_sibling_or_token())
+        .all(|it| it.kind().is_trivia())
+}
@@ -2,11 +2,13 @@
 //! incremental reparsing.
 
 mod lexer;
+mod brace_recovery;
 mod text_token_source;
 mod text_tree_sink;
 mod reparsing;
 
 use crate::{syntax_node::GreenNode, SyntaxError};
+use brace_recovery::BraceRecovery;
 use text_token_source::TextTokenSource;
 use text_tree_sink::TextTreeSink;
 
@@ -17,8 +19,9 @@ pub(crate) use self::reparsing::incremental_reparse;
 pub(crate) fn parse_text(text: &str) -> (GreenNode, Vec<SyntaxError>) {
     let (tokens, lexer_errors) = tokenize(&text);
 
-    let mut token_source = TextTokenSource::new(text, &tokens);
-    let mut tree_sink = TextTreeSink::new(text, &tokens);
+    let recovery = BraceRecovery::new(text, &tokens);
+    let mut token_source = TextTokenSource::new(text, &tokens, &recovery);
+    let mut tree_sink = TextTreeSink::new(text, &tokens, &recovery);
 
     ra_parser::parse(&mut token_source, &mut tree_sink);
 
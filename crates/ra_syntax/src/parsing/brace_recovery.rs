@@ -0,0 +1,136 @@
+//! Recovery from unbalanced curly braces.
+//!
+//! A single stray `}` or a missing `}` changes the nesting of everything after
+//! it, which the parser alone can't recover from locally. So, if the braces of
+//! the text don't balance, we use the indentation to guess which brace is
+//! wrong, like a human reader would:
+//!   - a `}` starting a line which is indented deeper than the line of the `{`
+//!     it would close doesn't close anything. It is hidden from the parser and
+//!     becomes an error node on its own.
+//!   - an item starting at column zero closes all blocks which are still open,
+//!     so the parser sees as many `}` right before it.
+//! Text with balanced braces is parsed unchanged.
+
+use crate::{
+    parsing::Token,
+    SyntaxKind::{self, *},
+    TextRange, TextUnit, T,
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct BraceRecovery {
+    /// Indices of the `}` which don't close anything.
+    stray: Vec<usize>,
+    /// Indices of the tokens before which the open blocks are closed, along
+    /// with the number of blocks.
+    closed_before: Vec<(usize, usize)>,
+}
+
+impl BraceRecovery {
+    pub(crate) fn new(text: &str, tokens: &[Token]) -> BraceRecovery {
+        let mut res = BraceRecovery::default();
+        if braces_are_balanced(tokens.iter().map(|it| it.kind)) {
+            return res;
+        }
+        let (n_opening, n_closing) =
+            tokens.iter().fold((0, 0), |(opening, closing), token| match token.kind {
+                T!['{'] => (opening + 1, closing),
+                T!['}'] => (opening, closing + 1),
+                _ => (opening, closing),
+            });
+        let mut extra_closing = n_closing.saturating_sub(n_opening);
+        let mut extra_opening = n_opening.saturating_sub(n_closing);
+
+        // The indentation of the lines of the open `{`.
+        let mut open_indents: Vec<usize> = Vec::new();
+        let mut line_indent = 0;
+        let mut at_line_start = true;
+        let mut offset = TextUnit::from(0);
+        for (idx, token) in tokens.iter().enumerate() {
+            let range = TextRange::offset_len(offset, token.len);
+            offset += token.len;
+            match token.kind {
+                WHITESPACE => {
+                    let ws = &text[range];
+                    if let Some(newline) = ws.rfind('\n') {
+                        line_indent = ws.len() - newline - 1;
+                        at_line_start = true;
+                    }
+                    continue;
+                }
+                COMMENT => {
+                    at_line_start = false;
+                    continue;
+                }
+                _ => (),
+            }
+            let indent = if at_line_start { Some(line_indent) } else { None };
+            at_line_start = false;
+
+            match token.kind {
+                T!['{'] => open_indents.push(line_indent),
+                T!['}'] => {
+                    let is_stray = match open_indents.last() {
+                        None => true,
+                        Some(&open_indent) => {
+                            extra_closing > 0 && indent.map_or(false, |it| it > open_indent)
+                        }
+                    };
+                    if is_stray {
+                        extra_closing = extra_closing.saturating_sub(1);
+                        res.stray.push(idx);
+                    } else {
+                        open_indents.pop();
+                    }
+                }
+                kind if indent == Some(0)
+                    && extra_opening > 0
+                    && !open_indents.is_empty()
+                    && is_item_start(kind) =>
+                {
+                    extra_opening = extra_opening.saturating_sub(open_indents.len());
+                    res.closed_before.push((idx, open_indents.len()));
+                    open_indents.clear();
+                }
+                _ => (),
+            }
+        }
+        res
+    }
+
+    /// Whether the `}` at `idx` doesn't close anything.
+    pub(crate) fn is_stray(&self, idx: usize) -> bool {
+        self.stray.binary_search(&idx).is_ok()
+    }
+
+    /// The number of blocks closed before the token at `idx`.
+    pub(crate) fn closed_before(&self, idx: usize) -> usize {
+        match self.closed_before.binary_search_by_key(&idx, |&(it, _)| it) {
+            Ok(i) => self.closed_before[i].1,
+            Err(_) => 0,
+        }
+    }
+}
+
+pub(crate) fn braces_are_balanced(kinds: impl Iterator<Item = SyntaxKind>) -> bool {
+    let mut depth = 0usize;
+    for kind in kinds {
+        match kind {
+            T!['{'] => depth += 1,
+            T!['}'] => match depth.checked_sub(1) {
+                Some(it) => depth = it,
+                None => return false,
+            },
+            _ => (),
+        }
+    }
+    depth == 0
+}
+
+fn is_item_start(kind: SyntaxKind) -> bool {
+    match kind {
+        FN_KW | STRUCT_KW | ENUM_KW | IMPL_KW | TRAIT_KW | MOD_KW | USE_KW | CONST_KW
+        | STATIC_KW | TYPE_KW | PUB_KW | EXTERN_KW | UNSAFE_KW | ASYNC_KW | POUND => true,
+        _ => false,
+    }
+}
@@ -3,8 +3,12 @@
 //! We use two simple strategies for this:
 //!   - if the edit modifies only a single token (like changing an identifier's
 //!     letter), we replace only this token.
-//!   - otherwise, we search for the nearest `{}` block which contains the edit
-//!     and try to parse only this block.
+//!   - otherwise, we search for the nearest `{}`, `()` or `[]` delimited node
+//!     (a block, a token tree, a parameter list, ...) which contains the edit
+//!     and try to parse only this node.
+//!
+//! Recovery from unbalanced braces depends on the whole file, so if it might
+//! have kicked in, only tokens which can't affect it are reparsed.
 
 use ra_parser::Reparser;
 use ra_text_edit::AtomTextEdit;
@@ -12,13 +16,14 @@ use ra_text_edit::AtomTextEdit;
 use crate::{
     algo,
     parsing::{
+        brace_recovery::{braces_are_balanced, BraceRecovery},
         lexer::{lex_single_syntax_kind, tokenize, Token},
         text_token_source::TextTokenSource,
         text_tree_sink::TextTreeSink,
     },
     syntax_node::{GreenNode, GreenToken, NodeOrToken, SyntaxElement, SyntaxNode},
     SyntaxError,
-    SyntaxKind::*,
+    SyntaxKind::{self, *},
     TextRange, TextUnit, T,
 };
 
@@ -27,10 +32,16 @@ pub(crate) fn incremental_reparse(
     edit: &AtomTextEdit,
     errors: Vec<SyntaxError>,
 ) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
-    if let Some((green, new_errors, old_range)) = reparse_token(node, &edit) {
+    let braces_balanced = errors.is_empty()
+        || braces_are_balanced(node.descendants_with_tokens().map(|it| it.kind()));
+
+    if let Some((green, new_errors, old_range)) = reparse_token(node, &edit, braces_balanced) {
         return Some((green, merge_errors(errors, new_errors, old_range, edit), old_range));
     }
 
+    if !braces_balanced {
+        return None;
+    }
     if let Some((green, new_errors, old_range)) = reparse_block(node, &edit) {
         return Some((green, merge_errors(errors, new_errors, old_range, edit), old_range));
     }
@@ -40,10 +51,13 @@ pub(crate) fn incremental_reparse(
 fn reparse_token<'node>(
     root: &'node SyntaxNode,
     edit: &AtomTextEdit,
+    braces_balanced: bool,
 ) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
     let prev_token = algo::find_covering_element(root, edit.delete).as_token()?.clone();
     let prev_token_kind = prev_token.kind();
     match prev_token_kind {
+        // Whitespace defines the indentation used by brace recovery.
+        WHITESPACE if !braces_balanced => None,
         WHITESPACE | COMMENT | IDENT | STRING | RAW_STRING => {
             if prev_token_kind == WHITESPACE || prev_token_kind == COMMENT {
                 // removing a new line may extends previous token
@@ -90,16 +104,11 @@ fn reparse_block<'node>(
     root: &'node SyntaxNode,
     edit: &AtomTextEdit,
 ) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
-    let (node, reparser) = find_reparsable_node(root, edit.delete)?;
-    let text = get_text_after_edit(node.clone().into(), edit);
+    let (node, reparser, text, tokens, new_lexer_errors) = find_reparsable_node(root, edit)?;
 
-    let (tokens, new_lexer_errors) = tokenize(&text);
-    if !is_balanced(&tokens) {
-        return None;
-    }
-
-    let mut token_source = TextTokenSource::new(&text, &tokens);
-    let mut tree_sink = TextTreeSink::new(&text, &tokens);
+    let recovery = BraceRecovery::new(&text, &tokens);
+    let mut token_source = TextTokenSource::new(&text, &tokens, &recovery);
+    let mut tree_sink = TextTreeSink::new(&text, &tokens, &recovery);
     reparser.parse(&mut token_source, &mut tree_sink);
 
     let (green, mut new_parser_errors) = tree_sink.finish();
@@ -126,8 +135,14 @@ fn is_contextual_kw(text: &str) -> bool {
     }
 }
 
-fn find_reparsable_node(node: &SyntaxNode, range: TextRange) -> Option<(SyntaxNode, Reparser)> {
-    let node = algo::find_covering_element(node, range);
+/// Finds the innermost node containing the edit which can be reparsed on its
+/// own, and which stays balanced after the edit. Returns it along with its
+/// edited text and tokens.
+fn find_reparsable_node(
+    root: &SyntaxNode,
+    edit: &AtomTextEdit,
+) -> Option<(SyntaxNode, Reparser, String, Vec<Token>, Vec<SyntaxError>)> {
+    let node = algo::find_covering_element(root, edit.delete);
 
     let mut ancestors = match node {
         NodeOrToken::Token(it) => it.parent().ancestors(),
@@ -136,33 +151,70 @@ fn find_reparsable_node(node: &SyntaxNode, range: TextRange) -> Option<(SyntaxNo
     ancestors.find_map(|node| {
         let first_child = node.first_child_or_token().map(|it| it.kind());
         let parent = node.parent().map(|it| it.kind());
-        Reparser::for_node(node.kind(), first_child, parent).map(|r| (node, r))
+        let reparser = Reparser::for_node(node.kind(), first_child, parent)?;
+
+        let text = get_text_after_edit(node.clone().into(), edit);
+        let (tokens, lexer_errors) = tokenize(&text);
+        if !is_balanced(&node, &tokens) {
+            return None;
+        }
+        Some((node, reparser, text, tokens, lexer_errors))
     })
 }
 
-fn is_balanced(tokens: &[Token]) -> bool {
-    if tokens.is_empty()
-        || tokens.first().unwrap().kind != T!['{']
-        || tokens.last().unwrap().kind != T!['}']
-    {
+fn is_balanced(node: &SyntaxNode, tokens: &[Token]) -> bool {
+    let (bra, ket) = match (tokens.first(), tokens.last()) {
+        (Some(first), Some(last)) if tokens.len() > 1 => (first.kind, last.kind),
+        _ => return false,
+    };
+    if closing_delimiter(bra) != Some(ket) {
         return false;
     }
+    let inner = &tokens[1..tokens.len() - 1];
+    if node.kind() == TOKEN_TREE {
+        // Token trees nest all delimiter kinds, so a mismatched inner delimiter
+        // would swallow tokens past the end of the reparsed node.
+        let mut stack = Vec::new();
+        for t in inner {
+            if let Some(closing) = closing_delimiter(t.kind) {
+                stack.push(closing);
+            } else if is_closing_delimiter(t.kind) && stack.pop() != Some(t.kind) {
+                return false;
+            }
+        }
+        return stack.is_empty();
+    }
     let mut balance = 0usize;
-    for t in &tokens[1..tokens.len() - 1] {
-        match t.kind {
-            T!['{'] => balance += 1,
-            T!['}'] => {
-                balance = match balance.checked_sub(1) {
-                    Some(b) => b,
-                    None => return false,
-                }
+    for t in inner {
+        if t.kind == bra {
+            balance += 1;
+        } else if t.kind == ket {
+            balance = match balance.checked_sub(1) {
+                Some(b) => b,
+                None => return false,
             }
-            _ => (),
         }
     }
     balance == 0
 }
 
+fn closing_delimiter(kind: SyntaxKind) -> Option<SyntaxKind> {
+    let res = match kind {
+        T!['{'] => T!['}'],
+        T!['('] => T![')'],
+        T!['['] => T![']'],
+        _ => return None,
+    };
+    Some(res)
+}
+
+fn is_closing_delimiter(kind: SyntaxKind) -> bool {
+    match kind {
+        T!['}'] | T![')'] | T![']'] => true,
+        _ => false,
+    }
+}
+
 fn merge_errors(
     old_errors: Vec<SyntaxError>,
     new_errors: Vec<SyntaxError>,
@@ -309,7 +361,7 @@ fn foo() {
 }
 ",
             "123",
-            14,
+            2,
         );
         do_check(
             r"
@@ -322,6 +374,71 @@ extern {
         );
     }
 
+    #[test]
+    fn reparse_delimited_node_tests() {
+        do_check(
+            r#"
+#[cfg_attr(feature = "serde", derive(<|>Serialize<|>))]
+struct Foo;
+"#,
+            "Serialize, Deserialize",
+            11,
+        );
+        do_check(
+            r"
+#[cfg_attr(all(unix, <|><|>), allow(dead_code))]
+struct Foo;
+",
+            "not(test)",
+            8,
+        );
+        do_check(
+            r"
+fn foo() {
+    foo!(a, [b<|><|>]);
+}
+",
+            ", c",
+            3,
+        );
+        do_check(
+            r"
+fn foo() {
+    let S { a<|><|> } = s;
+}
+",
+            ", b, ..",
+            5,
+        );
+        do_check(
+            r"
+fn foo(x: i32<|><|>) {}
+",
+            ", y: u32",
+            8,
+        );
+        do_check(
+            r"
+type F = Box<dyn Fn(i32<|><|>) -> ()>;
+",
+            ", u32",
+            5,
+        );
+    }
+
+    #[test]
+    fn reparse_outer_block_if_token_tree_becomes_unbalanced() {
+        do_check(
+            r"
+fn foo() {
+    foo!(a, [b<|><|>]);
+}
+",
+            ")",
+            21,
+        );
+    }
+
     #[test]
     fn reparse_token_tests() {
         do_check(
@@ -451,4 +568,92 @@ enum Foo {
             105,
         )
     }
+
+    #[test]
+    fn reparse_token_with_unbalanced_braces() {
+        do_check(
+            r"
+fn foo() {
+    let x = 1;
+    }
+}
+
+fn <|>bar<|>() {}
+",
+            "baz",
+            3,
+        );
+        do_check(
+            r"
+fn foo() {
+    if x {
+}
+
+fn bar() { <|>y<|> }
+",
+            "zz",
+            1,
+        );
+    }
+
+    #[test]
+    fn no_block_reparse_with_unbalanced_braces() {
+        let check = |before: &str, replace_with: &str| {
+            let (range, before) = extract_range(before);
+            let edit = AtomTextEdit::replace(range, replace_with.to_owned());
+            let after = edit.apply(before.clone());
+
+            let before = SourceFile::parse(&before);
+            let incrementally_reparsed =
+                incremental_reparse(before.tree().syntax(), &edit, before.errors.to_vec());
+            assert!(incrementally_reparsed.is_none());
+
+            let fully_reparsed = SourceFile::parse(&after);
+            let reparsed = before.reparse(&edit);
+            assert_eq_text!(
+                &format!("{:#?}", fully_reparsed.tree().syntax()),
+                &format!("{:#?}", reparsed.tree().syntax()),
+            );
+            assert_eq!(fully_reparsed.errors(), reparsed.errors());
+        };
+
+        // The indentation decides which `}` is stray.
+        check(
+            r"
+fn foo() {
+    let x = 1;
+<|>    <|>}
+}
+
+fn bar() {}
+",
+            "        ",
+        );
+        // Moving the `}` to its own line makes it the stray one.
+        check(
+            r"
+fn foo() {
+    if x {<|><|>}
+    }
+}
+
+fn bar() {}
+",
+            "\n        ",
+        );
+    }
+
+    #[test]
+    fn reparse_inside_unfinished_fn_signature() {
+        do_check(
+            r"
+impl S {
+    fn foo(x:<|><|>
+    fn bar() {}
+}
+",
+            " i32",
+            33,
+        )
+    }
 }
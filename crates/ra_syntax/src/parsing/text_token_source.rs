@@ -3,7 +3,11 @@
 use ra_parser::Token as PToken;
 use ra_parser::TokenSource;
 
-use crate::{parsing::lexer::Token, SyntaxKind::EOF, TextRange, TextUnit};
+use crate::{
+    parsing::{brace_recovery::BraceRecovery, lexer::Token},
+    SyntaxKind::EOF,
+    TextRange, TextUnit, T,
+};
 
 pub(crate) struct TextTokenSource<'t> {
     text: &'t str,
@@ -69,12 +73,22 @@ fn mk_token(pos: usize, start_offsets: &[TextUnit], tokens: &[Token]) -> PToken
 
 impl<'t> TextTokenSource<'t> {
     /// Generate input from tokens(expect comment and whitespace).
-    pub fn new(text: &'t str, raw_tokens: &'t [Token]) -> TextTokenSource<'t> {
+    /// Stray `}` are skipped as well, and the blocks closed by brace recovery
+    /// get empty `}` tokens.
+    pub fn new(
+        text: &'t str,
+        raw_tokens: &'t [Token],
+        recovery: &BraceRecovery,
+    ) -> TextTokenSource<'t> {
         let mut tokens = Vec::new();
         let mut start_offsets = Vec::new();
         let mut len = 0.into();
-        for &token in raw_tokens.iter() {
-            if !token.kind.is_trivia() {
+        for (idx, &token) in raw_tokens.iter().enumerate() {
+            if !token.kind.is_trivia() && !recovery.is_stray(idx) {
+                for _ in 0..recovery.closed_before(idx) {
+                    tokens.push(Token { kind: T!['}'], len: 0.into() });
+                    start_offsets.push(len);
+                }
                 tokens.push(token);
                 start_offsets.push(len);
             }
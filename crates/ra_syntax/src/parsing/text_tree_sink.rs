@@ -5,22 +5,27 @@ use std::mem;
 use ra_parser::{ParseError, TreeSink};
 
 use crate::{
-    parsing::Token,
+    parsing::{brace_recovery::BraceRecovery, Token},
     syntax_node::GreenNode,
     SmolStr, SyntaxError,
     SyntaxKind::{self, *},
-    SyntaxTreeBuilder, TextRange, TextUnit,
+    SyntaxTreeBuilder, TextRange, TextUnit, T,
 };
 
 /// Bridges the parser with our specific syntax tree representation.
 ///
 /// `TextTreeSink` also handles attachment of trivia (whitespace) to nodes.
+/// Stray `}`, which the parser doesn't see, are attached like trivia, but
+/// never to the following node.
 pub(crate) struct TextTreeSink<'a> {
     text: &'a str,
     tokens: &'a [Token],
+    recovery: &'a BraceRecovery,
     text_pos: TextUnit,
     token_pos: usize,
     state: State,
+    /// Kinds of the nodes which are started, but not yet finished.
+    parents: Vec<SyntaxKind>,
     inner: SyntaxTreeBuilder,
 }
 
@@ -34,9 +39,15 @@ impl<'a> TreeSink for TextTreeSink<'a> {
     fn token(&mut self, kind: SyntaxKind, n_tokens: u8) {
         match mem::replace(&mut self.state, State::Normal) {
             State::PendingStart => unreachable!(),
-            State::PendingFinish => self.inner.finish_node(),
+            State::PendingFinish => self.finish_pending_node(),
             State::Normal => (),
         }
+        if kind == T!['}'] && self.next_token_kind() != Some(T!['}']) {
+            // This `}` was inserted by brace recovery to close a block
+            // before an item. The trivia belong to the item.
+            self.inner.error(ParseError("expected R_CURLY".into()), self.text_pos);
+            return;
+        }
         self.eat_trivias();
         let n_tokens = n_tokens as usize;
         let len = self.tokens[self.token_pos..self.token_pos + n_tokens]
@@ -49,20 +60,28 @@ impl<'a> TreeSink for TextTreeSink<'a> {
     fn start_node(&mut self, kind: SyntaxKind) {
         match mem::replace(&mut self.state, State::Normal) {
             State::PendingStart => {
+                self.parents.push(kind);
                 self.inner.start_node(kind);
                 // No need to attach trivias to previous node: there is no
                 // previous node.
                 return;
             }
-            State::PendingFinish => self.inner.finish_node(),
+            State::PendingFinish => self.finish_pending_node(),
             State::Normal => (),
         }
 
         let n_trivias =
-            self.tokens[self.token_pos..].iter().take_while(|it| it.kind.is_trivia()).count();
-        let leading_trivias = &self.tokens[self.token_pos..self.token_pos + n_trivias];
-        let mut trivia_end =
-            self.text_pos + leading_trivias.iter().map(|it| it.len).sum::<TextUnit>();
+            (self.token_pos..self.tokens.len()).take_while(|&it| self.is_skipped(it)).count();
+        // A comment on the same line as the preceding token, like in
+        // `A = 1, // first variant`, is not a leading comment.
+        let n_trailing_trivias = if self.follows_closed_token() {
+            n_trailing_trivias(self.upcoming_tokens(n_trivias))
+        } else {
+            0
+        };
+        let trivias = &self.tokens[self.token_pos..self.token_pos + n_trivias];
+        let mut trivia_end = self.text_pos + trivias.iter().map(|it| it.len).sum::<TextUnit>();
+        let leading_trivias = &trivias[n_trailing_trivias..];
 
         let n_attached_trivias = {
             let leading_trivias = leading_trivias.iter().rev().map(|it| {
@@ -74,6 +93,7 @@ impl<'a> TreeSink for TextTreeSink<'a> {
             n_attached_trivias(kind, leading_trivias)
         };
         self.eat_n_trivias(n_trivias - n_attached_trivias);
+        self.parents.push(kind);
         self.inner.start_node(kind);
         self.eat_n_trivias(n_attached_trivias);
     }
@@ -81,7 +101,7 @@ impl<'a> TreeSink for TextTreeSink<'a> {
     fn finish_node(&mut self) {
         match mem::replace(&mut self.state, State::PendingFinish) {
             State::PendingStart => unreachable!(),
-            State::PendingFinish => self.inner.finish_node(),
+            State::PendingFinish => self.finish_pending_node(),
             State::Normal => (),
         }
     }
@@ -92,13 +112,15 @@ impl<'a> TreeSink for TextTreeSink<'a> {
 }
 
 impl<'a> TextTreeSink<'a> {
-    pub(super) fn new(text: &'a str, tokens: &'a [Token]) -> Self {
+    pub(super) fn new(text: &'a str, tokens: &'a [Token], recovery: &'a BraceRecovery) -> Self {
         Self {
             text,
             tokens,
+            recovery,
             text_pos: 0.into(),
             token_pos: 0,
             state: State::PendingStart,
+            parents: Vec::new(),
             inner: SyntaxTreeBuilder::default(),
         }
     }
@@ -107,6 +129,7 @@ impl<'a> TextTreeSink<'a> {
         match mem::replace(&mut self.state, State::Normal) {
             State::PendingFinish => {
                 self.eat_trivias();
+                self.parents.pop();
                 self.inner.finish_node()
             }
             State::PendingStart | State::Normal => unreachable!(),
@@ -115,23 +138,80 @@ impl<'a> TextTreeSink<'a> {
         self.inner.finish_raw()
     }
 
+    /// Finishes the node whose end was deferred until the next event,
+    /// attaching a comment on the same line as its end to it first.
+    ///
+    /// A comment after a separator, like in `A = 1, // first variant`, stays
+    /// in the parent: the comma belongs to the list, so the comment can't be
+    /// attached to the variant without moving the comma into it as well.
+    fn finish_pending_node(&mut self) {
+        let kind = self.parents.pop().unwrap();
+        if attaches_trivias(kind) {
+            let n_trivias =
+                self.tokens[self.token_pos..].iter().take_while(|it| it.kind.is_trivia()).count();
+            let n_trailing_trivias = n_trailing_trivias(self.upcoming_tokens(n_trivias));
+            self.eat_n_trivias(n_trailing_trivias);
+        }
+        self.inner.finish_node();
+    }
+
     fn eat_trivias(&mut self) {
-        while let Some(&token) = self.tokens.get(self.token_pos) {
-            if !token.kind.is_trivia() {
-                break;
-            }
-            self.do_token(token.kind, token.len, 1);
+        while self.token_pos < self.tokens.len() && self.is_skipped(self.token_pos) {
+            self.eat_trivia();
         }
     }
 
     fn eat_n_trivias(&mut self, n: usize) {
         for _ in 0..n {
-            let token = self.tokens[self.token_pos];
-            assert!(token.kind.is_trivia());
+            assert!(self.is_skipped(self.token_pos));
+            self.eat_trivia();
+        }
+    }
+
+    fn eat_trivia(&mut self) {
+        let token = self.tokens[self.token_pos];
+        if token.kind.is_trivia() {
             self.do_token(token.kind, token.len, 1);
+            return;
+        }
+        self.inner.start_node(ERROR);
+        self.inner.error(ParseError("unmatched `}`".into()), self.text_pos);
+        self.do_token(token.kind, token.len, 1);
+        self.inner.finish_node();
+    }
+
+    /// Whether the token at `pos` is not seen by the parser.
+    fn is_skipped(&self, pos: usize) -> bool {
+        self.tokens[pos].kind.is_trivia() || self.recovery.is_stray(pos)
+    }
+
+    /// Kinds and texts of the next `n` tokens.
+    fn upcoming_tokens(&self, n: usize) -> impl Iterator<Item = (SyntaxKind, &'a str)> + 'a {
+        let text = self.text;
+        self.tokens[self.token_pos..self.token_pos + n].iter().scan(
+            self.text_pos,
+            move |start, it| {
+                let range = TextRange::offset_len(*start, it.len);
+                *start += it.len;
+                Some((it.kind, &text[range]))
+            },
+        )
+    }
+
+    /// Whether the current token directly follows a token which doesn't
+    /// open a delimited list.
+    fn follows_closed_token(&self) -> bool {
+        match self.token_pos.checked_sub(1).map(|it| self.tokens[it].kind) {
+            Some(T!['{']) | Some(T!['(']) | Some(T!['[']) | None => false,
+            Some(kind) => !kind.is_trivia(),
         }
     }
 
+    fn next_token_kind(&self) -> Option<SyntaxKind> {
+        let pos = (self.token_pos..self.tokens.len()).find(|&it| !self.is_skipped(it))?;
+        Some(self.tokens[pos].kind)
+    }
+
     fn do_token(&mut self, kind: SyntaxKind, len: TextUnit, n_tokens: usize) {
         let range = TextRange::offset_len(self.text_pos, len);
         let text: SmolStr = self.text[range].into();
@@ -141,29 +221,53 @@ impl<'a> TextTreeSink<'a> {
     }
 }
 
+fn attaches_trivias(kind: SyntaxKind) -> bool {
+    match kind {
+        MACRO_CALL | CONST_DEF | TYPE_ALIAS_DEF | STRUCT_DEF | ENUM_DEF | ENUM_VARIANT | FN_DEF
+        | TRAIT_DEF | MODULE | RECORD_FIELD_DEF | STATIC_DEF => true,
+        _ => false,
+    }
+}
+
 fn n_attached_trivias<'a>(
     kind: SyntaxKind,
     trivias: impl Iterator<Item = (SyntaxKind, &'a str)>,
 ) -> usize {
-    match kind {
-        MACRO_CALL | CONST_DEF | TYPE_ALIAS_DEF | STRUCT_DEF | ENUM_DEF | ENUM_VARIANT | FN_DEF
-        | TRAIT_DEF | MODULE | RECORD_FIELD_DEF | STATIC_DEF => {
-            let mut res = 0;
-            for (i, (kind, text)) in trivias.enumerate() {
-                match kind {
-                    WHITESPACE => {
-                        if text.contains("\n\n") {
-                            break;
-                        }
-                    }
-                    COMMENT => {
-                        res = i + 1;
-                    }
-                    _ => (),
+    if !attaches_trivias(kind) {
+        return 0;
+    }
+    let mut res = 0;
+    for (i, (kind, text)) in trivias.enumerate() {
+        match kind {
+            WHITESPACE => {
+                if text.contains("\n\n") {
+                    break;
+                }
+            }
+            COMMENT => {
+                res = i + 1;
+            }
+            _ => break,
+        }
+    }
+    res
+}
+
+/// Counts the trivias which form comments on the same line as the text
+/// preceding them.
+fn n_trailing_trivias<'a>(trivias: impl Iterator<Item = (SyntaxKind, &'a str)>) -> usize {
+    let mut res = 0;
+    for (i, (kind, text)) in trivias.enumerate() {
+        match kind {
+            WHITESPACE if !text.contains('\n') => (),
+            COMMENT => {
+                res = i + 1;
+                if text.contains('\n') {
+                    break;
                 }
             }
-            res
+            _ => break,
         }
-        _ => 0,
     }
+    res
 }
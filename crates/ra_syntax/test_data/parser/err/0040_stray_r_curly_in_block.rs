@@ -0,0 +1,6 @@
+fn foo() {
+    let x = 1;
+    }
+}
+
+fn bar() {}
@@ -0,0 +1,6 @@
+fn foo() {
+    if x {
+}
+
+/// Docs
+fn bar() {}
@@ -0,0 +1,5 @@
+impl S {
+    fn foo(x:
+    fn bar() {}
+    fn baz(&self) {}
+}
@@ -0,0 +1 @@
+type A = Box<for<'a> Fn(&'a u8) + Send + 'static>;
@@ -0,0 +1 @@
+fn foo(x: impl Iterator<Item = u32> + Send + 'static, y: impl (Clone) + ?Sized) {}
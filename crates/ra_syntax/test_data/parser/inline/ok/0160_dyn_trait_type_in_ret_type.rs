@@ -0,0 +1 @@
+fn foo() -> dyn Iterator<Item = u32> + Send + 'static {}
@@ -0,0 +1,3 @@
+type A = &(dyn Trait + Send);
+type B = &'a mut (Trait + Send + 'a);
+type C = *const (dyn for<'a> Trait<'a> + Send);
@@ -0,0 +1,6 @@
+fn foo() {
+    let g = || {
+        yield;
+        yield 92;
+    };
+}
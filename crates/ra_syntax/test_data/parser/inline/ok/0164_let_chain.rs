@@ -0,0 +1,4 @@
+fn foo() {
+    if let Some(x) = a && let Some(y) = x.b && y > 0 {}
+    while a && let Some(x) = b() {}
+}
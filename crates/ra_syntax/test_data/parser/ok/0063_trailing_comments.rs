@@ -0,0 +1,17 @@
+// A comment after a comma stays in the list, as the comma isn't part of
+// the variant or field before it.
+
+enum E {
+    A = 1, // first variant
+    /// Docs of B.
+    B = 2 // second variant
+}
+
+struct S {
+    a: u32, // first field
+    b: u32 // second field
+}
+
+struct T; // trailing
+
+fn f() {} // trailing
@@ -4,10 +4,10 @@ mod text_edit;
 
 use text_unit::{TextRange, TextUnit};
 
-pub use crate::text_edit::{TextEdit, TextEditBuilder};
+pub use crate::text_edit::{TextEdit, TextEditBuilder, TextEditConflict};
 
 /// Must not overlap with other `AtomTextEdit`s
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AtomTextEdit {
     /// Refers to offsets in the original text
     pub delete: TextRange,
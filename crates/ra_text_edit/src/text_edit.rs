@@ -1,13 +1,31 @@
 //! FIXME: write short doc here
 
+use std::fmt;
+
 use crate::AtomTextEdit;
 use text_unit::{TextRange, TextUnit};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TextEdit {
     atoms: Vec<AtomTextEdit>,
 }
 
+/// Two atoms which change overlapping parts of the text, so they can't be
+/// applied together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEditConflict {
+    pub first: AtomTextEdit,
+    pub second: AtomTextEdit,
+}
+
+impl fmt::Display for TextEditConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "edits of {:?} and {:?} overlap", self.first.delete, self.second.delete)
+    }
+}
+
+impl std::error::Error for TextEditConflict {}
+
 #[derive(Debug, Default)]
 pub struct TextEditBuilder {
     atoms: Vec<AtomTextEdit>,
@@ -50,12 +68,35 @@ impl TextEdit {
         builder.finish()
     }
 
-    pub(crate) fn from_atoms(mut atoms: Vec<AtomTextEdit>) -> TextEdit {
+    pub(crate) fn from_atoms(atoms: Vec<AtomTextEdit>) -> TextEdit {
+        match TextEdit::try_from_atoms(atoms) {
+            Ok(edit) => edit,
+            Err(conflict) => panic!("{}", conflict),
+        }
+    }
+
+    /// Sorts the atoms, checking that none of them overlap. Insertions at the
+    /// same offset are kept in their original order.
+    pub fn try_from_atoms(mut atoms: Vec<AtomTextEdit>) -> Result<TextEdit, TextEditConflict> {
         atoms.sort_by_key(|a| (a.delete.start(), a.delete.end()));
-        for (a1, a2) in atoms.iter().zip(atoms.iter().skip(1)) {
-            assert!(a1.delete.end() <= a2.delete.start())
+        for pair in atoms.windows(2) {
+            if pair[0].delete.end() > pair[1].delete.start() {
+                return Err(TextEditConflict { first: pair[0].clone(), second: pair[1].clone() });
+            }
+        }
+        Ok(TextEdit { atoms })
+    }
+
+    /// Combines two edits of the same text. Atoms of `other` which are also
+    /// part of `self` are only applied once.
+    pub fn union(self, other: TextEdit) -> Result<TextEdit, TextEditConflict> {
+        let mut atoms = self.atoms;
+        for atom in other.atoms {
+            if !atoms.contains(&atom) {
+                atoms.push(atom);
+            }
         }
-        TextEdit { atoms }
+        TextEdit::try_from_atoms(atoms)
     }
 
     pub fn as_atoms(&self) -> &[AtomTextEdit] {
@@ -99,3 +140,62 @@ impl TextEdit {
         Some(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::from_to(start.into(), end.into())
+    }
+
+    #[test]
+    fn union_of_adjacent_edits() {
+        let first = TextEdit::replace(range(0, 3), "foo".to_string());
+        let second = TextEdit::replace(range(3, 6), "bar".to_string());
+        let edit = first.union(second).unwrap();
+        assert_eq!(edit.apply("abcdef!"), "foobar!");
+    }
+
+    #[test]
+    fn union_keeps_order_of_insertions_at_same_offset() {
+        let first = TextEdit::insert(3.into(), "foo".to_string());
+        let second = TextEdit::insert(3.into(), "bar".to_string());
+        let edit = first.union(second).unwrap();
+        assert_eq!(edit.apply("abcdef"), "abcfoobardef");
+    }
+
+    #[test]
+    fn union_coalesces_identical_atoms() {
+        let first = TextEdit::insert(3.into(), "foo".to_string());
+        let second = TextEdit::insert(3.into(), "foo".to_string());
+        let edit = first.union(second).unwrap();
+        assert_eq!(edit.apply("abcdef"), "abcfoodef");
+
+        let first = TextEdit::replace(range(1, 4), "x".to_string());
+        let second = TextEdit::replace(range(1, 4), "x".to_string());
+        let edit = first.union(second).unwrap();
+        assert_eq!(edit.apply("abcdef"), "axef");
+    }
+
+    #[test]
+    fn union_detects_nested_atoms() {
+        let first = TextEdit::replace(range(0, 6), "foo".to_string());
+        let second = TextEdit::insert(3.into(), "bar".to_string());
+        let conflict = first.union(second).unwrap_err();
+        assert_eq!(conflict.first.delete, range(0, 6));
+        assert_eq!(conflict.second.delete, range(3, 3));
+    }
+
+    #[test]
+    fn union_detects_overlapping_atoms() {
+        let first = TextEdit::replace(range(2, 5), "foo".to_string());
+        let second = TextEdit::replace(range(2, 5), "bar".to_string());
+        assert!(first.union(second).is_err());
+
+        let first = TextEdit::delete(range(3, 6));
+        let second = TextEdit::delete(range(0, 4));
+        let conflict = first.union(second).unwrap_err();
+        assert_eq!(conflict.to_string(), "edits of [0; 4) and [3; 6) overlap");
+    }
+}
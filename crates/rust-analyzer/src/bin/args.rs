@@ -33,6 +33,10 @@ pub(crate) enum Command {
         path: PathBuf,
         what: BenchWhat,
     },
+    Diagnostics {
+        with_deps: bool,
+        path: PathBuf,
+    },
     RunServer,
     Version,
 }
@@ -201,6 +205,38 @@ ARGS:
                 };
                 Command::Bench { path, what }
             }
+            "diagnostics" => {
+                if matches.contains(["-h", "--help"]) {
+                    eprintln!(
+                        "\
+ra-cli-diagnostics
+
+USAGE:
+    rust-analyzer diagnostics [FLAGS] [PATH]
+
+FLAGS:
+    -h, --help        Prints help information
+        --with-deps   Also report the diagnostics of the dependencies
+
+ARGS:
+    <PATH>    Project to analyse
+
+Prints the diagnostics as JSON lines and fails if any of them is an error."
+                    );
+                    return Ok(Err(HelpPrinted));
+                }
+
+                let with_deps: bool = matches.contains("--with-deps");
+                let path = {
+                    let mut trailing = matches.free()?;
+                    if trailing.len() != 1 {
+                        bail!("Invalid flags");
+                    }
+                    trailing.pop().unwrap().into()
+                };
+
+                Command::Diagnostics { with_deps, path }
+            }
             _ => {
                 eprintln!(
                     "\
@@ -215,6 +251,7 @@ FLAGS:
 SUBCOMMANDS:
     analysis-bench
     analysis-stats
+    diagnostics
     highlight
     parse
     symbols"
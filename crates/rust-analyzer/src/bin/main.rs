@@ -3,9 +3,11 @@
 //! Based on cli flags, either spawns an LSP server, or runs a batch analysis
 mod args;
 
-use lsp_server::Connection;
+use lsp_server::{Connection, Message, Response};
 
-use rust_analyzer::{cli, from_json, show_message, Result, ServerConfig};
+use rust_analyzer::{
+    cli, config_warnings_message, from_json, show_message, OffsetEncoding, Result, ServerConfig,
+};
 
 use crate::args::HelpPrinted;
 
@@ -34,6 +36,10 @@ fn main() -> Result<()> {
             cli::analysis_bench(args.verbosity, path.as_ref(), what)?
         }
 
+        args::Command::Diagnostics { path, with_deps } => {
+            cli::diagnostics(path.as_ref(), with_deps)?
+        }
+
         args::Command::RunServer => run_server()?,
         args::Command::Version => println!("rust-analyzer {}", env!("REV")),
     }
@@ -51,9 +57,34 @@ fn run_server() -> Result<()> {
     log::info!("lifecycle: server started");
 
     let (connection, io_threads) = Connection::stdio();
-    let server_capabilities = serde_json::to_value(rust_analyzer::server_capabilities()).unwrap();
+    let (initialize_id, initialize_params) = match connection.receiver.recv()? {
+        Message::Request(req) if req.method == "initialize" => (req.id, req.params),
+        msg => return Err(format!("expected initialize request, got {:?}", msg).into()),
+    };
+
+    let offset_encoding = OffsetEncoding::negotiate(&initialize_params["capabilities"]);
+    log::info!("offset encoding: {}", offset_encoding.as_str());
+    let resolve_code_actions =
+        rust_analyzer::supports_code_action_resolve(&initialize_params["capabilities"]);
+
+    let mut server_capabilities =
+        serde_json::to_value(rust_analyzer::server_capabilities()).unwrap();
+    server_capabilities["workspace"]["fileOperations"] =
+        rust_analyzer::file_operations_capability();
+    server_capabilities["codeActionProvider"] = rust_analyzer::code_action_capability();
+    let mut initialize_result = serde_json::Map::new();
+    if offset_encoding != OffsetEncoding::default() {
+        let encoding = serde_json::Value::from(offset_encoding.as_str());
+        server_capabilities["positionEncoding"] = encoding.clone();
+        initialize_result.insert("offsetEncoding".to_string(), encoding);
+    }
+    initialize_result.insert("capabilities".to_string(), server_capabilities);
+    connection.sender.send(Response::new_ok(initialize_id, initialize_result).into()).unwrap();
+    match connection.receiver.recv()? {
+        Message::Notification(not) if not.method == "initialized" => (),
+        msg => return Err(format!("expected initialized notification, got {:?}", msg).into()),
+    }
 
-    let initialize_params = connection.initialize(server_capabilities)?;
     let initialize_params =
         from_json::<lsp_types::InitializeParams>("InitializeParams", initialize_params)?;
 
@@ -72,22 +103,28 @@ fn run_server() -> Result<()> {
         .filter(|workspaces| !workspaces.is_empty())
         .unwrap_or_else(|| vec![root]);
 
-    let server_config = initialize_params
-        .initialization_options
-        .and_then(|v| {
-            from_json::<ServerConfig>("config", v)
-                .map_err(|e| {
-                    log::error!("{}", e);
-                    show_message(lsp_types::MessageType::Error, e.to_string(), &connection.sender);
-                })
-                .ok()
-        })
-        .unwrap_or_default();
+    let server_config = match initialize_params.initialization_options {
+        Some(options) => {
+            let (config, warnings) = ServerConfig::from_json(options);
+            if !warnings.is_empty() {
+                let message = format!(
+                    "rust-analyzer: invalid configuration, using defaults for: {}",
+                    config_warnings_message(&warnings)
+                );
+                log::error!("{}", message);
+                show_message(lsp_types::MessageType::Warning, message, &connection.sender);
+            }
+            config
+        }
+        None => ServerConfig::default(),
+    };
 
     rust_analyzer::main_loop(
         workspace_roots,
         initialize_params.capabilities,
         server_config,
+        offset_encoding,
+        resolve_code_actions,
         connection,
     )?;
 
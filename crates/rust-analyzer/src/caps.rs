@@ -1,5 +1,7 @@
 //! Advertizes the capabilities of the LSP Server.
 
+use serde_json::json;
+
 use crate::semantic_tokens;
 
 use lsp_types::{
@@ -9,14 +11,15 @@ use lsp_types::{
     SelectionRangeProviderCapability, SemanticTokensDocumentProvider, SemanticTokensLegend,
     SemanticTokensOptions, ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability,
     TextDocumentSyncKind, TextDocumentSyncOptions, TypeDefinitionProviderCapability,
-    WorkDoneProgressOptions,
+    WorkDoneProgressOptions, WorkspaceCapability, WorkspaceFolderCapability,
+    WorkspaceFolderCapabilityChangeNotifications,
 };
 
 pub fn server_capabilities() -> ServerCapabilities {
     ServerCapabilities {
         text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
             open_close: Some(true),
-            change: Some(TextDocumentSyncKind::Full),
+            change: Some(TextDocumentSyncKind::Incremental),
             will_save: None,
             will_save_wait_until: None,
             save: Some(SaveOptions::default()),
@@ -58,7 +61,14 @@ pub fn server_capabilities() -> ServerCapabilities {
         document_link_provider: None,
         color_provider: None,
         execute_command_provider: None,
-        workspace: None,
+        workspace: Some(WorkspaceCapability {
+            workspace_folders: Some(WorkspaceFolderCapability {
+                supported: Some(true),
+                change_notifications: Some(WorkspaceFolderCapabilityChangeNotifications::Bool(
+                    true,
+                )),
+            }),
+        }),
         call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
         semantic_tokens_provider: Some(
             SemanticTokensOptions {
@@ -76,3 +86,53 @@ pub fn server_capabilities() -> ServerCapabilities {
         experimental: Default::default(),
     }
 }
+
+/// The `codeActionProvider` capability, with the `resolveProvider` from LSP
+/// 3.16, which `lsp-types` doesn't support yet: the edits of assists are
+/// computed only when the client resolves them.
+pub fn code_action_capability() -> serde_json::Value {
+    json!({ "resolveProvider": true })
+}
+
+/// Whether the client can resolve the edits of code actions lazily.
+pub fn supports_code_action_resolve(client_capabilities: &serde_json::Value) -> bool {
+    let properties = client_capabilities
+        .pointer("/textDocument/codeAction/resolveSupport/properties")
+        .and_then(|it| it.as_array());
+    match properties {
+        Some(properties) => properties.iter().any(|it| it == "edit"),
+        None => false,
+    }
+}
+
+/// The `workspace.fileOperations` capability from LSP 3.16, which `lsp-types`
+/// doesn't support yet: we want to hear about renamed Rust files and
+/// directories, to update the module tree.
+pub fn file_operations_capability() -> serde_json::Value {
+    json!({
+        "willRename": {
+            "filters": [
+                { "scheme": "file", "pattern": { "glob": "**/*.rs", "matches": "file" } },
+                { "scheme": "file", "pattern": { "glob": "**", "matches": "folder" } },
+            ]
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_action_resolve_support() {
+        let edit = json!({
+            "textDocument": { "codeAction": { "resolveSupport": { "properties": ["edit"] } } }
+        });
+        assert!(supports_code_action_resolve(&edit));
+        let command = json!({
+            "textDocument": { "codeAction": { "resolveSupport": { "properties": ["command"] } } }
+        });
+        assert!(!supports_code_action_resolve(&command));
+        assert!(!supports_code_action_resolve(&json!({ "textDocument": {} })));
+    }
+}
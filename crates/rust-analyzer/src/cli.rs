@@ -3,6 +3,7 @@
 mod load_cargo;
 mod analysis_stats;
 mod analysis_bench;
+mod diagnostics;
 mod progress_report;
 
 use std::io::Read;
@@ -62,6 +63,7 @@ pub fn highlight(rainbow: bool) -> Result<()> {
 
 pub use analysis_bench::{analysis_bench, BenchWhat, Position};
 pub use analysis_stats::analysis_stats;
+pub use diagnostics::diagnostics;
 
 fn file() -> Result<SourceFile> {
     let text = read_stdin()?;
@@ -245,6 +245,9 @@ pub fn analysis_stats(
     );
     println!("Type mismatches: {}", num_type_mismatches);
     println!("Inference: {:?}, {}", inference_time.elapsed(), ra_prof::memory_usage());
+    for (label, count) in ra_prof::counters() {
+        println!("{}: {}", label, count);
+    }
     println!("Total: {:?}, {}", analysis_time.elapsed(), ra_prof::memory_usage());
 
     if memory_usage {
@@ -0,0 +1,160 @@
+//! Analyze all files in the project for diagnostics and print them as JSON
+//! lines. Fails if any of the diagnostics is an error.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use ra_db::{SourceDatabaseExt, SourceRootId};
+use ra_ide::{AnalysisHost, Severity};
+use ra_project_model::PackageRoot;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::cli::load_cargo::load_cargo;
+
+#[derive(Debug, Serialize)]
+struct FileDiagnostic {
+    file: PathBuf,
+    range: Range,
+    severity: &'static str,
+    code: &'static str,
+    message: String,
+}
+
+/// Zero-based lines and UTF-16 columns, like in LSP.
+#[derive(Debug, Serialize)]
+struct Range {
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+pub fn diagnostics(path: &Path, with_deps: bool) -> Result<()> {
+    let (host, roots) = load_cargo(path)?;
+    let diagnostics = workspace_diagnostics(&host, &roots, with_deps);
+
+    let mut num_errors = 0;
+    for diagnostic in diagnostics {
+        if diagnostic.severity == "error" {
+            num_errors += 1;
+        }
+        println!("{}", serde_json::to_string(&diagnostic)?);
+    }
+
+    if num_errors > 0 {
+        bail!("found {} error diagnostic(s)", num_errors);
+    }
+    Ok(())
+}
+
+fn workspace_diagnostics(
+    host: &AnalysisHost,
+    roots: &FxHashMap<SourceRootId, PackageRoot>,
+    with_deps: bool,
+) -> Vec<FileDiagnostic> {
+    let db = host.raw_database();
+    let analysis = host.analysis();
+
+    let mut roots = roots
+        .iter()
+        .filter(|(_, project_root)| with_deps || project_root.is_member())
+        .collect::<Vec<_>>();
+    roots.sort_by(|(_, a), (_, b)| a.path().cmp(b.path()));
+
+    let mut res = Vec::new();
+    for (&source_root_id, project_root) in roots {
+        let mut files = db
+            .source_root(source_root_id)
+            .walk()
+            .map(|file_id| (db.file_relative_path(file_id), file_id))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        for (relative_path, file_id) in files {
+            let line_index = analysis.file_line_index(file_id).unwrap();
+            let file = relative_path.to_path(project_root.path());
+            for diagnostic in analysis.diagnostics(file_id).unwrap() {
+                let start = line_index.line_col(diagnostic.range.start());
+                let end = line_index.line_col(diagnostic.range.end());
+                let severity = match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::WeakWarning => "weak_warning",
+                };
+                res.push(FileDiagnostic {
+                    file: file.clone(),
+                    range: Range {
+                        start_line: start.line,
+                        start_column: start.col_utf16,
+                        end_line: end.line,
+                        end_column: end.col_utf16,
+                    },
+                    severity,
+                    code: diagnostic.code,
+                    message: diagnostic.message,
+                });
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+    use tempfile::TempDir;
+    use test_utils::parse_fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_of_json_project() {
+        let tmp_dir = TempDir::new().unwrap();
+        let path = tmp_dir.path();
+        let project = json!({
+            "roots": [path],
+            "crates": [ {
+                "root_module": path.join("src/lib.rs"),
+                "deps": [],
+                "edition": "2018",
+                "atom_cfgs": [],
+                "key_value_cfgs": {}
+            } ]
+        });
+        let fixture = format!(
+            r#"
+//- rust-project.json
+{PROJECT}
+
+//- src/lib.rs
+mod foo;
+mod bar;
+
+//- src/foo.rs
+pub fn foo() {{}}
+"#,
+            PROJECT = project.to_string(),
+        );
+        for entry in parse_fixture(&fixture) {
+            let file = path.join(entry.meta);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, entry.text).unwrap();
+        }
+
+        let (host, roots) = load_cargo(path).unwrap();
+        let diagnostics = workspace_diagnostics(&host, &roots, false);
+        assert_eq!(
+            serde_json::to_value(&diagnostics).unwrap(),
+            json!([{
+                "file": path.join("src/lib.rs"),
+                "range": { "start_line": 1, "start_column": 0, "end_line": 1, "end_column": 8 },
+                "severity": "error",
+                "code": "unresolved-module",
+                "message": "unresolved module",
+            }])
+        );
+    }
+}
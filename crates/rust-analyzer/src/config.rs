@@ -1,16 +1,25 @@
 //! Config used by the language server.
 //!
-//! We currently get this config from `initialize` LSP request, which is not the
-//! best way to do it, but was the simplest thing we could implement.
+//! The initial config comes from the `initialize` LSP request. Afterwards, on
+//! `workspace/didChangeConfiguration`, the server pulls the new settings with a
+//! `workspace/configuration` request, and applies those which can change at
+//! runtime.
 //!
 //! Of particular interest is the `feature_flags` hash map: while other fields
 //! configure the server itself, feature flags are passed into analysis, and
 //! tweak things like automatic insertion of `()` in completions.
 
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
 use rustc_hash::FxHashMap;
 
+use ra_ide::FeatureFlags;
 use ra_project_model::CargoFeatures;
-use serde::{Deserialize, Deserializer};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use serde_json::{Map, Value};
 
 /// Client provided initialization options
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -27,6 +36,12 @@ pub struct ServerConfig {
     pub exclude_globs: Vec<String>,
     #[serde(deserialize_with = "nullable_bool_false")]
     pub use_client_watching: bool,
+    /// Whether to ask the client to watch the manifests of the workspace
+    /// members, and to reload the workspaces when one of them changes.
+    ///
+    /// Defaults to `true`
+    #[serde(deserialize_with = "nullable_bool_true")]
+    pub reload_on_manifest_change: bool,
 
     pub lru_capacity: Option<usize>,
 
@@ -48,6 +63,23 @@ pub struct ServerConfig {
 
     /// Cargo feature configurations.
     pub cargo_features: CargoFeatures,
+
+    /// If set, every request is profiled under its method name, and the
+    /// profile is written to this file as a Chrome trace on shutdown.
+    pub profile_file: Option<PathBuf>,
+
+    /// Overrides for individual workspace folders, keyed by the folder path.
+    pub root_overrides: FxHashMap<PathBuf, RootConfig>,
+}
+
+/// Workspace loading options which can differ between workspace folders.
+///
+/// Unset fields fall back to the corresponding `ServerConfig` value.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RootConfig {
+    pub with_sysroot: Option<bool>,
+    pub cargo_features: Option<CargoFeatures>,
 }
 
 impl Default for ServerConfig {
@@ -56,6 +88,7 @@ impl Default for ServerConfig {
             publish_decorations: false,
             exclude_globs: Vec::new(),
             use_client_watching: false,
+            reload_on_manifest_change: true,
             lru_capacity: None,
             max_inlay_hint_length: None,
             cargo_watch_enable: true,
@@ -66,8 +99,236 @@ impl Default for ServerConfig {
             feature_flags: FxHashMap::default(),
             cargo_features: Default::default(),
             rustfmt_args: Vec::new(),
+            profile_file: None,
+            root_overrides: FxHashMap::default(),
+        }
+    }
+}
+
+/// The keys of `ServerConfig`.
+const SERVER_CONFIG_FIELDS: &[&str] = &[
+    "publishDecorations",
+    "excludeGlobs",
+    "useClientWatching",
+    "reloadOnManifestChange",
+    "lruCapacity",
+    "maxInlayHintLength",
+    "cargoWatchEnable",
+    "cargoWatchArgs",
+    "cargoWatchCommand",
+    "cargoWatchAllTargets",
+    "withSysroot",
+    "featureFlags",
+    "rustfmtArgs",
+    "cargoFeatures",
+    "profileFile",
+    "rootOverrides",
+];
+
+/// The keys of `CargoFeatures`.
+const CARGO_FEATURES_FIELDS: &[&str] = &["noDefaultFeatures", "allFeatures", "features"];
+
+/// A setting sent by the client which could not be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigWarning {
+    /// Dotted path of the setting, like `cargoFeatures.allFeatures`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}`: {}", self.path, self.message)
+    }
+}
+
+/// The settings which differ between two configs.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ConfigChange {
+    /// Settings which are applied to the running server.
+    pub applied: Vec<&'static str>,
+    /// Settings which only take effect after the server is restarted.
+    pub needs_reload: Vec<&'static str>,
+}
+
+impl ServerConfig {
+    /// Deserializes the settings sent by the client one by one. Unknown keys
+    /// and invalid values are skipped, falling back to their defaults, and
+    /// reported as warnings.
+    pub fn from_json(json: Value) -> (ServerConfig, Vec<ConfigWarning>) {
+        let mut warnings = Vec::new();
+        let json = match json {
+            Value::Object(map) => map,
+            Value::Null => Map::new(),
+            other => {
+                warnings.push(ConfigWarning {
+                    path: "<root>".to_string(),
+                    message: format!("expected an object, got `{}`", other),
+                });
+                Map::new()
+            }
+        };
+
+        let mut valid = Map::new();
+        for (key, value) in json {
+            let value = match (key.as_str(), value) {
+                ("featureFlags", Value::Object(flags)) => {
+                    Value::Object(valid_feature_flags(flags, &mut warnings))
+                }
+                ("cargoFeatures", Value::Object(features)) => {
+                    Value::Object(valid_fields::<CargoFeatures>(
+                        "cargoFeatures.",
+                        CARGO_FEATURES_FIELDS,
+                        features,
+                        &mut warnings,
+                    ))
+                }
+                (_, value) => value,
+            };
+            valid.insert(key, value);
+        }
+        let valid = valid_fields::<ServerConfig>("", SERVER_CONFIG_FIELDS, valid, &mut warnings);
+
+        let config = ServerConfig::deserialize(&Value::Object(valid)).unwrap_or_else(|e| {
+            warnings.push(ConfigWarning { path: "<root>".to_string(), message: e.to_string() });
+            ServerConfig::default()
+        });
+        (config, warnings)
+    }
+
+    /// The feature flags to pass to the analysis.
+    pub fn feature_flags(&self) -> FeatureFlags {
+        let mut ff = FeatureFlags::default();
+        for (flag, &value) in &self.feature_flags {
+            // Unknown flags were already reported by `from_json`.
+            let _ = ff.set(flag.as_str(), value);
+        }
+        ff
+    }
+
+    /// Compares `self` with a `new` config, sorting the settings which
+    /// changed by whether they can be applied without a restart.
+    pub fn diff(&self, new: &ServerConfig) -> ConfigChange {
+        let mut res = ConfigChange::default();
+        let mut check = |changed: bool, name: &'static str, at_runtime: bool| {
+            if !changed {
+                return;
+            }
+            if at_runtime {
+                res.applied.push(name);
+            } else {
+                res.needs_reload.push(name);
+            }
+        };
+        check(self.publish_decorations != new.publish_decorations, "publishDecorations", false);
+        check(self.exclude_globs != new.exclude_globs, "excludeGlobs", false);
+        check(self.use_client_watching != new.use_client_watching, "useClientWatching", false);
+        check(
+            self.reload_on_manifest_change != new.reload_on_manifest_change,
+            "reloadOnManifestChange",
+            false,
+        );
+        check(self.lru_capacity != new.lru_capacity, "lruCapacity", false);
+        check(self.max_inlay_hint_length != new.max_inlay_hint_length, "maxInlayHintLength", true);
+        check(self.cargo_watch_enable != new.cargo_watch_enable, "cargoWatchEnable", true);
+        check(self.cargo_watch_args != new.cargo_watch_args, "cargoWatchArgs", true);
+        check(self.cargo_watch_command != new.cargo_watch_command, "cargoWatchCommand", true);
+        check(
+            self.cargo_watch_all_targets != new.cargo_watch_all_targets,
+            "cargoWatchAllTargets",
+            true,
+        );
+        check(self.with_sysroot != new.with_sysroot, "withSysroot", false);
+        check(self.feature_flags != new.feature_flags, "featureFlags", true);
+        check(self.rustfmt_args != new.rustfmt_args, "rustfmtArgs", true);
+        check(self.cargo_features != new.cargo_features, "cargoFeatures", false);
+        check(self.profile_file != new.profile_file, "profileFile", false);
+        check(self.root_overrides != new.root_overrides, "rootOverrides", false);
+        res
+    }
+
+    /// Returns a config with the settings of `new` which can be applied at
+    /// runtime, and the others kept from `self`.
+    pub fn with_runtime_settings(&self, new: &ServerConfig) -> ServerConfig {
+        ServerConfig {
+            max_inlay_hint_length: new.max_inlay_hint_length,
+            cargo_watch_enable: new.cargo_watch_enable,
+            cargo_watch_args: new.cargo_watch_args.clone(),
+            cargo_watch_command: new.cargo_watch_command.clone(),
+            cargo_watch_all_targets: new.cargo_watch_all_targets,
+            feature_flags: new.feature_flags.clone(),
+            rustfmt_args: new.rustfmt_args.clone(),
+            ..self.clone()
+        }
+    }
+
+    pub fn with_sysroot_for(&self, root: &Path) -> bool {
+        self.root_overrides.get(root).and_then(|it| it.with_sysroot).unwrap_or(self.with_sysroot)
+    }
+
+    pub fn cargo_features_for(&self, root: &Path) -> &CargoFeatures {
+        self.root_overrides
+            .get(root)
+            .and_then(|it| it.cargo_features.as_ref())
+            .unwrap_or(&self.cargo_features)
+    }
+}
+
+/// Formats `warnings` as a single message for the user.
+pub fn config_warnings_message(warnings: &[ConfigWarning]) -> String {
+    warnings.iter().map(|it| it.to_string()).collect::<Vec<_>>().join("; ")
+}
+
+/// Keeps the entries of `map` which are known fields of `T` and can be
+/// deserialized on their own, reporting the other ones.
+fn valid_fields<T: DeserializeOwned>(
+    prefix: &str,
+    fields: &[&str],
+    map: Map<String, Value>,
+    warnings: &mut Vec<ConfigWarning>,
+) -> Map<String, Value> {
+    let mut res = Map::new();
+    for (key, value) in map {
+        let path = format!("{}{}", prefix, key);
+        if !fields.contains(&key.as_str()) {
+            warnings.push(ConfigWarning { path, message: "unknown setting".to_string() });
+            continue;
+        }
+        let mut single = Map::new();
+        single.insert(key.clone(), value.clone());
+        match T::deserialize(&Value::Object(single)) {
+            Ok(_) => {
+                res.insert(key, value);
+            }
+            Err(e) => warnings.push(ConfigWarning { path, message: e.to_string() }),
+        }
+    }
+    res
+}
+
+fn valid_feature_flags(
+    flags: Map<String, Value>,
+    warnings: &mut Vec<ConfigWarning>,
+) -> Map<String, Value> {
+    let mut known = FeatureFlags::default();
+    let mut res = Map::new();
+    for (flag, value) in flags {
+        let path = format!("featureFlags.{}", flag);
+        match value {
+            Value::Bool(it) => {
+                if known.set(&flag, it).is_err() {
+                    warnings.push(ConfigWarning { path, message: "unknown feature flag".into() });
+                    continue;
+                }
+                res.insert(flag, value);
+            }
+            other => warnings.push(ConfigWarning {
+                path,
+                message: format!("invalid type: `{}`, expected a boolean", other),
+            }),
         }
     }
+    res
 }
 
 /// Deserializes a null value to a bool false by default
@@ -102,4 +363,67 @@ mod test {
             serde_json::from_str(r#"{"publishDecorations":null, "lruCapacity":null}"#).unwrap()
         );
     }
+
+    #[test]
+    fn deserialize_root_overrides() {
+        let config: ServerConfig = serde_json::from_str(
+            r#"{
+                "withSysroot": true,
+                "rootOverrides": { "/ws/b": { "withSysroot": false } }
+            }"#,
+        )
+        .unwrap();
+        assert!(config.with_sysroot_for(Path::new("/ws/a")));
+        assert!(!config.with_sysroot_for(Path::new("/ws/b")));
+        assert_eq!(config.cargo_features_for(Path::new("/ws/b")), &config.cargo_features);
+    }
+
+    #[test]
+    fn partially_invalid_config_keeps_valid_settings() {
+        let (config, warnings) = ServerConfig::from_json(serde_json::json!({
+            "lruCapacity": 64,
+            "cargoWatchArgs": "--all",
+            "maxInlayHintLenght": 20,
+            "featureFlags": { "lsp.diagnostics": false, "no.such-flag": true },
+            "cargoFeatures": { "allFeatures": "yes", "features": ["serde"] },
+        }));
+
+        assert_eq!(config.lru_capacity, Some(64));
+        assert_eq!(config.cargo_watch_args, ServerConfig::default().cargo_watch_args);
+        assert!(!config.feature_flags().get("lsp.diagnostics"));
+        assert_eq!(config.cargo_features.features, vec!["serde".to_string()]);
+        assert!(config.cargo_features.all_features);
+
+        let mut paths: Vec<&str> = warnings.iter().map(|it| it.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "cargoFeatures.allFeatures",
+                "cargoWatchArgs",
+                "featureFlags.no.such-flag",
+                "maxInlayHintLenght",
+            ]
+        );
+        let args_warning = warnings.iter().find(|it| it.path == "cargoWatchArgs").unwrap();
+        assert!(args_warning.message.contains("expected a sequence"), "{}", args_warning);
+    }
+
+    #[test]
+    fn diff_separates_runtime_settings() {
+        let old = ServerConfig::default();
+        let (new, warnings) = ServerConfig::from_json(serde_json::json!({
+            "maxInlayHintLength": 10,
+            "withSysroot": false,
+        }));
+        assert!(warnings.is_empty());
+
+        let change = old.diff(&new);
+        assert_eq!(change.applied, vec!["maxInlayHintLength"]);
+        assert_eq!(change.needs_reload, vec!["withSysroot"]);
+
+        let applied = old.with_runtime_settings(&new);
+        assert_eq!(applied.max_inlay_hint_length, Some(10));
+        assert!(applied.with_sysroot);
+    }
 }
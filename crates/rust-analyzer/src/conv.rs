@@ -1,6 +1,8 @@
 //! Convenience module responsible for translating between rust-analyzer's types
 //! and LSP types.
 
+use std::{ops::Deref, sync::Arc};
+
 use lsp_types::{
     self, CreateFile, DiagnosticSeverity, DocumentChangeOperation, DocumentChanges, Documentation,
     Location, LocationLink, MarkupContent, MarkupKind, Position, Range, RenameFile, ResourceOp,
@@ -9,10 +11,10 @@ use lsp_types::{
     WorkspaceEdit,
 };
 use ra_ide::{
-    translate_offset_with_edit, CompletionItem, CompletionItemKind, FileId, FilePosition,
-    FileRange, FileSystemEdit, Fold, FoldKind, Highlight, HighlightModifier, HighlightTag,
-    InsertTextFormat, LineCol, LineIndex, NavigationTarget, RangeInfo, ReferenceAccess, Severity,
-    SourceChange, SourceFileEdit,
+    translate_offset_with_edit, CompletionItem, CompletionItemKind, DiagnosticTag, FileId,
+    FilePosition, FileRange, FileSystemEdit, Fold, FoldKind, Highlight, HighlightModifier,
+    HighlightTag, InsertTextFormat, LineCol, LineColUtf8, LineIndex, NavigationTarget, RangeInfo,
+    ReferenceAccess, Severity, SourceChange, SourceFileEdit,
 };
 use ra_syntax::{SyntaxKind, TextRange, TextUnit};
 use ra_text_edit::{AtomTextEdit, TextEdit};
@@ -24,7 +26,7 @@ use crate::{
     world::WorldSnapshot,
     Result,
 };
-use semantic_tokens::ATTRIBUTE;
+use semantic_tokens::{ATTRIBUTE, UNSAFE_OP};
 
 pub trait Conv {
     type Output;
@@ -107,15 +109,26 @@ impl Conv for Severity {
     fn conv(self) -> DiagnosticSeverity {
         match self {
             Severity::Error => DiagnosticSeverity::Error,
+            Severity::Warning => DiagnosticSeverity::Warning,
             Severity::WeakWarning => DiagnosticSeverity::Hint,
         }
     }
 }
 
-impl ConvWith<(&LineIndex, LineEndings)> for CompletionItem {
+impl Conv for DiagnosticTag {
+    type Output = lsp_types::DiagnosticTag;
+    fn conv(self) -> lsp_types::DiagnosticTag {
+        match self {
+            DiagnosticTag::Unnecessary => lsp_types::DiagnosticTag::Unnecessary,
+            DiagnosticTag::Deprecated => lsp_types::DiagnosticTag::Deprecated,
+        }
+    }
+}
+
+impl ConvWith<(&LineMap, LineEndings)> for CompletionItem {
     type Output = ::lsp_types::CompletionItem;
 
-    fn conv_with(self, ctx: (&LineIndex, LineEndings)) -> ::lsp_types::CompletionItem {
+    fn conv_with(self, ctx: (&LineMap, LineEndings)) -> ::lsp_types::CompletionItem {
         let mut additional_text_edits = Vec::new();
         let mut text_edit = None;
         // LSP does not allow arbitrary edits in completion, so we have to do a
@@ -150,6 +163,12 @@ impl ConvWith<(&LineIndex, LineEndings)> for CompletionItem {
             additional_text_edits: Some(additional_text_edits),
             documentation: self.documentation().map(|it| it.conv()),
             deprecated: Some(self.deprecated()),
+            // Clients sort by `sortText` lexicographically, so more relevant
+            // items get a smaller, fixed-width prefix.
+            sort_text: Some(match self.sort_text() {
+                Some(it) => it.to_string(),
+                None => format!("{:03}{}", u8::MAX - self.relevance(), self.label()),
+            }),
             ..Default::default()
         };
 
@@ -157,6 +176,14 @@ impl ConvWith<(&LineIndex, LineEndings)> for CompletionItem {
             res.tags = Some(vec![lsp_types::CompletionItemTag::Deprecated])
         }
 
+        // LSP can't grey out items, so the detail tells why it can't be used.
+        if self.inaccessible() {
+            res.detail = Some(match res.detail {
+                Some(detail) => format!("{} (inaccessible)", detail),
+                None => "inaccessible".to_string(),
+            });
+        }
+
         res.insert_text_format = Some(match self.insert_text_format() {
             InsertTextFormat::Snippet => lsp_types::InsertTextFormat::Snippet,
             InsertTextFormat::PlainText => lsp_types::InsertTextFormat::PlainText,
@@ -166,37 +193,117 @@ impl ConvWith<(&LineIndex, LineEndings)> for CompletionItem {
     }
 }
 
-impl ConvWith<&LineIndex> for Position {
+/// How the client counts the columns of `Position`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffsetEncoding {
+    /// In bytes, which clients can opt into with the `offsetEncoding`
+    /// extension of clangd or with `positionEncodings`.
+    Utf8,
+    /// In UTF-16 code units, which is the default of the protocol.
+    Utf16,
+}
+
+impl Default for OffsetEncoding {
+    fn default() -> OffsetEncoding {
+        OffsetEncoding::Utf16
+    }
+}
+
+impl OffsetEncoding {
+    /// Picks the encoding to use from the encodings supported by the client,
+    /// preferring UTF-8 as it needs no conversion.
+    pub fn negotiate(client_capabilities: &serde_json::Value) -> OffsetEncoding {
+        let supported = client_capabilities
+            .get("offsetEncoding")
+            .or_else(|| client_capabilities.get("general")?.get("positionEncodings"))
+            .and_then(|it| it.as_array());
+        match supported {
+            Some(encodings) if encodings.iter().any(|it| it == "utf-8") => OffsetEncoding::Utf8,
+            _ => OffsetEncoding::Utf16,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OffsetEncoding::Utf8 => "utf-8",
+            OffsetEncoding::Utf16 => "utf-16",
+        }
+    }
+}
+
+/// The `LineIndex` of a file, together with the encoding the client uses for
+/// columns. All the conversions between `Position`s and offsets go through it.
+#[derive(Debug, Clone)]
+pub struct LineMap {
+    index: Arc<LineIndex>,
+    encoding: OffsetEncoding,
+}
+
+impl LineMap {
+    pub fn new(index: Arc<LineIndex>, encoding: OffsetEncoding) -> LineMap {
+        LineMap { index, encoding }
+    }
+
+    pub fn encoding(&self) -> OffsetEncoding {
+        self.encoding
+    }
+}
+
+impl Deref for LineMap {
+    type Target = LineIndex;
+
+    fn deref(&self) -> &LineIndex {
+        &self.index
+    }
+}
+
+impl ConvWith<&LineMap> for Position {
     type Output = TextUnit;
 
-    fn conv_with(self, line_index: &LineIndex) -> TextUnit {
-        let line_col = LineCol { line: self.line as u32, col_utf16: self.character as u32 };
-        line_index.offset(line_col)
+    fn conv_with(self, line_map: &LineMap) -> TextUnit {
+        let line = self.line as u32;
+        match line_map.encoding {
+            OffsetEncoding::Utf8 => {
+                line_map.offset_utf8(LineColUtf8 { line, col: self.character as u32 })
+            }
+            OffsetEncoding::Utf16 => {
+                line_map.offset(LineCol { line, col_utf16: self.character as u32 })
+            }
+        }
     }
 }
 
-impl ConvWith<&LineIndex> for TextUnit {
+impl ConvWith<&LineMap> for TextUnit {
     type Output = Position;
 
-    fn conv_with(self, line_index: &LineIndex) -> Position {
-        let line_col = line_index.line_col(self);
-        Position::new(u64::from(line_col.line), u64::from(line_col.col_utf16))
+    fn conv_with(self, line_map: &LineMap) -> Position {
+        let (line, col) = match line_map.encoding {
+            OffsetEncoding::Utf8 => {
+                let line_col = line_map.line_col_utf8(self);
+                (line_col.line, line_col.col)
+            }
+            OffsetEncoding::Utf16 => {
+                let line_col = line_map.line_col(self);
+                (line_col.line, line_col.col_utf16)
+            }
+        };
+        Position::new(u64::from(line), u64::from(col))
     }
 }
 
-impl ConvWith<&LineIndex> for TextRange {
+impl ConvWith<&LineMap> for TextRange {
     type Output = Range;
 
-    fn conv_with(self, line_index: &LineIndex) -> Range {
-        Range::new(self.start().conv_with(line_index), self.end().conv_with(line_index))
+    fn conv_with(self, line_map: &LineMap) -> Range {
+        Range::new(self.start().conv_with(line_map), self.end().conv_with(line_map))
     }
 }
 
-impl ConvWith<&LineIndex> for Range {
+impl ConvWith<&LineMap> for Range {
     type Output = TextRange;
 
-    fn conv_with(self, line_index: &LineIndex) -> TextRange {
-        TextRange::from_to(self.start.conv_with(line_index), self.end.conv_with(line_index))
+    fn conv_with(self, line_map: &LineMap) -> TextRange {
+        TextRange::from_to(self.start.conv_with(line_map), self.end.conv_with(line_map))
     }
 }
 
@@ -232,21 +339,18 @@ impl Conv for ra_ide::FunctionSignature {
     }
 }
 
-impl ConvWith<(&LineIndex, LineEndings)> for TextEdit {
+impl ConvWith<(&LineMap, LineEndings)> for TextEdit {
     type Output = Vec<lsp_types::TextEdit>;
 
-    fn conv_with(self, ctx: (&LineIndex, LineEndings)) -> Vec<lsp_types::TextEdit> {
+    fn conv_with(self, ctx: (&LineMap, LineEndings)) -> Vec<lsp_types::TextEdit> {
         self.as_atoms().iter().map_conv_with(ctx).collect()
     }
 }
 
-impl ConvWith<(&LineIndex, LineEndings)> for &AtomTextEdit {
+impl ConvWith<(&LineMap, LineEndings)> for &AtomTextEdit {
     type Output = lsp_types::TextEdit;
 
-    fn conv_with(
-        self,
-        (line_index, line_endings): (&LineIndex, LineEndings),
-    ) -> lsp_types::TextEdit {
+    fn conv_with(self, (line_index, line_endings): (&LineMap, LineEndings)) -> lsp_types::TextEdit {
         let mut new_text = self.insert.clone();
         if line_endings == LineEndings::Dos {
             new_text = new_text.replace('\n', "\r\n");
@@ -257,7 +361,7 @@ impl ConvWith<(&LineIndex, LineEndings)> for &AtomTextEdit {
 
 pub(crate) struct FoldConvCtx<'a> {
     pub(crate) text: &'a str,
-    pub(crate) line_index: &'a LineIndex,
+    pub(crate) line_index: &'a LineMap,
     pub(crate) line_folding_only: bool,
 }
 
@@ -343,6 +447,7 @@ impl Conv for Highlight {
             HighlightTag::LiteralString => SemanticTokenType::STRING,
             HighlightTag::Attribute => ATTRIBUTE,
             HighlightTag::Keyword => SemanticTokenType::KEYWORD,
+            HighlightTag::UnsafeOp => UNSAFE_OP,
         };
 
         for modifier in self.modifiers.iter() {
@@ -406,7 +511,7 @@ impl TryConvWith<&WorldSnapshot> for &TextDocumentPositionParams {
     type Output = FilePosition;
     fn try_conv_with(self, world: &WorldSnapshot) -> Result<FilePosition> {
         let file_id = self.text_document.try_conv_with(world)?;
-        let line_index = world.analysis().file_line_index(file_id)?;
+        let line_index = world.file_line_index(file_id)?;
         let offset = self.position.conv_with(&line_index);
         Ok(FilePosition { file_id, offset })
     }
@@ -416,7 +521,7 @@ impl TryConvWith<&WorldSnapshot> for (&TextDocumentIdentifier, Range) {
     type Output = FileRange;
     fn try_conv_with(self, world: &WorldSnapshot) -> Result<FileRange> {
         let file_id = self.0.try_conv_with(world)?;
-        let line_index = world.analysis().file_line_index(file_id)?;
+        let line_index = world.file_line_index(file_id)?;
         let range = self.1.conv_with(&line_index);
         Ok(FileRange { file_id, range })
     }
@@ -436,21 +541,37 @@ impl<T: TryConvWith<CTX>, CTX: Copy> TryConvWith<CTX> for Vec<T> {
 impl TryConvWith<&WorldSnapshot> for SourceChange {
     type Output = req::SourceChange;
     fn try_conv_with(self, world: &WorldSnapshot) -> Result<req::SourceChange> {
-        let cursor_position = match self.cursor_position {
+        // Refuse to send overlapping edits of a file rather than let the
+        // client corrupt the buffer.
+        let change = match self.merge_source_file_edits() {
+            Ok(it) => it,
+            Err(err) => {
+                let uri = err.file_id.try_conv_with(world)?;
+                return Err(format!("conflicting edits in {}: {}", uri, err.conflict).into());
+            }
+        };
+        let cursor_position = match change.cursor_position {
             None => None,
             Some(pos) => {
-                let line_index = world.analysis().file_line_index(pos.file_id)?;
-                let edit = self
+                let line_index = world.file_line_index(pos.file_id)?;
+                let edit = change
                     .source_file_edits
                     .iter()
                     .find(|it| it.file_id == pos.file_id)
                     .map(|it| &it.edit);
-                let line_col = match edit {
-                    Some(edit) => translate_offset_with_edit(&*line_index, pos.offset, edit),
-                    None => line_index.line_col(pos.offset),
+                let position = match (edit, line_index.encoding()) {
+                    (Some(edit), OffsetEncoding::Utf16) => {
+                        let line_col = translate_offset_with_edit(&*line_index, pos.offset, edit);
+                        Position::new(u64::from(line_col.line), u64::from(line_col.col_utf16))
+                    }
+                    (Some(edit), encoding) => {
+                        // The cursor position is in the edited text, so index that instead.
+                        let text = edit.apply(&world.analysis().file_text(pos.file_id)?);
+                        let line_index = LineMap::new(Arc::new(LineIndex::new(&text)), encoding);
+                        pos.offset.conv_with(&line_index)
+                    }
+                    (None, _) => pos.offset.conv_with(&line_index),
                 };
-                let position =
-                    Position::new(u64::from(line_col.line), u64::from(line_col.col_utf16));
                 Some(TextDocumentPositionParams {
                     text_document: TextDocumentIdentifier::new(pos.file_id.try_conv_with(world)?),
                     position,
@@ -458,17 +579,17 @@ impl TryConvWith<&WorldSnapshot> for SourceChange {
             }
         };
         let mut document_changes: Vec<DocumentChangeOperation> = Vec::new();
-        for resource_op in self.file_system_edits.try_conv_with(world)? {
+        for resource_op in change.file_system_edits.try_conv_with(world)? {
             document_changes.push(DocumentChangeOperation::Op(resource_op));
         }
-        for text_document_edit in self.source_file_edits.try_conv_with(world)? {
+        for text_document_edit in change.source_file_edits.try_conv_with(world)? {
             document_changes.push(DocumentChangeOperation::Edit(text_document_edit));
         }
         let workspace_edit = WorkspaceEdit {
             changes: None,
             document_changes: Some(DocumentChanges::Operations(document_changes)),
         };
-        Ok(req::SourceChange { label: self.label, workspace_edit, cursor_position })
+        Ok(req::SourceChange { label: change.label, workspace_edit, cursor_position })
     }
 }
 
@@ -479,7 +600,7 @@ impl TryConvWith<&WorldSnapshot> for SourceFileEdit {
             uri: self.file_id.try_conv_with(world)?,
             version: None,
         };
-        let line_index = world.analysis().file_line_index(self.file_id)?;
+        let line_index = world.file_line_index(self.file_id)?;
         let line_endings = world.file_line_endings(self.file_id);
         let edits =
             self.edit.as_atoms().iter().map_conv_with((&line_index, line_endings)).collect();
@@ -508,9 +629,10 @@ impl TryConvWith<&WorldSnapshot> for FileSystemEdit {
 impl TryConvWith<&WorldSnapshot> for &NavigationTarget {
     type Output = Location;
     fn try_conv_with(self, world: &WorldSnapshot) -> Result<Location> {
-        let line_index = world.analysis().file_line_index(self.file_id())?;
+        let file_id = self.file_id().ok_or("navigation target has no location")?;
+        let line_index = world.file_line_index(file_id)?;
         let range = self.range();
-        to_location(self.file_id(), range, &world, &line_index)
+        to_location(file_id, range, &world, &line_index)
     }
 }
 
@@ -519,9 +641,10 @@ impl TryConvWith<&WorldSnapshot> for (FileId, RangeInfo<NavigationTarget>) {
     fn try_conv_with(self, world: &WorldSnapshot) -> Result<LocationLink> {
         let (src_file_id, target) = self;
 
-        let target_uri = target.info.file_id().try_conv_with(world)?;
-        let src_line_index = world.analysis().file_line_index(src_file_id)?;
-        let tgt_line_index = world.analysis().file_line_index(target.info.file_id())?;
+        let tgt_file_id = target.info.file_id().ok_or("navigation target has no location")?;
+        let target_uri = tgt_file_id.try_conv_with(world)?;
+        let src_line_index = world.file_line_index(src_file_id)?;
+        let tgt_line_index = world.file_line_index(tgt_file_id)?;
 
         let target_range = target.info.full_range().conv_with(&tgt_line_index);
 
@@ -547,6 +670,7 @@ impl TryConvWith<&WorldSnapshot> for (FileId, RangeInfo<Vec<NavigationTarget>>)
         let (file_id, RangeInfo { range, info: navs }) = self;
         let links = navs
             .into_iter()
+            .filter(|nav| nav.file_id().is_some())
             .map(|nav| (file_id, RangeInfo::new(range, nav)))
             .try_conv_with_to_vec(world)?;
         if world.options.supports_location_link {
@@ -565,7 +689,7 @@ pub fn to_call_hierarchy_item(
     file_id: FileId,
     range: TextRange,
     world: &WorldSnapshot,
-    line_index: &LineIndex,
+    line_index: &LineMap,
     nav: NavigationTarget,
 ) -> Result<lsp_types::CallHierarchyItem> {
     Ok(lsp_types::CallHierarchyItem {
@@ -583,7 +707,7 @@ pub fn to_location(
     file_id: FileId,
     range: TextRange,
     world: &WorldSnapshot,
-    line_index: &LineIndex,
+    line_index: &LineMap,
 ) -> Result<Location> {
     let url = file_id.try_conv_with(world)?;
     let loc = Location::new(url, range.conv_with(line_index));
@@ -671,7 +795,7 @@ fn main() <fold>{
             Fold { range: ranges[3], kind: FoldKind::Block },
         ];
 
-        let line_index = LineIndex::new(&text);
+        let line_index = LineMap::new(Arc::new(LineIndex::new(&text)), OffsetEncoding::Utf16);
         let ctx = FoldConvCtx { text: &text, line_index: &line_index, line_folding_only: true };
         let converted: Vec<_> = folds.into_iter().map_conv_with(&ctx).collect();
 
@@ -684,4 +808,42 @@ fn main() <fold>{
             assert_eq!(folding_range.end_character, None);
         }
     }
+
+    fn check_positions(encoding: OffsetEncoding, expected: &[(u32, u64, u64)]) {
+        let text = "fn main() {\n    let s = \"メ🦀\"; 1\n}\n";
+        let line_map = LineMap::new(Arc::new(LineIndex::new(text)), encoding);
+        for &(offset, line, character) in expected {
+            let offset = TextUnit::from(offset);
+            let position = Position::new(line, character);
+            assert_eq!(offset.conv_with(&line_map), position);
+            assert_eq!(position.conv_with(&line_map), offset);
+        }
+    }
+
+    #[test]
+    fn conv_positions_utf16() {
+        check_positions(
+            OffsetEncoding::Utf16,
+            &[(12, 1, 0), (25, 1, 13), (28, 1, 14), (32, 1, 16), (33, 1, 17), (37, 2, 0)],
+        );
+    }
+
+    #[test]
+    fn conv_positions_utf8() {
+        check_positions(
+            OffsetEncoding::Utf8,
+            &[(12, 1, 0), (25, 1, 13), (28, 1, 16), (32, 1, 20), (33, 1, 21), (37, 2, 0)],
+        );
+    }
+
+    #[test]
+    fn negotiate_offset_encoding() {
+        let clangd = serde_json::json!({ "offsetEncoding": ["utf-8", "utf-16"] });
+        assert_eq!(OffsetEncoding::negotiate(&clangd), OffsetEncoding::Utf8);
+        let general = serde_json::json!({ "general": { "positionEncodings": ["utf-8"] } });
+        assert_eq!(OffsetEncoding::negotiate(&general), OffsetEncoding::Utf8);
+        let utf16_only = serde_json::json!({ "offsetEncoding": ["utf-16"] });
+        assert_eq!(OffsetEncoding::negotiate(&utf16_only), OffsetEncoding::Utf16);
+        assert_eq!(OffsetEncoding::negotiate(&serde_json::json!({})), OffsetEncoding::Utf16);
+    }
 }
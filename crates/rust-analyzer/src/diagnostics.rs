@@ -3,13 +3,16 @@
 use std::{collections::HashMap, sync::Arc};
 
 use lsp_types::{CodeActionOrCommand, Diagnostic, Range};
-use ra_ide::FileId;
+use ra_ide::{DiagnosticsDependencies, FileId, SourceRootId};
+use rustc_hash::FxHashSet;
 
 pub type CheckFixes = Arc<HashMap<FileId, Vec<Fix>>>;
 
 #[derive(Debug, Default, Clone)]
 pub struct DiagnosticCollection {
     pub native: HashMap<FileId, Vec<Diagnostic>>,
+    /// The source roots the native diagnostics of each file were computed from.
+    pub native_dependencies: HashMap<FileId, DiagnosticsDependencies>,
     pub check: HashMap<FileId, Vec<Diagnostic>>,
     pub check_fixes: CheckFixes,
 }
@@ -24,7 +27,7 @@ pub struct Fix {
 pub enum DiagnosticTask {
     ClearCheck,
     AddCheck(FileId, Diagnostic, Vec<CodeActionOrCommand>),
-    SetNative(FileId, Vec<Diagnostic>),
+    SetNative(FileId, Vec<Diagnostic>, DiagnosticsDependencies),
 }
 
 impl DiagnosticCollection {
@@ -54,8 +57,33 @@ impl DiagnosticCollection {
         diagnostics.push(diagnostic);
     }
 
-    pub fn set_native_diagnostics(&mut self, file_id: FileId, diagnostics: Vec<Diagnostic>) {
+    pub fn set_native_diagnostics(
+        &mut self,
+        file_id: FileId,
+        diagnostics: Vec<Diagnostic>,
+        dependencies: DiagnosticsDependencies,
+    ) {
         self.native.insert(file_id, diagnostics);
+        self.native_dependencies.insert(file_id, dependencies);
+    }
+
+    /// Returns the files among `files` whose native diagnostics may be
+    /// affected by changes to `changed_roots`, including the ones whose
+    /// diagnostics were not computed yet.
+    pub fn files_to_refresh(
+        &self,
+        files: impl IntoIterator<Item = FileId>,
+        changed_roots: &FxHashSet<SourceRootId>,
+    ) -> Vec<FileId> {
+        files
+            .into_iter()
+            .filter(|file_id| match self.native_dependencies.get(file_id) {
+                Some(dependencies) => {
+                    changed_roots.iter().any(|&root| dependencies.depends_on(root))
+                }
+                None => true,
+            })
+            .collect()
     }
 
     pub fn diagnostics_for(&self, file_id: FileId) -> impl Iterator<Item = &Diagnostic> {
@@ -71,8 +99,8 @@ impl DiagnosticCollection {
                 self.add_check_diagnostic(file_id, diagnostic, fixes);
                 vec![file_id]
             }
-            DiagnosticTask::SetNative(file_id, diagnostics) => {
-                self.set_native_diagnostics(file_id, diagnostics);
+            DiagnosticTask::SetNative(file_id, diagnostics, dependencies) => {
+                self.set_native_diagnostics(file_id, diagnostics, dependencies);
                 vec![file_id]
             }
         }
@@ -85,3 +113,37 @@ fn are_diagnostics_equal(left: &Diagnostic, right: &Diagnostic) -> bool {
         && left.range == right.range
         && left.message == right.message
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refreshes_only_files_depending_on_changed_roots() {
+        let mut collection = DiagnosticCollection::default();
+        // `FileId(1)` uses a struct defined in the crate of `SourceRootId(1)`.
+        collection.set_native_diagnostics(
+            FileId(1),
+            Vec::new(),
+            vec![SourceRootId(0), SourceRootId(1)].into_iter().collect(),
+        );
+        collection.set_native_diagnostics(
+            FileId(2),
+            Vec::new(),
+            vec![SourceRootId(2)].into_iter().collect(),
+        );
+        let files = vec![FileId(1), FileId(2), FileId(3)];
+
+        let changed_roots = vec![SourceRootId(1)].into_iter().collect();
+        assert_eq!(
+            collection.files_to_refresh(files.iter().copied(), &changed_roots),
+            vec![FileId(1), FileId(3)]
+        );
+
+        let changed_roots = vec![SourceRootId(3)].into_iter().collect();
+        assert_eq!(
+            collection.files_to_refresh(files.iter().copied(), &changed_roots),
+            vec![FileId(3)]
+        );
+    }
+}
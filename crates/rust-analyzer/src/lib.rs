@@ -42,8 +42,12 @@ use serde::de::DeserializeOwned;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 pub use crate::{
-    caps::server_capabilities,
-    config::ServerConfig,
+    caps::{
+        code_action_capability, file_operations_capability, server_capabilities,
+        supports_code_action_resolve,
+    },
+    config::{config_warnings_message, ServerConfig},
+    conv::OffsetEncoding,
     main_loop::LspError,
     main_loop::{main_loop, show_message},
 };
@@ -3,6 +3,7 @@
 
 mod handlers;
 mod subscriptions;
+mod status;
 pub(crate) mod pending_requests;
 
 use std::{
@@ -10,26 +11,37 @@ use std::{
     error::Error,
     fmt, panic,
     path::PathBuf,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
     time::{Duration, Instant},
 };
 
-use crossbeam_channel::{select, unbounded, RecvError, Sender};
+use crossbeam_channel::{at, never, select, unbounded, RecvError, Sender};
 use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
-use lsp_types::{ClientCapabilities, NumberOrString};
+use lsp_types::{
+    ClientCapabilities, NumberOrString, Position, Range, TextDocumentContentChangeEvent,
+};
 use ra_cargo_watch::{url_from_path_with_drive_lowercasing, CheckOptions, CheckTask};
-use ra_ide::{Canceled, FeatureFlags, FileId, LibraryData, SourceRootId};
+use ra_ide::{Canceled, FeatureFlags, FileId, LibraryData, LineIndex, SourceRootId};
 use ra_prof::profile;
-use ra_vfs::{VfsFile, VfsTask, Watch};
+use ra_project_model::ProjectWorkspace;
+use ra_syntax::TextUnit;
+use ra_vfs::{LineEndings, VfsFile, VfsTask, Watch};
 use relative_path::RelativePathBuf;
 use rustc_hash::FxHashSet;
 use serde::{de::DeserializeOwned, Serialize};
 use threadpool::ThreadPool;
 
 use crate::{
+    config::config_warnings_message,
+    conv::{ConvWith, LineMap, OffsetEncoding},
     diagnostics::DiagnosticTask,
     main_loop::{
         pending_requests::{PendingRequest, PendingRequests},
+        status::{ErrorKind, StatusReporter},
         subscriptions::Subscriptions,
     },
     req,
@@ -60,7 +72,9 @@ impl Error for LspError {}
 pub fn main_loop(
     ws_roots: Vec<PathBuf>,
     client_caps: ClientCapabilities,
-    config: ServerConfig,
+    mut config: ServerConfig,
+    offset_encoding: OffsetEncoding,
+    resolve_code_actions: bool,
     connection: Connection,
 ) -> Result<()> {
     log::info!("server_config: {:#?}", config);
@@ -85,52 +99,22 @@ pub fn main_loop(
     }
 
     let mut loop_state = LoopState::default();
+    loop_state.supports_configuration_request =
+        client_caps.workspace.as_ref().and_then(|it| it.configuration).unwrap_or(false);
+    send_status(&connection.sender, loop_state.status.start_loading());
     let mut world_state = {
-        let feature_flags = {
-            let mut ff = FeatureFlags::default();
-            for (flag, value) in config.feature_flags {
-                if ff.set(flag.as_str(), value).is_err() {
-                    log::error!("unknown feature flag: {:?}", flag);
-                    show_message(
-                        req::MessageType::Error,
-                        format!("unknown feature flag: {:?}", flag),
-                        &connection.sender,
-                    );
-                }
-            }
-            ff
-        };
+        let feature_flags = config.feature_flags();
         log::info!("feature_flags: {:#?}", feature_flags);
 
-        // FIXME: support dynamic workspace loading.
-        let workspaces = {
-            let mut loaded_workspaces = Vec::new();
-            for ws_root in &ws_roots {
-                let workspace = ra_project_model::ProjectWorkspace::discover_with_sysroot(
-                    ws_root.as_path(),
-                    config.with_sysroot,
-                    &config.cargo_features,
-                );
-                match workspace {
-                    Ok(workspace) => loaded_workspaces.push(workspace),
-                    Err(e) => {
-                        log::error!("loading workspace failed: {:?}", e);
-                        if let Some(ra_project_model::CargoTomlNotFoundError(_)) = e.downcast_ref()
-                        {
-                            if !feature_flags.get("notifications.cargo-toml-not-found") {
-                                continue;
-                            }
-                        }
-                        show_message(
-                            req::MessageType::Error,
-                            format!("rust-analyzer failed to load workspace: {:?}", e),
-                            &connection.sender,
-                        );
-                    }
-                }
-            }
-            loaded_workspaces
-        };
+        let (workspaces, errors) = load_workspaces(&ws_roots, &config, &feature_flags);
+        for error in errors {
+            report_error(
+                &connection.sender,
+                &mut loop_state.status,
+                ErrorKind::WorkspaceLoad,
+                error,
+            );
+        }
 
         let globs = config
             .exclude_globs
@@ -138,25 +122,54 @@ pub fn main_loop(
             .map(|glob| crate::vfs_glob::Glob::new(glob))
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        if config.use_client_watching {
-            let registration_options = req::DidChangeWatchedFilesRegistrationOptions {
-                watchers: workspaces
-                    .iter()
-                    .flat_map(|ws| ws.to_roots())
-                    .filter(|root| root.is_member())
-                    .map(|root| format!("{}/**/*.rs", root.path().display()))
-                    .map(|glob_pattern| req::FileSystemWatcher { glob_pattern, kind: None })
-                    .collect(),
-            };
-            let registration = req::Registration {
-                id: "file-watcher".to_string(),
-                method: "workspace/didChangeWatchedFiles".to_string(),
-                register_options: Some(serde_json::to_value(registration_options).unwrap()),
-            };
-            let params = req::RegistrationParams { registrations: vec![registration] };
-            let request =
-                request_new::<req::RegisterCapability>(loop_state.next_request_id(), params);
-            connection.sender.send(request.into()).unwrap();
+        let can_register_watchers = client_caps
+            .workspace
+            .as_ref()
+            .and_then(|it| it.did_change_watched_files.as_ref())
+            .and_then(|it| it.dynamic_registration)
+            .unwrap_or(false);
+        if can_register_watchers {
+            // The VFS only watches Rust files, so the client watches the
+            // manifests and lockfiles of the workspace members even when the
+            // server watches the file system itself.
+            let member_roots = workspaces
+                .iter()
+                .flat_map(|ws| ws.to_roots())
+                .filter(|root| root.is_member())
+                .collect::<Vec<_>>();
+            let mut globs = Vec::new();
+            for root in member_roots.iter() {
+                if config.use_client_watching {
+                    globs.push(format!("{}/**/*.rs", root.path().display()));
+                }
+                if config.reload_on_manifest_change {
+                    for manifest in MANIFESTS {
+                        globs.push(format!("{}/{}", root.path().display(), manifest));
+                    }
+                }
+            }
+            if config.reload_on_manifest_change {
+                for ws_root in ws_roots.iter() {
+                    globs.push(format!("{}/Cargo.lock", ws_root.display()));
+                }
+            }
+            if !globs.is_empty() {
+                let registration_options = req::DidChangeWatchedFilesRegistrationOptions {
+                    watchers: globs
+                        .into_iter()
+                        .map(|glob_pattern| req::FileSystemWatcher { glob_pattern, kind: None })
+                        .collect(),
+                };
+                let registration = req::Registration {
+                    id: "file-watcher".to_string(),
+                    method: "workspace/didChangeWatchedFiles".to_string(),
+                    register_options: Some(serde_json::to_value(registration_options).unwrap()),
+                };
+                let params = req::RegistrationParams { registrations: vec![registration] };
+                let request =
+                    request_new::<req::RegisterCapability>(loop_state.next_request_id(), params);
+                connection.sender.send(request.into()).unwrap();
+            }
         }
 
         let options = {
@@ -167,6 +180,10 @@ pub fn main_loop(
                     .and_then(|it| it.definition)
                     .and_then(|it| it.link_support)
                     .unwrap_or(false),
+                supports_hierarchical_symbols: text_document_caps
+                    .and_then(|it| it.document_symbol.as_ref())
+                    .and_then(|it| it.hierarchical_document_symbol_support)
+                    .unwrap_or(false),
                 line_folding_only: text_document_caps
                     .and_then(|it| it.folding_range.as_ref())
                     .and_then(|it| it.line_folding_only)
@@ -174,11 +191,14 @@ pub fn main_loop(
                 max_inlay_hint_length: config.max_inlay_hint_length,
                 cargo_watch: CheckOptions {
                     enable: config.cargo_watch_enable,
-                    args: config.cargo_watch_args,
-                    command: config.cargo_watch_command,
+                    args: config.cargo_watch_args.clone(),
+                    command: config.cargo_watch_command.clone(),
                     all_targets: config.cargo_watch_all_targets,
                 },
-                rustfmt_args: config.rustfmt_args,
+                rustfmt_args: config.rustfmt_args.clone(),
+                profile_requests: config.profile_file.is_some(),
+                offset_encoding,
+                resolve_code_actions,
             }
         };
 
@@ -193,16 +213,32 @@ pub fn main_loop(
         )
     };
 
+    let trace = config.profile_file.clone().map(|path| {
+        let recorder = Arc::new(ra_prof::TraceRecorder::default());
+        ra_prof::install_sink(recorder.clone());
+        if env::var("RA_PROFILE").is_err() {
+            ra_prof::set_filter(ra_prof::Filter::from_spec("*"));
+        }
+        (path, recorder)
+    });
+
     let pool = ThreadPool::default();
     let (task_sender, task_receiver) = unbounded::<Task>();
-    let (libdata_sender, libdata_receiver) = unbounded::<LibraryData>();
+    let (libdata_sender, libdata_receiver) = unbounded::<(usize, LibraryData)>();
+    let (workspace_sender, workspace_receiver) =
+        unbounded::<(usize, Vec<ProjectWorkspace>, Vec<String>)>();
 
     log::info!("server initialized, serving requests");
     {
         let task_sender = task_sender;
         let libdata_sender = libdata_sender;
+        let workspace_sender = workspace_sender;
         loop {
             log::trace!("selecting");
+            let diagnostics_timer = match loop_state.diagnostics_deadline {
+                Some(deadline) => at(deadline),
+                None => never(),
+            };
             let event = select! {
                 recv(&connection.receiver) -> msg => match msg {
                     Ok(msg) => Event::Msg(msg),
@@ -213,11 +249,19 @@ pub fn main_loop(
                     Ok(task) => Event::Vfs(task),
                     Err(RecvError) => return Err("vfs died".into()),
                 },
-                recv(libdata_receiver) -> data => Event::Lib(data.unwrap()),
+                recv(libdata_receiver) -> data => {
+                    let (generation, data) = data.unwrap();
+                    Event::Lib(generation, data)
+                }
+                recv(workspace_receiver) -> workspaces => {
+                    let (reload, workspaces, errors) = workspaces.unwrap();
+                    Event::Workspaces(reload, workspaces, errors)
+                }
                 recv(world_state.check_watcher.task_recv) -> task => match task {
                     Ok(task) => Event::CheckWatcher(task),
                     Err(RecvError) => return Err("check watcher died".into()),
-                }
+                },
+                recv(diagnostics_timer) -> _ => Event::PublishDiagnostics,
             };
             if let Event::Msg(Message::Request(req)) = &event {
                 if connection.handle_shutdown(&req)? {
@@ -228,7 +272,9 @@ pub fn main_loop(
                 &pool,
                 &task_sender,
                 &libdata_sender,
+                &workspace_sender,
                 &connection,
+                &mut config,
                 &mut world_state,
                 &mut loop_state,
                 event,
@@ -249,9 +295,48 @@ pub fn main_loop(
     let vfs = Arc::try_unwrap(world_state.vfs).expect("all snapshots should be dead");
     drop(vfs);
 
+    if let Some((path, recorder)) = trace {
+        ra_prof::remove_sink();
+        log::info!("writing profile to {}", path.display());
+        if let Err(e) = recorder.write_chrome_trace(&path) {
+            log::error!("failed to write profile to {}: {}", path.display(), e);
+        }
+    }
+
     Ok(())
 }
 
+/// Loads the workspaces of `ws_roots`, returning the errors to report for
+/// those which failed to load.
+fn load_workspaces(
+    ws_roots: &[PathBuf],
+    config: &ServerConfig,
+    feature_flags: &FeatureFlags,
+) -> (Vec<ProjectWorkspace>, Vec<String>) {
+    let mut loaded_workspaces = Vec::new();
+    let mut errors = Vec::new();
+    for ws_root in ws_roots {
+        let workspace = ProjectWorkspace::discover_with_sysroot(
+            ws_root.as_path(),
+            config.with_sysroot_for(ws_root),
+            config.cargo_features_for(ws_root),
+        );
+        match workspace {
+            Ok(workspace) => loaded_workspaces.push(workspace),
+            Err(e) => {
+                if let Some(ra_project_model::CargoTomlNotFoundError(_)) = e.downcast_ref() {
+                    if !feature_flags.get("notifications.cargo-toml-not-found") {
+                        log::error!("loading workspace failed: {:?}", e);
+                        continue;
+                    }
+                }
+                errors.push(format!("{:?}", e));
+            }
+        }
+    }
+    (loaded_workspaces, errors)
+}
+
 #[derive(Debug)]
 enum Task {
     Respond(Response),
@@ -263,8 +348,13 @@ enum Event {
     Msg(Message),
     Task(Task),
     Vfs(VfsTask),
-    Lib(LibraryData),
+    Lib(usize, LibraryData),
+    /// Workspaces reloaded after a manifest changed, with the number of the
+    /// reload and the errors of the workspaces which failed to load.
+    Workspaces(usize, Vec<ProjectWorkspace>, Vec<String>),
     CheckWatcher(CheckTask),
+    /// The changes settled down, the pending diagnostics can be published.
+    PublishDiagnostics,
 }
 
 impl fmt::Debug for Event {
@@ -301,8 +391,10 @@ impl fmt::Debug for Event {
             Event::Msg(it) => fmt::Debug::fmt(it, f),
             Event::Task(it) => fmt::Debug::fmt(it, f),
             Event::Vfs(it) => fmt::Debug::fmt(it, f),
-            Event::Lib(it) => fmt::Debug::fmt(it, f),
+            Event::Lib(_, it) => fmt::Debug::fmt(it, f),
+            Event::Workspaces(reload, _, _) => f.debug_tuple("Workspaces").field(reload).finish(),
             Event::CheckWatcher(it) => fmt::Debug::fmt(it, f),
+            Event::PublishDiagnostics => f.write_str("PublishDiagnostics"),
         }
     }
 }
@@ -318,6 +410,25 @@ struct LoopState {
     in_flight_libraries: usize,
     pending_libraries: Vec<(SourceRootId, Vec<(FileId, RelativePathBuf, Arc<String>)>)>,
     workspace_loaded: bool,
+    // Bumped whenever workspace folders change. Libraries indexed for an
+    // older generation refer to source roots of a VFS which no longer exists.
+    workspace_generation: usize,
+    // Bumped for every workspace reload. Reloads which have been superseded
+    // by a later one are dropped.
+    workspace_reloads: Arc<AtomicUsize>,
+    // Whether the client supports `workspace/configuration`, used to fetch
+    // the settings on `workspace/didChangeConfiguration`.
+    supports_configuration_request: bool,
+    configuration_request: Option<RequestId>,
+    // The settings last sent by the client, including the ones which only
+    // take effect after a restart, so that a pending restart is only
+    // reported once.
+    client_config: Option<ServerConfig>,
+    status: StatusReporter,
+    // Open files whose diagnostics need to be recomputed once the changes
+    // settle down, at `diagnostics_deadline`.
+    pending_diagnostics: FxHashSet<FileId>,
+    diagnostics_deadline: Option<Instant>,
 }
 
 impl LoopState {
@@ -328,13 +439,28 @@ impl LoopState {
         assert!(inserted);
         res
     }
+
+    /// Schedules the diagnostics of `files` to be published, postponing the
+    /// already pending ones until no change happened for
+    /// `DIAGNOSTICS_DEBOUNCE_DELAY`.
+    fn schedule_diagnostics(&mut self, files: impl IntoIterator<Item = FileId>) {
+        self.pending_diagnostics.extend(files);
+        if !self.pending_diagnostics.is_empty() {
+            self.diagnostics_deadline = Some(Instant::now() + DIAGNOSTICS_DEBOUNCE_DELAY);
+        }
+    }
 }
 
+/// How long to wait for further changes before recomputing the diagnostics.
+const DIAGNOSTICS_DEBOUNCE_DELAY: Duration = Duration::from_millis(50);
+
 fn loop_turn(
     pool: &ThreadPool,
     task_sender: &Sender<Task>,
-    libdata_sender: &Sender<LibraryData>,
+    libdata_sender: &Sender<(usize, LibraryData)>,
+    workspace_sender: &Sender<(usize, Vec<ProjectWorkspace>, Vec<String>)>,
     connection: &Connection,
+    config: &mut ServerConfig,
     world_state: &mut WorldState,
     loop_state: &mut LoopState,
     event: Event,
@@ -349,6 +475,7 @@ fn loop_turn(
         log::info!("queued count = {}", queue_count);
     }
 
+    let mut state_changed = false;
     match event {
         Event::Task(task) => {
             on_task(task, &connection.sender, &mut loop_state.pending_requests, world_state);
@@ -357,16 +484,54 @@ fn loop_turn(
         Event::Vfs(task) => {
             world_state.vfs.write().handle_task(task);
         }
-        Event::Lib(lib) => {
-            world_state.add_lib(lib);
-            world_state.maybe_collect_garbage();
+        Event::Lib(generation, lib) => {
+            if generation == loop_state.workspace_generation {
+                world_state.add_lib(lib);
+                world_state.maybe_collect_garbage();
+            }
             loop_state.in_flight_libraries -= 1;
         }
+        Event::Workspaces(reload, workspaces, errors) => {
+            if reload == loop_state.workspace_reloads.load(Ordering::SeqCst) {
+                on_workspaces_reloaded(
+                    &connection.sender,
+                    world_state,
+                    loop_state,
+                    workspaces,
+                    errors,
+                );
+                state_changed = true;
+                let subscriptions = loop_state.subscriptions.subscriptions();
+                loop_state.schedule_diagnostics(subscriptions);
+            }
+        }
         Event::CheckWatcher(task) => on_check_task(task, world_state, task_sender)?,
+        Event::PublishDiagnostics => {
+            loop_state.diagnostics_deadline = None;
+            let pending = std::mem::take(&mut loop_state.pending_diagnostics);
+            let files: Vec<FileId> = loop_state
+                .subscriptions
+                .subscriptions()
+                .into_iter()
+                .filter(|file_id| pending.contains(file_id))
+                .collect();
+            // Until the new diagnostics arrive, which might never happen if
+            // their computation gets canceled, any change refreshes them.
+            for file_id in files.iter() {
+                world_state.diagnostics.native_dependencies.remove(file_id);
+            }
+            publish_diagnostics_on_threadpool(
+                pool,
+                world_state.snapshot(),
+                task_sender.clone(),
+                files,
+            )
+        }
         Event::Msg(msg) => match msg {
             Message::Request(req) => on_request(
                 world_state,
                 &mut loop_state.pending_requests,
+                &mut loop_state.status,
                 pool,
                 task_sender,
                 &connection.sender,
@@ -374,27 +539,43 @@ fn loop_turn(
                 req,
             )?,
             Message::Notification(not) => {
-                on_notification(
-                    &connection.sender,
-                    world_state,
-                    &mut loop_state.pending_requests,
-                    &mut loop_state.subscriptions,
-                    not,
-                )?;
+                on_notification(&connection.sender, config, world_state, loop_state, not)?;
             }
             Message::Response(resp) => {
                 let removed = loop_state.pending_responses.remove(&resp.id);
                 if !removed {
                     log::error!("unexpected response: {:?}", resp)
+                } else if loop_state.configuration_request.as_ref() == Some(&resp.id) {
+                    loop_state.configuration_request = None;
+                    on_configuration_response(
+                        &connection.sender,
+                        config,
+                        world_state,
+                        loop_state,
+                        resp,
+                    );
                 }
             }
         },
     };
 
-    let mut state_changed = false;
-    if let Some(changes) = world_state.process_changes() {
+    if world_state.workspace_reload_requested {
+        world_state.workspace_reload_requested = false;
+        schedule_workspace_reload(
+            &connection.sender,
+            workspace_sender,
+            config,
+            world_state,
+            loop_state,
+        );
+    }
+
+    if let Some((changed_roots, libs)) = world_state.process_changes() {
         state_changed = true;
-        loop_state.pending_libraries.extend(changes);
+        let subscriptions = loop_state.subscriptions.subscriptions();
+        let files = world_state.diagnostics.files_to_refresh(subscriptions, &changed_roots);
+        loop_state.schedule_diagnostics(files);
+        loop_state.pending_libraries.extend(libs);
     }
 
     let max_in_flight_libs = pool.max_count().saturating_sub(2).max(1);
@@ -404,10 +585,11 @@ fn loop_turn(
         let (root, files) = loop_state.pending_libraries.pop().unwrap();
         loop_state.in_flight_libraries += 1;
         let sender = libdata_sender.clone();
+        let generation = loop_state.workspace_generation;
         pool.execute(move || {
             log::info!("indexing {:?} ... ", root);
             let data = LibraryData::prepare(root, files);
-            sender.send(data).unwrap();
+            sender.send((generation, data)).unwrap();
         });
     }
 
@@ -417,6 +599,7 @@ fn loop_turn(
         && loop_state.in_flight_libraries == 0
     {
         loop_state.workspace_loaded = true;
+        send_status(&connection.sender, loop_state.status.finish_loading());
         let n_packages: usize = world_state.workspaces.iter().map(|it| it.n_packages()).sum();
         if world_state.feature_flags().get("notifications.workspace-loaded") {
             let msg = format!("workspace loaded, {} rust packages", n_packages);
@@ -425,11 +608,10 @@ fn loop_turn(
         world_state.check_watcher.update();
     }
 
-    if state_changed {
-        update_file_notifications_on_threadpool(
+    if state_changed && world_state.options.publish_decorations {
+        publish_decorations_on_threadpool(
             pool,
             world_state.snapshot(),
-            world_state.options.publish_decorations,
             task_sender.clone(),
             loop_state.subscriptions.subscriptions(),
         )
@@ -459,6 +641,15 @@ fn on_task(
     match task {
         Task::Respond(response) => {
             if let Some(completed) = pending_requests.finish(&response.id) {
+                // Requests canceled by the client are counted when they are
+                // canceled, and don't get here.
+                if response
+                    .error
+                    .as_ref()
+                    .map_or(false, |it| it.code == ErrorCode::RequestCanceled as i32)
+                {
+                    ra_prof::count("requests cancelled");
+                }
                 log::info!("handled req#{} in {:?}", completed.id, completed.duration);
                 state.complete_request(completed);
                 msg_sender.send(response.into()).unwrap();
@@ -474,6 +665,7 @@ fn on_task(
 fn on_request(
     world: &mut WorldState,
     pending_requests: &mut PendingRequests,
+    status: &mut StatusReporter,
     pool: &ThreadPool,
     task_sender: &Sender<Task>,
     msg_sender: &Sender<Message>,
@@ -487,10 +679,15 @@ fn on_request(
         task_sender,
         msg_sender,
         pending_requests,
+        status,
         request_received,
     };
     pool_dispatcher
         .on_sync::<req::CollectGarbage>(|s, ()| Ok(s.collect_garbage()))?
+        .on_sync::<req::ReloadWorkspace>(|s, ()| {
+            s.workspace_reload_requested = true;
+            Ok(())
+        })?
         .on_sync::<req::JoinLines>(|s, p| handlers::handle_join_lines(s.snapshot(), p))?
         .on_sync::<req::OnEnter>(|s, p| handlers::handle_on_enter(s.snapshot(), p))?
         .on_sync::<req::SelectionRangeRequest>(|s, p| {
@@ -502,6 +699,9 @@ fn on_request(
         .on::<req::AnalyzerStatus>(handlers::handle_analyzer_status)?
         .on::<req::SyntaxTree>(handlers::handle_syntax_tree)?
         .on::<req::ExpandMacro>(handlers::handle_expand_macro)?
+        .on::<req::ViewHir>(handlers::handle_view_hir)?
+        .on::<req::ViewCrateGraph>(handlers::handle_view_crate_graph)?
+        .on::<req::TypeOfRange>(handlers::handle_type_of_range)?
         .on::<req::OnTypeFormatting>(handlers::handle_on_type_formatting)?
         .on::<req::DocumentSymbolRequest>(handlers::handle_document_symbol)?
         .on::<req::WorkspaceSymbol>(handlers::handle_workspace_symbol)?
@@ -509,10 +709,12 @@ fn on_request(
         .on::<req::GotoImplementation>(handlers::handle_goto_implementation)?
         .on::<req::GotoTypeDefinition>(handlers::handle_goto_type_definition)?
         .on::<req::ParentModule>(handlers::handle_parent_module)?
+        .on::<req::OpenDocs>(handlers::handle_open_docs)?
         .on::<req::Runnables>(handlers::handle_runnables)?
         .on::<req::DecorationsRequest>(handlers::handle_decorations)?
         .on::<req::Completion>(handlers::handle_completion)?
         .on::<req::CodeActionRequest>(handlers::handle_code_action)?
+        .on::<req::CodeActionResolve>(handlers::handle_code_action_resolve)?
         .on::<req::CodeLensRequest>(handlers::handle_code_lens)?
         .on::<req::CodeLensResolve>(handlers::handle_code_lens_resolve)?
         .on::<req::FoldingRangeRequest>(handlers::handle_folding_range)?
@@ -520,6 +722,7 @@ fn on_request(
         .on::<req::HoverRequest>(handlers::handle_hover)?
         .on::<req::PrepareRenameRequest>(handlers::handle_prepare_rename)?
         .on::<req::Rename>(handlers::handle_rename)?
+        .on::<req::WillRenameFiles>(handlers::handle_will_rename_files)?
         .on::<req::References>(handlers::handle_references)?
         .on::<req::Formatting>(handlers::handle_formatting)?
         .on::<req::DocumentHighlightRequest>(handlers::handle_document_highlight)?
@@ -536,11 +739,13 @@ fn on_request(
 
 fn on_notification(
     msg_sender: &Sender<Message>,
+    config: &ServerConfig,
     state: &mut WorldState,
-    pending_requests: &mut PendingRequests,
-    subs: &mut Subscriptions,
+    loop_state: &mut LoopState,
     not: Notification,
 ) -> Result<()> {
+    let pending_requests = &mut loop_state.pending_requests;
+    let subs = &mut loop_state.subscriptions;
     let not = match notification_cast::<req::Cancel>(not) {
         Ok(params) => {
             let id: RequestId = match params.id {
@@ -573,11 +778,27 @@ fn on_notification(
         Err(not) => not,
     };
     let not = match notification_cast::<req::DidChangeTextDocument>(not) {
-        Ok(mut params) => {
+        Ok(params) => {
             let uri = params.text_document.uri;
             let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
-            let text =
-                params.content_changes.pop().ok_or_else(|| "empty changes".to_string())?.text;
+            let file_id = state.vfs.read().path2file(&path);
+            let mut text = match file_id {
+                Some(file_id) => {
+                    state.analysis_host.analysis().file_text(FileId(file_id.0))?.to_string()
+                }
+                None => String::new(),
+            };
+            apply_document_changes(
+                &mut text,
+                params.content_changes,
+                state.options.offset_encoding,
+            );
+            // The analysis only sees `\n`, keep the line endings of the file.
+            if let Some(file_id) = file_id {
+                if state.file_line_endings(FileId(file_id.0)) == LineEndings::Dos {
+                    text = text.replace('\n', "\r\n");
+                }
+            }
             state.vfs.write().change_file_overlay(path.as_path(), text);
             return Ok(());
         }
@@ -607,6 +828,21 @@ fn on_notification(
     };
     let not = match notification_cast::<req::DidChangeConfiguration>(not) {
         Ok(_params) => {
+            // The shape of the params depends on the client, so the settings
+            // are pulled in the same shape as the initialization options.
+            if loop_state.supports_configuration_request {
+                let request_id = loop_state.next_request_id();
+                let params = req::ConfigurationParams {
+                    items: vec![req::ConfigurationItem {
+                        scope_uri: None,
+                        section: Some("rust-analyzer".to_string()),
+                    }],
+                };
+                let request =
+                    request_new::<req::WorkspaceConfiguration>(request_id.clone(), params);
+                msg_sender.send(request.into()).unwrap();
+                loop_state.configuration_request = Some(request_id);
+            }
             return Ok(());
         }
         Err(not) => not,
@@ -617,16 +853,232 @@ fn on_notification(
             for change in params.changes {
                 let uri = change.uri;
                 let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
-                vfs.notify_changed(path)
+                let is_manifest = path
+                    .file_name()
+                    .and_then(|it| it.to_str())
+                    .map_or(false, |it| MANIFESTS.contains(&it));
+                if is_manifest {
+                    state.workspace_reload_requested = true;
+                } else {
+                    vfs.notify_changed(path)
+                }
             }
             return Ok(());
         }
         Err(not) => not,
     };
+    let not = match notification_cast::<req::DidChangeWorkspaceFolders>(not) {
+        Ok(params) => {
+            on_workspace_folders_change(msg_sender, config, state, loop_state, params.event)?;
+            return Ok(());
+        }
+        Err(not) => not,
+    };
     log::error!("unhandled notification: {:?}", not);
     Ok(())
 }
 
+fn on_configuration_response(
+    msg_sender: &Sender<Message>,
+    config: &mut ServerConfig,
+    state: &mut WorldState,
+    loop_state: &mut LoopState,
+    resp: Response,
+) {
+    let settings = match resp.result {
+        Some(serde_json::Value::Array(mut items)) if !items.is_empty() => items.swap_remove(0),
+        _ => {
+            log::error!("failed to fetch the configuration: {:?}", resp.error);
+            return;
+        }
+    };
+    let (new_config, warnings) = ServerConfig::from_json(settings);
+    if !warnings.is_empty() {
+        report_error(
+            msg_sender,
+            &mut loop_state.status,
+            ErrorKind::Config,
+            config_warnings_message(&warnings),
+        );
+    }
+
+    let change = loop_state.client_config.as_ref().unwrap_or(config).diff(&new_config);
+    log::info!("configuration changed: {:?}", change);
+    if !change.applied.is_empty() {
+        *config = config.with_runtime_settings(&new_config);
+        state.update_config(config);
+    }
+    if !change.needs_reload.is_empty() {
+        let msg = format!(
+            "rust-analyzer: changing {} requires a restart of the server",
+            change.needs_reload.join(", ")
+        );
+        show_message(req::MessageType::Info, msg, msg_sender);
+    }
+    loop_state.client_config = Some(new_config);
+}
+
+fn on_workspace_folders_change(
+    msg_sender: &Sender<Message>,
+    config: &ServerConfig,
+    state: &mut WorldState,
+    loop_state: &mut LoopState,
+    event: req::WorkspaceFoldersChangeEvent,
+) -> Result<()> {
+    let mut roots = state.roots.clone();
+    for folder in event.removed {
+        let uri = folder.uri;
+        let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
+        roots.retain(|root| *root != path);
+    }
+    for folder in event.added {
+        let uri = folder.uri;
+        let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
+        if !roots.contains(&path) {
+            roots.push(path);
+        }
+    }
+    send_status(msg_sender, loop_state.status.start_loading());
+    let (workspaces, errors) = load_workspaces(&roots, config, state.feature_flags());
+    for error in errors {
+        report_error(msg_sender, &mut loop_state.status, ErrorKind::WorkspaceLoad, error);
+    }
+    reload_workspaces(msg_sender, state, loop_state, roots, workspaces);
+    Ok(())
+}
+
+/// Files whose changes affect the crate graph.
+const MANIFESTS: &[&str] = &["Cargo.toml", "Cargo.lock"];
+
+/// How long to wait for further manifest changes before reloading the
+/// workspaces.
+const WORKSPACE_RELOAD_DELAY: Duration = Duration::from_millis(500);
+
+/// Reloads the workspaces in the background, once the manifests stop
+/// changing: the reload only starts if no other one was scheduled in the
+/// meantime.
+fn schedule_workspace_reload(
+    msg_sender: &Sender<Message>,
+    workspace_sender: &Sender<(usize, Vec<ProjectWorkspace>, Vec<String>)>,
+    config: &ServerConfig,
+    state: &WorldState,
+    loop_state: &mut LoopState,
+) {
+    send_status(msg_sender, loop_state.status.start_loading());
+    let reload = loop_state.workspace_reloads.fetch_add(1, Ordering::SeqCst) + 1;
+    let reloads = Arc::clone(&loop_state.workspace_reloads);
+    let workspace_sender = workspace_sender.clone();
+    let config = config.clone();
+    let feature_flags = state.feature_flags().clone();
+    let roots = state.roots.clone();
+    thread::spawn(move || {
+        thread::sleep(WORKSPACE_RELOAD_DELAY);
+        if reloads.load(Ordering::SeqCst) != reload {
+            return;
+        }
+        log::info!("reloading workspaces");
+        let (workspaces, errors) = load_workspaces(&roots, &config, &feature_flags);
+        // The main loop might be shutting down already.
+        let _ = workspace_sender.send((reload, workspaces, errors));
+    });
+}
+
+fn on_workspaces_reloaded(
+    msg_sender: &Sender<Message>,
+    state: &mut WorldState,
+    loop_state: &mut LoopState,
+    workspaces: Vec<ProjectWorkspace>,
+    errors: Vec<String>,
+) {
+    for error in errors {
+        report_error(msg_sender, &mut loop_state.status, ErrorKind::WorkspaceLoad, error);
+    }
+    if state.knows_roots_of(&workspaces) {
+        state.update_workspaces(workspaces);
+        // Otherwise, loading finishes once the libraries are indexed.
+        if loop_state.workspace_loaded {
+            send_status(msg_sender, loop_state.status.finish_loading());
+        }
+    } else {
+        let roots = state.roots.clone();
+        reload_workspaces(msg_sender, state, loop_state, roots, workspaces);
+    }
+    state.check_watcher.update();
+}
+
+/// Rebuilds the world from scratch for `workspaces`.
+fn reload_workspaces(
+    msg_sender: &Sender<Message>,
+    state: &mut WorldState,
+    loop_state: &mut LoopState,
+    roots: Vec<PathBuf>,
+    workspaces: Vec<ProjectWorkspace>,
+) {
+    // `FileId`s don't survive the reload, so retract everything published so
+    // far. Diagnostics for files which are still open are recomputed below.
+    let published: FxHashSet<FileId> =
+        state.diagnostics.native.keys().chain(state.diagnostics.check.keys()).copied().collect();
+    for file_id in published {
+        let path = state.vfs.read().file2path(VfsFile(file_id.0));
+        let uri = match url_from_path_with_drive_lowercasing(&path) {
+            Ok(uri) => uri,
+            Err(err) => {
+                log::error!("Couldn't convert path to url ({}): {:?}", err, path.to_string_lossy());
+                continue;
+            }
+        };
+        let params = req::PublishDiagnosticsParams { uri, diagnostics: Vec::new(), version: None };
+        let not = notification_new::<req::PublishDiagnostics>(params);
+        msg_sender.send(not.into()).unwrap();
+    }
+
+    let open_files = state.reload(roots, workspaces, &loop_state.subscriptions.subscriptions());
+    loop_state.subscriptions = Subscriptions::default();
+    for file_id in open_files {
+        loop_state.subscriptions.add_sub(file_id);
+    }
+    loop_state.pending_libraries.clear();
+    loop_state.workspace_generation += 1;
+    loop_state.workspace_loaded = false;
+    // Reloads in flight were started for the previous workspace folders.
+    loop_state.workspace_reloads.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Applies the changes of a `didChange` notification in order. Each change
+/// refers to the text produced by the previous ones, so the line index is
+/// rebuilt after every edit.
+fn apply_document_changes(
+    text: &mut String,
+    content_changes: Vec<TextDocumentContentChangeEvent>,
+    offset_encoding: OffsetEncoding,
+) {
+    for change in content_changes {
+        // Line endings are normalized in the analysis, do the same for the
+        // inserted text so that later changes land on the right offsets.
+        let new_text = change.text.replace("\r\n", "\n");
+        match change.range {
+            Some(range) => {
+                let line_map = LineMap::new(Arc::new(LineIndex::new(text)), offset_encoding);
+                // Positions past the end of the text, like the start of the
+                // line after the last one, refer to the end of the text.
+                let end: Position = TextUnit::of_str(text).conv_with(&line_map);
+                let clamp = |pos: Position| {
+                    if (pos.line, pos.character) > (end.line, end.character) {
+                        end
+                    } else {
+                        pos
+                    }
+                };
+                let range = Range::new(clamp(range.start), clamp(range.end)).conv_with(&line_map);
+                let start = range.start().to_usize().min(text.len());
+                let end = range.end().to_usize().min(text.len()).max(start);
+                text.replace_range(start..end, &new_text);
+            }
+            None => *text = new_text,
+        }
+    }
+}
+
 fn on_check_task(
     task: CheckTask,
     world_state: &mut WorldState,
@@ -689,6 +1141,7 @@ struct PoolDispatcher<'a> {
     pool: &'a ThreadPool,
     world: &'a mut WorldState,
     pending_requests: &'a mut PendingRequests,
+    status: &'a mut StatusReporter,
     msg_sender: &'a Sender<Message>,
     task_sender: &'a Sender<Task>,
     request_received: Instant,
@@ -711,12 +1164,32 @@ impl<'a> PoolDispatcher<'a> {
                 return Ok(self);
             }
         };
+        let profile_request = self.world.options.profile_requests;
         let world = panic::AssertUnwindSafe(&mut *self.world);
-        let task = panic::catch_unwind(move || {
+        let request_id = id.clone();
+        let task = match panic::catch_unwind(move || {
+            let _p = if profile_request { Some(profile(R::METHOD)) } else { None };
             let result = f(world.0, params);
             result_to_task::<R>(id, result)
-        })
-        .map_err(|_| format!("sync task {:?} panicked", R::METHOD))?;
+        }) {
+            Ok(task) => task,
+            // Only the main loop changes the world, so nothing can cancel a
+            // sync task: the handler misuses a snapshot.
+            Err(payload) if payload.is::<Canceled>() => {
+                report_error(
+                    self.msg_sender,
+                    self.status,
+                    ErrorKind::Cancellation,
+                    format!("sync task {:?} was canceled", R::METHOD),
+                );
+                Task::Respond(Response::new_err(
+                    request_id,
+                    ErrorCode::ContentModified as i32,
+                    "content modified".to_string(),
+                ))
+            }
+            Err(_) => return Err(format!("sync task {:?} panicked", R::METHOD).into()),
+        };
         on_task(task, self.msg_sender, self.pending_requests, self.world);
         Ok(self)
     }
@@ -739,6 +1212,8 @@ impl<'a> PoolDispatcher<'a> {
             let world = self.world.snapshot();
             let sender = self.task_sender.clone();
             move || {
+                let _p =
+                    if world.options.profile_requests { Some(profile(R::METHOD)) } else { None };
                 let result = f(world, params);
                 let task = result_to_task::<R>(id, result);
                 sender.send(task).unwrap();
@@ -796,10 +1271,11 @@ where
         Err(e) => match e.downcast::<LspError>() {
             Ok(lsp_error) => Response::new_err(id, lsp_error.code, lsp_error.message),
             Err(e) => {
+                // An edit canceled the request, so any result would be stale.
                 if is_canceled(&e) {
                     Response::new_err(
                         id,
-                        ErrorCode::ContentModified as i32,
+                        ErrorCode::RequestCanceled as i32,
                         "content modified".to_string(),
                     )
                 } else {
@@ -811,46 +1287,77 @@ where
     Task::Respond(response)
 }
 
-fn update_file_notifications_on_threadpool(
+fn publish_diagnostics_on_threadpool(
     pool: &ThreadPool,
     world: WorldSnapshot,
-    publish_decorations: bool,
     task_sender: Sender<Task>,
-    subscriptions: Vec<FileId>,
+    files: Vec<FileId>,
 ) {
-    log::trace!("updating notifications for {:?}", subscriptions);
-    let publish_diagnostics = world.feature_flags().get("lsp.diagnostics");
+    if files.is_empty() || !world.feature_flags().get("lsp.diagnostics") {
+        return;
+    }
+    log::trace!("updating diagnostics for {:?}", files);
     pool.execute(move || {
-        for file_id in subscriptions {
-            if publish_diagnostics {
-                match handlers::publish_diagnostics(&world, file_id) {
-                    Err(e) => {
-                        if !is_canceled(&e) {
-                            log::error!("failed to compute diagnostics: {:?}", e);
-                        }
-                    }
-                    Ok(task) => {
-                        task_sender.send(Task::Diagnostic(task)).unwrap();
+        for file_id in files {
+            match handlers::publish_diagnostics(&world, file_id) {
+                Err(e) => {
+                    if !is_canceled(&e) {
+                        log::error!("failed to compute diagnostics: {:?}", e);
                     }
                 }
+                Ok(task) => {
+                    task_sender.send(Task::Diagnostic(task)).unwrap();
+                }
             }
-            if publish_decorations {
-                match handlers::publish_decorations(&world, file_id) {
-                    Err(e) => {
-                        if !is_canceled(&e) {
-                            log::error!("failed to compute decorations: {:?}", e);
-                        }
-                    }
-                    Ok(params) => {
-                        let not = notification_new::<req::PublishDecorations>(params);
-                        task_sender.send(Task::Notify(not)).unwrap();
+        }
+    });
+}
+
+fn publish_decorations_on_threadpool(
+    pool: &ThreadPool,
+    world: WorldSnapshot,
+    task_sender: Sender<Task>,
+    subscriptions: Vec<FileId>,
+) {
+    log::trace!("updating decorations for {:?}", subscriptions);
+    pool.execute(move || {
+        for file_id in subscriptions {
+            match handlers::publish_decorations(&world, file_id) {
+                Err(e) => {
+                    if !is_canceled(&e) {
+                        log::error!("failed to compute decorations: {:?}", e);
                     }
                 }
+                Ok(params) => {
+                    let not = notification_new::<req::PublishDecorations>(params);
+                    task_sender.send(Task::Notify(not)).unwrap();
+                }
             }
         }
     });
 }
 
+/// Shows `message` as an error, unless the same error was shown recently.
+fn report_error(
+    msg_sender: &Sender<Message>,
+    status: &mut StatusReporter,
+    kind: ErrorKind,
+    message: String,
+) {
+    log::error!("{:?}: {}", kind, message);
+    if let Some(params) = status.report_error(kind, message, Instant::now()) {
+        let not = notification_new::<req::ShowMessage>(params);
+        msg_sender.send(not.into()).unwrap();
+    }
+}
+
+fn send_status(msg_sender: &Sender<Message>, status: Option<req::ServerStatus>) {
+    if let Some(status) = status {
+        let not = notification_new::<req::Status>(status);
+        msg_sender.send(not.into()).unwrap();
+    }
+}
+
 pub fn show_message(typ: req::MessageType, message: impl Into<String>, sender: &Sender<Message>) {
     let message = message.into();
     let params = req::ShowMessageParams { typ, message };
@@ -889,3 +1396,69 @@ where
 {
     Request::new(id, R::METHOD.to_string(), params)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(
+        range: Option<((u64, u64), (u64, u64))>,
+        text: &str,
+    ) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: range.map(|((l1, c1), (l2, c2))| {
+                Range::new(Position::new(l1, c1), Position::new(l2, c2))
+            }),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn apply_changes_in_order() {
+        let mut text = String::new();
+        apply_document_changes(&mut text, vec![], OffsetEncoding::Utf16);
+        assert_eq!(text, "");
+        apply_document_changes(
+            &mut text,
+            vec![change(None, "fn main() {}\n"), change(Some(((0, 11), (0, 11))), "\n    92\n")],
+            OffsetEncoding::Utf16,
+        );
+        assert_eq!(text, "fn main() {\n    92\n}\n");
+        apply_document_changes(
+            &mut text,
+            vec![change(Some(((1, 4), (1, 6))), "\"🦀\""), change(Some(((1, 7), (1, 7))), "!")],
+            OffsetEncoding::Utf16,
+        );
+        assert_eq!(text, "fn main() {\n    \"🦀!\"\n}\n");
+        apply_document_changes(
+            &mut text,
+            vec![change(Some(((1, 9), (1, 10))), "?")],
+            OffsetEncoding::Utf8,
+        );
+        assert_eq!(text, "fn main() {\n    \"🦀?\"\n}\n");
+        apply_document_changes(
+            &mut text,
+            vec![change(Some(((0, 0), (2, 1))), "fn main() {}\r\n")],
+            OffsetEncoding::Utf8,
+        );
+        assert_eq!(text, "fn main() {}\n\n");
+    }
+
+    #[test]
+    fn apply_changes_past_the_end() {
+        let mut text = "fn main() {}\nfn foo() {}".to_string();
+        apply_document_changes(
+            &mut text,
+            vec![change(Some(((0, 12), (2, 0))), "")],
+            OffsetEncoding::Utf16,
+        );
+        assert_eq!(text, "fn main() {}");
+        apply_document_changes(
+            &mut text,
+            vec![change(Some(((5, 3), (7, 0))), "\n")],
+            OffsetEncoding::Utf8,
+        );
+        assert_eq!(text, "fn main() {}\n");
+    }
+}
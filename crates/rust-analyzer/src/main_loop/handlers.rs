@@ -13,20 +13,20 @@ use lsp_server::ErrorCode;
 use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
     CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
-    CodeAction, CodeActionOrCommand, CodeActionResponse, CodeLens, Command, CompletionItem,
-    Diagnostic, DocumentFormattingParams, DocumentHighlight, DocumentSymbol, FoldingRange,
-    FoldingRangeParams, Hover, HoverContents, Location, MarkupContent, MarkupKind, Position,
+    CodeAction, CodeActionOrCommand, CodeLens, Command, CompletionItem, Diagnostic,
+    DocumentFormattingParams, DocumentHighlight, DocumentSymbol, FoldingRange, FoldingRangeParams,
+    Hover, HoverContents, Location, MarkupContent, MarkupKind, NumberOrString, Position,
     PrepareRenameResponse, Range, RenameParams, SemanticTokens, SemanticTokensParams,
     SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensResult, SymbolInformation,
-    TextDocumentIdentifier, TextEdit, WorkspaceEdit,
+    TextDocumentIdentifier, TextEdit, Url, WorkspaceEdit,
 };
 use ra_ide::{
-    AssistId, FileId, FilePosition, FileRange, Query, RangeInfo, Runnable, RunnableKind,
-    SearchScope,
+    Annotation, AnnotationKind, AssistId, FileId, FilePosition, FileRange, Query, RangeInfo,
+    Runnable, RunnableKind, SearchScope, SourceChange,
 };
 use ra_prof::profile;
 use ra_syntax::{AstNode, SyntaxKind, TextRange, TextUnit};
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
 use serde_json::to_value;
 
@@ -53,13 +53,17 @@ pub fn handle_analyzer_status(world: WorldSnapshot, _: ()) -> Result<String> {
         let mark = if is_last { "*" } else { " " };
         writeln!(buf, "{}{:4} {:<36}{}ms", mark, r.id, r.method, r.duration.as_millis()).unwrap();
     }
+    writeln!(buf, "\ncounters:").unwrap();
+    for (label, count) in ra_prof::counters() {
+        writeln!(buf, "{}: {}", label, count).unwrap();
+    }
     Ok(buf)
 }
 
 pub fn handle_syntax_tree(world: WorldSnapshot, params: req::SyntaxTreeParams) -> Result<String> {
     let _p = profile("handle_syntax_tree");
     let id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(id)?;
+    let line_index = world.file_line_index(id)?;
     let text_range = params.range.map(|p| p.conv_with(&line_index));
     let res = world.analysis().syntax_tree(id, text_range)?;
     Ok(res)
@@ -71,7 +75,7 @@ pub fn handle_expand_macro(
 ) -> Result<Option<req::ExpandedMacro>> {
     let _p = profile("handle_expand_macro");
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let offset = params.position.map(|p| p.conv_with(&line_index));
 
     match offset {
@@ -83,31 +87,53 @@ pub fn handle_expand_macro(
     }
 }
 
+pub fn handle_view_hir(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<String>> {
+    let _p = profile("handle_view_hir");
+    let position = params.try_conv_with(&world)?;
+    let res = world.analysis().view_hir(position)?;
+    Ok(res)
+}
+
+pub fn handle_view_crate_graph(
+    world: WorldSnapshot,
+    params: req::ViewCrateGraphParams,
+) -> Result<String> {
+    let _p = profile("handle_view_crate_graph");
+    let dot = world.analysis().view_crate_graph(params.full)?;
+    Ok(dot)
+}
+
+pub fn handle_type_of_range(
+    world: WorldSnapshot,
+    params: req::TypeOfRangeParams,
+) -> Result<Option<req::TypeOfRangeResult>> {
+    let _p = profile("handle_type_of_range");
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let line_index = world.file_line_index(file_id)?;
+    let range = params.range.conv_with(&line_index);
+    let res = world.analysis().type_of_range(FileRange { file_id, range })?;
+    Ok(res.map(|it| req::TypeOfRangeResult { ty: it.info, range: it.range.conv_with(&line_index) }))
+}
+
 pub fn handle_selection_range(
     world: WorldSnapshot,
     params: req::SelectionRangeParams,
 ) -> Result<Vec<req::SelectionRange>> {
     let _p = profile("handle_selection_range");
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     params
         .positions
         .into_iter()
         .map_conv_with(&line_index)
-        .map(|position| {
-            let mut ranges = Vec::new();
-            {
-                let mut range = TextRange::from_to(position, position);
-                loop {
-                    ranges.push(range);
-                    let frange = FileRange { file_id, range };
-                    let next = world.analysis().extend_selection(frange)?;
-                    if next == range {
-                        break;
-                    } else {
-                        range = next
-                    }
-                }
+        .map(|offset| {
+            let position = FilePosition { file_id, offset };
+            let mut ranges = world.analysis().selection_ranges(position)?;
+            if ranges.is_empty() {
+                ranges.push(TextRange::offset_len(offset, 0.into()));
             }
             let mut range = req::SelectionRange {
                 range: ranges.last().unwrap().conv_with(&line_index),
@@ -130,7 +156,7 @@ pub fn handle_find_matching_brace(
 ) -> Result<Vec<Position>> {
     let _p = profile("handle_find_matching_brace");
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let res = params
         .offsets
         .into_iter()
@@ -177,7 +203,7 @@ pub fn handle_on_type_formatting(
 ) -> Result<Option<Vec<TextEdit>>> {
     let _p = profile("handle_on_type_formatting");
     let mut position = params.text_document_position.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(position.file_id)?;
+    let line_index = world.file_line_index(position.file_id)?;
     let line_endings = world.file_line_endings(position.file_id);
 
     // in `ra_ide`, the `on_type` invariant is that
@@ -212,7 +238,7 @@ pub fn handle_document_symbol(
 ) -> Result<Option<req::DocumentSymbolResponse>> {
     let _p = profile("handle_document_symbol");
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
 
     let mut parents: Vec<(DocumentSymbol, Option<usize>)> = Vec::new();
 
@@ -228,21 +254,54 @@ pub fn handle_document_symbol(
         };
         parents.push((doc_symbol, symbol.parent));
     }
-    let mut res = Vec::new();
-    while let Some((node, parent)) = parents.pop() {
-        match parent {
-            None => res.push(node),
-            Some(i) => {
-                let children = &mut parents[i].0.children;
-                if children.is_none() {
-                    *children = Some(Vec::new());
-                }
-                children.as_mut().unwrap().push(node);
-            }
+
+    // Builds the hierarchy from the flat list. Children always come after
+    // their parent, so popping from the back fills in all children of a node
+    // before the node itself is attached to its parent.
+    let mut document_symbols = Vec::new();
+    while let Some((mut node, parent)) = parents.pop() {
+        if let Some(children) = &mut node.children {
+            children.reverse();
         }
+        let siblings = match parent {
+            None => &mut document_symbols,
+            Some(i) => parents[i].0.children.get_or_insert_with(Vec::new),
+        };
+        siblings.push(node);
     }
+    document_symbols.reverse();
+
+    let res = if world.options.supports_hierarchical_symbols {
+        req::DocumentSymbolResponse::Nested(document_symbols)
+    } else {
+        let url = &params.text_document.uri;
+        let mut symbol_information = Vec::new();
+        for symbol in document_symbols {
+            flatten_document_symbol(&symbol, None, url, &mut symbol_information);
+        }
+        req::DocumentSymbolResponse::Flat(symbol_information)
+    };
+
+    Ok(Some(res))
+}
+
+fn flatten_document_symbol(
+    symbol: &DocumentSymbol,
+    container_name: Option<String>,
+    url: &Url,
+    res: &mut Vec<SymbolInformation>,
+) {
+    res.push(SymbolInformation {
+        name: symbol.name.clone(),
+        kind: symbol.kind,
+        deprecated: symbol.deprecated,
+        location: Location::new(url.clone(), symbol.range),
+        container_name,
+    });
 
-    Ok(Some(res.into()))
+    for child in symbol.children.iter().flatten() {
+        flatten_document_symbol(child, Some(symbol.name.clone()), url, res);
+    }
 }
 
 pub fn handle_workspace_symbol(
@@ -299,6 +358,10 @@ pub fn handle_goto_definition(
         None => return Ok(None),
         Some(it) => it,
     };
+    // Builtin types have nothing to navigate to: their docs are shown on hover.
+    if nav_info.info.iter().all(|nav| nav.file_id().is_none()) {
+        return Ok(None);
+    }
     let res = (position.file_id, nav_info).try_conv_with(&world)?;
     Ok(Some(res))
 }
@@ -340,13 +403,37 @@ pub fn handle_parent_module(
     world.analysis().parent_module(position)?.iter().try_conv_with_to_vec(&world)
 }
 
+pub fn handle_open_docs(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<String>> {
+    let _p = profile("handle_open_docs");
+    let position = params.try_conv_with(&world)?;
+    let docs = match world.analysis().external_docs(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    // Prefer the docs generated by `cargo doc` for the crates of the workspace.
+    if let Some(doc_dir) = world.doc_dir_for(docs.crate_root) {
+        let (path, fragment) = match docs.path.find('#') {
+            Some(idx) => (&docs.path[..idx], Some(&docs.path[idx + 1..])),
+            None => (docs.path.as_str(), None),
+        };
+        if let Ok(mut url) = Url::from_file_path(doc_dir.join(path)) {
+            url.set_fragment(fragment);
+            return Ok(Some(url.to_string()));
+        }
+    }
+    Ok(Some(docs.docs_rs_url))
+}
+
 pub fn handle_runnables(
     world: WorldSnapshot,
     params: req::RunnablesParams,
 ) -> Result<Vec<req::Runnable>> {
     let _p = profile("handle_runnables");
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let offset = params.position.map(|it| it.conv_with(&line_index));
     let mut res = Vec::new();
     let workspace_root = world.workspace_root_for(file_id);
@@ -423,7 +510,7 @@ pub fn handle_completion(
         None => return Ok(None),
         Some(items) => items,
     };
-    let line_index = world.analysis().file_line_index(position.file_id)?;
+    let line_index = world.file_line_index(position.file_id)?;
     let line_endings = world.file_line_endings(position.file_id);
     let items: Vec<CompletionItem> =
         items.into_iter().map(|item| item.conv_with((&line_index, line_endings))).collect();
@@ -439,7 +526,7 @@ pub fn handle_folding_range(
     let file_id = params.text_document.try_conv_with(&world)?;
     let folds = world.analysis().folding_ranges(file_id)?;
     let text = world.analysis().file_text(file_id)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let ctx = FoldConvCtx {
         text: &text,
         line_index: &line_index,
@@ -457,7 +544,17 @@ pub fn handle_signature_help(
     let position = params.try_conv_with(&world)?;
     if let Some(call_info) = world.analysis().call_info(position)? {
         let active_parameter = call_info.active_parameter.map(|it| it as i64);
-        let sig_info = call_info.signature.conv();
+        let mut sig_info = call_info.signature.conv();
+        // Signature help can't be styled, so the parameters which are already
+        // given are marked in their documentation instead.
+        if let Some(parameters) = &mut sig_info.parameters {
+            for &idx in call_info.provided_parameters.iter() {
+                if let Some(param) = parameters.get_mut(idx) {
+                    param.documentation =
+                        Some(lsp_types::Documentation::String("already provided".to_string()));
+                }
+            }
+        }
 
         Ok(Some(req::SignatureHelp {
             signatures: vec![sig_info],
@@ -479,7 +576,7 @@ pub fn handle_hover(
         None => return Ok(None),
         Some(info) => info,
     };
-    let line_index = world.analysis.file_line_index(position.file_id)?;
+    let line_index = world.file_line_index(position.file_id)?;
     let range = info.range.conv_with(&line_index);
     let res = Hover {
         contents: HoverContents::Markup(MarkupContent {
@@ -498,16 +595,17 @@ pub fn handle_prepare_rename(
     let _p = profile("handle_prepare_rename");
     let position = params.try_conv_with(&world)?;
 
-    let optional_change = world.analysis().rename(position, "dummy")?;
-    let range = match optional_change {
-        None => return Ok(None),
-        Some(it) => it.range,
+    let RangeInfo { range, info: placeholder } = match world.analysis().prepare_rename(position)? {
+        Ok(it) => it,
+        Err(err) => {
+            return Err(LspError::new(ErrorCode::InvalidRequest as i32, err.to_string()).into())
+        }
     };
 
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let range = range.conv_with(&line_index);
-    Ok(Some(PrepareRenameResponse::Range(range)))
+    Ok(Some(PrepareRenameResponse::RangeWithPlaceholder { range, placeholder }))
 }
 
 pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
@@ -533,6 +631,62 @@ pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Optio
     Ok(Some(source_change_req.workspace_edit))
 }
 
+pub fn handle_will_rename_files(
+    world: WorldSnapshot,
+    params: req::RenameFilesParams,
+) -> Result<Option<WorkspaceEdit>> {
+    let _p = profile("handle_will_rename_files");
+    let mut source_file_edits = Vec::new();
+    let mut edited_files = FxHashSet::default();
+    for file in params.files {
+        let (old_path, new_path) = match (file.old_uri.to_file_path(), file.new_uri.to_file_path())
+        {
+            (Ok(old_path), Ok(new_path)) => (old_path, new_path),
+            _ => continue,
+        };
+        // Moving a directory moves the module defined by its `mod.rs`.
+        let (old_path, new_path) = if old_path.extension().is_none() {
+            (old_path.join("mod.rs"), new_path.join("mod.rs"))
+        } else {
+            (old_path, new_path)
+        };
+        let file_id = match Url::from_file_path(&old_path)
+            .ok()
+            .and_then(|uri| world.uri_to_file_id(&uri).ok())
+        {
+            Some(it) => it,
+            None => continue,
+        };
+        let source_root = world.analysis().file_source_root(file_id)?;
+        let new_path = match world.path_to_relative_path(source_root, &new_path) {
+            Some(it) => it,
+            None => continue,
+        };
+        let change = match world.analysis().will_rename_file(file_id, &new_path)? {
+            Some(it) => it,
+            None => continue,
+        };
+        // Skip renames whose edits would overlap with the ones of another file.
+        if change.source_file_edits.iter().any(|it| edited_files.contains(&it.file_id)) {
+            continue;
+        }
+        edited_files.extend(change.source_file_edits.iter().map(|it| it.file_id));
+        source_file_edits.extend(change.source_file_edits);
+    }
+    if source_file_edits.is_empty() {
+        return Ok(None);
+    }
+
+    let change = SourceChange {
+        label: "rename files".to_string(),
+        source_file_edits,
+        file_system_edits: Vec::new(),
+        cursor_position: None,
+    };
+    let source_change_req = change.try_conv_with(&world)?;
+    Ok(Some(source_change_req.workspace_edit))
+}
+
 pub fn handle_references(
     world: WorldSnapshot,
     params: req::ReferenceParams,
@@ -544,12 +698,14 @@ pub fn handle_references(
         None => return Ok(None),
         Some(refs) => refs,
     };
+    // LSP can't tell them apart, so possibly related occurrences are listed
+    // after the verified ones.
+    let possibly_related = refs.possibly_related().to_vec();
 
-    let locations = if params.context.include_declaration {
+    let mut locations: Vec<Location> = if params.context.include_declaration {
         refs.into_iter()
             .filter_map(|reference| {
-                let line_index =
-                    world.analysis().file_line_index(reference.file_range.file_id).ok()?;
+                let line_index = world.file_line_index(reference.file_range.file_id).ok()?;
                 to_location(
                     reference.file_range.file_id,
                     reference.file_range.range,
@@ -564,8 +720,7 @@ pub fn handle_references(
         refs.references()
             .iter()
             .filter_map(|reference| {
-                let line_index =
-                    world.analysis().file_line_index(reference.file_range.file_id).ok()?;
+                let line_index = world.file_line_index(reference.file_range.file_id).ok()?;
                 to_location(
                     reference.file_range.file_id,
                     reference.file_range.range,
@@ -576,6 +731,11 @@ pub fn handle_references(
             })
             .collect()
     };
+    locations.extend(possibly_related.into_iter().filter_map(|reference| {
+        let line_index = world.file_line_index(reference.file_range.file_id).ok()?;
+        to_location(reference.file_range.file_id, reference.file_range.range, &world, &line_index)
+            .ok()
+    }));
 
     Ok(Some(locations))
 }
@@ -589,7 +749,7 @@ pub fn handle_formatting(
     let file = world.analysis().file_text(file_id)?;
     let crate_ids = world.analysis().crate_for(file_id)?;
 
-    let file_line_index = world.analysis().file_line_index(file_id)?;
+    let file_line_index = world.file_line_index(file_id)?;
     let end_position = TextUnit::of_str(&file).conv_with(&file_line_index);
 
     let mut rustfmt = process::Command::new("rustfmt");
@@ -648,14 +808,14 @@ pub fn handle_formatting(
 pub fn handle_code_action(
     world: WorldSnapshot,
     params: req::CodeActionParams,
-) -> Result<Option<CodeActionResponse>> {
+) -> Result<Option<Vec<req::CodeAction>>> {
     let _p = profile("handle_code_action");
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let range = params.range.conv_with(&line_index);
 
     let diagnostics = world.analysis().diagnostics(file_id)?;
-    let mut res = CodeActionResponse::default();
+    let mut res: Vec<req::CodeAction> = Vec::new();
 
     let fixes_from_diagnostics = diagnostics
         .into_iter()
@@ -688,7 +848,42 @@ pub fn handle_code_action(
         if fix_range.intersection(&range).is_none() {
             continue;
         }
-        res.push(fix.action.clone());
+        let action = match fix.action.clone() {
+            CodeActionOrCommand::CodeAction(action) => action,
+            CodeActionOrCommand::Command(command) => CodeAction {
+                title: command.title.clone(),
+                kind: None,
+                diagnostics: None,
+                edit: None,
+                command: Some(command),
+                is_preferred: None,
+            },
+        };
+        res.push(action.into());
+    }
+
+    // Clients which can resolve code actions get the assists without their
+    // edits, which are computed by `handle_code_action_resolve`.
+    if world.options.resolve_code_actions {
+        for assist in world.analysis().unresolved_assists(FileRange { file_id, range })? {
+            let data = req::CodeActionData {
+                text_document: params.text_document.clone(),
+                start: range.start().into(),
+                end: range.end().into(),
+                id: assist.id.0.to_string(),
+                label: assist.label.clone(),
+            };
+            let action = CodeAction {
+                title: assist.label,
+                kind: assist_kind(assist.id),
+                diagnostics: None,
+                edit: None,
+                command: None,
+                is_preferred: None,
+            };
+            res.push(req::CodeAction { action, data: Some(data) });
+        }
+        return Ok(Some(res));
     }
 
     let mut groups = FxHashMap::default();
@@ -703,11 +898,11 @@ pub fn handle_code_action(
                 match groups.entry(group_label.clone()) {
                     Entry::Occupied(entry) => {
                         let idx: usize = *entry.get();
-                        match &mut res[idx] {
-                            CodeActionOrCommand::CodeAction(CodeAction {
+                        match &mut res[idx].action {
+                            CodeAction {
                                 command: Some(Command { arguments: Some(arguments), .. }),
                                 ..
-                            }) => match arguments.as_mut_slice() {
+                            } => match arguments.as_mut_slice() {
                                 [serde_json::Value::Array(arguments)] => arguments.push(arg),
                                 _ => panic!("invalid group"),
                             },
@@ -729,15 +924,9 @@ pub fn handle_code_action(
             arguments: Some(vec![arg]),
         };
 
-        let kind = match assist.id {
-            AssistId("introduce_variable") => Some("refactor.extract.variable".to_string()),
-            AssistId("add_custom_impl") => Some("refactor.rewrite.add_custom_impl".to_string()),
-            _ => None,
-        };
-
         let action = CodeAction {
             title,
-            kind,
+            kind: assist_kind(assist.id),
             diagnostics: None,
             edit: None,
             command: Some(command),
@@ -749,60 +938,100 @@ pub fn handle_code_action(
     Ok(Some(res))
 }
 
+fn assist_kind(id: AssistId) -> Option<String> {
+    match id {
+        AssistId("extract_variable") => Some("refactor.extract.variable".to_string()),
+        AssistId("add_custom_impl") => Some("refactor.rewrite.add_custom_impl".to_string()),
+        _ => None,
+    }
+}
+
+pub fn handle_code_action_resolve(
+    world: WorldSnapshot,
+    mut params: req::CodeAction,
+) -> Result<req::CodeAction> {
+    let _p = profile("handle_code_action_resolve");
+    let data = match params.data.take() {
+        Some(it) => it,
+        None => {
+            return Err(LspError::new(
+                ErrorCode::InvalidParams as i32,
+                "code action has no data to resolve it".into(),
+            )
+            .into())
+        }
+    };
+    let file_id = data.text_document.try_conv_with(&world)?;
+    let range = TextRange::from_to(data.start.into(), data.end.into());
+    let assist =
+        world.analysis().resolve_assist(FileRange { file_id, range }, &data.id, &data.label)?;
+    let assist = match assist {
+        Some(it) => it,
+        None => {
+            return Err(LspError::new(
+                ErrorCode::ContentModified as i32,
+                format!("code action `{}` is not applicable anymore", data.label),
+            )
+            .into())
+        }
+    };
+    let source_change: req::SourceChange = assist.source_change.try_conv_with(&world)?;
+    params.action.edit = Some(source_change.workspace_edit);
+    Ok(params)
+}
+
 pub fn handle_code_lens(
     world: WorldSnapshot,
     params: req::CodeLensParams,
 ) -> Result<Option<Vec<CodeLens>>> {
     let _p = profile("handle_code_lens");
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
+    let with_references = world.feature_flags().get("lsp.code-lens.references");
 
     let mut lenses: Vec<CodeLens> = Default::default();
-
-    // Gather runnables
-    for runnable in world.analysis().runnables(file_id)? {
-        let title = match &runnable.kind {
-            RunnableKind::Test { .. } | RunnableKind::TestMod { .. } => "▶️\u{fe0e}Run Test",
-            RunnableKind::Bench { .. } => "Run Bench",
-            RunnableKind::Bin => "Run",
-        }
-        .to_string();
-        let r = to_lsp_runnable(&world, file_id, runnable)?;
-        let lens = CodeLens {
-            range: r.range,
-            command: Some(Command {
-                title,
-                command: "rust-analyzer.runSingle".into(),
-                arguments: Some(vec![to_value(r).unwrap()]),
-            }),
-            data: None,
+    for annotation in world.analysis().annotations(file_id, with_references)? {
+        let range = annotation.range.conv_with(&line_index);
+        let lens_params = |position: FilePosition| {
+            let pos = position.offset.conv_with(&line_index);
+            req::TextDocumentPositionParams::new(params.text_document.clone(), pos)
         };
-
-        lenses.push(lens);
-    }
-
-    // Handle impls
-    lenses.extend(
-        world
-            .analysis()
-            .file_structure(file_id)?
-            .into_iter()
-            .filter(|it| match it.kind {
-                SyntaxKind::TRAIT_DEF | SyntaxKind::STRUCT_DEF | SyntaxKind::ENUM_DEF => true,
-                _ => false,
-            })
-            .map(|it| {
-                let range = it.node_range.conv_with(&line_index);
-                let pos = range.start;
-                let lens_params =
-                    req::TextDocumentPositionParams::new(params.text_document.clone(), pos);
+        let lens = match annotation.kind {
+            AnnotationKind::Runnable { runnable } => {
+                let title = match &runnable.kind {
+                    RunnableKind::Test { .. } | RunnableKind::TestMod { .. } => {
+                        "▶️\u{fe0e}Run Test"
+                    }
+                    RunnableKind::Bench { .. } => "Run Bench",
+                    RunnableKind::Bin => "Run",
+                }
+                .to_string();
+                let r = to_lsp_runnable(&world, file_id, runnable)?;
                 CodeLens {
                     range,
-                    command: None,
-                    data: Some(to_value(CodeLensResolveData::Impls(lens_params)).unwrap()),
+                    command: Some(Command {
+                        title,
+                        command: "rust-analyzer.runSingle".into(),
+                        arguments: Some(vec![to_value(r).unwrap()]),
+                    }),
+                    data: None,
                 }
-            }),
-    );
+            }
+            AnnotationKind::HasImpls { position, .. } => CodeLens {
+                range,
+                command: None,
+                data: Some(to_value(CodeLensResolveData::Impls(lens_params(position))).unwrap()),
+            },
+            AnnotationKind::HasReferences { position, .. } => CodeLens {
+                range,
+                command: None,
+                data: Some(
+                    to_value(CodeLensResolveData::References(lens_params(position))).unwrap(),
+                ),
+            },
+        };
+        lenses.push(lens);
+    }
 
     Ok(Some(lenses))
 }
@@ -811,51 +1040,77 @@ pub fn handle_code_lens(
 #[serde(rename_all = "camelCase")]
 enum CodeLensResolveData {
     Impls(req::TextDocumentPositionParams),
+    References(req::TextDocumentPositionParams),
 }
 
 pub fn handle_code_lens_resolve(world: WorldSnapshot, code_lens: CodeLens) -> Result<CodeLens> {
     let _p = profile("handle_code_lens_resolve");
     let data = code_lens.data.unwrap();
     let resolve = from_json::<Option<CodeLensResolveData>>("CodeLensResolveData", data)?;
-    match resolve {
+    let (lens_params, kind) = match resolve {
         Some(CodeLensResolveData::Impls(lens_params)) => {
-            let locations: Vec<Location> =
-                match handle_goto_implementation(world, lens_params.clone())? {
-                    Some(req::GotoDefinitionResponse::Scalar(loc)) => vec![loc],
-                    Some(req::GotoDefinitionResponse::Array(locs)) => locs,
-                    Some(req::GotoDefinitionResponse::Link(links)) => links
-                        .into_iter()
-                        .map(|link| Location::new(link.target_uri, link.target_selection_range))
-                        .collect(),
-                    _ => vec![],
-                };
-
-            let title = if locations.len() == 1 {
-                "1 implementation".into()
-            } else {
-                format!("{} implementations", locations.len())
-            };
+            let position = lens_params.try_conv_with(&world)?;
+            (lens_params, AnnotationKind::HasImpls { position, data: None })
+        }
+        Some(CodeLensResolveData::References(lens_params)) => {
+            let position = lens_params.try_conv_with(&world)?;
+            (lens_params, AnnotationKind::HasReferences { position, data: None })
+        }
+        None => {
+            return Ok(CodeLens {
+                range: code_lens.range,
+                command: Some(Command { title: "Error".into(), ..Default::default() }),
+                data: None,
+            })
+        }
+    };
 
-            // We cannot use the 'editor.action.showReferences' command directly
-            // because that command requires vscode types which we convert in the handler
-            // on the client side.
-            let cmd = Command {
-                title,
-                command: "rust-analyzer.showReferences".into(),
-                arguments: Some(vec![
-                    to_value(&lens_params.text_document.uri).unwrap(),
-                    to_value(code_lens.range.start).unwrap(),
-                    to_value(locations).unwrap(),
-                ]),
-            };
-            Ok(CodeLens { range: code_lens.range, command: Some(cmd), data: None })
+    let file_id = lens_params.text_document.try_conv_with(&world)?;
+    let line_index = world.file_line_index(file_id)?;
+    let range = code_lens.range.conv_with(&line_index);
+    let annotation = world.analysis().resolve_annotation(Annotation { range, kind })?;
+    let (locations, noun): (Vec<Location>, &str) = match annotation.kind {
+        AnnotationKind::HasImpls { data, .. } => {
+            let locations = data
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|nav| nav.try_conv_with(&world).ok())
+                .collect();
+            (locations, "implementation")
         }
-        None => Ok(CodeLens {
-            range: code_lens.range,
-            command: Some(Command { title: "Error".into(), ..Default::default() }),
-            data: None,
-        }),
-    }
+        AnnotationKind::HasReferences { data, .. } => {
+            let locations = data
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|frange| {
+                    let line_index = world.file_line_index(frange.file_id).ok()?;
+                    to_location(frange.file_id, frange.range, &world, &line_index).ok()
+                })
+                .collect();
+            (locations, "reference")
+        }
+        AnnotationKind::Runnable { .. } => unreachable!(),
+    };
+
+    let title = if locations.len() == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", locations.len(), noun)
+    };
+
+    // We cannot use the 'editor.action.showReferences' command directly
+    // because that command requires vscode types which we convert in the handler
+    // on the client side.
+    let cmd = Command {
+        title,
+        command: "rust-analyzer.showReferences".into(),
+        arguments: Some(vec![
+            to_value(&lens_params.text_document.uri).unwrap(),
+            to_value(code_lens.range.start).unwrap(),
+            to_value(locations).unwrap(),
+        ]),
+    };
+    Ok(CodeLens { range: code_lens.range, command: Some(cmd), data: None })
 }
 
 pub fn handle_document_highlight(
@@ -864,7 +1119,7 @@ pub fn handle_document_highlight(
 ) -> Result<Option<Vec<DocumentHighlight>>> {
     let _p = profile("handle_document_highlight");
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
 
     let refs = match world
         .analysis()
@@ -892,22 +1147,25 @@ pub fn handle_ssr(world: WorldSnapshot, params: req::SsrParams) -> Result<req::S
 
 pub fn publish_diagnostics(world: &WorldSnapshot, file_id: FileId) -> Result<DiagnosticTask> {
     let _p = profile("publish_diagnostics");
-    let line_index = world.analysis().file_line_index(file_id)?;
-    let diagnostics: Vec<Diagnostic> = world
-        .analysis()
-        .diagnostics(file_id)?
+    let line_index = world.file_line_index(file_id)?;
+    let (diagnostics, dependencies) = world.analysis().diagnostics_with_dependencies(file_id)?;
+    let diagnostics: Vec<Diagnostic> = diagnostics
         .into_iter()
         .map(|d| Diagnostic {
             range: d.range.conv_with(&line_index),
             severity: Some(d.severity.conv()),
-            code: None,
+            code: Some(NumberOrString::String(d.code.to_string())),
             source: Some("rust-analyzer".to_string()),
             message: d.message,
             related_information: None,
-            tags: None,
+            tags: if d.tags.is_empty() {
+                None
+            } else {
+                Some(d.tags.into_iter().map(|it| it.conv()).collect())
+            },
         })
         .collect();
-    Ok(DiagnosticTask::SetNative(file_id, diagnostics))
+    Ok(DiagnosticTask::SetNative(file_id, diagnostics, dependencies))
 }
 
 pub fn publish_decorations(
@@ -926,7 +1184,7 @@ fn to_lsp_runnable(
 ) -> Result<req::Runnable> {
     let spec = CargoTargetSpec::for_file(world, file_id)?;
     let args = CargoTargetSpec::runnable_args(spec, &runnable.kind)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let label = match &runnable.kind {
         RunnableKind::Test { test_id } => format!("test {}", test_id),
         RunnableKind::TestMod { path } => format!("test-mod {}", path),
@@ -947,7 +1205,7 @@ fn to_lsp_runnable(
     })
 }
 fn highlight(world: &WorldSnapshot, file_id: FileId) -> Result<Vec<Decoration>> {
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let res = world
         .analysis()
         .highlight(file_id)?
@@ -968,7 +1226,7 @@ pub fn handle_inlay_hints(
     let _p = profile("handle_inlay_hints");
     let file_id = params.text_document.try_conv_with(&world)?;
     let analysis = world.analysis();
-    let line_index = analysis.file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     Ok(analysis
         .inlay_hints(file_id, world.options.max_inlay_hint_length)?
         .into_iter()
@@ -996,7 +1254,7 @@ pub fn handle_call_hierarchy_prepare(
         Some(it) => it,
     };
 
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
     let RangeInfo { range, info: navs } = nav_info;
     let res = navs
         .into_iter()
@@ -1026,8 +1284,11 @@ pub fn handle_call_hierarchy_incoming(
     let mut res = vec![];
 
     for call_item in call_items.into_iter() {
-        let file_id = call_item.target.file_id();
-        let line_index = world.analysis().file_line_index(file_id)?;
+        let file_id = match call_item.target.file_id() {
+            Some(it) => it,
+            None => continue,
+        };
+        let line_index = world.file_line_index(file_id)?;
         let range = call_item.target.range();
         let item = to_call_hierarchy_item(file_id, range, &world, &line_index, call_item.target)?;
         res.push(CallHierarchyIncomingCall {
@@ -1058,8 +1319,11 @@ pub fn handle_call_hierarchy_outgoing(
     let mut res = vec![];
 
     for call_item in call_items.into_iter() {
-        let file_id = call_item.target.file_id();
-        let line_index = world.analysis().file_line_index(file_id)?;
+        let file_id = match call_item.target.file_id() {
+            Some(it) => it,
+            None => continue,
+        };
+        let line_index = world.file_line_index(file_id)?;
         let range = call_item.target.range();
         let item = to_call_hierarchy_item(file_id, range, &world, &line_index, call_item.target)?;
         res.push(CallHierarchyOutgoingCall {
@@ -1078,7 +1342,7 @@ pub fn handle_semantic_tokens(
     let _p = profile("handle_semantic_tokens");
 
     let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id)?;
+    let line_index = world.file_line_index(file_id)?;
 
     let mut builder = SemanticTokensBuilder::default();
 
@@ -1099,7 +1363,7 @@ pub fn handle_semantic_tokens_range(
     let _p = profile("handle_semantic_tokens_range");
 
     let frange = (&params.text_document, params.range).try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(frange.file_id)?;
+    let line_index = world.file_line_index(frange.file_id)?;
 
     let mut builder = SemanticTokensBuilder::default();
 
@@ -32,6 +32,9 @@ impl From<PendingRequest> for CompletedRequest {
 #[derive(Debug, Default)]
 pub(crate) struct PendingRequests {
     map: FxHashMap<RequestId, PendingRequest>,
+    /// Requests canceled by the client whose handlers are still running,
+    /// with the time of cancellation.
+    canceled: FxHashMap<RequestId, Instant>,
 }
 
 impl PendingRequests {
@@ -41,13 +44,28 @@ impl PendingRequests {
         assert!(prev.is_none(), "duplicate request with id {}", id);
     }
     pub(crate) fn cancel(&mut self, id: &RequestId) -> bool {
-        self.map.remove(id).is_some()
+        if self.map.remove(id).is_none() {
+            return false;
+        }
+        ra_prof::count("requests cancelled");
+        self.canceled.insert(id.clone(), Instant::now());
+        true
     }
+    /// Returns `None` if the request was canceled, as the client already got
+    /// a response for it.
     pub(crate) fn finish(&mut self, id: &RequestId) -> Option<CompletedRequest> {
+        if let Some(canceled) = self.canceled.remove(id) {
+            ra_prof::count_by("ms spent after cancellation requested", elapsed_ms(canceled));
+            return None;
+        }
         self.map.remove(id).map(CompletedRequest::from)
     }
 }
 
+pub(crate) fn elapsed_ms(start: Instant) -> usize {
+    start.elapsed().as_millis() as usize
+}
+
 const N_COMPLETED_REQUESTS: usize = 10;
 
 #[derive(Debug, Default)]
@@ -0,0 +1,163 @@
+//! Keeps track of the state of the server and of the errors worth the user's
+//! attention.
+//!
+//! Errors are shown with `window/showMessage`, unless the same error was shown
+//! less than `DEDUP_WINDOW` ago: a broken `Cargo.toml` would otherwise show a
+//! popup for every reload of the workspace. The state of the server is
+//! published with the `rust-analyzer/status` notification.
+
+use std::time::{Duration, Instant};
+
+use rustc_hash::FxHashMap;
+
+use crate::req::{MessageType, ServerStatus, ShowMessageParams};
+
+/// How long an error is not shown again after it was shown.
+const DEDUP_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ErrorKind {
+    /// The configuration sent by the client is invalid.
+    Config,
+    /// A workspace failed to load.
+    WorkspaceLoad,
+    /// A request was canceled although nothing could have invalidated it.
+    Cancellation,
+}
+
+impl ErrorKind {
+    fn description(self) -> &'static str {
+        match self {
+            ErrorKind::Config => "invalid configuration",
+            ErrorKind::WorkspaceLoad => "failed to load workspace",
+            ErrorKind::Cancellation => "internal error",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct StatusReporter {
+    /// The last status sent to the client.
+    status: Option<ServerStatus>,
+    /// The errors of the workspaces loaded since loading last started.
+    workspace_errors: Vec<String>,
+    shown: FxHashMap<(ErrorKind, String), Instant>,
+}
+
+impl StatusReporter {
+    /// Records an error, and returns the message to show, unless the same
+    /// error was already shown recently.
+    pub(crate) fn report_error(
+        &mut self,
+        kind: ErrorKind,
+        message: String,
+        now: Instant,
+    ) -> Option<ShowMessageParams> {
+        if kind == ErrorKind::WorkspaceLoad {
+            self.workspace_errors.push(message.clone());
+        }
+        self.shown.retain(|_, shown| now.duration_since(*shown) < DEDUP_WINDOW);
+        let key = (kind, message);
+        if self.shown.contains_key(&key) {
+            return None;
+        }
+        let message = format!(
+            "rust-analyzer: {}: {}\nSee the rust-analyzer log for details.",
+            kind.description(),
+            key.1
+        );
+        self.shown.insert(key, now);
+        Some(ShowMessageParams { typ: MessageType::Error, message })
+    }
+
+    /// The workspaces are being loaded. Returns the new status, if it changed.
+    pub(crate) fn start_loading(&mut self) -> Option<ServerStatus> {
+        self.workspace_errors.clear();
+        self.set_status(ServerStatus::Loading)
+    }
+
+    /// The workspaces are loaded and indexed. Returns the new status, if it
+    /// changed.
+    pub(crate) fn finish_loading(&mut self) -> Option<ServerStatus> {
+        let status = match self.workspace_errors.as_slice() {
+            [] => ServerStatus::Ready,
+            [error] => ServerStatus::Degraded(format!("failed to load workspace: {}", error)),
+            errors => ServerStatus::Degraded(format!(
+                "failed to load {} workspaces: {}",
+                errors.len(),
+                errors.join("; ")
+            )),
+        };
+        self.set_status(status)
+    }
+
+    fn set_status(&mut self, status: ServerStatus) -> Option<ServerStatus> {
+        if self.status.as_ref() == Some(&status) {
+            return None;
+        }
+        self.status = Some(status.clone());
+        Some(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_shown_once_per_window() {
+        let mut reporter = StatusReporter::default();
+        let start = Instant::now();
+        let mut shown = |kind: ErrorKind, message: &str, delay: Duration| {
+            reporter.report_error(kind, message.to_string(), start + delay).is_some()
+        };
+        let half = DEDUP_WINDOW / 2;
+
+        assert!(shown(ErrorKind::WorkspaceLoad, "no Cargo.toml", Duration::from_secs(0)));
+        assert!(!shown(ErrorKind::WorkspaceLoad, "no Cargo.toml", half));
+        assert!(shown(ErrorKind::WorkspaceLoad, "bad Cargo.toml", half));
+        assert!(shown(ErrorKind::Config, "no Cargo.toml", half));
+        assert!(shown(ErrorKind::WorkspaceLoad, "no Cargo.toml", DEDUP_WINDOW));
+        assert!(!shown(ErrorKind::WorkspaceLoad, "no Cargo.toml", DEDUP_WINDOW + half));
+    }
+
+    #[test]
+    fn status_transitions() {
+        let mut reporter = StatusReporter::default();
+        let now = Instant::now();
+
+        assert_eq!(reporter.start_loading(), Some(ServerStatus::Loading));
+        assert_eq!(reporter.start_loading(), None);
+        assert_eq!(reporter.finish_loading(), Some(ServerStatus::Ready));
+        assert_eq!(reporter.finish_loading(), None);
+
+        // Errors which don't come from the workspaces don't degrade the status.
+        reporter.report_error(ErrorKind::Config, "unknown feature flag".to_string(), now);
+        assert_eq!(reporter.start_loading(), Some(ServerStatus::Loading));
+        assert_eq!(reporter.finish_loading(), Some(ServerStatus::Ready));
+
+        assert_eq!(reporter.start_loading(), Some(ServerStatus::Loading));
+        reporter.report_error(ErrorKind::WorkspaceLoad, "no Cargo.toml".to_string(), now);
+        assert_eq!(
+            reporter.finish_loading(),
+            Some(ServerStatus::Degraded("failed to load workspace: no Cargo.toml".to_string()))
+        );
+
+        // Errors are forgotten when the workspaces are loaded again.
+        assert_eq!(reporter.start_loading(), Some(ServerStatus::Loading));
+        assert_eq!(reporter.finish_loading(), Some(ServerStatus::Ready));
+
+        // Errors degrade the status even when they are not shown again.
+        assert_eq!(reporter.start_loading(), Some(ServerStatus::Loading));
+        assert!(reporter
+            .report_error(ErrorKind::WorkspaceLoad, "no Cargo.toml".to_string(), now)
+            .is_none());
+        reporter.report_error(ErrorKind::WorkspaceLoad, "bad Cargo.lock".to_string(), now);
+        assert_eq!(
+            reporter.finish_loading(),
+            Some(ServerStatus::Degraded(
+                "failed to load 2 workspaces: no Cargo.toml; bad Cargo.lock".to_string()
+            ))
+        );
+    }
+}
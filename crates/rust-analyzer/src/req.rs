@@ -6,8 +6,8 @@ use serde::{Deserialize, Serialize};
 
 pub use lsp_types::{
     notification::*, request::*, ApplyWorkspaceEditParams, CodeActionParams, CodeLens,
-    CodeLensParams, CompletionParams, CompletionResponse, DiagnosticTag,
-    DidChangeConfigurationParams, DidChangeWatchedFilesParams,
+    CodeLensParams, CompletionParams, CompletionResponse, ConfigurationItem, ConfigurationParams,
+    DiagnosticTag, DidChangeConfigurationParams, DidChangeWatchedFilesParams,
     DidChangeWatchedFilesRegistrationOptions, DocumentOnTypeFormattingParams, DocumentSymbolParams,
     DocumentSymbolResponse, FileSystemWatcher, Hover, InitializeResult, MessageType,
     PartialResultParams, ProgressParams, ProgressParamsValue, ProgressToken,
@@ -15,7 +15,7 @@ pub use lsp_types::{
     SelectionRangeParams, SemanticTokensParams, SemanticTokensRangeParams,
     SemanticTokensRangeResult, SemanticTokensResult, ServerCapabilities, ShowMessageParams,
     SignatureHelp, SymbolKind, TextDocumentEdit, TextDocumentPositionParams, TextEdit,
-    WorkDoneProgressParams, WorkspaceEdit, WorkspaceSymbolParams,
+    WorkDoneProgressParams, WorkspaceEdit, WorkspaceFoldersChangeEvent, WorkspaceSymbolParams,
 };
 
 pub enum AnalyzerStatus {}
@@ -34,6 +34,14 @@ impl Request for CollectGarbage {
     const METHOD: &'static str = "rust-analyzer/collectGarbage";
 }
 
+pub enum ReloadWorkspace {}
+
+impl Request for ReloadWorkspace {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/reloadWorkspace";
+}
+
 pub enum SyntaxTree {}
 
 impl Request for SyntaxTree {
@@ -71,6 +79,53 @@ pub struct ExpandMacroParams {
     pub position: Option<Position>,
 }
 
+pub enum ViewHir {}
+
+impl Request for ViewHir {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<String>;
+    const METHOD: &'static str = "rust-analyzer/viewHir";
+}
+
+pub enum ViewCrateGraph {}
+
+impl Request for ViewCrateGraph {
+    type Params = ViewCrateGraphParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewCrateGraph";
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewCrateGraphParams {
+    /// Show all the crates, not only the workspace crates and their direct
+    /// dependencies.
+    pub full: bool,
+}
+
+pub enum TypeOfRange {}
+
+impl Request for TypeOfRange {
+    type Params = TypeOfRangeParams;
+    type Result = Option<TypeOfRangeResult>;
+    const METHOD: &'static str = "rust-analyzer/typeOfRange";
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeOfRangeParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeOfRangeResult {
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub range: Range,
+}
+
 pub enum FindMatchingBrace {}
 
 impl Request for FindMatchingBrace {
@@ -101,6 +156,24 @@ impl Notification for PublishDecorations {
     const METHOD: &'static str = "rust-analyzer/publishDecorations";
 }
 
+pub enum Status {}
+
+impl Notification for Status {
+    type Params = ServerStatus;
+    const METHOD: &'static str = "rust-analyzer/status";
+}
+
+/// The state of the server, for clients to display.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "state", content = "reason")]
+pub enum ServerStatus {
+    /// The workspaces are being loaded and indexed.
+    Loading,
+    Ready,
+    /// Some workspaces failed to load, for the given reason.
+    Degraded(String),
+}
+
 #[derive(Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct PublishDecorationsParams {
@@ -124,6 +197,14 @@ impl Request for ParentModule {
     const METHOD: &'static str = "rust-analyzer/parentModule";
 }
 
+pub enum OpenDocs {}
+
+impl Request for OpenDocs {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<String>;
+    const METHOD: &'static str = "rust-analyzer/openDocs";
+}
+
 pub enum JoinLines {}
 
 impl Request for JoinLines {
@@ -220,3 +301,72 @@ impl Request for Ssr {
 pub struct SsrParams {
     pub arg: String,
 }
+
+/// `textDocument/codeAction`, with the `data` field of code actions from LSP
+/// 3.16, which `lsp-types` doesn't support yet.
+pub enum CodeActionRequest {}
+
+impl Request for CodeActionRequest {
+    type Params = CodeActionParams;
+    type Result = Option<Vec<CodeAction>>;
+    const METHOD: &'static str = "textDocument/codeAction";
+}
+
+/// `codeAction/resolve` from LSP 3.16: computes the edit of a code action
+/// returned without one.
+pub enum CodeActionResolve {}
+
+impl Request for CodeActionResolve {
+    type Params = CodeAction;
+    type Result = CodeAction;
+    const METHOD: &'static str = "codeAction/resolve";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CodeAction {
+    #[serde(flatten)]
+    pub action: lsp_types::CodeAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<CodeActionData>,
+}
+
+impl From<lsp_types::CodeAction> for CodeAction {
+    fn from(action: lsp_types::CodeAction) -> CodeAction {
+        CodeAction { action, data: None }
+    }
+}
+
+/// Identifies the assist of an unresolved code action.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionData {
+    pub text_document: TextDocumentIdentifier,
+    /// The range the code actions were requested for, as offsets: the document
+    /// might have changed before the action is resolved.
+    pub start: u32,
+    pub end: u32,
+    pub id: String,
+    pub label: String,
+}
+
+/// `workspace/willRenameFiles` from LSP 3.16, which `lsp-types` doesn't
+/// support yet.
+pub enum WillRenameFiles {}
+
+impl Request for WillRenameFiles {
+    type Params = RenameFilesParams;
+    type Result = Option<WorkspaceEdit>;
+    const METHOD: &'static str = "workspace/willRenameFiles";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RenameFilesParams {
+    pub files: Vec<FileRename>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRename {
+    pub old_uri: Url,
+    pub new_uri: Url,
+}
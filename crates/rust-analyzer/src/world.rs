@@ -6,6 +6,7 @@
 use std::{
     path::{Path, PathBuf},
     sync::Arc,
+    time::Instant,
 };
 
 use crossbeam_channel::{unbounded, Receiver};
@@ -13,29 +14,37 @@ use lsp_server::ErrorCode;
 use lsp_types::Url;
 use parking_lot::RwLock;
 use ra_cargo_watch::{url_from_path_with_drive_lowercasing, CheckOptions, CheckWatcher};
+use ra_db::{SourceDatabase, SourceDatabaseExt};
 use ra_ide::{
-    Analysis, AnalysisChange, AnalysisHost, CrateGraph, FeatureFlags, FileId, LibraryData,
+    Analysis, AnalysisChange, AnalysisHost, CrateGraph, CrateId, FeatureFlags, FileId, LibraryData,
     SourceRootId,
 };
 use ra_project_model::{get_rustc_cfg_options, ProjectWorkspace};
 use ra_vfs::{LineEndings, RootEntry, Vfs, VfsChange, VfsFile, VfsRoot, VfsTask, Watch};
 use relative_path::RelativePathBuf;
+use rustc_hash::FxHashSet;
 
 use crate::{
+    conv::{LineMap, OffsetEncoding},
     diagnostics::{CheckFixes, DiagnosticCollection},
-    main_loop::pending_requests::{CompletedRequest, LatestRequests},
+    main_loop::pending_requests::{elapsed_ms, CompletedRequest, LatestRequests},
     vfs_glob::{Glob, RustPackageFilterBuilder},
-    LspError, Result,
+    LspError, Result, ServerConfig,
 };
 
 #[derive(Debug, Clone)]
 pub struct Options {
     pub publish_decorations: bool,
     pub supports_location_link: bool,
+    pub supports_hierarchical_symbols: bool,
     pub line_folding_only: bool,
     pub max_inlay_hint_length: Option<usize>,
     pub rustfmt_args: Vec<String>,
     pub cargo_watch: CheckOptions,
+    pub profile_requests: bool,
+    pub offset_encoding: OffsetEncoding,
+    /// Whether the client supports `codeAction/resolve`.
+    pub resolve_code_actions: bool,
 }
 
 /// `WorldState` is the primary mutable state of the language server
@@ -56,6 +65,12 @@ pub struct WorldState {
     pub latest_requests: Arc<RwLock<LatestRequests>>,
     pub check_watcher: CheckWatcher,
     pub diagnostics: DiagnosticCollection,
+    /// Set when the workspaces should be loaded again, e.g. because a manifest
+    /// changed. The main loop reloads them at the end of its turn.
+    pub workspace_reload_requested: bool,
+    lru_capacity: Option<usize>,
+    exclude_globs: Vec<Glob>,
+    watch: bool,
 }
 
 /// An immutable snapshot of the world's state at a point in time.
@@ -100,6 +115,7 @@ impl WorldState {
         }
         let (task_sender, task_receiver) = unbounded();
         let task_sender = Box::new(move |t| task_sender.send(t).unwrap());
+        let watch_enabled = watch.0;
         let (mut vfs, vfs_roots) = Vfs::new(roots, task_sender, watch);
         let roots_to_scan = vfs_roots.len();
         for r in vfs_roots {
@@ -109,44 +125,18 @@ impl WorldState {
             change.set_debug_root_path(SourceRootId(r.0), vfs_root_path.display().to_string());
         }
 
-        // FIXME: Read default cfgs from config
-        let default_cfg_options = {
-            let mut opts = get_rustc_cfg_options();
-            opts.insert_atom("test".into());
-            opts.insert_atom("debug_assertion".into());
-            opts
-        };
-
         // Create crate graph from all the workspaces
-        let mut crate_graph = CrateGraph::default();
-        let mut load = |path: &std::path::Path| {
+        let mut load = |path: &Path| {
             let vfs_file = vfs.load(path);
             vfs_file.map(|f| FileId(f.0))
         };
-        for ws in workspaces.iter() {
-            let (graph, crate_names) = ws.to_crate_graph(&default_cfg_options, &mut load);
-            let shift = crate_graph.extend(graph);
-            for (crate_id, name) in crate_names {
-                change.set_debug_crate_name(crate_id.shift(shift), name)
-            }
+        let (crate_graph, crate_names) = crate_graph(&workspaces, &mut load);
+        for (crate_id, name) in crate_names {
+            change.set_debug_crate_name(crate_id, name)
         }
         change.set_crate_graph(crate_graph);
 
-        // FIXME: Figure out the multi-workspace situation
-        let check_watcher = workspaces
-            .iter()
-            .find_map(|w| match w {
-                ProjectWorkspace::Cargo { cargo, .. } => Some(cargo),
-                ProjectWorkspace::Json { .. } => None,
-            })
-            .map(|cargo| {
-                let cargo_project_root = cargo.workspace_root().to_path_buf();
-                CheckWatcher::new(&options.cargo_watch, cargo_project_root)
-            })
-            .unwrap_or_else(|| {
-                log::warn!("Cargo check watching only supported for cargo workspaces, disabling");
-                CheckWatcher::dummy()
-            });
+        let check_watcher = check_watcher(&workspaces, &options.cargo_watch);
 
         let mut analysis_host = AnalysisHost::new(lru_capacity, feature_flags);
         analysis_host.apply_change(change);
@@ -161,18 +151,104 @@ impl WorldState {
             latest_requests: Default::default(),
             check_watcher,
             diagnostics: Default::default(),
+            workspace_reload_requested: false,
+            lru_capacity,
+            exclude_globs: exclude_globs.to_vec(),
+            watch: watch_enabled,
         }
     }
 
-    /// Returns a vec of libraries
-    /// FIXME: better API here
+    /// Replaces the set of workspace folders and the workspaces loaded from
+    /// them.
+    ///
+    /// The VFS, the crate graph and the analysis are rebuilt from scratch, so
+    /// `FileId`s handed out before the reload become invalid. Overlays of
+    /// `open_files` are carried over, and the new ids of those files which are
+    /// still part of the world are returned.
+    pub fn reload(
+        &mut self,
+        folder_roots: Vec<PathBuf>,
+        workspaces: Vec<ProjectWorkspace>,
+        open_files: &[FileId],
+    ) -> Vec<FileId> {
+        let overlays = {
+            let analysis = self.analysis_host.analysis();
+            let vfs = self.vfs.read();
+            open_files
+                .iter()
+                .filter_map(|&file_id| {
+                    let text = analysis.file_text(file_id).ok()?;
+                    Some((vfs.file2path(VfsFile(file_id.0)), text.to_string()))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let latest_requests = Arc::clone(&self.latest_requests);
+        *self = WorldState::new(
+            folder_roots,
+            workspaces,
+            self.lru_capacity,
+            &self.exclude_globs,
+            Watch(self.watch),
+            self.options.clone(),
+            self.feature_flags().clone(),
+        );
+        self.latest_requests = latest_requests;
+
+        let mut res = Vec::new();
+        let mut vfs = self.vfs.write();
+        for (path, text) in overlays {
+            if let Some(file) = vfs.add_file_overlay(&path, text) {
+                res.push(FileId(file.0));
+            }
+        }
+        res
+    }
+
+    /// Whether the packages of `workspaces` are all in roots of the VFS.
+    pub fn knows_roots_of(&self, workspaces: &[ProjectWorkspace]) -> bool {
+        package_roots(workspaces).is_subset(&package_roots(&self.workspaces))
+    }
+
+    /// Switches to `workspaces`, reloaded from the same workspace folders after
+    /// a manifest or a lockfile changed.
+    ///
+    /// Unlike `reload`, this keeps the VFS, so `FileId`s stay valid, and the
+    /// analysis is only touched if the crate graph changed. The packages of
+    /// `workspaces` must be in roots the VFS already knows about, see
+    /// `knows_roots_of`.
+    pub fn update_workspaces(&mut self, workspaces: Vec<ProjectWorkspace>) {
+        let (crate_graph, crate_names) = {
+            let mut vfs = self.vfs.write();
+            let mut load = |path: &Path| vfs.load(path).map(|f| FileId(f.0));
+            crate_graph(&workspaces, &mut load)
+        };
+        if *self.analysis_host.raw_database().crate_graph() != crate_graph {
+            let mut change = AnalysisChange::new();
+            for (crate_id, name) in crate_names {
+                change.set_debug_crate_name(crate_id, name)
+            }
+            change.set_crate_graph(crate_graph);
+            self.analysis_host.apply_change(change);
+        }
+        self.workspaces = Arc::new(workspaces);
+    }
+
+    /// Applies the pending VFS changes to the analysis. Returns the source
+    /// roots of the local files which changed and the library roots which
+    /// still need to be indexed.
     pub fn process_changes(
         &mut self,
-    ) -> Option<Vec<(SourceRootId, Vec<(FileId, RelativePathBuf, Arc<String>)>)>> {
+    ) -> Option<(
+        FxHashSet<SourceRootId>,
+        Vec<(SourceRootId, Vec<(FileId, RelativePathBuf, Arc<String>)>)>,
+    )> {
         let changes = self.vfs.write().commit_changes();
         if changes.is_empty() {
             return None;
         }
+        let mut changed_roots = FxHashSet::default();
+        let mut changed_files = Vec::new();
         let mut libs = Vec::new();
         let mut change = AnalysisChange::new();
         for c in changes {
@@ -182,6 +258,7 @@ impl WorldState {
                     let is_local = self.roots.iter().any(|r| root_path.starts_with(r));
                     if is_local {
                         self.roots_to_scan -= 1;
+                        changed_roots.insert(SourceRootId(root.0));
                         for (file, path, text) in files {
                             change.add_file(SourceRootId(root.0), FileId(file.0), path, text);
                         }
@@ -194,18 +271,27 @@ impl WorldState {
                     }
                 }
                 VfsChange::AddFile { root, file, path, text } => {
+                    changed_roots.insert(SourceRootId(root.0));
                     change.add_file(SourceRootId(root.0), FileId(file.0), path, text);
                 }
                 VfsChange::RemoveFile { root, file, path } => {
+                    changed_roots.insert(SourceRootId(root.0));
                     change.remove_file(SourceRootId(root.0), FileId(file.0), path)
                 }
                 VfsChange::ChangeFile { file, text } => {
+                    changed_files.push(FileId(file.0));
                     change.change_file(FileId(file.0), text);
                 }
             }
         }
+        // Applying a change cancels the requests in flight and waits for them
+        // to unwind, which is the bulk of the time spent here.
+        let start = Instant::now();
         self.analysis_host.apply_change(change);
-        Some(libs)
+        ra_prof::count_by("ms spent after cancellation requested", elapsed_ms(start));
+        let db = self.analysis_host.raw_database();
+        changed_roots.extend(changed_files.into_iter().map(|file_id| db.file_source_root(file_id)));
+        Some((changed_roots, libs))
     }
 
     pub fn add_lib(&mut self, data: LibraryData) {
@@ -241,6 +327,75 @@ impl WorldState {
     pub fn feature_flags(&self) -> &FeatureFlags {
         self.analysis_host.feature_flags()
     }
+
+    /// Applies the settings of `config` which can change without restarting
+    /// the server. They are used by the snapshots taken afterwards.
+    pub fn update_config(&mut self, config: &ServerConfig) {
+        self.options.max_inlay_hint_length = config.max_inlay_hint_length;
+        self.options.rustfmt_args = config.rustfmt_args.clone();
+        let cargo_watch = CheckOptions {
+            enable: config.cargo_watch_enable,
+            args: config.cargo_watch_args.clone(),
+            command: config.cargo_watch_command.clone(),
+            all_targets: config.cargo_watch_all_targets,
+        };
+        if cargo_watch != self.options.cargo_watch {
+            self.check_watcher = check_watcher(&self.workspaces, &cargo_watch);
+            self.options.cargo_watch = cargo_watch;
+        }
+        self.analysis_host.update_feature_flags(config.feature_flags());
+    }
+}
+
+// FIXME: Figure out the multi-workspace situation
+fn check_watcher(workspaces: &[ProjectWorkspace], options: &CheckOptions) -> CheckWatcher {
+    workspaces
+        .iter()
+        .find_map(|w| match w {
+            ProjectWorkspace::Cargo { cargo, .. } => Some(cargo),
+            ProjectWorkspace::Json { .. } => None,
+        })
+        .map(|cargo| {
+            let cargo_project_root = cargo.workspace_root().to_path_buf();
+            CheckWatcher::new(options, cargo_project_root)
+        })
+        .unwrap_or_else(|| {
+            log::warn!("Cargo check watching only supported for cargo workspaces, disabling");
+            CheckWatcher::dummy()
+        })
+}
+
+/// Builds the crate graph of all the `workspaces`, using `load` to get the ids
+/// of the crate roots.
+fn crate_graph(
+    workspaces: &[ProjectWorkspace],
+    load: &mut dyn FnMut(&Path) -> Option<FileId>,
+) -> (CrateGraph, Vec<(CrateId, String)>) {
+    // FIXME: Read default cfgs from config
+    let default_cfg_options = {
+        let mut opts = get_rustc_cfg_options();
+        opts.insert_atom("test".into());
+        opts.insert_atom("debug_assertion".into());
+        opts
+    };
+
+    let mut crate_graph = CrateGraph::default();
+    let mut crate_names = Vec::new();
+    for ws in workspaces.iter() {
+        let (graph, names) = ws.to_crate_graph(&default_cfg_options, load);
+        let shift = crate_graph.extend(graph);
+        crate_names.extend(names.into_iter().map(|(crate_id, name)| (crate_id.shift(shift), name)));
+    }
+    (crate_graph, crate_names)
+}
+
+/// The package roots of `workspaces`, each of which is a root of the VFS.
+fn package_roots(workspaces: &[ProjectWorkspace]) -> FxHashSet<(PathBuf, bool)> {
+    workspaces
+        .iter()
+        .flat_map(|ws| ws.to_roots())
+        .map(|root| (root.path().clone(), root.is_member()))
+        .collect()
 }
 
 impl WorldSnapshot {
@@ -248,6 +403,11 @@ impl WorldSnapshot {
         &self.analysis
     }
 
+    pub fn file_line_index(&self, file_id: FileId) -> Result<LineMap> {
+        let index = self.analysis.file_line_index(file_id)?;
+        Ok(LineMap::new(index, self.options.offset_encoding))
+    }
+
     pub fn uri_to_file_id(&self, uri: &Url) -> Result<FileId> {
         let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
         let file = self.vfs.read().path2file(&path).ok_or_else(|| {
@@ -283,6 +443,18 @@ impl WorldSnapshot {
         Ok(url)
     }
 
+    /// Converts `path` to a path relative to the source root `root`, or
+    /// returns `None` if it lies outside of it.
+    pub fn path_to_relative_path(
+        &self,
+        root: SourceRootId,
+        path: &Path,
+    ) -> Option<RelativePathBuf> {
+        let base = self.vfs.read().root2path(VfsRoot(root.0));
+        let path = path.strip_prefix(base).ok()?;
+        RelativePathBuf::from_path(path).ok()
+    }
+
     pub fn status(&self) -> String {
         let mut res = String::new();
         if self.workspaces.is_empty() {
@@ -308,7 +480,143 @@ impl WorldSnapshot {
         self.workspaces.iter().find_map(|ws| ws.workspace_root_for(&path))
     }
 
+    /// Returns the directory with the generated docs of the workspace member
+    /// with the given crate root, if it exists.
+    pub fn doc_dir_for(&self, crate_root: FileId) -> Option<PathBuf> {
+        let path = self.vfs.read().file2path(VfsFile(crate_root.0));
+        self.workspaces.iter().find_map(|ws| ws.doc_dir_for(&path)).filter(|it| it.is_dir())
+    }
+
     pub fn feature_flags(&self) -> &FeatureFlags {
         self.analysis.feature_flags()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use ra_cargo_watch::CheckOptions;
+    use ra_db::SourceDatabase;
+    use ra_ide::{Edition, FeatureFlags, FileId};
+    use ra_project_model::{JsonProject, ProjectWorkspace};
+    use ra_vfs::Watch;
+
+    use super::{Options, WorldState};
+    use crate::{conv::OffsetEncoding, ServerConfig};
+
+    fn test_options() -> Options {
+        Options {
+            publish_decorations: false,
+            supports_location_link: false,
+            supports_hierarchical_symbols: false,
+            line_folding_only: false,
+            max_inlay_hint_length: None,
+            rustfmt_args: Vec::new(),
+            cargo_watch: CheckOptions {
+                enable: false,
+                args: Vec::new(),
+                command: "check".to_string(),
+                all_targets: true,
+            },
+            profile_requests: false,
+            offset_encoding: OffsetEncoding::default(),
+            resolve_code_actions: false,
+        }
+    }
+
+    fn json_workspace(json: serde_json::Value) -> ProjectWorkspace {
+        let project: JsonProject = serde_json::from_value(json).unwrap();
+        ProjectWorkspace::Json { project }
+    }
+
+    #[test]
+    fn update_workspaces_changes_crate_graph_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let a = root.join("a/lib.rs");
+        let b = root.join("b/lib.rs");
+        for path in &[&a, &b] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let krate = |root_module: &Path, edition: &str, deps: serde_json::Value| {
+            serde_json::json!({
+                "root_module": root_module,
+                "edition": edition,
+                "deps": deps,
+                "atom_cfgs": [],
+                "key_value_cfgs": {},
+            })
+        };
+        let before = json_workspace(serde_json::json!({
+            "roots": [root],
+            "crates": [krate(&a, "2015", serde_json::json!([]))],
+        }));
+        let after = json_workspace(serde_json::json!({
+            "roots": [root],
+            "crates": [
+                krate(&a, "2018", serde_json::json!([{ "crate": 1, "name": "b" }])),
+                krate(&b, "2018", serde_json::json!([])),
+            ],
+        }));
+
+        let mut world = WorldState::new(
+            vec![root.clone()],
+            vec![before],
+            None,
+            &[],
+            Watch(false),
+            test_options(),
+            FeatureFlags::default(),
+        );
+        let file_id =
+            |world: &WorldState, path: &Path| FileId(world.vfs.write().load(path).unwrap().0);
+        let a_before = file_id(&world, &a);
+
+        // No new roots, so the analysis can be updated in place.
+        assert!(world.knows_roots_of(&[after.clone()]));
+        world.update_workspaces(vec![after]);
+
+        let a_after = file_id(&world, &a);
+        let b_after = file_id(&world, &b);
+        assert_eq!(a_before, a_after);
+
+        let graph = world.analysis_host.raw_database().crate_graph();
+        let a_crate = graph.crate_id_for_crate_root(a_after).unwrap();
+        assert_eq!(graph.edition(a_crate), Edition::Edition2018);
+        let deps = graph
+            .dependencies(a_crate)
+            .map(|dep| (dep.name.to_string(), graph.crate_root(dep.crate_id)))
+            .collect::<Vec<_>>();
+        assert_eq!(deps, vec![("b".to_string(), b_after)]);
+    }
+
+    #[test]
+    fn runtime_config_applies_to_next_snapshot() {
+        let mut world = WorldState::new(
+            Vec::new(),
+            Vec::new(),
+            None,
+            &[],
+            Watch(false),
+            test_options(),
+            FeatureFlags::default(),
+        );
+        let before = world.snapshot();
+
+        let (config, warnings) = ServerConfig::from_json(serde_json::json!({
+            "maxInlayHintLength": 5,
+            "featureFlags": { "completion.enable-postfix": false },
+        }));
+        assert!(warnings.is_empty());
+        world.update_config(&config);
+        let after = world.snapshot();
+
+        assert_eq!(before.options.max_inlay_hint_length, None);
+        assert!(before.feature_flags().get("completion.enable-postfix"));
+        assert_eq!(after.options.max_inlay_hint_length, Some(5));
+        assert!(!after.feature_flags().get("completion.enable-postfix"));
+    }
+}
@@ -2,14 +2,18 @@ mod support;
 
 use std::{collections::HashMap, time::Instant};
 
+use lsp_server::ErrorCode;
 use lsp_types::{
-    CodeActionContext, DidOpenTextDocumentParams, DocumentFormattingParams, FormattingOptions,
-    PartialResultParams, Position, Range, TextDocumentItem, TextDocumentPositionParams,
-    WorkDoneProgressParams,
+    CodeActionContext, DidChangeTextDocumentParams, DidChangeWorkspaceFoldersParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, FormattingOptions, PartialResultParams,
+    Position, Range, TextDocumentContentChangeEvent, TextDocumentItem, TextDocumentPositionParams,
+    Url, VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceFolder,
+    WorkspaceFoldersChangeEvent,
 };
 use rust_analyzer::req::{
-    CodeActionParams, CodeActionRequest, Completion, CompletionParams, DidOpenTextDocument,
-    Formatting, OnEnter, Runnables, RunnablesParams,
+    CodeActionParams, CodeActionRequest, Completion, CompletionParams, DidChangeTextDocument,
+    DidChangeWorkspaceFolders, DidOpenTextDocument, Formatting, OnEnter, Runnables,
+    RunnablesParams, WorkspaceSymbol, WorkspaceSymbolParams,
 };
 use serde_json::json;
 use tempfile::TempDir;
@@ -459,6 +463,62 @@ fn main() {{}}
     );
 }
 
+#[test]
+fn typing_cancels_completion_in_flight() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- src/lib.rs
+use std::collections::Spam;
+"#,
+    )
+    .with_sysroot(true)
+    .server();
+    server.wait_until_workspace_is_loaded();
+
+    let completion = || CompletionParams {
+        text_document_position: TextDocumentPositionParams::new(
+            server.doc_id("src/lib.rs"),
+            Position::new(0, 23),
+        ),
+        context: None,
+        partial_result_params: PartialResultParams::default(),
+        work_done_progress_params: WorkDoneProgressParams::default(),
+    };
+    // The first completion has to analyze the standard library, so it is
+    // still running when the edit arrives.
+    let first = server.send_request_in_background::<Completion>(completion());
+    let start = Instant::now();
+    server.notification::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+        text_document: VersionedTextDocumentIdentifier {
+            uri: server.doc_id("src/lib.rs").uri,
+            version: Some(1),
+        },
+        content_changes: vec![TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "use std::collections::S;\n".to_string(),
+        }],
+    });
+    let second = server.send_request_in_background::<Completion>(completion());
+
+    let error = server.response(first).error.expect("the first completion wasn't canceled");
+    assert_eq!(error.code, ErrorCode::RequestCanceled as i32);
+    let elapsed = start.elapsed();
+    assert!(elapsed.as_millis() < 2000, "canceling completion took {:?}", elapsed);
+
+    let res = server.response(second).result.expect("the second completion failed");
+    assert!(format!("{}", res).contains("HashMap"));
+}
+
 #[test]
 fn diagnostics_dont_block_typing() {
     if skip_slow_tests() {
@@ -580,3 +640,94 @@ version = \"0.0.0\"
         }),
     );
 }
+
+#[test]
+fn loads_workspace_folders_added_after_initialization() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let code = r#"
+//- foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- foo/src/lib.rs
+pub struct FooSpam;
+
+//- bar/Cargo.toml
+[package]
+name = "bar"
+version = "0.0.0"
+
+//- bar/src/lib.rs
+pub struct BarSpam;
+"#;
+
+    let server = Project::with_fixture(code).root("foo").server();
+    server.wait_until_workspace_is_loaded();
+    server.request::<WorkspaceSymbol>(
+        WorkspaceSymbolParams {
+            query: "BarSpam".to_string(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        },
+        json!([]),
+    );
+
+    server.notification::<DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+        event: WorkspaceFoldersChangeEvent {
+            added: vec![WorkspaceFolder {
+                uri: Url::from_file_path(server.path().join("bar")).unwrap(),
+                name: "bar".to_string(),
+            }],
+            removed: vec![],
+        },
+    });
+    server.wait_until_workspace_is_loaded();
+    server.request::<WorkspaceSymbol>(
+        WorkspaceSymbolParams {
+            query: "Spam".to_string(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        },
+        json!([
+          {
+            "name": "BarSpam",
+            "kind": 23,
+            "location": { "uri": "file:///[..]bar/src/lib.rs", "range": "{...}" }
+          },
+          {
+            "name": "FooSpam",
+            "kind": 23,
+            "location": { "uri": "file:///[..]foo/src/lib.rs", "range": "{...}" }
+          }
+        ]),
+    );
+
+    server.notification::<DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+        event: WorkspaceFoldersChangeEvent {
+            added: vec![],
+            removed: vec![WorkspaceFolder {
+                uri: Url::from_file_path(server.path().join("foo")).unwrap(),
+                name: "foo".to_string(),
+            }],
+        },
+    });
+    server.wait_until_workspace_is_loaded();
+    server.request::<WorkspaceSymbol>(
+        WorkspaceSymbolParams {
+            query: "Spam".to_string(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        },
+        json!([
+          {
+            "name": "BarSpam",
+            "kind": 23,
+            "location": { "uri": "file:///[..]bar/src/lib.rs", "range": "{...}" }
+          }
+        ]),
+    );
+}
@@ -7,7 +7,7 @@ use std::{
 };
 
 use crossbeam_channel::{after, select, Receiver};
-use lsp_server::{Connection, Message, Notification, Request};
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
     notification::{DidOpenTextDocument, Exit},
     request::Shutdown,
@@ -19,7 +19,7 @@ use serde_json::{to_string_pretty, Value};
 use tempfile::TempDir;
 use test_utils::{find_mismatch, parse_fixture};
 
-use rust_analyzer::{main_loop, req, ServerConfig};
+use rust_analyzer::{main_loop, req, OffsetEncoding, ServerConfig};
 
 pub struct Project<'a> {
     fixture: &'a str,
@@ -81,6 +81,7 @@ pub fn project(fixture: &str) -> Server {
 
 pub struct Server {
     req_id: Cell<u64>,
+    workspace_loads: Cell<usize>,
     messages: RefCell<Vec<Message>>,
     dir: TempDir,
     _thread: jod_thread::JoinHandle<()>,
@@ -117,14 +118,22 @@ impl Server {
                         experimental: None,
                     },
                     ServerConfig { with_sysroot, ..ServerConfig::default() },
+                    OffsetEncoding::default(),
+                    false,
                     connection,
                 )
                 .unwrap()
             })
             .expect("failed to spawn a thread");
 
-        let res =
-            Server { req_id: Cell::new(1), dir, messages: Default::default(), client, _thread };
+        let res = Server {
+            req_id: Cell::new(1),
+            workspace_loads: Cell::new(0),
+            dir,
+            messages: Default::default(),
+            client,
+            _thread,
+        };
 
         for (path, text) in files {
             res.notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
@@ -181,6 +190,41 @@ impl Server {
         let r = Request::new(id.into(), R::METHOD.to_string(), params);
         self.send_request_(r)
     }
+    /// Sends a request without waiting for the response, so that it overlaps
+    /// with the following messages. See `response`.
+    pub fn send_request_in_background<R>(&self, params: R::Params) -> RequestId
+    where
+        R: lsp_types::request::Request,
+        R::Params: Serialize,
+    {
+        let id = self.req_id.get();
+        self.req_id.set(id + 1);
+
+        let r = Request::new(id.into(), R::METHOD.to_string(), params);
+        let id = r.id.clone();
+        self.client.sender.send(r.into()).unwrap();
+        id
+    }
+    /// Waits for the response to a request sent with
+    /// `send_request_in_background`.
+    pub fn response(&self, id: RequestId) -> Response {
+        let received = self.messages.borrow().iter().find_map(|msg| match msg {
+            Message::Response(res) if res.id == id => Some(res.clone()),
+            _ => None,
+        });
+        if let Some(res) = received {
+            return res;
+        }
+        while let Some(msg) = self.recv() {
+            match msg {
+                Message::Request(req) => panic!("unexpected request: {:?}", req),
+                Message::Notification(_) => (),
+                Message::Response(res) if res.id == id => return res,
+                Message::Response(_) => (),
+            }
+        }
+        panic!("no response");
+    }
     fn send_request_(&self, r: Request) -> Value {
         let id = r.id.clone();
         self.client.sender.send(r.into()).unwrap();
@@ -199,8 +243,11 @@ impl Server {
         }
         panic!("no response");
     }
+    /// Waits for the next "workspace loaded" message, which is sent again
+    /// every time workspace folders change.
     pub fn wait_until_workspace_is_loaded(&self) {
-        self.wait_for_message_cond(1, &|msg: &Message| match msg {
+        self.workspace_loads.set(self.workspace_loads.get() + 1);
+        self.wait_for_message_cond(self.workspace_loads.get(), &|msg: &Message| match msg {
             Message::Notification(n) if n.method == "window/showMessage" => {
                 let msg =
                     n.clone().extract::<req::ShowMessageParams>("window/showMessage").unwrap();
@@ -22,27 +22,21 @@ export async function createClient(config: Config, serverPath: string): Promise<
     const traceOutputChannel = vscode.window.createOutputChannel(
         'Rust Analyzer Language Server Trace',
     );
-    const cargoWatchOpts = config.cargoWatchOptions;
-
     const clientOptions: lc.LanguageClientOptions = {
         documentSelector: [{ scheme: 'file', language: 'rust' }],
-        initializationOptions: {
-            publishDecorations: !config.highlightingSemanticTokens,
-            lruCapacity: config.lruCapacity,
-            maxInlayHintLength: config.maxInlayHintLength,
-            cargoWatchEnable: cargoWatchOpts.enable,
-            cargoWatchArgs: cargoWatchOpts.arguments,
-            cargoWatchCommand: cargoWatchOpts.command,
-            cargoWatchAllTargets: cargoWatchOpts.allTargets,
-            excludeGlobs: config.excludeGlobs,
-            useClientWatching: config.useClientWatching,
-            featureFlags: config.featureFlags,
-            withSysroot: config.withSysroot,
-            cargoFeatures: config.cargoFeatures,
-            rustfmtArgs: config.rustfmtArgs,
-        },
+        initializationOptions: serverConfig(config),
+        synchronize: { configurationSection: 'rust-analyzer' },
         traceOutputChannel,
         middleware: {
+            workspace: {
+                // The server expects its settings in the same shape as the
+                // initialization options.
+                configuration(params: lc.ConfigurationParams) {
+                    return params.items.map(item =>
+                        item.section === 'rust-analyzer' ? serverConfig(config) : null
+                    );
+                }
+            },
             // Workaround for https://github.com/microsoft/vscode-languageserver-node/issues/576
             async provideDocumentSemanticTokens(document: vscode.TextDocument, token: vscode.CancellationToken, next: DocumentSemanticsTokensSignature) {
                 const res = await next(document, token);
@@ -99,3 +93,23 @@ export async function createClient(config: Config, serverPath: string): Promise<
 
     return res;
 }
+
+function serverConfig(config: Config) {
+    const cargoWatchOpts = config.cargoWatchOptions;
+    return {
+        publishDecorations: !config.highlightingSemanticTokens,
+        lruCapacity: config.lruCapacity,
+        maxInlayHintLength: config.maxInlayHintLength,
+        cargoWatchEnable: cargoWatchOpts.enable,
+        cargoWatchArgs: cargoWatchOpts.arguments,
+        cargoWatchCommand: cargoWatchOpts.command,
+        cargoWatchAllTargets: cargoWatchOpts.allTargets,
+        excludeGlobs: config.excludeGlobs,
+        useClientWatching: config.useClientWatching,
+        reloadOnManifestChange: config.reloadOnManifestChange,
+        featureFlags: config.featureFlags,
+        withSysroot: config.withSysroot,
+        cargoFeatures: config.cargoFeatures,
+        rustfmtArgs: config.rustfmtArgs,
+    };
+}
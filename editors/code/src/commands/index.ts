@@ -10,8 +10,12 @@ export * from './matching_brace';
 export * from './join_lines';
 export * from './on_enter';
 export * from './parent_module';
+export * from './open_docs';
 export * from './syntax_tree';
 export * from './expand_macro';
+export * from './view_hir';
+export * from './view_crate_graph';
+export * from './type_of_selection';
 export * from './runnables';
 export * from './ssr';
 export * from './server_version';
@@ -20,6 +24,14 @@ export function collectGarbage(ctx: Ctx): Cmd {
     return async () => ctx.client.sendRequest(ra.collectGarbage, null);
 }
 
+export function reloadWorkspace(ctx: Ctx): Cmd {
+    return async () => ctx.client.sendRequest(ra.reloadWorkspace, null);
+}
+
+export function showServerLog(ctx: Ctx): Cmd {
+    return () => ctx.client.outputChannel.show(true);
+}
+
 export function showReferences(ctx: Ctx): Cmd {
     return (uri: string, position: lc.Position, locations: lc.Location[]) => {
         const client = ctx.client;
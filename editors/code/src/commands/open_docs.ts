@@ -0,0 +1,23 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Opens the rustdoc page of the item under the cursor in the browser.
+export function openDocs(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const url = await client.sendRequest(ra.openDocs, {
+            textDocument: { uri: editor.document.uri.toString() },
+            position: client.code2ProtocolConverter.asPosition(
+                editor.selection.active,
+            ),
+        });
+        if (url == null) return;
+
+        await vscode.env.openExternal(vscode.Uri.parse(url));
+    };
+}
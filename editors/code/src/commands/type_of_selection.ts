@@ -0,0 +1,27 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Shows the type of the expression covering the selection, and highlights
+// the expression which was actually analyzed.
+export function typeOfSelection(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const response = await client.sendRequest(ra.typeOfRange, {
+            textDocument: { uri: editor.document.uri.toString() },
+            range: client.code2ProtocolConverter.asRange(editor.selection),
+        });
+        if (!response) {
+            vscode.window.showInformationMessage('No expression or pattern in the selection');
+            return;
+        }
+
+        const range = client.protocol2CodeConverter.asRange(response.range);
+        editor.selection = new vscode.Selection(range.start, range.end);
+        vscode.window.showInformationMessage(response.type);
+    };
+}
@@ -0,0 +1,20 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Opens the crate graph the server has loaded, in the DOT format of graphviz,
+// to debug workspace loading
+export function viewCrateGraph(ctx: Ctx, full: boolean): Cmd {
+    return async () => {
+        const client = ctx.client;
+        if (!client) return;
+
+        const dot = await client.sendRequest(ra.viewCrateGraph, { full });
+        const document = await vscode.workspace.openTextDocument({
+            language: 'dot',
+            content: dot,
+        });
+        return vscode.window.showTextDocument(document, vscode.ViewColumn.Two, true);
+    };
+}
@@ -0,0 +1,54 @@
+import * as vscode from 'vscode';
+import * as ra from '../rust-analyzer-api';
+
+import { Ctx, Cmd } from '../ctx';
+
+// Opens the virtual file that will show the lowered body and the inferred
+// types of the function at the cursor
+//
+// The contents of the file come from the `TextDocumentContentProvider`
+export function viewHir(ctx: Ctx): Cmd {
+    const tdcp = new TextDocumentContentProvider(ctx);
+    ctx.pushCleanup(
+        vscode.workspace.registerTextDocumentContentProvider(
+            'rust-analyzer',
+            tdcp,
+        ),
+    );
+
+    return async () => {
+        const document = await vscode.workspace.openTextDocument(tdcp.uri);
+        tdcp.eventEmitter.fire(tdcp.uri);
+        return vscode.window.showTextDocument(
+            document,
+            vscode.ViewColumn.Two,
+            true,
+        );
+    };
+}
+
+class TextDocumentContentProvider
+    implements vscode.TextDocumentContentProvider {
+    uri = vscode.Uri.parse('rust-analyzer://viewHir/hir.txt');
+    eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+
+    constructor(private readonly ctx: Ctx) {
+    }
+
+    async provideTextDocumentContent(_uri: vscode.Uri): Promise<string> {
+        const editor = vscode.window.activeTextEditor;
+        const client = this.ctx.client;
+        if (!editor || !client) return '';
+
+        const hir = await client.sendRequest(ra.viewHir, {
+            textDocument: { uri: editor.document.uri.toString() },
+            position: editor.selection.active,
+        });
+
+        return hir ?? 'Not inside a function, const or static';
+    }
+
+    get onDidChange(): vscode.Event<vscode.Uri> {
+        return this.eventEmitter.event;
+    }
+}
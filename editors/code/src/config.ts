@@ -21,7 +21,6 @@ export class Config {
     private static readonly rootSection = "rust-analyzer";
     private static readonly requiresReloadOpts = [
         "cargoFeatures",
-        "cargo-watch",
         "highlighting.semanticTokens"
     ]
         .map(opt => `${Config.rootSection}.${opt}`);
@@ -152,6 +151,7 @@ export class Config {
     get maxInlayHintLength() { return this.cfg.get("maxInlayHintLength") as number; }
     get excludeGlobs() { return this.cfg.get("excludeGlobs") as string[]; }
     get useClientWatching() { return this.cfg.get("useClientWatching") as boolean; }
+    get reloadOnManifestChange() { return this.cfg.get("reloadOnManifestChange") as boolean; }
     get featureFlags() { return this.cfg.get("featureFlags") as Record<string, boolean>; }
     get rustfmtArgs() { return this.cfg.get("rustfmtArgs") as string[]; }
 
@@ -230,6 +230,8 @@ const TAG_TO_SCOPES = new Map<string, string[]>([
     ["keyword", ["keyword"]],
     ["keyword.unsafe", ["keyword.other.unsafe"]],
     ["keyword.control", ["keyword.control"]],
+
+    ["unsafe_op", ["keyword.other.unsafe"]],
 ]);
 
 function randomU32Numbers(seed: number) {
@@ -2,7 +2,7 @@ import * as vscode from 'vscode';
 
 import * as commands from './commands';
 import { activateInlayHints } from './inlay_hints';
-import { activateStatusDisplay } from './status_display';
+import { activateStatusDisplay, activateServerStatusDisplay } from './status_display';
 import { Ctx } from './ctx';
 import { activateHighlighting } from './highlighting';
 import { ensureServerBinary } from './installation/server';
@@ -68,11 +68,17 @@ export async function activate(context: vscode.ExtensionContext) {
 
     ctx.registerCommand('analyzerStatus', commands.analyzerStatus);
     ctx.registerCommand('collectGarbage', commands.collectGarbage);
+    ctx.registerCommand('reloadWorkspace', commands.reloadWorkspace);
     ctx.registerCommand('matchingBrace', commands.matchingBrace);
     ctx.registerCommand('joinLines', commands.joinLines);
     ctx.registerCommand('parentModule', commands.parentModule);
+    ctx.registerCommand('openDocs', commands.openDocs);
     ctx.registerCommand('syntaxTree', commands.syntaxTree);
     ctx.registerCommand('expandMacro', commands.expandMacro);
+    ctx.registerCommand('viewHir', commands.viewHir);
+    ctx.registerCommand('viewCrateGraph', (ctx) => commands.viewCrateGraph(ctx, false));
+    ctx.registerCommand('viewFullCrateGraph', (ctx) => commands.viewCrateGraph(ctx, true));
+    ctx.registerCommand('typeOfSelection', commands.typeOfSelection);
     ctx.registerCommand('run', commands.run);
 
     defaultOnEnter.dispose();
@@ -86,8 +92,10 @@ export async function activate(context: vscode.ExtensionContext) {
     ctx.registerCommand('showReferences', commands.showReferences);
     ctx.registerCommand('applySourceChange', commands.applySourceChange);
     ctx.registerCommand('selectAndApplySourceChange', commands.selectAndApplySourceChange);
+    ctx.registerCommand('showServerLog', commands.showServerLog);
 
     activateStatusDisplay(ctx);
+    activateServerStatusDisplay(ctx);
 
     if (!ctx.config.highlightingSemanticTokens) {
         activateHighlighting(ctx);
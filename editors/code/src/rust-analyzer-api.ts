@@ -22,6 +22,9 @@ export const analyzerStatus = request<null, string>("analyzerStatus");
 export const collectGarbage = request<null, null>("collectGarbage");
 
 
+export const reloadWorkspace = request<null, null>("reloadWorkspace");
+
+
 export interface SyntaxTreeParams {
     textDocument: lc.TextDocumentIdentifier;
     range: Option<lc.Range>;
@@ -40,6 +43,26 @@ export interface ExpandedMacro {
 export const expandMacro = request<ExpandMacroParams, Option<ExpandedMacro>>("expandMacro");
 
 
+export const viewHir = request<lc.TextDocumentPositionParams, Option<string>>("viewHir");
+
+
+export interface ViewCrateGraphParams {
+    full: boolean;
+}
+export const viewCrateGraph = request<ViewCrateGraphParams, string>("viewCrateGraph");
+
+
+export interface TypeOfRangeParams {
+    textDocument: lc.TextDocumentIdentifier;
+    range: lc.Range;
+}
+export interface TypeOfRangeResult {
+    type: string;
+    range: lc.Range;
+}
+export const typeOfRange = request<TypeOfRangeParams, Option<TypeOfRangeResult>>("typeOfRange");
+
+
 export interface FindMatchingBraceParams {
     textDocument: lc.TextDocumentIdentifier;
     offsets: Vec<lc.Position>;
@@ -62,6 +85,9 @@ export const decorationsRequest = request<lc.TextDocumentIdentifier, Vec<Decorat
 export const parentModule = request<lc.TextDocumentPositionParams, Vec<lc.Location>>("parentModule");
 
 
+export const openDocs = request<lc.TextDocumentPositionParams, Option<string>>("openDocs");
+
+
 export interface JoinLinesParams {
     textDocument: lc.TextDocumentIdentifier;
     range: lc.Range;
@@ -110,6 +136,13 @@ export const ssr = request<SsrParams, SourceChange>("ssr");
 export const publishDecorations = notification<PublishDecorationsParams>("publishDecorations");
 
 
+export type ServerStatus =
+    | { state: "loading" }
+    | { state: "ready" }
+    | { state: "degraded"; reason: string };
+export const status = notification<ServerStatus>("rust-analyzer/status");
+
+
 export interface SourceChange {
     label: string;
     workspaceEdit: lc.WorkspaceEdit;
@@ -2,6 +2,7 @@ import * as vscode from 'vscode';
 
 import { WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressReport, WorkDoneProgressEnd, Disposable } from 'vscode-languageclient';
 
+import * as ra from './rust-analyzer-api';
 import { Ctx } from './ctx';
 
 const spinnerFrames = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
@@ -19,6 +20,34 @@ export function activateStatusDisplay(ctx: Ctx) {
     }
 }
 
+export function activateServerStatusDisplay(ctx: Ctx) {
+    const statusBarItem = vscode.window.createStatusBarItem(
+        vscode.StatusBarAlignment.Left,
+        11,
+    );
+    statusBarItem.command = 'rust-analyzer.showServerLog';
+    ctx.pushCleanup(statusBarItem);
+    ctx.client.onNotification(ra.status, params => {
+        switch (params.state) {
+            case 'loading':
+                statusBarItem.text = '$(sync~spin) rust-analyzer';
+                statusBarItem.tooltip = 'Loading the workspace';
+                statusBarItem.show();
+                break;
+
+            case 'ready':
+                statusBarItem.hide();
+                break;
+
+            case 'degraded':
+                statusBarItem.text = '$(warning) rust-analyzer';
+                statusBarItem.tooltip = `${params.reason}\nClick to show the server log.`;
+                statusBarItem.show();
+                break;
+        }
+    });
+}
+
 class StatusDisplay implements Disposable {
     packageName?: string;
 
@@ -68,7 +68,7 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else",
         "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "macro",
         "match", "mod", "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super",
-        "trait", "true", "try", "type", "unsafe", "use", "where", "while",
+        "trait", "true", "try", "type", "unsafe", "use", "where", "while", "yield",
     ],
     contextual_keywords: &["auto", "default", "existential", "union"],
     literals: &[
@@ -147,6 +147,7 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "IF_EXPR",
         "WHILE_EXPR",
         "CONDITION",
+        "LET_EXPR",
         "LOOP_EXPR",
         "FOR_EXPR",
         "CONTINUE_EXPR",
@@ -154,6 +155,7 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "LABEL",
         "BLOCK_EXPR",
         "RETURN_EXPR",
+        "YIELD_EXPR",
         "MATCH_EXPR",
         "MATCH_ARM_LIST",
         "MATCH_ARM",
@@ -201,6 +203,7 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "NAME",
         "NAME_REF",
         "LET_STMT",
+        "LET_ELSE",
         "EXPR_STMT",
         "TYPE_PARAM_LIST",
         "LIFETIME_PARAM",
@@ -296,7 +299,7 @@ macro_rules! ast_enums {
 
 pub(crate) const AST_SRC: AstSrc = AstSrc {
     nodes: &ast_nodes! {
-        struct SourceFile: ModuleItemOwner, FnDefOwner {
+        struct SourceFile: ModuleItemOwner, FnDefOwner, AttrsOwner {
             modules: [Module],
         }
 
@@ -341,7 +344,7 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
             ItemList,
         }
 
-        struct ItemList: FnDefOwner, ModuleItemOwner {
+        struct ItemList: FnDefOwner, ModuleItemOwner, AttrsOwner {
             impl_items: [ImplItem],
         }
 
@@ -395,8 +398,9 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
         struct ContinueExpr {}
         struct BreakExpr { Expr }
         struct Label {}
-        struct BlockExpr { Block  }
+        struct BlockExpr { Label, Block }
         struct ReturnExpr { Expr }
+        struct YieldExpr { Expr }
         struct CallExpr: ArgListOwner { Expr }
         struct MethodCallExpr: ArgListOwner {
             Expr, NameRef, TypeArgList,
@@ -409,6 +413,7 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
         struct RefExpr { Expr }
         struct PrefixExpr { Expr }
         struct BoxExpr { Expr }
+        struct LetExpr { Pat, Expr }
         struct RangeExpr {}
         struct BinExpr {}
         struct Literal {}
@@ -479,7 +484,9 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
         struct LetStmt: TypeAscriptionOwner {
             Pat,
             initializer: Expr,
+            LetElse,
         }
+        struct LetElse { BlockExpr }
         struct Condition { Pat, Expr }
         struct Block: AttrsOwner, ModuleItemOwner {
             statements: [Stmt],
@@ -587,6 +594,7 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
             Label,
             BlockExpr,
             ReturnExpr,
+            YieldExpr,
             MatchExpr,
             RecordLit,
             CallExpr,
@@ -604,6 +612,7 @@ pub(crate) const AST_SRC: AstSrc = AstSrc {
             Literal,
             MacroCall,
             BoxExpr,
+            LetExpr,
         }
 
         enum Pat {